@@ -464,6 +464,36 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::GetReferences>,
             ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::PrepareCallHierarchy>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetIncomingCalls>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetOutgoingCalls>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::PrepareTypeHierarchy>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSupertypes>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSubtypes>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetSemanticTokens>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetCodeLens>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::ResolveCodeLens>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetFoldingRanges>,
+            ))
             .add_request_handler(user_handler(
                 forward_read_only_project_request::<proto::SearchProject>,
             ))
@@ -506,6 +536,9 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_mutating_project_request::<proto::ApplyCodeAction>,
             ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::ApplyCodeLens>,
+            ))
             .add_request_handler(user_handler(
                 forward_mutating_project_request::<proto::PrepareRename>,
             ))
@@ -542,6 +575,12 @@ impl Server {
             .add_request_handler(user_handler(
                 forward_mutating_project_request::<proto::BlameBuffer>,
             ))
+            .add_request_handler(user_handler(
+                forward_mutating_project_request::<proto::SetIndexText>,
+            ))
+            .add_request_handler(user_handler(
+                forward_read_only_project_request::<proto::GetHeadText>,
+            ))
             .add_request_handler(user_handler(
                 forward_mutating_project_request::<proto::MultiLspQuery>,
             ))
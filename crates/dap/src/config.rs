@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How a debug session gets hold of the debuggee.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugRequestKind {
+    /// Start a new process under the debugger.
+    #[default]
+    Launch,
+    /// Attach to an already running process.
+    Attach,
+}
+
+/// A debug configuration, as defined in a `.zed/debug.json` file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct DebugAdapterConfig {
+    /// Human readable name of the configuration.
+    pub label: String,
+    /// The identifier of the debug adapter, sent to it when initializing.
+    /// Defaults to the name of the adapter's executable.
+    #[serde(default)]
+    pub adapter_id: Option<String>,
+    /// The executable of the debug adapter, which must speak DAP over stdio.
+    pub command: String,
+    /// Arguments to pass to the debug adapter.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Environment variables to set for the debug adapter.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// The working directory of the debug adapter. Defaults to the worktree root.
+    #[serde(default)]
+    pub cwd: Option<PathBuf>,
+    /// Whether to launch a new debuggee or attach to a running one.
+    #[serde(default)]
+    pub request: DebugRequestKind,
+    /// Adapter-specific arguments, passed as-is to the `launch` or `attach` request.
+    #[serde(default)]
+    pub configuration: Value,
+}
+
+impl DebugAdapterConfig {
+    /// Returns the identifier to send to the debug adapter when initializing it.
+    pub fn adapter_id(&self) -> String {
+        self.adapter_id.clone().unwrap_or_else(|| {
+            std::path::Path::new(&self.command)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.command.clone())
+        })
+    }
+
+    /// Returns the arguments of the `launch` or `attach` request for this configuration.
+    pub fn request_arguments(&self) -> Value {
+        match &self.configuration {
+            Value::Null => Value::Object(Default::default()),
+            configuration => configuration.clone(),
+        }
+    }
+}
+
+/// The contents of a `.zed/debug.json` file.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DebugConfigFile(pub Vec<DebugAdapterConfig>);
+
+impl DebugConfigFile {
+    pub fn parse(contents: &str) -> Result<Self> {
+        Ok(serde_json_lenient::from_str(contents)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_debug_config_file() {
+        let file = DebugConfigFile::parse(
+            r#"[
+                // Debug the current crate's binary.
+                {
+                    "label": "Debug binary",
+                    "command": "/usr/bin/lldb-dap",
+                    "configuration": { "program": "target/debug/app" },
+                },
+                {
+                    "label": "Attach to Python",
+                    "adapter_id": "debugpy",
+                    "command": "python",
+                    "args": ["-m", "debugpy.adapter"],
+                    "request": "attach",
+                }
+            ]"#,
+        )
+        .unwrap();
+
+        let [launch, attach] = file.0.as_slice() else {
+            panic!("unexpected configurations {file:?}");
+        };
+        assert_eq!(launch.request, DebugRequestKind::Launch);
+        assert_eq!(launch.adapter_id(), "lldb-dap");
+        assert_eq!(
+            launch.request_arguments(),
+            json!({ "program": "target/debug/app" })
+        );
+        assert_eq!(attach.request, DebugRequestKind::Attach);
+        assert_eq!(attach.adapter_id(), "debugpy");
+        assert_eq!(attach.args, vec!["-m", "debugpy.adapter"]);
+        assert_eq!(attach.request_arguments(), json!({}));
+    }
+}
@@ -0,0 +1,697 @@
+mod config;
+mod protocol;
+mod transport;
+
+pub use config::*;
+pub use protocol::*;
+
+use anyhow::{anyhow, Context, Result};
+use collections::HashMap;
+use futures::{
+    channel::oneshot, io::BufWriter, select, AsyncRead, AsyncWrite, Future, FutureExt, StreamExt,
+};
+use gpui::{AsyncAppContext, BackgroundExecutor, Task};
+use parking_lot::Mutex;
+use serde_json::Value;
+use smol::{
+    channel,
+    io::BufReader,
+    process::{self, Child},
+};
+
+#[cfg(target_os = "windows")]
+use smol::process::windows::CommandExt;
+
+use std::{
+    ffi::OsString,
+    fmt,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicU64, Ordering::SeqCst},
+        Arc,
+    },
+    time::Duration,
+};
+use util::{ResultExt, TryFutureExt};
+
+const DAP_REQUEST_TIMEOUT: Duration = Duration::from_secs(60 * 2);
+
+type EventHandler = Box<dyn Send + FnMut(Value, AsyncAppContext)>;
+type RequestHandler = Box<dyn Send + FnMut(u64, Value, AsyncAppContext)>;
+type ResponseHandler = Box<dyn Send + FnOnce(Result<Value>)>;
+
+/// Represents a launchable debug adapter.
+#[derive(Debug, Clone)]
+pub struct DebugAdapterBinary {
+    pub path: PathBuf,
+    pub arguments: Vec<OsString>,
+    pub env: Option<HashMap<String, String>>,
+}
+
+impl DebugAdapterBinary {
+    pub fn from_config(config: &DebugAdapterConfig) -> Self {
+        Self {
+            path: config.command.clone().into(),
+            arguments: config.args.iter().map(OsString::from).collect(),
+            env: Some(config.env.clone()),
+        }
+    }
+}
+
+/// Identifies a running debug adapter.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct DebugAdapterClientId(pub usize);
+
+impl fmt::Display for DebugAdapterClientId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A client connected to a running debug adapter process.
+pub struct DebugAdapterClient {
+    id: DebugAdapterClientId,
+    name: Arc<str>,
+    next_seq: Arc<AtomicU64>,
+    outbound_tx: channel::Sender<String>,
+    event_handlers: Arc<Mutex<HashMap<&'static str, EventHandler>>>,
+    request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
+    response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+    capabilities: Mutex<Capabilities>,
+    executor: BackgroundExecutor,
+    #[allow(clippy::type_complexity)]
+    io_tasks: Mutex<Option<(Task<Option<()>>, Task<Option<()>>)>>,
+    process: Mutex<Option<Child>>,
+}
+
+/// Handle to a debug adapter event or request handler. The handler is removed when this is dropped.
+#[must_use]
+pub struct Subscription {
+    name: &'static str,
+    handlers: Option<Subscribed>,
+}
+
+enum Subscribed {
+    Event(Arc<Mutex<HashMap<&'static str, EventHandler>>>),
+    Request(Arc<Mutex<HashMap<&'static str, RequestHandler>>>),
+}
+
+impl DebugAdapterClient {
+    /// Starts a debug adapter process, communicating with it over stdio.
+    pub fn new(
+        id: DebugAdapterClientId,
+        binary: DebugAdapterBinary,
+        working_dir: &Path,
+        cx: AsyncAppContext,
+    ) -> Result<Self> {
+        log::info!(
+            "starting debug adapter. binary path: {:?}, working directory: {:?}, args: {:?}",
+            binary.path,
+            working_dir,
+            &binary.arguments
+        );
+
+        let mut command = process::Command::new(&binary.path);
+        command
+            .current_dir(working_dir)
+            .args(&binary.arguments)
+            .envs(binary.env.unwrap_or_default())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(windows)]
+        command.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+        let mut process = command.spawn().with_context(|| {
+            format!(
+                "failed to spawn debug adapter. path: {:?}, working directory: {:?}, args: {:?}",
+                binary.path, working_dir, &binary.arguments
+            )
+        })?;
+
+        let stdin = process.stdin.take().unwrap();
+        let stdout = process.stdout.take().unwrap();
+        let stderr = process.stderr.take().unwrap();
+        cx.background_executor()
+            .spawn(Self::handle_stderr(stderr).log_err())
+            .detach();
+
+        let mut client = Self::new_internal(id, stdin, stdout, Some(process), cx);
+        if let Some(name) = binary.path.file_name() {
+            client.name = name.to_string_lossy().into();
+        }
+        Ok(client)
+    }
+
+    fn new_internal<Stdin, Stdout>(
+        id: DebugAdapterClientId,
+        stdin: Stdin,
+        stdout: Stdout,
+        process: Option<Child>,
+        cx: AsyncAppContext,
+    ) -> Self
+    where
+        Stdin: AsyncWrite + Unpin + Send + 'static,
+        Stdout: AsyncRead + Unpin + Send + 'static,
+    {
+        let (outbound_tx, outbound_rx) = channel::unbounded::<String>();
+        let next_seq = Arc::new(AtomicU64::new(1));
+        let event_handlers = Arc::new(Mutex::new(HashMap::<_, EventHandler>::default()));
+        let request_handlers = Arc::new(Mutex::new(HashMap::<_, RequestHandler>::default()));
+        let response_handlers =
+            Arc::new(Mutex::new(Some(HashMap::<_, ResponseHandler>::default())));
+
+        let input_task = cx.spawn({
+            let event_handlers = event_handlers.clone();
+            let request_handlers = request_handlers.clone();
+            let response_handlers = response_handlers.clone();
+            let outbound_tx = outbound_tx.clone();
+            let next_seq = next_seq.clone();
+            move |cx| {
+                Self::handle_input(
+                    stdout,
+                    event_handlers,
+                    request_handlers,
+                    response_handlers,
+                    outbound_tx,
+                    next_seq,
+                    cx,
+                )
+                .log_err()
+            }
+        });
+        let output_task = cx
+            .background_executor()
+            .spawn(Self::handle_output(stdin, outbound_rx, response_handlers.clone()).log_err());
+
+        Self {
+            id,
+            name: "".into(),
+            next_seq,
+            outbound_tx,
+            event_handlers,
+            request_handlers,
+            response_handlers,
+            capabilities: Default::default(),
+            executor: cx.background_executor().clone(),
+            io_tasks: Mutex::new(Some((input_task, output_task))),
+            process: Mutex::new(process),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_input<Stdout>(
+        stdout: Stdout,
+        event_handlers: Arc<Mutex<HashMap<&'static str, EventHandler>>>,
+        request_handlers: Arc<Mutex<HashMap<&'static str, RequestHandler>>>,
+        response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+        outbound_tx: channel::Sender<String>,
+        next_seq: Arc<AtomicU64>,
+        cx: AsyncAppContext,
+    ) -> Result<()>
+    where
+        Stdout: AsyncRead + Unpin + Send + 'static,
+    {
+        let _clear_response_handlers = util::defer({
+            let response_handlers = response_handlers.clone();
+            move || {
+                response_handlers.lock().take();
+            }
+        });
+
+        // Read and parse messages in the background, and only dispatch
+        // events and reverse requests on the main thread.
+        let (messages_tx, mut messages_rx) = futures::channel::mpsc::unbounded();
+        let read_task = cx.background_executor().spawn(async move {
+            let mut stdout = BufReader::new(stdout);
+            let mut buffer = Vec::new();
+            loop {
+                transport::read_message(&mut stdout, &mut buffer).await?;
+                log::trace!("incoming message: {}", String::from_utf8_lossy(&buffer));
+                match serde_json::from_slice::<Message>(&buffer) {
+                    Ok(Message::Response(response)) => {
+                        let handler = response_handlers
+                            .lock()
+                            .as_mut()
+                            .and_then(|handlers| handlers.remove(&response.request_seq));
+                        if let Some(handler) = handler {
+                            if response.success {
+                                handler(Ok(response.body.unwrap_or(Value::Null)));
+                            } else {
+                                handler(Err(anyhow!(response.message.unwrap_or_else(|| format!(
+                                    "{} request failed",
+                                    response.command
+                                )))));
+                            }
+                        }
+                    }
+                    Ok(message) => messages_tx.unbounded_send(message)?,
+                    Err(error) => log::warn!(
+                        "failed to deserialize debug adapter message: {error}\n{}",
+                        String::from_utf8_lossy(&buffer)
+                    ),
+                }
+            }
+        });
+
+        while let Some(message) = messages_rx.next().await {
+            match message {
+                Message::Event(event) => {
+                    let mut event_handlers = event_handlers.lock();
+                    if let Some(handler) = event_handlers.get_mut(event.event.as_str()) {
+                        handler(event.body.unwrap_or(Value::Null), cx.clone());
+                    } else {
+                        log::info!("unhandled debug adapter event {}", event.event);
+                    }
+                }
+                Message::Request(request) => {
+                    let mut request_handlers = request_handlers.lock();
+                    if let Some(handler) = request_handlers.get_mut(request.command.as_str()) {
+                        handler(
+                            request.seq,
+                            request.arguments.unwrap_or(Value::Null),
+                            cx.clone(),
+                        );
+                    } else {
+                        drop(request_handlers);
+                        let response = AnyResponse {
+                            seq: next_seq.fetch_add(1, SeqCst),
+                            request_seq: request.seq,
+                            success: false,
+                            command: request.command,
+                            message: Some("unsupported request".into()),
+                            body: None,
+                        };
+                        if let Some(response) =
+                            serde_json::to_string(&Message::Response(response)).log_err()
+                        {
+                            outbound_tx.try_send(response).ok();
+                        }
+                    }
+                }
+                Message::Response(_) => {}
+            }
+
+            // Don't starve the main thread when receiving lots of events at once.
+            smol::future::yield_now().await;
+        }
+        read_task.await
+    }
+
+    async fn handle_stderr<Stderr>(stderr: Stderr) -> Result<()>
+    where
+        Stderr: AsyncRead + Unpin + Send + 'static,
+    {
+        use smol::io::AsyncBufReadExt;
+
+        let mut stderr = BufReader::new(stderr);
+        let mut buffer = String::new();
+        loop {
+            buffer.clear();
+            if stderr.read_line(&mut buffer).await? == 0 {
+                return Ok(());
+            }
+            log::debug!("debug adapter stderr: {}", buffer.trim_end());
+        }
+    }
+
+    async fn handle_output<Stdin>(
+        stdin: Stdin,
+        outbound_rx: channel::Receiver<String>,
+        response_handlers: Arc<Mutex<Option<HashMap<u64, ResponseHandler>>>>,
+    ) -> Result<()>
+    where
+        Stdin: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut stdin = BufWriter::new(stdin);
+        let _clear_response_handlers = util::defer({
+            let response_handlers = response_handlers.clone();
+            move || {
+                response_handlers.lock().take();
+            }
+        });
+        while let Ok(message) = outbound_rx.recv().await {
+            log::trace!("outgoing message: {}", message);
+            transport::write_message(&mut stdin, &message).await?;
+        }
+        Ok(())
+    }
+
+    /// Initializes the debug adapter by sending the `initialize` request.
+    ///
+    /// [DAP Specification](https://microsoft.github.io/debug-adapter-protocol/specification#Requests_Initialize)
+    pub async fn initialize(&self, adapter_id: String) -> Result<Capabilities> {
+        let capabilities = self
+            .request::<requests::Initialize>(InitializeRequestArguments {
+                client_id: Some("zed".into()),
+                client_name: Some("Zed".into()),
+                adapter_id,
+                lines_start_at1: Some(true),
+                columns_start_at1: Some(true),
+                path_format: Some("path".into()),
+                supports_variable_type: Some(true),
+                supports_run_in_terminal_request: Some(false),
+            })
+            .await?;
+        *self.capabilities.lock() = capabilities.clone();
+        Ok(capabilities)
+    }
+
+    /// Ends the debug session, terminating the debuggee if it was launched by the adapter.
+    pub async fn shutdown(&self, terminate_debuggee: bool) -> Result<()> {
+        let result = self
+            .request::<requests::Disconnect>(DisconnectArguments {
+                restart: None,
+                terminate_debuggee: Some(terminate_debuggee),
+            })
+            .await;
+        self.io_tasks.lock().take();
+        if let Some(mut process) = self.process.lock().take() {
+            process.kill().log_err();
+        }
+        result
+    }
+
+    pub fn id(&self) -> DebugAdapterClientId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities.lock().clone()
+    }
+
+    /// Sends a request to the debug adapter and waits for its response.
+    pub fn request<R: Request>(
+        &self,
+        arguments: R::Arguments,
+    ) -> impl 'static + Future<Output = Result<R::Response>> {
+        let seq = self.next_seq.fetch_add(1, SeqCst);
+        let message = serde_json::to_value(arguments).map(|arguments| {
+            let request = AnyRequest {
+                seq,
+                command: R::COMMAND.into(),
+                arguments: (!arguments.is_null()).then_some(arguments),
+            };
+            serde_json::to_string(&Message::Request(request)).unwrap()
+        });
+
+        let (tx, rx) = oneshot::channel();
+        let handle_response = self
+            .response_handlers
+            .lock()
+            .as_mut()
+            .ok_or_else(|| anyhow!("debug adapter shut down"))
+            .map(|handlers| {
+                handlers.insert(
+                    seq,
+                    Box::new(move |result: Result<Value>| {
+                        let response = result.and_then(|body| {
+                            serde_json::from_value(body).with_context(|| {
+                                format!("failed to deserialize {} response", R::COMMAND)
+                            })
+                        });
+                        tx.send(response).ok();
+                    }),
+                );
+            });
+        let send = message.map_err(anyhow::Error::from).and_then(|message| {
+            self.outbound_tx
+                .try_send(message)
+                .context("failed to write to debug adapter's stdin")
+        });
+
+        let mut timeout = self.executor.timer(DAP_REQUEST_TIMEOUT).fuse();
+        async move {
+            handle_response?;
+            send?;
+
+            let command = R::COMMAND;
+            select! {
+                response = rx.fuse() => response.context("debug adapter shut down")?,
+                _ = timeout => {
+                    log::error!("cancelled debug adapter request {command:?} seq {seq} which took over {DAP_REQUEST_TIMEOUT:?}");
+                    anyhow::bail!("debug adapter request timeout");
+                }
+            }
+        }
+    }
+
+    /// Registers a handler for events of the given type sent by the debug adapter.
+    pub fn on_event<E, F>(&self, mut f: F) -> Subscription
+    where
+        E: Event,
+        F: 'static + Send + FnMut(E::Body, AsyncAppContext),
+    {
+        let prev_handler = self.event_handlers.lock().insert(
+            E::EVENT,
+            Box::new(move |body, cx| {
+                if let Some(body) = serde_json::from_value(body).log_err() {
+                    f(body, cx);
+                }
+            }),
+        );
+        assert!(
+            prev_handler.is_none(),
+            "registered multiple handlers for the same debug adapter event"
+        );
+        Subscription {
+            name: E::EVENT,
+            handlers: Some(Subscribed::Event(self.event_handlers.clone())),
+        }
+    }
+
+    /// Registers a handler for requests of the given type sent by the other side of the connection.
+    pub fn on_request<R, F, Fut>(&self, mut f: F) -> Subscription
+    where
+        R: Request,
+        F: 'static + Send + FnMut(R::Arguments, AsyncAppContext) -> Fut,
+        Fut: 'static + Future<Output = Result<R::Response>>,
+    {
+        let outbound_tx = self.outbound_tx.clone();
+        let next_seq = self.next_seq.clone();
+        let prev_handler = self.request_handlers.lock().insert(
+            R::COMMAND,
+            Box::new(move |request_seq, arguments, cx| {
+                let response = serde_json::from_value(arguments)
+                    .map_err(anyhow::Error::from)
+                    .map(|arguments| f(arguments, cx.clone()));
+                let outbound_tx = outbound_tx.clone();
+                let next_seq = next_seq.clone();
+                cx.foreground_executor()
+                    .spawn(async move {
+                        let result = match response {
+                            Ok(response) => response.await,
+                            Err(error) => Err(error),
+                        };
+                        let response = match result.and_then(|body| Ok(serde_json::to_value(body)?))
+                        {
+                            Ok(body) => AnyResponse {
+                                seq: next_seq.fetch_add(1, SeqCst),
+                                request_seq,
+                                success: true,
+                                command: R::COMMAND.into(),
+                                message: None,
+                                body: (!body.is_null()).then_some(body),
+                            },
+                            Err(error) => AnyResponse {
+                                seq: next_seq.fetch_add(1, SeqCst),
+                                request_seq,
+                                success: false,
+                                command: R::COMMAND.into(),
+                                message: Some(error.to_string()),
+                                body: None,
+                            },
+                        };
+                        if let Some(response) =
+                            serde_json::to_string(&Message::Response(response)).log_err()
+                        {
+                            outbound_tx.try_send(response).ok();
+                        }
+                    })
+                    .detach();
+            }),
+        );
+        assert!(
+            prev_handler.is_none(),
+            "registered multiple handlers for the same debug adapter request"
+        );
+        Subscription {
+            name: R::COMMAND,
+            handlers: Some(Subscribed::Request(self.request_handlers.clone())),
+        }
+    }
+
+    /// Sends an event to the other side of the connection.
+    fn send_event<E: Event>(&self, body: E::Body) -> Result<()> {
+        let body = serde_json::to_value(body)?;
+        let message = serde_json::to_string(&Message::Event(AnyEvent {
+            seq: self.next_seq.fetch_add(1, SeqCst),
+            event: E::EVENT.into(),
+            body: (!body.is_null()).then_some(body),
+        }))?;
+        self.outbound_tx.try_send(message)?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DebugAdapterClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DebugAdapterClient")
+            .field("id", &self.id.0)
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Subscription {
+    pub fn detach(&mut self) {
+        self.handlers = None;
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        match self.handlers.take() {
+            Some(Subscribed::Event(handlers)) => {
+                handlers.lock().remove(self.name);
+            }
+            Some(Subscribed::Request(handlers)) => {
+                handlers.lock().remove(self.name);
+            }
+            None => {}
+        }
+    }
+}
+
+/// A debug adapter that runs in-process, for testing.
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Clone)]
+pub struct FakeDebugAdapter {
+    pub adapter: Arc<DebugAdapterClient>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl FakeDebugAdapter {
+    /// Creates a client connected to a fake debug adapter, which by default
+    /// answers `initialize` with the given capabilities.
+    pub fn new(
+        id: DebugAdapterClientId,
+        name: String,
+        capabilities: Capabilities,
+        cx: AsyncAppContext,
+    ) -> (DebugAdapterClient, FakeDebugAdapter) {
+        let (stdin_writer, stdin_reader) = async_pipe::pipe();
+        let (stdout_writer, stdout_reader) = async_pipe::pipe();
+
+        let mut client =
+            DebugAdapterClient::new_internal(id, stdin_writer, stdout_reader, None, cx.clone());
+        client.name = name.as_str().into();
+        let mut adapter =
+            DebugAdapterClient::new_internal(id, stdout_writer, stdin_reader, None, cx);
+        adapter.name = name.into();
+        let fake = FakeDebugAdapter {
+            adapter: Arc::new(adapter),
+        };
+        fake.handle_request::<requests::Initialize, _, _>(move |_, _| {
+            let capabilities = capabilities.clone();
+            async move { Ok(capabilities) }
+        });
+        (client, fake)
+    }
+
+    /// Handles requests of the given type, replacing any previous handler.
+    pub fn handle_request<R, F, Fut>(
+        &self,
+        mut handler: F,
+    ) -> futures::channel::mpsc::UnboundedReceiver<()>
+    where
+        R: 'static + Request,
+        F: 'static + Send + FnMut(R::Arguments, AsyncAppContext) -> Fut,
+        Fut: 'static + Send + Future<Output = Result<R::Response>>,
+    {
+        let (responded_tx, responded_rx) = futures::channel::mpsc::unbounded();
+        self.adapter.request_handlers.lock().remove(R::COMMAND);
+        self.adapter
+            .on_request::<R, _, _>(move |arguments, cx| {
+                let result = handler(arguments, cx.clone());
+                let responded_tx = responded_tx.clone();
+                let executor = cx.background_executor().clone();
+                async move {
+                    executor.simulate_random_delay().await;
+                    let result = result.await;
+                    responded_tx.unbounded_send(()).ok();
+                    result
+                }
+            })
+            .detach();
+        responded_rx
+    }
+
+    /// Sends an event to the client.
+    pub fn send_event<E: Event>(&self, body: E::Body) {
+        self.adapter.send_event::<E>(body).log_err();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+
+    #[gpui::test]
+    async fn test_fake_debug_adapter(cx: &mut TestAppContext) {
+        let (client, fake) = FakeDebugAdapter::new(
+            DebugAdapterClientId(0),
+            "fake-adapter".into(),
+            Capabilities {
+                supports_configuration_done_request: Some(true),
+                ..Default::default()
+            },
+            cx.to_async(),
+        );
+
+        let capabilities = client.initialize("fake".into()).await.unwrap();
+        assert_eq!(capabilities.supports_configuration_done_request, Some(true));
+        assert_eq!(client.capabilities(), capabilities);
+
+        fake.handle_request::<requests::Threads, _, _>(|_, _| async move {
+            Ok(ThreadsResponse {
+                threads: vec![Thread {
+                    id: 1,
+                    name: "main".into(),
+                }],
+            })
+        });
+        let threads = client.request::<requests::Threads>(()).await.unwrap();
+        assert_eq!(threads.threads[0].name, "main");
+
+        // Requests without a handler fail instead of hanging.
+        assert!(client
+            .request::<requests::Pause>(ThreadArguments { thread_id: 1 })
+            .await
+            .is_err());
+
+        let (stopped_tx, stopped_rx) = channel::unbounded();
+        client
+            .on_event::<events::Stopped, _>(move |body, _| {
+                stopped_tx.try_send(body).unwrap();
+            })
+            .detach();
+        fake.send_event::<events::Stopped>(StoppedEventBody {
+            reason: "breakpoint".into(),
+            description: None,
+            thread_id: Some(1),
+            all_threads_stopped: Some(true),
+            text: None,
+        });
+        let stopped = stopped_rx.recv().await.unwrap();
+        assert_eq!(stopped.reason, "breakpoint");
+        assert_eq!(stopped.thread_id, Some(1));
+    }
+}
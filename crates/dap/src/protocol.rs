@@ -0,0 +1,383 @@
+//! Types of the [Debug Adapter Protocol](https://microsoft.github.io/debug-adapter-protocol/specification).
+//!
+//! Only the subset of the protocol used by Zed is modeled here.
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+/// A request sent from the client to the debug adapter (or vice versa, for reverse requests).
+pub trait Request {
+    type Arguments: 'static + Serialize + DeserializeOwned + Send;
+    type Response: 'static + Serialize + DeserializeOwned + Send;
+    const COMMAND: &'static str;
+}
+
+/// An event sent from the debug adapter to the client.
+pub trait Event {
+    type Body: 'static + Serialize + DeserializeOwned + Send;
+    const EVENT: &'static str;
+}
+
+/// Any message exchanged with a debug adapter, before its payload is deserialized.
+///
+/// [DAP Specification](https://microsoft.github.io/debug-adapter-protocol/specification#Base_Protocol_ProtocolMessage)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub(crate) enum Message {
+    Request(AnyRequest),
+    Response(AnyResponse),
+    Event(AnyEvent),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct AnyRequest {
+    pub seq: u64,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AnyResponse {
+    pub seq: u64,
+    pub request_seq: u64,
+    pub success: bool,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct AnyEvent {
+    pub seq: u64,
+    pub event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitializeRequestArguments {
+    #[serde(rename = "clientID", skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_name: Option<String>,
+    #[serde(rename = "adapterID")]
+    pub adapter_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines_start_at1: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columns_start_at1: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_variable_type: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supports_run_in_terminal_request: Option<bool>,
+}
+
+/// The features supported by a debug adapter.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    #[serde(default)]
+    pub supports_configuration_done_request: Option<bool>,
+    #[serde(default)]
+    pub supports_conditional_breakpoints: Option<bool>,
+    #[serde(default)]
+    pub supports_terminate_request: Option<bool>,
+    #[serde(default)]
+    pub support_terminate_debuggee: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Source {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_reference: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceBreakpoint {
+    pub line: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Breakpoint {
+    #[serde(default)]
+    pub id: Option<u64>,
+    pub verified: bool,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub source: Option<Source>,
+    #[serde(default)]
+    pub line: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBreakpointsArguments {
+    pub source: Source,
+    pub breakpoints: Vec<SourceBreakpoint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_modified: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBreakpointsResponse {
+    pub breakpoints: Vec<Breakpoint>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigurationDoneArguments {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thread {
+    pub id: u64,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadsResponse {
+    pub threads: Vec<Thread>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTraceArguments {
+    pub thread_id: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_frame: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub levels: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackFrame {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub source: Option<Source>,
+    /// The 1-based line of the frame, or 0 if the frame has no source.
+    pub line: u64,
+    pub column: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StackTraceResponse {
+    pub stack_frames: Vec<StackFrame>,
+    #[serde(default)]
+    pub total_frames: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesArguments {
+    pub frame_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scope {
+    pub name: String,
+    pub variables_reference: u64,
+    #[serde(default)]
+    pub expensive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopesResponse {
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesArguments {
+    pub variables_reference: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Variable {
+    pub name: String,
+    pub value: String,
+    #[serde(rename = "type", default)]
+    pub type_: Option<String>,
+    /// If greater than zero, the variable has children which can be
+    /// retrieved with a `variables` request.
+    #[serde(default)]
+    pub variables_reference: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariablesResponse {
+    pub variables: Vec<Variable>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadArguments {
+    pub thread_id: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinueResponse {
+    #[serde(default)]
+    pub all_threads_continued: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DisconnectArguments {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminate_debuggee: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoppedEventBody {
+    pub reason: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub thread_id: Option<u64>,
+    #[serde(default)]
+    pub all_threads_stopped: Option<bool>,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContinuedEventBody {
+    pub thread_id: u64,
+    #[serde(default)]
+    pub all_threads_continued: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExitedEventBody {
+    pub exit_code: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputEventBody {
+    #[serde(default)]
+    pub category: Option<String>,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadEventBody {
+    pub reason: String,
+    pub thread_id: u64,
+}
+
+pub mod requests {
+    use super::*;
+
+    macro_rules! request {
+        ($name:ident, $command:literal, $arguments:ty, $response:ty) => {
+            #[derive(Debug)]
+            pub enum $name {}
+
+            impl Request for $name {
+                type Arguments = $arguments;
+                type Response = $response;
+                const COMMAND: &'static str = $command;
+            }
+        };
+    }
+
+    request!(
+        Initialize,
+        "initialize",
+        InitializeRequestArguments,
+        Capabilities
+    );
+    request!(Launch, "launch", Value, ());
+    request!(Attach, "attach", Value, ());
+    request!(
+        SetBreakpoints,
+        "setBreakpoints",
+        SetBreakpointsArguments,
+        SetBreakpointsResponse
+    );
+    request!(
+        ConfigurationDone,
+        "configurationDone",
+        ConfigurationDoneArguments,
+        ()
+    );
+    request!(Threads, "threads", (), ThreadsResponse);
+    request!(
+        StackTrace,
+        "stackTrace",
+        StackTraceArguments,
+        StackTraceResponse
+    );
+    request!(Scopes, "scopes", ScopesArguments, ScopesResponse);
+    request!(
+        Variables,
+        "variables",
+        VariablesArguments,
+        VariablesResponse
+    );
+    request!(Continue, "continue", ThreadArguments, ContinueResponse);
+    request!(Next, "next", ThreadArguments, ());
+    request!(StepIn, "stepIn", ThreadArguments, ());
+    request!(StepOut, "stepOut", ThreadArguments, ());
+    request!(Pause, "pause", ThreadArguments, ());
+    request!(Disconnect, "disconnect", DisconnectArguments, ());
+}
+
+pub mod events {
+    use super::*;
+
+    macro_rules! event {
+        ($name:ident, $event:literal, $body:ty) => {
+            #[derive(Debug)]
+            pub enum $name {}
+
+            impl Event for $name {
+                type Body = $body;
+                const EVENT: &'static str = $event;
+            }
+        };
+    }
+
+    event!(Initialized, "initialized", ());
+    event!(Stopped, "stopped", StoppedEventBody);
+    event!(Continued, "continued", ContinuedEventBody);
+    event!(Exited, "exited", ExitedEventBody);
+    event!(Terminated, "terminated", Option<Value>);
+    event!(Output, "output", OutputEventBody);
+    event!(Thread, "thread", ThreadEventBody);
+}
@@ -0,0 +1,73 @@
+use anyhow::{anyhow, Result};
+use futures::{AsyncBufReadExt, AsyncRead, AsyncReadExt as _, AsyncWrite, AsyncWriteExt as _};
+use smol::io::BufReader;
+
+const CONTENT_LEN_HEADER: &str = "Content-Length: ";
+const HEADER_DELIMITER: &[u8; 4] = b"\r\n\r\n";
+
+/// Reads a single message from a debug adapter's output, using the same
+/// `Content-Length` framing as the language server protocol.
+pub(crate) async fn read_message<Input>(
+    reader: &mut BufReader<Input>,
+    buffer: &mut Vec<u8>,
+) -> Result<()>
+where
+    Input: AsyncRead + Unpin + Send + 'static,
+{
+    buffer.clear();
+    loop {
+        if buffer.len() >= HEADER_DELIMITER.len()
+            && buffer[(buffer.len() - HEADER_DELIMITER.len())..] == HEADER_DELIMITER[..]
+        {
+            break;
+        }
+
+        if reader.read_until(b'\n', buffer).await? == 0 {
+            return Err(anyhow!("cannot read debug adapter message headers"));
+        }
+    }
+
+    let headers = std::str::from_utf8(buffer)?;
+    let message_len = headers
+        .split('\n')
+        .find_map(|line| line.strip_prefix(CONTENT_LEN_HEADER))
+        .ok_or_else(|| anyhow!("invalid debug adapter message header {headers:?}"))?
+        .trim_end()
+        .parse()?;
+
+    buffer.resize(message_len, 0);
+    reader.read_exact(buffer).await?;
+    Ok(())
+}
+
+/// Writes a single message to a debug adapter's input.
+pub(crate) async fn write_message<Output>(output: &mut Output, message: &str) -> Result<()>
+where
+    Output: AsyncWrite + Unpin + Send + 'static,
+{
+    output
+        .write_all(format!("{CONTENT_LEN_HEADER}{}\r\n\r\n", message.len()).as_bytes())
+        .await?;
+    output.write_all(message.as_bytes()).await?;
+    output.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    async fn test_read_message() {
+        let mut buffer = Vec::new();
+        let mut reader = BufReader::new(
+            b"Content-Length: 13\r\n\r\n{\"seq\":1}    Content-Type: application/json\r\nContent-Length: 2\r\n\r\n{}"
+                as &[u8],
+        );
+        read_message(&mut reader, &mut buffer).await.unwrap();
+        assert_eq!(buffer, b"{\"seq\":1}    ");
+        read_message(&mut reader, &mut buffer).await.unwrap();
+        assert_eq!(buffer, b"{}");
+        assert!(read_message(&mut reader, &mut buffer).await.is_err());
+    }
+}
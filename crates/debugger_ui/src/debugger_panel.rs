@@ -0,0 +1,740 @@
+use anyhow::Context as _;
+use collections::{HashMap, HashSet};
+use dap::{DebugAdapterConfig, Scope, StackFrame, Variable};
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{
+    actions, Action, AnyElement, AppContext, AsyncWindowContext, EventEmitter, FocusHandle,
+    FocusableView, Model, Subscription, Task, View, WeakView,
+};
+use project::{
+    debugger::{DebugSession, DebugSessionEvent, DebugSessionStatus},
+    Project,
+};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use text::{Bias, Point};
+use ui::{prelude::*, Disclosure, ListItem, Tooltip};
+use util::{ResultExt, TryFutureExt};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    notifications::DetachAndPromptErr,
+    Workspace,
+};
+use worktree::WorktreeId;
+
+actions!(
+    debugger,
+    [
+        Start,
+        Continue,
+        Pause,
+        StepOver,
+        StepInto,
+        StepOut,
+        Stop,
+        ToggleFocus
+    ]
+);
+
+const DEBUG_PANEL_KEY: &str = "DebugPanel";
+
+/// Row highlight for the line the debuggee is stopped at.
+struct DebugStoppedRow;
+
+#[derive(Serialize, Deserialize)]
+struct SerializedDebugPanel {
+    size: Option<Pixels>,
+    position: Option<SerializedDockPosition>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SerializedDockPosition {
+    Left,
+    Bottom,
+    Right,
+}
+
+pub struct DebugPanel {
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    focus_handle: FocusHandle,
+    size: Option<Pixels>,
+    position: DockPosition,
+    configurations: Vec<(WorktreeId, DebugAdapterConfig)>,
+    session: Option<Model<DebugSession>>,
+    selected_frame_id: Option<u64>,
+    scopes: Vec<Scope>,
+    variables: HashMap<u64, Vec<Variable>>,
+    expanded_variables: HashSet<u64>,
+    stopped_editor: Option<WeakView<Editor>>,
+    pending_serialization: Task<Option<()>>,
+    _session_subscription: Option<Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl DebugPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(DEBUG_PANEL_KEY) })
+            .await
+            .context("loading debug panel")
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedDebugPanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = Self::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.size = serialized_panel.size.map(|px| px.round());
+                    if let Some(position) = serialized_panel.position {
+                        panel.position = match position {
+                            SerializedDockPosition::Left => DockPosition::Left,
+                            SerializedDockPosition::Bottom => DockPosition::Bottom,
+                            SerializedDockPosition::Right => DockPosition::Right,
+                        };
+                    }
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let weak_workspace = workspace.weak_handle();
+        cx.new_view(|cx| {
+            let subscriptions = vec![cx.subscribe(&project, |this, _, event, cx| {
+                if let project::Event::DebugSessionsChanged = event {
+                    this.sync_session(cx);
+                }
+            })];
+            let mut this = Self {
+                workspace: weak_workspace,
+                project,
+                focus_handle: cx.focus_handle(),
+                size: None,
+                position: DockPosition::Bottom,
+                configurations: Vec::new(),
+                session: None,
+                selected_frame_id: None,
+                scopes: Vec::new(),
+                variables: HashMap::default(),
+                expanded_variables: HashSet::default(),
+                stopped_editor: None,
+                pending_serialization: Task::ready(None),
+                _session_subscription: None,
+                _subscriptions: subscriptions,
+            };
+            this.refresh_configurations(cx);
+            this.sync_session(cx);
+            this
+        })
+    }
+
+    /// Runs `f` on the debug panel of the workspace, if it has one.
+    pub(crate) fn dispatch(
+        workspace: &mut Workspace,
+        cx: &mut ViewContext<Workspace>,
+        f: impl FnOnce(&mut Self, &mut ViewContext<Self>),
+    ) {
+        if let Some(panel) = workspace.panel::<Self>(cx) {
+            panel.update(cx, f);
+        }
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let size = self.size;
+        let position = match self.position {
+            DockPosition::Left => SerializedDockPosition::Left,
+            DockPosition::Bottom => SerializedDockPosition::Bottom,
+            DockPosition::Right => SerializedDockPosition::Right,
+        };
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        DEBUG_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedDebugPanel {
+                            size,
+                            position: Some(position),
+                        })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    fn refresh_configurations(&mut self, cx: &mut ViewContext<Self>) {
+        let configurations = self.project.read(cx).debug_configurations(cx);
+        cx.spawn(|this, mut cx| async move {
+            let configurations = configurations.await;
+            this.update(&mut cx, |this, cx| {
+                this.configurations = configurations;
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Shows the most recently started debug session of the project.
+    fn sync_session(&mut self, cx: &mut ViewContext<Self>) {
+        let latest_session = self.project.read(cx).debug_sessions().last().cloned();
+        if latest_session == self.session {
+            return;
+        }
+
+        self.clear_stopped_location(cx);
+        self._session_subscription = latest_session.as_ref().map(|session| {
+            cx.subscribe(session, |this, _, event, cx| match event {
+                DebugSessionEvent::Stopped => this.show_stopped_location(cx),
+                DebugSessionEvent::Continued | DebugSessionEvent::Terminated => {
+                    this.clear_stopped_location(cx)
+                }
+                DebugSessionEvent::Output => cx.notify(),
+            })
+        });
+        self.session = latest_session;
+        cx.notify();
+    }
+
+    pub fn start(&mut self, _: &Start, cx: &mut ViewContext<Self>) {
+        if self.configurations.is_empty() {
+            self.refresh_configurations(cx);
+            cx.emit(PanelEvent::Activate);
+            return;
+        }
+        self.start_configuration(0, cx);
+    }
+
+    fn start_configuration(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some((worktree_id, config)) = self.configurations.get(ix).cloned() else {
+            return;
+        };
+        self.project
+            .update(cx, |project, cx| {
+                project.start_debug_session(worktree_id, config, cx)
+            })
+            .detach_and_prompt_err("Failed to start debugging", cx, |_, _| None);
+    }
+
+    pub fn continue_(&mut self, _: &Continue, cx: &mut ViewContext<Self>) {
+        match self.session_status(cx) {
+            None => self.start(&Start, cx),
+            Some(DebugSessionStatus::Stopped) => {
+                self.update_session(cx, |session, cx| session.continue_thread(cx))
+            }
+            Some(_) => {}
+        }
+    }
+
+    pub fn pause(&mut self, _: &Pause, cx: &mut ViewContext<Self>) {
+        if self.session_status(cx) == Some(DebugSessionStatus::Running) {
+            self.update_session(cx, |session, cx| session.pause(cx));
+        }
+    }
+
+    pub fn step_over(&mut self, _: &StepOver, cx: &mut ViewContext<Self>) {
+        self.update_session(cx, |session, cx| session.step_over(cx));
+    }
+
+    pub fn step_into(&mut self, _: &StepInto, cx: &mut ViewContext<Self>) {
+        self.update_session(cx, |session, cx| session.step_in(cx));
+    }
+
+    pub fn step_out(&mut self, _: &StepOut, cx: &mut ViewContext<Self>) {
+        self.update_session(cx, |session, cx| session.step_out(cx));
+    }
+
+    pub fn stop(&mut self, _: &Stop, cx: &mut ViewContext<Self>) {
+        self.update_session(cx, |session, cx| session.stop(cx));
+    }
+
+    fn session_status(&self, cx: &AppContext) -> Option<DebugSessionStatus> {
+        Some(self.session.as_ref()?.read(cx).status())
+    }
+
+    fn update_session(
+        &mut self,
+        cx: &mut ViewContext<Self>,
+        f: impl FnOnce(
+            &mut DebugSession,
+            &mut gpui::ModelContext<DebugSession>,
+        ) -> Task<anyhow::Result<()>>,
+    ) {
+        if let Some(session) = self.session.clone() {
+            session.update(cx, f).detach_and_log_err(cx);
+        }
+    }
+
+    fn show_stopped_location(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(session) = self.session.as_ref() else {
+            return;
+        };
+        if let Some(frame) = session.read(cx).stack_frames().first().cloned() {
+            self.select_frame(frame, cx);
+        }
+    }
+
+    fn clear_stopped_location(&mut self, cx: &mut ViewContext<Self>) {
+        self.selected_frame_id = None;
+        self.scopes.clear();
+        self.variables.clear();
+        self.expanded_variables.clear();
+        if let Some(editor) = self
+            .stopped_editor
+            .take()
+            .and_then(|editor| editor.upgrade())
+        {
+            editor.update(cx, |editor, cx| {
+                editor.clear_row_highlights::<DebugStoppedRow>();
+                cx.notify();
+            });
+        }
+        cx.notify();
+    }
+
+    fn select_frame(&mut self, frame: StackFrame, cx: &mut ViewContext<Self>) {
+        let Some(session) = self.session.clone() else {
+            return;
+        };
+        self.selected_frame_id = Some(frame.id);
+        self.scopes.clear();
+        self.variables.clear();
+        self.expanded_variables.clear();
+
+        let scopes = session.read(cx).scopes(frame.id, cx);
+        cx.spawn(|this, mut cx| async move {
+            let scopes = scopes.await?;
+            this.update(&mut cx, |this, cx| {
+                if this.selected_frame_id == Some(frame.id) {
+                    this.scopes = scopes;
+                    cx.notify();
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+
+        if let Some(path) = frame.source.as_ref().and_then(|source| source.path.clone()) {
+            self.open_frame_location(PathBuf::from(path), frame.line, cx);
+        }
+        cx.notify();
+    }
+
+    fn open_frame_location(&mut self, path: PathBuf, line: u64, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let open_task =
+            workspace.update(cx, |workspace, cx| workspace.open_abs_path(path, false, cx));
+        cx.spawn(|this, mut cx| {
+            async move {
+                let item = open_task.await?;
+                let editor = item
+                    .downcast::<Editor>()
+                    .context("debugged file is not opened in an editor")?;
+                this.update(&mut cx, |this, cx| {
+                    if let Some(previous_editor) = this
+                        .stopped_editor
+                        .take()
+                        .and_then(|editor| editor.upgrade())
+                    {
+                        previous_editor.update(cx, |editor, _| {
+                            editor.clear_row_highlights::<DebugStoppedRow>()
+                        });
+                    }
+                    editor.update(cx, |editor, cx| {
+                        let snapshot = editor.buffer().read(cx).snapshot(cx);
+                        let point = snapshot
+                            .clip_point(Point::new(line.saturating_sub(1) as u32, 0), Bias::Left);
+                        let anchor = snapshot.anchor_before(point);
+                        editor.highlight_rows::<DebugStoppedRow>(
+                            anchor..=anchor,
+                            Some(cx.theme().status().warning_background),
+                            true,
+                            cx,
+                        );
+                        editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                            selections.select_ranges([point..point])
+                        });
+                    });
+                    this.stopped_editor = Some(editor.downgrade());
+                })
+            }
+            .log_err()
+        })
+        .detach();
+    }
+
+    fn toggle_variable(&mut self, variables_reference: u64, cx: &mut ViewContext<Self>) {
+        if !self.expanded_variables.remove(&variables_reference) {
+            self.expanded_variables.insert(variables_reference);
+            if !self.variables.contains_key(&variables_reference) {
+                self.load_variables(variables_reference, cx);
+            }
+        }
+        cx.notify();
+    }
+
+    fn load_variables(&mut self, variables_reference: u64, cx: &mut ViewContext<Self>) {
+        let Some(session) = self.session.as_ref() else {
+            return;
+        };
+        let frame_id = self.selected_frame_id;
+        let variables = session.read(cx).variables(variables_reference, cx);
+        cx.spawn(|this, mut cx| async move {
+            let variables = variables.await?;
+            this.update(&mut cx, |this, cx| {
+                if this.selected_frame_id == frame_id {
+                    this.variables.insert(variables_reference, variables);
+                    cx.notify();
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_toolbar(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let status = self.session_status(cx);
+        let stopped = status == Some(DebugSessionStatus::Stopped);
+        let resume_button = if status == Some(DebugSessionStatus::Running) {
+            IconButton::new("debug-pause", IconName::DebugPause)
+                .tooltip(|cx| Tooltip::for_action("Pause", &Pause, cx))
+                .on_click(cx.listener(|this, _, cx| this.pause(&Pause, cx)))
+        } else {
+            IconButton::new("debug-continue", IconName::Play)
+                .disabled(status.is_some() && !stopped)
+                .tooltip(|cx| Tooltip::for_action("Continue", &Continue, cx))
+                .on_click(cx.listener(|this, _, cx| this.continue_(&Continue, cx)))
+        };
+
+        h_flex()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(resume_button)
+            .child(
+                IconButton::new("debug-step-over", IconName::DebugStepOver)
+                    .disabled(!stopped)
+                    .tooltip(|cx| Tooltip::for_action("Step Over", &StepOver, cx))
+                    .on_click(cx.listener(|this, _, cx| this.step_over(&StepOver, cx))),
+            )
+            .child(
+                IconButton::new("debug-step-into", IconName::DebugStepInto)
+                    .disabled(!stopped)
+                    .tooltip(|cx| Tooltip::for_action("Step Into", &StepInto, cx))
+                    .on_click(cx.listener(|this, _, cx| this.step_into(&StepInto, cx))),
+            )
+            .child(
+                IconButton::new("debug-step-out", IconName::DebugStepOut)
+                    .disabled(!stopped)
+                    .tooltip(|cx| Tooltip::for_action("Step Out", &StepOut, cx))
+                    .on_click(cx.listener(|this, _, cx| this.step_out(&StepOut, cx))),
+            )
+            .child(
+                IconButton::new("debug-stop", IconName::Stop)
+                    .disabled(status.is_none())
+                    .tooltip(|cx| Tooltip::for_action("Stop", &Stop, cx))
+                    .on_click(cx.listener(|this, _, cx| this.stop(&Stop, cx))),
+            )
+            .children(self.session.as_ref().map(|session| {
+                let session = session.read(cx);
+                let status = match session.status() {
+                    DebugSessionStatus::Starting => "Starting",
+                    DebugSessionStatus::Running => "Running",
+                    DebugSessionStatus::Stopped => "Paused",
+                    DebugSessionStatus::Terminated => "Terminated",
+                };
+                Label::new(format!("{} — {status}", session.config().label))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+            }))
+    }
+
+    fn render_configurations(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        if self.configurations.is_empty() {
+            return v_flex().p_4().child(
+                Label::new("No debug configurations found. Define them in .zed/debug.json.")
+                    .color(Color::Muted),
+            );
+        }
+
+        v_flex().p_1().children(
+            self.configurations
+                .iter()
+                .enumerate()
+                .map(|(ix, (_, config))| {
+                    ListItem::new(("debug-configuration", ix))
+                        .start_slot(Icon::new(IconName::Debug).color(Color::Muted))
+                        .child(Label::new(config.label.clone()))
+                        .on_click(cx.listener(move |this, _, cx| this.start_configuration(ix, cx)))
+                }),
+        )
+    }
+
+    fn render_session(
+        &self,
+        session: &Model<DebugSession>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let border_color = cx.theme().colors().border;
+        h_flex()
+            .size_full()
+            .items_start()
+            .child(
+                v_flex()
+                    .id("debug-stack-frames")
+                    .w_1_3()
+                    .h_full()
+                    .overflow_y_scroll()
+                    .border_r_1()
+                    .border_color(border_color)
+                    .child(self.render_stack_frames(session, cx)),
+            )
+            .child(
+                v_flex()
+                    .id("debug-variables")
+                    .w_1_3()
+                    .h_full()
+                    .overflow_y_scroll()
+                    .border_r_1()
+                    .border_color(border_color)
+                    .children(self.render_variables(cx)),
+            )
+            .child(
+                div()
+                    .id("debug-output")
+                    .flex_1()
+                    .h_full()
+                    .p_1()
+                    .overflow_y_scroll()
+                    .text_buffer(cx)
+                    .child(session.read(cx).output().to_string()),
+            )
+    }
+
+    fn render_stack_frames(
+        &self,
+        session: &Model<DebugSession>,
+        cx: &mut ViewContext<Self>,
+    ) -> impl IntoElement {
+        let session = session.read(cx);
+        let active_thread_id = session.active_thread_id();
+        let threads = session
+            .threads()
+            .iter()
+            .map(|thread| {
+                let thread_id = thread.id;
+                ListItem::new(("debug-thread", thread_id as usize))
+                    .selected(active_thread_id == Some(thread_id))
+                    .child(Label::new(thread.name.clone()).size(LabelSize::Small))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        if let Some(session) = this.session.clone() {
+                            session
+                                .update(cx, |session, cx| session.select_thread(thread_id, cx))
+                                .detach_and_log_err(cx);
+                        }
+                    }))
+            })
+            .collect::<Vec<_>>();
+        let frames =
+            session
+                .stack_frames()
+                .iter()
+                .map(|frame| {
+                    let location = frame
+                        .source
+                        .as_ref()
+                        .and_then(|source| source.name.clone().or_else(|| source.path.clone()))
+                        .map(|name| format!("{name}:{}", frame.line))
+                        .unwrap_or_default();
+                    let clicked_frame = frame.clone();
+                    ListItem::new(("debug-frame", frame.id as usize))
+                        .indent_level(1)
+                        .selected(self.selected_frame_id == Some(frame.id))
+                        .child(
+                            h_flex()
+                                .gap_2()
+                                .child(Label::new(frame.name.clone()).size(LabelSize::Small))
+                                .child(
+                                    Label::new(location)
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                ),
+                        )
+                        .on_click(cx.listener(move |this, _, cx| {
+                            this.select_frame(clicked_frame.clone(), cx)
+                        }))
+                })
+                .collect::<Vec<_>>();
+
+        v_flex().p_1().children(threads).children(frames)
+    }
+
+    fn render_variables(&self, cx: &mut ViewContext<Self>) -> Vec<AnyElement> {
+        let mut rows = Vec::new();
+        for scope in &self.scopes {
+            rows.push(self.render_variable_row(
+                scope.name.clone(),
+                None,
+                scope.variables_reference,
+                0,
+                cx,
+            ));
+            self.render_children(scope.variables_reference, 1, &mut rows, cx);
+        }
+        rows
+    }
+
+    fn render_children(
+        &self,
+        variables_reference: u64,
+        depth: usize,
+        rows: &mut Vec<AnyElement>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if !self.expanded_variables.contains(&variables_reference) {
+            return;
+        }
+        for variable in self
+            .variables
+            .get(&variables_reference)
+            .into_iter()
+            .flatten()
+        {
+            rows.push(self.render_variable_row(
+                variable.name.clone(),
+                Some(variable.value.clone()),
+                variable.variables_reference,
+                depth,
+                cx,
+            ));
+            // Guard against adapters that report cyclic references.
+            if variable.variables_reference != variables_reference {
+                self.render_children(variable.variables_reference, depth + 1, rows, cx);
+            }
+        }
+    }
+
+    fn render_variable_row(
+        &self,
+        name: String,
+        value: Option<String>,
+        variables_reference: u64,
+        depth: usize,
+        cx: &mut ViewContext<Self>,
+    ) -> AnyElement {
+        let expandable = variables_reference > 0;
+        let is_open = self.expanded_variables.contains(&variables_reference);
+        ListItem::new(SharedString::from(format!(
+            "debug-variable-{depth}-{variables_reference}-{name}"
+        )))
+        .indent_level(depth)
+        .start_slot(expandable.then(|| {
+            Disclosure::new(
+                ("debug-variable-toggle", variables_reference as usize),
+                is_open,
+            )
+        }))
+        .child(
+            h_flex()
+                .gap_2()
+                .child(Label::new(name).size(LabelSize::Small))
+                .children(
+                    value.map(|value| Label::new(value).size(LabelSize::Small).color(Color::Muted)),
+                ),
+        )
+        .when(expandable, |item| {
+            item.on_click(
+                cx.listener(move |this, _, cx| this.toggle_variable(variables_reference, cx)),
+            )
+        })
+        .into_any_element()
+    }
+}
+
+impl EventEmitter<PanelEvent> for DebugPanel {}
+
+impl FocusableView for DebugPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for DebugPanel {
+    fn persistent_name() -> &'static str {
+        "Debug Panel"
+    }
+
+    fn position(&self, _: &WindowContext) -> DockPosition {
+        self.position
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        self.position = position;
+        self.serialize(cx);
+    }
+
+    fn size(&self, _: &WindowContext) -> Pixels {
+        self.size.unwrap_or_else(|| match self.position {
+            DockPosition::Left | DockPosition::Right => px(320.),
+            DockPosition::Bottom => px(240.),
+        })
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.size = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, _: &WindowContext) -> Option<IconName> {
+        Some(IconName::Debug)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Debug Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn set_active(&mut self, active: bool, cx: &mut ViewContext<Self>) {
+        if active {
+            self.refresh_configurations(cx);
+        }
+    }
+}
+
+impl Render for DebugPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("DebugPanel")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .child(self.render_toolbar(cx))
+            .child(match self.session.clone() {
+                Some(session) => self.render_session(&session, cx).into_any_element(),
+                None => self.render_configurations(cx).into_any_element(),
+            })
+    }
+}
@@ -0,0 +1,36 @@
+mod debugger_panel;
+
+pub use debugger_panel::*;
+use gpui::AppContext;
+use workspace::Workspace;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, cx| {
+                workspace.toggle_panel_focus::<DebugPanel>(cx);
+            })
+            .register_action(|workspace, action: &Start, cx| {
+                DebugPanel::dispatch(workspace, cx, |panel, cx| panel.start(action, cx));
+            })
+            .register_action(|workspace, action: &Continue, cx| {
+                DebugPanel::dispatch(workspace, cx, |panel, cx| panel.continue_(action, cx));
+            })
+            .register_action(|workspace, action: &Pause, cx| {
+                DebugPanel::dispatch(workspace, cx, |panel, cx| panel.pause(action, cx));
+            })
+            .register_action(|workspace, action: &StepOver, cx| {
+                DebugPanel::dispatch(workspace, cx, |panel, cx| panel.step_over(action, cx));
+            })
+            .register_action(|workspace, action: &StepInto, cx| {
+                DebugPanel::dispatch(workspace, cx, |panel, cx| panel.step_into(action, cx));
+            })
+            .register_action(|workspace, action: &StepOut, cx| {
+                DebugPanel::dispatch(workspace, cx, |panel, cx| panel.step_out(action, cx));
+            })
+            .register_action(|workspace, action: &Stop, cx| {
+                DebugPanel::dispatch(workspace, cx, |panel, cx| panel.stop(action, cx));
+            });
+    })
+    .detach();
+}
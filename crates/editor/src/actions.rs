@@ -86,6 +86,12 @@ pub struct UnfoldAt {
     pub buffer_row: MultiBufferRow,
 }
 
+#[derive(PartialEq, Clone, Deserialize, Default)]
+pub struct FoldAtLevel {
+    /// The nesting depth of the ranges to fold, where 1 is the outermost level.
+    pub level: u32,
+}
+
 #[derive(PartialEq, Clone, Deserialize, Default)]
 pub struct MoveUpByLines {
     #[serde(default)]
@@ -141,6 +147,7 @@ impl_actions!(
         ExpandExcerptsUp,
         ExpandExcerptsDown,
         FoldAt,
+        FoldAtLevel,
         MoveDownByLines,
         MovePageDown,
         MovePageUp,
@@ -206,6 +213,9 @@ gpui::actions!(
         ExpandMacroRecursively,
         FindAllReferences,
         Fold,
+        FoldAll,
+        FoldAllComments,
+        FoldAllImports,
         FoldSelectedRanges,
         Format,
         GoToDefinition,
@@ -290,6 +300,7 @@ gpui::actions!(
         SortLinesCaseInsensitive,
         SortLinesCaseSensitive,
         SplitSelectionIntoLines,
+        StageHunk,
         Tab,
         TabPrev,
         ToggleGitBlame,
@@ -301,10 +312,13 @@ gpui::actions!(
         ToggleIndentGuides,
         ToggleSoftWrap,
         ToggleTabBar,
+        ToggleBreakpoint,
         Transpose,
         Undo,
         UndoSelection,
+        UnfoldAll,
         UnfoldLines,
+        UnstageHunk,
         UniqueLinesCaseInsensitive,
         UniqueLinesCaseSensitive,
     ]
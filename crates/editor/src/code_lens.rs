@@ -0,0 +1,532 @@
+use std::time::Duration;
+
+use collections::{BTreeMap, HashMap, HashSet};
+use futures::future;
+use gpui::{Model, Task, ViewContext, WeakView};
+use language::{
+    language_settings::language_settings, point_from_lsp, Bias, Buffer, BufferId, ClientCommand,
+    OffsetRangeExt, Point,
+};
+use multi_buffer::{Anchor, ExcerptId, ToPoint};
+use project::{CodeLens, Location, Project};
+use text::ToPoint as _;
+use ui::{prelude::*, Label, LabelSize};
+
+use crate::{
+    display_map::{
+        BlockContext, BlockDisposition, BlockId, BlockProperties, BlockStyle, RenderBlock,
+        ToDisplayPoint,
+    },
+    Editor, EditorMode, ToggleCodeActions,
+};
+
+const CODE_LENS_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Code lenses of the buffers shown in an editor, rendered as blocks above the lines they refer to.
+#[derive(Default)]
+pub(super) struct CodeLensState {
+    lines: HashMap<BlockId, CodeLensLine>,
+    /// The versions of the buffers whose code lenses were last requested.
+    fetched_versions: HashMap<BufferId, clock::Global>,
+    refresh_task: Option<Task<Option<()>>>,
+    resolve_task: Option<Task<Option<()>>>,
+}
+
+/// The code lenses shown above a line of an excerpt.
+#[derive(Clone)]
+struct CodeLensLine {
+    position: Anchor,
+    buffer: Model<Buffer>,
+    lenses: Vec<CodeLensEntry>,
+}
+
+#[derive(Clone)]
+struct CodeLensEntry {
+    lens: CodeLens,
+    /// Whether the command of the lens was requested from the language server,
+    /// as servers can compute it lazily.
+    resolved: bool,
+}
+
+impl Editor {
+    /// Requests the code lenses of a buffer again, or of all buffers when no buffer is given.
+    pub(super) fn invalidate_code_lens(
+        &mut self,
+        buffer_id: Option<BufferId>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        match buffer_id {
+            Some(buffer_id) => {
+                self.code_lens.fetched_versions.remove(&buffer_id);
+            }
+            None => self.code_lens.fetched_versions.clear(),
+        }
+        self.refresh_code_lens(cx);
+    }
+
+    /// Removes the code lenses shown in removed excerpts.
+    pub(super) fn remove_excerpt_code_lenses(
+        &mut self,
+        excerpt_ids: &[ExcerptId],
+        cx: &mut ViewContext<Self>,
+    ) {
+        let removed_block_ids = self
+            .code_lens
+            .lines
+            .iter()
+            .filter(|(_, line)| excerpt_ids.contains(&line.position.excerpt_id))
+            .map(|(block_id, _)| *block_id)
+            .collect::<HashSet<_>>();
+        if !removed_block_ids.is_empty() {
+            self.code_lens
+                .lines
+                .retain(|block_id, _| !removed_block_ids.contains(block_id));
+            self.remove_blocks(removed_block_ids, None, cx);
+        }
+    }
+
+    /// Requests the code lenses of the buffers near the viewport that changed since their lenses
+    /// were last requested, and replaces their shown lenses once the visible ones are resolved.
+    pub(super) fn refresh_code_lens(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        if self.mode != EditorMode::Full {
+            return;
+        }
+
+        let buffers = self
+            .buffers_near_viewport(cx)
+            .into_iter()
+            .filter(|buffer| {
+                let buffer = buffer.read(cx);
+                self.code_lens.fetched_versions.get(&buffer.remote_id()) != Some(&buffer.version())
+            })
+            .collect::<Vec<_>>();
+        if buffers.is_empty() {
+            return;
+        }
+
+        self.code_lens.refresh_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor().timer(CODE_LENS_DEBOUNCE).await;
+
+            let requests = editor
+                .update(&mut cx, |_, cx| {
+                    buffers
+                        .into_iter()
+                        .map(|buffer| {
+                            let (buffer_id, version, enabled) = {
+                                let buffer = buffer.read(cx);
+                                let settings =
+                                    language_settings(buffer.language(), buffer.file(), cx);
+                                (buffer.remote_id(), buffer.version(), settings.code_lens)
+                            };
+                            let request = enabled.then(|| {
+                                project.update(cx, |project, cx| project.code_lens(&buffer, cx))
+                            });
+                            async move {
+                                let response = match request {
+                                    Some(request) => request.await,
+                                    None => Ok(Vec::new()),
+                                };
+                                (buffer, buffer_id, version, response)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .ok()?;
+            let mut lenses = Vec::new();
+            let mut fetched_versions = HashMap::default();
+            for (buffer, buffer_id, version, response) in future::join_all(requests).await {
+                match response {
+                    Ok(buffer_lenses) => {
+                        lenses.push((buffer, buffer_lenses));
+                        fetched_versions.insert(buffer_id, version);
+                    }
+                    Err(error) => log::error!("failed to fetch code lenses: {error:#}"),
+                }
+            }
+
+            // Resolve the visible lenses before showing them, so that their
+            // titles don't disappear while the new lenses are resolved.
+            let resolve = editor
+                .update(&mut cx, |editor, cx| {
+                    let mut lines = editor.code_lens_lines(lenses, cx);
+                    lines.extend(
+                        editor
+                            .code_lens
+                            .lines
+                            .values()
+                            .filter(|line| {
+                                !fetched_versions.contains_key(&line.buffer.read(cx).remote_id())
+                            })
+                            .cloned(),
+                    );
+                    editor.resolve_code_lens_lines(lines, &project, cx)
+                })
+                .ok()?;
+            let lines = resolve.await;
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.code_lens.fetched_versions.extend(fetched_versions);
+                    editor.show_code_lens_lines(lines, cx);
+                    editor.resolve_visible_code_lenses(cx);
+                })
+                .ok()
+        }));
+    }
+
+    /// Resolves the commands of the code lenses that were scrolled into view.
+    pub(super) fn resolve_visible_code_lenses(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        if self.code_lens.lines.is_empty() || self.code_lens.resolve_task.is_some() {
+            return;
+        }
+
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let visible_rows = self.rows_near_viewport(&snapshot);
+        let (block_ids, lines): (Vec<_>, Vec<_>) = self
+            .code_lens
+            .lines
+            .iter()
+            .filter(|(_, line)| {
+                line.lenses.iter().any(|entry| !entry.resolved)
+                    && visible_rows.contains(&line.position.to_point(&snapshot).row)
+            })
+            .map(|(block_id, line)| (*block_id, line.clone()))
+            .unzip();
+        if lines.is_empty() {
+            return;
+        }
+
+        let resolve = self.resolve_code_lens_lines(lines, &project, cx);
+        self.code_lens.resolve_task = Some(cx.spawn(|editor, mut cx| async move {
+            let lines = resolve.await;
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.code_lens.resolve_task = None;
+                    let editor_handle = cx.view().downgrade();
+                    let mut blocks = HashMap::default();
+                    for (block_id, line) in block_ids.into_iter().zip(lines) {
+                        // The block may have been replaced by a refresh in the meantime.
+                        if let Some(current_line) = editor.code_lens.lines.get_mut(&block_id) {
+                            blocks.insert(
+                                block_id,
+                                (None, render_code_lens_line(editor_handle.clone(), &line)),
+                            );
+                            *current_line = line;
+                        }
+                    }
+                    editor.replace_blocks(blocks, None, cx);
+                    editor.resolve_visible_code_lenses(cx);
+                })
+                .ok()
+        }));
+    }
+
+    /// Groups code lenses by the excerpt lines they are shown above.
+    fn code_lens_lines(
+        &self,
+        lenses: Vec<(Model<Buffer>, Vec<CodeLens>)>,
+        cx: &mut ViewContext<Self>,
+    ) -> Vec<CodeLensLine> {
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+        let mut lines = Vec::new();
+        for (buffer, lenses) in lenses {
+            let buffer_snapshot = buffer.read(cx).snapshot();
+            let mut lenses_by_row = BTreeMap::<u32, Vec<CodeLens>>::new();
+            for lens in lenses {
+                let row = lens.range.start.to_point(&buffer_snapshot).row;
+                lenses_by_row.entry(row).or_default().push(lens);
+            }
+
+            for (excerpt_id, excerpt_range) in multi_buffer.excerpts_for_buffer(&buffer, cx) {
+                let context = excerpt_range.context.to_point(&buffer_snapshot);
+                for (row, lenses) in lenses_by_row.range(context.start.row..=context.end.row) {
+                    let indent = buffer_snapshot.indent_size_for_line(*row);
+                    let line_start = buffer_snapshot.anchor_before(Point::new(*row, indent.len));
+                    let Some(position) = snapshot.anchor_in_excerpt(excerpt_id, line_start) else {
+                        continue;
+                    };
+                    lines.push(CodeLensLine {
+                        position,
+                        buffer: buffer.clone(),
+                        lenses: lenses
+                            .iter()
+                            .map(|lens| CodeLensEntry {
+                                resolved: lens.lsp_lens.command.is_some(),
+                                lens: lens.clone(),
+                            })
+                            .collect(),
+                    });
+                }
+            }
+        }
+        lines
+    }
+
+    fn resolve_code_lens_lines(
+        &self,
+        mut lines: Vec<CodeLensLine>,
+        project: &Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Vec<CodeLensLine>> {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let visible_rows = self.rows_near_viewport(&snapshot);
+        let mut requests = Vec::new();
+        for (line_ix, line) in lines.iter().enumerate() {
+            if !visible_rows.contains(&line.position.to_point(&snapshot).row) {
+                continue;
+            }
+            for (lens_ix, entry) in line.lenses.iter().enumerate() {
+                if entry.resolved {
+                    continue;
+                }
+                let request = project.update(cx, |project, cx| {
+                    project.resolve_code_lens(&line.buffer, entry.lens.clone(), cx)
+                });
+                requests.push(async move { (line_ix, lens_ix, request.await) });
+            }
+        }
+
+        cx.background_executor().spawn(async move {
+            for (line_ix, lens_ix, response) in future::join_all(requests).await {
+                let entry = &mut lines[line_ix].lenses[lens_ix];
+                entry.resolved = true;
+                match response {
+                    Ok(lens) => entry.lens = lens,
+                    Err(error) => log::error!("failed to resolve code lens: {error:#}"),
+                }
+            }
+            lines
+        })
+    }
+
+    /// Shows the given lines, keeping the blocks of the lines that are already shown so that
+    /// refreshing the lenses doesn't make the lines below them jump.
+    fn show_code_lens_lines(&mut self, lines: Vec<CodeLensLine>, cx: &mut ViewContext<Self>) {
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut old_block_ids_by_row = self
+            .code_lens
+            .lines
+            .iter()
+            .map(|(block_id, line)| (line.position.to_point(&snapshot).row, *block_id))
+            .collect::<HashMap<_, _>>();
+
+        let editor = cx.view().downgrade();
+        let mut blocks_to_replace = HashMap::default();
+        let mut new_lines = Vec::new();
+        for line in lines {
+            let row = line.position.to_point(&snapshot).row;
+            let Some(block_id) = old_block_ids_by_row.remove(&row) else {
+                new_lines.push(line);
+                continue;
+            };
+            let old_line = &self.code_lens.lines[&block_id];
+            let unchanged = old_line.lenses.len() == line.lenses.len()
+                && old_line.lenses.iter().zip(&line.lenses).all(|(old, new)| {
+                    old.resolved == new.resolved
+                        && old.lens.lsp_lens.command == new.lens.lsp_lens.command
+                });
+            if !unchanged {
+                blocks_to_replace.insert(
+                    block_id,
+                    (None, render_code_lens_line(editor.clone(), &line)),
+                );
+            }
+            self.code_lens.lines.insert(block_id, line);
+        }
+
+        let removed_block_ids = old_block_ids_by_row.into_values().collect::<HashSet<_>>();
+        if !removed_block_ids.is_empty() {
+            self.code_lens
+                .lines
+                .retain(|block_id, _| !removed_block_ids.contains(block_id));
+            self.remove_blocks(removed_block_ids, None, cx);
+        }
+        if !blocks_to_replace.is_empty() {
+            self.replace_blocks(blocks_to_replace, None, cx);
+        }
+        if !new_lines.is_empty() {
+            let block_ids = self.insert_blocks(
+                new_lines.iter().map(|line| BlockProperties {
+                    position: line.position,
+                    height: 1,
+                    style: BlockStyle::Flex,
+                    render: render_code_lens_line(editor.clone(), line),
+                    disposition: BlockDisposition::Above,
+                }),
+                None,
+                cx,
+            );
+            self.code_lens
+                .lines
+                .extend(block_ids.into_iter().zip(new_lines));
+        }
+    }
+
+    fn run_code_lens(
+        &mut self,
+        position: Anchor,
+        buffer: Model<Buffer>,
+        lens: CodeLens,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(command) = lens.lsp_lens.command.clone() else {
+            return;
+        };
+        let Some(workspace) = self.workspace() else {
+            return;
+        };
+        let project = workspace.read(cx).project().clone();
+        match project
+            .read(cx)
+            .code_lens_client_command(&buffer, &lens, cx)
+        {
+            Some(ClientCommand::RunRunnable) => {
+                let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+                let row = position.to_display_point(&display_map).row();
+                self.focus(cx);
+                self.toggle_code_actions(
+                    &ToggleCodeActions {
+                        deployed_from_indicator: Some(row),
+                    },
+                    cx,
+                );
+            }
+            Some(ClientCommand::ShowReferences) => {
+                self.show_code_lens_locations(command, cx);
+            }
+            None => {
+                let apply_code_lens = project.update(cx, |project, cx| {
+                    project.apply_code_lens(buffer, lens, true, cx)
+                });
+                let workspace = workspace.downgrade();
+                cx.spawn(|editor, cx| async move {
+                    let project_transaction = apply_code_lens.await?;
+                    Self::open_project_transaction(
+                        &editor,
+                        workspace,
+                        project_transaction,
+                        command.title,
+                        cx,
+                    )
+                    .await
+                })
+                .detach_and_log_err(cx);
+            }
+        }
+    }
+
+    /// Opens the locations passed to a "show references" command, which is how servers
+    /// link reference and implementation counts to the locations they counted.
+    fn show_code_lens_locations(&mut self, command: lsp::Command, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace() else {
+            return;
+        };
+        let Some(lsp_locations) = command
+            .arguments
+            .and_then(|mut arguments| (arguments.len() == 3).then(|| arguments.remove(2)))
+            .and_then(|locations| {
+                serde_json::from_value::<Vec<lsp::Location>>(locations)
+                    .map_err(|error| log::error!("invalid code lens locations: {error}"))
+                    .ok()
+            })
+        else {
+            return;
+        };
+
+        let project = workspace.read(cx).project().clone();
+        let workspace = workspace.downgrade();
+        let location_tasks = lsp_locations
+            .into_iter()
+            .filter_map(|lsp_location| {
+                let abs_path = lsp_location.uri.to_file_path().ok()?;
+                let open_buffer = project.update(cx, |project, cx| {
+                    let project_path = project.project_path_for_absolute_path(&abs_path, cx)?;
+                    Some(project.open_buffer(project_path, cx))
+                })?;
+                Some(async move { (open_buffer.await, lsp_location.range) })
+            })
+            .collect::<Vec<_>>();
+        let replica_id = self.replica_id(cx);
+        let title = command.title;
+        cx.spawn(|_, mut cx| async move {
+            let mut locations = Vec::new();
+            for (buffer, range) in future::join_all(location_tasks).await {
+                let buffer = buffer?;
+                let range = buffer.update(&mut cx, |buffer, _| {
+                    let start = buffer.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+                    let end = buffer.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+                    buffer.anchor_after(start)..buffer.anchor_before(end)
+                })?;
+                locations.push(Location { buffer, range });
+            }
+            if locations.is_empty() {
+                return anyhow::Ok(());
+            }
+            workspace.update(&mut cx, |workspace, cx| {
+                Self::open_locations_in_multibuffer(
+                    workspace, locations, replica_id, title, false, cx,
+                );
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+fn render_code_lens_line(editor: WeakView<Editor>, line: &CodeLensLine) -> RenderBlock {
+    let position = line.position;
+    let buffer = line.buffer.clone();
+    let lenses = line
+        .lenses
+        .iter()
+        .filter(|entry| entry.lens.lsp_lens.command.is_some())
+        .map(|entry| entry.lens.clone())
+        .collect::<Vec<_>>();
+    Box::new(move |cx: &mut BlockContext| {
+        let mut element = h_flex()
+            .id(cx.block_id)
+            .h(cx.line_height)
+            .pl(cx.anchor_x)
+            .gap_1();
+        for (ix, lens) in lenses.iter().enumerate() {
+            let Some(command) = lens.lsp_lens.command.as_ref() else {
+                continue;
+            };
+            if ix > 0 {
+                element = element.child(
+                    Label::new("|")
+                        .size(LabelSize::XSmall)
+                        .color(Color::Disabled),
+                );
+            }
+            element = element.child(
+                div()
+                    .id(ix)
+                    .cursor_pointer()
+                    .child(
+                        Label::new(command.title.clone())
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                    .on_click({
+                        let editor = editor.clone();
+                        let buffer = buffer.clone();
+                        let lens = lens.clone();
+                        move |_, cx| {
+                            editor
+                                .update(cx, |editor, cx| {
+                                    editor.run_code_lens(position, buffer.clone(), lens.clone(), cx)
+                                })
+                                .ok();
+                        }
+                    }),
+            );
+        }
+        element.into_any_element()
+    })
+}
@@ -43,13 +43,13 @@ pub(crate) use inlay_map::Inlay;
 use inlay_map::{InlayMap, InlaySnapshot};
 pub use inlay_map::{InlayOffset, InlayPoint};
 use language::{
-    language_settings::language_settings, ChunkRenderer, OffsetUtf16, Point,
-    Subscription as BufferSubscription,
+    language_settings::language_settings, BufferId, ChunkRenderer, FoldingRange, HighlightId,
+    OffsetUtf16, Point, Subscription as BufferSubscription,
 };
 use lsp::DiagnosticSeverity;
 use multi_buffer::{
-    Anchor, AnchorRangeExt, MultiBuffer, MultiBufferPoint, MultiBufferRow, MultiBufferSnapshot,
-    ToOffset, ToPoint,
+    Anchor, AnchorRangeExt, ExcerptId, MultiBuffer, MultiBufferPoint, MultiBufferRow,
+    MultiBufferSnapshot, ToOffset, ToPoint,
 };
 use serde::Deserialize;
 use std::{
@@ -80,8 +80,19 @@ pub trait ToDisplayPoint {
     fn to_display_point(&self, map: &DisplaySnapshot) -> DisplayPoint;
 }
 
-type TextHighlights = TreeMap<Option<TypeId>, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>;
+/// Identifies a set of [`TextHighlights`] sharing a style.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum HighlightKey {
+    /// Highlights of the language server's semantic tokens, one set per syntax style.
+    /// These come first, so that all other highlights are applied over them.
+    SemanticToken(HighlightId),
+    Type(TypeId),
+}
+
+type TextHighlights = TreeMap<HighlightKey, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>;
 type InlayHighlights = TreeMap<TypeId, TreeMap<InlayId, (HighlightStyle, InlayHighlight)>>;
+/// The syntax-aware folding ranges of each buffer, sorted by their start.
+pub(crate) type FoldingRanges = TreeMap<BufferId, Arc<[FoldingRange<Anchor>]>>;
 
 /// Decides how text in a [`MultiBuffer`] should be displayed in a buffer, handling inlay hints,
 /// folding, hard tabs, soft wrapping, custom blocks (like diagnostics), and highlighting.
@@ -107,6 +118,9 @@ pub struct DisplayMap {
     inlay_highlights: InlayHighlights,
     /// A container for explicitly foldable ranges, which supersede indentation based fold range suggestions.
     crease_map: CreaseMap,
+    /// Ranges that can be folded according to the syntax of the buffers, which supersede indentation
+    /// based fold range suggestions in the buffers that have them.
+    folding_ranges: FoldingRanges,
     fold_placeholder: FoldPlaceholder,
     pub clip_at_line_ends: bool,
 }
@@ -152,6 +166,7 @@ impl DisplayMap {
             wrap_map,
             block_map,
             crease_map,
+            folding_ranges: Default::default(),
             fold_placeholder,
             text_highlights: Default::default(),
             inlay_highlights: Default::default(),
@@ -179,6 +194,7 @@ impl DisplayMap {
             wrap_snapshot,
             block_snapshot,
             crease_snapshot: self.crease_map.snapshot(),
+            folding_ranges: self.folding_ranges.clone(),
             text_highlights: self.text_highlights.clone(),
             inlay_highlights: self.inlay_highlights.clone(),
             clip_at_line_ends: self.clip_at_line_ends,
@@ -265,6 +281,37 @@ impl DisplayMap {
         self.crease_map.remove(crease_ids, &snapshot)
     }
 
+    /// Replaces the folding ranges of a buffer, or falls back to folding it by indentation.
+    pub(crate) fn set_buffer_folding_ranges(
+        &mut self,
+        buffer_id: BufferId,
+        folding_ranges: Option<Arc<[FoldingRange<Anchor>]>>,
+    ) {
+        match folding_ranges {
+            Some(folding_ranges) => self.folding_ranges.insert(buffer_id, folding_ranges),
+            None => {
+                self.folding_ranges.remove(&buffer_id);
+            }
+        }
+    }
+
+    pub(crate) fn remove_excerpt_folding_ranges(&mut self, excerpt_ids: &[ExcerptId]) {
+        let mut folding_ranges = FoldingRanges::default();
+        for (buffer_id, buffer_ranges) in self.folding_ranges.iter() {
+            let buffer_ranges = buffer_ranges
+                .iter()
+                .filter(|folding_range| {
+                    !excerpt_ids.contains(&folding_range.range.start.excerpt_id)
+                })
+                .cloned()
+                .collect::<Arc<[_]>>();
+            if !buffer_ranges.is_empty() {
+                folding_ranges.insert(*buffer_id, buffer_ranges);
+            }
+        }
+        self.folding_ranges = folding_ranges;
+    }
+
     pub fn insert_blocks(
         &mut self,
         blocks: impl IntoIterator<Item = BlockProperties<Anchor>>,
@@ -374,7 +421,7 @@ impl DisplayMap {
         style: HighlightStyle,
     ) {
         self.text_highlights
-            .insert(Some(type_id), Arc::new((style, ranges)));
+            .insert(HighlightKey::Type(type_id), Arc::new((style, ranges)));
     }
 
     pub(crate) fn highlight_inlays(
@@ -396,12 +443,31 @@ impl DisplayMap {
         }
     }
 
+    /// Replaces the highlights of semantic tokens, given as ranges sorted by their start for
+    /// each syntax style.
+    pub(crate) fn set_semantic_token_highlights(
+        &mut self,
+        highlights: impl IntoIterator<Item = (HighlightId, HighlightStyle, Vec<Range<Anchor>>)>,
+    ) {
+        self.text_highlights
+            .retain(|key, _| !matches!(key, HighlightKey::SemanticToken(_)));
+        for (highlight_id, style, ranges) in highlights {
+            self.text_highlights.insert(
+                HighlightKey::SemanticToken(highlight_id),
+                Arc::new((style, ranges)),
+            );
+        }
+    }
+
     pub fn text_highlights(&self, type_id: TypeId) -> Option<(HighlightStyle, &[Range<Anchor>])> {
-        let highlights = self.text_highlights.get(&Some(type_id))?;
+        let highlights = self.text_highlights.get(&HighlightKey::Type(type_id))?;
         Some((highlights.0, &highlights.1))
     }
     pub fn clear_highlights(&mut self, type_id: TypeId) -> bool {
-        let mut cleared = self.text_highlights.remove(&Some(type_id)).is_some();
+        let mut cleared = self
+            .text_highlights
+            .remove(&HighlightKey::Type(type_id))
+            .is_some();
         cleared |= self.inlay_highlights.remove(&type_id).is_some();
         cleared
     }
@@ -492,6 +558,7 @@ pub struct DisplaySnapshot {
     pub buffer_snapshot: MultiBufferSnapshot,
     pub fold_snapshot: FoldSnapshot,
     pub crease_snapshot: CreaseSnapshot,
+    folding_ranges: FoldingRanges,
     inlay_snapshot: InlaySnapshot,
     tab_snapshot: TabSnapshot,
     wrap_snapshot: WrapSnapshot,
@@ -512,6 +579,12 @@ impl DisplaySnapshot {
         self.buffer_snapshot.len() == 0
     }
 
+    /// Changes whenever the text of the display rows or their layout changes, but not when the
+    /// buffer is only reparsed.
+    pub fn rows_version(&self) -> usize {
+        self.block_snapshot.version
+    }
+
     pub fn buffer_rows(
         &self,
         start_row: DisplayRow,
@@ -970,11 +1043,20 @@ impl DisplaySnapshot {
             .unwrap_or(false)
     }
 
+    /// Whether a range that can be folded starts on the given row.
+    pub fn starts_fold(&self, buffer_row: MultiBufferRow) -> bool {
+        match self.buffer_folding_ranges(buffer_row) {
+            Some(folding_ranges) => self
+                .largest_folding_range_at(folding_ranges, buffer_row)
+                .is_some(),
+            None => self.starts_indent(buffer_row),
+        }
+    }
+
     pub fn foldable_range(
         &self,
         buffer_row: MultiBufferRow,
     ) -> Option<(Range<Point>, FoldPlaceholder)> {
-        let start = MultiBufferPoint::new(buffer_row.0, self.buffer_snapshot.line_len(buffer_row));
         if let Some(crease) = self
             .crease_snapshot
             .query_row(buffer_row, &self.buffer_snapshot)
@@ -983,46 +1065,192 @@ impl DisplaySnapshot {
                 crease.range.to_point(&self.buffer_snapshot),
                 crease.placeholder.clone(),
             ))
-        } else if self.starts_indent(MultiBufferRow(start.row))
-            && !self.is_line_folded(MultiBufferRow(start.row))
-        {
-            let start_line_indent = self.line_indent_for_buffer_row(buffer_row);
-            let max_point = self.buffer_snapshot.max_point();
-            let mut end = None;
-
-            for row in (buffer_row.0 + 1)..=max_point.row {
-                let line_indent = self.line_indent_for_buffer_row(MultiBufferRow(row));
-                if !line_indent.is_line_blank()
-                    && line_indent.raw_len() <= start_line_indent.raw_len()
+        } else if self.is_line_folded(buffer_row) {
+            None
+        } else if let Some(folding_ranges) = self.buffer_folding_ranges(buffer_row) {
+            self.largest_folding_range_at(folding_ranges, buffer_row)
+                .map(|range| (range, self.fold_placeholder.clone()))
+        } else {
+            self.indent_fold_range(buffer_row)
+                .map(|range| (range, self.fold_placeholder.clone()))
+        }
+    }
+
+    /// All the ranges that can be folded, with their nesting depth starting at 1, sorted by their
+    /// start. Buffers without syntax-aware folding ranges contribute their indentation based ranges.
+    pub fn folding_ranges_with_depth(&self) -> Vec<(FoldingRange<Point>, u32)> {
+        let mut ranges = self
+            .folding_ranges
+            .values()
+            .flat_map(|folding_ranges| folding_ranges.iter())
+            .map(|folding_range| FoldingRange {
+                range: folding_range.range.to_point(&self.buffer_snapshot),
+                kind: folding_range.kind,
+            })
+            .filter(|folding_range| folding_range.range.end.row > folding_range.range.start.row)
+            .collect::<Vec<_>>();
+        ranges.extend(
+            self.indent_fold_ranges()
+                .into_iter()
+                .filter(|range| {
+                    self.buffer_folding_ranges(MultiBufferRow(range.start.row))
+                        .is_none()
+                })
+                .map(|range| FoldingRange { range, kind: None }),
+        );
+        ranges.sort_by(|a, b| {
+            a.range
+                .start
+                .cmp(&b.range.start)
+                .then_with(|| b.range.end.cmp(&a.range.end))
+        });
+
+        let mut enclosing_ends = Vec::<Point>::new();
+        ranges
+            .into_iter()
+            .map(|range| {
+                while enclosing_ends
+                    .last()
+                    .map_or(false, |end| *end < range.range.end)
                 {
-                    let prev_row = row - 1;
-                    end = Some(Point::new(
-                        prev_row,
-                        self.buffer_snapshot.line_len(MultiBufferRow(prev_row)),
-                    ));
-                    break;
+                    enclosing_ends.pop();
+                }
+                enclosing_ends.push(range.range.end);
+                let depth = enclosing_ends.len() as u32;
+                (range, depth)
+            })
+            .collect()
+    }
+
+    fn buffer_folding_ranges(&self, buffer_row: MultiBufferRow) -> Option<&[FoldingRange<Anchor>]> {
+        let (buffer, _) = self.buffer_snapshot.buffer_line_for_row(buffer_row)?;
+        self.folding_ranges
+            .get(&buffer.remote_id())
+            .map(|folding_ranges| folding_ranges.as_ref())
+    }
+
+    fn largest_folding_range_at(
+        &self,
+        folding_ranges: &[FoldingRange<Anchor>],
+        buffer_row: MultiBufferRow,
+    ) -> Option<Range<Point>> {
+        let start_ix = folding_ranges.partition_point(|folding_range| {
+            folding_range
+                .range
+                .start
+                .to_point(&self.buffer_snapshot)
+                .row
+                < buffer_row.0
+        });
+        folding_ranges[start_ix..]
+            .iter()
+            .map(|folding_range| folding_range.range.to_point(&self.buffer_snapshot))
+            .take_while(|range| range.start.row == buffer_row.0)
+            .filter(|range| range.end.row > range.start.row)
+            .max_by_key(|range| range.end)
+    }
+
+    /// The ranges that [`Self::indent_fold_range`] returns for every row, computed in a single
+    /// pass over the lines with a stack of the ranges that are still open.
+    fn indent_fold_ranges(&self) -> Vec<Range<Point>> {
+        struct OpenRange {
+            row: u32,
+            indent: u32,
+            /// Whether a more indented line follows, as opposed to a blank one so far.
+            confirmed: bool,
+        }
+
+        let max_row = self.buffer_snapshot.max_buffer_row().0;
+        let mut ranges = Vec::new();
+        let mut open_ranges = Vec::<OpenRange>::new();
+        let mut last_non_blank_row = 0;
+        let mut close = |open_range: OpenRange, last_non_blank_row: u32| {
+            // Trailing blank lines aren't folded.
+            let end_row = last_non_blank_row.max(open_range.row);
+            let line_end =
+                |row| Point::new(row, self.buffer_snapshot.line_len(MultiBufferRow(row)));
+            ranges.push(line_end(open_range.row)..line_end(end_row));
+        };
+
+        for row in 0..=max_row {
+            let line_indent = self.line_indent_for_buffer_row(MultiBufferRow(row));
+            let indent = line_indent.raw_len();
+            let is_blank = line_indent.is_line_blank();
+
+            if let Some(candidate) = open_ranges.last_mut().filter(|range| !range.confirmed) {
+                if indent > candidate.indent {
+                    candidate.confirmed = true;
+                } else if !is_blank {
+                    open_ranges.pop();
                 }
             }
+            if is_blank {
+                continue;
+            }
 
-            let mut row_before_line_breaks = end.unwrap_or(max_point);
-            while row_before_line_breaks.row > start.row
-                && self
-                    .buffer_snapshot
-                    .is_line_blank(MultiBufferRow(row_before_line_breaks.row))
+            while open_ranges
+                .last()
+                .map_or(false, |open_range| open_range.indent >= indent)
             {
-                row_before_line_breaks.row -= 1;
+                close(open_ranges.pop().unwrap(), last_non_blank_row);
             }
+            if row < max_row {
+                open_ranges.push(OpenRange {
+                    row,
+                    indent,
+                    confirmed: false,
+                });
+            }
+            last_non_blank_row = row;
+        }
 
-            row_before_line_breaks = Point::new(
-                row_before_line_breaks.row,
-                self.buffer_snapshot
-                    .line_len(MultiBufferRow(row_before_line_breaks.row)),
-            );
+        for open_range in open_ranges.into_iter().rev() {
+            if open_range.confirmed {
+                close(open_range, last_non_blank_row);
+            }
+        }
+        ranges
+    }
 
-            Some((start..row_before_line_breaks, self.fold_placeholder.clone()))
-        } else {
-            None
+    fn indent_fold_range(&self, buffer_row: MultiBufferRow) -> Option<Range<Point>> {
+        if !self.starts_indent(buffer_row) {
+            return None;
+        }
+
+        let start = MultiBufferPoint::new(buffer_row.0, self.buffer_snapshot.line_len(buffer_row));
+        let start_line_indent = self.line_indent_for_buffer_row(buffer_row);
+        let max_point = self.buffer_snapshot.max_point();
+        let mut end = None;
+
+        for row in (buffer_row.0 + 1)..=max_point.row {
+            let line_indent = self.line_indent_for_buffer_row(MultiBufferRow(row));
+            if !line_indent.is_line_blank() && line_indent.raw_len() <= start_line_indent.raw_len()
+            {
+                let prev_row = row - 1;
+                end = Some(Point::new(
+                    prev_row,
+                    self.buffer_snapshot.line_len(MultiBufferRow(prev_row)),
+                ));
+                break;
+            }
         }
+
+        let mut row_before_line_breaks = end.unwrap_or(max_point);
+        while row_before_line_breaks.row > start.row
+            && self
+                .buffer_snapshot
+                .is_line_blank(MultiBufferRow(row_before_line_breaks.row))
+        {
+            row_before_line_breaks.row -= 1;
+        }
+
+        row_before_line_breaks = Point::new(
+            row_before_line_breaks.row,
+            self.buffer_snapshot
+                .line_len(MultiBufferRow(row_before_line_breaks.row)),
+        );
+
+        Some(start..row_before_line_breaks)
     }
 
     #[cfg(any(test, feature = "test-support"))]
@@ -1030,7 +1258,9 @@ impl DisplaySnapshot {
         &self,
     ) -> Option<Arc<(HighlightStyle, Vec<Range<Anchor>>)>> {
         let type_id = TypeId::of::<Tag>();
-        self.text_highlights.get(&Some(type_id)).cloned()
+        self.text_highlights
+            .get(&HighlightKey::Type(type_id))
+            .cloned()
     }
 
     #[allow(unused)]
@@ -2018,6 +2248,45 @@ pub mod tests {
         });
     }
 
+    #[gpui::test]
+    fn test_indent_fold_ranges(cx: &mut gpui::AppContext) {
+        init_test(cx, |_| {});
+
+        let text = "fn a() {\n    if b {\n        c();\n\n    }\n  \n}\n\nfn d() {\n  \n    e();\n\n\nf\n    g\n";
+        let buffer = MultiBuffer::build_simple(text, cx);
+        let map = cx.new_model(|cx| {
+            DisplayMap::new(
+                buffer.clone(),
+                font("Helvetica"),
+                px(14.0),
+                None,
+                true,
+                1,
+                1,
+                0,
+                FoldPlaceholder::test(),
+                cx,
+            )
+        });
+        let snapshot = map.update(cx, |map, cx| map.snapshot(cx));
+
+        let mut ranges = snapshot.indent_fold_ranges();
+        ranges.sort_by_key(|range| range.start);
+        let expected = (0..=snapshot.buffer_snapshot.max_buffer_row().0)
+            .filter_map(|row| snapshot.indent_fold_range(MultiBufferRow(row)))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, expected);
+        assert_eq!(
+            ranges,
+            [
+                Point::new(0, 8)..Point::new(4, 5),
+                Point::new(1, 10)..Point::new(2, 12),
+                Point::new(8, 8)..Point::new(10, 8),
+                Point::new(13, 1)..Point::new(14, 5),
+            ]
+        );
+    }
+
     #[gpui::test]
     fn test_tabs_with_multibyte_chars(cx: &mut gpui::AppContext) {
         init_test(cx, |_| {});
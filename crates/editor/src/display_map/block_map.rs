@@ -9,7 +9,7 @@ use language::{BufferSnapshot, Chunk, Patch, Point};
 use multi_buffer::{Anchor, ExcerptId, ExcerptRange, MultiBufferRow, ToPoint as _};
 use parking_lot::Mutex;
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     cmp::{self, Ordering},
     fmt::Debug,
     ops::{Deref, DerefMut, Range, RangeBounds},
@@ -31,6 +31,7 @@ pub struct BlockMap {
     wrap_snapshot: RefCell<WrapSnapshot>,
     blocks: Vec<Arc<Block>>,
     transforms: RefCell<SumTree<Transform>>,
+    version: Cell<usize>,
     show_excerpt_controls: bool,
     buffer_header_height: u8,
     excerpt_header_height: u8,
@@ -48,6 +49,8 @@ pub struct BlockMapWriter<'a>(&'a mut BlockMap);
 pub struct BlockSnapshot {
     wrap_snapshot: WrapSnapshot,
     transforms: SumTree<Transform>,
+    /// Incremented whenever the rows change, including the layers below the blocks.
+    pub version: usize,
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -235,6 +238,7 @@ impl BlockMap {
             next_block_id: AtomicUsize::new(0),
             blocks: Vec::new(),
             transforms: RefCell::new(SumTree::from_item(Transform::isomorphic(row_count), &())),
+            version: Cell::new(0),
             wrap_snapshot: RefCell::new(wrap_snapshot.clone()),
             show_excerpt_controls,
             buffer_header_height,
@@ -259,6 +263,7 @@ impl BlockMap {
             snapshot: BlockSnapshot {
                 wrap_snapshot,
                 transforms: self.transforms.borrow().clone(),
+                version: self.version.get(),
             },
         }
     }
@@ -473,6 +478,7 @@ impl BlockMap {
 
         drop(cursor);
         *transforms = new_transforms;
+        self.version.set(self.version.get() + 1);
     }
 
     pub fn replace_renderers(&mut self, mut renderers: HashMap<BlockId, RenderBlock>) {
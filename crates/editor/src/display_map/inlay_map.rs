@@ -6,7 +6,6 @@ use multi_buffer::{
     Anchor, MultiBufferChunks, MultiBufferRow, MultiBufferRows, MultiBufferSnapshot, ToOffset,
 };
 use std::{
-    any::TypeId,
     cmp,
     iter::Peekable,
     ops::{Add, AddAssign, Range, Sub, SubAssign},
@@ -16,7 +15,7 @@ use std::{
 use sum_tree::{Bias, Cursor, SumTree, TreeMap};
 use text::{Patch, Rope};
 
-use super::Highlights;
+use super::{HighlightKey, Highlights};
 
 /// Decides where the [`Inlay`]s should be displayed.
 ///
@@ -191,7 +190,7 @@ pub struct InlayBufferRows<'a> {
 struct HighlightEndpoint {
     offset: InlayOffset,
     is_start: bool,
-    tag: Option<TypeId>,
+    tag: HighlightKey,
     style: HighlightStyle,
 }
 
@@ -219,7 +218,7 @@ pub struct InlayChunks<'a> {
     max_output_offset: InlayOffset,
     highlight_styles: HighlightStyles,
     highlight_endpoints: Peekable<vec::IntoIter<HighlightEndpoint>>,
-    active_highlights: BTreeMap<Option<TypeId>, HighlightStyle>,
+    active_highlights: BTreeMap<HighlightKey, HighlightStyle>,
     highlights: Highlights<'a>,
     snapshot: &'a InlaySnapshot,
 }
@@ -1062,7 +1061,7 @@ impl InlaySnapshot {
         &self,
         cursor: &mut Cursor<'_, Transform, (InlayOffset, usize)>,
         range: &Range<InlayOffset>,
-        text_highlights: &TreeMap<Option<TypeId>, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>,
+        text_highlights: &TreeMap<HighlightKey, Arc<(HighlightStyle, Vec<Range<Anchor>>)>>,
         highlight_endpoints: &mut Vec<HighlightEndpoint>,
     ) {
         while cursor.start().0 < range.end {
@@ -1175,7 +1174,7 @@ mod tests {
     use project::{InlayHint, InlayHintLabel, ResolveState};
     use rand::prelude::*;
     use settings::SettingsStore;
-    use std::{cmp::Reverse, env, sync::Arc};
+    use std::{any::TypeId, cmp::Reverse, env, sync::Arc};
     use text::Patch;
     use util::post_inc;
 
@@ -1674,7 +1673,7 @@ mod tests {
             text_highlight_ranges.sort_by_key(|range| (range.start, Reverse(range.end)));
             log::info!("highlighting text ranges {text_highlight_ranges:?}");
             text_highlights.insert(
-                Some(TypeId::of::<()>()),
+                HighlightKey::Type(TypeId::of::<()>()),
                 Arc::new((
                     HighlightStyle::default(),
                     text_highlight_ranges
@@ -15,10 +15,13 @@
 pub mod actions;
 mod blame_entry_tooltip;
 mod blink_manager;
+mod code_lens;
 mod debounced_delay;
 pub mod display_map;
 mod editor_settings;
 mod element;
+mod file_state;
+mod folding_ranges;
 mod git;
 mod highlight_matching_bracket;
 mod hover_links;
@@ -35,6 +38,7 @@ mod persistence;
 mod rust_analyzer_ext;
 pub mod scroll;
 mod selections_collection;
+mod semantic_tokens;
 pub mod tasks;
 
 #[cfg(test)]
@@ -49,7 +53,7 @@ use anyhow::{anyhow, Context as _, Result};
 use blink_manager::BlinkManager;
 use client::{Collaborator, ParticipantIndex};
 use clock::ReplicaId;
-use collections::{BTreeMap, Bound, HashMap, HashSet, VecDeque};
+use collections::{BTreeMap, BTreeSet, Bound, HashMap, HashSet, VecDeque};
 use convert_case::{Case, Casing};
 use debounced_delay::DebouncedDelay;
 use display_map::*;
@@ -59,6 +63,7 @@ use element::LineWithInvisibles;
 pub use element::{
     CursorLayout, EditorElement, HighlightedRange, HighlightedRangeLine, PointForPosition,
 };
+pub use file_state::{LineFingerprints, RowMap, SerializedPoint};
 use futures::FutureExt;
 use fuzzy::{StringMatch, StringMatchCandidate};
 use git::blame::GitBlame;
@@ -86,8 +91,8 @@ use language::{
     char_kind,
     language_settings::{self, all_language_settings, InlayHintSettings},
     markdown, point_from_lsp, AutoindentMode, BracketPair, Buffer, Capability, CharKind, CodeLabel,
-    CursorShape, Diagnostic, Documentation, IndentKind, IndentSize, Language, OffsetRangeExt,
-    Point, Selection, SelectionGoal, TransactionId,
+    CursorShape, Diagnostic, Documentation, FoldingRange, FoldingRangeKind, IndentKind, IndentSize,
+    Language, OffsetRangeExt, Point, Selection, SelectionGoal, TransactionId,
 };
 use language::{BufferRow, Runnable, RunnableRange};
 use linked_editing_ranges::refresh_linked_ranges;
@@ -118,6 +123,7 @@ use serde::{Deserialize, Serialize};
 use settings::{update_settings_file, Settings, SettingsStore};
 use smallvec::SmallVec;
 use snippet::Snippet;
+use snippet_provider::SnippetRegistry;
 use std::{
     any::TypeId,
     borrow::Cow,
@@ -263,6 +269,7 @@ pub fn init_settings(cx: &mut AppContext) {
 
 pub fn init(cx: &mut AppContext) {
     init_settings(cx);
+    file_state::prune_file_states(cx);
 
     workspace::register_project_item::<Editor>(cx);
     workspace::register_followable_item::<Editor>(cx);
@@ -423,6 +430,33 @@ impl Default for ScrollbarMarkerState {
     }
 }
 
+/// The text drawn in the minimap, kept between frames while the rows and their highlighting
+/// don't change.
+struct MinimapTextState {
+    rows_version: usize,
+    non_text_state_update_count: usize,
+    max_columns: u32,
+    text_color: Hsla,
+    syntax: Option<Arc<SyntaxTheme>>,
+    rows: Range<DisplayRow>,
+    /// Positioned relative to the top of the first display row.
+    quads: Arc<[PaintQuad]>,
+}
+
+impl Default for MinimapTextState {
+    fn default() -> Self {
+        Self {
+            rows_version: 0,
+            non_text_state_update_count: 0,
+            max_columns: 0,
+            text_color: Hsla::default(),
+            syntax: None,
+            rows: DisplayRow(0)..DisplayRow(0),
+            quads: Arc::from([]),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct RunnableTasks {
     templates: Vec<(TaskSourceKind, TaskTemplate)>,
@@ -493,6 +527,7 @@ pub struct Editor {
     background_highlights: TreeMap<TypeId, BackgroundHighlight>,
     gutter_highlights: TreeMap<TypeId, GutterHighlight>,
     scrollbar_marker_state: ScrollbarMarkerState,
+    minimap_text_state: MinimapTextState,
     active_indent_guides_state: ActiveIndentGuidesState,
     nav_history: Option<ItemNavHistory>,
     context_menu: RwLock<Option<ContextMenu>>,
@@ -506,6 +541,10 @@ pub struct Editor {
     document_highlights_task: Option<Task<()>>,
     linked_editing_range_task: Option<Task<Option<()>>>,
     linked_edit_ranges: linked_editing_ranges::LinkedEditingRanges,
+    semantic_tokens: semantic_tokens::SemanticTokensState,
+    code_lens: code_lens::CodeLensState,
+    folding_ranges: folding_ranges::FoldingRangesState,
+    file_state: file_state::FileState,
     pending_rename: Option<RenameState>,
     searchable: bool,
     cursor_shape: CursorShape,
@@ -1654,6 +1693,7 @@ impl Editor {
         });
         clone.selections.clone_state(&self.selections);
         clone.scroll_manager.clone_state(&self.scroll_manager);
+        clone.file_state = file_state::FileState::restored();
         clone.searchable = self.searchable;
         clone
     }
@@ -1733,6 +1773,14 @@ impl Editor {
                 project_subscriptions.push(cx.subscribe(project, |editor, _, event, cx| {
                     if let project::Event::RefreshInlayHints = event {
                         editor.refresh_inlay_hints(InlayHintRefreshReason::RefreshRequested, cx);
+                    } else if let project::Event::RefreshSemanticTokens = event {
+                        editor.refresh_semantic_tokens(cx);
+                    } else if let project::Event::RefreshCodeLens = event {
+                        editor.invalidate_code_lens(None, cx);
+                    } else if let project::Event::LanguageServerAdded(_) = event {
+                        editor.refresh_semantic_tokens(cx);
+                        editor.invalidate_code_lens(None, cx);
+                        editor.invalidate_folding_ranges(None, cx);
                     } else if let project::Event::SnippetEdit(id, snippet_edits) = event {
                         if let Some(buffer) = editor.buffer.read(cx).buffer(*id) {
                             let focus_handle = editor.focus_handle(cx);
@@ -1747,6 +1795,10 @@ impl Editor {
                                 }
                             }
                         }
+                    } else if let project::Event::BreakpointsChanged(id) = event {
+                        if editor.buffer.read(cx).buffer(*id).is_some() {
+                            cx.notify();
+                        }
                     }
                 }));
                 let task_inventory = project.read(cx).task_inventory().clone();
@@ -1812,6 +1864,7 @@ impl Editor {
             background_highlights: Default::default(),
             gutter_highlights: TreeMap::default(),
             scrollbar_marker_state: ScrollbarMarkerState::default(),
+            minimap_text_state: MinimapTextState::default(),
             active_indent_guides_state: ActiveIndentGuidesState::default(),
             nav_history: None,
             context_menu: RwLock::new(None),
@@ -1890,10 +1943,17 @@ impl Editor {
             ],
             tasks_update_task: None,
             linked_edit_ranges: Default::default(),
+            semantic_tokens: Default::default(),
+            code_lens: Default::default(),
+            folding_ranges: Default::default(),
+            file_state: Default::default(),
             previous_search_ranges: None,
             breadcrumb_header: None,
         };
         this.tasks_update_task = Some(this.refresh_runnables(cx));
+        this.refresh_semantic_tokens(cx);
+        this.refresh_code_lens(cx);
+        this.refresh_folding_ranges(cx);
         this._subscriptions.extend(project_subscriptions);
 
         this.end_selection(cx);
@@ -2112,6 +2172,11 @@ impl Editor {
         self.completion_provider = Some(provider);
     }
 
+    /// Removes the completion provider, e.g. to wrap it in another one.
+    pub fn take_completion_provider(&mut self) -> Option<Box<dyn CompletionProvider>> {
+        self.completion_provider.take()
+    }
+
     pub fn set_inline_completion_provider<T>(
         &mut self,
         provider: Option<Model<T>>,
@@ -2367,6 +2432,7 @@ impl Editor {
             if self.git_blame_inline_enabled {
                 self.start_inline_blame_timer(cx);
             }
+            self.serialize_file_state(cx);
         }
 
         self.blink_manager.update(cx, BlinkManager::pause_blinking);
@@ -3909,6 +3975,30 @@ impl Editor {
             .collect()
     }
 
+    /// The multi-buffer rows that are visible, and a screen above and below them.
+    fn rows_near_viewport(&self, snapshot: &MultiBufferSnapshot) -> Range<u32> {
+        let visible_line_count = self.visible_line_count().unwrap_or(0.).ceil() as u32;
+        let top_row = self.scroll_manager.anchor().anchor.to_point(snapshot).row;
+        top_row.saturating_sub(visible_line_count)..top_row + visible_line_count * 2
+    }
+
+    /// The buffers with excerpts in [`Self::rows_near_viewport`], which language server data is
+    /// requested for instead of for every buffer in a large multi-buffer.
+    fn buffers_near_viewport(&self, cx: &AppContext) -> Vec<Model<Buffer>> {
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+        let rows = self.rows_near_viewport(&snapshot);
+        let range =
+            Point::new(rows.start, 0)..snapshot.clip_point(Point::new(rows.end, 0), Bias::Left);
+        let mut buffer_ids = HashSet::default();
+        multi_buffer
+            .range_to_buffer_ranges(range, cx)
+            .into_iter()
+            .filter(|(buffer, _, _)| buffer_ids.insert(buffer.read(cx).remote_id()))
+            .map(|(buffer, _, _)| buffer)
+            .collect()
+    }
+
     pub fn text_layout_details(&self, cx: &WindowContext) -> TextLayoutDetails {
         TextLayoutDetails {
             text_system: cx.text_system().clone(),
@@ -5050,6 +5140,102 @@ impl Editor {
             }))
     }
 
+    fn render_breakpoint_indicator(
+        &self,
+        row: DisplayRow,
+        cx: &mut ViewContext<Self>,
+    ) -> IconButton {
+        IconButton::new(
+            ("breakpoint_indicator", row.0 as usize),
+            ui::IconName::DebugBreakpoint,
+        )
+        .shape(ui::IconButtonShape::Square)
+        .icon_size(IconSize::XSmall)
+        .icon_color(Color::Error)
+        .on_click(cx.listener(move |editor, _e, cx| {
+            let snapshot = editor.snapshot(cx);
+            let row = DisplayPoint::new(row, 0)
+                .to_point(&snapshot.display_snapshot)
+                .row;
+            editor.toggle_breakpoint_at_row(MultiBufferRow(row), cx);
+        }))
+    }
+
+    pub fn toggle_breakpoint(&mut self, _: &ToggleBreakpoint, cx: &mut ViewContext<Self>) {
+        let rows = self
+            .selections
+            .all::<Point>(cx)
+            .into_iter()
+            .map(|selection| MultiBufferRow(selection.head().row))
+            .collect::<BTreeSet<_>>();
+        for row in rows {
+            self.toggle_breakpoint_at_row(row, cx);
+        }
+    }
+
+    fn toggle_breakpoint_at_row(&mut self, row: MultiBufferRow, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        let Some((buffer, offset, _)) = self
+            .buffer
+            .read(cx)
+            .point_to_buffer_offset(Point::new(row.0, 0), cx)
+        else {
+            return;
+        };
+        let buffer_row = buffer.read(cx).offset_to_point(offset).row;
+        project.update(cx, |project, cx| {
+            project.toggle_breakpoint(&buffer, buffer_row, cx)
+        });
+    }
+
+    /// Returns the display rows of the breakpoints set in the given range of rows.
+    fn breakpoint_display_rows(
+        &self,
+        snapshot: &EditorSnapshot,
+        range: Range<DisplayRow>,
+        cx: &AppContext,
+    ) -> Vec<DisplayRow> {
+        let Some(project) = self.project.as_ref() else {
+            return Vec::new();
+        };
+        let project = project.read(cx);
+        let buffer_snapshot = &snapshot.buffer_snapshot;
+        let start = DisplayPoint::new(range.start, 0).to_point(&snapshot.display_snapshot);
+        let end = snapshot
+            .display_snapshot
+            .clip_point(DisplayPoint::new(range.end, 0), Bias::Left)
+            .to_point(&snapshot.display_snapshot);
+        let visible_range = buffer_snapshot.anchor_before(start)..buffer_snapshot.anchor_after(end);
+
+        let mut rows = Vec::new();
+        for (excerpt_id, excerpt_buffer, buffer_range) in
+            buffer_snapshot.excerpts_in_ranges([visible_range])
+        {
+            for breakpoint in project.breakpoints_for_buffer(excerpt_buffer.remote_id()) {
+                if !buffer_range.contains(&text::ToOffset::to_offset(breakpoint, excerpt_buffer)) {
+                    continue;
+                }
+                let Some(anchor) = buffer_snapshot.anchor_in_excerpt(excerpt_id, *breakpoint)
+                else {
+                    continue;
+                };
+                let point = anchor.to_point(buffer_snapshot);
+                if snapshot.is_line_folded(MultiBufferRow(point.row)) {
+                    continue;
+                }
+                let row = point.to_display_point(&snapshot.display_snapshot).row();
+                if range.contains(&row) {
+                    rows.push(row);
+                }
+            }
+        }
+        rows.sort_unstable();
+        rows.dedup();
+        rows
+    }
+
     pub fn context_menu_visible(&self) -> bool {
         self.context_menu
             .read()
@@ -9956,6 +10142,50 @@ impl Editor {
         self.fold_ranges(ranges, true, cx);
     }
 
+    pub fn fold_all(&mut self, _: &FoldAll, cx: &mut ViewContext<Self>) {
+        self.fold_folding_ranges(|_, depth| depth == 1, cx);
+    }
+
+    pub fn unfold_all(&mut self, _: &UnfoldAll, cx: &mut ViewContext<Self>) {
+        let len = self.buffer.read(cx).len(cx);
+        self.unfold_ranges([0..len], true, true, cx);
+    }
+
+    pub fn fold_all_comments(&mut self, _: &FoldAllComments, cx: &mut ViewContext<Self>) {
+        self.fold_folding_ranges(
+            |folding_range, _| folding_range.kind == Some(FoldingRangeKind::Comment),
+            cx,
+        );
+    }
+
+    pub fn fold_all_imports(&mut self, _: &FoldAllImports, cx: &mut ViewContext<Self>) {
+        self.fold_folding_ranges(
+            |folding_range, _| folding_range.kind == Some(FoldingRangeKind::Imports),
+            cx,
+        );
+    }
+
+    pub fn fold_at_level(&mut self, action: &FoldAtLevel, cx: &mut ViewContext<Self>) {
+        self.fold_folding_ranges(|_, depth| depth == action.level, cx);
+    }
+
+    /// Folds the foldable ranges of the whole buffer that match the predicate, which receives each
+    /// range along with its nesting depth.
+    fn fold_folding_ranges(
+        &mut self,
+        predicate: impl Fn(&FoldingRange<Point>, u32) -> bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
+        let fold_ranges = display_map
+            .folding_ranges_with_depth()
+            .into_iter()
+            .filter(|(folding_range, depth)| predicate(folding_range, *depth))
+            .map(|(folding_range, _)| (folding_range.range, display_map.fold_placeholder.clone()))
+            .collect::<Vec<_>>();
+        self.fold_ranges(fold_ranges, true, cx);
+    }
+
     pub fn fold_ranges<T: ToOffset + Clone>(
         &mut self,
         ranges: impl IntoIterator<Item = (Range<T>, FoldPlaceholder)>,
@@ -9988,6 +10218,8 @@ impl Editor {
 
             cx.notify();
 
+            self.serialize_file_state(cx);
+
             if let Some(active_diagnostics) = self.active_diagnostics.take() {
                 // Clear diagnostics block when folding a range that contains it.
                 let snapshot = self.snapshot(cx);
@@ -10036,6 +10268,7 @@ impl Editor {
             cx.notify();
             self.scrollbar_marker_state.dirty = true;
             self.active_indent_guides_state.dirty = true;
+            self.serialize_file_state(cx);
         }
     }
 
@@ -10858,6 +11091,34 @@ impl Editor {
         results
     }
 
+    pub fn background_highlights_of_type_in_range<T: 'static>(
+        &self,
+        search_range: Range<Anchor>,
+        display_snapshot: &DisplaySnapshot,
+    ) -> Vec<Range<DisplayPoint>> {
+        let Some((_, ranges)) = self.background_highlights.get(&TypeId::of::<T>()) else {
+            return Vec::new();
+        };
+        let buffer = &display_snapshot.buffer_snapshot;
+        let start_ix = match ranges.binary_search_by(|probe| {
+            if probe.end.cmp(&search_range.start, buffer).is_gt() {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            }
+        }) {
+            Ok(i) | Err(i) => i,
+        };
+        ranges[start_ix..]
+            .iter()
+            .take_while(|range| range.start.cmp(&search_range.end, buffer).is_lt())
+            .map(|range| {
+                range.start.to_display_point(display_snapshot)
+                    ..range.end.to_display_point(display_snapshot)
+            })
+            .collect()
+    }
+
     pub fn background_highlight_row_ranges<T: 'static>(
         &self,
         search_range: Range<Anchor>,
@@ -11097,6 +11358,10 @@ impl Editor {
                     }
                 }
 
+                self.refresh_semantic_tokens(cx);
+                self.refresh_code_lens(cx);
+                self.refresh_folding_ranges(cx);
+
                 let Some(project) = &self.project else { return };
                 let telemetry = project.read(cx).client().telemetry().clone();
                 refresh_linked_ranges(self, cx);
@@ -11114,9 +11379,16 @@ impl Editor {
                     excerpts: excerpts.clone(),
                 });
                 self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                self.refresh_semantic_tokens(cx);
+                let buffer_id = buffer.read(cx).remote_id();
+                self.invalidate_code_lens(Some(buffer_id), cx);
+                self.invalidate_folding_ranges(Some(buffer_id), cx);
             }
             multi_buffer::Event::ExcerptsRemoved { ids } => {
                 self.refresh_inlay_hints(InlayHintRefreshReason::ExcerptsRemoved(ids.clone()), cx);
+                self.refresh_semantic_tokens(cx);
+                self.remove_excerpt_code_lenses(ids, cx);
+                self.remove_excerpt_folding_ranges(ids, cx);
                 cx.emit(EditorEvent::ExcerptsRemoved { ids: ids.clone() })
             }
             multi_buffer::Event::ExcerptsEdited { ids } => {
@@ -11127,11 +11399,15 @@ impl Editor {
             }
             multi_buffer::Event::Reparsed(buffer_id) => {
                 self.tasks_update_task = Some(self.refresh_runnables(cx));
+                self.invalidate_folding_ranges(Some(*buffer_id), cx);
 
                 cx.emit(EditorEvent::Reparsed(*buffer_id));
             }
             multi_buffer::Event::LanguageChanged(buffer_id) => {
                 linked_editing_ranges::refresh_linked_ranges(self, cx);
+                self.refresh_semantic_tokens(cx);
+                self.invalidate_code_lens(Some(*buffer_id), cx);
+                self.invalidate_folding_ranges(Some(*buffer_id), cx);
                 cx.emit(EditorEvent::Reparsed(*buffer_id));
                 cx.notify();
             }
@@ -11174,6 +11450,9 @@ impl Editor {
             )),
             cx,
         );
+        self.refresh_semantic_tokens(cx);
+        self.invalidate_code_lens(None, cx);
+        self.invalidate_folding_ranges(None, cx);
         let editor_settings = EditorSettings::get_global(cx);
         self.scroll_manager.vertical_scroll_margin = editor_settings.vertical_scroll_margin;
         self.show_breadcrumbs = editor_settings.toolbar.breadcrumbs;
@@ -11744,8 +12023,14 @@ impl CompletionProvider for Model<Project> {
         options: CompletionContext,
         cx: &mut ViewContext<Editor>,
     ) -> Task<Result<Vec<Completion>>> {
-        self.update(cx, |project, cx| {
+        let snippets = snippet_completions(buffer, buffer_position, cx);
+        let project_completions = self.update(cx, |project, cx| {
             project.completions(&buffer, buffer_position, options, cx)
+        });
+        cx.background_executor().spawn(async move {
+            let mut completions = project_completions.await?;
+            completions.extend(snippets);
+            Ok(completions)
         })
     }
 
@@ -11808,6 +12093,71 @@ impl CompletionProvider for Model<Project> {
     }
 }
 
+/// Builds completions for the user-defined snippets that apply to the language at `buffer_position`.
+///
+/// Snippets are only offered when the cursor is preceded by a word, which is replaced when the
+/// snippet is confirmed.
+fn snippet_completions(
+    buffer: &Model<Buffer>,
+    buffer_position: text::Anchor,
+    cx: &AppContext,
+) -> Vec<Completion> {
+    use language::ToOffset as _;
+
+    let Some(registry) = SnippetRegistry::try_global(cx) else {
+        return Vec::new();
+    };
+    let snapshot = buffer.read(cx).snapshot();
+    let language_name = snapshot.language_at(buffer_position).map(|l| l.name());
+    let snippets = registry.snippets_for_language(language_name.as_deref());
+    if snippets.is_empty() {
+        return Vec::new();
+    }
+
+    let scope = snapshot.language_scope_at(buffer_position);
+    let end = buffer_position.to_offset(&snapshot);
+    let start = end
+        - snapshot
+            .reversed_chars_at(end)
+            .take_while(|c| char_kind(&scope, *c) == CharKind::Word)
+            .map(char::len_utf8)
+            .sum::<usize>();
+    if start == end {
+        return Vec::new();
+    }
+    let old_range = snapshot.anchor_before(start)..buffer_position;
+
+    snippets
+        .iter()
+        .flat_map(|snippet| {
+            let old_range = old_range.clone();
+            snippet.prefixes.iter().map(move |prefix| {
+                let documentation = match &snippet.description {
+                    Some(description) => Documentation::SingleLine(description.clone()),
+                    None => Documentation::MultiLinePlainText(snippet.body.clone()),
+                };
+                Completion {
+                    old_range: old_range.clone(),
+                    new_text: snippet.body.clone(),
+                    label: CodeLabel::plain(prefix.clone(), None),
+                    server_id: LanguageServerId::NONE,
+                    documentation: Some(documentation),
+                    lsp_completion: lsp::CompletionItem {
+                        label: prefix.clone(),
+                        kind: Some(lsp::CompletionItemKind::SNIPPET),
+                        detail: Some(snippet.name.clone()),
+                        insert_text: Some(snippet.body.clone()),
+                        insert_text_format: Some(lsp::InsertTextFormat::SNIPPET),
+                        ..Default::default()
+                    },
+                    confirm: None,
+                    show_new_completions_on_confirm: false,
+                }
+            })
+        })
+        .collect()
+}
+
 fn inlay_hint_settings(
     location: Anchor,
     snapshot: &MultiBufferSnapshot,
@@ -12001,7 +12351,7 @@ impl EditorSnapshot {
                 cx,
             ))
         } else if folded
-            || (self.starts_indent(buffer_row) && (row_contains_cursor || self.gutter_hovered))
+            || (self.starts_fold(buffer_row) && (row_contains_cursor || self.gutter_hovered))
         {
             Some(
                 Disclosure::new(("indent-fold-indicator", buffer_row.0), !folded)
@@ -14,6 +14,8 @@ pub struct EditorSettings {
     pub use_on_type_format: bool,
     pub toolbar: Toolbar,
     pub scrollbar: Scrollbar,
+    pub minimap: Minimap,
+    pub sticky_scroll: StickyScroll,
     pub gutter: Gutter,
     pub scroll_beyond_last_line: ScrollBeyondLastLine,
     pub vertical_scroll_margin: f32,
@@ -81,6 +83,21 @@ pub struct Scrollbar {
     pub cursors: bool,
 }
 
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct Minimap {
+    pub show: ShowMinimap,
+    pub width: f32,
+    pub git_diff: bool,
+    pub search_results: bool,
+    pub diagnostics: bool,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct StickyScroll {
+    pub enabled: bool,
+    pub max_depth: usize,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct Gutter {
     pub line_numbers: bool,
@@ -106,6 +123,20 @@ pub enum ShowScrollbar {
     Never,
 }
 
+/// When to show the minimap in the editor.
+///
+/// Default: never
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShowMinimap {
+    /// Show the minimap in editors of a single file.
+    Auto,
+    /// Always show the minimap.
+    Always,
+    /// Never show the minimap.
+    Never,
+}
+
 /// The key to use for adding multiple cursors
 ///
 /// Default: alt
@@ -173,6 +204,10 @@ pub struct EditorSettingsContent {
     pub toolbar: Option<ToolbarContent>,
     /// Scrollbar related settings
     pub scrollbar: Option<ScrollbarContent>,
+    /// Minimap related settings
+    pub minimap: Option<MinimapContent>,
+    /// Sticky scroll related settings
+    pub sticky_scroll: Option<StickyScrollContent>,
     /// Gutter related settings
     pub gutter: Option<GutterContent>,
     /// Whether the editor will scroll beyond the last line.
@@ -266,6 +301,45 @@ pub struct ScrollbarContent {
     pub cursors: Option<bool>,
 }
 
+/// Minimap related settings
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct MinimapContent {
+    /// When to show the minimap in the editor.
+    ///
+    /// Default: never
+    pub show: Option<ShowMinimap>,
+    /// The width of the minimap in pixels.
+    ///
+    /// Default: 100
+    pub width: Option<f32>,
+    /// Whether to show git diff indicators in the minimap.
+    ///
+    /// Default: true
+    pub git_diff: Option<bool>,
+    /// Whether to highlight buffer search results in the minimap.
+    ///
+    /// Default: true
+    pub search_results: Option<bool>,
+    /// Whether to highlight diagnostics in the minimap.
+    ///
+    /// Default: true
+    pub diagnostics: Option<bool>,
+}
+
+/// Sticky scroll related settings
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct StickyScrollContent {
+    /// Whether to pin the first lines of the scopes that enclose the top of the
+    /// editor, like functions and types, while scrolling through them.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// The maximum number of nested scopes to pin.
+    ///
+    /// Default: 5
+    pub max_depth: Option<usize>,
+}
+
 /// Gutter related settings
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct GutterContent {
@@ -2,7 +2,7 @@ use super::*;
 use crate::{
     scroll::scroll_amount::ScrollAmount,
     test::{
-        assert_text_with_selections, build_editor, editor_hunks,
+        assert_text_with_selections, build_editor, build_editor_with_project, editor_hunks,
         editor_lsp_test_context::EditorLspTestContext, editor_test_context::EditorTestContext,
         expanded_hunks, expanded_hunks_background_highlights, select_ranges,
     },
@@ -11,7 +11,7 @@ use crate::{
 use futures::StreamExt;
 use gpui::{
     div, SemanticVersion, TestAppContext, UpdateGlobal, VisualTestContext, WindowBounds,
-    WindowOptions,
+    WindowHandle, WindowOptions,
 };
 use indoc::indoc;
 use language::{
@@ -31,7 +31,7 @@ use project::FakeFs;
 use serde_json::{self, json};
 use std::sync::atomic;
 use std::sync::atomic::AtomicUsize;
-use std::{cell::RefCell, future::Future, rc::Rc, time::Instant};
+use std::{cell::RefCell, future::Future, path::PathBuf, rc::Rc, time::Instant};
 use unindent::Unindent;
 use util::{
     assert_set_eq,
@@ -39,7 +39,7 @@ use util::{
 };
 use workspace::{
     item::{FollowEvent, FollowableItem, Item, ItemHandle},
-    NavigationEntry, ViewId,
+    ItemId, NavigationEntry, ViewId,
 };
 
 #[gpui::test]
@@ -909,6 +909,176 @@ fn test_fold_action(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_fold_syntax_aware_ranges(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_folds_query(
+            r#"
+            (_ "{" "}") @fold
+            (line_comment)+ @fold.comment
+            "#,
+        )
+        .unwrap(),
+    );
+
+    let mut cx = EditorTestContext::new(cx).await;
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    cx.set_state(indoc! {"
+        ˇ// one
+        // two
+        mod a {
+            fn b() {
+                if true {
+                    1;
+                }
+            }
+        }
+    "});
+    cx.run_until_parked();
+    cx.executor()
+        .advance_clock(super::folding_ranges::FOLDING_RANGES_DEBOUNCE);
+    cx.run_until_parked();
+
+    cx.update_editor(|editor, cx| {
+        let snapshot = editor.snapshot(cx);
+        assert!(snapshot.starts_fold(MultiBufferRow(3)));
+        assert!(!snapshot.starts_fold(MultiBufferRow(5)));
+
+        editor.fold_at_level(&FoldAtLevel { level: 2 }, cx);
+        assert_eq!(
+            editor.display_text(cx),
+            indoc! {"
+                // one
+                // two
+                mod a {
+                    fn b() {⋯
+                    }
+                }
+            "}
+        );
+
+        editor.unfold_all(&UnfoldAll, cx);
+        editor.fold_all_comments(&FoldAllComments, cx);
+        assert_eq!(
+            editor.display_text(cx),
+            indoc! {"
+                // one⋯
+                mod a {
+                    fn b() {
+                        if true {
+                            1;
+                        }
+                    }
+                }
+            "}
+        );
+
+        editor.fold_all(&FoldAll, cx);
+        assert_eq!(
+            editor.display_text(cx),
+            indoc! {"
+                // one⋯
+                mod a {⋯
+                }
+            "}
+        );
+
+        editor.unfold_all(&UnfoldAll, cx);
+        assert_eq!(
+            editor.display_text(cx),
+            editor.buffer.read(cx).read(cx).text()
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_folding_ranges_near_viewport(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let language = Arc::new(
+        Language::new(
+            LanguageConfig::default(),
+            Some(tree_sitter_rust::language()),
+        )
+        .with_folds_query(r#"(_ "{" "}") @fold"#)
+        .unwrap(),
+    );
+    // The bodies aren't indented, so only the syntax trees can tell that they fold.
+    let buffer_1 = cx.new_model(|cx| {
+        Buffer::local("fn a() {\nx;\n}\n".repeat(50), cx).with_language(language.clone(), cx)
+    });
+    let buffer_2 =
+        cx.new_model(|cx| Buffer::local("fn b() {\nx;\n}\n", cx).with_language(language, cx));
+    let multi_buffer = cx.new_model(|cx| {
+        let mut multi_buffer = MultiBuffer::new(0, ReadWrite);
+        multi_buffer.push_excerpts(
+            buffer_1.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(150, 0),
+                primary: None,
+            }],
+            cx,
+        );
+        multi_buffer.push_excerpts(
+            buffer_2.clone(),
+            [ExcerptRange {
+                context: Point::new(0, 0)..Point::new(3, 0),
+                primary: None,
+            }],
+            cx,
+        );
+        multi_buffer
+    });
+    let editor = cx.add_window(|cx| build_editor(multi_buffer, cx));
+    _ = editor.update(cx, |editor, cx| editor.set_visible_line_count(10., cx));
+    cx.run_until_parked();
+    cx.executor()
+        .advance_clock(super::folding_ranges::FOLDING_RANGES_DEBOUNCE);
+    cx.run_until_parked();
+
+    let buffer_2_row = editor
+        .update(cx, |editor, cx| {
+            let text = editor.text(cx);
+            let row = text.lines().position(|line| line == "fn b() {").unwrap() as u32;
+            let snapshot = editor.snapshot(cx);
+            assert!(snapshot.starts_fold(MultiBufferRow(0)));
+            // The second buffer is far below the viewport.
+            assert!(!snapshot.starts_fold(MultiBufferRow(row)));
+            row
+        })
+        .unwrap();
+
+    _ = editor.update(cx, |editor, cx| {
+        editor.set_scroll_position(point(0., buffer_2_row as f32), cx)
+    });
+    cx.executor()
+        .advance_clock(super::folding_ranges::FOLDING_RANGES_DEBOUNCE);
+    cx.run_until_parked();
+    _ = editor.update(cx, |editor, cx| {
+        assert!(editor
+            .snapshot(cx)
+            .starts_fold(MultiBufferRow(buffer_2_row)));
+    });
+
+    update_test_language_settings(cx, |settings| {
+        settings.defaults.folding_ranges = Some(false);
+    });
+    cx.executor()
+        .advance_clock(super::folding_ranges::FOLDING_RANGES_DEBOUNCE);
+    cx.run_until_parked();
+    _ = editor.update(cx, |editor, cx| {
+        assert!(!editor
+            .snapshot(cx)
+            .starts_fold(MultiBufferRow(buffer_2_row)));
+    });
+}
+
 #[gpui::test]
 fn test_fold_action_whitespace_sensitive_language(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -9644,6 +9814,160 @@ async fn test_multibuffer_reverts(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_stage_and_unstage_hunks(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let head_text = "one\ntwo\nthree\nfour\n".to_string();
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "one\nTWO\nthree\nFOUR\n",
+        }),
+    )
+    .await;
+    fs.set_head_for_repo(
+        Path::new("/dir/.git"),
+        &[(Path::new("a.txt"), head_text.clone())],
+    );
+    fs.set_index_for_repo(
+        Path::new("/dir/.git"),
+        &[(Path::new("a.txt"), head_text.clone())],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    let multi_buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+    let (editor, cx) =
+        cx.add_window_view(|cx| build_editor_with_project(project.clone(), multi_buffer, cx));
+    cx.executor().run_until_parked();
+
+    let index_text = |cx: &mut VisualTestContext| {
+        let mut index_text = None;
+        fs.with_git_state(Path::new("/dir/.git"), false, |state| {
+            index_text = state.index_contents.get(Path::new("a.txt")).cloned();
+        });
+        let diff_base = buffer.read_with(cx, |buffer, _| {
+            buffer.diff_base().map(|diff_base| diff_base.to_string())
+        });
+        assert_eq!(diff_base, index_text, "diff base should match the index");
+        index_text.unwrap()
+    };
+
+    // Staging a hunk only writes that hunk to the index.
+    editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 1)..Point::new(1, 1)])
+        });
+        editor.stage_hunk(&StageHunk, cx);
+    });
+    cx.executor().run_until_parked();
+    assert_eq!(index_text(cx), "one\nTWO\nthree\nfour\n");
+    buffer.read_with(cx, |buffer, _| {
+        git::diff::assert_hunks(
+            buffer.snapshot().git_diff_hunks_in_row_range(0..4),
+            buffer,
+            "one\nTWO\nthree\nfour\n",
+            &[(3..4, "four\n", "FOUR\n")],
+        );
+    });
+
+    // Unstaging a hunk restores its text from HEAD, even if other lines are staged.
+    editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(3, 0)..Point::new(3, 0)])
+        });
+        editor.stage_hunk(&StageHunk, cx);
+    });
+    cx.executor().run_until_parked();
+    assert_eq!(index_text(cx), "one\nTWO\nthree\nFOUR\n");
+
+    editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 0)..Point::new(1, 0)])
+        });
+        editor.unstage_hunk(&UnstageHunk, cx);
+    });
+    cx.executor().run_until_parked();
+    assert_eq!(index_text(cx), "one\ntwo\nthree\nFOUR\n");
+
+    // Lines without staged changes leave the index untouched.
+    editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(2, 0)..Point::new(2, 0)])
+        });
+        editor.unstage_hunk(&UnstageHunk, cx);
+    });
+    cx.executor().run_until_parked();
+    assert_eq!(index_text(cx), "one\ntwo\nthree\nFOUR\n");
+}
+
+#[gpui::test]
+async fn test_stage_hunks_with_line_endings_and_unsaved_changes(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".git": {},
+            "a.txt": "one\r\nTWO\r\nthree\r\n",
+        }),
+    )
+    .await;
+    fs.set_index_for_repo(
+        Path::new("/dir/.git"),
+        &[(Path::new("a.txt"), "one\r\ntwo\r\nthree\r\n".to_string())],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    let multi_buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+    let (editor, cx) =
+        cx.add_window_view(|cx| build_editor_with_project(project.clone(), multi_buffer, cx));
+    cx.executor().run_until_parked();
+
+    let index_text = |cx: &mut VisualTestContext| {
+        cx.executor().run_until_parked();
+        let mut index_text = None;
+        fs.with_git_state(Path::new("/dir/.git"), false, |state| {
+            index_text = state.index_contents.get(Path::new("a.txt")).cloned();
+        });
+        index_text.unwrap()
+    };
+
+    // Hunks of buffers with unsaved changes aren't staged.
+    editor.update(cx, |editor, cx| {
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(1, 1)..Point::new(1, 1)])
+        });
+        editor.insert("!", cx);
+        editor.stage_hunk(&StageHunk, cx);
+    });
+    assert_eq!(index_text(cx), "one\r\ntwo\r\nthree\r\n");
+
+    // The staged text keeps the line endings of the index.
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    editor.update(cx, |editor, cx| editor.stage_hunk(&StageHunk, cx));
+    assert_eq!(index_text(cx), "one\r\nT!WO\r\nthree\r\n");
+}
+
 #[gpui::test]
 async fn test_mutlibuffer_in_navigation_history(cx: &mut gpui::TestAppContext) {
     init_test(cx, |_| {});
@@ -12367,6 +12691,246 @@ fn test_crease_insertion_and_rendering(cx: &mut TestAppContext) {
     assert!(!snapshot.is_line_folded(MultiBufferRow(1)));
 }
 
+#[gpui::test]
+async fn test_hot_exit_restores_unsaved_contents(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/hot_exit", json!({ "main.rs": "one\n" }))
+        .await;
+    let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+    let item_id = persist_unsaved_changes(fs.clone(), workspace_id, cx).await;
+
+    let (workspace, mut window_cx) = hot_exit_workspace(fs, workspace_id, cx).await;
+    let cx = &mut window_cx;
+    let editor = deserialize_editor(workspace, item_id, cx).await;
+    editor.update(cx, |editor, cx| {
+        assert_eq!(editor.text(cx), "zero\none\n");
+        assert!(editor.buffer().read(cx).is_dirty(cx));
+
+        // Restoring the unsaved contents can be undone.
+        editor.undo(&Undo, cx);
+        assert_eq!(editor.text(cx), "one\n");
+    });
+}
+
+#[gpui::test]
+async fn test_hot_exit_restores_untitled_buffer(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/hot_exit", json!({})).await;
+    let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+
+    let (workspace, mut window_cx) = hot_exit_workspace(fs.clone(), workspace_id, cx).await;
+    let editor = workspace
+        .update(&mut window_cx, |workspace, cx| {
+            let project = workspace.project().clone();
+            let buffer = project.update(cx, |project, cx| {
+                project.create_local_buffer("fn main() {}\n", Some(rust_lang()), cx)
+            });
+            let editor = cx.new_view(|cx| Editor::for_buffer(buffer, Some(project), cx));
+            workspace.add_item_to_active_pane(Box::new(editor.clone()), None, cx);
+            editor
+        })
+        .unwrap();
+    editor
+        .update(&mut window_cx, |editor, cx| {
+            editor.persist_unsaved_changes(cx).unwrap()
+        })
+        .await
+        .unwrap();
+    let item_id = editor.item_id().as_u64() as ItemId;
+
+    let (workspace, mut window_cx) = hot_exit_workspace(fs, workspace_id, cx).await;
+    let cx = &mut window_cx;
+    let editor = deserialize_editor(workspace, item_id, cx).await;
+    editor.update(cx, |editor, cx| {
+        assert_eq!(editor.text(cx), "fn main() {}\n");
+        let buffer = editor.buffer().read(cx).as_singleton().unwrap().read(cx);
+        assert!(buffer.file().is_none());
+        assert_eq!(buffer.language().unwrap().name().as_ref(), "Rust");
+    });
+
+    // The contents stay stored for the restored editor, until they're saved.
+    let restored_item_id = editor.item_id().as_u64() as ItemId;
+    let (_, contents, language, _) = crate::persistence::DB
+        .get_serialized_editor(restored_item_id, workspace_id)
+        .unwrap()
+        .unwrap();
+    assert_eq!(contents.as_deref(), Some("fn main() {}\n"));
+    assert_eq!(language.as_deref(), Some("Rust"));
+}
+
+#[gpui::test]
+async fn test_hot_exit_file_changed_on_disk(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/hot_exit", json!({ "main.rs": "one\n" }))
+        .await;
+    let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+
+    for (answer, expected_text) in [(0, "zero\none\n"), (1, "one\n")] {
+        // The stored contents edit a different version of the file than the one on disk.
+        let item_id = answer as ItemId + 1;
+        crate::persistence::DB
+            .save_unsaved_contents(
+                item_id,
+                workspace_id,
+                PathBuf::from("/hot_exit/main.rs"),
+                "zero\none\n".to_string(),
+                None,
+                Some("outdated".to_string()),
+            )
+            .await
+            .unwrap();
+
+        let (workspace, mut window_cx) = hot_exit_workspace(fs.clone(), workspace_id, cx).await;
+        let cx = &mut window_cx;
+        let editor = deserialize_editor(workspace, item_id, cx).await;
+        assert!(cx.has_pending_prompt());
+        cx.simulate_prompt_answer(answer);
+        cx.run_until_parked();
+        assert!(!cx.has_pending_prompt());
+
+        editor.update(cx, |editor, cx| {
+            assert_eq!(editor.text(cx), expected_text, "answer {answer}");
+            assert_eq!(editor.buffer().read(cx).is_dirty(cx), answer == 0);
+        });
+        let (_, contents, _, _) = crate::persistence::DB
+            .get_serialized_editor(item_id, workspace_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(contents, None);
+    }
+}
+
+#[gpui::test]
+async fn test_hot_exit_clears_contents_after_save(cx: &mut gpui::TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/hot_exit", json!({ "main.rs": "one\n" }))
+        .await;
+    let workspace_id = workspace::WORKSPACE_DB.next_id().await.unwrap();
+    let item_id = persist_unsaved_changes(fs.clone(), workspace_id, cx).await;
+
+    let (workspace, mut window_cx) = hot_exit_workspace(fs, workspace_id, cx).await;
+    let cx = &mut window_cx;
+    let editor = deserialize_editor(workspace, item_id, cx).await;
+    let restored_item_id = editor.item_id().as_u64() as ItemId;
+    let stored_contents = || {
+        crate::persistence::DB
+            .get_serialized_editor(restored_item_id, workspace_id)
+            .unwrap()
+            .unwrap()
+            .1
+    };
+    assert_eq!(stored_contents().as_deref(), Some("zero\none\n"));
+
+    let project = workspace
+        .update(cx, |workspace, _| workspace.project().clone())
+        .unwrap();
+    editor
+        .update(cx, |editor, cx| editor.save(false, project, cx))
+        .await
+        .unwrap();
+    cx.run_until_parked();
+    editor.update(cx, |editor, cx| {
+        assert!(!editor.buffer().read(cx).is_dirty(cx));
+    });
+    assert_eq!(stored_contents(), None);
+}
+
+/// Opens a workspace with the given database id for a new project of the files in `/hot_exit`,
+/// as when Zed starts.
+async fn hot_exit_workspace(
+    fs: Arc<FakeFs>,
+    workspace_id: WorkspaceId,
+    cx: &mut TestAppContext,
+) -> (WindowHandle<Workspace>, VisualTestContext) {
+    let project = Project::test(fs, ["/hot_exit".as_ref()], cx).await;
+    project.update(cx, |project, _| project.languages().add(rust_lang()));
+    let workspace = cx.add_window(|cx| {
+        let mut workspace = Workspace::test_new(project, cx);
+        workspace.set_database_id(workspace_id);
+        workspace
+    });
+    let window_cx = VisualTestContext::from_window(*workspace, cx);
+    (workspace, window_cx)
+}
+
+/// Edits `/hot_exit/main.rs` in a new workspace, and stores its unsaved contents as when
+/// quitting. Returns the item id of its editor.
+async fn persist_unsaved_changes(
+    fs: Arc<FakeFs>,
+    workspace_id: WorkspaceId,
+    cx: &mut TestAppContext,
+) -> ItemId {
+    let (workspace, mut window_cx) = hot_exit_workspace(fs, workspace_id, cx).await;
+    let cx = &mut window_cx;
+    let worktree_id = workspace
+        .update(cx, |workspace, cx| {
+            workspace
+                .project()
+                .read(cx)
+                .worktrees()
+                .next()
+                .unwrap()
+                .read(cx)
+                .id()
+        })
+        .unwrap();
+    let editor = workspace
+        .update(cx, |workspace, cx| {
+            workspace.open_path((worktree_id, "main.rs"), None, true, cx)
+        })
+        .unwrap()
+        .await
+        .unwrap()
+        .downcast::<Editor>()
+        .unwrap();
+    editor
+        .update(cx, |editor, cx| {
+            editor
+                .buffer()
+                .update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+            editor.persist_unsaved_changes(cx).unwrap()
+        })
+        .await
+        .unwrap();
+    cx.run_until_parked();
+    editor.item_id().as_u64() as ItemId
+}
+
+/// Opens an editor that was stored for a workspace, as when the workspace is opened again.
+async fn deserialize_editor(
+    workspace: WindowHandle<Workspace>,
+    item_id: ItemId,
+    cx: &mut VisualTestContext,
+) -> View<Editor> {
+    let editor = workspace
+        .update(cx, |workspace, cx| {
+            let project = workspace.project().clone();
+            let workspace_id = workspace.database_id().unwrap();
+            let workspace_handle = cx.view().downgrade();
+            workspace.active_pane().update(cx, |_, cx| {
+                Editor::deserialize(project, workspace_handle, workspace_id, item_id, cx)
+            })
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    workspace
+        .update(cx, |workspace, cx| {
+            workspace.add_item_to_active_pane(Box::new(editor.clone()), None, cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    editor
+}
+
 fn empty_range(row: usize, column: usize) -> Range<DisplayPoint> {
     let point = DisplayPoint::new(DisplayRow(row as u32), column as u32);
     point..point
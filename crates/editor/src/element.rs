@@ -5,7 +5,8 @@ use crate::{
         BlockContext, BlockStyle, DisplaySnapshot, HighlightedChunk, ToDisplayPoint, TransformBlock,
     },
     editor_settings::{
-        CurrentLineHighlight, DoubleClickInMultibuffer, MultiCursorModifier, ShowScrollbar,
+        CurrentLineHighlight, DoubleClickInMultibuffer, MultiCursorModifier, ShowMinimap,
+        ShowScrollbar,
     },
     git::{
         blame::{CommitDetails, GitBlame},
@@ -17,12 +18,12 @@ use crate::{
     hunk_status,
     items::BufferSearchHighlights,
     mouse_context_menu::{self, MouseContextMenu},
-    scroll::scroll_amount::ScrollAmount,
+    scroll::{scroll_amount::ScrollAmount, Autoscroll},
     CodeActionsMenu, CursorShape, DisplayPoint, DisplayRow, DocumentHighlightRead,
     DocumentHighlightWrite, Editor, EditorMode, EditorSettings, EditorSnapshot, EditorStyle,
     ExpandExcerpts, GutterDimensions, HalfPageDown, HalfPageUp, HoveredCursor, HunkToExpand,
-    LineDown, LineUp, OpenExcerpts, PageDown, PageUp, Point, RowExt, RowRangeExt, SelectPhase,
-    Selection, SoftWrap, ToPoint, CURSORS_VISIBLE_FOR, MAX_LINE_LEN,
+    LineDown, LineUp, MinimapTextState, OpenExcerpts, PageDown, PageUp, Point, RowExt, RowRangeExt,
+    SelectPhase, Selection, SoftWrap, ToPoint, CURSORS_VISIBLE_FOR, MAX_LINE_LEN,
 };
 use client::ParticipantIndex;
 use collections::{BTreeMap, HashMap};
@@ -31,16 +32,17 @@ use gpui::{
     anchored, deferred, div, fill, outline, point, px, quad, relative, size, svg,
     transparent_black, Action, AnchorCorner, AnyElement, AvailableSpace, Bounds, ClipboardItem,
     ContentMask, Corners, CursorStyle, DispatchPhase, Edges, Element, ElementInputHandler, Entity,
-    FontId, GlobalElementId, Hitbox, Hsla, InteractiveElement, IntoElement, Length,
+    FontId, GlobalElementId, HighlightStyle, Hitbox, Hsla, InteractiveElement, IntoElement, Length,
     ModifiersChangedEvent, MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, PaintQuad,
     ParentElement, Pixels, ScrollDelta, ScrollWheelEvent, ShapedLine, SharedString, Size,
-    StatefulInteractiveElement, Style, Styled, TextRun, TextStyle, TextStyleRefinement, View,
-    ViewContext, WeakView, WindowContext,
+    StatefulInteractiveElement, Style, Styled, StyledText, TextRun, TextStyle, TextStyleRefinement,
+    View, ViewContext, WeakView, WindowContext,
 };
 use itertools::Itertools;
 use language::language_settings::{
     IndentGuideBackgroundColoring, IndentGuideColoring, IndentGuideSettings, ShowWhitespaceSetting,
 };
+use language::OutlineItem;
 use lsp::DiagnosticSeverity;
 use multi_buffer::{Anchor, MultiBufferPoint, MultiBufferRow};
 use project::{
@@ -59,7 +61,7 @@ use std::{
     sync::Arc,
 };
 use sum_tree::Bias;
-use theme::{ActiveTheme, PlayerColor};
+use theme::{ActiveTheme, PlayerColor, Theme};
 use ui::prelude::*;
 use ui::{h_flex, ButtonLike, ButtonStyle, ContextMenu, Tooltip};
 use util::ResultExt;
@@ -317,8 +319,14 @@ impl EditorElement {
         register_action(view, cx, Editor::unfold_lines);
         register_action(view, cx, Editor::unfold_at);
         register_action(view, cx, Editor::fold_selected_ranges);
+        register_action(view, cx, Editor::fold_all);
+        register_action(view, cx, Editor::unfold_all);
+        register_action(view, cx, Editor::fold_all_comments);
+        register_action(view, cx, Editor::fold_all_imports);
+        register_action(view, cx, Editor::fold_at_level);
         register_action(view, cx, Editor::show_completions);
         register_action(view, cx, Editor::toggle_code_actions);
+        register_action(view, cx, Editor::toggle_breakpoint);
         register_action(view, cx, Editor::open_excerpts);
         register_action(view, cx, Editor::open_excerpts_in_split);
         register_action(view, cx, Editor::toggle_soft_wrap);
@@ -395,6 +403,8 @@ impl EditorElement {
         register_action(view, cx, Editor::accept_partial_inline_completion);
         register_action(view, cx, Editor::accept_inline_completion);
         register_action(view, cx, Editor::revert_selected_hunks);
+        register_action(view, cx, Editor::stage_hunk);
+        register_action(view, cx, Editor::unstage_hunk);
         register_action(view, cx, Editor::open_active_item_in_terminal)
     }
 
@@ -442,6 +452,7 @@ impl EditorElement {
         position_map: &PositionMap,
         text_hitbox: &Hitbox,
         gutter_hitbox: &Hitbox,
+        gutter_dimensions: &GutterDimensions,
         cx: &mut ViewContext<Editor>,
     ) {
         if cx.default_prevented() {
@@ -456,6 +467,23 @@ impl EditorElement {
             cx.notify();
             return;
         } else if gutter_hitbox.is_hovered(cx) {
+            // Clicking to the left of the line numbers toggles a breakpoint on the line.
+            let blame_width = gutter_dimensions
+                .git_blame_entries_width
+                .unwrap_or(Pixels::ZERO);
+            let gutter_x = event.position.x - gutter_hitbox.left();
+            if editor.project.is_some()
+                && gutter_x >= blame_width
+                && gutter_x < gutter_dimensions.left_padding
+            {
+                let row = position_map
+                    .point_for_position(text_hitbox.bounds, event.position)
+                    .previous_valid
+                    .to_point(&position_map.snapshot.display_snapshot)
+                    .row;
+                editor.toggle_breakpoint_at_row(MultiBufferRow(row), cx);
+                return;
+            }
             click_count = 3; // Simulate triple-click when clicking the gutter to select lines
         } else if !text_hitbox.is_hovered(cx) {
             return;
@@ -1135,6 +1163,192 @@ impl EditorElement {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn layout_minimap(
+        &self,
+        snapshot: &EditorSnapshot,
+        bounds: Bounds<Pixels>,
+        minimap_width: Pixels,
+        scroll_position: gpui::Point<f32>,
+        rows_per_page: f32,
+        max_scroll_top: f32,
+        cx: &mut WindowContext,
+    ) -> Option<MinimapLayout> {
+        if minimap_width == Pixels::ZERO {
+            return None;
+        }
+        let minimap_settings = EditorSettings::get_global(cx).minimap;
+
+        // The minimap is to the left of the scrollbar, and scrolls along with the editor when the
+        // file doesn't fit in it, so that the viewport reaches its top and bottom together.
+        let minimap_bounds = Bounds::from_corners(
+            point(
+                self.scrollbar_left(&bounds) - minimap_width,
+                bounds.origin.y,
+            ),
+            point(self.scrollbar_left(&bounds), bounds.lower_left().y),
+        );
+        let total_rows = snapshot.max_point().row().next_row().as_f32();
+        let minimap_rows = minimap_bounds.size.height / MinimapLayout::ROW_HEIGHT;
+        let max_first_row = (total_rows - minimap_rows).max(0.);
+        let scroll_fraction = if max_scroll_top > 0. {
+            (scroll_position.y / max_scroll_top).clamp(0., 1.)
+        } else {
+            0.
+        };
+        let first_row = scroll_fraction * max_first_row;
+        let drag_row_height = if max_scroll_top > 0. && max_first_row < max_scroll_top {
+            MinimapLayout::ROW_HEIGHT * (1. - max_first_row / max_scroll_top)
+        } else {
+            MinimapLayout::ROW_HEIGHT
+        };
+
+        let mut minimap = MinimapLayout {
+            hitbox: cx.insert_hitbox(minimap_bounds, false),
+            first_row,
+            visible_row_range: scroll_position.y..scroll_position.y + rows_per_page,
+            drag_row_height,
+            highlight_quads: Vec::new(),
+            text_quads: Arc::from([]),
+            git_quads: Vec::new(),
+        };
+
+        let start_row = DisplayRow(first_row.floor() as u32);
+        let end_row = DisplayRow(
+            ((first_row + minimap_rows).ceil() as u32).min(snapshot.max_point().row().next_row().0),
+        );
+        let start_point = DisplayPoint::new(start_row, 0).to_point(snapshot);
+        let end_point = if end_row > snapshot.max_point().row() {
+            snapshot.buffer_snapshot.max_point()
+        } else {
+            DisplayPoint::new(end_row, 0).to_point(snapshot)
+        };
+        let theme = cx.theme().clone();
+
+        if minimap_settings.search_results {
+            let search_range = snapshot.buffer_snapshot.anchor_before(start_point)
+                ..snapshot.buffer_snapshot.anchor_after(end_point);
+            let color = theme.colors().search_match_background;
+            for range in self
+                .editor
+                .read(cx)
+                .background_highlights_of_type_in_range::<BufferSearchHighlights>(
+                    search_range,
+                    &snapshot.display_snapshot,
+                )
+            {
+                minimap.push_range_quads(range, start_row..end_row, color, snapshot);
+            }
+        }
+
+        if minimap_settings.diagnostics {
+            for diagnostic in diagnostic_display_ranges(snapshot, start_point..end_point, &theme) {
+                let mut color = diagnostic.color;
+                color.fade_out(0.5);
+                minimap.push_range_quads(
+                    diagnostic.start..diagnostic.end,
+                    start_row..end_row,
+                    color,
+                    snapshot,
+                );
+            }
+        }
+
+        minimap.text_quads =
+            self.layout_minimap_text(snapshot, start_row..end_row, minimap.max_columns(), cx);
+
+        if minimap_settings.git_diff {
+            let hunks = diff_hunk_display_rows(
+                snapshot,
+                MultiBufferRow(start_point.row)..MultiBufferRow(end_point.row + 1),
+                &theme,
+            );
+            for hunk in hunks {
+                let bounds = Bounds::from_corners(
+                    point(Pixels::ZERO, MinimapLayout::row_top(hunk.start)),
+                    point(
+                        MinimapLayout::GIT_MARKER_WIDTH,
+                        MinimapLayout::row_top(hunk.end.next_row()),
+                    ),
+                );
+                minimap.git_quads.push(fill(bounds, hunk.color));
+            }
+        }
+
+        Some(minimap)
+    }
+
+    /// Draws the text of the minimap's rows, reusing the quads of the previous frame when the rows
+    /// and their highlighting haven't changed, and the rows still in view when it scrolled.
+    fn layout_minimap_text(
+        &self,
+        snapshot: &EditorSnapshot,
+        rows: Range<DisplayRow>,
+        max_columns: u32,
+        cx: &mut WindowContext,
+    ) -> Arc<[PaintQuad]> {
+        self.editor.update(cx, |editor, _| {
+            let state = &mut editor.minimap_text_state;
+            let text_color = self.style.text.color;
+            let rows_version = snapshot.rows_version();
+            let non_text_state_update_count =
+                snapshot.buffer_snapshot.non_text_state_update_count();
+            let is_valid = state.rows_version == rows_version
+                && state.non_text_state_update_count == non_text_state_update_count
+                && state.max_columns == max_columns
+                && state.text_color == text_color
+                && state
+                    .syntax
+                    .as_ref()
+                    .map_or(false, |syntax| Arc::ptr_eq(syntax, &self.style.syntax));
+            if is_valid && state.rows == rows {
+                return state.quads.clone();
+            }
+
+            let mut cached_rows = if is_valid {
+                state.rows.start.max(rows.start)..state.rows.end.min(rows.end)
+            } else {
+                rows.start..rows.start
+            };
+            if cached_rows.is_empty() {
+                cached_rows = rows.start..rows.start;
+            }
+
+            let mut quads = MinimapLayout::text_quads(
+                snapshot.highlighted_chunks(rows.start..cached_rows.start, true, &self.style),
+                rows.start,
+                max_columns,
+                text_color,
+            );
+            let cached_tops =
+                MinimapLayout::row_top(cached_rows.start)..MinimapLayout::row_top(cached_rows.end);
+            quads.extend(
+                state
+                    .quads
+                    .iter()
+                    .filter(|quad| cached_tops.contains(&quad.bounds.top()))
+                    .cloned(),
+            );
+            quads.extend(MinimapLayout::text_quads(
+                snapshot.highlighted_chunks(cached_rows.end..rows.end, true, &self.style),
+                cached_rows.end,
+                max_columns,
+                text_color,
+            ));
+
+            *state = MinimapTextState {
+                rows_version,
+                non_text_state_update_count,
+                max_columns,
+                text_color,
+                syntax: Some(self.style.syntax.clone()),
+                rows,
+                quads: Arc::from(quads),
+            };
+            state.quads.clone()
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn prepaint_gutter_fold_toggles(
         &self,
@@ -1366,6 +1580,128 @@ impl EditorElement {
         Some(element)
     }
 
+    /// Pins the lines that start the outline items enclosing the top of the viewport, so that it's
+    /// clear which scopes the visible lines are in.
+    #[allow(clippy::too_many_arguments)]
+    fn layout_sticky_headers(
+        &self,
+        snapshot: &EditorSnapshot,
+        start_row: DisplayRow,
+        gutter_hitbox: &Hitbox,
+        text_hitbox: &Hitbox,
+        gutter_dimensions: &GutterDimensions,
+        scroll_pixel_position: gpui::Point<Pixels>,
+        line_height: Pixels,
+        em_advance: Pixels,
+        cx: &mut WindowContext,
+    ) -> Vec<AnyElement> {
+        let sticky_scroll = EditorSettings::get_global(cx).sticky_scroll;
+        if !sticky_scroll.enabled || snapshot.mode != EditorMode::Full {
+            return Vec::new();
+        }
+
+        let top_point = DisplayPoint::new(start_row, 0).to_point(snapshot);
+        let Some((_, items)) = snapshot
+            .buffer_snapshot
+            .symbols_containing(top_point, Some(&self.style.syntax))
+        else {
+            return Vec::new();
+        };
+
+        // Each header covers a row, so an item is pinned while it starts above the row that its
+        // header covers and ends below it.
+        let mut headers: Vec<(DisplayPoint, OutlineItem<Anchor>)> = Vec::new();
+        for item in items {
+            if headers.len() >= sticky_scroll.max_depth {
+                break;
+            }
+            let start = item.range.start.to_display_point(snapshot);
+            let end = item.range.end.to_display_point(snapshot);
+            let covered_row = DisplayRow(start_row.0 + headers.len() as u32);
+            let below_previous_header = headers.last().map_or(true, |(previous_start, _)| {
+                previous_start.row() < start.row()
+            });
+            if start.row() < covered_row && end.row() > covered_row && below_previous_header {
+                headers.push((start, item));
+            }
+        }
+
+        let width = text_hitbox.right() - gutter_hitbox.left();
+        let header_count = headers.len();
+        let colors = cx.theme().colors();
+        let (line_number_color, border_color) = (colors.editor_line_number, colors.border_variant);
+        headers
+            .into_iter()
+            .enumerate()
+            .map(|(ix, (start, item))| {
+                let line_number = (start.to_point(snapshot).row + 1).to_string();
+                let line_number_highlight = HighlightStyle {
+                    color: Some(line_number_color),
+                    ..Default::default()
+                };
+                let text_left = gutter_dimensions.margin + em_advance * start.column() as f32
+                    - scroll_pixel_position.x;
+                let scope_start = item.range.start;
+                let editor = self.editor.clone();
+
+                let mut element = h_flex()
+                    .w(width)
+                    .h(line_height)
+                    .bg(self.style.background)
+                    .when(ix + 1 == header_count, |header| {
+                        header.border_b_1().border_color(border_color)
+                    })
+                    .occlude()
+                    .cursor_pointer()
+                    .child(
+                        h_flex()
+                            .flex_none()
+                            .w(gutter_hitbox.size.width)
+                            .h_full()
+                            .justify_end()
+                            .pr(gutter_dimensions.right_padding)
+                            .child(StyledText::new(line_number.clone()).with_highlights(
+                                &self.style.text,
+                                [(0..line_number.len(), line_number_highlight)],
+                            )),
+                    )
+                    .child(
+                        div().relative().flex_1().h_full().overflow_hidden().child(
+                            div().absolute().left(text_left).child(
+                                StyledText::new(item.text)
+                                    .with_highlights(&self.style.text, item.highlight_ranges),
+                            ),
+                        ),
+                    )
+                    // Jump to the start of the scope, where its header was.
+                    .on_mouse_down(MouseButton::Left, move |_, cx| {
+                        editor.update(cx, |editor, cx| {
+                            editor.change_selections(
+                                Some(Autoscroll::top_relative(ix)),
+                                cx,
+                                |selections| {
+                                    selections.select_anchor_ranges([scope_start..scope_start])
+                                },
+                            );
+                        });
+                        cx.stop_propagation();
+                    })
+                    .into_any_element();
+
+                let origin = point(
+                    gutter_hitbox.left(),
+                    text_hitbox.top() + line_height * ix as f32,
+                );
+                let available_space = size(
+                    AvailableSpace::Definite(width),
+                    AvailableSpace::Definite(line_height),
+                );
+                element.prepaint_as_root(origin, available_space, cx);
+                element
+            })
+            .collect()
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn layout_blame_entries(
         &self,
@@ -1607,6 +1943,37 @@ impl EditorElement {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn layout_breakpoint_indicators(
+        &self,
+        line_height: Pixels,
+        scroll_pixel_position: gpui::Point<Pixels>,
+        gutter_dimensions: &GutterDimensions,
+        gutter_hitbox: &Hitbox,
+        snapshot: &EditorSnapshot,
+        rows: Range<DisplayRow>,
+        cx: &mut WindowContext,
+    ) -> Vec<AnyElement> {
+        self.editor.update(cx, |editor, cx| {
+            editor
+                .breakpoint_display_rows(snapshot, rows, cx)
+                .into_iter()
+                .map(|display_row| {
+                    let button = editor.render_breakpoint_indicator(display_row, cx);
+                    prepaint_gutter_button(
+                        button,
+                        display_row,
+                        line_height,
+                        gutter_dimensions,
+                        scroll_pixel_position,
+                        gutter_hitbox,
+                        cx,
+                    )
+                })
+                .collect_vec()
+        })
+    }
+
     fn layout_code_actions_indicator(
         &self,
         line_height: Pixels,
@@ -3016,6 +3383,10 @@ impl EditorElement {
             if let Some(indicator) = layout.code_actions_indicator.as_mut() {
                 indicator.paint(cx);
             }
+
+            for breakpoint_indicator in layout.breakpoint_indicators.iter_mut() {
+                breakpoint_indicator.paint(cx);
+            }
         });
     }
 
@@ -3351,6 +3722,132 @@ impl EditorElement {
         }
     }
 
+    fn paint_minimap(&mut self, layout: &mut EditorLayout, cx: &mut WindowContext) {
+        let Some(minimap) = layout.minimap_layout.as_ref() else {
+            return;
+        };
+
+        let viewport_bounds = minimap.viewport_bounds();
+        // The background also goes under the scrollbar, which the text doesn't reach.
+        let background_bounds =
+            Bounds::from_corners(minimap.hitbox.origin, layout.hitbox.lower_right());
+        cx.paint_layer(background_bounds, |cx| {
+            cx.paint_quad(quad(
+                background_bounds,
+                Corners::default(),
+                self.style.background,
+                Edges {
+                    top: Pixels::ZERO,
+                    right: Pixels::ZERO,
+                    bottom: Pixels::ZERO,
+                    left: MinimapLayout::BORDER_WIDTH,
+                },
+                cx.theme().colors().scrollbar_track_border,
+            ));
+
+            cx.with_content_mask(
+                Some(ContentMask {
+                    bounds: minimap.hitbox.bounds,
+                }),
+                |cx| {
+                    let origin = minimap.quads_origin();
+                    for quad in minimap
+                        .highlight_quads
+                        .iter()
+                        .chain(minimap.text_quads.iter())
+                        .chain(&minimap.git_quads)
+                    {
+                        let mut quad = quad.clone();
+                        quad.bounds.origin += origin;
+                        cx.paint_quad(quad);
+                    }
+
+                    let mut viewport_background = cx.theme().colors().scrollbar_thumb_background;
+                    viewport_background.fade_out(0.5);
+                    cx.paint_quad(fill(viewport_bounds, viewport_background));
+                },
+            );
+        });
+
+        cx.set_cursor_style(CursorStyle::Arrow, &minimap.hitbox);
+
+        let drag_row_height = minimap.drag_row_height;
+        let first_row = minimap.first_row;
+        let rows_per_page = minimap.visible_row_range.end - minimap.visible_row_range.start;
+
+        cx.on_mouse_event({
+            let editor = self.editor.clone();
+            let mut mouse_position = cx.mouse_position();
+            move |event: &MouseMoveEvent, phase, cx| {
+                if phase == DispatchPhase::Capture {
+                    return;
+                }
+
+                editor.update(cx, |editor, cx| {
+                    if event.pressed_button == Some(MouseButton::Left)
+                        && editor.scroll_manager.is_dragging_minimap()
+                    {
+                        let mut position = editor.scroll_position(cx);
+                        position.y += (event.position.y - mouse_position.y) / drag_row_height;
+                        if position.y < 0.0 {
+                            position.y = 0.0;
+                        }
+                        editor.set_scroll_position(position, cx);
+                        cx.stop_propagation();
+                    } else {
+                        editor.scroll_manager.set_is_dragging_minimap(false, cx);
+                    }
+                    mouse_position = event.position;
+                })
+            }
+        });
+
+        if self.editor.read(cx).scroll_manager.is_dragging_minimap() {
+            cx.on_mouse_event({
+                let editor = self.editor.clone();
+                move |_: &MouseUpEvent, phase, cx| {
+                    if phase == DispatchPhase::Capture {
+                        return;
+                    }
+
+                    editor.update(cx, |editor, cx| {
+                        editor.scroll_manager.set_is_dragging_minimap(false, cx);
+                        cx.stop_propagation();
+                    });
+                }
+            });
+        } else {
+            cx.on_mouse_event({
+                let editor = self.editor.clone();
+                let hitbox = minimap.hitbox.clone();
+                move |event: &MouseDownEvent, phase, cx| {
+                    if phase == DispatchPhase::Capture
+                        || event.button != MouseButton::Left
+                        || !hitbox.is_hovered(cx)
+                    {
+                        return;
+                    }
+
+                    editor.update(cx, |editor, cx| {
+                        editor.scroll_manager.set_is_dragging_minimap(true, cx);
+
+                        // Clicking outside of the viewport centers it on the clicked row.
+                        let y = event.position.y;
+                        if y < viewport_bounds.top() || viewport_bounds.bottom() < y {
+                            let clicked_row =
+                                first_row + (y - hitbox.top()) / MinimapLayout::ROW_HEIGHT;
+                            let mut position = editor.scroll_position(cx);
+                            position.y = (clicked_row - rows_per_page / 2.).max(0.);
+                            editor.set_scroll_position(position, cx);
+                        }
+
+                        cx.stop_propagation();
+                    });
+                }
+            });
+        }
+    }
+
     fn collect_fast_scrollbar_markers(
         &self,
         layout: &EditorLayout,
@@ -3404,34 +3901,11 @@ impl EditorElement {
                             let max_point = snapshot.display_snapshot.buffer_snapshot.max_point();
                             let mut marker_quads = Vec::new();
                             if scrollbar_settings.git_diff {
-                                let marker_row_ranges = snapshot
-                                    .buffer_snapshot
-                                    .git_diff_hunks_in_range(
-                                        MultiBufferRow::MIN..MultiBufferRow::MAX,
-                                    )
-                                    .map(|hunk| {
-                                        let start_display_row =
-                                            MultiBufferPoint::new(hunk.associated_range.start.0, 0)
-                                                .to_display_point(&snapshot.display_snapshot)
-                                                .row();
-                                        let mut end_display_row =
-                                            MultiBufferPoint::new(hunk.associated_range.end.0, 0)
-                                                .to_display_point(&snapshot.display_snapshot)
-                                                .row();
-                                        if end_display_row != start_display_row {
-                                            end_display_row.0 -= 1;
-                                        }
-                                        let color = match hunk_status(&hunk) {
-                                            DiffHunkStatus::Added => theme.status().created,
-                                            DiffHunkStatus::Modified => theme.status().modified,
-                                            DiffHunkStatus::Removed => theme.status().deleted,
-                                        };
-                                        ColoredRange {
-                                            start: start_display_row,
-                                            end: end_display_row,
-                                            color,
-                                        }
-                                    });
+                                let marker_row_ranges = diff_hunk_display_rows(
+                                    &snapshot.display_snapshot,
+                                    MultiBufferRow::MIN..MultiBufferRow::MAX,
+                                    &theme,
+                                );
 
                                 marker_quads.extend(
                                     scrollbar_layout
@@ -3477,37 +3951,16 @@ impl EditorElement {
                             }
 
                             if scrollbar_settings.diagnostics {
-                                let diagnostics = snapshot
-                                    .buffer_snapshot
-                                    .diagnostics_in_range::<_, Point>(
-                                        Point::zero()..max_point,
-                                        false,
-                                    )
-                                    // We want to sort by severity, in order to paint the most severe diagnostics last.
-                                    .sorted_by_key(|diagnostic| {
-                                        std::cmp::Reverse(diagnostic.diagnostic.severity)
-                                    });
-
-                                let marker_row_ranges = diagnostics.into_iter().map(|diagnostic| {
-                                    let start_display = diagnostic
-                                        .range
-                                        .start
-                                        .to_display_point(&snapshot.display_snapshot);
-                                    let end_display = diagnostic
-                                        .range
-                                        .end
-                                        .to_display_point(&snapshot.display_snapshot);
-                                    let color = match diagnostic.diagnostic.severity {
-                                        DiagnosticSeverity::ERROR => theme.status().error,
-                                        DiagnosticSeverity::WARNING => theme.status().warning,
-                                        DiagnosticSeverity::INFORMATION => theme.status().info,
-                                        _ => theme.status().hint,
-                                    };
-                                    ColoredRange {
-                                        start: start_display.row(),
-                                        end: end_display.row(),
-                                        color,
-                                    }
+                                let marker_row_ranges = diagnostic_display_ranges(
+                                    &snapshot.display_snapshot,
+                                    Point::zero()..max_point,
+                                    &theme,
+                                )
+                                .into_iter()
+                                .map(|range| ColoredRange {
+                                    start: range.start.row(),
+                                    end: range.end.row(),
+                                    color: range.color,
                                 });
                                 marker_quads.extend(
                                     scrollbar_layout
@@ -3597,6 +4050,17 @@ impl EditorElement {
         }
     }
 
+    fn paint_sticky_headers(&mut self, layout: &mut EditorLayout, cx: &mut WindowContext) {
+        if layout.sticky_headers.is_empty() {
+            return;
+        }
+        cx.paint_layer(layout.hitbox.bounds, |cx| {
+            for header in &mut layout.sticky_headers {
+                header.paint(cx);
+            }
+        });
+    }
+
     fn paint_blocks(&mut self, layout: &mut EditorLayout, cx: &mut WindowContext) {
         for mut block in layout.blocks.drain(..) {
             block.element.paint(cx);
@@ -3680,6 +4144,7 @@ impl EditorElement {
             let editor = self.editor.clone();
             let text_hitbox = layout.text_hitbox.clone();
             let gutter_hitbox = layout.gutter_hitbox.clone();
+            let gutter_dimensions = layout.gutter_dimensions;
 
             move |event: &MouseDownEvent, phase, cx| {
                 if phase == DispatchPhase::Bubble {
@@ -3692,6 +4157,7 @@ impl EditorElement {
                                 &position_map,
                                 &text_hitbox,
                                 &gutter_hitbox,
+                                &gutter_dimensions,
                                 cx,
                             );
                         }),
@@ -3759,6 +4225,24 @@ impl EditorElement {
         bounds.upper_right().x - self.style.scrollbar_width
     }
 
+    /// The width of the minimap, or zero when it isn't shown.
+    fn minimap_width(&self, snapshot: &EditorSnapshot, cx: &WindowContext) -> Pixels {
+        if snapshot.mode != EditorMode::Full {
+            return Pixels::ZERO;
+        }
+        let minimap_settings = EditorSettings::get_global(cx).minimap;
+        let show_minimap = match minimap_settings.show {
+            ShowMinimap::Auto => self.editor.read(cx).is_singleton(cx),
+            ShowMinimap::Always => true,
+            ShowMinimap::Never => false,
+        };
+        if show_minimap {
+            px(minimap_settings.width.max(0.))
+        } else {
+            Pixels::ZERO
+        }
+    }
+
     fn column_pixels(&self, column: usize, cx: &WindowContext) -> Pixels {
         let style = &self.style;
         let font_size = style.text.font_size.to_pixels(cx.rem_size());
@@ -4616,13 +5100,23 @@ impl Element for EditorElement {
                         self.max_line_number_width(&snapshot, cx),
                         cx,
                     );
-                    let text_width = bounds.size.width - gutter_dimensions.width;
-
-                    let right_margin = if snapshot.mode == EditorMode::Full {
-                        EditorElement::SCROLLBAR_WIDTH
+                    // The text doesn't go under the minimap, which is left of the scrollbar.
+                    let minimap_width = self.minimap_width(&snapshot, cx);
+                    let text_width = if minimap_width > Pixels::ZERO {
+                        bounds.size.width
+                            - gutter_dimensions.width
+                            - minimap_width
+                            - EditorElement::SCROLLBAR_WIDTH
                     } else {
-                        px(0.)
+                        bounds.size.width - gutter_dimensions.width
                     };
+
+                    let right_margin =
+                        if snapshot.mode == EditorMode::Full && minimap_width == Pixels::ZERO {
+                            EditorElement::SCROLLBAR_WIDTH
+                        } else {
+                            px(0.)
+                        };
                     let overscroll = size(em_width + right_margin, px(0.));
 
                     snapshot = self.editor.update(cx, |editor, cx| {
@@ -4909,6 +5403,18 @@ impl Element for EditorElement {
                         }
                     }
 
+                    let sticky_headers = self.layout_sticky_headers(
+                        &snapshot,
+                        start_row,
+                        &gutter_hitbox,
+                        &text_hitbox,
+                        &gutter_dimensions,
+                        scroll_pixel_position,
+                        line_height,
+                        em_advance,
+                        cx,
+                    );
+
                     let blamed_display_rows = self.layout_blame_entries(
                         buffer_rows.into_iter(),
                         em_width,
@@ -4995,6 +5501,16 @@ impl Element for EditorElement {
                         cx,
                     );
 
+                    let minimap_layout = self.layout_minimap(
+                        &snapshot,
+                        bounds,
+                        minimap_width,
+                        scroll_position,
+                        height_in_lines,
+                        max_scroll_top,
+                        cx,
+                    );
+
                     let gutter_settings = EditorSettings::get_global(cx).gutter;
 
                     let mut _context_menu_visible = false;
@@ -5058,6 +5574,16 @@ impl Element for EditorElement {
                         vec![]
                     };
 
+                    let breakpoint_indicators = self.layout_breakpoint_indicators(
+                        line_height,
+                        scroll_pixel_position,
+                        &gutter_dimensions,
+                        &gutter_hitbox,
+                        &snapshot,
+                        start_row..end_row,
+                        cx,
+                    );
+
                     if !cx.has_active_drag() {
                         self.layout_hover_popovers(
                             &snapshot,
@@ -5140,6 +5666,8 @@ impl Element for EditorElement {
                         gutter_dimensions,
                         content_origin,
                         scrollbar_layout,
+                        minimap_layout,
+                        sticky_headers,
                         active_rows,
                         highlighted_rows,
                         highlighted_ranges,
@@ -5156,6 +5684,7 @@ impl Element for EditorElement {
                         selections,
                         mouse_context_menu,
                         test_indicators,
+                        breakpoint_indicators,
                         code_actions_indicator,
                         gutter_fold_toggles,
                         crease_trailers,
@@ -5244,6 +5773,8 @@ impl Element for EditorElement {
                         self.paint_gutter_indicators(layout, cx);
                     }
 
+                    self.paint_sticky_headers(layout, cx);
+                    self.paint_minimap(layout, cx);
                     self.paint_scrollbar(layout, cx);
                     self.paint_mouse_context_menu(layout, cx);
                 });
@@ -5268,6 +5799,8 @@ pub struct EditorLayout {
     gutter_dimensions: GutterDimensions,
     content_origin: gpui::Point<Pixels>,
     scrollbar_layout: Option<ScrollbarLayout>,
+    minimap_layout: Option<MinimapLayout>,
+    sticky_headers: Vec<AnyElement>,
     mode: EditorMode,
     wrap_guides: SmallVec<[(Pixels, bool); 2]>,
     indent_guides: Option<Vec<IndentGuideLayout>>,
@@ -5288,6 +5821,7 @@ pub struct EditorLayout {
     selections: Vec<(PlayerColor, Vec<SelectionLayout>)>,
     code_actions_indicator: Option<AnyElement>,
     test_indicators: Vec<AnyElement>,
+    breakpoint_indicators: Vec<AnyElement>,
     gutter_fold_toggles: Vec<Option<AnyElement>>,
     crease_trailers: Vec<Option<CreaseTrailerLayout>>,
     mouse_context_menu: Option<AnyElement>,
@@ -5301,6 +5835,66 @@ impl EditorLayout {
     }
 }
 
+/// The display rows of the diff hunks within the given buffer rows, ending at the last row of each
+/// hunk.
+fn diff_hunk_display_rows<'a>(
+    snapshot: &'a DisplaySnapshot,
+    buffer_rows: Range<MultiBufferRow>,
+    theme: &'a Theme,
+) -> impl Iterator<Item = ColoredRange<DisplayRow>> + 'a {
+    snapshot
+        .buffer_snapshot
+        .git_diff_hunks_in_range(buffer_rows)
+        .map(|hunk| {
+            let start_row = MultiBufferPoint::new(hunk.associated_range.start.0, 0)
+                .to_display_point(snapshot)
+                .row();
+            let mut end_row = MultiBufferPoint::new(hunk.associated_range.end.0, 0)
+                .to_display_point(snapshot)
+                .row();
+            if end_row != start_row {
+                end_row.0 -= 1;
+            }
+            let color = match hunk_status(&hunk) {
+                DiffHunkStatus::Added => theme.status().created,
+                DiffHunkStatus::Modified => theme.status().modified,
+                DiffHunkStatus::Removed => theme.status().deleted,
+            };
+            ColoredRange {
+                start: start_row,
+                end: end_row,
+                color,
+            }
+        })
+}
+
+/// The display ranges of the diagnostics within the given range, ordered so that the most severe
+/// ones come last and get painted on top.
+fn diagnostic_display_ranges(
+    snapshot: &DisplaySnapshot,
+    range: Range<Point>,
+    theme: &Theme,
+) -> Vec<ColoredRange<DisplayPoint>> {
+    snapshot
+        .buffer_snapshot
+        .diagnostics_in_range::<_, Point>(range, false)
+        .sorted_by_key(|diagnostic| std::cmp::Reverse(diagnostic.diagnostic.severity))
+        .map(|diagnostic| {
+            let color = match diagnostic.diagnostic.severity {
+                DiagnosticSeverity::ERROR => theme.status().error,
+                DiagnosticSeverity::WARNING => theme.status().warning,
+                DiagnosticSeverity::INFORMATION => theme.status().info,
+                _ => theme.status().hint,
+            };
+            ColoredRange {
+                start: diagnostic.range.start.to_display_point(snapshot),
+                end: diagnostic.range.end.to_display_point(snapshot),
+                color,
+            }
+        })
+        .collect()
+}
+
 struct ColoredRange<T> {
     start: T,
     end: T,
@@ -5413,6 +6007,148 @@ impl ScrollbarLayout {
     }
 }
 
+/// The quads of the minimap are positioned relative to its left edge and the top of the first
+/// display row, and get moved into place when they're painted.
+struct MinimapLayout {
+    hitbox: Hitbox,
+    /// The display row at the top of the minimap, which isn't whole while scrolling.
+    first_row: f32,
+    visible_row_range: Range<f32>,
+    /// How far the viewport moves in the minimap when the editor scrolls by one row.
+    drag_row_height: Pixels,
+    /// Search results and diagnostics, which are painted below the text.
+    highlight_quads: Vec<PaintQuad>,
+    text_quads: Arc<[PaintQuad]>,
+    git_quads: Vec<PaintQuad>,
+}
+
+impl MinimapLayout {
+    const BORDER_WIDTH: Pixels = px(1.0);
+    const ROW_HEIGHT: Pixels = px(3.0);
+    const TEXT_HEIGHT: Pixels = px(2.0);
+    const COLUMN_WIDTH: Pixels = px(1.0);
+    const GIT_MARKER_WIDTH: Pixels = px(2.0);
+    const TEXT_PADDING: Pixels = px(6.0);
+
+    fn y_for_row(&self, row: f32) -> Pixels {
+        self.hitbox.top() + (row - self.first_row) * Self::ROW_HEIGHT
+    }
+
+    fn row_top(row: DisplayRow) -> Pixels {
+        row.as_f32() * Self::ROW_HEIGHT
+    }
+
+    fn quads_origin(&self) -> gpui::Point<Pixels> {
+        point(self.hitbox.left(), self.y_for_row(0.))
+    }
+
+    fn max_columns(&self) -> u32 {
+        ((self.hitbox.size.width - Self::TEXT_PADDING) / Self::COLUMN_WIDTH).max(0.) as u32
+    }
+
+    fn viewport_bounds(&self) -> Bounds<Pixels> {
+        Bounds::from_corners(
+            point(
+                self.hitbox.left(),
+                self.y_for_row(self.visible_row_range.start),
+            ),
+            point(
+                self.hitbox.right(),
+                self.y_for_row(self.visible_row_range.end),
+            ),
+        )
+    }
+
+    fn columns_bounds(row: DisplayRow, columns: Range<u32>, height: Pixels) -> Bounds<Pixels> {
+        let x_for_column = |column: u32| Self::TEXT_PADDING + column as f32 * Self::COLUMN_WIDTH;
+        let top = Self::row_top(row);
+        Bounds::from_corners(
+            point(x_for_column(columns.start), top),
+            point(x_for_column(columns.end), top + height),
+        )
+    }
+
+    /// Highlights the columns of a range in each of the minimap's rows that it spans.
+    fn push_range_quads(
+        &mut self,
+        range: Range<DisplayPoint>,
+        rows: Range<DisplayRow>,
+        color: Hsla,
+        snapshot: &DisplaySnapshot,
+    ) {
+        let max_columns = self.max_columns();
+        let start_row = range.start.row().max(rows.start);
+        let end_row = range.end.row().min(rows.end.previous_row());
+        for row in start_row.0..=end_row.0 {
+            let row = DisplayRow(row);
+            let start_column = if row == range.start.row() {
+                range.start.column()
+            } else {
+                0
+            };
+            let end_column = if row == range.end.row() {
+                range.end.column()
+            } else {
+                snapshot.line_len(row)
+            };
+            let start_column = start_column.min(max_columns);
+            let end_column = end_column.max(start_column + 1).min(max_columns);
+            if start_column < end_column {
+                let bounds = Self::columns_bounds(row, start_column..end_column, Self::ROW_HEIGHT);
+                self.highlight_quads.push(fill(bounds, color));
+            }
+        }
+    }
+
+    /// Draws each run of non-whitespace characters as a bar in the color of its text.
+    fn text_quads<'a>(
+        chunks: impl Iterator<Item = HighlightedChunk<'a>>,
+        start_row: DisplayRow,
+        max_columns: u32,
+        default_color: Hsla,
+    ) -> Vec<PaintQuad> {
+        let mut quads = Vec::new();
+        let mut push_run = |run: &mut Option<(u32, Hsla)>, row, end_column| {
+            if let Some((start_column, color)) = run.take() {
+                let bounds = Self::columns_bounds(row, start_column..end_column, Self::TEXT_HEIGHT);
+                quads.push(fill(bounds, color));
+            }
+        };
+
+        let mut row = start_row;
+        let mut column = 0;
+        let mut run: Option<(u32, Hsla)> = None;
+        for chunk in chunks {
+            let mut color = chunk
+                .style
+                .and_then(|style| style.color)
+                .unwrap_or(default_color);
+            color.fade_out(0.3);
+            for (ix, line) in chunk.text.split('\n').enumerate() {
+                if ix > 0 {
+                    push_run(&mut run, row, column.min(max_columns));
+                    row.0 += 1;
+                    column = 0;
+                }
+                for character in line.chars() {
+                    if column >= max_columns {
+                        break;
+                    }
+                    if character.is_whitespace() {
+                        push_run(&mut run, row, column);
+                    } else if run.map_or(true, |(_, run_color)| run_color != color) {
+                        push_run(&mut run, row, column);
+                        run = Some((column, color));
+                    }
+                    column += 1;
+                }
+            }
+        }
+        push_run(&mut run, row, column.min(max_columns));
+        quads
+    }
+}
+
 struct CreaseTrailerLayout {
     element: AnyElement,
     bounds: Bounds<Pixels>,
@@ -5790,12 +6526,14 @@ mod tests {
     use super::*;
     use crate::{
         display_map::{BlockDisposition, BlockProperties},
+        editor_settings::{MinimapContent, StickyScrollContent},
         editor_tests::{init_test, update_test_language_settings},
         Editor, MultiBuffer,
     };
     use gpui::{TestAppContext, VisualTestContext};
-    use language::language_settings;
+    use language::{language_settings, Buffer, Language, LanguageConfig};
     use log::info;
+    use settings::SettingsStore;
     use std::num::NonZeroU32;
     use ui::Context;
     use util::test::sample_text;
@@ -6073,6 +6811,122 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    fn test_minimap_layout(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|settings, cx| {
+                settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.minimap = Some(MinimapContent {
+                        show: Some(ShowMinimap::Always),
+                        width: Some(100.),
+                        git_diff: None,
+                        search_results: None,
+                        diagnostics: None,
+                    });
+                });
+            })
+        });
+
+        let window = cx.add_window(|cx| {
+            let buffer = MultiBuffer::build_simple(&sample_text(20, 6, 'a'), cx);
+            Editor::new(EditorMode::Full, buffer, None, true, cx)
+        });
+        let cx = &mut VisualTestContext::from_window(*window, cx);
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|cx| editor.read(cx).style().unwrap().clone());
+
+        let (_, state) = cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style.clone())
+        });
+        let minimap = state.minimap_layout.as_ref().unwrap();
+        assert_eq!(minimap.hitbox.size.width, px(100.));
+        assert_eq!(
+            minimap.hitbox.right(),
+            state.hitbox.right() - EditorElement::SCROLLBAR_WIDTH
+        );
+        assert!(state.text_hitbox.right() <= minimap.hitbox.left());
+        // The whole file fits in the minimap, so it doesn't scroll, and every line is drawn.
+        assert_eq!(minimap.first_row, 0.);
+        assert_eq!(minimap.text_quads.len(), 20);
+
+        // Nothing changed, so the next frame reuses the text.
+        let text_quads = minimap.text_quads.clone();
+        let (_, state) = cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style)
+        });
+        let minimap = state.minimap_layout.as_ref().unwrap();
+        assert!(Arc::ptr_eq(&minimap.text_quads, &text_quads));
+    }
+
+    #[gpui::test]
+    fn test_sticky_headers(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|settings, cx| {
+                settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.sticky_scroll = Some(StickyScrollContent {
+                        enabled: Some(true),
+                        max_depth: Some(5),
+                    });
+                });
+            })
+        });
+
+        let language = Arc::new(
+            Language::new(
+                LanguageConfig::default(),
+                Some(tree_sitter_rust::language()),
+            )
+            .with_outline_query(
+                r#"(mod_item "mod" @context name: (_) @name) @item
+                (function_item "fn" @context name: (_) @name) @item"#,
+            )
+            .unwrap(),
+        );
+        let body = "        let x = 1;\n".repeat(20);
+        let text = format!("mod a {{\n    fn b() {{\n{body}    }}\n}}\n");
+        let window = cx.add_window(|cx| {
+            let buffer = cx.new_model(|cx| Buffer::local(text, cx).with_language(language, cx));
+            let buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer, cx));
+            Editor::new(EditorMode::Full, buffer, None, true, cx)
+        });
+        let cx = &mut VisualTestContext::from_window(*window, cx);
+        cx.run_until_parked();
+        let editor = window.root(cx).unwrap();
+        let style = cx.update(|cx| editor.read(cx).style().unwrap().clone());
+
+        // Nothing encloses the top of the editor before scrolling.
+        let (_, state) = cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style.clone())
+        });
+        assert_eq!(state.sticky_headers.len(), 0);
+
+        // Both the module and the function are pinned once their first lines scroll out of view.
+        editor.update(cx, |editor, cx| {
+            editor.set_scroll_position(gpui::Point::new(0., 10.), cx)
+        });
+        let (_, state) = cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style.clone())
+        });
+        assert_eq!(state.sticky_headers.len(), 2);
+
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|settings, cx| {
+                settings.update_user_settings::<EditorSettings>(cx, |settings| {
+                    settings.sticky_scroll = Some(StickyScrollContent {
+                        enabled: Some(true),
+                        max_depth: Some(1),
+                    });
+                });
+            })
+        });
+        let (_, state) = cx.draw(point(px(500.), px(500.)), size(px(500.), px(500.)), |_| {
+            EditorElement::new(&editor, style)
+        });
+        assert_eq!(state.sticky_headers.len(), 1);
+    }
+
     #[gpui::test]
     fn test_all_invisibles_drawing(cx: &mut TestAppContext) {
         const TAB_SIZE: u32 = 4;
@@ -0,0 +1,562 @@
+use std::{
+    hash::Hasher as _,
+    ops::Range,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use collections::FxHasher;
+use gpui::{AppContext, Task, ViewContext};
+use language::Point;
+use multi_buffer::{Anchor, MultiBufferSnapshot, ToPoint as _};
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+use text::Bias;
+use util::ResultExt as _;
+
+use crate::{persistence::DB, scroll::Autoscroll, Editor, EditorMode};
+
+const SERIALIZE_FILE_STATE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// How long the state of a file is kept after it was last stored.
+const FILE_STATE_MAX_AGE: Duration = Duration::from_secs(90 * 24 * 60 * 60);
+
+/// Deletes the states of files that weren't stored for a while, or that were deleted.
+pub(super) fn prune_file_states(cx: &AppContext) {
+    let oldest = SystemTime::now()
+        .checked_sub(FILE_STATE_MAX_AGE)
+        .unwrap_or(UNIX_EPOCH);
+    cx.background_executor()
+        .spawn(async move {
+            DB.delete_file_states_before(timestamp_millis(oldest))
+                .await
+                .log_err();
+            for path in DB.file_state_paths().log_err().unwrap_or_default() {
+                if !path.exists() {
+                    DB.delete_file_state(path).await.log_err();
+                }
+            }
+        })
+        .detach();
+}
+
+fn timestamp_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+/// The folds and selections of the file of an editor, which are stored per file path so that they
+/// are restored when the file is opened again.
+#[derive(Default)]
+pub(super) struct FileState {
+    restored: bool,
+    /// What is stored for the file, when it's known.
+    stored: Option<StoredFileState>,
+    restore_task: Option<Task<()>>,
+    serialize_task: Option<Task<Option<()>>>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+enum StoredFileState {
+    /// Nothing is stored, since the file has no folds and its cursor is at its start.
+    Empty,
+    Ranges {
+        version: clock::Global,
+        folds: String,
+        selections: String,
+    },
+}
+
+impl FileState {
+    /// The state of an editor that already has the folds and selections of its file, like a clone
+    /// of another editor.
+    pub(super) fn restored() -> Self {
+        Self {
+            restored: true,
+            stored: None,
+            restore_task: None,
+            serialize_task: None,
+        }
+    }
+}
+
+/// A position in a file, stored as its row and column so that it can be re-anchored in the file's
+/// contents when they change outside of the editor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializedPoint {
+    pub row: u32,
+    pub column: u32,
+}
+
+impl From<Point> for SerializedPoint {
+    fn from(point: Point) -> Self {
+        Self {
+            row: point.row,
+            column: point.column,
+        }
+    }
+}
+
+/// A selection is stored from its tail to its head, so that its start is after its end when it's
+/// reversed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct SerializedRange {
+    start: SerializedPoint,
+    end: SerializedPoint,
+}
+
+impl From<Range<Point>> for SerializedRange {
+    fn from(range: Range<Point>) -> Self {
+        Self {
+            start: range.start.into(),
+            end: range.end.into(),
+        }
+    }
+}
+
+/// A hash of each line of a file, used to find where the lines of the version of the file that a
+/// state was stored for are in its current contents.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineFingerprints(Vec<u64>);
+
+impl LineFingerprints {
+    pub fn new(buffer: &MultiBufferSnapshot) -> Self {
+        fn fingerprint(line: &str) -> u64 {
+            let mut hasher = FxHasher::default();
+            hasher.write(line.as_bytes());
+            hasher.finish()
+        }
+
+        // Lines are hashed whole, since the hash of a line would otherwise depend on how it is
+        // split into chunks.
+        let mut fingerprints = Vec::new();
+        let mut line = String::new();
+        for chunk in buffer.text_for_range(0..buffer.len()) {
+            let mut chunk_lines = chunk.split('\n');
+            if let Some(first_line) = chunk_lines.next() {
+                line.push_str(first_line);
+            }
+            for chunk_line in chunk_lines {
+                fingerprints.push(fingerprint(&line));
+                line.clear();
+                line.push_str(chunk_line);
+            }
+        }
+        fingerprints.push(fingerprint(&line));
+        Self(fingerprints)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self(
+            bytes
+                .chunks_exact(8)
+                .filter_map(|bytes| Some(u64::from_le_bytes(bytes.try_into().ok()?)))
+                .collect(),
+        )
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0
+            .iter()
+            .flat_map(|fingerprint| fingerprint.to_le_bytes())
+            .collect()
+    }
+
+    /// Maps the rows of the contents these fingerprints were computed from to the rows of the
+    /// contents that `new` was computed from.
+    pub fn row_map(&self, new: &LineFingerprints) -> RowMap {
+        let mut rows = vec![Err(0); self.0.len()];
+        for op in capture_diff_slices(Algorithm::Myers, &self.0, &new.0) {
+            match op {
+                DiffOp::Equal {
+                    old_index,
+                    new_index,
+                    len,
+                } => {
+                    for ix in 0..len {
+                        rows[old_index + ix] = Ok((new_index + ix) as u32);
+                    }
+                }
+                DiffOp::Delete {
+                    old_index,
+                    old_len,
+                    new_index,
+                }
+                | DiffOp::Replace {
+                    old_index,
+                    old_len,
+                    new_index,
+                    ..
+                } => {
+                    for row in &mut rows[old_index..old_index + old_len] {
+                        *row = Err(new_index as u32);
+                    }
+                }
+                DiffOp::Insert { .. } => {}
+            }
+        }
+        RowMap {
+            rows,
+            max_row: new.0.len().saturating_sub(1) as u32,
+        }
+    }
+}
+
+/// Where the rows of a previous version of a file are in its current contents. Each row is either
+/// the row its unchanged line moved to, or the row where its line was deleted or changed.
+pub struct RowMap {
+    rows: Vec<Result<u32, u32>>,
+    max_row: u32,
+}
+
+impl RowMap {
+    /// The position of a point in the current contents, if its line is still there.
+    pub fn point(&self, point: SerializedPoint, buffer: &MultiBufferSnapshot) -> Option<Point> {
+        let row = (*self.rows.get(point.row as usize)?).ok()?;
+        Some(buffer.clip_point(Point::new(row, point.column), Bias::Left))
+    }
+
+    /// The position of a point in the current contents, or the start of the row where its line
+    /// was when it was deleted or changed.
+    pub fn nearest_point(&self, point: SerializedPoint, buffer: &MultiBufferSnapshot) -> Point {
+        self.point(point, buffer).unwrap_or_else(|| {
+            let row = match self.rows.get(point.row as usize) {
+                Some(Ok(row) | Err(row)) => *row,
+                None => self.max_row,
+            };
+            buffer.clip_point(Point::new(row.min(self.max_row), 0), Bias::Left)
+        })
+    }
+}
+
+impl Editor {
+    /// The path that the state of the editor's file is stored for, when it edits a single local
+    /// file in a workspace.
+    fn file_state_path(&self, cx: &ViewContext<Self>) -> Option<PathBuf> {
+        if self.mode != EditorMode::Full || self.workspace.is_none() {
+            return None;
+        }
+        let buffer = self.buffer.read(cx).as_singleton()?;
+        let path = buffer.read(cx).file()?.as_local()?.abs_path(cx);
+        Some(path)
+    }
+
+    /// Restores the folds and selections stored for the editor's file, re-anchoring them in the
+    /// current contents of the file. Folds whose lines changed are dropped.
+    pub(super) fn restore_file_state(&mut self, cx: &mut ViewContext<Self>) {
+        if self.file_state.restored || self.file_state.restore_task.is_some() {
+            return;
+        }
+        let Some(path) = self.file_state_path(cx) else {
+            self.file_state.restored = true;
+            return;
+        };
+
+        // Reading the state and diffing the file's lines takes a while for large files.
+        let buffer = self.buffer.read(cx).snapshot(cx);
+        let file_state = cx.background_executor().spawn(async move {
+            let Some((line_fingerprints, folds, selections)) = DB.get_file_state(path)? else {
+                return Ok(None);
+            };
+            let folds = serde_json::from_str::<Vec<SerializedRange>>(&folds)?;
+            let selections = serde_json::from_str::<Vec<SerializedRange>>(&selections)?;
+
+            let row_map = LineFingerprints::from_bytes(&line_fingerprints)
+                .row_map(&LineFingerprints::new(&buffer));
+            let folds = folds
+                .into_iter()
+                .filter_map(|fold| {
+                    let range =
+                        row_map.point(fold.start, &buffer)?..row_map.point(fold.end, &buffer)?;
+                    (range.start < range.end)
+                        .then(|| buffer.anchor_after(range.start)..buffer.anchor_before(range.end))
+                })
+                .collect::<Vec<_>>();
+            let selections = selections
+                .into_iter()
+                .map(|selection| {
+                    buffer.anchor_before(row_map.nearest_point(selection.start, &buffer))
+                        ..buffer.anchor_before(row_map.nearest_point(selection.end, &buffer))
+                })
+                .collect::<Vec<_>>();
+            anyhow::Ok(Some((folds, selections)))
+        });
+
+        self.file_state.restore_task = Some(cx.spawn(|editor, mut cx| async move {
+            let file_state = file_state.await.log_err();
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.file_state.restored = true;
+                    let Some(file_state) = file_state else {
+                        return;
+                    };
+                    let Some((folds, selections)) = file_state else {
+                        editor.file_state.stored = Some(StoredFileState::Empty);
+                        return;
+                    };
+
+                    let display_snapshot = editor
+                        .display_map
+                        .update(cx, |display_map, cx| display_map.snapshot(cx));
+                    let placeholder = display_snapshot.fold_placeholder.clone();
+                    editor.fold_ranges(
+                        folds
+                            .into_iter()
+                            .map(|fold| (fold, placeholder.clone()))
+                            .collect::<Vec<_>>(),
+                        false,
+                        cx,
+                    );
+
+                    // Don't move the cursor when the editor was opened at a specific position, or
+                    // when it was moved while the state was being restored.
+                    let buffer = &display_snapshot.buffer_snapshot;
+                    let at_start = editor
+                        .selections
+                        .disjoint_anchors()
+                        .iter()
+                        .all(|selection| selection.head().to_point(buffer) == Point::zero());
+                    if !selections.is_empty() && at_start {
+                        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                            s.select_ranges(selections)
+                        });
+                    }
+                })
+                .ok();
+        }));
+    }
+
+    /// Stores the folds and selections of the editor's file, once they stop changing. Nothing is
+    /// stored for a file without folds whose cursor is at its start, and the state isn't written
+    /// again when it didn't change.
+    pub(super) fn serialize_file_state(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.file_state.restored {
+            return;
+        }
+        let Some(path) = self.file_state_path(cx) else {
+            return;
+        };
+
+        self.file_state.serialize_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor()
+                .timer(SERIALIZE_FILE_STATE_DEBOUNCE)
+                .await;
+            let (buffer, version, folds, selections, stored) = editor
+                .update(&mut cx, |editor, cx| {
+                    let version = editor.buffer.read(cx).as_singleton()?.read(cx).version();
+                    let display_snapshot = editor
+                        .display_map
+                        .update(cx, |display_map, cx| display_map.snapshot(cx));
+                    let buffer = display_snapshot.buffer_snapshot.clone();
+                    let folds = display_snapshot
+                        .folds_in_range(0..buffer.len())
+                        .map(|fold| fold.range.start..fold.range.end)
+                        .collect::<Vec<_>>();
+                    let selections = editor
+                        .selections
+                        .disjoint_anchors()
+                        .iter()
+                        .map(|selection| selection.tail()..selection.head())
+                        .collect::<Vec<_>>();
+                    let stored = editor.file_state.stored.clone();
+                    Some((buffer, version, folds, selections, stored))
+                })
+                .ok()??;
+
+            let file_state = cx.background_executor().spawn(async move {
+                let serialize = |ranges: Vec<Range<Anchor>>| {
+                    ranges
+                        .into_iter()
+                        .map(|range| {
+                            SerializedRange::from(
+                                range.start.to_point(&buffer)..range.end.to_point(&buffer),
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let folds = serialize(folds);
+                let selections = serialize(selections);
+                let origin = SerializedPoint::from(Point::zero());
+                let is_empty = folds.is_empty()
+                    && selections
+                        .iter()
+                        .all(|selection| selection.start == origin && selection.end == origin);
+                if is_empty {
+                    if stored != Some(StoredFileState::Empty) {
+                        DB.delete_file_state(path).await?;
+                    }
+                    return anyhow::Ok(StoredFileState::Empty);
+                }
+
+                let folds = serde_json::to_string(&folds)?;
+                let selections = serde_json::to_string(&selections)?;
+                let updated_at = timestamp_millis(SystemTime::now());
+                let contents_unchanged = matches!(
+                    &stored,
+                    Some(StoredFileState::Ranges { version: stored_version, .. })
+                        if *stored_version == version
+                );
+                let file_state = StoredFileState::Ranges {
+                    version,
+                    folds: folds.clone(),
+                    selections: selections.clone(),
+                };
+                if stored.as_ref() == Some(&file_state) {
+                    return Ok(file_state);
+                }
+                // The line fingerprints only need to be computed again when the contents changed.
+                if contents_unchanged {
+                    DB.save_file_state_ranges(path, folds, selections, updated_at)
+                        .await?;
+                } else {
+                    DB.save_file_state(
+                        path,
+                        LineFingerprints::new(&buffer).to_bytes(),
+                        folds,
+                        selections,
+                        updated_at,
+                    )
+                    .await?;
+                }
+                Ok(file_state)
+            });
+            let file_state = file_state.await.log_err()?;
+
+            editor
+                .update(&mut cx, |editor, _| {
+                    editor.file_state.stored = Some(file_state);
+                })
+                .ok()
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{editor_tests::init_test, FoldPlaceholder};
+    use gpui::{AppContext, TestAppContext, View, VisualTestContext, WindowHandle};
+    use multi_buffer::MultiBuffer;
+    use project::{FakeFs, Project, WorktreeId};
+    use serde_json::json;
+    use workspace::{CloseActiveItem, Workspace};
+
+    #[gpui::test]
+    fn test_row_map(cx: &mut AppContext) {
+        let old = MultiBuffer::build_simple("a\nb\nc\nd\ne", cx)
+            .read(cx)
+            .snapshot(cx);
+        let new = MultiBuffer::build_simple("x\na\nc\nD\ne", cx)
+            .read(cx)
+            .snapshot(cx);
+
+        let row_map = LineFingerprints::new(&old).row_map(&LineFingerprints::new(&new));
+        let point = |row, column| SerializedPoint { row, column };
+        assert_eq!(row_map.point(point(0, 1), &new), Some(Point::new(1, 1)));
+        assert_eq!(row_map.point(point(1, 0), &new), None);
+        assert_eq!(row_map.point(point(2, 0), &new), Some(Point::new(2, 0)));
+        assert_eq!(row_map.point(point(3, 0), &new), None);
+        assert_eq!(row_map.nearest_point(point(3, 1), &new), Point::new(3, 0));
+        assert_eq!(row_map.point(point(4, 0), &new), Some(Point::new(4, 0)));
+        assert_eq!(row_map.nearest_point(point(9, 0), &new), Point::new(4, 0));
+
+        let bytes = LineFingerprints::new(&old).to_bytes();
+        assert_eq!(
+            LineFingerprints::from_bytes(&bytes),
+            LineFingerprints::new(&old)
+        );
+    }
+
+    #[gpui::test]
+    async fn test_restore_file_state(cx: &mut TestAppContext) {
+        init_test(cx, |_| {});
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/file_state",
+            json!({ "main.rs": "fn a() {\n    b();\n}\n\nfn c() {\n    d();\n}\n" }),
+        )
+        .await;
+        let project = Project::test(fs, ["/file_state".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees().next().unwrap().read(cx).id()
+        });
+
+        async fn open(
+            workspace: WindowHandle<Workspace>,
+            worktree_id: WorktreeId,
+            cx: &mut VisualTestContext,
+        ) -> View<Editor> {
+            let editor = workspace
+                .update(cx, |workspace, cx| {
+                    workspace.open_path((worktree_id, "main.rs"), None, true, cx)
+                })
+                .unwrap()
+                .await
+                .unwrap()
+                .downcast::<Editor>()
+                .unwrap();
+            cx.run_until_parked();
+            editor
+        }
+
+        let editor = open(workspace, worktree_id, cx).await;
+        editor.update(cx, |editor, cx| {
+            editor.fold_ranges(
+                [(Point::new(4, 8)..Point::new(6, 0), FoldPlaceholder::test())],
+                true,
+                cx,
+            );
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::new(1, 6)..Point::new(1, 4)])
+            });
+        });
+        cx.executor().advance_clock(SERIALIZE_FILE_STATE_DEBOUNCE);
+        cx.run_until_parked();
+
+        workspace
+            .update(cx, |workspace, cx| {
+                workspace.active_pane().update(cx, |pane, cx| {
+                    pane.close_active_item(&CloseActiveItem { save_intent: None }, cx)
+                })
+            })
+            .unwrap()
+            .unwrap()
+            .await
+            .unwrap();
+        drop(editor);
+        cx.run_until_parked();
+
+        let editor = open(workspace, worktree_id, cx).await;
+        editor.update(cx, |editor, cx| {
+            let display_snapshot = editor
+                .display_map
+                .update(cx, |display_map, cx| display_map.snapshot(cx));
+            let buffer = &display_snapshot.buffer_snapshot;
+            let folds = display_snapshot
+                .folds_in_range(0..buffer.len())
+                .map(|fold| fold.range.start.to_point(buffer)..fold.range.end.to_point(buffer))
+                .collect::<Vec<_>>();
+            assert_eq!(folds, [Point::new(4, 8)..Point::new(6, 0)]);
+
+            let selection = editor.selections.newest::<Point>(cx);
+            assert!(selection.reversed);
+            assert_eq!(selection.range(), Point::new(1, 4)..Point::new(1, 6));
+        });
+
+        // A file without folds whose cursor is at its start has nothing stored.
+        let path = PathBuf::from("/file_state/main.rs");
+        assert!(DB.get_file_state(path.clone()).unwrap().is_some());
+        editor.update(cx, |editor, cx| {
+            editor.unfold_all(&Default::default(), cx);
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::zero()..Point::zero()])
+            });
+        });
+        cx.executor().advance_clock(SERIALIZE_FILE_STATE_DEBOUNCE);
+        cx.run_until_parked();
+        assert!(DB.get_file_state(path).unwrap().is_none());
+    }
+}
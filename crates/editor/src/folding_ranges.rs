@@ -0,0 +1,205 @@
+use std::{sync::Arc, time::Duration};
+
+use collections::HashMap;
+use futures::future;
+use gpui::{Task, ViewContext};
+use language::{
+    language_settings::language_settings, BufferId, BufferSnapshot, FoldingRange,
+    OffsetRangeExt as _,
+};
+use multi_buffer::{Anchor, ExcerptId, MultiBufferSnapshot};
+use util::ResultExt as _;
+
+use crate::{Editor, EditorMode};
+
+pub(super) const FOLDING_RANGES_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Syntax-aware folding ranges of the buffers shown in an editor, which decide the lines that can
+/// be folded instead of their indentation.
+#[derive(Default)]
+pub(super) struct FoldingRangesState {
+    /// The versions of the buffers whose folding ranges were last requested.
+    fetched_versions: HashMap<BufferId, clock::Global>,
+    refresh_task: Option<Task<Option<()>>>,
+}
+
+impl Editor {
+    /// Requests the folding ranges of a buffer again, e.g. after it was reparsed, or of all
+    /// buffers when no buffer is given.
+    pub(super) fn invalidate_folding_ranges(
+        &mut self,
+        buffer_id: Option<BufferId>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        match buffer_id {
+            Some(buffer_id) => {
+                self.folding_ranges.fetched_versions.remove(&buffer_id);
+            }
+            None => self.folding_ranges.fetched_versions.clear(),
+        }
+        self.refresh_folding_ranges(cx);
+    }
+
+    /// Stops folding by the ranges that were in removed excerpts.
+    pub(super) fn remove_excerpt_folding_ranges(
+        &mut self,
+        excerpt_ids: &[ExcerptId],
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.display_map.update(cx, |display_map, _| {
+            display_map.remove_excerpt_folding_ranges(excerpt_ids)
+        });
+    }
+
+    /// Requests the folding ranges of the buffers near the viewport that changed since their
+    /// ranges were last requested, from their primary language server, falling back to the folds
+    /// query of their language when the server doesn't report any.
+    pub(super) fn refresh_folding_ranges(&mut self, cx: &mut ViewContext<Self>) {
+        if self.mode != EditorMode::Full {
+            return;
+        }
+
+        let buffers = self
+            .buffers_near_viewport(cx)
+            .into_iter()
+            .filter(|buffer| {
+                let buffer = buffer.read(cx);
+                self.folding_ranges
+                    .fetched_versions
+                    .get(&buffer.remote_id())
+                    != Some(&buffer.version())
+            })
+            .collect::<Vec<_>>();
+        if buffers.is_empty() {
+            return;
+        }
+
+        let project = self.project.clone();
+        self.folding_ranges.refresh_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor()
+                .timer(FOLDING_RANGES_DEBOUNCE)
+                .await;
+
+            let requests = editor
+                .update(&mut cx, |_, cx| {
+                    buffers
+                        .into_iter()
+                        .map(|buffer| {
+                            let (enabled, version) = {
+                                let buffer = buffer.read(cx);
+                                let settings =
+                                    language_settings(buffer.language(), buffer.file(), cx);
+                                (settings.folding_ranges, buffer.version())
+                            };
+                            let request = project.as_ref().filter(|_| enabled).map(|project| {
+                                project
+                                    .update(cx, |project, cx| project.folding_ranges(&buffer, cx))
+                            });
+                            async move {
+                                let lsp_ranges = match request {
+                                    Some(request) => request.await.log_err().unwrap_or_default(),
+                                    None => Vec::new(),
+                                };
+                                (buffer, version, enabled, lsp_ranges)
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .ok()?;
+            let responses = future::join_all(requests).await;
+
+            let (snapshot, responses) = editor
+                .update(&mut cx, |editor, cx| {
+                    let responses = responses
+                        .into_iter()
+                        .map(|(buffer, version, enabled, lsp_ranges)| {
+                            (buffer.read(cx).snapshot(), version, enabled, lsp_ranges)
+                        })
+                        .collect::<Vec<_>>();
+                    (editor.buffer.read(cx).snapshot(cx), responses)
+                })
+                .ok()?;
+            // Querying the syntax trees of large buffers takes a while.
+            let folding_ranges = cx
+                .background_executor()
+                .spawn(async move {
+                    responses
+                        .into_iter()
+                        .map(|(buffer, version, enabled, lsp_ranges)| {
+                            let ranges = enabled
+                                .then(|| buffer_folding_ranges(&snapshot, &buffer, lsp_ranges))
+                                .flatten();
+                            (buffer.remote_id(), version, ranges)
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await;
+
+            editor
+                .update(&mut cx, |editor, cx| {
+                    editor.display_map.update(cx, |display_map, _| {
+                        for (buffer_id, _, ranges) in &folding_ranges {
+                            display_map.set_buffer_folding_ranges(*buffer_id, ranges.clone());
+                        }
+                    });
+                    editor.folding_ranges.fetched_versions.extend(
+                        folding_ranges
+                            .into_iter()
+                            .map(|(buffer_id, version, _)| (buffer_id, version)),
+                    );
+                    cx.notify();
+                })
+                .ok()
+        }));
+    }
+}
+
+/// The folding ranges of a buffer in each of its excerpts, or `None` to fold it by indentation.
+fn buffer_folding_ranges(
+    snapshot: &MultiBufferSnapshot,
+    buffer: &BufferSnapshot,
+    lsp_ranges: Vec<FoldingRange<text::Anchor>>,
+) -> Option<Arc<[FoldingRange<Anchor>]>> {
+    let buffer_ranges = if lsp_ranges.is_empty() {
+        buffer
+            .folding_ranges()?
+            .into_iter()
+            .map(|folding_range| FoldingRange {
+                range: buffer.anchor_after(folding_range.range.start)
+                    ..buffer.anchor_before(folding_range.range.end),
+                kind: folding_range.kind,
+            })
+            .collect()
+    } else {
+        lsp_ranges
+    };
+    Some(excerpt_folding_ranges(snapshot, buffer, buffer_ranges).into())
+}
+
+/// Maps the folding ranges of a buffer into each of its excerpts, keeping the ranges that are fully
+/// contained in the excerpt.
+fn excerpt_folding_ranges(
+    snapshot: &MultiBufferSnapshot,
+    buffer: &BufferSnapshot,
+    buffer_ranges: Vec<FoldingRange<text::Anchor>>,
+) -> Vec<FoldingRange<Anchor>> {
+    let mut ranges = Vec::new();
+    for (excerpt_id, excerpt_buffer, excerpt_range) in snapshot.excerpts() {
+        if excerpt_buffer.remote_id() != buffer.remote_id() {
+            continue;
+        }
+        let excerpt_range = excerpt_range.context.to_offset(buffer);
+        ranges.extend(buffer_ranges.iter().filter_map(|folding_range| {
+            let range = folding_range.range.to_offset(buffer);
+            if range.start < excerpt_range.start || range.end > excerpt_range.end {
+                return None;
+            }
+            Some(FoldingRange {
+                range: snapshot.anchor_in_excerpt(excerpt_id, folding_range.range.start)?
+                    ..snapshot.anchor_in_excerpt(excerpt_id, folding_range.range.end)?,
+                kind: folding_range.kind,
+            })
+        }));
+    }
+    ranges
+}
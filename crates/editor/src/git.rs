@@ -1,4 +1,5 @@
 pub mod blame;
+mod staging;
 
 use std::ops::Range;
 
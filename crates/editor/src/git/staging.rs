@@ -0,0 +1,224 @@
+use std::ops::Range;
+
+use collections::HashMap;
+use git::diff::{BufferDiff, DiffHunk};
+use gpui::{Model, ViewContext};
+use language::{Buffer, BufferSnapshot, LineEnding, Point};
+use multi_buffer::MultiBufferRow;
+use text::{BufferId, Rope, ToOffset as _};
+use workspace::{notifications::NotificationId, Toast};
+
+use crate::{hunks_for_selections, Editor, StageHunk, UnstageHunk};
+
+impl Editor {
+    /// Writes the selected hunks to the git index, leaving the other changes of their files unstaged.
+    pub fn stage_hunk(&mut self, _: &StageHunk, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+
+        let multi_buffer = self.buffer.read(cx);
+        let snapshot = multi_buffer.snapshot(cx);
+        let mut hunks_by_buffer = HashMap::<BufferId, Vec<DiffHunk<MultiBufferRow>>>::default();
+        for hunk in hunks_for_selections(&snapshot, &self.selections.disjoint_anchors()) {
+            hunks_by_buffer
+                .entry(hunk.buffer_id)
+                .or_default()
+                .push(hunk);
+        }
+
+        let mut new_index_texts = Vec::new();
+        let mut dirty_buffers = Vec::new();
+        for (buffer_id, mut hunks) in hunks_by_buffer {
+            let Some(buffer) = multi_buffer.buffer(buffer_id) else {
+                continue;
+            };
+            // The hunks are staged as they are in the buffer, which has to match the file
+            // so that the index doesn't contain changes that were never saved.
+            if buffer.read(cx).is_dirty() {
+                dirty_buffers.push(buffer);
+                continue;
+            }
+            let Some(index_text) = buffer.read(cx).diff_base() else {
+                continue;
+            };
+            let buffer_snapshot = buffer.read(cx).snapshot();
+
+            hunks.sort_by_key(|hunk| hunk.diff_base_byte_range.start);
+            let mut new_index_text = String::new();
+            let mut index_offset = 0;
+            for hunk in hunks {
+                new_index_text.extend(
+                    index_text.chunks_in_range(index_offset..hunk.diff_base_byte_range.start),
+                );
+                new_index_text.extend(buffer_snapshot.text_for_range(hunk.buffer_range));
+                index_offset = hunk.diff_base_byte_range.end;
+            }
+            new_index_text.extend(index_text.chunks_in_range(index_offset..index_text.len()));
+            new_index_texts.push((buffer, new_index_text));
+        }
+
+        for (buffer, new_index_text) in new_index_texts {
+            project
+                .update(cx, |project, cx| {
+                    project.set_index_text(&buffer, Some(new_index_text), cx)
+                })
+                .detach_and_log_err(cx);
+        }
+
+        if !dirty_buffers.is_empty() {
+            if let Some(workspace) = self.workspace() {
+                workspace.update(cx, |workspace, cx| {
+                    struct StageHunkInDirtyBuffer;
+
+                    workspace.show_toast(
+                        Toast::new(
+                            NotificationId::unique::<StageHunkInDirtyBuffer>(),
+                            "Save the file before staging its hunks",
+                        ),
+                        cx,
+                    )
+                })
+            }
+        }
+    }
+
+    /// Resets the staged changes on the selected lines to their contents at HEAD in the git index.
+    pub fn unstage_hunk(&mut self, _: &UnstageHunk, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+
+        let multi_buffer = self.buffer.read(cx);
+        let mut rows_by_buffer = HashMap::<BufferId, (Model<Buffer>, Vec<Range<u32>>)>::default();
+        for selection in self.selections.all::<Point>(cx) {
+            for (buffer, range, _) in multi_buffer.range_to_buffer_ranges(selection.range(), cx) {
+                let buffer_snapshot = buffer.read(cx);
+                let rows = buffer_snapshot.offset_to_point(range.start).row
+                    ..buffer_snapshot.offset_to_point(range.end).row;
+                rows_by_buffer
+                    .entry(buffer_snapshot.remote_id())
+                    .or_insert_with(|| (buffer.clone(), Vec::new()))
+                    .1
+                    .push(rows);
+            }
+        }
+
+        for (buffer, buffer_rows) in rows_by_buffer.into_values() {
+            let Some(index_text) = buffer.read(cx).diff_base().cloned() else {
+                continue;
+            };
+            let buffer_snapshot = buffer.read(cx).snapshot();
+            let index_rows = buffer_rows
+                .into_iter()
+                .map(|rows| index_rows_for_buffer_rows(&buffer_snapshot, &index_text, rows))
+                .collect::<Vec<_>>();
+            let head_text = project.read(cx).load_head_text(&buffer, cx);
+            let project = project.downgrade();
+            cx.spawn(|_, mut cx| async move {
+                // A file that is not part of HEAD can only be unstaged as a whole.
+                let new_index_text = match head_text.await? {
+                    Some(head_text) => {
+                        let new_index_text = cx
+                            .background_executor()
+                            .spawn(unstage_index_rows(
+                                buffer_snapshot.remote_id(),
+                                index_text.clone(),
+                                head_text,
+                                index_rows,
+                            ))
+                            .await;
+                        if new_index_text == index_text.to_string() {
+                            return anyhow::Ok(());
+                        }
+                        Some(new_index_text)
+                    }
+                    None => None,
+                };
+                project
+                    .update(&mut cx, |project, cx| {
+                        project.set_index_text(&buffer, new_index_text, cx)
+                    })?
+                    .await
+            })
+            .detach_and_log_err(cx);
+        }
+    }
+}
+
+/// Maps an inclusive range of buffer rows to the rows of the buffer's index text, growing it to
+/// cover the index rows replaced by any unstaged hunk that it touches.
+fn index_rows_for_buffer_rows(
+    buffer: &BufferSnapshot,
+    index_text: &Rope,
+    rows: Range<u32>,
+) -> Range<u32> {
+    // The number of rows that the index text is ahead of the buffer after the preceding hunks.
+    let mut row_delta = 0i64;
+    let mut start = None;
+    for hunk in buffer.git_diff_hunks_intersecting_range(text::Anchor::MIN..text::Anchor::MAX) {
+        let index_start = index_text
+            .offset_to_point(hunk.diff_base_byte_range.start)
+            .row;
+        let index_end = index_text
+            .offset_to_point(hunk.diff_base_byte_range.end)
+            .row;
+        if start.is_none() {
+            if rows.start < hunk.associated_range.start {
+                start = Some((rows.start as i64 + row_delta) as u32);
+            } else if rows.start < hunk.associated_range.end {
+                start = Some(index_start);
+            }
+        }
+        if rows.end < hunk.associated_range.start {
+            let end = (rows.end as i64 + row_delta) as u32;
+            return start.unwrap_or(end)..end;
+        } else if rows.end < hunk.associated_range.end {
+            let end = index_end.saturating_sub(1).max(index_start);
+            return start.unwrap_or(index_start)..end;
+        }
+        row_delta = index_end as i64 - hunk.associated_range.end as i64;
+    }
+
+    let end = (rows.end as i64 + row_delta) as u32;
+    start.unwrap_or((rows.start as i64 + row_delta) as u32)..end
+}
+
+/// Replaces the hunks of the index text that differ from HEAD on the given inclusive row ranges
+/// with their HEAD contents.
+async fn unstage_index_rows(
+    buffer_id: BufferId,
+    index_text: Rope,
+    mut head_text: String,
+    index_rows: Vec<Range<u32>>,
+) -> String {
+    LineEnding::normalize(&mut head_text);
+    let head_text = Rope::from(head_text.as_str());
+    let index = text::Buffer::new(0, buffer_id, index_text.to_string()).snapshot();
+    let mut diff = BufferDiff::new();
+    diff.update(&head_text, &index).await;
+
+    let mut new_index_text = String::new();
+    let mut index_offset = 0;
+    for hunk in diff.hunks_intersecting_range(text::Anchor::MIN..text::Anchor::MAX, &index) {
+        // Like with the unstaged hunks, a removal can be picked from the rows next to it.
+        let hunk_rows = &hunk.associated_range;
+        let selected = index_rows.iter().any(|rows| {
+            if hunk_rows.is_empty() {
+                rows.start <= hunk_rows.start && hunk_rows.start <= rows.end + 1
+            } else {
+                hunk_rows.start <= rows.end && rows.start < hunk_rows.end
+            }
+        });
+        if !selected {
+            continue;
+        }
+
+        let start = hunk.buffer_range.start.to_offset(&index);
+        new_index_text.extend(index.text_for_range(index_offset..start));
+        new_index_text.extend(head_text.chunks_in_range(hunk.diff_base_byte_range));
+        index_offset = hunk.buffer_range.end.to_offset(&index);
+    }
+    new_index_text.extend(index.text_for_range(index_offset..index.len()));
+    new_index_text
+}
@@ -9,8 +9,8 @@ use futures::future::try_join_all;
 use git::repository::GitFileStatus;
 use gpui::{
     point, AnyElement, AppContext, AsyncWindowContext, Context, Entity, EntityId, EventEmitter,
-    IntoElement, Model, ParentElement, Pixels, SharedString, Styled, Task, View, ViewContext,
-    VisualContext, WeakView, WindowContext,
+    IntoElement, Model, ParentElement, Pixels, PromptLevel, SharedString, Styled, Task, View,
+    ViewContext, VisualContext, WeakView, WindowContext,
 };
 use language::{
     proto::serialize_anchor as serialize_text_anchor, Bias, Buffer, CharKind, Point, SelectionGoal,
@@ -19,6 +19,7 @@ use multi_buffer::AnchorRangeExt;
 use project::{search::SearchQuery, FormatTrigger, Item as _, Project, ProjectPath};
 use rpc::proto::{self, update_view, PeerId};
 use settings::Settings;
+use sha2::{Digest, Sha256};
 use workspace::item::{ItemSettings, TabContentParams};
 
 use std::{
@@ -792,6 +793,41 @@ impl Item for Editor {
         })
     }
 
+    fn persist_unsaved_changes(&mut self, cx: &mut ViewContext<Self>) -> Option<Task<Result<()>>> {
+        let (_, Some(workspace_id)) = self.workspace.as_ref()? else {
+            return None;
+        };
+        let workspace_id = *workspace_id;
+        let item_id = cx.view().item_id().as_u64() as ItemId;
+        let buffer = self.buffer().read(cx).as_singleton()?;
+        let buffer = buffer.read(cx);
+        let (path, base_text_fingerprint) = match buffer.file() {
+            Some(file) => {
+                let path = file.as_local()?.abs_path(cx);
+                let saved_text = buffer.rope_for_version(buffer.saved_version());
+                (Some(path), Some(text_fingerprint(&saved_text.to_string())))
+            }
+            None if self.project.as_ref()?.read(cx).is_local() => (None, None),
+            None => return None,
+        };
+        let contents = buffer.text();
+        let language = buffer
+            .language()
+            .map(|language| language.name().to_string());
+
+        Some(cx.background_executor().spawn(async move {
+            DB.save_unsaved_contents(
+                item_id,
+                workspace_id,
+                path.unwrap_or_default(),
+                contents,
+                language,
+                base_text_fingerprint,
+            )
+            .await
+        }))
+    }
+
     fn as_searchable(&self, handle: &View<Self>) -> Option<Box<dyn SearchableItemHandle>> {
         Some(Box::new(handle.clone()))
     }
@@ -848,6 +884,7 @@ impl Item for Editor {
 
     fn added_to_workspace(&mut self, workspace: &mut Workspace, cx: &mut ViewContext<Self>) {
         self.workspace = Some((workspace.weak_handle(), workspace.database_id()));
+        self.restore_file_state(cx);
         let Some(workspace_id) = workspace.database_id() else {
             return;
         };
@@ -878,13 +915,24 @@ impl Item for Editor {
 
             cx.subscribe(&buffer, |this, buffer, event, cx| {
                 if let Some((_, Some(workspace_id))) = this.workspace.as_ref() {
-                    if let language::Event::FileHandleChanged = event {
-                        serialize(
-                            buffer,
-                            *workspace_id,
-                            cx.view().item_id().as_u64() as ItemId,
-                            cx,
-                        );
+                    let workspace_id = *workspace_id;
+                    let item_id = cx.view().item_id().as_u64() as ItemId;
+                    match event {
+                        language::Event::FileHandleChanged => {
+                            serialize(buffer, workspace_id, item_id, cx);
+                        }
+                        // Unsaved contents that were restored are kept until they're saved or
+                        // undone, in case Zed doesn't quit cleanly.
+                        language::Event::DirtyChanged if !buffer.read(cx).is_dirty() => {
+                            cx.background_executor()
+                                .spawn(async move {
+                                    DB.clear_unsaved_contents(item_id, workspace_id)
+                                        .await
+                                        .log_err()
+                                })
+                                .detach();
+                        }
+                        _ => {}
                     }
                 }
             })
@@ -937,12 +985,50 @@ impl Item for Editor {
         item_id: ItemId,
         cx: &mut ViewContext<Pane>,
     ) -> Task<Result<View<Self>>> {
+        let serialized_editor = DB
+            .get_serialized_editor(item_id, workspace_id)
+            .and_then(|editor| editor.context("No editor stored for this item"));
+        let (path, contents, language, base_text_fingerprint) = match serialized_editor {
+            Ok(serialized_editor) => serialized_editor,
+            Err(error) => return Task::ready(Err(error)),
+        };
+
+        if path.as_os_str().is_empty() {
+            // Untitled editors are only stored along with their unsaved contents.
+            let Some(contents) = contents.filter(|_| project.read(cx).is_local()) else {
+                return Task::ready(Err(anyhow!("No path stored for this editor")));
+            };
+            let language =
+                language.map(|name| project.read(cx).languages().language_for_name(&name));
+            return cx.spawn(|pane, mut cx| async move {
+                let language = match language {
+                    Some(language) => language.await.log_err(),
+                    None => None,
+                };
+                let buffer = project.update(&mut cx, |project, cx| {
+                    let buffer = project.create_local_buffer("", language, cx);
+                    // Insert the contents as an edit, so that the buffer stays dirty.
+                    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, contents)], None, cx));
+                    buffer
+                })?;
+                let editor = pane.update(&mut cx, |_, cx| {
+                    cx.new_view(|cx| {
+                        let mut editor = Editor::for_buffer(buffer, Some(project), cx);
+
+                        editor.read_scroll_position_from_db(item_id, workspace_id, cx);
+                        editor
+                    })
+                })?;
+                let restored_item_id = editor.item_id().as_u64() as ItemId;
+                DB.move_unsaved_contents(item_id, restored_item_id, workspace_id, None)
+                    .await
+                    .log_err();
+                Ok(editor)
+            });
+        }
+
         let project_item: Result<_> = project.update(cx, |project, cx| {
             // Look up the path with this key associated, create a self with that path
-            let path = DB
-                .get_path(item_id, workspace_id)?
-                .context("No path stored for this editor")?;
-
             let (worktree, path) = project
                 .find_local_worktree(&path, cx)
                 .with_context(|| format!("No worktree for path: {path:?}"))?;
@@ -966,6 +1052,15 @@ impl Item for Editor {
                             let mut editor = Editor::for_buffer(buffer, Some(project), cx);
 
                             editor.read_scroll_position_from_db(item_id, workspace_id, cx);
+                            if let Some(contents) = contents {
+                                editor.restore_unsaved_contents(
+                                    contents,
+                                    base_text_fingerprint,
+                                    item_id,
+                                    workspace_id,
+                                    cx,
+                                );
+                            }
                             editor
                         })
                     })
@@ -975,6 +1070,81 @@ impl Item for Editor {
     }
 }
 
+impl Editor {
+    /// Applies the unsaved contents that were stored on quit to the buffer, in a transaction that
+    /// can be undone. If the file changed on disk since, asks whether to restore them anyway.
+    /// Restored contents stay stored for this editor until they're saved or undone.
+    fn restore_unsaved_contents(
+        &mut self,
+        contents: String,
+        base_text_fingerprint: Option<String>,
+        item_id: ItemId,
+        workspace_id: WorkspaceId,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(buffer) = self.buffer().read(cx).as_singleton() else {
+            return;
+        };
+        let restored_item_id = cx.view().item_id().as_u64() as ItemId;
+        cx.spawn(|_, mut cx| async move {
+            let (saved_text_fingerprint, file_name, diff) =
+                buffer.update(&mut cx, |buffer, cx| {
+                    let saved_text = buffer.rope_for_version(buffer.saved_version());
+                    let file_name = buffer.file().map_or_else(
+                        || "untitled".to_string(),
+                        |file| file.file_name(cx).to_string_lossy().to_string(),
+                    );
+                    (
+                        text_fingerprint(&saved_text.to_string()),
+                        file_name,
+                        buffer.diff(contents, cx),
+                    )
+                })?;
+            let diff = diff.await;
+
+            let mut restore = true;
+            if base_text_fingerprint.as_ref() != Some(&saved_text_fingerprint) {
+                let answer = cx.update(|cx| {
+                    cx.prompt(
+                        PromptLevel::Warning,
+                        &format!(
+                            "{file_name} changed on disk since its unsaved changes were kept."
+                        ),
+                        Some(
+                            "Restoring them replaces the contents from disk, which can be undone.",
+                        ),
+                        &["Restore Unsaved Changes", "Keep Disk Version"],
+                    )
+                })?;
+                restore = answer.await.ok() == Some(0);
+            }
+            if !restore {
+                return DB.clear_unsaved_contents(item_id, workspace_id).await;
+            }
+
+            buffer.update(&mut cx, |buffer, cx| {
+                buffer.finalize_last_transaction();
+                buffer.apply_diff(diff, cx);
+                buffer.finalize_last_transaction();
+            })?;
+            DB.move_unsaved_contents(
+                item_id,
+                restored_item_id,
+                workspace_id,
+                Some(saved_text_fingerprint),
+            )
+            .await
+        })
+        .detach_and_log_err(cx);
+    }
+}
+
+/// Identifies the contents of a file, to tell whether it changed since unsaved changes were made
+/// to it.
+fn text_fingerprint(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
 impl ProjectItem for Editor {
     type Item = Buffer;
 
@@ -10,10 +10,20 @@ define_connection!(
     // editors(
     //   item_id: usize,
     //   workspace_id: usize,
-    //   path: PathBuf,
+    //   path: PathBuf, // empty for untitled editors, which are only stored with their contents
     //   scroll_top_row: usize,
     //   scroll_vertical_offset: f32,
     //   scroll_horizontal_offset: f32,
+    //   contents: Option<String>, // unsaved contents, stored on quit
+    //   language: Option<String>,
+    //   base_text_fingerprint: Option<String>, // of the file contents that `contents` edit
+    // )
+    // file_states(
+    //   path: PathBuf,
+    //   line_fingerprints: Vec<u8>, // a hash of each line of the file, to re-anchor the state
+    //   folds: String, // JSON
+    //   selections: String, // JSON
+    //   updated_at: i64, // milliseconds since the unix epoch
     // )
     pub static ref DB: EditorDb<WorkspaceDb> =
         &[sql! (
@@ -31,13 +41,34 @@ define_connection!(
             ALTER TABLE editors ADD COLUMN scroll_top_row INTEGER NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_horizontal_offset REAL NOT NULL DEFAULT 0;
             ALTER TABLE editors ADD COLUMN scroll_vertical_offset REAL NOT NULL DEFAULT 0;
+        ),
+        sql! (
+            ALTER TABLE editors ADD COLUMN contents TEXT;
+            ALTER TABLE editors ADD COLUMN language TEXT;
+            ALTER TABLE editors ADD COLUMN base_text_fingerprint TEXT;
+        ),
+        sql! (
+            CREATE TABLE file_states(
+                path BLOB NOT NULL PRIMARY KEY,
+                line_fingerprints BLOB NOT NULL,
+                folds TEXT NOT NULL,
+                selections TEXT NOT NULL
+            ) STRICT;
+        ),
+        sql! (
+            ALTER TABLE file_states ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
         )];
 );
 
 impl EditorDb {
+    // Returns the path, and the unsaved contents with their language and base text fingerprint
     query! {
-        pub fn get_path(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<PathBuf>> {
-            SELECT path FROM editors
+        pub fn get_serialized_editor(
+            item_id: ItemId,
+            workspace_id: WorkspaceId
+        ) -> Result<Option<(PathBuf, Option<String>, Option<String>, Option<String>)>> {
+            SELECT path, contents, language, base_text_fingerprint
+            FROM editors
             WHERE item_id = ? AND workspace_id = ?
         }
     }
@@ -55,6 +86,60 @@ impl EditorDb {
         }
     }
 
+    query! {
+        pub async fn save_unsaved_contents(
+            item_id: ItemId,
+            workspace_id: WorkspaceId,
+            path: PathBuf,
+            contents: String,
+            language: Option<String>,
+            base_text_fingerprint: Option<String>
+        ) -> Result<()> {
+            INSERT INTO editors
+                (item_id, workspace_id, path, contents, language, base_text_fingerprint)
+            VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT DO UPDATE SET
+                path = ?3,
+                contents = ?4,
+                language = ?5,
+                base_text_fingerprint = ?6
+        }
+    }
+
+    // Moves the unsaved contents stored for an editor to another one of the workspace, with the
+    // fingerprint of the file contents that they now edit
+    query! {
+        pub async fn move_unsaved_contents(
+            from_item_id: ItemId,
+            to_item_id: ItemId,
+            workspace_id: WorkspaceId,
+            base_text_fingerprint: Option<String>
+        ) -> Result<()> {
+            INSERT INTO editors
+                (item_id, workspace_id, path, contents, language, base_text_fingerprint)
+            SELECT ?2, workspace_id, path, contents, language, ?4
+            FROM editors
+            WHERE item_id = ?1 AND workspace_id = ?3 AND contents IS NOT NULL
+            ON CONFLICT DO UPDATE SET
+                contents = excluded.contents,
+                language = excluded.language,
+                base_text_fingerprint = excluded.base_text_fingerprint;
+
+            UPDATE editors
+            SET contents = NULL, language = NULL, base_text_fingerprint = NULL
+            WHERE item_id = ?1 AND workspace_id = ?3
+        }
+    }
+
+    query! {
+        pub async fn clear_unsaved_contents(item_id: ItemId, workspace_id: WorkspaceId) -> Result<()> {
+            UPDATE editors
+            SET contents = NULL, language = NULL, base_text_fingerprint = NULL
+            WHERE item_id = ? AND workspace_id = ?
+        }
+    }
+
     // Returns the scroll top row, and offset
     query! {
         pub fn get_scroll_position(item_id: ItemId, workspace_id: WorkspaceId) -> Result<Option<(u32, f32, f32)>> {
@@ -80,4 +165,66 @@ impl EditorDb {
             WHERE item_id = ?1 AND workspace_id = ?2
         }
     }
+
+    // Returns the line fingerprints, folds and selections stored for the file
+    query! {
+        pub fn get_file_state(path: PathBuf) -> Result<Option<(Vec<u8>, String, String)>> {
+            SELECT line_fingerprints, folds, selections
+            FROM file_states
+            WHERE path = ?
+        }
+    }
+
+    query! {
+        pub async fn save_file_state(
+            path: PathBuf,
+            line_fingerprints: Vec<u8>,
+            folds: String,
+            selections: String,
+            updated_at: i64
+        ) -> Result<()> {
+            INSERT INTO file_states
+                (path, line_fingerprints, folds, selections, updated_at)
+            VALUES
+                (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT DO UPDATE SET
+                line_fingerprints = ?2,
+                folds = ?3,
+                selections = ?4,
+                updated_at = ?5
+        }
+    }
+
+    // Updates the folds and selections stored for a file whose contents didn't change, keeping
+    // its line fingerprints
+    query! {
+        pub async fn save_file_state_ranges(
+            path: PathBuf,
+            folds: String,
+            selections: String,
+            updated_at: i64
+        ) -> Result<()> {
+            UPDATE file_states
+            SET folds = ?2, selections = ?3, updated_at = ?4
+            WHERE path = ?1
+        }
+    }
+
+    query! {
+        pub async fn delete_file_state(path: PathBuf) -> Result<()> {
+            DELETE FROM file_states WHERE path = ?
+        }
+    }
+
+    query! {
+        pub async fn delete_file_states_before(updated_at: i64) -> Result<()> {
+            DELETE FROM file_states WHERE updated_at < ?
+        }
+    }
+
+    query! {
+        pub fn file_state_paths() -> Result<Vec<PathBuf>> {
+            SELECT path FROM file_states
+        }
+    }
 }
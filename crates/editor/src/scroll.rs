@@ -137,6 +137,7 @@ pub struct ScrollManager {
     show_scrollbars: bool,
     hide_scrollbar_task: Option<Task<()>>,
     dragging_scrollbar: bool,
+    dragging_minimap: bool,
     visible_line_count: Option<f32>,
     forbid_vertical_scroll: bool,
 }
@@ -151,6 +152,7 @@ impl ScrollManager {
             show_scrollbars: true,
             hide_scrollbar_task: None,
             dragging_scrollbar: false,
+            dragging_minimap: false,
             last_autoscroll: None,
             visible_line_count: None,
             forbid_vertical_scroll: false,
@@ -322,6 +324,17 @@ impl ScrollManager {
         }
     }
 
+    pub fn is_dragging_minimap(&self) -> bool {
+        self.dragging_minimap
+    }
+
+    pub fn set_is_dragging_minimap(&mut self, dragging: bool, cx: &mut ViewContext<Editor>) {
+        if dragging != self.dragging_minimap {
+            self.dragging_minimap = dragging;
+            cx.notify();
+        }
+    }
+
     pub fn clamp_scroll_left(&mut self, max: f32) -> bool {
         if max < self.anchor.offset.x {
             self.anchor.offset.x = max;
@@ -366,7 +379,10 @@ impl Editor {
             cx.spawn(|editor, mut cx| async move {
                 editor
                     .update(&mut cx, |editor, cx| {
-                        editor.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx)
+                        editor.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+                        editor.refresh_code_lens(cx);
+                        editor.resolve_visible_code_lenses(cx);
+                        editor.refresh_folding_ranges(cx);
                     })
                     .ok()
             })
@@ -430,6 +446,9 @@ impl Editor {
         );
 
         self.refresh_inlay_hints(InlayHintRefreshReason::NewLinesShown, cx);
+        self.refresh_code_lens(cx);
+        self.resolve_visible_code_lenses(cx);
+        self.refresh_folding_ranges(cx);
     }
 
     pub fn scroll_position(&self, cx: &mut ViewContext<Self>) -> gpui::Point<f32> {
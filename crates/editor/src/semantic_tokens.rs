@@ -0,0 +1,170 @@
+use std::{ops::Range, time::Duration};
+
+use collections::HashMap;
+use futures::future;
+use gpui::{Task, ViewContext};
+use language::{language_settings::language_settings, HighlightId};
+use multi_buffer::Anchor;
+use project::{SemanticToken, SemanticTokens};
+use text::BufferId;
+use theme::ActiveTheme;
+
+use crate::{Editor, EditorMode};
+
+const SEMANTIC_TOKENS_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Semantic tokens of the buffers shown in an editor, for the languages that opted into them.
+#[derive(Default)]
+pub(super) struct SemanticTokensState {
+    buffers: HashMap<BufferId, SemanticTokens>,
+    refresh_task: Option<Task<Option<()>>>,
+}
+
+impl Editor {
+    /// Requests new semantic tokens for all buffers with the `semantic_tokens` setting enabled.
+    pub(super) fn refresh_semantic_tokens(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(project) = self.project.clone() else {
+            return;
+        };
+        if self.mode != EditorMode::Full {
+            return;
+        }
+
+        let buffers = self
+            .buffer
+            .read(cx)
+            .all_buffers()
+            .into_iter()
+            .filter(|buffer| {
+                let buffer = buffer.read(cx);
+                language_settings(buffer.language(), buffer.file(), cx).semantic_tokens
+            })
+            .collect::<Vec<_>>();
+        let had_tokens = !self.semantic_tokens.buffers.is_empty();
+        self.semantic_tokens.buffers.retain(|buffer_id, _| {
+            buffers
+                .iter()
+                .any(|buffer| buffer.read(cx).remote_id() == *buffer_id)
+        });
+        if buffers.is_empty() {
+            self.semantic_tokens.refresh_task = None;
+            if had_tokens {
+                self.apply_semantic_tokens(cx);
+            }
+            return;
+        }
+
+        self.semantic_tokens.refresh_task = Some(cx.spawn(|editor, mut cx| async move {
+            cx.background_executor()
+                .timer(SEMANTIC_TOKENS_DEBOUNCE)
+                .await;
+
+            let requests = editor
+                .update(&mut cx, |_, cx| {
+                    buffers
+                        .into_iter()
+                        .map(|buffer| {
+                            let buffer_id = buffer.read(cx).remote_id();
+                            let request = project
+                                .update(cx, |project, cx| project.semantic_tokens(&buffer, cx));
+                            async move { (buffer_id, request.await) }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .ok()?;
+            let responses = future::join_all(requests).await;
+
+            editor
+                .update(&mut cx, |editor, cx| {
+                    for (buffer_id, response) in responses {
+                        match response {
+                            Ok(tokens) => {
+                                editor.semantic_tokens.buffers.insert(buffer_id, tokens);
+                            }
+                            // Keep showing the old tokens.
+                            Err(error) => log::error!("failed to fetch semantic tokens: {error:#}"),
+                        }
+                    }
+                    editor.apply_semantic_tokens(cx);
+                })
+                .ok()
+        }));
+    }
+
+    /// Replaces the semantic token highlights of the display map with the current tokens,
+    /// styled through the syntax theme.
+    pub(super) fn apply_semantic_tokens(&mut self, cx: &mut ViewContext<Self>) {
+        let syntax_theme = cx.theme().syntax().clone();
+        let snapshot = self.buffer.read(cx).snapshot(cx);
+        let mut highlight_ids = HashMap::<String, Option<HighlightId>>::default();
+        let mut highlights = HashMap::<HighlightId, Vec<Range<Anchor>>>::default();
+
+        // Excerpts and the tokens within them are both ordered, so the ranges
+        // of every highlight come out sorted.
+        for (excerpt_id, buffer, excerpt_range) in snapshot.excerpts() {
+            let Some(tokens) = self.semantic_tokens.buffers.get(&buffer.remote_id()) else {
+                continue;
+            };
+            let context = excerpt_range.context;
+            for token in &tokens.tokens {
+                if token.range.end.cmp(&context.start, buffer).is_le() {
+                    continue;
+                }
+                if token.range.start.cmp(&context.end, buffer).is_ge() {
+                    break;
+                }
+                let Some(capture_name) = capture_name(tokens, token) else {
+                    continue;
+                };
+                let highlight_id =
+                    *highlight_ids
+                        .entry(capture_name)
+                        .or_insert_with_key(|capture_name| {
+                            HighlightId::for_capture_name(capture_name, &syntax_theme)
+                        });
+                let Some(highlight_id) = highlight_id else {
+                    continue;
+                };
+                let (Some(start), Some(end)) = (
+                    snapshot.anchor_in_excerpt(excerpt_id, token.range.start),
+                    snapshot.anchor_in_excerpt(excerpt_id, token.range.end),
+                ) else {
+                    continue;
+                };
+                highlights.entry(highlight_id).or_default().push(start..end);
+            }
+        }
+
+        self.display_map.update(cx, |display_map, _| {
+            display_map.set_semantic_token_highlights(highlights.into_iter().filter_map(
+                |(highlight_id, ranges)| {
+                    Some((highlight_id, highlight_id.style(&syntax_theme)?, ranges))
+                },
+            ))
+        });
+        cx.notify();
+    }
+}
+
+/// Translates a semantic token into a capture name that is matched against the syntax theme
+/// like the captures of the highlight queries, with the token's modifiers appended.
+/// For example, a mutable variable becomes `variable.mutable`.
+fn capture_name(tokens: &SemanticTokens, token: &SemanticToken) -> Option<String> {
+    let mut capture_name = match tokens.token_type(token)? {
+        "class" | "enum" | "interface" | "struct" | "type" | "typeParameter" => "type",
+        "enumMember" => "variant",
+        "parameter" => "variable.parameter",
+        "method" => "function.method",
+        "macro" => "function.macro",
+        "modifier" => "keyword",
+        "regexp" => "string.regex",
+        "decorator" => "attribute",
+        token_type => token_type,
+    }
+    .to_string();
+    for modifier in tokens.token_modifiers(token) {
+        capture_name.push('.');
+        capture_name.push_str(modifier);
+    }
+    Some(capture_name)
+}
@@ -0,0 +1,263 @@
+mod file_history_settings;
+mod panel;
+mod persistence;
+mod snapshot_diff;
+
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use gpui::{
+    actions, AppContext, Context, EntityId, EventEmitter, Global, Model, ModelContext,
+    Subscription, Task, ViewContext,
+};
+use language::{Buffer, BufferEvent};
+use persistence::FILE_HISTORY_DB;
+use settings::Settings;
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use time::OffsetDateTime;
+use util::ResultExt;
+use workspace::Workspace;
+
+pub use file_history_settings::*;
+pub use panel::*;
+pub use snapshot_diff::*;
+
+actions!(file_history, [ToggleFocus, RestoreSnapshot]);
+
+pub fn init(cx: &mut AppContext) {
+    FileHistorySettings::register(cx);
+
+    let file_history = cx.new_model(|_| FileHistory::default());
+    cx.set_global(GlobalFileHistory(file_history.clone()));
+
+    let oldest = oldest_snapshot_time(SystemTime::now(), FileHistorySettings::get_global(cx));
+    cx.background_executor()
+        .spawn(async move {
+            FILE_HISTORY_DB
+                .delete_snapshots_before(timestamp_millis(oldest))
+                .await
+                .log_err();
+        })
+        .detach();
+
+    cx.observe_new_views(
+        move |workspace: &mut Workspace, cx: &mut ViewContext<Workspace>| {
+            workspace.register_action(|workspace, _: &ToggleFocus, cx| {
+                workspace.toggle_panel_focus::<FileHistoryPanel>(cx);
+            });
+
+            // Buffers are saved by formatting, refactorings and project-wide replacements too,
+            // whether or not they're open in an editor.
+            let project = workspace.project().clone();
+            file_history.update(cx, |file_history, cx| {
+                for buffer in project.read(cx).opened_buffers() {
+                    file_history.track(&buffer, cx);
+                }
+            });
+
+            let file_history = file_history.clone();
+            cx.subscribe(&project, move |_, _, event, cx| {
+                if let project::Event::BufferOpened(buffer) = event {
+                    file_history.update(cx, |file_history, cx| file_history.track(buffer, cx));
+                }
+            })
+            .detach();
+        },
+    )
+    .detach();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotKind {
+    /// The file was saved.
+    Save,
+    /// The file changed on disk, and the snapshot has its content from before it was reloaded.
+    ExternalChange,
+}
+
+impl SnapshotKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotKind::Save => "save",
+            SnapshotKind::ExternalChange => "external_change",
+        }
+    }
+
+    fn from_str(kind: &str) -> Option<Self> {
+        match kind {
+            "save" => Some(SnapshotKind::Save),
+            "external_change" => Some(SnapshotKind::ExternalChange),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SnapshotKind::Save => "Saved",
+            SnapshotKind::ExternalChange => "Changed on Disk",
+        }
+    }
+}
+
+/// A recorded content of a file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub id: i64,
+    pub kind: SnapshotKind,
+    pub created_at: OffsetDateTime,
+}
+
+pub enum Event {
+    SnapshotRecorded { abs_path: PathBuf },
+}
+
+struct GlobalFileHistory(Model<FileHistory>);
+
+impl Global for GlobalFileHistory {}
+
+/// Records the contents of the local files of the open buffers, so that they can be compared
+/// with and restored from later, whether or not they're committed.
+#[derive(Default)]
+pub struct FileHistory {
+    buffers: HashMap<EntityId, Subscription>,
+}
+
+impl EventEmitter<Event> for FileHistory {}
+
+impl FileHistory {
+    pub fn global(cx: &AppContext) -> Option<Model<Self>> {
+        cx.try_global::<GlobalFileHistory>()
+            .map(|file_history| file_history.0.clone())
+    }
+
+    fn track(&mut self, buffer: &Model<Buffer>, cx: &mut ModelContext<Self>) {
+        let buffer_id = buffer.entity_id();
+        if self.buffers.contains_key(&buffer_id) {
+            return;
+        }
+
+        let subscription = cx.subscribe(buffer, |this, buffer, event, cx| match event {
+            BufferEvent::Saved => this.record(&buffer, SnapshotKind::Save, cx),
+            // The buffer is reloaded asynchronously, so it still has the previous content.
+            BufferEvent::FileHandleChanged => {
+                let reloading = {
+                    let buffer = buffer.read(cx);
+                    !buffer.is_dirty() && buffer.file().map_or(false, |file| !file.is_deleted())
+                };
+                if reloading {
+                    this.record(&buffer, SnapshotKind::ExternalChange, cx);
+                }
+            }
+            _ => {}
+        });
+        self.buffers.insert(buffer_id, subscription);
+        cx.observe_release(buffer, move |this, _, _| {
+            this.buffers.remove(&buffer_id);
+        })
+        .detach();
+    }
+
+    fn record(&mut self, buffer: &Model<Buffer>, kind: SnapshotKind, cx: &mut ModelContext<Self>) {
+        let settings = FileHistorySettings::get_global(cx);
+        if !settings.enabled {
+            return;
+        }
+        let buffer = buffer.read(cx);
+        let Some(abs_path) = buffer
+            .file()
+            .and_then(|file| file.as_local())
+            .map(|file| file.abs_path(cx))
+        else {
+            return;
+        };
+        if buffer.len() > settings.max_file_size {
+            return;
+        }
+
+        let content = buffer.text();
+        let max_snapshots = settings.max_snapshots_per_file;
+        let now = SystemTime::now();
+        let oldest = oldest_snapshot_time(now, settings);
+        cx.spawn(|this, mut cx| async move {
+            let recorded = FILE_HISTORY_DB
+                .record_snapshot(
+                    abs_path.clone(),
+                    kind.as_str().to_string(),
+                    content,
+                    timestamp_millis(now),
+                    max_snapshots,
+                    timestamp_millis(oldest),
+                )
+                .await?;
+            if recorded {
+                this.update(&mut cx, |_, cx| {
+                    cx.emit(Event::SnapshotRecorded { abs_path });
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// The snapshots of a file, newest first.
+    pub fn snapshots(abs_path: PathBuf, cx: &AppContext) -> Task<Result<Vec<Snapshot>>> {
+        cx.background_executor().spawn(async move {
+            Ok(FILE_HISTORY_DB
+                .snapshots(abs_path)?
+                .into_iter()
+                .filter_map(|(id, kind, created_at)| {
+                    Some(Snapshot {
+                        id,
+                        kind: SnapshotKind::from_str(&kind)?,
+                        created_at: OffsetDateTime::from_unix_timestamp_nanos(
+                            created_at as i128 * 1_000_000,
+                        )
+                        .ok()?,
+                    })
+                })
+                .collect())
+        })
+    }
+
+    pub fn snapshot_content(snapshot_id: i64, cx: &AppContext) -> Task<Result<String>> {
+        cx.background_executor().spawn(async move {
+            FILE_HISTORY_DB
+                .snapshot_content(snapshot_id)?
+                .ok_or_else(|| anyhow!("snapshot {snapshot_id} was deleted"))
+        })
+    }
+}
+
+/// Replaces the content of the buffer with the content of a snapshot, in a transaction that can be
+/// undone. The buffer isn't saved.
+pub fn restore_snapshot(
+    buffer: Model<Buffer>,
+    snapshot_id: i64,
+    cx: &mut AppContext,
+) -> Task<Result<()>> {
+    let content = FileHistory::snapshot_content(snapshot_id, cx);
+    cx.spawn(|mut cx| async move {
+        let content = content.await?;
+        let diff = buffer
+            .update(&mut cx, |buffer, cx| buffer.diff(content, cx))?
+            .await;
+        buffer.update(&mut cx, |buffer, cx| {
+            buffer.finalize_last_transaction();
+            buffer.apply_diff(diff, cx);
+            buffer.finalize_last_transaction();
+        })
+    })
+}
+
+/// The time before which snapshots are deleted.
+fn oldest_snapshot_time(now: SystemTime, settings: &FileHistorySettings) -> SystemTime {
+    let max_age = Duration::from_secs(settings.max_age_days.saturating_mul(24 * 60 * 60));
+    now.checked_sub(max_age).unwrap_or(UNIX_EPOCH)
+}
+
+fn timestamp_millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
@@ -0,0 +1,64 @@
+use gpui::Pixels;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileHistoryDockPosition {
+    Left,
+    Right,
+    Bottom,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct FileHistorySettings {
+    pub enabled: bool,
+    pub max_snapshots_per_file: usize,
+    pub max_age_days: u64,
+    pub max_file_size: usize,
+    pub dock: FileHistoryDockPosition,
+    pub default_width: Pixels,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct FileHistorySettingsContent {
+    /// Whether to record the contents of local files when they're saved, and before they're
+    /// reloaded after changing on disk.
+    ///
+    /// Default: true
+    pub enabled: Option<bool>,
+    /// The number of snapshots to keep for each file. Older snapshots are deleted first.
+    ///
+    /// Default: 50
+    pub max_snapshots_per_file: Option<usize>,
+    /// The number of days to keep snapshots for.
+    ///
+    /// Default: 30
+    pub max_age_days: Option<u64>,
+    /// The size in bytes above which files are not recorded.
+    ///
+    /// Default: 1048576
+    pub max_file_size: Option<usize>,
+    /// Where to dock the file history panel.
+    ///
+    /// Default: right
+    pub dock: Option<FileHistoryDockPosition>,
+    /// Default width of the file history panel, in pixels.
+    ///
+    /// Default: 300
+    pub default_width: Option<f32>,
+}
+
+impl Settings for FileHistorySettings {
+    const KEY: Option<&'static str> = Some("file_history");
+
+    type FileContent = FileHistorySettingsContent;
+
+    fn load(
+        sources: SettingsSources<Self::FileContent>,
+        _: &mut gpui::AppContext,
+    ) -> anyhow::Result<Self> {
+        sources.json_merge()
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{
+    restore_snapshot, Event, FileHistory, FileHistoryDockPosition, FileHistorySettings, Snapshot,
+    SnapshotDiffView, SnapshotKind, ToggleFocus,
+};
+use editor::Editor;
+use gpui::{
+    Action, AppContext, AsyncWindowContext, EventEmitter, FocusHandle, FocusableView, Model,
+    Subscription, Task, View, WeakView,
+};
+use language::Buffer;
+use project::Fs;
+use settings::Settings as _;
+use std::{path::PathBuf, sync::Arc};
+use ui::{prelude::*, ListItem, Tooltip};
+use util::ResultExt as _;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+/// The file of the active editor, whose history the panel shows.
+struct ActiveFile {
+    buffer: Model<Buffer>,
+    abs_path: PathBuf,
+}
+
+/// Lists the snapshots of the file in the active editor, newest first.
+pub struct FileHistoryPanel {
+    fs: Arc<dyn Fs>,
+    workspace: WeakView<Workspace>,
+    focus_handle: FocusHandle,
+    size: Option<Pixels>,
+    active_file: Option<ActiveFile>,
+    snapshots: Vec<Snapshot>,
+    load_task: Task<Option<()>>,
+    _file_history_subscription: Option<Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl FileHistoryPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        workspace.update(&mut cx, |workspace, cx| Self::new(workspace, cx))
+    }
+
+    pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let fs = workspace.app_state().fs.clone();
+        let active_buffer = active_buffer(workspace, cx);
+        let workspace_view = cx.view().clone();
+        cx.new_view(|cx| {
+            let subscriptions =
+                vec![cx.subscribe(&workspace_view, |this, workspace, event, cx| {
+                    if let workspace::Event::ActiveItemChanged = event {
+                        let active_buffer = active_buffer(workspace.read(cx), cx);
+                        this.set_active_buffer(active_buffer, cx);
+                    }
+                })];
+            let file_history_subscription = FileHistory::global(cx).map(|file_history| {
+                cx.subscribe(&file_history, |this, _, event, cx| match event {
+                    Event::SnapshotRecorded { abs_path } => {
+                        if this
+                            .active_file
+                            .as_ref()
+                            .map_or(false, |file| &file.abs_path == abs_path)
+                        {
+                            this.load_snapshots(cx);
+                        }
+                    }
+                })
+            });
+            let mut this = Self {
+                fs,
+                workspace: workspace_view.downgrade(),
+                focus_handle: cx.focus_handle(),
+                size: None,
+                active_file: None,
+                snapshots: Vec::new(),
+                load_task: Task::ready(None),
+                _file_history_subscription: file_history_subscription,
+                _subscriptions: subscriptions,
+            };
+            this.set_active_buffer(active_buffer, cx);
+            this
+        })
+    }
+
+    fn set_active_buffer(&mut self, buffer: Option<Model<Buffer>>, cx: &mut ViewContext<Self>) {
+        // Keep showing the history of the last file when another kind of item is activated.
+        let Some(buffer) = buffer else {
+            return;
+        };
+        let Some(abs_path) = buffer
+            .read(cx)
+            .file()
+            .and_then(|file| file.as_local())
+            .map(|file| file.abs_path(cx))
+        else {
+            return;
+        };
+        if self
+            .active_file
+            .as_ref()
+            .map_or(false, |file| file.abs_path == abs_path)
+        {
+            return;
+        }
+
+        self.active_file = Some(ActiveFile { buffer, abs_path });
+        self.snapshots.clear();
+        self.load_snapshots(cx);
+        cx.notify();
+    }
+
+    fn load_snapshots(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(abs_path) = self.active_file.as_ref().map(|file| file.abs_path.clone()) else {
+            return;
+        };
+        let snapshots = FileHistory::snapshots(abs_path.clone(), cx);
+        self.load_task = cx.spawn(|this, mut cx| async move {
+            let snapshots = snapshots.await.log_err()?;
+            this.update(&mut cx, |this, cx| {
+                if this
+                    .active_file
+                    .as_ref()
+                    .map_or(false, |file| file.abs_path == abs_path)
+                {
+                    this.snapshots = snapshots;
+                    cx.notify();
+                }
+            })
+            .ok()
+        });
+    }
+
+    fn open_snapshot(&mut self, snapshot: Snapshot, cx: &mut ViewContext<Self>) {
+        let Some(buffer) = self.active_file.as_ref().map(|file| file.buffer.clone()) else {
+            return;
+        };
+        self.workspace
+            .update(cx, |workspace, cx| {
+                SnapshotDiffView::open(workspace, buffer, snapshot, cx)
+            })
+            .log_err();
+    }
+
+    fn restore_snapshot(&mut self, snapshot: &Snapshot, cx: &mut ViewContext<Self>) {
+        let Some(buffer) = self.active_file.as_ref().map(|file| file.buffer.clone()) else {
+            return;
+        };
+        restore_snapshot(buffer, snapshot.id, cx).detach_and_log_err(cx);
+    }
+
+    fn render_snapshot(
+        &self,
+        ix: usize,
+        snapshot: &Snapshot,
+        cx: &mut ViewContext<Self>,
+    ) -> AnyElement {
+        let icon = match snapshot.kind {
+            SnapshotKind::Save => IconName::Save,
+            SnapshotKind::ExternalChange => IconName::FileGeneric,
+        };
+        let time = time_format::format_localized_timestamp(
+            snapshot.created_at,
+            time::OffsetDateTime::now_utc(),
+            cx.local_timezone(),
+            time_format::TimestampFormat::Relative,
+        );
+        let open_snapshot = snapshot.clone();
+        let restored_snapshot = snapshot.clone();
+
+        ListItem::new(("snapshot", ix))
+            .start_slot(Icon::new(icon).size(IconSize::Small).color(Color::Muted))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(snapshot.kind.label()).size(LabelSize::Small))
+                    .child(Label::new(time).size(LabelSize::Small).color(Color::Muted)),
+            )
+            .end_slot(
+                IconButton::new(("restore-snapshot", ix), IconName::RotateCcw)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::text("Restore Snapshot", cx))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.restore_snapshot(&restored_snapshot, cx)
+                    })),
+            )
+            .on_click(cx.listener(move |this, _, cx| this.open_snapshot(open_snapshot.clone(), cx)))
+            .into_any_element()
+    }
+}
+
+fn active_buffer(workspace: &Workspace, cx: &AppContext) -> Option<Model<Buffer>> {
+    workspace
+        .active_item(cx)?
+        .act_as::<Editor>(cx)?
+        .read(cx)
+        .buffer()
+        .read(cx)
+        .as_singleton()
+}
+
+impl EventEmitter<PanelEvent> for FileHistoryPanel {}
+
+impl FocusableView for FileHistoryPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for FileHistoryPanel {
+    fn persistent_name() -> &'static str {
+        "File History Panel"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match FileHistorySettings::get_global(cx).dock {
+            FileHistoryDockPosition::Left => DockPosition::Left,
+            FileHistoryDockPosition::Right => DockPosition::Right,
+            FileHistoryDockPosition::Bottom => DockPosition::Bottom,
+        }
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<FileHistorySettings>(
+            self.fs.clone(),
+            cx,
+            move |settings| {
+                let dock = match position {
+                    DockPosition::Left => FileHistoryDockPosition::Left,
+                    DockPosition::Right => FileHistoryDockPosition::Right,
+                    DockPosition::Bottom => FileHistoryDockPosition::Bottom,
+                };
+                settings.dock = Some(dock);
+            },
+        );
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.size.unwrap_or_else(|| match self.position(cx) {
+            DockPosition::Left | DockPosition::Right => {
+                FileHistorySettings::get_global(cx).default_width
+            }
+            DockPosition::Bottom => px(240.),
+        })
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.size = size;
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        FileHistorySettings::get_global(cx)
+            .enabled
+            .then_some(IconName::HistoryRerun)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("File History")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
+
+impl Render for FileHistoryPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let snapshots = self
+            .snapshots
+            .iter()
+            .enumerate()
+            .map(|(ix, snapshot)| self.render_snapshot(ix, snapshot, cx))
+            .collect::<Vec<_>>();
+        let message = if self.active_file.is_none() {
+            Some("Open a file to see its history.")
+        } else if snapshots.is_empty() {
+            Some(
+                "No history yet. Snapshots are recorded when the file is saved or changes on disk.",
+            )
+        } else {
+            None
+        };
+        let file_name = self.active_file.as_ref().and_then(|file| {
+            file.abs_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        });
+
+        v_flex()
+            .key_context("FileHistoryPanel")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .children(file_name.map(|file_name| {
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(
+                        Label::new(file_name)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+            }))
+            .child(
+                v_flex()
+                    .id("snapshots")
+                    .p_1()
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .children(snapshots)
+                    .children(message.map(|message| {
+                        Label::new(message)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    })),
+            )
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use db::sqlez_macros::sql;
+use db::{define_connection, query};
+
+define_connection!(
+    // Current schema shape using pseudo-rust syntax:
+    // file_history_snapshots(
+    //   snapshot_id: i64,
+    //   abs_path: PathBuf,
+    //   kind: String,
+    //   content: String,
+    //   created_at: i64, // milliseconds since the unix epoch
+    // )
+    pub static ref FILE_HISTORY_DB: FileHistoryDb<()> =
+        &[sql!(
+            CREATE TABLE file_history_snapshots(
+                snapshot_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                abs_path BLOB NOT NULL,
+                kind TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            ) STRICT;
+            CREATE INDEX file_history_snapshots_by_path
+                ON file_history_snapshots(abs_path, created_at);
+        )];
+);
+
+impl FileHistoryDb {
+    // Returns the id, kind and creation time of the snapshots of a file, newest first.
+    query! {
+        pub fn snapshots(abs_path: PathBuf) -> Result<Vec<(i64, String, i64)>> {
+            SELECT snapshot_id, kind, created_at
+            FROM file_history_snapshots
+            WHERE abs_path = ?
+            ORDER BY created_at DESC, snapshot_id DESC
+        }
+    }
+
+    query! {
+        pub fn snapshot_content(snapshot_id: i64) -> Result<Option<String>> {
+            SELECT content
+            FROM file_history_snapshots
+            WHERE snapshot_id = ?
+        }
+    }
+
+    /// Records the content of a file, unless it's the same as in its latest snapshot, and deletes
+    /// the snapshots that the retention limits don't allow anymore. Returns whether a snapshot was
+    /// recorded.
+    pub async fn record_snapshot(
+        &self,
+        abs_path: PathBuf,
+        kind: String,
+        content: String,
+        created_at: i64,
+        max_snapshots: usize,
+        oldest_created_at: i64,
+    ) -> Result<bool> {
+        self.write(move |conn| {
+            conn.with_savepoint("record_snapshot", || {
+                let latest_content = conn.select_row_bound::<PathBuf, String>(sql!(
+                    SELECT content
+                    FROM file_history_snapshots
+                    WHERE abs_path = ?
+                    ORDER BY created_at DESC, snapshot_id DESC
+                    LIMIT 1
+                ))?(abs_path.clone())?;
+                if latest_content.as_ref() == Some(&content) {
+                    return Ok(false);
+                }
+
+                conn.exec_bound(sql!(
+                    INSERT INTO file_history_snapshots(abs_path, kind, content, created_at)
+                    VALUES (?, ?, ?, ?)
+                ))?((abs_path.clone(), kind, content, created_at))?;
+                conn.exec_bound(sql!(
+                    DELETE FROM file_history_snapshots
+                    WHERE abs_path = ?1 AND snapshot_id NOT IN (
+                        SELECT snapshot_id
+                        FROM file_history_snapshots
+                        WHERE abs_path = ?1
+                        ORDER BY created_at DESC, snapshot_id DESC
+                        LIMIT ?2
+                    )
+                ))?((abs_path, max_snapshots))?;
+                conn.exec_bound(sql!(
+                    DELETE FROM file_history_snapshots WHERE created_at < ?
+                ))?(oldest_created_at)?;
+                Ok(true)
+            })
+        })
+        .await
+    }
+
+    query! {
+        pub async fn delete_snapshots_before(created_at: i64) -> Result<()> {
+            DELETE FROM file_history_snapshots WHERE created_at < ?
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[gpui::test]
+    async fn test_record_snapshot() {
+        let db = FileHistoryDb(db::open_test_db("test_record_snapshot").await);
+        let path = PathBuf::from("/project/main.rs");
+        let record = |content: &str, created_at: i64| {
+            db.record_snapshot(
+                path.clone(),
+                "save".into(),
+                content.into(),
+                created_at,
+                2,
+                0,
+            )
+        };
+
+        assert!(record("one", 1).await.unwrap());
+        // Saving the same content again doesn't record anything.
+        assert!(!record("one", 2).await.unwrap());
+        assert!(record("two", 3).await.unwrap());
+        assert!(record("three", 4).await.unwrap());
+
+        // Only the newest snapshots are kept.
+        let snapshots = db.snapshots(path.clone()).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        let contents = snapshots
+            .iter()
+            .map(|(id, _, _)| db.snapshot_content(*id).unwrap().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(contents, ["three", "two"]);
+
+        db.delete_snapshots_before(4).await.unwrap();
+        assert_eq!(db.snapshots(path).unwrap().len(), 1);
+    }
+}
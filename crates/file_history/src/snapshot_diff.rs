@@ -0,0 +1,287 @@
+use crate::{restore_snapshot, FileHistory, RestoreSnapshot, Snapshot};
+use editor::Editor;
+use gpui::{
+    AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView, Hsla, Model, Subscription,
+    Task, View,
+};
+use language::{Buffer, BufferEvent, Point};
+use similar::{ChangeTag, TextDiff};
+use std::{ops::Range, time::Duration};
+use ui::{prelude::*, Tooltip};
+use workspace::{
+    item::{Item, ItemEvent, TabContentParams},
+    Workspace,
+};
+
+const UPDATE_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Highlights the lines of the snapshot that aren't in the current content.
+enum DeletedLines {}
+/// Highlights the lines of the current content that aren't in the snapshot.
+enum InsertedLines {}
+
+/// Shows a snapshot of a file side by side with the current content of its buffer.
+pub struct SnapshotDiffView {
+    buffer: Model<Buffer>,
+    snapshot: Snapshot,
+    snapshot_editor: View<Editor>,
+    buffer_editor: View<Editor>,
+    update_task: Task<Option<()>>,
+    _buffer_subscription: Subscription,
+}
+
+impl SnapshotDiffView {
+    pub fn open(
+        workspace: &mut Workspace,
+        buffer: Model<Buffer>,
+        snapshot: Snapshot,
+        cx: &mut ViewContext<Workspace>,
+    ) {
+        let content = FileHistory::snapshot_content(snapshot.id, cx);
+        cx.spawn(|workspace, mut cx| async move {
+            let content = content.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                let project = workspace.project().clone();
+                let view = cx.new_view(|cx| {
+                    let language = buffer.read(cx).language().cloned();
+                    let snapshot_buffer = cx.new_model(|cx| {
+                        let mut snapshot_buffer = Buffer::local(content, cx);
+                        snapshot_buffer.set_language(language, cx);
+                        snapshot_buffer
+                    });
+                    let snapshot_editor = cx.new_view(|cx| {
+                        let mut editor = Editor::for_buffer(snapshot_buffer, None, cx);
+                        editor.set_read_only(true);
+                        editor
+                    });
+                    let buffer_editor =
+                        cx.new_view(|cx| Editor::for_buffer(buffer.clone(), Some(project), cx));
+                    let buffer_subscription = cx.subscribe(&buffer, |this, _, event, cx| {
+                        if let BufferEvent::Edited = event {
+                            this.update_highlights(UPDATE_DEBOUNCE, cx);
+                        }
+                    });
+                    let mut this = Self {
+                        buffer,
+                        snapshot,
+                        snapshot_editor,
+                        buffer_editor,
+                        update_task: Task::ready(None),
+                        _buffer_subscription: buffer_subscription,
+                    };
+                    this.update_highlights(Duration::ZERO, cx);
+                    this
+                });
+                workspace.add_item_to_active_pane(Box::new(view), None, cx);
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Diffs the snapshot with the current content on the background, and highlights the lines
+    /// that differ.
+    fn update_highlights(&mut self, debounce: Duration, cx: &mut ViewContext<Self>) {
+        let old_text = self.snapshot_editor.read(cx).text(cx);
+        let new_text = self.buffer.read(cx).text();
+        self.update_task = cx.spawn(|this, mut cx| async move {
+            if !debounce.is_zero() {
+                cx.background_executor().timer(debounce).await;
+            }
+            let (deleted, inserted) = cx
+                .background_executor()
+                .spawn(async move { changed_rows(&old_text, &new_text) })
+                .await;
+            this.update(&mut cx, |this, cx| {
+                let theme = cx.theme().status();
+                let (deleted_color, inserted_color) =
+                    (theme.deleted_background, theme.created_background);
+                highlight_rows::<DeletedLines>(&this.snapshot_editor, deleted, deleted_color, cx);
+                highlight_rows::<InsertedLines>(&this.buffer_editor, inserted, inserted_color, cx);
+            })
+            .ok()
+        });
+    }
+
+    fn restore(&mut self, _: &RestoreSnapshot, cx: &mut ViewContext<Self>) {
+        restore_snapshot(self.buffer.clone(), self.snapshot.id, cx).detach_and_log_err(cx);
+    }
+
+    fn file_name(&self, cx: &AppContext) -> SharedString {
+        self.buffer
+            .read(cx)
+            .file()
+            .map_or_else(
+                || "untitled".to_string(),
+                |file| file.file_name(cx).to_string_lossy().to_string(),
+            )
+            .into()
+    }
+
+    fn render_editor_header(&self, label: String, cx: &ViewContext<Self>) -> impl IntoElement {
+        h_flex()
+            .px_2()
+            .py_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(Label::new(label).size(LabelSize::Small).color(Color::Muted))
+    }
+}
+
+/// The ranges of rows that were deleted from the old text and inserted in the new one.
+fn changed_rows(old_text: &str, new_text: &str) -> (Vec<Range<u32>>, Vec<Range<u32>>) {
+    fn push_row(ranges: &mut Vec<Range<u32>>, row: usize) {
+        let row = row as u32;
+        match ranges.last_mut() {
+            Some(range) if range.end == row => range.end += 1,
+            _ => ranges.push(row..row + 1),
+        }
+    }
+
+    let mut deleted = Vec::new();
+    let mut inserted = Vec::new();
+    let diff = TextDiff::from_lines(old_text, new_text);
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Delete => push_row(&mut deleted, change.old_index().unwrap_or_default()),
+            ChangeTag::Insert => push_row(&mut inserted, change.new_index().unwrap_or_default()),
+            ChangeTag::Equal => {}
+        }
+    }
+    (deleted, inserted)
+}
+
+fn highlight_rows<T: 'static>(
+    editor: &View<Editor>,
+    rows: Vec<Range<u32>>,
+    color: Hsla,
+    cx: &mut WindowContext,
+) {
+    editor.update(cx, |editor, cx| {
+        editor.clear_row_highlights::<T>();
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        for rows in rows {
+            let start = snapshot.anchor_before(Point::new(rows.start, 0));
+            let end = snapshot.anchor_before(Point::new(rows.end - 1, 0));
+            editor.highlight_rows::<T>(start..=end, Some(color), false, cx);
+        }
+        cx.notify();
+    });
+}
+
+impl EventEmitter<()> for SnapshotDiffView {}
+
+impl FocusableView for SnapshotDiffView {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        self.buffer_editor.focus_handle(cx)
+    }
+}
+
+impl Item for SnapshotDiffView {
+    type Event = ();
+
+    fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement {
+        let color = if params.selected {
+            Color::Default
+        } else {
+            Color::Muted
+        };
+        h_flex()
+            .gap_2()
+            .child(Icon::new(IconName::HistoryRerun).color(color))
+            .child(Label::new(format!("{} (history)", self.file_name(cx))).color(color))
+            .into_any()
+    }
+
+    fn tab_tooltip_text(&self, cx: &AppContext) -> Option<SharedString> {
+        let abs_path = self
+            .buffer
+            .read(cx)
+            .file()
+            .and_then(|file| file.as_local())
+            .map(|file| file.abs_path(cx))?;
+        Some(abs_path.to_string_lossy().to_string().into())
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("file history diff")
+    }
+
+    fn to_item_events(_event: &Self::Event, _f: impl FnMut(ItemEvent)) {}
+}
+
+impl Render for SnapshotDiffView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let time = time_format::format_localized_timestamp(
+            self.snapshot.created_at,
+            time::OffsetDateTime::now_utc(),
+            cx.local_timezone(),
+            time_format::TimestampFormat::Relative,
+        );
+
+        v_flex()
+            .key_context("SnapshotDiffView")
+            .on_action(cx.listener(Self::restore))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_2()
+                    .justify_between()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new(format!("{} {time}", self.snapshot.kind.label())))
+                    .child(
+                        Button::new("restore-snapshot", "Restore")
+                            .icon(IconName::RotateCcw)
+                            .icon_size(IconSize::Small)
+                            .icon_position(IconPosition::Start)
+                            .tooltip(|cx| {
+                                Tooltip::with_meta(
+                                    "Restore Snapshot",
+                                    Some(&RestoreSnapshot),
+                                    "The change can be undone",
+                                    cx,
+                                )
+                            })
+                            .on_click(
+                                cx.listener(|this, _, cx| this.restore(&RestoreSnapshot, cx)),
+                            ),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .flex_1()
+                    .size_full()
+                    .child(
+                        v_flex()
+                            .flex_1()
+                            .h_full()
+                            .border_r_1()
+                            .border_color(cx.theme().colors().border)
+                            .child(self.render_editor_header("Snapshot".into(), cx))
+                            .child(div().flex_1().child(self.snapshot_editor.clone())),
+                    )
+                    .child(
+                        v_flex()
+                            .flex_1()
+                            .h_full()
+                            .child(self.render_editor_header("Current".into(), cx))
+                            .child(div().flex_1().child(self.buffer_editor.clone())),
+                    ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_rows() {
+        let (deleted, inserted) = changed_rows("a\nb\nc\nd\n", "a\nx\ny\nc\nd\ne\n");
+        assert_eq!(deleted, [1..2]);
+        assert_eq!(inserted, [1..3, 5..6]);
+    }
+}
@@ -633,6 +633,7 @@ impl Watcher for RealWatcher {
 
 #[cfg(any(test, feature = "test-support"))]
 pub struct FakeFs {
+    this: std::sync::Weak<Self>,
     // Use an unfair lock to ensure tests are deterministic.
     state: Mutex<FakeFsState>,
     executor: gpui::BackgroundExecutor,
@@ -783,7 +784,8 @@ lazy_static::lazy_static! {
 #[cfg(any(test, feature = "test-support"))]
 impl FakeFs {
     pub fn new(executor: gpui::BackgroundExecutor) -> Arc<Self> {
-        Arc::new(Self {
+        Arc::new_cyclic(|this| Self {
+            this: this.clone(),
             executor,
             state: Mutex::new(FakeFsState {
                 root: Arc::new(Mutex::new(FakeFsEntry::Dir {
@@ -982,6 +984,17 @@ impl FakeFs {
         });
     }
 
+    pub fn set_head_for_repo(&self, dot_git: &Path, head_state: &[(&Path, String)]) {
+        self.with_git_state(dot_git, true, |state| {
+            state.head_contents.clear();
+            state.head_contents.extend(
+                head_state
+                    .iter()
+                    .map(|(path, content)| (path.to_path_buf(), content.clone())),
+            );
+        });
+    }
+
     pub fn set_blame_for_repo(&self, dot_git: &Path, blames: Vec<(&Path, git::blame::Blame)>) {
         self.with_git_state(dot_git, true, |state| {
             state.blames.clear();
@@ -1583,7 +1596,19 @@ impl Fs for FakeFs {
             let state = git_repo_state
                 .get_or_insert_with(|| Arc::new(Mutex::new(FakeGitRepositoryState::default())))
                 .clone();
-            Some(git::repository::FakeGitRepository::open(state))
+            let this = self.this.clone();
+            let work_directory = normalize_path(abs_dot_git.parent()?);
+            Some(git::repository::FakeGitRepository::open(
+                state,
+                Arc::new(move |repo_path| {
+                    let this = this.upgrade()?;
+                    let state = this.state.lock();
+                    let path = work_directory.join(repo_path);
+                    let entry = state.read_path(&path).ok()?;
+                    let entry = entry.lock();
+                    String::from_utf8(entry.file_content(&path).ok()?.clone()).ok()
+                }),
+            ))
         } else {
             None
         }
@@ -1,8 +1,9 @@
 use crate::Oid;
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context as _, Result};
 use collections::HashMap;
+use std::io::Write;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
 
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
@@ -47,3 +48,48 @@ pub fn get_messages(working_directory: &Path, shas: &[Oid]) -> Result<HashMap<Oi
         )
         .collect::<HashMap<Oid, String>>())
 }
+
+/// Commits the staged changes with the given message, replacing the HEAD commit when amending.
+/// Runs the git binary so that the user's hooks and signing configuration apply.
+pub fn commit(
+    git_binary: &Path,
+    working_directory: &Path,
+    message: &str,
+    amend: bool,
+) -> Result<()> {
+    let mut command = Command::new(git_binary);
+
+    command
+        .current_dir(working_directory)
+        .args(["commit", "--quiet", "--cleanup=strip", "--file=-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if amend {
+        command.arg("--amend");
+    }
+
+    #[cfg(windows)]
+    command.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| anyhow!("Failed to start git commit process: {}", e))?;
+
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("failed to get pipe to stdin of git commit command")?;
+    stdin.write_all(message.as_bytes())?;
+    drop(stdin);
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| anyhow!("Failed to read git commit output: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("git commit process failed: {}", stderr.trim()));
+    }
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use crate::GitHostingProviderRegistry;
-use crate::{blame::Blame, status::GitStatus};
+use crate::{
+    blame::Blame,
+    status::{GitChange, GitStatus, GitStatusEntry},
+};
 use anyhow::{Context, Result};
-use collections::HashMap;
+use collections::{BTreeSet, HashMap};
 use git2::BranchType;
 use parking_lot::Mutex;
 use rope::Rope;
@@ -29,6 +32,19 @@ pub trait GitRepository: Send + Sync {
     /// Note that for symlink entries, this will return the contents of the symlink, not the target.
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String>;
 
+    /// Loads a git repository entry's contents at HEAD.
+    fn load_head_text(&self, relative_file_path: &Path) -> Option<String>;
+
+    /// Writes the given contents to the index entry of a file, e.g. to stage some of its changes.
+    /// Removes the file from the index when the contents are `None`.
+    fn set_index_text(&self, relative_file_path: &Path, content: Option<String>) -> Result<()>;
+
+    /// Stages the working copy contents of the given paths, removing deleted files from the index.
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
+    /// Resets the index entries of the given paths to their contents at HEAD.
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()>;
+
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> Option<String>;
     fn branch_name(&self) -> Option<String>;
@@ -47,6 +63,12 @@ pub trait GitRepository: Send + Sync {
     fn create_branch(&self, _: &str) -> Result<()>;
 
     fn blame(&self, path: &Path, content: Rope) -> Result<crate::blame::Blame>;
+
+    /// Returns the message of the HEAD commit, e.g. to edit it when amending.
+    fn head_commit_message(&self) -> Option<String>;
+
+    /// Commits the staged changes, replacing the HEAD commit if `amend` is set.
+    fn commit(&self, message: &str, amend: bool) -> Result<()>;
 }
 
 impl std::fmt::Debug for dyn GitRepository {
@@ -106,6 +128,108 @@ impl GitRepository for RealGitRepository {
         None
     }
 
+    fn load_head_text(&self, relative_file_path: &Path) -> Option<String> {
+        fn logic(repo: &git2::Repository, relative_file_path: &Path) -> Result<Option<String>> {
+            check_path_to_repo_path_errors(relative_file_path)?;
+
+            // A repository without commits has nothing at HEAD.
+            let Ok(head) = repo.head() else {
+                return Ok(None);
+            };
+            let oid = match head.peel_to_tree()?.get_path(relative_file_path) {
+                Ok(entry) => entry.id(),
+                Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+
+            let content = repo.find_blob(oid)?.content().to_owned();
+            Ok(Some(String::from_utf8(content)?))
+        }
+
+        match logic(&self.repository.lock(), relative_file_path) {
+            Ok(value) => return value,
+            Err(err) => log::error!("Error loading head text: {:?}", err),
+        }
+        None
+    }
+
+    fn set_index_text(&self, relative_file_path: &Path, content: Option<String>) -> Result<()> {
+        const STAGE_NORMAL: i32 = 0;
+        check_path_to_repo_path_errors(relative_file_path)?;
+
+        let repo = self.repository.lock();
+        let mut index = repo.index()?;
+        if let Some(content) = content {
+            // Keep the mode of an existing entry, e.g. for executable files. The zeroed
+            // stat data makes git compare the working copy by content afterwards.
+            let mut entry = index
+                .get_path(relative_file_path, STAGE_NORMAL)
+                .unwrap_or_else(|| git2::IndexEntry {
+                    ctime: git2::IndexTime::new(0, 0),
+                    mtime: git2::IndexTime::new(0, 0),
+                    dev: 0,
+                    ino: 0,
+                    mode: 0o100644,
+                    uid: 0,
+                    gid: 0,
+                    file_size: 0,
+                    id: git2::Oid::zero(),
+                    flags: 0,
+                    flags_extended: 0,
+                    path: Vec::new(),
+                });
+            entry.ctime = git2::IndexTime::new(0, 0);
+            entry.mtime = git2::IndexTime::new(0, 0);
+            entry.file_size = content.len() as u32;
+            entry.path = relative_file_path
+                .to_string_lossy()
+                .replace('\\', "/")
+                .into_bytes();
+            index.add_frombuffer(&entry, content.as_bytes())?;
+        } else {
+            index.remove_path(relative_file_path)?;
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let repo = self.repository.lock();
+        let working_directory = repo
+            .workdir()
+            .context("failed to read git work directory")?
+            .to_path_buf();
+        let mut index = repo.index()?;
+        for path in paths {
+            check_path_to_repo_path_errors(path)?;
+            if working_directory.join(path).exists() {
+                index.add_path(path)?;
+            } else {
+                index.remove_path(path)?;
+            }
+        }
+        index.write()?;
+        Ok(())
+    }
+
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let repo = self.repository.lock();
+        let head = repo
+            .head()
+            .and_then(|head| head.peel(git2::ObjectType::Commit));
+        if let Ok(head) = head {
+            repo.reset_default(Some(&head), paths.iter().map(|path| path.as_path()))?;
+        } else {
+            // Without any commits, unstaging a file removes it from the index.
+            let mut index = repo.index()?;
+            for path in paths {
+                index.remove_path(path)?;
+            }
+            index.write()?;
+        }
+        Ok(())
+    }
+
     fn remote_url(&self, name: &str) -> Option<String> {
         let repo = self.repository.lock();
         let remote = repo.find_remote(name).ok()?;
@@ -133,6 +257,23 @@ impl GitRepository for RealGitRepository {
         GitStatus::new(&self.git_binary_path, &working_directory, path_prefix)
     }
 
+    fn head_commit_message(&self) -> Option<String> {
+        let repo = self.repository.lock();
+        let commit = repo.head().ok()?.peel_to_commit().ok()?;
+        let message = String::from_utf8_lossy(commit.message_bytes());
+        Some(message.trim_end().to_string())
+    }
+
+    fn commit(&self, message: &str, amend: bool) -> Result<()> {
+        let working_directory = self
+            .repository
+            .lock()
+            .workdir()
+            .context("failed to read git work directory")?
+            .to_path_buf();
+        crate::commit::commit(&self.git_binary_path, &working_directory, message, amend)
+    }
+
     fn branches(&self) -> Result<Vec<Branch>> {
         let repo = self.repository.lock();
         let local_branches = repo.branches(Some(BranchType::Local))?;
@@ -202,22 +343,32 @@ impl GitRepository for RealGitRepository {
     }
 }
 
-#[derive(Debug, Clone, Default)]
 pub struct FakeGitRepository {
     state: Arc<Mutex<FakeGitRepositoryState>>,
+    load_working_copy_text: Arc<dyn Send + Sync + Fn(&Path) -> Option<String>>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct FakeGitRepositoryState {
     pub index_contents: HashMap<PathBuf, String>,
+    pub head_contents: HashMap<PathBuf, String>,
+    /// The messages of the commits made through the repository, oldest first.
+    pub commit_messages: Vec<String>,
     pub blames: HashMap<PathBuf, Blame>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub branch_name: Option<String>,
 }
 
 impl FakeGitRepository {
-    pub fn open(state: Arc<Mutex<FakeGitRepositoryState>>) -> Arc<dyn GitRepository> {
-        Arc::new(FakeGitRepository { state })
+    /// Opens a fake repository, reading the contents of staged files with the given function.
+    pub fn open(
+        state: Arc<Mutex<FakeGitRepositoryState>>,
+        load_working_copy_text: Arc<dyn Send + Sync + Fn(&Path) -> Option<String>>,
+    ) -> Arc<dyn GitRepository> {
+        Arc::new(FakeGitRepository {
+            state,
+            load_working_copy_text,
+        })
     }
 }
 
@@ -229,6 +380,39 @@ impl GitRepository for FakeGitRepository {
         state.index_contents.get(path).cloned()
     }
 
+    fn load_head_text(&self, path: &Path) -> Option<String> {
+        let state = self.state.lock();
+        state.head_contents.get(path).cloned()
+    }
+
+    fn set_index_text(&self, path: &Path, content: Option<String>) -> Result<()> {
+        let mut state = self.state.lock();
+        match content {
+            Some(content) => state.index_contents.insert(path.to_path_buf(), content),
+            None => state.index_contents.remove(path),
+        };
+        Ok(())
+    }
+
+    fn stage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        for path in paths {
+            let content = (self.load_working_copy_text)(path);
+            self.set_index_text(path, content)?;
+        }
+        Ok(())
+    }
+
+    fn unstage_paths(&self, paths: &[RepoPath]) -> Result<()> {
+        let mut state = self.state.lock();
+        for path in paths {
+            match state.head_contents.get(&path.0).cloned() {
+                Some(content) => state.index_contents.insert(path.0.clone(), content),
+                None => state.index_contents.remove(&path.0),
+            };
+        }
+        Ok(())
+    }
+
     fn remote_url(&self, _name: &str) -> Option<String> {
         None
     }
@@ -243,7 +427,7 @@ impl GitRepository for FakeGitRepository {
     }
 
     fn statuses(&self, path_prefix: &Path) -> Result<GitStatus> {
-        let state = self.state.lock();
+        let state = self.state.lock().clone();
         let mut entries = state
             .worktree_statuses
             .iter()
@@ -256,8 +440,44 @@ impl GitRepository for FakeGitRepository {
             })
             .collect::<Vec<_>>();
         entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        // Compare the contents of HEAD, the index and the working copy of every known file.
+        let paths = state
+            .head_contents
+            .keys()
+            .chain(state.index_contents.keys())
+            .chain(state.worktree_statuses.keys().map(|repo_path| &repo_path.0))
+            .filter(|path| path.starts_with(path_prefix))
+            .collect::<BTreeSet<_>>();
+        let change = |old: Option<&String>, new: Option<&String>| match (old, new) {
+            (None, Some(_)) => Some(GitChange::Added),
+            (Some(_), None) => Some(GitChange::Deleted),
+            (Some(old), Some(new)) if old != new => Some(GitChange::Modified),
+            _ => None,
+        };
+        let changes = paths
+            .into_iter()
+            .filter_map(|path| {
+                let head_text = state.head_contents.get(path);
+                let index_text = state.index_contents.get(path);
+                let working_copy_text = (self.load_working_copy_text)(path);
+                let unstaged = if index_text.is_none() && working_copy_text.is_some() {
+                    Some(GitChange::Untracked)
+                } else {
+                    change(index_text, working_copy_text.as_ref())
+                };
+                let staged = change(head_text, index_text);
+                (staged.is_some() || unstaged.is_some()).then(|| GitStatusEntry {
+                    repo_path: RepoPath(path.clone()),
+                    staged,
+                    unstaged,
+                })
+            })
+            .collect::<Vec<_>>();
+
         Ok(GitStatus {
             entries: entries.into(),
+            changes: changes.into(),
         })
     }
 
@@ -285,6 +505,21 @@ impl GitRepository for FakeGitRepository {
             .with_context(|| format!("failed to get blame for {:?}", path))
             .cloned()
     }
+
+    fn head_commit_message(&self) -> Option<String> {
+        let state = self.state.lock();
+        state.commit_messages.last().cloned()
+    }
+
+    fn commit(&self, message: &str, amend: bool) -> Result<()> {
+        let mut state = self.state.lock();
+        if amend {
+            state.commit_messages.pop().context("no commit to amend")?;
+        }
+        state.head_contents = state.index_contents.clone();
+        state.commit_messages.push(message.to_string());
+        Ok(())
+    }
 }
 
 fn check_path_to_repo_path_errors(relative_file_path: &Path) -> Result<()> {
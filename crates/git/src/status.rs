@@ -9,6 +9,55 @@ use std::{
 #[derive(Clone)]
 pub struct GitStatus {
     pub entries: Arc<[(RepoPath, GitFileStatus)]>,
+    /// The changed files, split into their staged and unstaged changes.
+    pub changes: Arc<[GitStatusEntry]>,
+}
+
+/// A kind of change to a file, as shown by one of the two columns of `git status --short`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GitChange {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Conflict,
+    Untracked,
+}
+
+/// A changed file, with its changes in the index relative to HEAD and in the working copy
+/// relative to the index.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GitStatusEntry {
+    pub repo_path: RepoPath,
+    pub staged: Option<GitChange>,
+    pub unstaged: Option<GitChange>,
+}
+
+impl GitStatusEntry {
+    /// Parses the two status letters of a `git status --porcelain` line.
+    fn parse(repo_path: RepoPath, status: &str) -> Option<Self> {
+        let mut letters = status.chars();
+        let (staged, unstaged) = (letters.next()?, letters.next()?);
+        let (staged, unstaged) = match (staged, unstaged) {
+            ('?', '?') => (None, Some(GitChange::Untracked)),
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => (None, Some(GitChange::Conflict)),
+            (staged, unstaged) => {
+                let change = |letter| match letter {
+                    'A' => Some(GitChange::Added),
+                    'M' | 'T' => Some(GitChange::Modified),
+                    'D' => Some(GitChange::Deleted),
+                    'R' | 'C' => Some(GitChange::Renamed),
+                    _ => None,
+                };
+                (change(staged), change(unstaged))
+            }
+        };
+        (staged.is_some() || unstaged.is_some()).then_some(Self {
+            repo_path,
+            staged,
+            unstaged,
+        })
+    }
 }
 
 impl GitStatus {
@@ -57,28 +106,31 @@ impl GitStatus {
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut entries = stdout
-            .split('\0')
-            .filter_map(|entry| {
-                if entry.is_char_boundary(3) {
-                    let (status, path) = entry.split_at(3);
-                    let status = status.trim();
-                    Some((
-                        RepoPath(PathBuf::from(path)),
-                        match status {
-                            "A" | "??" => GitFileStatus::Added,
-                            "M" => GitFileStatus::Modified,
-                            _ => return None,
-                        },
-                    ))
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+        let mut entries = Vec::new();
+        let mut changes = Vec::new();
+        let mut lines = stdout.split('\0');
+        while let Some(entry) = lines.next() {
+            if !entry.is_char_boundary(3) {
+                continue;
+            }
+            let (status, path) = entry.split_at(3);
+            // Renames and copies are followed by their original path.
+            if status.contains(['R', 'C']) {
+                lines.next();
+            }
+            let repo_path = RepoPath(PathBuf::from(path));
+            changes.extend(GitStatusEntry::parse(repo_path.clone(), status));
+            match status.trim() {
+                "A" | "??" => entries.push((repo_path, GitFileStatus::Added)),
+                "M" => entries.push((repo_path, GitFileStatus::Modified)),
+                _ => {}
+            }
+        }
         entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        changes.sort_unstable_by(|a, b| a.repo_path.cmp(&b.repo_path));
         Ok(Self {
             entries: entries.into(),
+            changes: changes.into(),
         })
     }
 
@@ -94,6 +146,7 @@ impl Default for GitStatus {
     fn default() -> Self {
         Self {
             entries: Arc::new([]),
+            changes: Arc::new([]),
         }
     }
 }
@@ -0,0 +1,16 @@
+mod panel;
+
+use gpui::{actions, AppContext};
+pub use panel::*;
+use workspace::Workspace;
+
+actions!(git_panel, [Commit, StageAll, UnstageAll, ToggleFocus]);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ToggleFocus, cx| {
+            workspace.toggle_panel_focus::<GitPanel>(cx);
+        });
+    })
+    .detach();
+}
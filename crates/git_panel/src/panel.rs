@@ -0,0 +1,870 @@
+use crate::{Commit, StageAll, ToggleFocus, UnstageAll};
+use anyhow::Context as _;
+use db::kvp::KEY_VALUE_STORE;
+use editor::Editor;
+use git::{
+    repository::{GitRepository, RepoPath},
+    status::{GitChange, GitStatusEntry},
+};
+use gpui::{
+    Action, AnyElement, AppContext, AsyncWindowContext, EventEmitter, FocusHandle, FocusableView,
+    Model, Subscription, Task, View, WeakView,
+};
+use project::{Project, ProjectPath, UpdatedEntriesSet, WorktreeId};
+use serde::{Deserialize, Serialize};
+use std::{path::Path, sync::Arc, time::Duration};
+use ui::{prelude::*, Checkbox, CheckboxWithLabel, ListItem};
+use util::{ResultExt, TryFutureExt};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    notifications::DetachAndPromptErr,
+    Workspace,
+};
+
+const GIT_PANEL_KEY: &str = "GitPanel";
+const REFRESH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+#[derive(Serialize, Deserialize)]
+struct SerializedGitPanel {
+    size: Option<Pixels>,
+    position: Option<SerializedDockPosition>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SerializedDockPosition {
+    Left,
+    Bottom,
+    Right,
+}
+
+/// A group of changed files in the panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Section {
+    Staged,
+    Unstaged,
+    Untracked,
+}
+
+impl Section {
+    fn label(self) -> &'static str {
+        match self {
+            Section::Staged => "Staged Changes",
+            Section::Unstaged => "Changes",
+            Section::Untracked => "Untracked Files",
+        }
+    }
+
+    /// The change of the entry that is listed in this section, if any.
+    fn change(self, entry: &GitStatusEntry) -> Option<GitChange> {
+        match self {
+            Section::Staged => entry.staged,
+            Section::Unstaged => entry
+                .unstaged
+                .filter(|change| *change != GitChange::Untracked),
+            Section::Untracked => entry
+                .unstaged
+                .filter(|change| *change == GitChange::Untracked),
+        }
+    }
+}
+
+/// The changed files of one of the project's git repositories.
+struct RepositoryChanges {
+    worktree_id: WorktreeId,
+    /// The working directory of the repository, relative to its worktree.
+    work_directory: Arc<Path>,
+    repo: Arc<dyn GitRepository>,
+    branch: Option<Arc<str>>,
+    entries: Vec<GitStatusEntry>,
+}
+
+pub struct GitPanel {
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    focus_handle: FocusHandle,
+    size: Option<Pixels>,
+    position: DockPosition,
+    commit_editor: View<Editor>,
+    amend: bool,
+    committing: bool,
+    repositories: Vec<RepositoryChanges>,
+    /// The index of the repository whose changes are shown and committed.
+    active_repository: usize,
+    refresh_task: Task<Option<()>>,
+    pending_serialization: Task<Option<()>>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl GitPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(GIT_PANEL_KEY) })
+            .await
+            .context("loading git panel")
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedGitPanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = Self::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.size = serialized_panel.size.map(|px| px.round());
+                    if let Some(position) = serialized_panel.position {
+                        panel.position = match position {
+                            SerializedDockPosition::Left => DockPosition::Left,
+                            SerializedDockPosition::Bottom => DockPosition::Bottom,
+                            SerializedDockPosition::Right => DockPosition::Right,
+                        };
+                    }
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let weak_workspace = workspace.weak_handle();
+        cx.new_view(|cx| {
+            let commit_editor = cx.new_view(|cx| {
+                let mut editor = Editor::auto_height(8, cx);
+                editor.set_placeholder_text("Commit message", cx);
+                editor
+            });
+            let subscriptions = vec![
+                cx.observe(&commit_editor, |_, _, cx| cx.notify()),
+                cx.subscribe(&project, |this, _, event, cx| match event {
+                    project::Event::WorktreeAdded
+                    | project::Event::WorktreeRemoved(_)
+                    | project::Event::WorktreeUpdatedGitRepositories => this.refresh(cx),
+                    project::Event::WorktreeUpdatedEntries(worktree_id, changes) => {
+                        if this.changes_affect_statuses(*worktree_id, changes, cx) {
+                            this.refresh(cx);
+                        }
+                    }
+                    _ => {}
+                }),
+            ];
+            let mut this = Self {
+                workspace: weak_workspace,
+                project,
+                focus_handle: cx.focus_handle(),
+                size: None,
+                position: DockPosition::Left,
+                commit_editor,
+                amend: false,
+                committing: false,
+                repositories: Vec::new(),
+                active_repository: 0,
+                refresh_task: Task::ready(None),
+                pending_serialization: Task::ready(None),
+                _subscriptions: subscriptions,
+            };
+            this.refresh(cx);
+            this
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let size = self.size;
+        let position = match self.position {
+            DockPosition::Left => SerializedDockPosition::Left,
+            DockPosition::Bottom => SerializedDockPosition::Bottom,
+            DockPosition::Right => SerializedDockPosition::Right,
+        };
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        GIT_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedGitPanel {
+                            size,
+                            position: Some(position),
+                        })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    /// Whether any of the changed entries can alter the status of a repository,
+    /// i.e. it lies inside a repository's working directory and isn't ignored.
+    fn changes_affect_statuses(
+        &self,
+        worktree_id: WorktreeId,
+        changes: &UpdatedEntriesSet,
+        cx: &AppContext,
+    ) -> bool {
+        let Some(worktree) = self.project.read(cx).worktree_for_id(worktree_id, cx) else {
+            return false;
+        };
+        let snapshot = worktree.read(cx).snapshot();
+        changes.iter().any(|(path, entry_id, _)| {
+            snapshot.repository_for_path(path).is_some()
+                && !snapshot
+                    .entry_for_id(*entry_id)
+                    .map_or(false, |entry| entry.is_ignored)
+        })
+    }
+
+    /// Reloads the statuses of all repositories in the visible local worktrees,
+    /// once no other refresh has been requested for [`REFRESH_DEBOUNCE`].
+    fn refresh(&mut self, cx: &mut ViewContext<Self>) {
+        let mut repositories = Vec::new();
+        for worktree in self.project.read(cx).visible_worktrees(cx) {
+            let worktree = worktree.read(cx);
+            let Some(local_worktree) = worktree.as_local() else {
+                continue;
+            };
+            for (work_directory, repository_entry) in local_worktree.repositories() {
+                if let Some(local_repo) = local_worktree.get_local_repo(repository_entry) {
+                    repositories.push((
+                        worktree.id(),
+                        work_directory.clone(),
+                        local_repo.repo().clone(),
+                        repository_entry.branch(),
+                    ));
+                }
+            }
+        }
+
+        self.refresh_task = cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(REFRESH_DEBOUNCE).await;
+            let repositories = cx
+                .background_executor()
+                .spawn(async move {
+                    repositories
+                        .into_iter()
+                        .map(|(worktree_id, work_directory, repo, branch)| {
+                            let entries = repo
+                                .statuses(Path::new(""))
+                                .log_err()
+                                .map(|status| status.changes.to_vec())
+                                .unwrap_or_default();
+                            RepositoryChanges {
+                                worktree_id,
+                                work_directory,
+                                repo,
+                                branch,
+                                entries,
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .await;
+
+            this.update(&mut cx, |this, cx| {
+                let active_repository =
+                    this.repositories
+                        .get(this.active_repository)
+                        .and_then(|active| {
+                            repositories.iter().position(|repository| {
+                                repository.worktree_id == active.worktree_id
+                                    && repository.work_directory == active.work_directory
+                            })
+                        });
+                this.active_repository = active_repository.unwrap_or(0);
+                this.repositories = repositories;
+                cx.notify();
+            })
+            .ok()
+        });
+    }
+
+    fn set_staged(
+        &mut self,
+        repository_ix: usize,
+        paths: Vec<RepoPath>,
+        staged: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(repository) = self.repositories.get(repository_ix) else {
+            return;
+        };
+        let repo = repository.repo.clone();
+        let message = if staged {
+            "Failed to stage changes"
+        } else {
+            "Failed to unstage changes"
+        };
+        cx.spawn(|this, mut cx| async move {
+            cx.background_executor()
+                .spawn(async move {
+                    if staged {
+                        repo.stage_paths(&paths)
+                    } else {
+                        repo.unstage_paths(&paths)
+                    }
+                })
+                .await?;
+            this.update(&mut cx, |this, cx| this.refresh(cx))
+        })
+        .detach_and_prompt_err(message, cx, |_, _| None);
+    }
+
+    fn section_paths(&self, sections: &[Section]) -> Vec<RepoPath> {
+        self.repositories
+            .get(self.active_repository)
+            .into_iter()
+            .flat_map(|repository| &repository.entries)
+            .filter(|entry| {
+                sections
+                    .iter()
+                    .any(|section| section.change(entry).is_some())
+            })
+            .map(|entry| entry.repo_path.clone())
+            .collect()
+    }
+
+    fn stage_all(&mut self, _: &StageAll, cx: &mut ViewContext<Self>) {
+        let paths = self.section_paths(&[Section::Unstaged, Section::Untracked]);
+        if !paths.is_empty() {
+            self.set_staged(self.active_repository, paths, true, cx);
+        }
+    }
+
+    fn unstage_all(&mut self, _: &UnstageAll, cx: &mut ViewContext<Self>) {
+        let paths = self.section_paths(&[Section::Staged]);
+        if !paths.is_empty() {
+            self.set_staged(self.active_repository, paths, false, cx);
+        }
+    }
+
+    fn toggle_amend(&mut self, cx: &mut ViewContext<Self>) {
+        self.amend = !self.amend;
+        // Start from the message of the amended commit, unless one was written already.
+        if self.amend && self.commit_editor.read(cx).text(cx).trim().is_empty() {
+            let message = self
+                .repositories
+                .get(self.active_repository)
+                .and_then(|repository| repository.repo.head_commit_message());
+            if let Some(message) = message {
+                self.commit_editor
+                    .update(cx, |editor, cx| editor.set_text(message, cx));
+            }
+        }
+        cx.notify();
+    }
+
+    fn can_commit(&self, cx: &AppContext) -> bool {
+        let Some(repository) = self.repositories.get(self.active_repository) else {
+            return false;
+        };
+        !self.committing
+            && !self.commit_editor.read(cx).text(cx).trim().is_empty()
+            && (self.amend
+                || repository
+                    .entries
+                    .iter()
+                    .any(|entry| entry.staged.is_some()))
+    }
+
+    fn commit(&mut self, _: &Commit, cx: &mut ViewContext<Self>) {
+        if !self.can_commit(cx) {
+            return;
+        }
+        let repo = self.repositories[self.active_repository].repo.clone();
+        let message = self.commit_editor.read(cx).text(cx);
+        let amend = self.amend;
+        self.committing = true;
+        cx.notify();
+        cx.spawn(|this, mut cx| async move {
+            let result = cx
+                .background_executor()
+                .spawn(async move { repo.commit(&message, amend) })
+                .await;
+            this.update(&mut cx, |this, cx| {
+                this.committing = false;
+                if result.is_ok() {
+                    this.amend = false;
+                    this.commit_editor.update(cx, |editor, cx| editor.clear(cx));
+                }
+                this.refresh(cx);
+            })?;
+            result
+        })
+        .detach_and_prompt_err("Failed to commit", cx, |_, _| None);
+    }
+
+    fn open_entry(
+        &mut self,
+        repository_ix: usize,
+        repo_path: &RepoPath,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(repository) = self.repositories.get(repository_ix) else {
+            return;
+        };
+        let project_path = ProjectPath {
+            worktree_id: repository.worktree_id,
+            path: repository.work_directory.join(&repo_path.0).into(),
+        };
+        if let Some(workspace) = self.workspace.upgrade() {
+            workspace.update(cx, |workspace, cx| {
+                workspace
+                    .open_path(project_path, None, true, cx)
+                    .detach_and_log_err(cx);
+            });
+        }
+    }
+
+    fn render_header(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        h_flex()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .flex_wrap()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .children(
+                self.repositories
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, repository)| {
+                        let name = repository
+                            .work_directory
+                            .file_name()
+                            .map(|name| name.to_string_lossy().to_string())
+                            .unwrap_or_else(|| {
+                                self.project
+                                    .read(cx)
+                                    .worktree_for_id(repository.worktree_id, cx)
+                                    .map(|worktree| worktree.read(cx).root_name().to_string())
+                                    .unwrap_or_default()
+                            });
+                        let label = match &repository.branch {
+                            Some(branch) => format!("{name} ({branch})"),
+                            None => name,
+                        };
+                        Button::new(("git-panel-repository", ix), label)
+                            .label_size(LabelSize::Small)
+                            .icon(IconName::FileGit)
+                            .icon_position(IconPosition::Start)
+                            .icon_size(IconSize::Small)
+                            .selected(ix == self.active_repository)
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.active_repository = ix;
+                                cx.notify();
+                            }))
+                    }),
+            )
+    }
+
+    fn render_section(&self, section: Section, cx: &mut ViewContext<Self>) -> Option<AnyElement> {
+        let repository_ix = self.active_repository;
+        let repository = self.repositories.get(repository_ix)?;
+        let entries = repository
+            .entries
+            .iter()
+            .filter_map(|entry| Some((entry, section.change(entry)?)))
+            .collect::<Vec<_>>();
+        if entries.is_empty() {
+            return None;
+        }
+
+        let staged = section == Section::Staged;
+        let paths = entries
+            .iter()
+            .map(|(entry, _)| entry.repo_path.clone())
+            .collect::<Vec<_>>();
+        let header = ListItem::new(SharedString::from(format!(
+            "git-panel-section-{}",
+            section.label()
+        )))
+        .child(
+            Label::new(format!("{} ({})", section.label(), entries.len()))
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+        )
+        .end_slot(
+            Button::new(
+                SharedString::from(format!("git-panel-toggle-{}", section.label())),
+                if staged { "Unstage All" } else { "Stage All" },
+            )
+            .label_size(LabelSize::Small)
+            .on_click(cx.listener(move |this, _, cx| {
+                this.set_staged(repository_ix, paths.clone(), !staged, cx)
+            })),
+        );
+
+        let rows = entries
+            .into_iter()
+            .enumerate()
+            .map(|(ix, (entry, change))| {
+                let repo_path = entry.repo_path.clone();
+                let file_name = repo_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let directory = repo_path
+                    .parent()
+                    .map(|parent| parent.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let (letter, color) = match change {
+                    GitChange::Added => ("A", Color::Created),
+                    GitChange::Modified => ("M", Color::Modified),
+                    GitChange::Deleted => ("D", Color::Deleted),
+                    GitChange::Renamed => ("R", Color::Modified),
+                    GitChange::Conflict => ("!", Color::Conflict),
+                    GitChange::Untracked => ("U", Color::Created),
+                };
+                let id = SharedString::from(format!("git-panel-{}-{ix}", section.label()));
+                let toggle_path = repo_path.clone();
+                ListItem::new(id.clone())
+                    .start_slot(
+                        Checkbox::new(
+                            id,
+                            if staged {
+                                Selection::Selected
+                            } else {
+                                Selection::Unselected
+                            },
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &Selection, cx| {
+                                this.set_staged(
+                                    repository_ix,
+                                    vec![toggle_path.clone()],
+                                    !staged,
+                                    cx,
+                                )
+                            },
+                        )),
+                    )
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new(file_name).size(LabelSize::Small))
+                            .child(
+                                Label::new(directory)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            ),
+                    )
+                    .end_slot(Label::new(letter).size(LabelSize::Small).color(color))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.open_entry(repository_ix, &repo_path, cx)
+                    }))
+                    .into_any_element()
+            });
+
+        Some(v_flex().child(header).children(rows).into_any_element())
+    }
+
+    fn render_commit_area(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        v_flex()
+            .p_2()
+            .gap_2()
+            .border_t_1()
+            .border_color(cx.theme().colors().border)
+            .child(
+                div()
+                    .p_1()
+                    .rounded_md()
+                    .border_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(self.commit_editor.clone()),
+            )
+            .child(
+                h_flex()
+                    .justify_between()
+                    .child(CheckboxWithLabel::new(
+                        "git-panel-amend",
+                        Label::new("Amend").size(LabelSize::Small),
+                        if self.amend {
+                            Selection::Selected
+                        } else {
+                            Selection::Unselected
+                        },
+                        cx.listener(|this, _, cx| this.toggle_amend(cx)),
+                    ))
+                    .child(
+                        Button::new(
+                            "git-panel-commit",
+                            if self.amend { "Amend" } else { "Commit" },
+                        )
+                        .style(ButtonStyle::Filled)
+                        .disabled(!self.can_commit(cx))
+                        .on_click(cx.listener(|this, _, cx| this.commit(&Commit, cx))),
+                    ),
+            )
+    }
+}
+
+impl EventEmitter<PanelEvent> for GitPanel {}
+
+impl FocusableView for GitPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for GitPanel {
+    fn persistent_name() -> &'static str {
+        "Git Panel"
+    }
+
+    fn position(&self, _: &WindowContext) -> DockPosition {
+        self.position
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        self.position = position;
+        self.serialize(cx);
+    }
+
+    fn size(&self, _: &WindowContext) -> Pixels {
+        self.size.unwrap_or_else(|| match self.position {
+            DockPosition::Left | DockPosition::Right => px(320.),
+            DockPosition::Bottom => px(240.),
+        })
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.size = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, _: &WindowContext) -> Option<IconName> {
+        Some(IconName::FileGit)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Git Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
+
+impl Render for GitPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let sections = [Section::Staged, Section::Unstaged, Section::Untracked]
+            .into_iter()
+            .filter_map(|section| self.render_section(section, cx))
+            .collect::<Vec<_>>();
+        let message = if self.repositories.is_empty() {
+            Some("No git repositories in the project.")
+        } else if sections.is_empty() {
+            Some("No changes.")
+        } else {
+            None
+        };
+
+        v_flex()
+            .key_context("GitPanel")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::commit))
+            .on_action(cx.listener(Self::stage_all))
+            .on_action(cx.listener(Self::unstage_all))
+            .size_full()
+            .child(self.render_header(cx))
+            .child(
+                v_flex()
+                    .id("git-panel-entries")
+                    .p_1()
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .children(sections)
+                    .children(message.map(|message| {
+                        Label::new(message)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    })),
+            )
+            .child(self.render_commit_area(cx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git::repository::GitFileStatus;
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_stage_and_commit(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/project",
+            json!({
+                ".git": {},
+                "a.txt": "a2",
+                "b.txt": "b",
+                "c.txt": "c",
+            }),
+        )
+        .await;
+        let dot_git = Path::new("/project/.git");
+        let committed = [
+            (Path::new("a.txt"), "a".to_string()),
+            (Path::new("b.txt"), "b".to_string()),
+        ];
+        fs.set_head_for_repo(dot_git, &committed);
+        fs.set_index_for_repo(dot_git, &committed);
+        fs.set_status_for_repo_via_git_operation(
+            dot_git,
+            &[
+                (Path::new("a.txt"), GitFileStatus::Modified),
+                (Path::new("c.txt"), GitFileStatus::Added),
+            ],
+        );
+
+        let project = Project::test(fs.clone(), ["/project".as_ref()], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| GitPanel::new(workspace, cx))
+            .unwrap();
+        refresh(cx);
+        assert_eq!(
+            visible_entries(&panel, cx),
+            [
+                (Section::Unstaged, "a.txt".into(), GitChange::Modified),
+                (Section::Untracked, "c.txt".into(), GitChange::Untracked),
+            ]
+        );
+
+        panel.update(cx, |panel, cx| panel.stage_all(&StageAll, cx));
+        refresh(cx);
+        assert_eq!(
+            visible_entries(&panel, cx),
+            [
+                (Section::Staged, "a.txt".into(), GitChange::Modified),
+                (Section::Staged, "c.txt".into(), GitChange::Added),
+            ]
+        );
+
+        panel.update(cx, |panel, cx| {
+            panel.set_staged(0, vec![RepoPath::from(Path::new("a.txt"))], false, cx)
+        });
+        refresh(cx);
+        assert_eq!(
+            visible_entries(&panel, cx),
+            [
+                (Section::Staged, "c.txt".into(), GitChange::Added),
+                (Section::Unstaged, "a.txt".into(), GitChange::Modified),
+            ]
+        );
+
+        // Nothing is committed without a message.
+        panel.update(cx, |panel, cx| panel.commit(&Commit, cx));
+        refresh(cx);
+        fs.with_git_state(dot_git, false, |state| {
+            assert!(state.commit_messages.is_empty());
+        });
+
+        panel.update(cx, |panel, cx| {
+            panel
+                .commit_editor
+                .update(cx, |editor, cx| editor.set_text("Add c", cx));
+            panel.commit(&Commit, cx);
+        });
+        refresh(cx);
+        fs.with_git_state(dot_git, false, |state| {
+            assert_eq!(state.commit_messages, ["Add c"]);
+            assert_eq!(
+                state
+                    .head_contents
+                    .get(Path::new("c.txt"))
+                    .map(String::as_str),
+                Some("c")
+            );
+            assert_eq!(
+                state
+                    .head_contents
+                    .get(Path::new("a.txt"))
+                    .map(String::as_str),
+                Some("a")
+            );
+        });
+        assert_eq!(
+            visible_entries(&panel, cx),
+            [(Section::Unstaged, "a.txt".into(), GitChange::Modified)]
+        );
+
+        // Amending starts from the message of the amended commit.
+        panel.update(cx, |panel, cx| {
+            assert_eq!(panel.commit_editor.read(cx).text(cx), "");
+            panel.toggle_amend(cx);
+            assert_eq!(panel.commit_editor.read(cx).text(cx), "Add c");
+            panel
+                .commit_editor
+                .update(cx, |editor, cx| editor.set_text("Add c and a", cx));
+            panel.stage_all(&StageAll, cx);
+        });
+        refresh(cx);
+        panel.update(cx, |panel, cx| panel.commit(&Commit, cx));
+        refresh(cx);
+        fs.with_git_state(dot_git, false, |state| {
+            assert_eq!(state.commit_messages, ["Add c and a"]);
+            assert_eq!(
+                state
+                    .head_contents
+                    .get(Path::new("a.txt"))
+                    .map(String::as_str),
+                Some("a2")
+            );
+        });
+        assert!(visible_entries(&panel, cx).is_empty());
+        panel.update(cx, |panel, _| assert!(!panel.amend));
+    }
+
+    fn refresh(cx: &mut VisualTestContext) {
+        cx.executor().advance_clock(REFRESH_DEBOUNCE);
+        cx.run_until_parked();
+    }
+
+    fn visible_entries(
+        panel: &View<GitPanel>,
+        cx: &mut VisualTestContext,
+    ) -> Vec<(Section, String, GitChange)> {
+        panel.update(cx, |panel, _| {
+            let repository = &panel.repositories[panel.active_repository];
+            [Section::Staged, Section::Unstaged, Section::Untracked]
+                .into_iter()
+                .flat_map(|section| {
+                    repository.entries.iter().filter_map(move |entry| {
+                        let change = section.change(entry)?;
+                        Some((section, entry.repo_path.to_string_lossy().into(), change))
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            editor::init_settings(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+        });
+    }
+}
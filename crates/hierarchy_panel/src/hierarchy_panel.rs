@@ -0,0 +1,55 @@
+mod panel;
+
+use editor::Editor;
+use gpui::{actions, AppContext};
+pub use panel::*;
+use workspace::Workspace;
+
+actions!(
+    hierarchy_panel,
+    [
+        ShowIncomingCalls,
+        ShowOutgoingCalls,
+        ShowSupertypes,
+        ShowSubtypes,
+        ToggleFocus
+    ]
+);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, cx| {
+                workspace.toggle_panel_focus::<HierarchyPanel>(cx);
+            })
+            .register_action(|workspace, _: &ShowIncomingCalls, cx| {
+                deploy(workspace, HierarchyKind::IncomingCalls, cx);
+            })
+            .register_action(|workspace, _: &ShowOutgoingCalls, cx| {
+                deploy(workspace, HierarchyKind::OutgoingCalls, cx);
+            })
+            .register_action(|workspace, _: &ShowSupertypes, cx| {
+                deploy(workspace, HierarchyKind::Supertypes, cx);
+            })
+            .register_action(|workspace, _: &ShowSubtypes, cx| {
+                deploy(workspace, HierarchyKind::Subtypes, cx);
+            });
+    })
+    .detach();
+}
+
+/// Shows the hierarchy of the symbol under the newest cursor of the active editor.
+fn deploy(workspace: &mut Workspace, kind: HierarchyKind, cx: &mut gpui::ViewContext<Workspace>) {
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((buffer, position)) = editor.update(cx, |editor, cx| {
+        let head = editor.selections.newest::<usize>(cx).head();
+        editor.buffer().read(cx).text_anchor_for_position(head, cx)
+    }) else {
+        return;
+    };
+    if let Some(panel) = workspace.focus_panel::<HierarchyPanel>(cx) {
+        panel.update(cx, |panel, cx| panel.show(kind, buffer, position, cx));
+    }
+}
@@ -0,0 +1,735 @@
+use crate::ToggleFocus;
+use anyhow::Context as _;
+use collections::HashMap;
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{
+    Action, AnyElement, AppContext, AsyncWindowContext, EventEmitter, FocusHandle, FocusableView,
+    Model, Task, View, WeakView,
+};
+use language::{Anchor, Buffer, OffsetRangeExt, Point, ToPoint};
+use project::{HierarchyItem, Location, Project};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+use ui::{prelude::*, ListItem};
+use util::{ResultExt, TryFutureExt};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+const HIERARCHY_PANEL_KEY: &str = "HierarchyPanel";
+
+#[derive(Serialize, Deserialize)]
+struct SerializedHierarchyPanel {
+    size: Option<Pixels>,
+    position: Option<SerializedDockPosition>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SerializedDockPosition {
+    Left,
+    Bottom,
+    Right,
+}
+
+/// The relation shown by the hierarchy panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HierarchyKind {
+    IncomingCalls,
+    OutgoingCalls,
+    Supertypes,
+    Subtypes,
+}
+
+impl HierarchyKind {
+    fn label(self) -> &'static str {
+        match self {
+            HierarchyKind::IncomingCalls => "Incoming Calls",
+            HierarchyKind::OutgoingCalls => "Outgoing Calls",
+            HierarchyKind::Supertypes => "Supertypes",
+            HierarchyKind::Subtypes => "Subtypes",
+        }
+    }
+
+    fn is_call_hierarchy(self) -> bool {
+        matches!(
+            self,
+            HierarchyKind::IncomingCalls | HierarchyKind::OutgoingCalls
+        )
+    }
+}
+
+struct HierarchyEntry {
+    item: HierarchyItem,
+    /// The calls linking this entry to its parent, empty for type hierarchies.
+    call_sites: Vec<Location>,
+    depth: usize,
+    /// The indices of the child entries, `None` until they are requested.
+    children: Option<Vec<usize>>,
+    expanded: bool,
+}
+
+pub struct HierarchyPanel {
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    focus_handle: FocusHandle,
+    size: Option<Pixels>,
+    position: DockPosition,
+    kind: HierarchyKind,
+    /// The position the hierarchy was requested for, to switch between directions.
+    origin: Option<(Model<Buffer>, Anchor)>,
+    entries: Vec<HierarchyEntry>,
+    roots: Vec<usize>,
+    selected_entry: Option<usize>,
+    loading_roots: bool,
+    prepare_task: Task<Option<()>>,
+    /// Dropping these cancels loading the children of the corresponding entry.
+    loading_children: HashMap<usize, Task<Option<()>>>,
+    pending_serialization: Task<Option<()>>,
+}
+
+impl HierarchyPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(HIERARCHY_PANEL_KEY) })
+            .await
+            .context("loading hierarchy panel")
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedHierarchyPanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = Self::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.size = serialized_panel.size.map(|px| px.round());
+                    if let Some(position) = serialized_panel.position {
+                        panel.position = match position {
+                            SerializedDockPosition::Left => DockPosition::Left,
+                            SerializedDockPosition::Bottom => DockPosition::Bottom,
+                            SerializedDockPosition::Right => DockPosition::Right,
+                        };
+                    }
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let weak_workspace = workspace.weak_handle();
+        cx.new_view(|cx| Self {
+            workspace: weak_workspace,
+            project,
+            focus_handle: cx.focus_handle(),
+            size: None,
+            position: DockPosition::Left,
+            kind: HierarchyKind::IncomingCalls,
+            origin: None,
+            entries: Vec::new(),
+            roots: Vec::new(),
+            selected_entry: None,
+            loading_roots: false,
+            prepare_task: Task::ready(None),
+            loading_children: HashMap::default(),
+            pending_serialization: Task::ready(None),
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let size = self.size;
+        let position = match self.position {
+            DockPosition::Left => SerializedDockPosition::Left,
+            DockPosition::Bottom => SerializedDockPosition::Bottom,
+            DockPosition::Right => SerializedDockPosition::Right,
+        };
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        HIERARCHY_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedHierarchyPanel {
+                            size,
+                            position: Some(position),
+                        })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    /// Replaces the shown hierarchy with the one of the symbol at the given position.
+    pub fn show(
+        &mut self,
+        kind: HierarchyKind,
+        buffer: Model<Buffer>,
+        position: Anchor,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let prepare = self.project.update(cx, |project, cx| {
+            if kind.is_call_hierarchy() {
+                project.prepare_call_hierarchy(&buffer, position, cx)
+            } else {
+                project.prepare_type_hierarchy(&buffer, position, cx)
+            }
+        });
+        self.kind = kind;
+        self.origin = Some((buffer, position));
+        self.entries.clear();
+        self.roots.clear();
+        self.selected_entry = None;
+        self.loading_roots = true;
+        self.loading_children.clear();
+        self.prepare_task = cx.spawn(|this, mut cx| {
+            async move {
+                let items = prepare.await;
+                this.update(&mut cx, |this, cx| {
+                    this.loading_roots = false;
+                    cx.notify();
+                    let items = items?;
+                    this.roots = items
+                        .into_iter()
+                        .map(|item| this.push_entry(item, Vec::new(), 0))
+                        .collect();
+                    // Expand the roots right away, their relatives are what was asked for.
+                    for root in this.roots.clone() {
+                        this.toggle_entry(root, cx);
+                    }
+                    anyhow::Ok(())
+                })?
+            }
+            .log_err()
+        });
+        cx.notify();
+    }
+
+    fn switch_kind(&mut self, kind: HierarchyKind, cx: &mut ViewContext<Self>) {
+        if let Some((buffer, position)) = self.origin.clone() {
+            self.show(kind, buffer, position, cx);
+        }
+    }
+
+    fn push_entry(
+        &mut self,
+        item: HierarchyItem,
+        call_sites: Vec<Location>,
+        depth: usize,
+    ) -> usize {
+        self.entries.push(HierarchyEntry {
+            item,
+            call_sites,
+            depth,
+            children: None,
+            expanded: false,
+        });
+        self.entries.len() - 1
+    }
+
+    fn toggle_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(entry) = self.entries.get_mut(ix) else {
+            return;
+        };
+        entry.expanded = !entry.expanded;
+        if entry.expanded && entry.children.is_none() && !self.loading_children.contains_key(&ix) {
+            self.load_children(ix, cx);
+        }
+        cx.notify();
+    }
+
+    fn load_children(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let item = self.entries[ix].item.clone();
+        let kind = self.kind;
+        let children = self.project.update(cx, |project, cx| {
+            let children: Task<anyhow::Result<Vec<(HierarchyItem, Vec<Location>)>>> = match kind {
+                HierarchyKind::IncomingCalls | HierarchyKind::OutgoingCalls => {
+                    let calls = if kind == HierarchyKind::IncomingCalls {
+                        project.incoming_calls(&item, cx)
+                    } else {
+                        project.outgoing_calls(&item, cx)
+                    };
+                    cx.spawn(|_, _| async move {
+                        Ok(calls
+                            .await?
+                            .into_iter()
+                            .map(|call| (call.item, call.call_sites))
+                            .collect())
+                    })
+                }
+                HierarchyKind::Supertypes | HierarchyKind::Subtypes => {
+                    let types = if kind == HierarchyKind::Supertypes {
+                        project.supertypes(&item, cx)
+                    } else {
+                        project.subtypes(&item, cx)
+                    };
+                    cx.spawn(|_, _| async move {
+                        Ok(types
+                            .await?
+                            .into_iter()
+                            .map(|item| (item, Vec::new()))
+                            .collect())
+                    })
+                }
+            };
+            children
+        });
+
+        let task = cx.spawn(|this, mut cx| {
+            async move {
+                let children = children.await;
+                this.update(&mut cx, |this, cx| {
+                    this.loading_children.remove(&ix);
+                    cx.notify();
+                    let children = match children {
+                        Ok(children) => children,
+                        Err(error) => {
+                            // Collapse the entry so that expanding it again retries.
+                            this.entries[ix].expanded = false;
+                            return Err(error);
+                        }
+                    };
+                    let depth = this.entries[ix].depth + 1;
+                    let children = children
+                        .into_iter()
+                        .map(|(item, call_sites)| this.push_entry(item, call_sites, depth))
+                        .collect();
+                    this.entries[ix].children = Some(children);
+                    Ok(())
+                })?
+            }
+            .log_err()
+        });
+        self.loading_children.insert(ix, task);
+    }
+
+    fn open_entry(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        self.selected_entry = Some(ix);
+        let item = &self.entries[ix].item;
+        self.open_location(
+            item.location.buffer.clone(),
+            item.selection_range.clone(),
+            cx,
+        );
+        cx.notify();
+    }
+
+    fn open_location(
+        &mut self,
+        buffer: Model<Buffer>,
+        range: Range<Anchor>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        workspace.update(cx, |workspace, cx| {
+            let pane = workspace.active_pane().clone();
+            let editor = workspace.open_project_item::<Editor>(pane, buffer.clone(), cx);
+            editor.update(cx, |editor, cx| {
+                let range = range.to_point(buffer.read(cx));
+                editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                    selections.select_ranges([range])
+                });
+            });
+        });
+    }
+
+    fn render_toolbar(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let kinds = if self.kind.is_call_hierarchy() {
+            [HierarchyKind::IncomingCalls, HierarchyKind::OutgoingCalls]
+        } else {
+            [HierarchyKind::Supertypes, HierarchyKind::Subtypes]
+        };
+        h_flex()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .children(kinds.into_iter().map(|kind| {
+                Button::new(kind.label(), kind.label())
+                    .label_size(LabelSize::Small)
+                    .selected(self.kind == kind)
+                    .disabled(self.origin.is_none())
+                    .on_click(cx.listener(move |this, _, cx| this.switch_kind(kind, cx)))
+            }))
+    }
+
+    fn render_entry(&self, ix: usize, rows: &mut Vec<AnyElement>, cx: &mut ViewContext<Self>) {
+        let entry = &self.entries[ix];
+        let detail = entry.item.detail.clone().or_else(|| {
+            // Call sites are more telling than details for call hierarchies.
+            (entry.call_sites.len() > 1).then(|| format!("{} calls", entry.call_sites.len()))
+        });
+        rows.push(
+            ListItem::new(("hierarchy-entry", ix))
+                .indent_level(entry.depth)
+                .selected(self.selected_entry == Some(ix))
+                .toggle(Some(entry.expanded))
+                .on_toggle(cx.listener(move |this, _, cx| this.toggle_entry(ix, cx)))
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(Label::new(entry.item.name.clone()).size(LabelSize::Small))
+                        .children(detail.map(|detail| {
+                            Label::new(detail)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted)
+                        })),
+                )
+                .on_click(cx.listener(move |this, _, cx| this.open_entry(ix, cx)))
+                .into_any_element(),
+        );
+
+        if !entry.expanded {
+            return;
+        }
+        for (site_ix, call_site) in entry.call_sites.iter().enumerate() {
+            rows.push(self.render_call_site(ix, site_ix, call_site, entry.depth + 1, cx));
+        }
+        match &entry.children {
+            Some(children) => {
+                for &child in children {
+                    self.render_entry(child, rows, cx);
+                }
+            }
+            None => rows.push(
+                ListItem::new(("hierarchy-loading", ix))
+                    .indent_level(entry.depth + 1)
+                    .child(
+                        Label::new("Loading…")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .into_any_element(),
+            ),
+        }
+    }
+
+    fn render_call_site(
+        &self,
+        ix: usize,
+        site_ix: usize,
+        call_site: &Location,
+        depth: usize,
+        cx: &mut ViewContext<Self>,
+    ) -> AnyElement {
+        let buffer = call_site.buffer.read(cx);
+        let row = call_site.range.start.to_point(buffer).row;
+        let line = buffer
+            .text_for_range(Point::new(row, 0)..Point::new(row, buffer.line_len(row)))
+            .collect::<String>();
+        let buffer = call_site.buffer.clone();
+        let range = call_site.range.clone();
+        ListItem::new(SharedString::from(format!(
+            "hierarchy-call-site-{ix}-{site_ix}"
+        )))
+        .indent_level(depth)
+        .child(
+            h_flex()
+                .gap_2()
+                .child(
+                    Label::new(format!("{}", row + 1))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                )
+                .child(Label::new(line.trim().to_string()).size(LabelSize::Small)),
+        )
+        .on_click(
+            cx.listener(move |this, _, cx| this.open_location(buffer.clone(), range.clone(), cx)),
+        )
+        .into_any_element()
+    }
+}
+
+impl EventEmitter<PanelEvent> for HierarchyPanel {}
+
+impl FocusableView for HierarchyPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for HierarchyPanel {
+    fn persistent_name() -> &'static str {
+        "Hierarchy Panel"
+    }
+
+    fn position(&self, _: &WindowContext) -> DockPosition {
+        self.position
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        self.position = position;
+        self.serialize(cx);
+    }
+
+    fn size(&self, _: &WindowContext) -> Pixels {
+        self.size.unwrap_or_else(|| match self.position {
+            DockPosition::Left | DockPosition::Right => px(320.),
+            DockPosition::Bottom => px(240.),
+        })
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.size = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, _: &WindowContext) -> Option<IconName> {
+        Some(IconName::ListTree)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Hierarchy Panel")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
+
+impl Render for HierarchyPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let mut rows = Vec::new();
+        for &root in &self.roots {
+            self.render_entry(root, &mut rows, cx);
+        }
+        let message = if self.origin.is_none() {
+            Some("Show the call or type hierarchy of a symbol to explore it here.")
+        } else if rows.is_empty() && !self.loading_roots {
+            Some("No hierarchy found for the symbol.")
+        } else {
+            None
+        };
+
+        v_flex()
+            .key_context("HierarchyPanel")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .child(self.render_toolbar(cx))
+            .child(
+                v_flex()
+                    .id("hierarchy-entries")
+                    .p_1()
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .children(rows)
+                    .children(message.map(|message| {
+                        Label::new(message)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    })),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt as _;
+    use gpui::{TestAppContext, VisualTestContext};
+    use language::{FakeLspAdapter, Language, LanguageConfig, LanguageMatcher};
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    };
+
+    #[gpui::test]
+    async fn test_expand_entries(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/dir",
+            json!({ "main.rs": "fn main() { a() }\nfn a() { b() }\nfn b() {}\n" }),
+        )
+        .await;
+        let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+        let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+        language_registry.add(Arc::new(Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+        )));
+        let mut fake_servers = language_registry.register_fake_lsp_adapter(
+            "Rust",
+            FakeLspAdapter {
+                capabilities: lsp::ServerCapabilities {
+                    call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/dir/main.rs", cx)
+            })
+            .await
+            .unwrap();
+        let fake_server = fake_servers.next().await.unwrap();
+
+        fake_server.handle_request::<lsp::request::CallHierarchyPrepare, _, _>({
+            let b_item = lsp_item("b", 2);
+            move |_, _| {
+                let b_item = b_item.clone();
+                async move { Ok(Some(vec![b_item])) }
+            }
+        });
+        // The callers of `main` can't be found the first time they're requested.
+        let requested_items = Arc::new(Mutex::new(Vec::new()));
+        let fail_main = Arc::new(AtomicBool::new(true));
+        fake_server.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>({
+            let requested_items = requested_items.clone();
+            move |params, _| {
+                requested_items
+                    .lock()
+                    .unwrap()
+                    .push(params.item.name.clone());
+                let calls = match params.item.name.as_str() {
+                    "b" => Ok(Some(vec![caller("a", 1)])),
+                    "a" => Ok(Some(vec![caller("main", 0)])),
+                    _ if fail_main.swap(false, Ordering::SeqCst) => {
+                        Err(anyhow::anyhow!("content modified"))
+                    }
+                    _ => Ok(None),
+                };
+                async move { calls }
+            }
+        });
+
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| HierarchyPanel::new(workspace, cx))
+            .unwrap();
+        let take_requested_items = || std::mem::take(&mut *requested_items.lock().unwrap());
+
+        // Only the callers of the root are requested.
+        panel.update(cx, |panel, cx| {
+            let position = buffer.read(cx).anchor_before(Point::new(2, 3));
+            panel.show(HierarchyKind::IncomingCalls, buffer.clone(), position, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(take_requested_items(), ["b"]);
+        panel.update(cx, |panel, _| {
+            assert_eq!(entries(panel), [("b", true, Some(1)), ("a", false, None)]);
+        });
+
+        // The callers of an entry are requested when it's expanded.
+        panel.update(cx, |panel, cx| panel.toggle_entry(1, cx));
+        cx.run_until_parked();
+        assert_eq!(take_requested_items(), ["a"]);
+        panel.update(cx, |panel, cx| {
+            assert_eq!(
+                entries(panel),
+                [
+                    ("b", true, Some(1)),
+                    ("a", true, Some(1)),
+                    ("main", false, None)
+                ]
+            );
+
+            // Collapsing and expanding again doesn't request them again.
+            panel.toggle_entry(1, cx);
+            panel.toggle_entry(1, cx);
+        });
+        cx.run_until_parked();
+        assert!(take_requested_items().is_empty());
+
+        // An entry whose callers failed to load is collapsed, and expanding it again retries.
+        panel.update(cx, |panel, cx| panel.toggle_entry(2, cx));
+        cx.run_until_parked();
+        assert_eq!(take_requested_items(), ["main"]);
+        panel.update(cx, |panel, cx| {
+            assert_eq!(entries(panel)[2], ("main", false, None));
+            assert!(panel.loading_children.is_empty());
+            panel.toggle_entry(2, cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(take_requested_items(), ["main"]);
+        panel.update(cx, |panel, _| {
+            assert_eq!(entries(panel)[2], ("main", true, Some(0)));
+        });
+    }
+
+    fn lsp_item(name: &str, row: u32) -> lsp::CallHierarchyItem {
+        let range = lsp::Range::new(lsp::Position::new(row, 3), lsp::Position::new(row, 4));
+        lsp::CallHierarchyItem {
+            name: name.to_string(),
+            kind: lsp::SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: lsp::Url::from_file_path("/dir/main.rs").unwrap(),
+            range,
+            selection_range: range,
+            data: None,
+        }
+    }
+
+    fn caller(name: &str, row: u32) -> lsp::CallHierarchyIncomingCall {
+        lsp::CallHierarchyIncomingCall {
+            from: lsp_item(name, row),
+            from_ranges: vec![lsp::Range::new(
+                lsp::Position::new(row, 9),
+                lsp::Position::new(row, 10),
+            )],
+        }
+    }
+
+    /// The name of each entry, whether it's expanded, and the number of its children once they
+    /// are loaded.
+    fn entries(panel: &HierarchyPanel) -> Vec<(&str, bool, Option<usize>)> {
+        panel
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.item.name.as_str(),
+                    entry.expanded,
+                    entry.children.as_ref().map(Vec::len),
+                )
+            })
+            .collect()
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            editor::init_settings(cx);
+            workspace::init_settings(cx);
+            client::init_settings(cx);
+            Project::init_settings(cx);
+        });
+    }
+}
@@ -6,7 +6,7 @@ pub use crate::{
 };
 use crate::{
     diagnostic_set::{DiagnosticEntry, DiagnosticGroup},
-    language_settings::{language_settings, IndentGuideSettings, LanguageSettings},
+    language_settings::{language_settings, FileLanguageSettings, IndentGuideSettings},
     markdown::parse_markdown,
     outline::OutlineItem,
     syntax_map::{
@@ -533,6 +533,21 @@ impl IndentGuide {
     }
 }
 
+/// A range of the buffer that can be folded, as reported by a folds query or a language server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FoldingRange<T> {
+    pub range: Range<T>,
+    pub kind: Option<FoldingRangeKind>,
+}
+
+/// The kind of a [`FoldingRange`], used to fold all the ranges of a kind at once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FoldingRangeKind {
+    Comment,
+    Imports,
+    Region,
+}
+
 impl Buffer {
     /// Create a new buffer with the given base text.
     pub fn local<T: Into<String>>(base_text: T, cx: &mut ModelContext<Self>) -> Self {
@@ -2520,7 +2535,7 @@ impl BufferSnapshot {
         &self,
         position: D,
         cx: &'a AppContext,
-    ) -> &'a LanguageSettings {
+    ) -> FileLanguageSettings<'a> {
         language_settings(self.language_at(position), self.file.as_ref(), cx)
     }
 
@@ -2981,6 +2996,98 @@ impl BufferSnapshot {
         })
     }
 
+    /// Returns the ranges that can be folded, according to the folds queries of the languages in
+    /// the buffer, sorted by their start. Returns `None` if none of these languages has a folds query.
+    ///
+    /// Each range starts at the end of its first line, so that the line stays visible when the range
+    /// is folded. A last line that only contains closing brackets is left out of the range too.
+    pub fn folding_ranges(&self) -> Option<Vec<FoldingRange<Point>>> {
+        let mut syntax_matches = self.syntax.matches(0..self.len(), self, |grammar| {
+            grammar.folds_config.as_ref().map(|config| &config.query)
+        });
+        if syntax_matches.grammars().is_empty() {
+            return None;
+        }
+
+        let configs = syntax_matches
+            .grammars()
+            .iter()
+            .map(|grammar| grammar.folds_config.as_ref())
+            .collect::<Vec<_>>();
+
+        let mut ranges = Vec::new();
+        while let Some(mat) = syntax_matches.peek() {
+            if let Some(config) = configs[mat.grammar_index] {
+                let mut byte_range: Option<Range<usize>> = None;
+                let mut kind = None;
+                for capture in mat.captures {
+                    let Some((_, capture_kind)) =
+                        config.captures.iter().find(|(ix, _)| *ix == capture.index)
+                    else {
+                        continue;
+                    };
+                    let node_range = capture.node.byte_range();
+                    kind = kind.or(*capture_kind);
+                    byte_range = Some(match byte_range {
+                        Some(range) => {
+                            range.start.min(node_range.start)..range.end.max(node_range.end)
+                        }
+                        None => node_range,
+                    });
+                }
+                if let Some(range) = byte_range.and_then(|range| self.folding_range_for(range)) {
+                    ranges.push(FoldingRange { range, kind });
+                }
+            }
+            syntax_matches.advance();
+        }
+
+        ranges.sort_by(|a, b| {
+            a.range
+                .start
+                .cmp(&b.range.start)
+                .then_with(|| b.range.end.cmp(&a.range.end))
+        });
+        // Quantified captures such as `(line_comment)+ @fold.comment` also match every suffix of
+        // the longest run, so drop ranges nested in a range of the same kind.
+        let mut result: Vec<FoldingRange<Point>> = Vec::with_capacity(ranges.len());
+        let mut last_end_by_kind = BTreeMap::new();
+        for range in ranges {
+            if result.last() == Some(&range) {
+                continue;
+            }
+            if let Some(kind) = range.kind {
+                if last_end_by_kind
+                    .get(&kind)
+                    .map_or(false, |end| *end >= range.range.end)
+                {
+                    continue;
+                }
+                last_end_by_kind.insert(kind, range.range.end);
+            }
+            result.push(range);
+        }
+        Some(result)
+    }
+
+    fn folding_range_for(&self, byte_range: Range<usize>) -> Option<Range<Point>> {
+        let start = self.offset_to_point(byte_range.start);
+        let mut end = self.offset_to_point(byte_range.end);
+        if end.column == 0 && end.row > start.row {
+            end = Point::new(end.row - 1, self.line_len(end.row - 1));
+        }
+        let last_line_start = Point::new(end.row, 0);
+        let is_closing_line = end.row > start.row
+            && self
+                .text_for_range(last_line_start..end)
+                .flat_map(|chunk| chunk.chars())
+                .all(|c| c.is_whitespace() || ")]}>,;".contains(c));
+        if is_closing_line {
+            end = Point::new(end.row - 1, self.line_len(end.row - 1));
+        }
+        (end.row > start.row).then(|| Point::new(start.row, self.line_len(start.row))..end)
+    }
+
     pub fn runnable_ranges(
         &self,
         range: Range<Anchor>,
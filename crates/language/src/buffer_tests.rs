@@ -1048,6 +1048,60 @@ fn test_range_for_syntax_ancestor(cx: &mut AppContext) {
     }
 }
 
+#[gpui::test]
+fn test_folding_ranges(cx: &mut AppContext) {
+    let language = rust_lang()
+        .with_folds_query(
+            r#"
+            (block) @fold
+            (line_comment)+ @fold.comment
+            (use_declaration)+ @fold.imports
+            "#,
+        )
+        .unwrap();
+    let text = r#"
+        use a::b;
+        use c::d;
+
+        // one
+        // two
+        fn main() {
+            if true {
+                b();
+            }
+            d(|| { 1 });
+        }
+    "#
+    .unindent();
+
+    cx.new_model(|cx| {
+        let buffer = Buffer::local(text, cx).with_language(Arc::new(language), cx);
+        let snapshot = buffer.snapshot();
+        assert_eq!(
+            snapshot.folding_ranges().unwrap(),
+            [
+                FoldingRange {
+                    range: Point::new(0, 9)..Point::new(1, 9),
+                    kind: Some(FoldingRangeKind::Imports),
+                },
+                FoldingRange {
+                    range: Point::new(3, 6)..Point::new(4, 6),
+                    kind: Some(FoldingRangeKind::Comment),
+                },
+                FoldingRange {
+                    range: Point::new(5, 11)..Point::new(9, 16),
+                    kind: None,
+                },
+                FoldingRange {
+                    range: Point::new(6, 13)..Point::new(7, 12),
+                    kind: None,
+                },
+            ]
+        );
+        buffer
+    });
+}
+
 #[gpui::test]
 fn test_autoindent_with_soft_tabs(cx: &mut AppContext) {
     init_settings(cx, |_| {});
@@ -5,7 +5,7 @@ use theme::SyntaxTheme;
 #[derive(Clone, Debug)]
 pub struct HighlightMap(Arc<[HighlightId]>);
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct HighlightId(pub u32);
 
 const DEFAULT_SYNTAX_HIGHLIGHT_ID: HighlightId = HighlightId(u32::MAX);
@@ -51,6 +51,13 @@ impl HighlightMap {
 }
 
 impl HighlightId {
+    /// Finds the theme's style for the given capture name, the same way as for the captures of
+    /// highlight queries.
+    pub fn for_capture_name(capture_name: &str, theme: &SyntaxTheme) -> Option<Self> {
+        let id = HighlightMap::new(&[capture_name], theme).get(0);
+        (!id.is_default()).then_some(id)
+    }
+
     pub(crate) fn is_default(&self) -> bool {
         *self == DEFAULT_SYNTAX_HIGHLIGHT_ID
     }
@@ -223,6 +223,10 @@ impl CachedLspAdapter {
         self.adapter.process_diagnostics(params)
     }
 
+    pub fn client_command(&self, command: &str) -> Option<ClientCommand> {
+        self.adapter.client_command(command)
+    }
+
     pub async fn process_completions(&self, completion_items: &mut [lsp::CompletionItem]) {
         self.adapter.process_completions(completion_items).await
     }
@@ -503,12 +507,28 @@ pub trait LspAdapter: 'static + Send + Sync {
         Default::default()
     }
 
+    /// Returns how the client runs a command that the language server expects the client to
+    /// handle, such as the command of a code lens, instead of executing it itself.
+    fn client_command(&self, command: &str) -> Option<ClientCommand> {
+        // VS Code's command for showing references, which many language servers use.
+        (command == "editor.action.showReferences").then_some(ClientCommand::ShowReferences)
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     fn as_fake(&self) -> Option<&FakeLspAdapter> {
         None
     }
 }
 
+/// A command of a language server that is run by the client, see [`LspAdapter::client_command`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClientCommand {
+    /// Runs the runnable at the position of the command, like the run indicator in the gutter.
+    RunRunnable,
+    /// Shows the locations passed as the third argument of the command.
+    ShowReferences,
+}
+
 async fn try_fetch_server_binary<L: LspAdapter + 'static + Send + Sync + ?Sized>(
     adapter: &L,
     delegate: &Arc<dyn LspAdapterDelegate>,
@@ -845,6 +865,7 @@ pub struct Grammar {
     pub(crate) brackets_config: Option<BracketConfig>,
     pub(crate) redactions_config: Option<RedactionConfig>,
     pub(crate) runnable_config: Option<RunnableConfig>,
+    pub(crate) folds_config: Option<FoldsConfig>,
     pub(crate) indents_config: Option<IndentConfig>,
     pub outline_config: Option<OutlineConfig>,
     pub embedding_config: Option<EmbeddingConfig>,
@@ -891,6 +912,12 @@ struct RedactionConfig {
     pub redaction_capture_ix: u32,
 }
 
+struct FoldsConfig {
+    query: Query,
+    /// The kind of the ranges captured by each capture index.
+    captures: Vec<(u32, Option<FoldingRangeKind>)>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 enum RunnableCapture {
     Named(SharedString),
@@ -945,6 +972,7 @@ impl Language {
                     override_config: None,
                     redactions_config: None,
                     runnable_config: None,
+                    folds_config: None,
                     error_query: Query::new(&ts_language, "(ERROR) @error").unwrap(),
                     ts_language,
                     highlight_map: Default::default(),
@@ -1005,6 +1033,11 @@ impl Language {
                 .with_runnable_query(query.as_ref())
                 .context("Error loading tests query")?;
         }
+        if let Some(query) = queries.folds {
+            self = self
+                .with_folds_query(query.as_ref())
+                .context("Error loading folds query")?;
+        }
         Ok(self)
     }
 
@@ -1301,6 +1334,35 @@ impl Language {
         Ok(self)
     }
 
+    pub fn with_folds_query(mut self, source: &str) -> anyhow::Result<Self> {
+        let grammar = self
+            .grammar_mut()
+            .ok_or_else(|| anyhow!("cannot mutate grammar"))?;
+
+        let query = Query::new(&grammar.ts_language, source)?;
+        let captures = query
+            .capture_names()
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, name)| {
+                let kind = match *name {
+                    "fold" => None,
+                    "fold.comment" => Some(FoldingRangeKind::Comment),
+                    "fold.imports" => Some(FoldingRangeKind::Imports),
+                    "fold.region" => Some(FoldingRangeKind::Region),
+                    _ => return None,
+                };
+                Some((ix as u32, kind))
+            })
+            .collect::<Vec<_>>();
+
+        if !captures.is_empty() {
+            grammar.folds_config = Some(FoldsConfig { query, captures });
+        }
+
+        Ok(self)
+    }
+
     fn grammar_mut(&mut self) -> Option<&mut Grammar> {
         Arc::get_mut(self.grammar.as_mut()?)
     }
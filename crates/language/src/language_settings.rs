@@ -1,18 +1,22 @@
 //! Provides `language`-related settings.
 
-use crate::{File, Language, LanguageServerName};
+use crate::{File, Language, LanguageServerName, LineEnding};
 use anyhow::Result;
 use collections::{HashMap, HashSet};
 use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
 use gpui::AppContext;
 use itertools::{Either, Itertools};
+use parking_lot::RwLock;
 use schemars::{
     schema::{InstanceType, ObjectValidation, Schema, SchemaObject},
     JsonSchema,
 };
 use serde::{Deserialize, Serialize};
-use settings::{Settings, SettingsLocation, SettingsSources};
-use std::{num::NonZeroU32, path::Path, sync::Arc};
+use settings::{
+    editorconfig::{self, EditorconfigProperties},
+    Settings, SettingsLocation, SettingsSources, SettingsStore,
+};
+use std::{fmt, num::NonZeroU32, ops::Deref, path::Path, sync::Arc};
 use util::serde::default_true;
 
 impl<'a> Into<SettingsLocation<'a>> for &'a dyn File {
@@ -30,13 +34,113 @@ pub fn init(cx: &mut AppContext) {
 }
 
 /// Returns the settings for the specified language from the provided file.
+///
+/// Properties from `.editorconfig` files apply on top of the user's settings,
+/// unless the project's local settings set them too.
 pub fn language_settings<'a>(
     language: Option<&Arc<Language>>,
     file: Option<&Arc<dyn File>>,
     cx: &'a AppContext,
-) -> &'a LanguageSettings {
+) -> FileLanguageSettings<'a> {
     let language_name = language.map(|l| l.name());
-    all_language_settings(file, cx).language(language_name.as_deref())
+    let all_settings = all_language_settings(file, cx);
+    let settings = all_settings.language(language_name.as_deref());
+    let Some(file) = file else {
+        return FileLanguageSettings::Shared(settings);
+    };
+
+    let store = cx.global::<SettingsStore>();
+    let (worktree_id, path) = (file.worktree_id(), file.path());
+    let Some(properties) = store.editorconfig_properties(worktree_id, path) else {
+        return FileLanguageSettings::Shared(settings);
+    };
+
+    // Merging the properties into the settings is done once per file and language, until the
+    // properties or the settings change.
+    let key = (worktree_id, path.clone(), language_name.clone());
+    if let Some((cached_properties, cached_settings)) =
+        all_settings.editorconfig_settings.0.read().get(&key)
+    {
+        if Arc::ptr_eq(cached_properties, &properties) {
+            return FileLanguageSettings::Editorconfig(cached_settings.clone());
+        }
+    }
+
+    let local_settings = store
+        .local_settings_for_path(worktree_id, path)
+        .collect::<Vec<_>>();
+    let set_locally = |key: &str| {
+        local_settings.iter().any(|local_settings| {
+            local_settings.get(key).is_some()
+                || language_name
+                    .as_deref()
+                    .and_then(|name| local_settings.get("languages")?.get(name)?.get(key))
+                    .is_some()
+        })
+    };
+    let mut merged_settings = settings.clone();
+    merge_editorconfig(&mut merged_settings, &properties, set_locally);
+    let merged_settings = Arc::new(merged_settings);
+    all_settings
+        .editorconfig_settings
+        .0
+        .write()
+        .insert(key, (properties, merged_settings.clone()));
+    FileLanguageSettings::Editorconfig(merged_settings)
+}
+
+/// The [`LanguageSettings`] of a file, which are shared with other files of the same
+/// language unless `.editorconfig` properties apply to it.
+#[derive(Debug, Clone)]
+pub enum FileLanguageSettings<'a> {
+    Shared(&'a LanguageSettings),
+    Editorconfig(Arc<LanguageSettings>),
+}
+
+impl FileLanguageSettings<'_> {
+    pub fn into_owned(self) -> LanguageSettings {
+        match self {
+            Self::Shared(settings) => settings.clone(),
+            Self::Editorconfig(settings) => settings.as_ref().clone(),
+        }
+    }
+}
+
+impl Deref for FileLanguageSettings<'_> {
+    type Target = LanguageSettings;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Shared(settings) => settings,
+            Self::Editorconfig(settings) => settings,
+        }
+    }
+}
+
+/// The language settings of files with `.editorconfig` properties, along with the properties
+/// they were merged with.
+#[derive(Default)]
+struct EditorconfigSettings(
+    RwLock<
+        HashMap<
+            (usize, Arc<Path>, Option<Arc<str>>),
+            (Arc<EditorconfigProperties>, Arc<LanguageSettings>),
+        >,
+    >,
+);
+
+/// The cache is never shared, since it depends on the settings it belongs to.
+impl Clone for EditorconfigSettings {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for EditorconfigSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EditorconfigSettings")
+            .finish_non_exhaustive()
+    }
 }
 
 /// Returns the settings for all languages from the provided file.
@@ -56,6 +160,7 @@ pub struct AllLanguageSettings {
     defaults: LanguageSettings,
     languages: HashMap<Arc<str>, LanguageSettings>,
     pub(crate) file_types: HashMap<Arc<str>, GlobSet>,
+    editorconfig_settings: EditorconfigSettings,
 }
 
 /// The settings for a particular language.
@@ -120,6 +225,15 @@ pub struct LanguageSettings {
     pub code_actions_on_format: HashMap<String, bool>,
     /// Whether to perform linked edits
     pub linked_edits: bool,
+    /// Whether to overlay highlights from the language server's semantic tokens.
+    pub semantic_tokens: bool,
+    /// Whether to show code lenses provided by the language server.
+    pub code_lens: bool,
+    /// Whether to fold by the folding ranges of language servers and syntax trees.
+    pub folding_ranges: bool,
+    /// The line ending to use when saving files, as set by `.editorconfig` files.
+    #[serde(skip)]
+    pub line_ending: Option<LineEnding>,
 }
 
 impl LanguageSettings {
@@ -340,6 +454,21 @@ pub struct LanguageSettingsContent {
     ///
     /// Default: true
     pub linked_edits: Option<bool>,
+    /// Whether to overlay highlights from the language server's semantic tokens on top of
+    /// the tree-sitter highlights, e.g. to distinguish mutable variables or macros.
+    ///
+    /// Default: false
+    pub semantic_tokens: Option<bool>,
+    /// Whether to show code lenses provided by the language server above the lines
+    /// they refer to, e.g. reference counts or actions to run a test.
+    ///
+    /// Default: false
+    pub code_lens: Option<bool>,
+    /// Whether to fold by the folding ranges that language servers and `folds.scm` queries
+    /// report, instead of by indentation.
+    ///
+    /// Default: true
+    pub folding_ranges: Option<bool>,
 }
 
 /// The contents of the inline completion settings.
@@ -746,6 +875,7 @@ impl settings::Settings for AllLanguageSettings {
             defaults,
             languages,
             file_types,
+            editorconfig_settings: EditorconfigSettings::default(),
         })
     }
 
@@ -823,6 +953,9 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
         src.code_actions_on_format.clone(),
     );
     merge(&mut settings.linked_edits, src.linked_edits);
+    merge(&mut settings.semantic_tokens, src.semantic_tokens);
+    merge(&mut settings.code_lens, src.code_lens);
+    merge(&mut settings.folding_ranges, src.folding_ranges);
 
     merge(
         &mut settings.preferred_line_length,
@@ -856,6 +989,61 @@ fn merge_settings(settings: &mut LanguageSettings, src: &LanguageSettingsContent
     merge(&mut settings.inlay_hints, src.inlay_hints);
 }
 
+fn merge_editorconfig(
+    settings: &mut LanguageSettings,
+    properties: &EditorconfigProperties,
+    set_locally: impl Fn(&str) -> bool,
+) {
+    fn merge<T>(target: &mut T, value: Option<T>, overridden: bool) {
+        if let Some(value) = value {
+            if !overridden {
+                *target = value;
+            }
+        }
+    }
+
+    merge(
+        &mut settings.hard_tabs,
+        properties
+            .indent_style
+            .map(|style| style == editorconfig::IndentStyle::Tab),
+        set_locally("hard_tabs"),
+    );
+    merge(
+        &mut settings.tab_size,
+        properties.indent_width(),
+        set_locally("tab_size"),
+    );
+    merge(
+        &mut settings.remove_trailing_whitespace_on_save,
+        properties.trim_trailing_whitespace,
+        set_locally("remove_trailing_whitespace_on_save"),
+    );
+    merge(
+        &mut settings.ensure_final_newline_on_save,
+        properties.insert_final_newline,
+        set_locally("ensure_final_newline_on_save"),
+    );
+    merge(
+        &mut settings.preferred_line_length,
+        properties
+            .max_line_length
+            .and_then(|max_line_length| match max_line_length {
+                editorconfig::MaxLineLength::Columns(columns) => Some(columns),
+                editorconfig::MaxLineLength::Off => None,
+            }),
+        set_locally("preferred_line_length"),
+    );
+    settings.line_ending = properties
+        .end_of_line
+        .and_then(|end_of_line| match end_of_line {
+            editorconfig::EndOfLine::Lf => Some(LineEnding::Unix),
+            editorconfig::EndOfLine::Crlf => Some(LineEnding::Windows),
+            // Zed does not support old Mac line endings.
+            editorconfig::EndOfLine::Cr => None,
+        });
+}
+
 /// Allows to enable/disable formatting with Prettier
 /// and configure default Prettier, used when no project-level Prettier installation is found.
 /// Prettier formatting is disabled by default.
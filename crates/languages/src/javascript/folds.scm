@@ -0,0 +1,9 @@
+(_ "{" "}") @fold
+(_ "(" ")") @fold
+(_ "[" "]") @fold
+
+(comment)+ @fold.comment
+
+(import_statement)+ @fold.imports
+
+(jsx_element) @fold
@@ -0,0 +1,28 @@
+(_ "{" "}") @fold
+(_ "(" ")") @fold
+(_ "[" "]") @fold
+
+[
+    (function_definition)
+    (class_definition)
+    (for_statement)
+    (while_statement)
+    (with_statement)
+    (elif_clause)
+    (else_clause)
+    (except_clause)
+    (finally_clause)
+] @fold
+
+(if_statement
+    "if" @fold
+    consequence: (block) @fold)
+
+(try_statement
+    "try" @fold
+    body: (block) @fold)
+
+(comment)+ @fold.comment
+
+(import_statement)+ @fold.imports
+(import_from_statement)+ @fold.imports
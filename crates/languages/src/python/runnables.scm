@@ -29,3 +29,22 @@
             )
         )
 )
+
+; module-level functions whose names start with `test`, collected by pytest
+(
+    (module
+        [
+            (function_definition
+                name: (identifier) @run @_pytest_function_name
+                (#match? @_pytest_function_name "^test.*")
+            )
+            (decorated_definition
+                definition: (function_definition
+                    name: (identifier) @run @_pytest_function_name
+                    (#match? @_pytest_function_name "^test.*")
+                )
+            )
+        ] @python-pytest-function
+    )
+    (#set! tag python-pytest-function)
+)
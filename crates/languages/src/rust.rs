@@ -176,6 +176,18 @@ impl LspAdapter for RustLspAdapter {
         Some("rust-analyzer/flycheck".into())
     }
 
+    fn client_command(&self, command: &str) -> Option<ClientCommand> {
+        match command {
+            "rust-analyzer.runSingle" | "rust-analyzer.debugSingle" => {
+                Some(ClientCommand::RunRunnable)
+            }
+            "rust-analyzer.showReferences" | "editor.action.showReferences" => {
+                Some(ClientCommand::ShowReferences)
+            }
+            _ => None,
+        }
+    }
+
     fn process_diagnostics(&self, params: &mut lsp::PublishDiagnosticsParams) {
         lazy_static! {
             static ref REGEX: Regex = Regex::new("(?m)`([^`]+)\n`$").unwrap();
@@ -0,0 +1,8 @@
+(_ "{" "}") @fold
+(_ "(" ")") @fold
+(_ "[" "]") @fold
+
+(line_comment)+ @fold.comment
+(block_comment) @fold.comment
+
+(use_declaration)+ @fold.imports
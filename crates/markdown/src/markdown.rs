@@ -529,6 +529,33 @@ impl Element for MarkdownElement {
                             // Without `w_0`, text doesn't wrap to the width of the container.
                             builder.push_div(div().flex_1().w_0());
                         }
+                        MarkdownTag::Table(_) => {
+                            builder.push_div(
+                                div()
+                                    .mb_2()
+                                    .w_full()
+                                    .border_1()
+                                    .border_color(self.style.rule_color),
+                            );
+                        }
+                        MarkdownTag::TableHead => {
+                            builder.push_text_style(TextStyleRefinement {
+                                font_weight: Some(FontWeight::BOLD),
+                                ..Default::default()
+                            });
+                            builder.push_div(
+                                div()
+                                    .h_flex()
+                                    .border_b_1()
+                                    .border_color(self.style.rule_color),
+                            );
+                        }
+                        MarkdownTag::TableRow => builder.push_div(div().h_flex()),
+                        MarkdownTag::TableCell => {
+                            // Without `w_0`, the cells take the width of their text instead of
+                            // sharing the width of the table.
+                            builder.push_div(div().flex_1().w_0().px_2().py_1());
+                        }
                         MarkdownTag::Emphasis => builder.push_text_style(TextStyleRefinement {
                             font_style: Some(FontStyle::Italic),
                             ..Default::default()
@@ -579,6 +606,13 @@ impl Element for MarkdownElement {
                         builder.pop_div();
                         builder.pop_div();
                     }
+                    MarkdownTagEnd::Table => builder.pop_div(),
+                    MarkdownTagEnd::TableHead => {
+                        builder.pop_div();
+                        builder.pop_text_style();
+                    }
+                    MarkdownTagEnd::TableRow => builder.pop_div(),
+                    MarkdownTagEnd::TableCell => builder.pop_div(),
                     MarkdownTagEnd::Emphasis => builder.pop_text_style(),
                     MarkdownTagEnd::Strong => builder.pop_text_style(),
                     MarkdownTagEnd::Strikethrough => builder.pop_text_style(),
@@ -1040,3 +1074,60 @@ impl RenderedText {
             .find(|link| link.source_range.contains(&source_index))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{px, size, TestAppContext};
+
+    #[gpui::test]
+    fn test_table(cx: &mut TestAppContext) {
+        let style = MarkdownStyle {
+            code_block: Default::default(),
+            inline_code: Default::default(),
+            block_quote: Default::default(),
+            link: Default::default(),
+            rule_color: Hsla::default(),
+            block_quote_border_color: Hsla::default(),
+            syntax: Arc::new(SyntaxTheme::default()),
+            selection_background_color: Hsla::default(),
+        };
+        let (markdown, cx) = cx.add_window_view(|cx| {
+            Markdown::new(
+                "| a | b |\n|---|---|\n| 1 | 2 |\n".into(),
+                style.clone(),
+                None,
+                cx,
+            )
+        });
+        // The source is parsed in the background.
+        cx.run_until_parked();
+
+        let (rendered, _) = cx.draw(point(px(0.), px(0.)), size(px(400.), px(400.)), |_| {
+            MarkdownElement::new(markdown, style, None)
+        });
+        let cells = rendered
+            .text
+            .lines
+            .iter()
+            .map(|line| (line.layout.text(), line.layout.bounds()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cells
+                .iter()
+                .map(|(text, _)| text.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "b", "1", "2"]
+        );
+
+        // The cells of a row are side by side, and the rows are one below the other.
+        let [(_, a), (_, b), (_, one), (_, two)] = cells.as_slice() else {
+            unreachable!()
+        };
+        assert_eq!(a.top(), b.top());
+        assert!(a.right() <= b.left());
+        assert_eq!(one.top(), two.top());
+        assert_eq!(one.left(), a.left());
+        assert!(a.bottom() <= one.top());
+    }
+}
@@ -10,7 +10,7 @@ use gpui::{AppContext, EventEmitter, Model, ModelContext};
 use itertools::Itertools;
 use language::{
     char_kind,
-    language_settings::{language_settings, LanguageSettings},
+    language_settings::{language_settings, FileLanguageSettings},
     AutoindentMode, Buffer, BufferChunks, BufferRow, BufferSnapshot, Capability, CharKind, Chunk,
     CursorShape, DiagnosticEntry, File, IndentGuide, IndentSize, Language, LanguageScope,
     OffsetRangeExt, OffsetUtf16, Outline, OutlineItem, Point, PointUtf16, Selection, TextDimension,
@@ -1667,7 +1667,7 @@ impl MultiBuffer {
         &self,
         point: T,
         cx: &'a AppContext,
-    ) -> &'a LanguageSettings {
+    ) -> FileLanguageSettings<'a> {
         let mut language = None;
         let mut file = None;
         if let Some((buffer, offset, _)) = self.point_to_buffer_offset(point, cx) {
@@ -3386,7 +3386,7 @@ impl MultiBufferSnapshot {
         &'a self,
         point: T,
         cx: &'a AppContext,
-    ) -> &'a LanguageSettings {
+    ) -> FileLanguageSettings<'a> {
         let mut language = None;
         let mut file = None;
         if let Some((buffer, offset)) = self.point_to_buffer_offset(point) {
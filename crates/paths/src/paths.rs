@@ -156,6 +156,14 @@ pub fn themes_dir() -> &'static PathBuf {
     THEMES_DIR.get_or_init(|| config_dir().join("themes"))
 }
 
+/// Returns the path to the snippets directory.
+///
+/// This is where the user's snippet files are stored.
+pub fn snippets_dir() -> &'static PathBuf {
+    static SNIPPETS_DIR: OnceLock<PathBuf> = OnceLock::new();
+    SNIPPETS_DIR.get_or_init(|| config_dir().join("snippets"))
+}
+
 /// Returns the path to the contexts directory.
 ///
 /// This is where the saved contexts from the Assistant are stored.
@@ -242,6 +250,12 @@ pub fn local_tasks_file_relative_path() -> &'static Path {
     LOCAL_TASKS_FILE_RELATIVE_PATH.get_or_init(|| Path::new(".zed/tasks.json"))
 }
 
+/// Returns the relative path to a `debug.json` file within a project.
+pub fn local_debug_file_relative_path() -> &'static Path {
+    static LOCAL_DEBUG_FILE_RELATIVE_PATH: OnceLock<&Path> = OnceLock::new();
+    LOCAL_DEBUG_FILE_RELATIVE_PATH.get_or_init(|| Path::new(".zed/debug.json"))
+}
+
 /// Returns the relative path to a `.vscode/tasks.json` file within a project.
 pub fn local_vscode_tasks_file_relative_path() -> &'static Path {
     static LOCAL_VSCODE_TASKS_FILE_RELATIVE_PATH: OnceLock<&Path> = OnceLock::new();
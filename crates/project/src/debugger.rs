@@ -0,0 +1,691 @@
+use crate::{Event, Project};
+use anyhow::{anyhow, Context as _, Result};
+use collections::HashMap;
+use dap::{
+    events, requests, DebugAdapterBinary, DebugAdapterClient, DebugAdapterClientId,
+    DebugAdapterConfig, DebugConfigFile, DebugRequestKind, Scope, ScopesArguments,
+    SetBreakpointsArguments, Source, SourceBreakpoint, StackFrame, StackTraceArguments,
+    StoppedEventBody, Thread, ThreadArguments, Variable, VariablesArguments,
+};
+use futures::{channel::oneshot, future::LocalBoxFuture, select, FutureExt};
+use gpui::{AppContext, Context, EventEmitter, Model, ModelContext, Task};
+use language::{Buffer, LocalFile as _};
+use paths::local_debug_file_relative_path;
+use std::{path::PathBuf, sync::Arc};
+use text::{BufferId, ToPoint as _};
+use util::ResultExt;
+use worktree::WorktreeId;
+
+/// The maximum number of bytes of debuggee output retained by a [`DebugSession`].
+const MAX_OUTPUT_LEN: usize = 1024 * 1024;
+
+/// Breakpoints and running debug sessions of a project.
+#[derive(Default)]
+pub struct Debugger {
+    breakpoints: HashMap<PathBuf, FileBreakpoints>,
+    /// The files of the open buffers whose breakpoints are anchored in them.
+    anchored_buffers: HashMap<BufferId, PathBuf>,
+    sessions: Vec<(Model<DebugSession>, gpui::Subscription)>,
+    next_client_id: usize,
+}
+
+/// The breakpoints of a file. While the file is open in a buffer, they are anchored in it so
+/// that they follow edits, and otherwise only their rows are kept.
+#[derive(Default)]
+struct FileBreakpoints {
+    rows: Vec<u32>,
+    anchors: Option<(BufferId, Vec<text::Anchor>)>,
+}
+
+impl FileBreakpoints {
+    fn rows(&self, snapshot: Option<&text::BufferSnapshot>) -> Vec<u32> {
+        match (&self.anchors, snapshot) {
+            (Some((buffer_id, anchors)), Some(snapshot)) if *buffer_id == snapshot.remote_id() => {
+                let mut rows = anchors
+                    .iter()
+                    .map(|anchor| anchor.to_point(snapshot).row)
+                    .collect::<Vec<_>>();
+                rows.sort_unstable();
+                rows.dedup();
+                rows
+            }
+            _ => self.rows.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugSessionStatus {
+    /// The debug adapter is being initialized.
+    Starting,
+    /// The debuggee is running.
+    Running,
+    /// The debuggee is paused, e.g. at a breakpoint.
+    Stopped,
+    /// The debuggee has exited, or the session was ended.
+    Terminated,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DebugSessionEvent {
+    /// The debuggee was paused, and its threads and stack frames were refreshed.
+    Stopped,
+    Continued,
+    Terminated,
+    Output,
+}
+
+/// A session with a running debug adapter.
+pub struct DebugSession {
+    config: DebugAdapterConfig,
+    client: Arc<DebugAdapterClient>,
+    status: DebugSessionStatus,
+    threads: Vec<Thread>,
+    active_thread_id: Option<u64>,
+    stack_frames: Vec<StackFrame>,
+    output: String,
+    _subscriptions: Vec<dap::Subscription>,
+}
+
+impl EventEmitter<DebugSessionEvent> for DebugSession {}
+
+impl DebugSession {
+    fn new(
+        client: Arc<DebugAdapterClient>,
+        config: DebugAdapterConfig,
+        initialized_tx: oneshot::Sender<()>,
+        cx: &mut ModelContext<Self>,
+    ) -> Self {
+        let this = cx.weak_model();
+        let mut initialized_tx = Some(initialized_tx);
+        let subscriptions = vec![
+            client.on_event::<events::Initialized, _>(move |_, _| {
+                if let Some(initialized_tx) = initialized_tx.take() {
+                    initialized_tx.send(()).ok();
+                }
+            }),
+            client.on_event::<events::Stopped, _>({
+                let this = this.clone();
+                move |body, mut cx| {
+                    this.update(&mut cx, |this, cx| this.handle_stopped(body, cx))
+                        .ok();
+                }
+            }),
+            client.on_event::<events::Continued, _>({
+                let this = this.clone();
+                move |_, mut cx| {
+                    this.update(&mut cx, |this, cx| this.set_running(cx)).ok();
+                }
+            }),
+            client.on_event::<events::Terminated, _>({
+                let this = this.clone();
+                move |_, mut cx| {
+                    this.update(&mut cx, |this, cx| this.set_terminated(cx))
+                        .ok();
+                }
+            }),
+            client.on_event::<events::Exited, _>({
+                let this = this.clone();
+                move |body, mut cx| {
+                    this.update(&mut cx, |this, cx| {
+                        this.push_output(
+                            &format!("Process exited with code {}\n", body.exit_code),
+                            cx,
+                        );
+                    })
+                    .ok();
+                }
+            }),
+            client.on_event::<events::Output, _>({
+                let this = this.clone();
+                move |body, mut cx| {
+                    this.update(&mut cx, |this, cx| this.push_output(&body.output, cx))
+                        .ok();
+                }
+            }),
+        ];
+
+        Self {
+            config,
+            client,
+            status: DebugSessionStatus::Starting,
+            threads: Vec::new(),
+            active_thread_id: None,
+            stack_frames: Vec::new(),
+            output: String::new(),
+            _subscriptions: subscriptions,
+        }
+    }
+
+    pub fn id(&self) -> DebugAdapterClientId {
+        self.client.id()
+    }
+
+    pub fn config(&self) -> &DebugAdapterConfig {
+        &self.config
+    }
+
+    pub fn status(&self) -> DebugSessionStatus {
+        self.status
+    }
+
+    pub fn threads(&self) -> &[Thread] {
+        &self.threads
+    }
+
+    pub fn active_thread_id(&self) -> Option<u64> {
+        self.active_thread_id
+    }
+
+    /// The stack frames of the active thread, innermost first.
+    pub fn stack_frames(&self) -> &[StackFrame] {
+        &self.stack_frames
+    }
+
+    /// The output produced by the debuggee and the debug adapter.
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    fn handle_stopped(&mut self, body: StoppedEventBody, cx: &mut ModelContext<Self>) {
+        self.status = DebugSessionStatus::Stopped;
+        let client = self.client.clone();
+        let thread_id = body.thread_id.or(self.active_thread_id);
+        cx.spawn(|this, mut cx| async move {
+            let threads = client.request::<requests::Threads>(()).await?.threads;
+            let thread_id = thread_id.or_else(|| threads.first().map(|thread| thread.id));
+            let stack_frames = match thread_id {
+                Some(thread_id) => Self::fetch_stack_frames(&client, thread_id).await?,
+                None => Vec::new(),
+            };
+            this.update(&mut cx, |this, cx| {
+                this.threads = threads;
+                this.active_thread_id = thread_id;
+                this.stack_frames = stack_frames;
+                cx.emit(DebugSessionEvent::Stopped);
+                cx.notify();
+            })
+        })
+        .detach_and_log_err(cx);
+        cx.notify();
+    }
+
+    async fn fetch_stack_frames(
+        client: &DebugAdapterClient,
+        thread_id: u64,
+    ) -> Result<Vec<StackFrame>> {
+        Ok(client
+            .request::<requests::StackTrace>(StackTraceArguments {
+                thread_id,
+                start_frame: None,
+                levels: None,
+            })
+            .await?
+            .stack_frames)
+    }
+
+    fn set_running(&mut self, cx: &mut ModelContext<Self>) {
+        if self.status == DebugSessionStatus::Terminated {
+            return;
+        }
+        self.status = DebugSessionStatus::Running;
+        self.stack_frames.clear();
+        cx.emit(DebugSessionEvent::Continued);
+        cx.notify();
+    }
+
+    fn set_terminated(&mut self, cx: &mut ModelContext<Self>) {
+        if self.status == DebugSessionStatus::Terminated {
+            return;
+        }
+        self.status = DebugSessionStatus::Terminated;
+        self.stack_frames.clear();
+        cx.emit(DebugSessionEvent::Terminated);
+        cx.notify();
+    }
+
+    fn push_output(&mut self, output: &str, cx: &mut ModelContext<Self>) {
+        self.output.push_str(output);
+        if self.output.len() > MAX_OUTPUT_LEN {
+            let mut start = self.output.len() - MAX_OUTPUT_LEN;
+            while !self.output.is_char_boundary(start) {
+                start += 1;
+            }
+            self.output.drain(..start);
+        }
+        cx.emit(DebugSessionEvent::Output);
+        cx.notify();
+    }
+
+    /// Makes the given thread the active one, fetching its stack frames.
+    pub fn select_thread(
+        &mut self,
+        thread_id: u64,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        self.active_thread_id = Some(thread_id);
+        let client = self.client.clone();
+        cx.spawn(|this, mut cx| async move {
+            let stack_frames = Self::fetch_stack_frames(&client, thread_id).await?;
+            this.update(&mut cx, |this, cx| {
+                if this.active_thread_id == Some(thread_id) {
+                    this.stack_frames = stack_frames;
+                    cx.notify();
+                }
+            })
+        })
+    }
+
+    pub fn continue_thread(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<requests::Continue>(cx)
+    }
+
+    pub fn step_over(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<requests::Next>(cx)
+    }
+
+    pub fn step_in(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<requests::StepIn>(cx)
+    }
+
+    pub fn step_out(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        self.resume::<requests::StepOut>(cx)
+    }
+
+    fn resume<R>(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>>
+    where
+        R: dap::Request<Arguments = ThreadArguments>,
+    {
+        let Some(thread_id) = self.active_thread_id else {
+            return Task::ready(Err(anyhow!("no active thread")));
+        };
+        if self.status != DebugSessionStatus::Stopped {
+            return Task::ready(Err(anyhow!("debuggee is not stopped")));
+        }
+
+        let request = self.client.request::<R>(ThreadArguments { thread_id });
+        self.set_running(cx);
+        cx.background_executor().spawn(async move {
+            request.await?;
+            Ok(())
+        })
+    }
+
+    pub fn pause(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let Some(thread_id) = self
+            .active_thread_id
+            .or_else(|| self.threads.first().map(|thread| thread.id))
+        else {
+            return Task::ready(Err(anyhow!("no thread to pause")));
+        };
+        let request = self
+            .client
+            .request::<requests::Pause>(ThreadArguments { thread_id });
+        cx.background_executor().spawn(request)
+    }
+
+    /// Ends the session, terminating the debuggee if it was launched by the debug adapter.
+    pub fn stop(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let client = self.client.clone();
+        let terminate_debuggee = self.config.request == DebugRequestKind::Launch;
+        self.set_terminated(cx);
+        cx.background_executor()
+            .spawn(async move { client.shutdown(terminate_debuggee).await })
+    }
+
+    pub fn scopes(&self, frame_id: u64, cx: &AppContext) -> Task<Result<Vec<Scope>>> {
+        let request = self
+            .client
+            .request::<requests::Scopes>(ScopesArguments { frame_id });
+        cx.background_executor()
+            .spawn(async move { Ok(request.await?.scopes) })
+    }
+
+    /// Fetches the children of a scope or of a structured variable.
+    pub fn variables(
+        &self,
+        variables_reference: u64,
+        cx: &AppContext,
+    ) -> Task<Result<Vec<Variable>>> {
+        let request = self
+            .client
+            .request::<requests::Variables>(VariablesArguments {
+                variables_reference,
+            });
+        cx.background_executor()
+            .spawn(async move { Ok(request.await?.variables) })
+    }
+
+    fn set_breakpoints(&self, path: PathBuf, lines: Vec<u64>, cx: &AppContext) -> Task<Result<()>> {
+        let request = self
+            .client
+            .request::<requests::SetBreakpoints>(set_breakpoints_arguments(path, lines));
+        cx.background_executor().spawn(async move {
+            request.await?;
+            Ok(())
+        })
+    }
+}
+
+fn set_breakpoints_arguments(path: PathBuf, lines: Vec<u64>) -> SetBreakpointsArguments {
+    SetBreakpointsArguments {
+        source: Source {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            path: Some(path.to_string_lossy().into_owned()),
+            source_reference: None,
+        },
+        breakpoints: lines
+            .into_iter()
+            .map(|line| SourceBreakpoint {
+                line,
+                column: None,
+                condition: None,
+            })
+            .collect(),
+        source_modified: None,
+    }
+}
+
+impl Project {
+    /// Loads the debug configurations defined in the `.zed/debug.json` file of each local worktree.
+    pub fn debug_configurations(
+        &self,
+        cx: &AppContext,
+    ) -> Task<Vec<(WorktreeId, DebugAdapterConfig)>> {
+        let fs = self.fs.clone();
+        let config_paths = self
+            .visible_worktrees(cx)
+            .filter(|worktree| worktree.read(cx).is_local())
+            .map(|worktree| {
+                let worktree = worktree.read(cx);
+                (
+                    worktree.id(),
+                    worktree.abs_path().join(local_debug_file_relative_path()),
+                )
+            })
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            let mut configurations = Vec::new();
+            for (worktree_id, config_path) in config_paths {
+                if !fs.is_file(&config_path).await {
+                    continue;
+                }
+                let file = async { DebugConfigFile::parse(&fs.load(&config_path).await?) }
+                    .await
+                    .with_context(|| format!("failed to load {config_path:?}"))
+                    .log_err();
+                if let Some(file) = file {
+                    configurations.extend(file.0.into_iter().map(|config| (worktree_id, config)));
+                }
+            }
+            configurations
+        })
+    }
+
+    pub fn debug_sessions(&self) -> impl Iterator<Item = &Model<DebugSession>> {
+        self.debugger.sessions.iter().map(|(session, _)| session)
+    }
+
+    /// Launches the debug adapter of the given configuration and starts debugging with it.
+    pub fn start_debug_session(
+        &mut self,
+        worktree_id: WorktreeId,
+        config: DebugAdapterConfig,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<DebugSession>>> {
+        if !self.is_local() {
+            return Task::ready(Err(anyhow!(
+                "debugging is only supported in local projects"
+            )));
+        }
+        let Some(worktree) = self.worktree_for_id(worktree_id, cx) else {
+            return Task::ready(Err(anyhow!("no worktree with id {worktree_id:?}")));
+        };
+        let worktree_root = worktree.read(cx).abs_path();
+        let working_dir = match &config.cwd {
+            Some(cwd) => worktree_root.join(cwd),
+            None => worktree_root.to_path_buf(),
+        };
+        let id = DebugAdapterClientId(self.debugger.next_client_id);
+        self.debugger.next_client_id += 1;
+
+        cx.spawn(|this, mut cx| async move {
+            let binary = DebugAdapterBinary::from_config(&config);
+            let client = DebugAdapterClient::new(id, binary, &working_dir, cx.clone())?;
+            this.update(&mut cx, |this, cx| {
+                this.start_debug_session_with_client(client, config, cx)
+            })?
+            .await
+        })
+    }
+
+    /// Starts debugging with an already running debug adapter.
+    pub fn start_debug_session_with_client(
+        &mut self,
+        client: DebugAdapterClient,
+        config: DebugAdapterConfig,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Model<DebugSession>>> {
+        let client = Arc::new(client);
+        let (initialized_tx, initialized_rx) = oneshot::channel();
+        let session = cx
+            .new_model(|cx| DebugSession::new(client.clone(), config.clone(), initialized_tx, cx));
+        let subscription = cx.subscribe(&session, |this, session, event, cx| {
+            if *event == DebugSessionEvent::Terminated {
+                this.debugger
+                    .sessions
+                    .retain(|(existing, _)| *existing != session);
+                cx.emit(Event::DebugSessionsChanged);
+            }
+        });
+        self.debugger.sessions.push((session.clone(), subscription));
+        cx.emit(Event::DebugSessionsChanged);
+
+        cx.spawn(|this, mut cx| async move {
+            let result = async {
+                let capabilities = client.initialize(config.adapter_id()).await?;
+
+                // Some adapters only respond to `launch` once the configuration is done,
+                // which happens after they send the `initialized` event.
+                let arguments = config.request_arguments();
+                let launch: LocalBoxFuture<Result<()>> = match config.request {
+                    DebugRequestKind::Launch => {
+                        client.request::<requests::Launch>(arguments).boxed_local()
+                    }
+                    DebugRequestKind::Attach => {
+                        client.request::<requests::Attach>(arguments).boxed_local()
+                    }
+                };
+                let mut launch = launch.fuse();
+                let mut initialized = initialized_rx.fuse();
+                let launched = select! {
+                    result = launch => {
+                        result?;
+                        true
+                    }
+                    _ = initialized => false,
+                };
+                if launched {
+                    initialized
+                        .await
+                        .context("debug adapter shut down before being initialized")?;
+                }
+
+                let breakpoints = this.update(&mut cx, |this, cx| this.breakpoint_lines(cx))?;
+                for (path, lines) in breakpoints {
+                    client
+                        .request::<requests::SetBreakpoints>(set_breakpoints_arguments(path, lines))
+                        .await
+                        .log_err();
+                }
+                if capabilities.supports_configuration_done_request == Some(true) {
+                    client
+                        .request::<requests::ConfigurationDone>(Default::default())
+                        .await?;
+                }
+                if !launched {
+                    launch.await?;
+                }
+                anyhow::Ok(())
+            }
+            .await;
+
+            match result {
+                Ok(()) => {
+                    session.update(&mut cx, |session, cx| {
+                        if session.status == DebugSessionStatus::Starting {
+                            session.set_running(cx);
+                        }
+                    })?;
+                    Ok(session)
+                }
+                Err(error) => {
+                    session
+                        .update(&mut cx, |session, cx| session.stop(cx))?
+                        .detach();
+                    Err(error)
+                }
+            }
+        })
+    }
+
+    /// Returns the positions of the breakpoints set in the given buffer.
+    pub fn breakpoints_for_buffer(&self, buffer_id: BufferId) -> &[text::Anchor] {
+        self.debugger
+            .anchored_buffers
+            .get(&buffer_id)
+            .and_then(|path| self.debugger.breakpoints.get(path)?.anchors.as_ref())
+            .map_or(&[], |(_, anchors)| anchors.as_slice())
+    }
+
+    /// Anchors the breakpoints of the buffer's file in it, so that they follow its edits until it's
+    /// released.
+    pub(crate) fn anchor_breakpoints(
+        &mut self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(path) = buffer_abs_path(buffer, cx) else {
+            return;
+        };
+        let buffer_id = buffer.read(cx).remote_id();
+        if self.debugger.anchored_buffers.get(&buffer_id) == Some(&path) {
+            return;
+        }
+        let Some(breakpoints) = self.debugger.breakpoints.get_mut(&path) else {
+            return;
+        };
+        let snapshot = buffer.read(cx).text_snapshot();
+        let anchors = breakpoints
+            .rows
+            .iter()
+            .map(|row| {
+                let point = snapshot.clip_point(language::Point::new(*row, 0), text::Bias::Left);
+                snapshot.anchor_before(point)
+            })
+            .collect();
+        breakpoints.anchors = Some((buffer_id, anchors));
+        self.debugger.anchored_buffers.insert(buffer_id, path);
+
+        // Adapters read the breakpoints' lines from the file on disk, so the lines that moved with
+        // unsaved edits are only sent once the file is saved.
+        cx.subscribe(buffer, |this, buffer, event, cx| {
+            if let language::Event::Saved = event {
+                this.send_buffer_breakpoints(&buffer, cx);
+            }
+        })
+        .detach();
+        cx.observe_release(buffer, |this, buffer, _| {
+            let buffer_id = buffer.remote_id();
+            let Some(path) = this.debugger.anchored_buffers.remove(&buffer_id) else {
+                return;
+            };
+            let Some(breakpoints) = this.debugger.breakpoints.get_mut(&path) else {
+                return;
+            };
+            breakpoints.rows = breakpoints.rows(Some(&buffer.text_snapshot()));
+            breakpoints.anchors = None;
+            if breakpoints.rows.is_empty() {
+                this.debugger.breakpoints.remove(&path);
+            }
+        })
+        .detach();
+    }
+
+    /// Adds a breakpoint on the given row of the buffer, or removes the one that is already there.
+    pub fn toggle_breakpoint(
+        &mut self,
+        buffer: &Model<Buffer>,
+        row: u32,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let Some(path) = buffer_abs_path(buffer, cx) else {
+            return;
+        };
+        self.debugger.breakpoints.entry(path.clone()).or_default();
+        self.anchor_breakpoints(buffer, cx);
+
+        let snapshot = buffer.read(cx).text_snapshot();
+        let buffer_id = snapshot.remote_id();
+        let Some(breakpoints) = self.debugger.breakpoints.get_mut(&path) else {
+            return;
+        };
+        let Some((_, anchors)) = breakpoints.anchors.as_mut() else {
+            return;
+        };
+        // Edits can move several breakpoints onto the same row, which are all removed.
+        let len = anchors.len();
+        anchors.retain(|breakpoint| breakpoint.to_point(&snapshot).row != row);
+        if anchors.len() == len {
+            anchors.push(snapshot.anchor_before(language::Point::new(row, 0)));
+        }
+        cx.emit(Event::BreakpointsChanged(buffer_id));
+        self.send_buffer_breakpoints(buffer, cx);
+    }
+
+    /// Sends the breakpoints of the buffer's file to the running debug sessions.
+    fn send_buffer_breakpoints(&self, buffer: &Model<Buffer>, cx: &mut ModelContext<Self>) {
+        let Some(path) = buffer_abs_path(buffer, cx) else {
+            return;
+        };
+        let Some(breakpoints) = self.debugger.breakpoints.get(&path) else {
+            return;
+        };
+        let snapshot = buffer.read(cx).text_snapshot();
+        let lines = breakpoint_lines(&breakpoints.rows(Some(&snapshot)));
+        for (session, _) in &self.debugger.sessions {
+            session
+                .read(cx)
+                .set_breakpoints(path.clone(), lines.clone(), cx)
+                .detach_and_log_err(cx);
+        }
+    }
+
+    /// Returns the 1-based lines of all breakpoints, grouped by the absolute path of their file.
+    fn breakpoint_lines(&self, cx: &AppContext) -> HashMap<PathBuf, Vec<u64>> {
+        self.debugger
+            .breakpoints
+            .iter()
+            .filter_map(|(path, breakpoints)| {
+                let snapshot = breakpoints
+                    .anchors
+                    .as_ref()
+                    .and_then(|(buffer_id, _)| self.buffer_for_id(*buffer_id))
+                    .map(|buffer| buffer.read(cx).text_snapshot());
+                let lines = breakpoint_lines(&breakpoints.rows(snapshot.as_ref()));
+                (!lines.is_empty()).then(|| (path.clone(), lines))
+            })
+            .collect()
+    }
+}
+
+fn buffer_abs_path(buffer: &Model<Buffer>, cx: &AppContext) -> Option<PathBuf> {
+    let file = buffer.read(cx).file()?.as_local()?;
+    Some(file.abs_path(cx))
+}
+
+fn breakpoint_lines(rows: &[u32]) -> Vec<u64> {
+    rows.iter().map(|row| *row as u64 + 1).collect()
+}
@@ -1,7 +1,8 @@
 use crate::{
-    CodeAction, CoreCompletion, DocumentHighlight, Hover, HoverBlock, HoverBlockKind, InlayHint,
-    InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip, Location,
-    LocationLink, MarkupContent, Project, ProjectTransaction, ResolveState,
+    CodeAction, CodeLens, CoreCompletion, DocumentHighlight, HierarchyCall, HierarchyItem, Hover,
+    HoverBlock, HoverBlockKind, InlayHint, InlayHintLabel, InlayHintLabelPart,
+    InlayHintLabelPartTooltip, InlayHintTooltip, LanguageServerToQuery, Location, LocationLink,
+    MarkupContent, Project, ProjectTransaction, ResolveState, SemanticToken, SemanticTokens,
 };
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
@@ -13,15 +14,17 @@ use language::{
     point_from_lsp, point_to_lsp,
     proto::{deserialize_anchor, deserialize_version, serialize_anchor, serialize_version},
     range_from_lsp, range_to_lsp, Anchor, Bias, Buffer, BufferSnapshot, CachedLspAdapter, CharKind,
-    OffsetRangeExt, PointUtf16, ToOffset, ToPointUtf16, Transaction, Unclipped,
+    FoldingRange, FoldingRangeKind, OffsetRangeExt, Point, PointUtf16, ToOffset, ToPointUtf16,
+    Transaction, Unclipped,
 };
 use lsp::{
     CompletionContext, CompletionListItemDefaultsEditRange, CompletionTriggerKind,
     DocumentHighlightKind, LanguageServer, LanguageServerId, LinkedEditingRangeServerCapabilities,
     OneOf, ServerCapabilities,
 };
+use serde::{Deserialize, Serialize};
 use std::{cmp::Reverse, ops::Range, path::Path, sync::Arc};
-use text::{BufferId, LineEnding};
+use text::{BufferId, BufferSnapshot as TextBufferSnapshot, LineEnding};
 
 pub fn lsp_formatting_options(tab_size: u32) -> lsp::FormattingOptions {
     lsp::FormattingOptions {
@@ -46,6 +49,11 @@ pub trait LspCommand: 'static + Sized + Send {
         None
     }
 
+    /// The language server that has to handle the request when a guest forwards it to the host.
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Primary
+    }
+
     fn to_lsp(
         &self,
         path: &Path,
@@ -164,6 +172,48 @@ pub(crate) struct LinkedEditingRange {
     pub position: Anchor,
 }
 
+pub(crate) struct PrepareCallHierarchy {
+    pub position: PointUtf16,
+}
+
+pub(crate) struct GetIncomingCalls {
+    pub item: lsp::CallHierarchyItem,
+    pub language_server_id: LanguageServerId,
+}
+
+pub(crate) struct GetOutgoingCalls {
+    pub item: lsp::CallHierarchyItem,
+    pub language_server_id: LanguageServerId,
+}
+
+pub(crate) struct PrepareTypeHierarchy {
+    pub position: PointUtf16,
+}
+
+pub(crate) struct GetSupertypes {
+    pub item: lsp::TypeHierarchyItem,
+    pub language_server_id: LanguageServerId,
+}
+
+pub(crate) struct GetSubtypes {
+    pub item: lsp::TypeHierarchyItem,
+    pub language_server_id: LanguageServerId,
+}
+
+pub(crate) struct GetSemanticTokensFull {
+    /// The buffer when the request was sent, which the positions of the tokens refer to.
+    pub snapshot: TextBufferSnapshot,
+}
+
+pub(crate) struct GetSemanticTokensDelta {
+    pub previous: SemanticTokens,
+    pub snapshot: TextBufferSnapshot,
+}
+
+pub(crate) struct GetCodeLens;
+
+pub(crate) struct GetFoldingRanges;
+
 #[async_trait(?Send)]
 impl LspCommand for PrepareRename {
     type Response = Option<Range<Anchor>>;
@@ -2718,3 +2768,1439 @@ impl LspCommand for LinkedEditingRange {
         BufferId::new(message.buffer_id)
     }
 }
+
+#[async_trait(?Send)]
+impl LspCommand for PrepareCallHierarchy {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::CallHierarchyPrepare;
+    type ProtoRequest = proto::PrepareCallHierarchy;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyPrepareParams {
+        lsp::CallHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::CallHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let language_server = language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut items = Vec::new();
+        for lsp_item in message.unwrap_or_default() {
+            items.push(
+                hierarchy_item_from_lsp(lsp_item, &project, &language_server, &mut cx).await?,
+            );
+        }
+        Ok(items)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::PrepareCallHierarchy {
+        proto::PrepareCallHierarchy {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(serialize_anchor(&buffer.anchor_before(self.position))),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::PrepareCallHierarchy,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::PrepareCallHierarchyResponse {
+        proto::PrepareCallHierarchyResponse {
+            items: response
+                .into_iter()
+                .map(|item| hierarchy_item_to_proto(item, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::PrepareCallHierarchyResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let mut items = Vec::new();
+        for item in message.items {
+            items.push(hierarchy_item_from_proto(item, &project, &mut cx).await?);
+        }
+        Ok(items)
+    }
+
+    fn buffer_id_from_proto(message: &proto::PrepareCallHierarchy) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetIncomingCalls {
+    type Response = Vec<HierarchyCall>;
+    type LspRequest = lsp::request::CallHierarchyIncomingCalls;
+    type ProtoRequest = proto::GetIncomingCalls;
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Other(self.language_server_id)
+    }
+
+    fn status(&self) -> Option<String> {
+        Some("Finding callers...".to_owned())
+    }
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyIncomingCallsParams {
+        lsp::CallHierarchyIncomingCallsParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::CallHierarchyIncomingCall>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyCall>> {
+        let language_server = language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut calls = Vec::new();
+        for call in message.unwrap_or_default() {
+            let item =
+                hierarchy_item_from_lsp(call.from, &project, &language_server, &mut cx).await?;
+            // The call sites of incoming calls are located in the caller.
+            let call_sites = call_sites_from_lsp(&item.location.buffer, call.from_ranges, &cx)?;
+            calls.push(HierarchyCall { item, call_sites });
+        }
+        Ok(calls)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetIncomingCalls {
+        proto::GetIncomingCalls {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+            language_server_id: self.language_server_id.0 as u64,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetIncomingCalls,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+            language_server_id: LanguageServerId(message.language_server_id as usize),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<HierarchyCall>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetIncomingCallsResponse {
+        proto::GetIncomingCallsResponse {
+            calls: response
+                .into_iter()
+                .map(|call| hierarchy_call_to_proto(call, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetIncomingCallsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyCall>> {
+        let mut calls = Vec::new();
+        for call in message.calls {
+            calls.push(hierarchy_call_from_proto(call, &project, &mut cx).await?);
+        }
+        Ok(calls)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetIncomingCalls) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetOutgoingCalls {
+    type Response = Vec<HierarchyCall>;
+    type LspRequest = lsp::request::CallHierarchyOutgoingCalls;
+    type ProtoRequest = proto::GetOutgoingCalls;
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Other(self.language_server_id)
+    }
+
+    fn status(&self) -> Option<String> {
+        Some("Finding callees...".to_owned())
+    }
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_call_hierarchy(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CallHierarchyOutgoingCallsParams {
+        lsp::CallHierarchyOutgoingCallsParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::CallHierarchyOutgoingCall>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyCall>> {
+        let language_server = language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut calls = Vec::new();
+        for call in message.unwrap_or_default() {
+            let item =
+                hierarchy_item_from_lsp(call.to, &project, &language_server, &mut cx).await?;
+            // The call sites of outgoing calls are located in the item that was expanded.
+            let call_sites = call_sites_from_lsp(&buffer, call.from_ranges, &cx)?;
+            calls.push(HierarchyCall { item, call_sites });
+        }
+        Ok(calls)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetOutgoingCalls {
+        proto::GetOutgoingCalls {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+            language_server_id: self.language_server_id.0 as u64,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetOutgoingCalls,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+            language_server_id: LanguageServerId(message.language_server_id as usize),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<HierarchyCall>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetOutgoingCallsResponse {
+        proto::GetOutgoingCallsResponse {
+            calls: response
+                .into_iter()
+                .map(|call| hierarchy_call_to_proto(call, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetOutgoingCallsResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyCall>> {
+        let mut calls = Vec::new();
+        for call in message.calls {
+            calls.push(hierarchy_call_from_proto(call, &project, &mut cx).await?);
+        }
+        Ok(calls)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetOutgoingCalls) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for PrepareTypeHierarchy {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchyPrepare;
+    type ProtoRequest = proto::PrepareTypeHierarchy;
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchyPrepareParams {
+        lsp::TypeHierarchyPrepareParams {
+            text_document_position_params: lsp::TextDocumentPositionParams {
+                text_document: lsp::TextDocumentIdentifier {
+                    uri: lsp::Url::from_file_path(path).unwrap(),
+                },
+                position: point_to_lsp(self.position),
+            },
+            work_done_progress_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let language_server = language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut items = Vec::new();
+        for lsp_item in message.unwrap_or_default() {
+            items.push(
+                hierarchy_item_from_lsp(lsp_item, &project, &language_server, &mut cx).await?,
+            );
+        }
+        Ok(items)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::PrepareTypeHierarchy {
+        proto::PrepareTypeHierarchy {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            position: Some(serialize_anchor(&buffer.anchor_before(self.position))),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::PrepareTypeHierarchy,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        let position = message
+            .position
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid position"))?;
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            position: buffer.update(&mut cx, |buffer, _| position.to_point_utf16(buffer))?,
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::PrepareTypeHierarchyResponse {
+        proto::PrepareTypeHierarchyResponse {
+            items: response
+                .into_iter()
+                .map(|item| hierarchy_item_to_proto(item, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::PrepareTypeHierarchyResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let mut items = Vec::new();
+        for item in message.items {
+            items.push(hierarchy_item_from_proto(item, &project, &mut cx).await?);
+        }
+        Ok(items)
+    }
+
+    fn buffer_id_from_proto(message: &proto::PrepareTypeHierarchy) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetSupertypes {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchySupertypes;
+    type ProtoRequest = proto::GetSupertypes;
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Other(self.language_server_id)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchySupertypesParams {
+        lsp::TypeHierarchySupertypesParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let language_server = language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut items = Vec::new();
+        for lsp_item in message.unwrap_or_default() {
+            items.push(
+                hierarchy_item_from_lsp(lsp_item, &project, &language_server, &mut cx).await?,
+            );
+        }
+        Ok(items)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSupertypes {
+        proto::GetSupertypes {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+            language_server_id: self.language_server_id.0 as u64,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetSupertypes,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+            language_server_id: LanguageServerId(message.language_server_id as usize),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetSupertypesResponse {
+        proto::GetSupertypesResponse {
+            items: response
+                .into_iter()
+                .map(|item| hierarchy_item_to_proto(item, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetSupertypesResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let mut items = Vec::new();
+        for item in message.items {
+            items.push(hierarchy_item_from_proto(item, &project, &mut cx).await?);
+        }
+        Ok(items)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSupertypes) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetSubtypes {
+    type Response = Vec<HierarchyItem>;
+    type LspRequest = lsp::request::TypeHierarchySubtypes;
+    type ProtoRequest = proto::GetSubtypes;
+
+    fn language_server_to_query(&self) -> LanguageServerToQuery {
+        LanguageServerToQuery::Other(self.language_server_id)
+    }
+
+    fn to_lsp(
+        &self,
+        _: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::TypeHierarchySubtypesParams {
+        lsp::TypeHierarchySubtypesParams {
+            item: self.item.clone(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<Vec<lsp::TypeHierarchyItem>>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let language_server = language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        let mut items = Vec::new();
+        for lsp_item in message.unwrap_or_default() {
+            items.push(
+                hierarchy_item_from_lsp(lsp_item, &project, &language_server, &mut cx).await?,
+            );
+        }
+        Ok(items)
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSubtypes {
+        proto::GetSubtypes {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            lsp_item: serde_json::to_vec(&self.item).unwrap(),
+            language_server_id: self.language_server_id.0 as u64,
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetSubtypes,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Ok(Self {
+            item: serde_json::from_slice(&message.lsp_item)?,
+            language_server_id: LanguageServerId(message.language_server_id as usize),
+        })
+    }
+
+    fn response_to_proto(
+        response: Vec<HierarchyItem>,
+        project: &mut Project,
+        peer_id: PeerId,
+        _: &clock::Global,
+        cx: &mut AppContext,
+    ) -> proto::GetSubtypesResponse {
+        proto::GetSubtypesResponse {
+            items: response
+                .into_iter()
+                .map(|item| hierarchy_item_to_proto(item, project, peer_id, cx))
+                .collect(),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetSubtypesResponse,
+        project: Model<Project>,
+        _: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<HierarchyItem>> {
+        let mut items = Vec::new();
+        for item in message.items {
+            items.push(hierarchy_item_from_proto(item, &project, &mut cx).await?);
+        }
+        Ok(items)
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSubtypes) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+fn supports_call_hierarchy(capabilities: &ServerCapabilities) -> bool {
+    match &capabilities.call_hierarchy_provider {
+        Some(lsp::CallHierarchyServerCapability::Simple(has_support)) => *has_support,
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// The fields shared by call and type hierarchy items.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LspHierarchyItem {
+    name: String,
+    kind: lsp::SymbolKind,
+    detail: Option<String>,
+    uri: lsp::Url,
+    range: lsp::Range,
+    selection_range: lsp::Range,
+}
+
+async fn hierarchy_item_from_lsp(
+    lsp_item: impl Serialize,
+    project: &Model<Project>,
+    (lsp_adapter, language_server): &(Arc<CachedLspAdapter>, Arc<LanguageServer>),
+    cx: &mut AsyncAppContext,
+) -> Result<HierarchyItem> {
+    let lsp_item = serde_json::to_value(lsp_item)?;
+    let LspHierarchyItem {
+        name,
+        kind,
+        detail,
+        uri,
+        range,
+        selection_range,
+    } = serde_json::from_value(lsp_item.clone())?;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer_via_lsp(
+                uri,
+                language_server.server_id(),
+                lsp_adapter.name.clone(),
+                cx,
+            )
+        })?
+        .await?;
+    let (range, selection_range) = buffer.read_with(cx, |buffer, _| {
+        (
+            anchor_range_from_lsp(buffer, range),
+            anchor_range_from_lsp(buffer, selection_range),
+        )
+    })?;
+    Ok(HierarchyItem {
+        name,
+        kind,
+        detail,
+        location: Location { buffer, range },
+        selection_range,
+        lsp_item,
+        language_server_id: language_server.server_id(),
+    })
+}
+
+fn call_sites_from_lsp(
+    buffer: &Model<Buffer>,
+    ranges: Vec<lsp::Range>,
+    cx: &AsyncAppContext,
+) -> Result<Vec<Location>> {
+    buffer.read_with(cx, |snapshot, _| {
+        ranges
+            .into_iter()
+            .map(|range| Location {
+                buffer: buffer.clone(),
+                range: anchor_range_from_lsp(snapshot, range),
+            })
+            .collect()
+    })
+}
+
+fn anchor_range_from_lsp(buffer: &Buffer, range: lsp::Range) -> Range<Anchor> {
+    let start = buffer.clip_point_utf16(point_from_lsp(range.start), Bias::Left);
+    let end = buffer.clip_point_utf16(point_from_lsp(range.end), Bias::Left);
+    buffer.anchor_after(start)..buffer.anchor_before(end)
+}
+
+fn hierarchy_item_to_proto(
+    item: HierarchyItem,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::HierarchyItem {
+    proto::HierarchyItem {
+        location: Some(location_to_proto(item.location, project, peer_id, cx)),
+        selection_range: Some(proto::AnchorRange {
+            start: Some(serialize_anchor(&item.selection_range.start)),
+            end: Some(serialize_anchor(&item.selection_range.end)),
+        }),
+        lsp_item: serde_json::to_vec(&item.lsp_item).unwrap(),
+        language_server_id: item.language_server_id.0 as u64,
+    }
+}
+
+async fn hierarchy_item_from_proto(
+    item: proto::HierarchyItem,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<HierarchyItem> {
+    let location = item.location.ok_or_else(|| anyhow!("missing location"))?;
+    let location = location_from_proto(location, project, cx).await?;
+    let selection_range = item
+        .selection_range
+        .ok_or_else(|| anyhow!("missing selection range"))?;
+    let start = selection_range
+        .start
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("missing selection start"))?;
+    let end = selection_range
+        .end
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("missing selection end"))?;
+    location
+        .buffer
+        .update(cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
+        .await?;
+
+    let lsp_item: serde_json::Value = serde_json::from_slice(&item.lsp_item)?;
+    let LspHierarchyItem {
+        name, kind, detail, ..
+    } = serde_json::from_value(lsp_item.clone())?;
+    Ok(HierarchyItem {
+        name,
+        kind,
+        detail,
+        location,
+        selection_range: start..end,
+        lsp_item,
+        language_server_id: LanguageServerId(item.language_server_id as usize),
+    })
+}
+
+fn hierarchy_call_to_proto(
+    call: HierarchyCall,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::HierarchyCall {
+    proto::HierarchyCall {
+        item: Some(hierarchy_item_to_proto(call.item, project, peer_id, cx)),
+        call_sites: call
+            .call_sites
+            .into_iter()
+            .map(|call_site| location_to_proto(call_site, project, peer_id, cx))
+            .collect(),
+    }
+}
+
+async fn hierarchy_call_from_proto(
+    call: proto::HierarchyCall,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<HierarchyCall> {
+    let item = call.item.ok_or_else(|| anyhow!("missing item"))?;
+    let item = hierarchy_item_from_proto(item, project, cx).await?;
+    let mut call_sites = Vec::new();
+    for call_site in call.call_sites {
+        call_sites.push(location_from_proto(call_site, project, cx).await?);
+    }
+    Ok(HierarchyCall { item, call_sites })
+}
+
+fn location_to_proto(
+    location: Location,
+    project: &mut Project,
+    peer_id: PeerId,
+    cx: &mut AppContext,
+) -> proto::Location {
+    let buffer_id = project.create_buffer_for_peer(&location.buffer, peer_id, cx);
+    proto::Location {
+        start: Some(serialize_anchor(&location.range.start)),
+        end: Some(serialize_anchor(&location.range.end)),
+        buffer_id: buffer_id.into(),
+    }
+}
+
+async fn location_from_proto(
+    location: proto::Location,
+    project: &Model<Project>,
+    cx: &mut AsyncAppContext,
+) -> Result<Location> {
+    let buffer_id = BufferId::new(location.buffer_id)?;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.wait_for_remote_buffer(buffer_id, cx)
+        })?
+        .await?;
+    let start = location
+        .start
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("missing location start"))?;
+    let end = location
+        .end
+        .and_then(deserialize_anchor)
+        .ok_or_else(|| anyhow!("missing location end"))?;
+    buffer
+        .update(cx, |buffer, _| buffer.wait_for_anchors([start, end]))?
+        .await?;
+    Ok(Location {
+        buffer,
+        range: start..end,
+    })
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetSemanticTokensFull {
+    type Response = SemanticTokens;
+    type LspRequest = lsp::request::SemanticTokensFullRequest;
+    type ProtoRequest = proto::GetSemanticTokens;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        semantic_tokens_options(capabilities).map_or(false, |options| {
+            !matches!(
+                options.full,
+                None | Some(lsp::SemanticTokensFullOptions::Bool(false))
+            )
+        })
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::SemanticTokensParams {
+        lsp::SemanticTokensParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::SemanticTokensResult>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        let (result_id, data) = match message {
+            Some(lsp::SemanticTokensResult::Tokens(tokens)) => (tokens.result_id, tokens.data),
+            Some(lsp::SemanticTokensResult::Partial(tokens)) => (None, tokens.data),
+            None => return Ok(SemanticTokens::default()),
+        };
+        let (_, language_server) =
+            language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        semantic_tokens_from_lsp(
+            result_id,
+            data,
+            language_server.capabilities(),
+            &self.snapshot,
+        )
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSemanticTokens {
+        proto::GetSemanticTokens {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetSemanticTokens,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            snapshot: buffer.read_with(&cx, |buffer, _| buffer.text_snapshot())?,
+        })
+    }
+
+    fn response_to_proto(
+        response: SemanticTokens,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetSemanticTokensResponse {
+        semantic_tokens_to_proto(response, buffer_version)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetSemanticTokensResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        semantic_tokens_from_proto(message, buffer, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSemanticTokens) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+/// Only sent to local language servers: guests don't have the host's token data to apply
+/// deltas to, so remotely this is resolved as a request for all tokens.
+#[async_trait(?Send)]
+impl LspCommand for GetSemanticTokensDelta {
+    type Response = SemanticTokens;
+    type LspRequest = lsp::request::SemanticTokensFullDeltaRequest;
+    type ProtoRequest = proto::GetSemanticTokens;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        supports_semantic_tokens_delta(capabilities)
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::SemanticTokensDeltaParams {
+        lsp::SemanticTokensDeltaParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            previous_result_id: self.previous.result_id.clone().unwrap_or_default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        message: Option<lsp::SemanticTokensFullDeltaResult>,
+        project: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        let (result_id, data) = match message {
+            Some(lsp::SemanticTokensFullDeltaResult::Tokens(tokens)) => {
+                (tokens.result_id, tokens.data)
+            }
+            Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(delta)) => (
+                delta.result_id,
+                apply_semantic_tokens_edits(self.previous.data, delta.edits)?,
+            ),
+            Some(lsp::SemanticTokensFullDeltaResult::PartialTokensDelta { edits }) => (
+                None,
+                apply_semantic_tokens_edits(self.previous.data, edits)?,
+            ),
+            None => return Ok(SemanticTokens::default()),
+        };
+        let (_, language_server) =
+            language_server_for_buffer(&project, &buffer, server_id, &mut cx)?;
+        semantic_tokens_from_lsp(
+            result_id,
+            data,
+            language_server.capabilities(),
+            &self.snapshot,
+        )
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetSemanticTokens {
+        proto::GetSemanticTokens {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        _: proto::GetSemanticTokens,
+        _: Model<Project>,
+        _: Model<Buffer>,
+        _: AsyncAppContext,
+    ) -> Result<Self> {
+        Err(anyhow!("semantic token deltas are not requested remotely"))
+    }
+
+    fn response_to_proto(
+        response: SemanticTokens,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetSemanticTokensResponse {
+        semantic_tokens_to_proto(response, buffer_version)
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetSemanticTokensResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        cx: AsyncAppContext,
+    ) -> Result<SemanticTokens> {
+        semantic_tokens_from_proto(message, buffer, cx).await
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetSemanticTokens) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+fn semantic_tokens_options(
+    capabilities: &ServerCapabilities,
+) -> Option<&lsp::SemanticTokensOptions> {
+    match capabilities.semantic_tokens_provider.as_ref()? {
+        lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(options) => Some(options),
+        lsp::SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+            Some(&options.semantic_tokens_options)
+        }
+    }
+}
+
+pub(crate) fn supports_semantic_tokens_delta(capabilities: &ServerCapabilities) -> bool {
+    matches!(
+        semantic_tokens_options(capabilities).and_then(|options| options.full.as_ref()),
+        Some(lsp::SemanticTokensFullOptions::Delta { delta: Some(true) })
+    )
+}
+
+/// Applies the edits of a semantic tokens delta to the previous tokens.
+fn apply_semantic_tokens_edits(
+    mut data: Vec<lsp::SemanticToken>,
+    mut edits: Vec<lsp::SemanticTokensEdit>,
+) -> Result<Vec<lsp::SemanticToken>> {
+    // Edits refer to the integers of the encoded tokens, five per token, and to the
+    // original tokens, so they are applied from the end.
+    edits.sort_by_key(|edit| Reverse(edit.start));
+    for edit in edits {
+        if edit.start % 5 != 0 || edit.delete_count % 5 != 0 {
+            return Err(anyhow!("semantic tokens edit does not align with tokens"));
+        }
+        let start = edit.start as usize / 5;
+        let end = start + edit.delete_count as usize / 5;
+        if end > data.len() {
+            return Err(anyhow!("semantic tokens edit is out of bounds"));
+        }
+        data.splice(start..end, edit.data.unwrap_or_default());
+    }
+    Ok(data)
+}
+
+/// Decodes the relative positions of the tokens, see the "Integer Encoding for Tokens" section
+/// of the specification.
+fn semantic_tokens_from_lsp(
+    result_id: Option<String>,
+    data: Vec<lsp::SemanticToken>,
+    capabilities: &ServerCapabilities,
+    snapshot: &TextBufferSnapshot,
+) -> Result<SemanticTokens> {
+    let legend = &semantic_tokens_options(capabilities)
+        .ok_or_else(|| anyhow!("language server does not provide semantic tokens"))?
+        .legend;
+    let mut tokens = Vec::with_capacity(data.len());
+    let mut line = 0;
+    let mut start = 0;
+    for token in &data {
+        if token.delta_line > 0 {
+            line += token.delta_line;
+            start = token.delta_start;
+        } else {
+            start += token.delta_start;
+        }
+        let token_start =
+            snapshot.clip_point_utf16(Unclipped(PointUtf16::new(line, start)), Bias::Left);
+        let token_end = snapshot.clip_point_utf16(
+            Unclipped(PointUtf16::new(line, start + token.length)),
+            Bias::Left,
+        );
+        tokens.push(SemanticToken {
+            range: snapshot.anchor_after(token_start)..snapshot.anchor_before(token_end),
+            token_type: token.token_type,
+            token_modifiers: token.token_modifiers_bitset,
+        });
+    }
+    Ok(SemanticTokens {
+        result_id,
+        token_types: legend
+            .token_types
+            .iter()
+            .map(|token_type| token_type.as_str().to_string())
+            .collect(),
+        token_modifiers: legend
+            .token_modifiers
+            .iter()
+            .map(|modifier| modifier.as_str().to_string())
+            .collect(),
+        tokens,
+        data,
+    })
+}
+
+fn semantic_tokens_to_proto(
+    tokens: SemanticTokens,
+    buffer_version: &clock::Global,
+) -> proto::GetSemanticTokensResponse {
+    proto::GetSemanticTokensResponse {
+        tokens: tokens
+            .tokens
+            .into_iter()
+            .map(|token| proto::SemanticToken {
+                start: Some(serialize_anchor(&token.range.start)),
+                end: Some(serialize_anchor(&token.range.end)),
+                token_type: token.token_type,
+                token_modifiers: token.token_modifiers,
+            })
+            .collect(),
+        token_types: tokens.token_types.to_vec(),
+        token_modifiers: tokens.token_modifiers.to_vec(),
+        version: serialize_version(buffer_version),
+    }
+}
+
+async fn semantic_tokens_from_proto(
+    message: proto::GetSemanticTokensResponse,
+    buffer: Model<Buffer>,
+    mut cx: AsyncAppContext,
+) -> Result<SemanticTokens> {
+    buffer
+        .update(&mut cx, |buffer, _| {
+            buffer.wait_for_version(deserialize_version(&message.version))
+        })?
+        .await?;
+    let tokens = message
+        .tokens
+        .into_iter()
+        .filter_map(|token| {
+            Some(SemanticToken {
+                range: deserialize_anchor(token.start?)?..deserialize_anchor(token.end?)?,
+                token_type: token.token_type,
+                token_modifiers: token.token_modifiers,
+            })
+        })
+        .collect();
+    Ok(SemanticTokens {
+        result_id: None,
+        token_types: message.token_types.into(),
+        token_modifiers: message.token_modifiers.into(),
+        tokens,
+        data: Vec::new(),
+    })
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetCodeLens {
+    type Response = Vec<CodeLens>;
+    type LspRequest = lsp::request::CodeLensRequest;
+    type ProtoRequest = proto::GetCodeLens;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        capabilities.code_lens_provider.is_some()
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::CodeLensParams {
+        lsp::CodeLensParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        lenses: Option<Vec<lsp::CodeLens>>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        server_id: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CodeLens>> {
+        buffer.update(&mut cx, |buffer, _| {
+            lenses
+                .unwrap_or_default()
+                .into_iter()
+                .map(|lsp_lens| CodeLens {
+                    server_id,
+                    range: anchor_range_from_lsp(buffer, lsp_lens.range),
+                    lsp_lens,
+                })
+                .collect()
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetCodeLens {
+        proto::GetCodeLens {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetCodeLens,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            snapshot: buffer.read_with(&cx, |buffer, _| buffer.text_snapshot())?,
+        })
+    }
+
+    fn response_to_proto(
+        lenses: Vec<CodeLens>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetCodeLensResponse {
+        proto::GetCodeLensResponse {
+            lenses: lenses.iter().map(Project::serialize_code_lens).collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetCodeLensResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<CodeLens>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        message
+            .lenses
+            .into_iter()
+            .map(Project::deserialize_code_lens)
+            .collect()
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetCodeLens) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl LspCommand for GetFoldingRanges {
+    type Response = Vec<FoldingRange<Anchor>>;
+    type LspRequest = lsp::request::FoldingRangeRequest;
+    type ProtoRequest = proto::GetFoldingRanges;
+
+    fn check_capabilities(&self, capabilities: &ServerCapabilities) -> bool {
+        match &capabilities.folding_range_provider {
+            Some(lsp::FoldingRangeProviderCapability::Simple(enabled)) => *enabled,
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    fn to_lsp(
+        &self,
+        path: &Path,
+        _: &Buffer,
+        _: &Arc<LanguageServer>,
+        _: &AppContext,
+    ) -> lsp::FoldingRangeParams {
+        lsp::FoldingRangeParams {
+            text_document: lsp::TextDocumentIdentifier {
+                uri: lsp::Url::from_file_path(path).unwrap(),
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }
+    }
+
+    async fn response_from_lsp(
+        self,
+        ranges: Option<Vec<lsp::FoldingRange>>,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        _: LanguageServerId,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<FoldingRange<Anchor>>> {
+        buffer.update(&mut cx, |buffer, _| {
+            let max_row = buffer.max_point().row;
+            let mut ranges = ranges
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|range| range.start_line < range.end_line && range.end_line <= max_row)
+                .map(|range| {
+                    // Only whole lines are folded, keeping the first line visible.
+                    let start = Point::new(range.start_line, buffer.line_len(range.start_line));
+                    let end = Point::new(range.end_line, buffer.line_len(range.end_line));
+                    FoldingRange {
+                        range: buffer.anchor_after(start)..buffer.anchor_before(end),
+                        kind: range.kind.map(|kind| match kind {
+                            lsp::FoldingRangeKind::Comment => FoldingRangeKind::Comment,
+                            lsp::FoldingRangeKind::Imports => FoldingRangeKind::Imports,
+                            lsp::FoldingRangeKind::Region => FoldingRangeKind::Region,
+                        }),
+                    }
+                })
+                .collect::<Vec<_>>();
+            ranges.sort_by(|a, b| a.range.start.cmp(&b.range.start, buffer));
+            ranges
+        })
+    }
+
+    fn to_proto(&self, project_id: u64, buffer: &Buffer) -> proto::GetFoldingRanges {
+        proto::GetFoldingRanges {
+            project_id,
+            buffer_id: buffer.remote_id().into(),
+            version: serialize_version(&buffer.version()),
+        }
+    }
+
+    async fn from_proto(
+        message: proto::GetFoldingRanges,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Self> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(Self {
+            snapshot: buffer.read_with(&cx, |buffer, _| buffer.text_snapshot())?,
+        })
+    }
+
+    fn response_to_proto(
+        ranges: Vec<FoldingRange<Anchor>>,
+        _: &mut Project,
+        _: PeerId,
+        buffer_version: &clock::Global,
+        _: &mut AppContext,
+    ) -> proto::GetFoldingRangesResponse {
+        proto::GetFoldingRangesResponse {
+            ranges: ranges
+                .into_iter()
+                .map(|range| proto::FoldingRange {
+                    start: Some(serialize_anchor(&range.range.start)),
+                    end: Some(serialize_anchor(&range.range.end)),
+                    kind: range.kind.map(|kind| match kind {
+                        FoldingRangeKind::Comment => proto::FoldingRangeKind::Comment as i32,
+                        FoldingRangeKind::Imports => proto::FoldingRangeKind::Imports as i32,
+                        FoldingRangeKind::Region => proto::FoldingRangeKind::Region as i32,
+                    }),
+                })
+                .collect(),
+            version: serialize_version(buffer_version),
+        }
+    }
+
+    async fn response_from_proto(
+        self,
+        message: proto::GetFoldingRangesResponse,
+        _: Model<Project>,
+        buffer: Model<Buffer>,
+        mut cx: AsyncAppContext,
+    ) -> Result<Vec<FoldingRange<Anchor>>> {
+        buffer
+            .update(&mut cx, |buffer, _| {
+                buffer.wait_for_version(deserialize_version(&message.version))
+            })?
+            .await?;
+        Ok(message
+            .ranges
+            .into_iter()
+            .filter_map(|range| {
+                let kind = match range.kind {
+                    Some(kind) => Some(match proto::FoldingRangeKind::from_i32(kind)? {
+                        proto::FoldingRangeKind::Comment => FoldingRangeKind::Comment,
+                        proto::FoldingRangeKind::Imports => FoldingRangeKind::Imports,
+                        proto::FoldingRangeKind::Region => FoldingRangeKind::Region,
+                    }),
+                    None => None,
+                };
+                Some(FoldingRange {
+                    range: deserialize_anchor(range.start?)?..deserialize_anchor(range.end?)?,
+                    kind,
+                })
+            })
+            .collect())
+    }
+
+    fn buffer_id_from_proto(message: &proto::GetFoldingRanges) -> Result<BufferId> {
+        BufferId::new(message.buffer_id)
+    }
+}
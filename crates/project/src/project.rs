@@ -1,5 +1,6 @@
 pub mod connection_manager;
 pub mod debounced_delay;
+pub mod debugger;
 pub mod lsp_command;
 pub mod lsp_ext_command;
 mod prettier_support;
@@ -33,7 +34,10 @@ use futures::{
     AsyncWriteExt, Future, FutureExt, StreamExt, TryFutureExt,
 };
 use fuzzy::CharBag;
-use git::{blame::Blame, repository::GitRepository};
+use git::{
+    blame::Blame,
+    repository::{GitRepository, RepoPath},
+};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use gpui::{
     AnyModel, AppContext, AsyncAppContext, BackgroundExecutor, BorrowAppContext, Context, Entity,
@@ -50,11 +54,11 @@ use language::{
         deserialize_anchor, deserialize_line_ending, deserialize_version, serialize_anchor,
         serialize_line_ending, serialize_version, split_operations,
     },
-    range_from_lsp, Bias, Buffer, BufferSnapshot, CachedLspAdapter, Capability, CodeLabel,
-    ContextProvider, Diagnostic, DiagnosticEntry, DiagnosticSet, Diff, Documentation,
-    Event as BufferEvent, File as _, Language, LanguageRegistry, LanguageServerName, LocalFile,
-    LspAdapterDelegate, Operation, Patch, PendingLanguageServer, PointUtf16, TextBufferSnapshot,
-    ToOffset, ToPointUtf16, Transaction, Unclipped,
+    range_from_lsp, Bias, Buffer, BufferSnapshot, CachedLspAdapter, Capability, ClientCommand,
+    CodeLabel, ContextProvider, Diagnostic, DiagnosticEntry, DiagnosticSet, Diff, Documentation,
+    Event as BufferEvent, File as _, FoldingRange, Language, LanguageRegistry, LanguageServerName,
+    LocalFile, LspAdapterDelegate, Operation, Patch, PendingLanguageServer, PointUtf16,
+    TextBufferSnapshot, ToOffset, ToPointUtf16, Transaction, Unclipped,
 };
 use log::error;
 use lsp::{
@@ -78,7 +82,10 @@ use rpc::{ErrorCode, ErrorExt as _};
 use search::SearchQuery;
 use search_history::SearchHistory;
 use serde::Serialize;
-use settings::{watch_config_file, Settings, SettingsLocation, SettingsStore};
+use settings::{
+    editorconfig::{Editorconfig, EDITORCONFIG_NAME},
+    watch_config_file, Settings, SettingsLocation, SettingsStore,
+};
 use sha2::{Digest, Sha256};
 use similar::{ChangeTag, TextDiff};
 use smol::channel::{Receiver, Sender};
@@ -105,7 +112,7 @@ use std::{
 };
 use task::{
     static_source::{StaticSource, TrackedFile},
-    RevealStrategy, TaskContext, TaskTemplate, TaskVariables, VariableName,
+    DependsOrder, RevealStrategy, TaskContext, TaskTemplate, TaskVariables, VariableName,
 };
 use terminals::Terminals;
 use text::{Anchor, BufferId, LineEnding};
@@ -213,6 +220,9 @@ pub struct Project {
     local_buffer_ids_by_path: HashMap<ProjectPath, BufferId>,
     local_buffer_ids_by_entry_id: HashMap<ProjectEntryId, BufferId>,
     buffer_snapshots: HashMap<BufferId, HashMap<LanguageServerId, Vec<LspBufferSnapshot>>>, // buffer_id -> server_id -> vec of snapshots
+    /// The last semantic tokens of each buffer and language server, which the next request
+    /// only asks for the changes since.
+    semantic_tokens: HashMap<(BufferId, LanguageServerId), SemanticTokens>,
     buffers_being_formatted: HashSet<BufferId>,
     buffers_needing_diff: HashSet<WeakModel<Buffer>>,
     git_diff_debouncer: DebouncedDelay,
@@ -220,6 +230,7 @@ pub struct Project {
     _maintain_buffer_languages: Task<()>,
     _maintain_workspace_config: Task<Result<()>>,
     terminals: Terminals,
+    debugger: debugger::Debugger,
     current_lsp_settings: HashMap<Arc<str>, LspSettings>,
     node: Option<Arc<dyn NodeRuntime>>,
     default_prettier: DefaultPrettier,
@@ -326,6 +337,7 @@ pub enum Event {
     Notification(String),
     LanguageServerPrompt(LanguageServerPromptRequest),
     LanguageNotFound(Model<Buffer>),
+    BufferOpened(Model<Buffer>),
     ActiveEntryChanged(Option<ProjectEntryId>),
     ActivateProjectPanel,
     WorktreeAdded,
@@ -354,8 +366,12 @@ pub enum Event {
     CollaboratorJoined(proto::PeerId),
     CollaboratorLeft(proto::PeerId),
     RefreshInlayHints,
+    RefreshSemanticTokens,
+    RefreshCodeLens,
     RevealInProjectPanel(ProjectEntryId),
     SnippetEdit(BufferId, Vec<(lsp::Range, Snippet)>),
+    BreakpointsChanged(BufferId),
+    DebugSessionsChanged,
 }
 
 pub enum LanguageServerState {
@@ -464,6 +480,18 @@ struct CoreCompletion {
     lsp_completion: lsp::CompletionItem,
 }
 
+/// A code lens provided by a language server, shown above the line it refers to.
+#[derive(Clone, Debug)]
+pub struct CodeLens {
+    /// The id of the language server that produced this code lens.
+    pub server_id: LanguageServerId,
+    /// The range of the buffer this code lens refers to.
+    pub range: Range<Anchor>,
+    /// The raw code lens provided by the language server. Its command is
+    /// missing until the code lens is resolved.
+    pub lsp_lens: lsp::CodeLens,
+}
+
 /// A code action provided by a language server.
 #[derive(Clone, Debug)]
 pub struct CodeAction {
@@ -534,6 +562,73 @@ pub struct DocumentHighlight {
     pub kind: DocumentHighlightKind,
 }
 
+/// A symbol returned by a call or type hierarchy request, which can be expanded further.
+#[derive(Debug, Clone)]
+pub struct HierarchyItem {
+    pub name: String,
+    pub kind: lsp::SymbolKind,
+    pub detail: Option<String>,
+    /// The whole range of the symbol, e.g. including its body.
+    pub location: Location,
+    /// The range that should be selected when navigating to the symbol, e.g. its name.
+    pub selection_range: Range<language::Anchor>,
+    /// The item as returned by the language server, which has to be sent back verbatim
+    /// when requesting its callers, callees, supertypes or subtypes.
+    lsp_item: serde_json::Value,
+    /// The language server that returned the item, which has to handle those requests.
+    pub language_server_id: LanguageServerId,
+}
+
+/// The semantic tokens of a buffer, as reported by a language server.
+#[derive(Debug, Clone, Default)]
+pub struct SemanticTokens {
+    /// Identifies this result, so that the next request only needs to return what changed since.
+    pub result_id: Option<String>,
+    /// The names of the token types that [`SemanticToken::token_type`] refers to.
+    pub token_types: Arc<[String]>,
+    /// The names of the modifiers that [`SemanticToken::token_modifiers`] refers to.
+    pub token_modifiers: Arc<[String]>,
+    pub tokens: Vec<SemanticToken>,
+    /// The tokens in the language server's encoding, which deltas apply to.
+    data: Vec<lsp::SemanticToken>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SemanticToken {
+    pub range: Range<language::Anchor>,
+    pub token_type: u32,
+    /// A bit set of indices into the modifier names.
+    pub token_modifiers: u32,
+}
+
+impl SemanticTokens {
+    pub fn token_type(&self, token: &SemanticToken) -> Option<&str> {
+        self.token_types
+            .get(token.token_type as usize)
+            .map(String::as_str)
+    }
+
+    pub fn token_modifiers<'a>(
+        &'a self,
+        token: &SemanticToken,
+    ) -> impl 'a + Iterator<Item = &'a str> {
+        let bits = token.token_modifiers;
+        self.token_modifiers
+            .iter()
+            .enumerate()
+            .filter(move |(ix, _)| *ix < 32 && bits & (1 << ix) != 0)
+            .map(|(_, modifier)| modifier.as_str())
+    }
+}
+
+/// A caller or callee of a call hierarchy item.
+#[derive(Debug, Clone)]
+pub struct HierarchyCall {
+    pub item: HierarchyItem,
+    /// The ranges of the calls, in the caller's buffer.
+    pub call_sites: Vec<Location>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Symbol {
     pub language_server_name: LanguageServerName,
@@ -673,6 +768,8 @@ impl Project {
         client.add_model_request_handler(Self::handle_apply_additional_edits_for_completion);
         client.add_model_request_handler(Self::handle_resolve_completion_documentation);
         client.add_model_request_handler(Self::handle_apply_code_action);
+        client.add_model_request_handler(Self::handle_resolve_code_lens);
+        client.add_model_request_handler(Self::handle_apply_code_lens);
         client.add_model_request_handler(Self::handle_on_type_formatting);
         client.add_model_request_handler(Self::handle_inlay_hints);
         client.add_model_request_handler(Self::handle_resolve_inlay_hint);
@@ -689,6 +786,15 @@ impl Project {
         client.add_model_request_handler(Self::handle_lsp_command::<GetReferences>);
         client.add_model_request_handler(Self::handle_lsp_command::<PrepareRename>);
         client.add_model_request_handler(Self::handle_lsp_command::<PerformRename>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareCallHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetIncomingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetOutgoingCalls>);
+        client.add_model_request_handler(Self::handle_lsp_command::<PrepareTypeHierarchy>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSupertypes>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSubtypes>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetSemanticTokensFull>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetCodeLens>);
+        client.add_model_request_handler(Self::handle_lsp_command::<GetFoldingRanges>);
         client.add_model_request_handler(Self::handle_search_project);
         client.add_model_request_handler(Self::handle_get_project_symbols);
         client.add_model_request_handler(Self::handle_open_buffer_for_symbol);
@@ -699,6 +805,8 @@ impl Project {
         client.add_model_message_handler(Self::handle_update_diff_base);
         client.add_model_request_handler(Self::handle_lsp_command::<lsp_ext_command::ExpandMacro>);
         client.add_model_request_handler(Self::handle_blame_buffer);
+        client.add_model_request_handler(Self::handle_set_index_text);
+        client.add_model_request_handler(Self::handle_get_head_text);
         client.add_model_request_handler(Self::handle_multi_lsp_query);
         client.add_model_request_handler(Self::handle_restart_language_servers);
         client.add_model_request_handler(Self::handle_task_context_for_location);
@@ -732,6 +840,7 @@ impl Project {
                 local_buffer_ids_by_path: Default::default(),
                 local_buffer_ids_by_entry_id: Default::default(),
                 buffer_snapshots: Default::default(),
+                semantic_tokens: Default::default(),
                 join_project_response_message_id: 0,
                 client_state: ProjectClientState::Local,
                 loading_buffers: HashMap::default(),
@@ -767,7 +876,9 @@ impl Project {
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
+                    task_diagnostics: HashMap::default(),
                 },
+                debugger: Default::default(),
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
                 node: Some(node),
                 default_prettier: DefaultPrettier::default(),
@@ -923,10 +1034,13 @@ impl Project {
                 buffers_needing_diff: Default::default(),
                 git_diff_debouncer: DebouncedDelay::new(),
                 buffer_snapshots: Default::default(),
+                semantic_tokens: Default::default(),
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
+                    task_diagnostics: HashMap::default(),
                 },
+                debugger: Default::default(),
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
                 node: None,
                 default_prettier: DefaultPrettier::default(),
@@ -1134,8 +1248,10 @@ impl Project {
                                 .push((file.worktree.clone(), Arc::clone(language)));
                         }
                     }
-                    language_formatters_to_check
-                        .push((buffer_file.map(|f| f.worktree_id(cx)), settings.clone()));
+                    language_formatters_to_check.push((
+                        buffer_file.map(|f| f.worktree_id(cx)),
+                        settings.into_owned(),
+                    ));
                 }
             }
         }
@@ -2280,7 +2396,9 @@ impl Project {
         let buffer = buffer_handle.read(cx);
         let buffer_id = buffer.remote_id();
         let text = buffer.as_rope().clone();
-        let line_ending = buffer.line_ending();
+        let line_ending = language_settings(buffer.language(), buffer.file(), cx)
+            .line_ending
+            .unwrap_or_else(|| buffer.line_ending());
         let version = buffer.version();
         if buffer.file().is_some_and(|file| !file.is_created()) {
             has_changed_file = true;
@@ -2438,7 +2556,11 @@ impl Project {
 
         self.detect_language_for_buffer(buffer, cx);
         self.register_buffer_with_language_servers(buffer, cx);
+        self.anchor_breakpoints(buffer, cx);
         cx.observe_release(buffer, |this, buffer, cx| {
+            let buffer_id = buffer.remote_id();
+            this.semantic_tokens
+                .retain(|(token_buffer_id, _), _| *token_buffer_id != buffer_id);
             if let Some(file) = File::from_dyn(buffer.file()) {
                 if file.is_local() {
                     let uri = lsp::Url::from_file_path(file.abs_path(cx)).unwrap();
@@ -2462,6 +2584,7 @@ impl Project {
                 sender.send(Ok(buffer.clone())).ok();
             }
         }
+        cx.emit(Event::BufferOpened(buffer.clone()));
         Ok(())
     }
 
@@ -2574,7 +2697,10 @@ impl Project {
                 }
             }
 
-            self.buffer_snapshots.remove(&buffer.remote_id());
+            let buffer_id = buffer.remote_id();
+            self.buffer_snapshots.remove(&buffer_id);
+            self.semantic_tokens
+                .retain(|(token_buffer_id, _), _| *token_buffer_id != buffer_id);
             let file_url = lsp::Url::from_file_path(old_path).unwrap();
             for (_, language_server) in self.language_servers_for_buffer(buffer, cx) {
                 language_server
@@ -3185,7 +3311,8 @@ impl Project {
         });
 
         let buffer_file = buffer.read(cx).file().cloned();
-        let settings = language_settings(Some(&new_language), buffer_file.as_ref(), cx).clone();
+        let settings =
+            language_settings(Some(&new_language), buffer_file.as_ref(), cx).into_owned();
         let buffer_file = File::from_dyn(buffer_file.as_ref());
         let worktree = buffer_file.as_ref().map(|f| f.worktree_id(cx));
         if let Some(prettier_plugins) = prettier_support::prettier_plugins_for_language(&settings) {
@@ -3654,6 +3781,36 @@ impl Project {
             })
             .detach();
 
+        language_server
+            .on_request::<lsp::request::SemanticTokensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |_, cx| {
+                            cx.emit(Event::RefreshSemanticTokens);
+                        })?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
+        language_server
+            .on_request::<lsp::request::CodeLensRefresh, _, _>({
+                let this = this.clone();
+                move |(), mut cx| {
+                    let this = this.clone();
+                    async move {
+                        this.update(&mut cx, |_, cx| {
+                            cx.emit(Event::RefreshCodeLens);
+                        })?;
+                        Ok(())
+                    }
+                }
+            })
+            .detach();
+
         language_server
             .on_request::<lsp::request::ShowMessageRequest, _, _>({
                 let this = this.clone();
@@ -3976,6 +4133,8 @@ impl Project {
 
             self.language_server_watched_paths.remove(&server_id);
             self.language_server_statuses.remove(&server_id);
+            self.semantic_tokens
+                .retain(|(_, token_server_id), _| *token_server_id != server_id);
             cx.notify();
 
             let server_state = self.language_servers.remove(&server_id);
@@ -4420,6 +4579,8 @@ impl Project {
             if let Some(work) = status.pending_work.remove(&token) {
                 if !work.is_disk_based_diagnostics_progress {
                     cx.emit(Event::RefreshInlayHints);
+                    cx.emit(Event::RefreshSemanticTokens);
+                    cx.emit(Event::RefreshCodeLens);
                 }
             }
             cx.notify();
@@ -5082,7 +5243,7 @@ impl Project {
                 })?;
 
             let settings = buffer.update(&mut cx, |buffer, cx| {
-                language_settings(buffer.language(), buffer.file(), cx).clone()
+                language_settings(buffer.language(), buffer.file(), cx).into_owned()
             })?;
 
             let remove_trailing_whitespace = settings.remove_trailing_whitespace_on_save;
@@ -5519,6 +5680,183 @@ impl Project {
         self.document_highlights_impl(buffer, position, cx)
     }
 
+    pub fn prepare_call_hierarchy<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareCallHierarchy { position },
+            cx,
+        )
+    }
+
+    /// Returns the functions calling the given call hierarchy item.
+    pub fn incoming_calls(
+        &self,
+        item: &HierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyCall>>> {
+        let lsp_item = match serde_json::from_value(item.lsp_item.clone()) {
+            Ok(lsp_item) => lsp_item,
+            Err(error) => return Task::ready(Err(error.into())),
+        };
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Other(item.language_server_id),
+            GetIncomingCalls {
+                item: lsp_item,
+                language_server_id: item.language_server_id,
+            },
+            cx,
+        )
+    }
+
+    /// Returns the functions called by the given call hierarchy item.
+    pub fn outgoing_calls(
+        &self,
+        item: &HierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyCall>>> {
+        let lsp_item = match serde_json::from_value(item.lsp_item.clone()) {
+            Ok(lsp_item) => lsp_item,
+            Err(error) => return Task::ready(Err(error.into())),
+        };
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Other(item.language_server_id),
+            GetOutgoingCalls {
+                item: lsp_item,
+                language_server_id: item.language_server_id,
+            },
+            cx,
+        )
+    }
+
+    pub fn prepare_type_hierarchy<T: ToPointUtf16>(
+        &self,
+        buffer: &Model<Buffer>,
+        position: T,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let position = position.to_point_utf16(buffer.read(cx));
+        self.request_lsp(
+            buffer.clone(),
+            LanguageServerToQuery::Primary,
+            PrepareTypeHierarchy { position },
+            cx,
+        )
+    }
+
+    pub fn supertypes(
+        &self,
+        item: &HierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let lsp_item = match serde_json::from_value(item.lsp_item.clone()) {
+            Ok(lsp_item) => lsp_item,
+            Err(error) => return Task::ready(Err(error.into())),
+        };
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Other(item.language_server_id),
+            GetSupertypes {
+                item: lsp_item,
+                language_server_id: item.language_server_id,
+            },
+            cx,
+        )
+    }
+
+    pub fn subtypes(
+        &self,
+        item: &HierarchyItem,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<HierarchyItem>>> {
+        let lsp_item = match serde_json::from_value(item.lsp_item.clone()) {
+            Ok(lsp_item) => lsp_item,
+            Err(error) => return Task::ready(Err(error.into())),
+        };
+        self.request_lsp(
+            item.location.buffer.clone(),
+            LanguageServerToQuery::Other(item.language_server_id),
+            GetSubtypes {
+                item: lsp_item,
+                language_server_id: item.language_server_id,
+            },
+            cx,
+        )
+    }
+
+    /// Requests the semantic tokens of the buffer. The last tokens are kept for each buffer and
+    /// language server, so that only the changes since are requested when the language server
+    /// supports it, whichever editor asks for them.
+    pub fn semantic_tokens(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<SemanticTokens>> {
+        let snapshot = buffer.read(cx).text_snapshot();
+        let buffer_id = snapshot.remote_id();
+        let server_id = self
+            .primary_language_server_for_buffer(buffer.read(cx), cx)
+            .map(|(_, server)| server.server_id());
+        let Some(server_id) = server_id.filter(|_| self.is_local()) else {
+            return self.request_lsp(
+                buffer.clone(),
+                LanguageServerToQuery::Primary,
+                GetSemanticTokensFull { snapshot },
+                cx,
+            );
+        };
+
+        let previous = self
+            .semantic_tokens
+            .get(&(buffer_id, server_id))
+            .filter(|previous| {
+                previous.result_id.is_some()
+                    && self
+                        .language_server_for_id(server_id)
+                        .map_or(false, |server| {
+                            supports_semantic_tokens_delta(server.capabilities())
+                        })
+            })
+            .cloned();
+        let request = match previous {
+            Some(previous) => self.request_lsp(
+                buffer.clone(),
+                LanguageServerToQuery::Other(server_id),
+                GetSemanticTokensDelta { previous, snapshot },
+                cx,
+            ),
+            None => self.request_lsp(
+                buffer.clone(),
+                LanguageServerToQuery::Other(server_id),
+                GetSemanticTokensFull { snapshot },
+                cx,
+            ),
+        };
+        cx.spawn(|this, mut cx| async move {
+            let response = request.await;
+            this.update(&mut cx, |this, _| match &response {
+                Ok(tokens) => {
+                    this.semantic_tokens
+                        .insert((buffer_id, server_id), tokens.clone());
+                }
+                // Request all tokens next time, in case the server lost track of the
+                // previous result.
+                Err(_) => {
+                    this.semantic_tokens.remove(&(buffer_id, server_id));
+                }
+            })?;
+            response
+        })
+    }
+
     pub fn symbols(&self, query: &str, cx: &mut ModelContext<Self>) -> Task<Result<Vec<Symbol>>> {
         let language_registry = self.languages.clone();
 
@@ -6499,6 +6837,168 @@ impl Project {
         }
     }
 
+    pub fn code_lens(
+        &self,
+        buffer_handle: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<CodeLens>>> {
+        self.request_lsp(
+            buffer_handle.clone(),
+            LanguageServerToQuery::Primary,
+            GetCodeLens,
+            cx,
+        )
+    }
+
+    /// The folding ranges that the primary language server of the buffer reports, sorted by their
+    /// start. Each range covers whole lines, from the end of its first line.
+    pub fn folding_ranges(
+        &self,
+        buffer_handle: &Model<Buffer>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<Vec<FoldingRange<Anchor>>>> {
+        self.request_lsp(
+            buffer_handle.clone(),
+            LanguageServerToQuery::Primary,
+            GetFoldingRanges,
+            cx,
+        )
+    }
+
+    /// Fills in the command of a code lens, for servers that compute them lazily.
+    pub fn resolve_code_lens(
+        &self,
+        buffer_handle: &Model<Buffer>,
+        mut lens: CodeLens,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<CodeLens>> {
+        if lens.lsp_lens.command.is_some() {
+            return Task::ready(Ok(lens));
+        }
+
+        if self.is_local() {
+            let buffer = buffer_handle.read(cx);
+            let Some((_, lang_server)) =
+                self.language_server_for_buffer(buffer, lens.server_id, cx)
+            else {
+                return Task::ready(Ok(lens));
+            };
+            let lang_server = lang_server.clone();
+            cx.background_executor().spawn(async move {
+                Self::try_resolve_code_lens(&lang_server, &mut lens).await?;
+                Ok(lens)
+            })
+        } else if let Some(project_id) = self.remote_id() {
+            let client = self.client.clone();
+            let request = proto::ResolveCodeLens {
+                project_id,
+                buffer_id: buffer_handle.read(cx).remote_id().into(),
+                lens: Some(Self::serialize_code_lens(&lens)),
+            };
+            cx.background_executor().spawn(async move {
+                let response = client.request(request).await?;
+                Self::deserialize_code_lens(
+                    response.lens.ok_or_else(|| anyhow!("missing code lens"))?,
+                )
+            })
+        } else {
+            Task::ready(Err(anyhow!("project does not have a remote id")))
+        }
+    }
+
+    /// Returns how the client runs the command of a code lens, if the adapter of the language
+    /// server that provided it expects the client to, rather than executing it on the server.
+    pub fn code_lens_client_command(
+        &self,
+        buffer: &Model<Buffer>,
+        lens: &CodeLens,
+        cx: &AppContext,
+    ) -> Option<ClientCommand> {
+        let command = lens.lsp_lens.command.as_ref()?;
+        let server_name = &self.language_server_statuses.get(&lens.server_id)?.name;
+        let language = buffer.read(cx).language()?;
+        self.languages
+            .lsp_adapters(language)
+            .into_iter()
+            .find(|adapter| adapter.name.0.as_ref() == server_name)?
+            .client_command(&command.command)
+    }
+
+    /// Executes the command of a code lens on the language server that provided it.
+    pub fn apply_code_lens(
+        &self,
+        buffer_handle: Model<Buffer>,
+        mut lens: CodeLens,
+        push_to_history: bool,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<ProjectTransaction>> {
+        if self.is_local() {
+            let buffer = buffer_handle.read(cx);
+            let Some((_, lang_server)) =
+                self.language_server_for_buffer(buffer, lens.server_id, cx)
+            else {
+                return Task::ready(Ok(Default::default()));
+            };
+            let lang_server = lang_server.clone();
+            cx.spawn(move |this, mut cx| async move {
+                Self::try_resolve_code_lens(&lang_server, &mut lens)
+                    .await
+                    .context("resolving a code lens")?;
+                let Some(command) = lens.lsp_lens.command else {
+                    return Ok(ProjectTransaction::default());
+                };
+                let supports_command = lang_server
+                    .capabilities()
+                    .execute_command_provider
+                    .as_ref()
+                    .map_or(false, |options| options.commands.contains(&command.command));
+                if !supports_command {
+                    return Err(anyhow!(
+                        "language server does not support the command {}",
+                        command.command
+                    ));
+                }
+
+                this.update(&mut cx, |this, _| {
+                    this.last_workspace_edits_by_language_server
+                        .remove(&lang_server.server_id());
+                })?;
+                lang_server
+                    .request::<lsp::request::ExecuteCommand>(lsp::ExecuteCommandParams {
+                        command: command.command,
+                        arguments: command.arguments.unwrap_or_default(),
+                        ..Default::default()
+                    })
+                    .await?;
+                this.update(&mut cx, |this, _| {
+                    this.last_workspace_edits_by_language_server
+                        .remove(&lang_server.server_id())
+                        .unwrap_or_default()
+                })
+            })
+        } else if let Some(project_id) = self.remote_id() {
+            let client = self.client.clone();
+            let request = proto::ApplyCodeLens {
+                project_id,
+                buffer_id: buffer_handle.read(cx).remote_id().into(),
+                lens: Some(Self::serialize_code_lens(&lens)),
+            };
+            cx.spawn(move |this, mut cx| async move {
+                let response = client
+                    .request(request)
+                    .await?
+                    .transaction
+                    .ok_or_else(|| anyhow!("missing transaction"))?;
+                this.update(&mut cx, |this, cx| {
+                    this.deserialize_project_transaction(response, push_to_history, cx)
+                })?
+                .await
+            })
+        } else {
+            Task::ready(Err(anyhow!("project does not have a remote id")))
+        }
+    }
+
     fn apply_on_type_formatting(
         &self,
         buffer: Model<Buffer>,
@@ -7761,6 +8261,15 @@ impl Project {
             inventory.remove_worktree_sources(id_to_remove);
         });
 
+        // The worktree may outlive the project's handle to it, so don't wait for its release
+        // to forget its settings and the EditorConfig properties of its files.
+        if let Some(worktree) = self.worktree_for_id(id_to_remove, cx) {
+            let root_id = worktree.entity_id().as_u64() as usize;
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.clear_local_settings(root_id, cx).log_err();
+            });
+        }
+
         self.worktrees.retain(|worktree| {
             if let Some(worktree) = worktree.upgrade() {
                 let id = worktree.read(cx).id();
@@ -7847,6 +8356,9 @@ impl Project {
             });
         })
         .detach();
+        if worktree.read(cx).is_local() {
+            self.load_parent_editorconfigs(worktree, cx);
+        }
 
         cx.emit(Event::WorktreeAdded);
         self.metadata_changed(cx);
@@ -8168,6 +8680,43 @@ impl Project {
         .detach();
     }
 
+    /// Loads the `.editorconfig` files of the directories above a local worktree, up to the first
+    /// one marked as the root, since they apply to the files of the worktree too.
+    fn load_parent_editorconfigs(&self, worktree: &Model<Worktree>, cx: &mut ModelContext<Self>) {
+        let fs = self.fs.clone();
+        let worktree_id = worktree.entity_id().as_u64() as usize;
+        let root_path = worktree.read(cx).abs_path();
+        cx.spawn(move |_, mut cx| async move {
+            let mut editorconfigs = Vec::new();
+            for directory in root_path.ancestors().skip(1) {
+                let editorconfig_path = directory.join(EDITORCONFIG_NAME);
+                if !fs.is_file(&editorconfig_path).await {
+                    continue;
+                }
+                let Some(content) = fs.load(&editorconfig_path).await.log_err() else {
+                    continue;
+                };
+                let Ok(relative_root_path) = root_path.strip_prefix(directory) else {
+                    continue;
+                };
+                let editorconfig = Editorconfig::parse(&content);
+                let is_root = editorconfig.root;
+                editorconfigs.push((Arc::from(relative_root_path), editorconfig));
+                if is_root {
+                    break;
+                }
+            }
+            if editorconfigs.is_empty() {
+                return;
+            }
+            cx.update_global::<SettingsStore, _>(|store, _| {
+                store.set_parent_editorconfigs(worktree_id, editorconfigs)
+            })
+            .ok();
+        })
+        .detach();
+    }
+
     fn update_local_worktree_settings(
         &mut self,
         worktree: &Model<Worktree>,
@@ -8182,8 +8731,13 @@ impl Project {
         let remote_worktree_id = worktree.read(cx).id();
 
         let mut settings_contents = Vec::new();
+        let mut editorconfig_contents = Vec::new();
+        let mut removed_paths = Vec::new();
         for (path, _, change) in changes.iter() {
             let removed = change == &PathChange::Removed;
+            if removed {
+                removed_paths.push(path);
+            }
             let abs_path = match worktree.read(cx).absolutize(path) {
                 Ok(abs_path) => abs_path,
                 Err(e) => {
@@ -8209,6 +8763,21 @@ impl Project {
                         },
                     )
                 });
+            } else if path.file_name() == Some(OsStr::new(EDITORCONFIG_NAME)) {
+                let Some(editorconfig_dir) = path.parent().map(Arc::from) else {
+                    continue;
+                };
+                let fs = self.fs.clone();
+                editorconfig_contents.push(async move {
+                    (
+                        editorconfig_dir,
+                        if removed {
+                            None
+                        } else {
+                            Some(async move { fs.load(&abs_path).await }.await)
+                        },
+                    )
+                });
             } else if path.ends_with(local_tasks_file_relative_path()) {
                 self.task_inventory().update(cx, |task_inventory, cx| {
                     if removed {
@@ -8258,7 +8827,12 @@ impl Project {
             }
         }
 
-        if settings_contents.is_empty() {
+        if !removed_paths.is_empty() {
+            cx.update_global::<SettingsStore, _>(|store, _| {
+                store.remove_editorconfig_properties(worktree_id.as_u64() as usize, removed_paths);
+            });
+        }
+        if settings_contents.is_empty() && editorconfig_contents.is_empty() {
             return;
         }
 
@@ -8266,8 +8840,18 @@ impl Project {
         cx.spawn(move |_, cx| async move {
             let settings_contents: Vec<(Arc<Path>, _)> =
                 futures::future::join_all(settings_contents).await;
+            let editorconfig_contents: Vec<(Arc<Path>, _)> =
+                futures::future::join_all(editorconfig_contents).await;
             cx.update(|cx| {
                 cx.update_global::<SettingsStore, _>(|store, cx| {
+                    for (directory, file_content) in editorconfig_contents {
+                        let file_content = file_content.and_then(|content| content.log_err());
+                        store.set_local_editorconfig(
+                            worktree_id.as_u64() as usize,
+                            directory,
+                            file_content.as_deref(),
+                        );
+                    }
                     for (directory, file_content) in settings_contents {
                         let file_content = file_content.and_then(|content| content.log_err());
                         store
@@ -8496,31 +9080,8 @@ impl Project {
     ) -> Task<Result<Blame>> {
         if self.is_local() {
             let blame_params = maybe!({
+                let (repo, relative_path) = self.local_repo_for_buffer(buffer, cx)?;
                 let buffer = buffer.read(cx);
-                let buffer_project_path = buffer
-                    .project_path(cx)
-                    .context("failed to get buffer project path")?;
-
-                let worktree = self
-                    .worktree_for_id(buffer_project_path.worktree_id, cx)
-                    .context("failed to get worktree")?
-                    .read(cx)
-                    .as_local()
-                    .context("worktree was not local")?
-                    .snapshot();
-
-                let (repo_entry, local_repo_entry) =
-                    match worktree.repo_for_path(&buffer_project_path.path) {
-                        Some(repo_for_path) => repo_for_path,
-                        None => anyhow::bail!(NoRepositoryError {}),
-                    };
-
-                let relative_path = repo_entry
-                    .relativize(&worktree, &buffer_project_path.path)
-                    .context("failed to relativize buffer path")?;
-
-                let repo = local_repo_entry.repo().clone();
-
                 let content = match version {
                     Some(version) => buffer.rope_for_version(&version).clone(),
                     None => buffer.as_rope().clone(),
@@ -8555,8 +9116,179 @@ impl Project {
         }
     }
 
+    /// Writes the given text to the git index entry of the buffer's file and makes it the
+    /// buffer's diff base right away, without waiting for the repository to be rescanned.
+    pub fn set_index_text(
+        &self,
+        buffer: &Model<Buffer>,
+        index_text: Option<String>,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let buffer_id = buffer.read(cx).remote_id();
+        let project_id = self.remote_id();
+        let client = self.client.clone();
+        let write = if self.is_local() {
+            let repo_and_path = self.local_repo_for_buffer(buffer, cx);
+            let index_text = index_text.clone();
+            let file_line_ending = buffer.read(cx).line_ending();
+            cx.background_executor().spawn(async move {
+                let (repo, relative_path) = repo_and_path?;
+                // Like the buffer's text, the index text uses `\n`. It's written with the line
+                // endings of the current index entry, or of the file if it isn't in the index.
+                let index_text = index_text.map(|index_text| {
+                    let line_ending = repo
+                        .load_index_text(&relative_path)
+                        .filter(|old_index_text| old_index_text.contains('\n'))
+                        .map_or(file_line_ending, |old_index_text| {
+                            LineEnding::detect(&old_index_text)
+                        });
+                    match line_ending {
+                        LineEnding::Unix => index_text,
+                        LineEnding::Windows => index_text.replace('\n', "\r\n"),
+                    }
+                });
+                repo.set_index_text(&relative_path, index_text)
+                    .with_context(|| format!("Failed to update index of {:?}", relative_path.0))
+            })
+        } else if let Some(project_id) = project_id {
+            let client = client.clone();
+            let index_text = index_text.clone();
+            cx.background_executor().spawn(async move {
+                client
+                    .request(proto::SetIndexText {
+                        project_id,
+                        buffer_id: buffer_id.into(),
+                        text: index_text,
+                    })
+                    .await?;
+                Ok(())
+            })
+        } else {
+            return Task::ready(Err(anyhow!("project is not shared")));
+        };
+
+        let buffer = buffer.downgrade();
+        let is_local = self.is_local();
+        cx.spawn(move |_, mut cx| async move {
+            write.await?;
+            buffer.update(&mut cx, |buffer, cx| {
+                buffer.set_diff_base(index_text.clone(), cx)
+            })?;
+            if let Some(project_id) = project_id.filter(|_| is_local) {
+                client
+                    .send(proto::UpdateDiffBase {
+                        project_id,
+                        buffer_id: buffer_id.into(),
+                        diff_base: index_text,
+                    })
+                    .log_err();
+            }
+            Ok(())
+        })
+    }
+
+    /// Loads the contents of the buffer's file at the HEAD commit of its git repository.
+    pub fn load_head_text(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Task<Result<Option<String>>> {
+        if self.is_local() {
+            let repo_and_path = self.local_repo_for_buffer(buffer, cx);
+            cx.background_executor().spawn(async move {
+                let (repo, relative_path) = repo_and_path?;
+                Ok(repo.load_head_text(&relative_path))
+            })
+        } else {
+            let Some(project_id) = self.remote_id() else {
+                return Task::ready(Err(anyhow!("project is not shared")));
+            };
+            let buffer_id = buffer.read(cx).remote_id();
+            let client = self.client.clone();
+            cx.background_executor().spawn(async move {
+                let response = client
+                    .request(proto::GetHeadText {
+                        project_id,
+                        buffer_id: buffer_id.into(),
+                    })
+                    .await?;
+                Ok(response.text)
+            })
+        }
+    }
+
+    fn local_repo_for_buffer(
+        &self,
+        buffer: &Model<Buffer>,
+        cx: &AppContext,
+    ) -> Result<(Arc<dyn GitRepository>, RepoPath)> {
+        let buffer_project_path = buffer
+            .read(cx)
+            .project_path(cx)
+            .context("failed to get buffer project path")?;
+
+        let worktree = self
+            .worktree_for_id(buffer_project_path.worktree_id, cx)
+            .context("failed to get worktree")?
+            .read(cx)
+            .as_local()
+            .context("worktree was not local")?
+            .snapshot();
+
+        let Some((repo_entry, local_repo_entry)) =
+            worktree.repo_for_path(&buffer_project_path.path)
+        else {
+            anyhow::bail!(NoRepositoryError {});
+        };
+
+        let relative_path = repo_entry
+            .relativize(&worktree, &buffer_project_path.path)
+            .context("failed to relativize buffer path")?;
+
+        Ok((local_repo_entry.repo().clone(), relative_path))
+    }
+
     // RPC message handlers
 
+    async fn handle_set_index_text(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::SetIndexText>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::Ack> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let buffer = this.update(&mut cx, |this, _cx| {
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })??;
+
+        this.update(&mut cx, |this, cx| {
+            this.set_index_text(&buffer, envelope.payload.text, cx)
+        })?
+        .await?;
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_get_head_text(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::GetHeadText>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::GetHeadTextResponse> {
+        let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+        let buffer = this.update(&mut cx, |this, _cx| {
+            this.opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", buffer_id))
+        })??;
+
+        let text = this
+            .update(&mut cx, |this, cx| this.load_head_text(&buffer, cx))?
+            .await?;
+        Ok(proto::GetHeadTextResponse { text })
+    }
+
     async fn handle_blame_buffer(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::BlameBuffer>,
@@ -9577,6 +10309,64 @@ impl Project {
         })
     }
 
+    async fn handle_resolve_code_lens(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ResolveCodeLens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ResolveCodeLensResponse> {
+        let lens = Self::deserialize_code_lens(
+            envelope
+                .payload
+                .lens
+                .ok_or_else(|| anyhow!("invalid code lens"))?,
+        )?;
+        let resolve_code_lens = this.update(&mut cx, |this, cx| {
+            let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+            let buffer = this
+                .opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", envelope.payload.buffer_id))?;
+            Ok::<_, anyhow::Error>(this.resolve_code_lens(&buffer, lens, cx))
+        })??;
+
+        let lens = resolve_code_lens.await?;
+        Ok(proto::ResolveCodeLensResponse {
+            lens: Some(Self::serialize_code_lens(&lens)),
+        })
+    }
+
+    async fn handle_apply_code_lens(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::ApplyCodeLens>,
+        mut cx: AsyncAppContext,
+    ) -> Result<proto::ApplyCodeLensResponse> {
+        let sender_id = envelope.original_sender_id()?;
+        let lens = Self::deserialize_code_lens(
+            envelope
+                .payload
+                .lens
+                .ok_or_else(|| anyhow!("invalid code lens"))?,
+        )?;
+        let apply_code_lens = this.update(&mut cx, |this, cx| {
+            let buffer_id = BufferId::new(envelope.payload.buffer_id)?;
+            let buffer = this
+                .opened_buffers
+                .get(&buffer_id)
+                .and_then(|buffer| buffer.upgrade())
+                .ok_or_else(|| anyhow!("unknown buffer id {}", envelope.payload.buffer_id))?;
+            Ok::<_, anyhow::Error>(this.apply_code_lens(buffer, lens, false, cx))
+        })??;
+
+        let project_transaction = apply_code_lens.await?;
+        let project_transaction = this.update(&mut cx, |this, cx| {
+            this.serialize_project_transaction_for_peer(project_transaction, sender_id, cx)
+        })?;
+        Ok(proto::ApplyCodeLensResponse {
+            transaction: Some(project_transaction),
+        })
+    }
+
     async fn handle_on_type_formatting(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::OnTypeFormatting>,
@@ -9797,6 +10587,18 @@ impl Project {
                         RevealStrategy::Never => proto::RevealStrategy::Never as i32,
                     },
                     tags: template.tags,
+                    depends_on: template.depends_on,
+                    depends_order: match template.depends_order {
+                        DependsOrder::Sequence => proto::DependsOrder::Sequence as i32,
+                        DependsOrder::Parallel => proto::DependsOrder::Parallel as i32,
+                    },
+                    problem_matchers: template
+                        .problem_matchers
+                        .iter()
+                        .filter_map(|problem_matcher| {
+                            serde_json::to_string(problem_matcher).log_err()
+                        })
+                        .collect(),
                 });
                 proto::TemplatePair { kind, template }
             })
@@ -9822,6 +10624,25 @@ impl Project {
         anyhow::Ok(())
     }
 
+    async fn try_resolve_code_lens(
+        lang_server: &LanguageServer,
+        lens: &mut CodeLens,
+    ) -> anyhow::Result<()> {
+        let can_resolve = lang_server
+            .capabilities()
+            .code_lens_provider
+            .as_ref()
+            .and_then(|options| options.resolve_provider)
+            .unwrap_or(false);
+        if can_resolve && lens.lsp_lens.command.is_none() {
+            lens.lsp_lens = lang_server
+                .request::<lsp::request::CodeLensResolve>(lens.lsp_lens.clone())
+                .await?;
+        }
+
+        anyhow::Ok(())
+    }
+
     async fn execute_code_actions_on_servers(
         project: &WeakModel<Project>,
         adapters_and_servers: &Vec<(Arc<CachedLspAdapter>, Arc<LanguageServer>)>,
@@ -9937,7 +10758,7 @@ impl Project {
             .update(&mut cx, |this, cx| {
                 this.request_lsp(
                     buffer_handle.clone(),
-                    LanguageServerToQuery::Primary,
+                    request.language_server_to_query(),
                     request,
                     cx,
                 )
@@ -10502,6 +11323,32 @@ impl Project {
         })
     }
 
+    fn serialize_code_lens(lens: &CodeLens) -> proto::CodeLens {
+        proto::CodeLens {
+            server_id: lens.server_id.0 as u64,
+            start: Some(serialize_anchor(&lens.range.start)),
+            end: Some(serialize_anchor(&lens.range.end)),
+            lsp_lens: serde_json::to_vec(&lens.lsp_lens).unwrap(),
+        }
+    }
+
+    fn deserialize_code_lens(lens: proto::CodeLens) -> Result<CodeLens> {
+        let start = lens
+            .start
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid start"))?;
+        let end = lens
+            .end
+            .and_then(deserialize_anchor)
+            .ok_or_else(|| anyhow!("invalid end"))?;
+        let lsp_lens = serde_json::from_slice(&lens.lsp_lens)?;
+        Ok(CodeLens {
+            server_id: LanguageServerId(lens.server_id as usize),
+            range: start..end,
+            lsp_lens,
+        })
+    }
+
     async fn handle_buffer_saved(
         this: Model<Self>,
         envelope: TypedEnvelope<proto::BufferSaved>,
@@ -10930,6 +11777,13 @@ impl Project {
                         proto::RevealStrategy::Always => RevealStrategy::Always,
                         proto::RevealStrategy::Never => RevealStrategy::Never,
                     };
+                    let depends_order =
+                        match proto::DependsOrder::from_i32(proto_template.depends_order)
+                            .unwrap_or(proto::DependsOrder::Sequence)
+                        {
+                            proto::DependsOrder::Sequence => DependsOrder::Sequence,
+                            proto::DependsOrder::Parallel => DependsOrder::Parallel,
+                        };
                     let task_template = TaskTemplate {
                         label: proto_template.label,
                         command: proto_template.command,
@@ -10940,6 +11794,15 @@ impl Project {
                         allow_concurrent_runs: proto_template.allow_concurrent_runs,
                         reveal,
                         tags: proto_template.tags,
+                        depends_on: proto_template.depends_on,
+                        depends_order,
+                        problem_matchers: proto_template
+                            .problem_matchers
+                            .iter()
+                            .filter_map(|problem_matcher| {
+                                serde_json::from_str(problem_matcher).log_err()
+                            })
+                            .collect(),
                     };
                     Some((task_source_kind, task_template))
                 })
@@ -1,11 +1,12 @@
-use crate::{Event, *};
+use crate::{debugger::DebugSessionStatus, Event, *};
+use dap::FakeDebugAdapter;
 use fs::FakeFs;
 use futures::{future, StreamExt};
 use gpui::{AppContext, SemanticVersion, UpdateGlobal};
 use language::{
     language_settings::{AllLanguageSettings, LanguageSettingsContent},
-    tree_sitter_rust, tree_sitter_typescript, Diagnostic, FakeLspAdapter, LanguageConfig,
-    LanguageMatcher, LineEnding, OffsetRangeExt, Point, ToPoint,
+    tree_sitter_rust, tree_sitter_typescript, ClientCommand, Diagnostic, FakeLspAdapter,
+    FoldingRangeKind, LanguageConfig, LanguageMatcher, LineEnding, OffsetRangeExt, Point, ToPoint,
 };
 use lsp::NumberOrString;
 use parking_lot::Mutex;
@@ -294,6 +295,142 @@ async fn test_managing_project_specific_settings(cx: &mut gpui::TestAppContext)
     );
 }
 
+#[gpui::test]
+async fn test_editorconfig_settings(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".editorconfig": r#"
+                root = true
+
+                [*]
+                indent_size = 4
+                trim_trailing_whitespace = false
+                end_of_line = crlf
+
+                [*.md]
+                max_line_length = 80
+            "#.unindent(),
+            "README.md": "# The root",
+            "a": {
+                "a.rs": "fn a() {\n    A\n}"
+            },
+            "b": {
+                ".editorconfig": "[*.rs]\nindent_style = tab\n",
+                ".zed": {
+                    "settings.json": r#"{ "tab_size": 2 }"#,
+                },
+                "b.rs": "fn b() {\n\tB\n}"
+            }
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+    let worktree = project.update(cx, |project, _| project.worktrees().next().unwrap());
+    cx.executor().run_until_parked();
+
+    let settings_for = |path: &str, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            let tree = worktree.read(cx);
+            let file =
+                File::for_entry(tree.entry_for_path(path).unwrap().clone(), worktree.clone());
+            language_settings(None, Some(&(file as _)), cx).into_owned()
+        })
+    };
+
+    let settings_a = settings_for("a/a.rs", cx);
+    assert_eq!(settings_a.tab_size.get(), 4);
+    assert!(!settings_a.hard_tabs);
+    assert!(!settings_a.remove_trailing_whitespace_on_save);
+    assert_eq!(settings_a.line_ending, Some(LineEnding::Windows));
+
+    // Local settings take precedence over `.editorconfig` properties.
+    let settings_b = settings_for("b/b.rs", cx);
+    assert_eq!(settings_b.tab_size.get(), 2);
+    assert!(settings_b.hard_tabs);
+    assert_eq!(settings_b.line_ending, Some(LineEnding::Windows));
+
+    let readme_settings = settings_for("README.md", cx);
+    assert_eq!(readme_settings.preferred_line_length, 80);
+
+    fs.insert_file("/the-root/b/.editorconfig", "root = true\n".into())
+        .await;
+    cx.executor().run_until_parked();
+    let settings_b = settings_for("b/b.rs", cx);
+    assert!(!settings_b.hard_tabs);
+    assert_eq!(settings_b.line_ending, None);
+
+    fs.remove_file("/the-root/.editorconfig".as_ref(), Default::default())
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let settings_a = settings_for("a/a.rs", cx);
+    assert!(settings_a.remove_trailing_whitespace_on_save);
+    assert_eq!(settings_a.line_ending, None);
+}
+
+#[gpui::test]
+async fn test_editorconfig_above_worktree(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/",
+        json!({
+            ".editorconfig": "root = true\n[*]\nend_of_line = crlf\n",
+            "parent": {
+                ".editorconfig": "[*.rs]\nindent_size = 3\n",
+                "the-root": {
+                    "a.rs": "fn a() {}",
+                },
+            },
+            "other": {
+                ".editorconfig": "root = true\n[*]\nindent_size = 8\n",
+                "the-root": {
+                    ".editorconfig": "root = true\n",
+                    "b.rs": "fn b() {}",
+                },
+            },
+        }),
+    )
+    .await;
+
+    let project = Project::test(
+        fs.clone(),
+        ["/parent/the-root".as_ref(), "/other/the-root".as_ref()],
+        cx,
+    )
+    .await;
+    cx.executor().run_until_parked();
+
+    let settings_for = |path: &str, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            let (worktree, path) = project
+                .read(cx)
+                .find_local_worktree(Path::new(path), cx)
+                .unwrap();
+            let tree = worktree.read(cx);
+            let file =
+                File::for_entry(tree.entry_for_path(path).unwrap().clone(), worktree.clone());
+            language_settings(None, Some(&(file as _)), cx).into_owned()
+        })
+    };
+
+    // `.editorconfig` files above the worktree apply, up to the one marked as the root.
+    let settings_a = settings_for("/parent/the-root/a.rs", cx);
+    assert_eq!(settings_a.tab_size.get(), 3);
+    assert_eq!(settings_a.line_ending, Some(LineEnding::Windows));
+
+    // The worktree's own root `.editorconfig` hides the ones above it.
+    let settings_b = settings_for("/other/the-root/b.rs", cx);
+    assert_eq!(settings_b.tab_size.get(), 4);
+    assert_eq!(settings_b.line_ending, None);
+}
+
 #[gpui::test]
 async fn test_managing_language_servers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -2382,99 +2519,776 @@ fn chunks_with_diagnostics<T: ToOffset + ToPoint>(
     chunks
 }
 
-#[gpui::test(iterations = 10)]
-async fn test_definition(cx: &mut gpui::TestAppContext) {
+#[gpui::test(iterations = 10)]
+async fn test_definition(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "const fn a() { A }",
+            "b.rs": "const y: i32 = crate::a()",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir/b.rs".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers =
+        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::GotoDefinition, _, _>(|params, _| async move {
+        let params = params.text_document_position_params;
+        assert_eq!(
+            params.text_document.uri.to_file_path().unwrap(),
+            Path::new("/dir/b.rs"),
+        );
+        assert_eq!(params.position, lsp::Position::new(0, 22));
+
+        Ok(Some(lsp::GotoDefinitionResponse::Scalar(
+            lsp::Location::new(
+                lsp::Url::from_file_path("/dir/a.rs").unwrap(),
+                lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 10)),
+            ),
+        )))
+    });
+
+    let mut definitions = project
+        .update(cx, |project, cx| project.definition(&buffer, 22, cx))
+        .await
+        .unwrap();
+
+    // Assert no new language server started
+    cx.executor().run_until_parked();
+    assert!(fake_servers.try_next().is_err());
+
+    assert_eq!(definitions.len(), 1);
+    let definition = definitions.pop().unwrap();
+    cx.update(|cx| {
+        let target_buffer = definition.target.buffer.read(cx);
+        assert_eq!(
+            target_buffer
+                .file()
+                .unwrap()
+                .as_local()
+                .unwrap()
+                .abs_path(cx),
+            Path::new("/dir/a.rs"),
+        );
+        assert_eq!(definition.target.range.to_offset(target_buffer), 9..10);
+        assert_eq!(
+            list_worktrees(&project, cx),
+            [("/dir/a.rs".as_ref(), false), ("/dir/b.rs".as_ref(), true)],
+        );
+
+        drop(definition);
+    });
+    cx.update(|cx| {
+        assert_eq!(list_worktrees(&project, cx), [("/dir/b.rs".as_ref(), true)]);
+    });
+
+    fn list_worktrees<'a>(
+        project: &'a Model<Project>,
+        cx: &'a AppContext,
+    ) -> Vec<(&'a Path, bool)> {
+        project
+            .read(cx)
+            .worktrees()
+            .map(|worktree| {
+                let worktree = worktree.read(cx);
+                (
+                    worktree.as_local().unwrap().abs_path().as_ref(),
+                    worktree.is_visible(),
+                )
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+#[gpui::test]
+async fn test_call_hierarchy(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn a() { b() }",
+            "b.rs": "pub fn b() {}",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                call_hierarchy_provider: Some(lsp::CallHierarchyServerCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+    let lsp_item = |name: &str, path: &str, range: lsp::Range| lsp::CallHierarchyItem {
+        name: name.to_string(),
+        kind: lsp::SymbolKind::FUNCTION,
+        tags: None,
+        detail: None,
+        uri: lsp::Url::from_file_path(path).unwrap(),
+        range,
+        selection_range: range,
+        data: None,
+    };
+    let b_item = lsp_item(
+        "b",
+        "/dir/b.rs",
+        lsp::Range::new(lsp::Position::new(0, 7), lsp::Position::new(0, 8)),
+    );
+    let a_item = lsp_item(
+        "a",
+        "/dir/a.rs",
+        lsp::Range::new(lsp::Position::new(0, 3), lsp::Position::new(0, 4)),
+    );
+    fake_server.handle_request::<lsp::request::CallHierarchyPrepare, _, _>({
+        let b_item = b_item.clone();
+        move |params, _| {
+            let b_item = b_item.clone();
+            async move {
+                assert_eq!(
+                    params.text_document_position_params.position,
+                    lsp::Position::new(0, 7)
+                );
+                Ok(Some(vec![b_item]))
+            }
+        }
+    });
+    fake_server.handle_request::<lsp::request::CallHierarchyIncomingCalls, _, _>(
+        move |params, _| {
+            let a_item = a_item.clone();
+            async move {
+                assert_eq!(params.item.name, "b");
+                Ok(Some(vec![lsp::CallHierarchyIncomingCall {
+                    from: a_item,
+                    from_ranges: vec![lsp::Range::new(
+                        lsp::Position::new(0, 9),
+                        lsp::Position::new(0, 10),
+                    )],
+                }]))
+            }
+        },
+    );
+
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_call_hierarchy(&buffer, 7, cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "b");
+    assert_eq!(items[0].location.buffer, buffer);
+    // Follow-up requests go to the server that returned the item.
+    assert_eq!(items[0].language_server_id, fake_server.server.server_id());
+
+    let calls = project
+        .update(cx, |project, cx| project.incoming_calls(&items[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].item.name, "a");
+    assert_eq!(
+        calls[0].item.language_server_id,
+        fake_server.server.server_id()
+    );
+    cx.update(|cx| {
+        let caller_buffer = calls[0].item.location.buffer.read(cx);
+        assert_eq!(
+            caller_buffer
+                .file()
+                .unwrap()
+                .as_local()
+                .unwrap()
+                .abs_path(cx),
+            Path::new("/dir/a.rs"),
+        );
+        let call_sites = calls[0]
+            .call_sites
+            .iter()
+            .map(|call_site| {
+                assert_eq!(call_site.buffer, calls[0].item.location.buffer);
+                call_site.range.to_offset(caller_buffer)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(call_sites, [9..10]);
+    });
+}
+
+#[gpui::test]
+async fn test_type_hierarchy(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "lib.rs": "trait A {}\nstruct B;\nimpl A for B {}\n",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            name: "the-primary-server",
+            ..Default::default()
+        },
+    );
+    language_registry.register_specific_fake_lsp_adapter(
+        "Rust",
+        false,
+        FakeLspAdapter {
+            name: "the-secondary-server",
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/lib.rs", cx)
+        })
+        .await
+        .unwrap();
+
+    let mut primary_server = None;
+    let mut secondary_server = None;
+    for _ in 0..2 {
+        let fake_server = fake_servers.next().await.unwrap();
+        if fake_server.server.name() == "the-primary-server" {
+            primary_server = Some(fake_server);
+        } else {
+            secondary_server = Some(fake_server);
+        }
+    }
+    let (primary_server, secondary_server) = (primary_server.unwrap(), secondary_server.unwrap());
+
+    let lsp_item = |name: &str, kind, row| lsp::TypeHierarchyItem {
+        name: name.to_string(),
+        kind,
+        tags: None,
+        detail: None,
+        uri: lsp::Url::from_file_path("/dir/lib.rs").unwrap(),
+        range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 1)),
+        selection_range: lsp::Range::new(lsp::Position::new(row, 0), lsp::Position::new(row, 1)),
+        data: None,
+    };
+    let a_item = lsp_item("A", lsp::SymbolKind::INTERFACE, 0);
+    let b_item = lsp_item("B", lsp::SymbolKind::STRUCT, 1);
+    primary_server.handle_request::<lsp::request::TypeHierarchyPrepare, _, _>({
+        let b_item = b_item.clone();
+        move |params, _| {
+            let b_item = b_item.clone();
+            async move {
+                assert_eq!(
+                    params.text_document_position_params.position,
+                    lsp::Position::new(1, 7)
+                );
+                Ok(Some(vec![b_item]))
+            }
+        }
+    });
+    primary_server.handle_request::<lsp::request::TypeHierarchySupertypes, _, _>({
+        let a_item = a_item.clone();
+        move |params, _| {
+            let a_item = a_item.clone();
+            async move {
+                assert_eq!(params.item.name, "B");
+                Ok(Some(vec![a_item]))
+            }
+        }
+    });
+    primary_server.handle_request::<lsp::request::TypeHierarchySubtypes, _, _>({
+        let b_item = b_item.clone();
+        move |params, _| {
+            let b_item = b_item.clone();
+            async move {
+                assert_eq!(params.item.name, "A");
+                Ok(Some(vec![b_item]))
+            }
+        }
+    });
+
+    let items = project
+        .update(cx, |project, cx| {
+            project.prepare_type_hierarchy(&buffer, Point::new(1, 7), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "B");
+    assert_eq!(items[0].kind, lsp::SymbolKind::STRUCT);
+    assert_eq!(
+        items[0].language_server_id,
+        primary_server.server.server_id()
+    );
+
+    let supertypes = project
+        .update(cx, |project, cx| project.supertypes(&items[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(supertypes.len(), 1);
+    assert_eq!(supertypes[0].name, "A");
+    cx.update(|cx| {
+        assert_eq!(supertypes[0].location.buffer, buffer);
+        assert_eq!(
+            buffer
+                .read(cx)
+                .summary_for_anchor::<Point>(&supertypes[0].selection_range.start),
+            Point::new(0, 0)
+        );
+    });
+
+    let subtypes = project
+        .update(cx, |project, cx| project.subtypes(&supertypes[0], cx))
+        .await
+        .unwrap();
+    assert_eq!(subtypes.len(), 1);
+    assert_eq!(subtypes[0].name, "B");
+
+    // Items returned by another server of the buffer are resolved by that server.
+    secondary_server.handle_request::<lsp::request::TypeHierarchySubtypes, _, _>(
+        move |params, _| async move {
+            assert_eq!(params.item.name, "A");
+            Ok(Some(Vec::new()))
+        },
+    );
+    let mut secondary_item = supertypes[0].clone();
+    secondary_item.language_server_id = secondary_server.server.server_id();
+    let subtypes = project
+        .update(cx, |project, cx| project.subtypes(&secondary_item, cx))
+        .await
+        .unwrap();
+    assert!(subtypes.is_empty());
+}
+
+#[gpui::test]
+async fn test_type_hierarchy_requests_from_guests(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "lib.rs": "trait A {}\nstruct B;\n" }))
+        .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter("Rust", Default::default());
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/lib.rs", cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    let server_id = fake_server.server.server_id();
+    let range = lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 7));
+    let a_item = lsp::TypeHierarchyItem {
+        name: "A".to_string(),
+        kind: lsp::SymbolKind::INTERFACE,
+        tags: None,
+        detail: None,
+        uri: lsp::Url::from_file_path("/dir/lib.rs").unwrap(),
+        range,
+        selection_range: range,
+        data: None,
+    };
+    fake_server.handle_request::<lsp::request::TypeHierarchySupertypes, _, _>({
+        let a_item = a_item.clone();
+        move |_, _| {
+            let a_item = a_item.clone();
+            async move { Ok(Some(vec![a_item])) }
+        }
+    });
+
+    // The guest sends the id of the server that returned the item along with it, which the host
+    // forwards the request to.
+    let request = GetSupertypes {
+        item: a_item,
+        language_server_id: server_id,
+    };
+    let message = buffer.read_with(cx, |buffer, _| request.to_proto(1, buffer));
+    assert_eq!(message.language_server_id, server_id.0 as u64);
+    let request =
+        GetSupertypes::from_proto(message, project.clone(), buffer.clone(), cx.to_async())
+            .await
+            .unwrap();
+    assert!(matches!(
+        request.language_server_to_query(),
+        LanguageServerToQuery::Other(id) if id == server_id
+    ));
+
+    let response = project
+        .update(cx, |project, cx| {
+            project.request_lsp(
+                buffer.clone(),
+                request.language_server_to_query(),
+                request,
+                cx,
+            )
+        })
+        .await
+        .unwrap();
+    let message = project.update(cx, |project, cx| {
+        GetSupertypes::response_to_proto(
+            response,
+            project,
+            proto::PeerId { owner_id: 0, id: 0 },
+            &clock::Global::new(),
+            cx,
+        )
+    });
+    assert_eq!(message.items.len(), 1);
+    assert_eq!(message.items[0].language_server_id, server_id.0 as u64);
+
+    // The guest's items keep the server id, for their own supertypes and subtypes.
+    let request = GetSupertypes {
+        item: serde_json::from_slice(&message.items[0].lsp_item).unwrap(),
+        language_server_id: server_id,
+    };
+    let items = request
+        .response_from_proto(message, project.clone(), buffer.clone(), cx.to_async())
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].name, "A");
+    assert_eq!(items[0].language_server_id, server_id);
+}
+
+#[gpui::test]
+async fn test_code_lens(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "fn a() {}\n\nfn b() { a() }",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                code_lens_provider: Some(lsp::CodeLensOptions {
+                    resolve_provider: Some(true),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.handle_request::<lsp::request::CodeLensRequest, _, _>(|_, _| async move {
+        Ok(Some(vec![lsp::CodeLens {
+            range: lsp::Range::new(lsp::Position::new(0, 3), lsp::Position::new(0, 4)),
+            command: None,
+            data: Some(json!({ "references": "a" })),
+        }]))
+    });
+    fake_server.handle_request::<lsp::request::CodeLensResolve, _, _>(|lens, _| async move {
+        assert_eq!(lens.data, Some(json!({ "references": "a" })));
+        Ok(lsp::CodeLens {
+            command: Some(lsp::Command {
+                title: "1 reference".to_string(),
+                command: "references".to_string(),
+                arguments: None,
+            }),
+            ..lens
+        })
+    });
+
+    let lenses = project
+        .update(cx, |project, cx| project.code_lens(&buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(lenses.len(), 1);
+    assert_eq!(lenses[0].lsp_lens.command, None);
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(lenses[0].range.to_offset(buffer), 3..4);
+    });
+
+    let lens = project
+        .update(cx, |project, cx| {
+            project.resolve_code_lens(&buffer, lenses[0].clone(), cx)
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        lens.lsp_lens
+            .command
+            .as_ref()
+            .map(|command| command.title.as_str()),
+        Some("1 reference")
+    );
+
+    // Commands are executed by the language server, unless its adapter handles them.
+    project.read_with(cx, |project, cx| {
+        assert_eq!(project.code_lens_client_command(&buffer, &lens, cx), None);
+        let mut show_references = lens.clone();
+        show_references.lsp_lens.command.as_mut().unwrap().command =
+            "editor.action.showReferences".to_string();
+        assert_eq!(
+            project.code_lens_client_command(&buffer, &show_references, cx),
+            Some(ClientCommand::ShowReferences)
+        );
+    });
+}
+
+#[gpui::test]
+async fn test_folding_ranges(cx: &mut gpui::TestAppContext) {
     init_test(cx);
 
     let fs = FakeFs::new(cx.executor());
     fs.insert_tree(
         "/dir",
         json!({
-            "a.rs": "const fn a() { A }",
-            "b.rs": "const y: i32 = crate::a()",
+            "a.rs": "// one\n// two\nfn a() {\n    b();\n}\n",
         }),
     )
     .await;
 
-    let project = Project::test(fs, ["/dir/b.rs".as_ref()], cx).await;
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
 
     let language_registry = project.read_with(cx, |project, _| project.languages().clone());
     language_registry.add(rust_lang());
-    let mut fake_servers =
-        language_registry.register_fake_lsp_adapter("Rust", FakeLspAdapter::default());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                folding_range_provider: Some(lsp::FoldingRangeProviderCapability::Simple(true)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
 
     let buffer = project
-        .update(cx, |project, cx| project.open_local_buffer("/dir/b.rs", cx))
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
         .await
         .unwrap();
 
     let fake_server = fake_servers.next().await.unwrap();
-    fake_server.handle_request::<lsp::request::GotoDefinition, _, _>(|params, _| async move {
-        let params = params.text_document_position_params;
+    fake_server.handle_request::<lsp::request::FoldingRangeRequest, _, _>(|_, _| async move {
+        Ok(Some(vec![
+            lsp::FoldingRange {
+                start_line: 2,
+                end_line: 3,
+                kind: None,
+                ..Default::default()
+            },
+            lsp::FoldingRange {
+                start_line: 0,
+                end_line: 1,
+                kind: Some(lsp::FoldingRangeKind::Comment),
+                ..Default::default()
+            },
+            // Ranges on a single line can't be folded.
+            lsp::FoldingRange {
+                start_line: 4,
+                end_line: 4,
+                kind: None,
+                ..Default::default()
+            },
+        ]))
+    });
+
+    let ranges = project
+        .update(cx, |project, cx| project.folding_ranges(&buffer, cx))
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
         assert_eq!(
-            params.text_document.uri.to_file_path().unwrap(),
-            Path::new("/dir/b.rs"),
+            ranges
+                .iter()
+                .map(|range| (range.range.to_point(buffer), range.kind))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    Point::new(0, 6)..Point::new(1, 6),
+                    Some(FoldingRangeKind::Comment)
+                ),
+                (Point::new(2, 8)..Point::new(3, 8), None),
+            ]
         );
-        assert_eq!(params.position, lsp::Position::new(0, 22));
-
-        Ok(Some(lsp::GotoDefinitionResponse::Scalar(
-            lsp::Location::new(
-                lsp::Url::from_file_path("/dir/a.rs").unwrap(),
-                lsp::Range::new(lsp::Position::new(0, 9), lsp::Position::new(0, 10)),
-            ),
-        )))
     });
+}
 
-    let mut definitions = project
-        .update(cx, |project, cx| project.definition(&buffer, 22, cx))
+#[gpui::test]
+async fn test_semantic_tokens(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            "a.rs": "let mut x = 1;\nx += 1;",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                semantic_tokens_provider: Some(
+                    lsp::SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        lsp::SemanticTokensOptions {
+                            legend: lsp::SemanticTokensLegend {
+                                token_types: vec![
+                                    lsp::SemanticTokenType::VARIABLE,
+                                    lsp::SemanticTokenType::KEYWORD,
+                                ],
+                                token_modifiers: vec![lsp::SemanticTokenModifier::new("mutable")],
+                            },
+                            full: Some(lsp::SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            ..Default::default()
+                        },
+                    ),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
         .await
         .unwrap();
 
-    // Assert no new language server started
-    cx.executor().run_until_parked();
-    assert!(fake_servers.try_next().is_err());
+    let fake_server = fake_servers.next().await.unwrap();
+    let token =
+        |delta_line, delta_start, length, token_type, token_modifiers_bitset| lsp::SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type,
+            token_modifiers_bitset,
+        };
+    fake_server.handle_request::<lsp::request::SemanticTokensFullRequest, _, _>(
+        move |_, _| async move {
+            Ok(Some(lsp::SemanticTokensResult::Tokens(
+                lsp::SemanticTokens {
+                    result_id: Some("1".into()),
+                    data: vec![
+                        token(0, 0, 3, 1, 0),
+                        token(0, 8, 1, 0, 1),
+                        token(1, 0, 1, 0, 1),
+                    ],
+                },
+            )))
+        },
+    );
+    fake_server.handle_request::<lsp::request::SemanticTokensFullDeltaRequest, _, _>(
+        |params, _| async move {
+            assert_eq!(params.previous_result_id, "1");
+            Ok(Some(lsp::SemanticTokensFullDeltaResult::TokensDelta(
+                lsp::SemanticTokensDelta {
+                    result_id: Some("2".into()),
+                    edits: vec![lsp::SemanticTokensEdit {
+                        start: 0,
+                        delete_count: 5,
+                        data: None,
+                    }],
+                },
+            )))
+        },
+    );
 
-    assert_eq!(definitions.len(), 1);
-    let definition = definitions.pop().unwrap();
-    cx.update(|cx| {
-        let target_buffer = definition.target.buffer.read(cx);
-        assert_eq!(
-            target_buffer
-                .file()
-                .unwrap()
-                .as_local()
-                .unwrap()
-                .abs_path(cx),
-            Path::new("/dir/a.rs"),
-        );
-        assert_eq!(definition.target.range.to_offset(target_buffer), 9..10);
-        assert_eq!(
-            list_worktrees(&project, cx),
-            [("/dir/a.rs".as_ref(), false), ("/dir/b.rs".as_ref(), true)],
-        );
+    let describe = |tokens: &SemanticTokens, cx: &mut gpui::TestAppContext| {
+        buffer.read_with(cx, |buffer, _| {
+            tokens
+                .tokens
+                .iter()
+                .map(|token| {
+                    (
+                        token.range.to_offset(buffer),
+                        tokens.token_type(token).unwrap().to_string(),
+                        tokens.token_modifiers(token).collect::<Vec<_>>().join("."),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+    };
 
-        drop(definition);
-    });
-    cx.update(|cx| {
-        assert_eq!(list_worktrees(&project, cx), [("/dir/b.rs".as_ref(), true)]);
-    });
+    // Tokens refer to the buffer when they were requested, even if it was edited since.
+    let request = project.update(cx, |project, cx| project.semantic_tokens(&buffer, cx));
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, " ")], None, cx));
+    let tokens = request.await.unwrap();
+    assert_eq!(tokens.result_id.as_deref(), Some("1"));
+    assert_eq!(
+        describe(&tokens, cx),
+        [
+            (1..4, "keyword".to_string(), "".to_string()),
+            (9..10, "variable".to_string(), "mutable".to_string()),
+            (16..17, "variable".to_string(), "mutable".to_string()),
+        ]
+    );
 
-    fn list_worktrees<'a>(
-        project: &'a Model<Project>,
-        cx: &'a AppContext,
-    ) -> Vec<(&'a Path, bool)> {
-        project
-            .read(cx)
-            .worktrees()
-            .map(|worktree| {
-                let worktree = worktree.read(cx);
-                (
-                    worktree.as_local().unwrap().abs_path().as_ref(),
-                    worktree.is_visible(),
-                )
-            })
-            .collect::<Vec<_>>()
-    }
+    // The next request only asks for the changes since the last result.
+    let tokens = project
+        .update(cx, |project, cx| project.semantic_tokens(&buffer, cx))
+        .await
+        .unwrap();
+    assert_eq!(tokens.result_id.as_deref(), Some("2"));
+    assert_eq!(
+        describe(&tokens, cx),
+        [
+            (9..10, "variable".to_string(), "mutable".to_string()),
+            (16..17, "variable".to_string(), "mutable".to_string()),
+        ]
+    );
 }
 
 #[gpui::test]
@@ -5171,6 +5985,224 @@ async fn test_reordering_worktrees(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_debug_session(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/dir",
+        json!({
+            ".zed": {
+                "debug.json": r#"[{ "label": "Debug main", "command": "fake-adapter" }]"#
+            },
+            "main.rs": "fn main() {\n    println!(\"hello\");\n}\n",
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees().next().unwrap().read(cx).id()
+    });
+
+    let configurations = project
+        .update(cx, |project, cx| project.debug_configurations(cx))
+        .await;
+    let [(config_worktree_id, config)] = configurations.as_slice() else {
+        panic!("unexpected debug configurations {configurations:?}");
+    };
+    assert_eq!(*config_worktree_id, worktree_id);
+    assert_eq!(config.label, "Debug main");
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/main.rs", cx)
+        })
+        .await
+        .unwrap();
+    let buffer_id = buffer.update(cx, |buffer, _| buffer.remote_id());
+    project.update(cx, |project, cx| {
+        project.toggle_breakpoint(&buffer, 1, cx);
+        project.toggle_breakpoint(&buffer, 2, cx);
+        project.toggle_breakpoint(&buffer, 2, cx);
+        assert_eq!(project.breakpoints_for_buffer(buffer_id).len(), 1);
+    });
+
+    // Breakpoints are kept by path when their buffer is released, and anchored again when it's
+    // reopened.
+    drop(buffer);
+    cx.run_until_parked();
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/main.rs", cx)
+        })
+        .await
+        .unwrap();
+    let buffer_id = buffer.update(cx, |buffer, _| buffer.remote_id());
+    project.update(cx, |project, cx| {
+        let snapshot = buffer.read(cx).snapshot();
+        let rows = project
+            .breakpoints_for_buffer(buffer_id)
+            .iter()
+            .map(|breakpoint| breakpoint.to_point(&snapshot).row)
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [1]);
+    });
+
+    let (client, fake) = FakeDebugAdapter::new(
+        dap::DebugAdapterClientId(0),
+        "fake-adapter".into(),
+        dap::Capabilities {
+            supports_configuration_done_request: Some(true),
+            ..Default::default()
+        },
+        cx.to_async(),
+    );
+    let set_breakpoints = Arc::new(Mutex::new(Vec::new()));
+    fake.handle_request::<dap::requests::Launch, _, _>(|_, _| async move { Ok(()) });
+    fake.handle_request::<dap::requests::SetBreakpoints, _, _>({
+        let set_breakpoints = set_breakpoints.clone();
+        move |arguments, _| {
+            set_breakpoints.lock().push((
+                arguments.source.path.unwrap(),
+                arguments
+                    .breakpoints
+                    .iter()
+                    .map(|breakpoint| breakpoint.line)
+                    .collect::<Vec<_>>(),
+            ));
+            async move { Ok(dap::SetBreakpointsResponse::default()) }
+        }
+    });
+    let mut configuration_done =
+        fake.handle_request::<dap::requests::ConfigurationDone, _, _>(|_, _| async move { Ok(()) });
+
+    let session = project.update(cx, |project, cx| {
+        project.start_debug_session_with_client(client, config.clone(), cx)
+    });
+    cx.run_until_parked();
+    assert!(set_breakpoints.lock().is_empty());
+
+    // Breakpoints are only sent once the adapter is initialized.
+    fake.send_event::<dap::events::Initialized>(());
+    configuration_done.next().await.unwrap();
+    let session = session.await.unwrap();
+    assert_eq!(
+        set_breakpoints.lock().drain(..).collect::<Vec<_>>(),
+        [("/dir/main.rs".to_string(), vec![2])]
+    );
+    session.update(cx, |session, _| {
+        assert_eq!(session.status(), DebugSessionStatus::Running)
+    });
+
+    fake.handle_request::<dap::requests::Threads, _, _>(|_, _| async move {
+        Ok(dap::ThreadsResponse {
+            threads: vec![dap::Thread {
+                id: 1,
+                name: "main".into(),
+            }],
+        })
+    });
+    fake.handle_request::<dap::requests::StackTrace, _, _>(|arguments, _| async move {
+        assert_eq!(arguments.thread_id, 1);
+        Ok(dap::StackTraceResponse {
+            stack_frames: vec![dap::StackFrame {
+                id: 7,
+                name: "main".into(),
+                source: None,
+                line: 2,
+                column: 5,
+            }],
+            total_frames: Some(1),
+        })
+    });
+    fake.send_event::<dap::events::Stopped>(dap::StoppedEventBody {
+        reason: "breakpoint".into(),
+        description: None,
+        thread_id: Some(1),
+        all_threads_stopped: Some(true),
+        text: None,
+    });
+    cx.run_until_parked();
+    session.update(cx, |session, _| {
+        assert_eq!(session.status(), DebugSessionStatus::Stopped);
+        assert_eq!(session.active_thread_id(), Some(1));
+        assert_eq!(session.stack_frames()[0].line, 2);
+    });
+
+    // Breakpoints toggled during the session are sent to the adapter.
+    project.update(cx, |project, cx| project.toggle_breakpoint(&buffer, 0, cx));
+    cx.run_until_parked();
+    assert_eq!(
+        set_breakpoints.lock().drain(..).collect::<Vec<_>>(),
+        [("/dir/main.rs".to_string(), vec![1, 2])]
+    );
+
+    // Breakpoints that moved with edits are sent again once the file is saved.
+    buffer.update(cx, |buffer, cx| buffer.edit([(11..11, "\n")], None, cx));
+    cx.run_until_parked();
+    assert!(set_breakpoints.lock().is_empty());
+    project
+        .update(cx, |project, cx| project.save_buffer(buffer.clone(), cx))
+        .await
+        .unwrap();
+    cx.run_until_parked();
+    assert_eq!(
+        set_breakpoints.lock().drain(..).collect::<Vec<_>>(),
+        [("/dir/main.rs".to_string(), vec![1, 3])]
+    );
+
+    fake.handle_request::<dap::requests::Continue, _, _>(|_, _| async move {
+        Ok(dap::ContinueResponse::default())
+    });
+    session
+        .update(cx, |session, cx| session.continue_thread(cx))
+        .await
+        .unwrap();
+    session.update(cx, |session, _| {
+        assert_eq!(session.status(), DebugSessionStatus::Running);
+        assert!(session.stack_frames().is_empty());
+    });
+
+    fake.send_event::<dap::events::Terminated>(None);
+    cx.run_until_parked();
+    session.update(cx, |session, _| {
+        assert_eq!(session.status(), DebugSessionStatus::Terminated)
+    });
+    project.update(cx, |project, _| {
+        assert_eq!(project.debug_sessions().count(), 0)
+    });
+}
+
+#[gpui::test]
+async fn test_toggle_breakpoints_on_joined_lines(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "main.rs": "a\nb\nc\n" }))
+        .await;
+    let project = Project::test(fs, ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/main.rs", cx)
+        })
+        .await
+        .unwrap();
+    let buffer_id = buffer.update(cx, |buffer, _| buffer.remote_id());
+    project.update(cx, |project, cx| {
+        project.toggle_breakpoint(&buffer, 1, cx);
+        project.toggle_breakpoint(&buffer, 2, cx);
+    });
+
+    // Joining the lines moves both breakpoints onto the same row, and toggling it removes both.
+    buffer.update(cx, |buffer, cx| buffer.edit([(3..4, "")], None, cx));
+    project.update(cx, |project, cx| {
+        assert_eq!(project.breakpoints_for_buffer(buffer_id).len(), 2);
+        project.toggle_breakpoint(&buffer, 1, cx);
+        assert!(project.breakpoints_for_buffer(buffer_id).is_empty());
+    });
+}
+
 async fn search(
     project: &Model<Project>,
     query: SearchQuery,
@@ -7,8 +7,8 @@ use std::{
     sync::Arc,
 };
 
-use anyhow::Result;
-use collections::{btree_map, BTreeMap, VecDeque};
+use anyhow::{bail, Context as _, Result};
+use collections::{btree_map, BTreeMap, HashSet, VecDeque};
 use futures::{
     channel::mpsc::{unbounded, UnboundedSender},
     StreamExt,
@@ -17,8 +17,8 @@ use gpui::{AppContext, Context, Model, ModelContext, Task};
 use itertools::Itertools;
 use language::{ContextProvider, Language, Location};
 use task::{
-    static_source::StaticSource, ResolvedTask, TaskContext, TaskId, TaskTemplate, TaskTemplates,
-    TaskVariables, VariableName,
+    static_source::StaticSource, DependsOrder, ResolvedTask, SpawnInTerminal, TaskContext, TaskId,
+    TaskTemplate, TaskTemplates, TaskVariables, VariableName,
 };
 use text::{Point, ToPoint};
 use util::{post_inc, NumericPrefixWithSuffix, ResultExt};
@@ -235,7 +235,7 @@ impl Inventory {
                 },
             );
         let not_used_score = post_inc(&mut lru_score);
-        let mut currently_resolved_tasks = self
+        let mut templates = self
             .sources
             .iter()
             .filter(|source| {
@@ -251,6 +251,10 @@ impl Inventory {
                     .map(|task| (&source.kind, task))
             })
             .chain(language_tasks.filter(|_| remote_templates_task.is_none()))
+            .map(|(kind, task)| (kind.clone(), task))
+            .collect::<Vec<_>>();
+        let mut currently_resolved_tasks = templates
+            .iter()
             .filter_map(|(kind, task)| {
                 let id_base = kind.to_id_base();
                 Some((kind, task.resolve_task(&id_base, task_context)?))
@@ -277,15 +281,21 @@ impl Inventory {
                 },
                 None => Vec::new(),
             };
-            let remote_tasks = remote_templates.into_iter().filter_map(|(kind, task)| {
+            let remote_tasks = remote_templates.iter().filter_map(|(kind, task)| {
                 let id_base = kind.to_id_base();
                 Some((
-                    kind,
+                    kind.clone(),
                     task.resolve_task(&id_base, &task_context)?,
                     not_used_score,
                 ))
             });
             currently_resolved_tasks.extend(remote_tasks);
+            templates.extend(remote_templates);
+            currently_resolved_tasks.retain_mut(|(kind, task, _)| {
+                resolve_prerequisites(&templates, kind, task, &task_context)
+                    .log_err()
+                    .is_some()
+            });
 
             let mut tasks_by_label = BTreeMap::default();
             tasks_by_label = previously_spawned_tasks.into_iter().fold(
@@ -348,6 +358,31 @@ impl Inventory {
         })
     }
 
+    /// Resolves the template given with the [`TaskContext`], along with all tasks it depends on.
+    /// The dependencies are looked up by their labels among the tasks available in the worktree of the template's source,
+    /// preferring the ones from the same source.
+    pub fn resolve_task_with_dependencies(
+        &self,
+        task_source_kind: &TaskSourceKind,
+        template: &TaskTemplate,
+        task_context: &TaskContext,
+    ) -> Result<Option<ResolvedTask>> {
+        let Some(mut resolved_task) =
+            template.resolve_task(&task_source_kind.to_id_base(), task_context)
+        else {
+            return Ok(None);
+        };
+        let mut templates = self.list_tasks(None, task_source_kind.worktree());
+        templates.push((task_source_kind.clone(), template.clone()));
+        resolve_prerequisites(
+            &templates,
+            task_source_kind,
+            &mut resolved_task,
+            task_context,
+        )?;
+        Ok(Some(resolved_task))
+    }
+
     /// Returns the last scheduled task by task_id if provided.
     /// Otherwise, returns the last scheduled task.
     pub fn last_scheduled_task(
@@ -384,6 +419,95 @@ impl Inventory {
     }
 }
 
+/// Resolves the tasks that the resolved task depends on, transitively, and stores them as its [`SpawnInTerminal::prerequisites`].
+/// Fails if any of the dependencies cannot be found or resolved, or if they form a cycle.
+fn resolve_prerequisites(
+    templates: &[(TaskSourceKind, TaskTemplate)],
+    task_source_kind: &TaskSourceKind,
+    resolved_task: &mut ResolvedTask,
+    task_context: &TaskContext,
+) -> Result<()> {
+    let template = resolved_task.original_task();
+    if template.depends_on.is_empty() {
+        return Ok(());
+    }
+    let mut stages = dependency_stages(
+        templates,
+        task_source_kind,
+        template,
+        task_context,
+        &mut vec![template.label.clone()],
+    )?;
+    // A task that is required by several others only needs to run once, as early as it is needed.
+    let mut scheduled_tasks = HashSet::default();
+    for stage in &mut stages {
+        stage.retain(|task| scheduled_tasks.insert(task.id.clone()));
+    }
+    stages.retain(|stage| !stage.is_empty());
+    if let Some(spawn_in_terminal) = resolved_task.resolved.as_mut() {
+        spawn_in_terminal.prerequisites = stages;
+    }
+    Ok(())
+}
+
+fn dependency_stages(
+    templates: &[(TaskSourceKind, TaskTemplate)],
+    task_source_kind: &TaskSourceKind,
+    template: &TaskTemplate,
+    task_context: &TaskContext,
+    labels_stack: &mut Vec<String>,
+) -> Result<Vec<Vec<SpawnInTerminal>>> {
+    let mut stages = Vec::<Vec<SpawnInTerminal>>::new();
+    for label in &template.depends_on {
+        if labels_stack.contains(label) {
+            bail!(
+                "Task `{}` has a cyclic dependency on `{label}`",
+                labels_stack.join("` -> `")
+            );
+        }
+        let (dependency_kind, dependency) = templates
+            .iter()
+            .filter(|(_, dependency)| &dependency.label == label)
+            .min_by_key(|(kind, _)| kind != task_source_kind)
+            .with_context(|| {
+                format!(
+                    "Task `{}` depends on unknown task `{label}`",
+                    template.label
+                )
+            })?;
+        let spawn_in_terminal = dependency
+            .resolve_task(&dependency_kind.to_id_base(), task_context)
+            .and_then(|resolved_task| resolved_task.resolved)
+            .with_context(|| format!("Failed to resolve task `{label}`"))?;
+
+        labels_stack.push(label.clone());
+        let mut dependency_stages = dependency_stages(
+            templates,
+            dependency_kind,
+            dependency,
+            task_context,
+            labels_stack,
+        )?;
+        labels_stack.pop();
+        if !spawn_in_terminal.command.is_empty() {
+            dependency_stages.push(vec![spawn_in_terminal]);
+        }
+
+        match template.depends_order {
+            DependsOrder::Sequence => stages.extend(dependency_stages),
+            DependsOrder::Parallel => {
+                for (ix, stage) in dependency_stages.into_iter().enumerate() {
+                    match stages.get_mut(ix) {
+                        Some(existing_stage) => existing_stage.extend(stage),
+                        None => stages.push(stage),
+                    }
+                }
+            }
+        }
+    }
+    Ok(stages)
+}
+
 fn task_lru_comparator(
     (kind_a, task_a, lru_score_a): &(TaskSourceKind, ResolvedTask, u32),
     (kind_b, task_b, lru_score_b): &(TaskSourceKind, ResolvedTask, u32),
@@ -459,6 +583,14 @@ mod test_inventory {
                 })
                 .collect(),
         );
+        static_templates_source(tasks, updates, cx)
+    }
+
+    pub(super) fn static_templates_source(
+        tasks: TaskTemplates,
+        updates: UnboundedSender<()>,
+        cx: &mut AppContext,
+    ) -> StaticSource {
         let (tx, rx) = futures::channel::mpsc::unbounded();
         let file = TrackedFile::new(rx, updates, cx);
         tx.unbounded_send(serde_json::to_string(&tasks).unwrap())
@@ -987,6 +1119,96 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_task_dependencies(cx: &mut TestAppContext) {
+        let inventory = cx.update(Inventory::new);
+        let task = |label: &str, command: &str, depends_on: &[&str], order| TaskTemplate {
+            label: label.to_string(),
+            command: command.to_string(),
+            depends_on: depends_on.iter().map(|label| label.to_string()).collect(),
+            depends_order: order,
+            ..TaskTemplate::default()
+        };
+        inventory.update(cx, |inventory, cx| {
+            inventory.add_source(
+                TaskSourceKind::UserInput,
+                |tx, cx| {
+                    static_templates_source(
+                        TaskTemplates(vec![
+                            task("build", "cargo build", &[], DependsOrder::Sequence),
+                            task("lint", "cargo clippy", &[], DependsOrder::Sequence),
+                            task("test", "cargo test", &["build"], DependsOrder::Sequence),
+                            task("check", "", &["lint", "test"], DependsOrder::Parallel),
+                            task(
+                                "ci",
+                                "echo done",
+                                &["build", "check"],
+                                DependsOrder::Sequence,
+                            ),
+                            task("cycle_a", "a", &["cycle_b"], DependsOrder::Sequence),
+                            task("cycle_b", "b", &["cycle_a"], DependsOrder::Sequence),
+                            task("unknown", "c", &["missing"], DependsOrder::Sequence),
+                        ]),
+                        tx,
+                        cx,
+                    )
+                },
+                cx,
+            );
+        });
+        cx.run_until_parked();
+
+        let prerequisites = |label: &str, cx: &mut TestAppContext| {
+            inventory.update(cx, |inventory, _| {
+                let (kind, template) = inventory
+                    .list_tasks(None, None)
+                    .into_iter()
+                    .find(|(_, template)| template.label == label)
+                    .unwrap();
+                inventory
+                    .resolve_task_with_dependencies(&kind, &template, &TaskContext::default())
+                    .map(|resolved_task| {
+                        resolved_task
+                            .unwrap()
+                            .resolved
+                            .unwrap()
+                            .prerequisites
+                            .into_iter()
+                            .map(|stage| stage.into_iter().map(|task| task.label).collect())
+                            .collect::<Vec<Vec<_>>>()
+                    })
+            })
+        };
+
+        assert_eq!(
+            prerequisites("build", cx).unwrap(),
+            Vec::<Vec<String>>::new()
+        );
+        assert_eq!(prerequisites("test", cx).unwrap(), vec![vec!["build"]]);
+        assert_eq!(
+            prerequisites("check", cx).unwrap(),
+            vec![vec!["lint", "build"], vec!["test"]],
+            "Parallel dependencies should share stages"
+        );
+        assert_eq!(
+            prerequisites("ci", cx).unwrap(),
+            vec![vec!["build"], vec!["lint"], vec!["test"]],
+            "Tasks needed several times should only run once, when first needed"
+        );
+        assert!(prerequisites("cycle_a", cx).is_err());
+        assert!(prerequisites("unknown", cx).is_err());
+
+        assert_eq!(
+            resolved_task_names(&inventory, None, cx)
+                .await
+                .into_iter()
+                .sorted()
+                .collect::<Vec<_>>(),
+            vec!["build", "check", "ci", "lint", "test"],
+            "Tasks with unresolvable dependencies should not be listed"
+        );
+    }
+
     pub(super) async fn resolved_task_names(
         inventory: &Model<Inventory>,
         worktree: Option<WorktreeId>,
@@ -1,10 +1,13 @@
 use crate::Project;
 use anyhow::Context as _;
-use collections::HashMap;
+use collections::{BTreeMap, BTreeSet, HashMap};
+use futures::{channel::mpsc, StreamExt};
 use gpui::{
     AnyWindowHandle, AppContext, Context, Entity, Model, ModelContext, SharedString, WeakModel,
 };
 use itertools::Itertools;
+use language::{Diagnostic, DiagnosticEntry, PointUtf16, Unclipped};
+use lsp::{DiagnosticSeverity, LanguageServerId};
 use settings::{Settings, SettingsLocation};
 use smol::channel::bounded;
 use std::{
@@ -13,18 +16,31 @@ use std::{
     io::Write,
     path::{Path, PathBuf},
 };
-use task::{SpawnInTerminal, TerminalWorkDir};
+use task::{
+    problem_matcher::{Problem, ProblemScanner, ProblemSeverity},
+    ProblemMatcher, SpawnInTerminal, TerminalWorkDir,
+};
 use terminal::{
     terminal_settings::{self, Shell, TerminalSettings, VenvSettingsContent},
     TaskState, TaskStatus, Terminal, TerminalBuilder,
 };
-use util::ResultExt;
+use util::{post_inc, ResultExt};
 
 // #[cfg(target_os = "macos")]
 // use std::os::unix::ffi::OsStrExt;
 
 pub struct Terminals {
     pub(crate) local_handles: Vec<WeakModel<terminal::Terminal>>,
+    /// Diagnostics found by the problem matchers of the tasks, by the full task label.
+    pub(crate) task_diagnostics: HashMap<String, TaskDiagnostics>,
+}
+
+pub(crate) struct TaskDiagnostics {
+    /// The task's diagnostics are reported as if they came from a separate language server.
+    server_id: LanguageServerId,
+    /// Incremented every time the task is spawned again.
+    run: usize,
+    diagnostics_by_path: BTreeMap<PathBuf, Vec<DiagnosticEntry<Unclipped<PointUtf16>>>>,
 }
 
 #[derive(Debug, Clone)]
@@ -90,6 +106,20 @@ impl Project {
             path,
         });
 
+        let collect_output_lines = spawn_task.as_ref().map_or(false, |spawn_task| {
+            spawn_task.collect_output || !spawn_task.problem_matchers.is_empty()
+        });
+        let task_problem_matchers = spawn_task.as_ref().map(|spawn_task| {
+            (
+                spawn_task.full_label.clone(),
+                spawn_task.problem_matchers.clone(),
+                spawn_task
+                    .cwd
+                    .as_ref()
+                    .and_then(|cwd| cwd.local_path())
+                    .map(ToOwned::to_owned),
+            )
+        });
         let is_terminal = spawn_task.is_none()
             && working_directory
                 .as_ref()
@@ -178,6 +208,7 @@ impl Project {
             Some(settings.blinking),
             settings.alternate_scroll,
             settings.max_scroll_history_lines,
+            collect_output_lines,
             window,
             completion_tx,
         )
@@ -203,6 +234,25 @@ impl Project {
             })
             .detach();
 
+            if let Some((task_label, problem_matchers, task_cwd)) = task_problem_matchers {
+                // Diagnostics of the previous run are stale now.
+                self.clear_task_diagnostics(&task_label, cx);
+                if !problem_matchers.is_empty() {
+                    let (lines_tx, lines_rx) = mpsc::unbounded();
+                    self.match_task_problems(task_label, &problem_matchers, task_cwd, lines_rx, cx);
+                    cx.subscribe(&terminal_handle, move |_, terminal, event, cx| {
+                        if let terminal::Event::Wakeup | terminal::Event::TaskFinished = event {
+                            let lines =
+                                terminal.update(cx, |terminal, _| terminal.take_output_lines());
+                            if !lines.is_empty() {
+                                lines_tx.unbounded_send(lines).ok();
+                            }
+                        }
+                    })
+                    .detach();
+                }
+            }
+
             // if the terminal is not a task, activate full Python virtual environment
             if is_terminal {
                 if let Some(python_settings) = &python_settings.as_option() {
@@ -303,6 +353,147 @@ impl Project {
     pub fn local_terminal_handles(&self) -> &Vec<WeakModel<terminal::Terminal>> {
         &self.terminals.local_handles
     }
+
+    /// Scans the task output for problems as the lines are printed, until the terminal is dropped.
+    fn match_task_problems(
+        &mut self,
+        task_label: String,
+        problem_matchers: &[ProblemMatcher],
+        task_cwd: Option<PathBuf>,
+        mut lines_rx: mpsc::UnboundedReceiver<Vec<String>>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let mut scanners = problem_matchers
+            .iter()
+            .filter_map(|problem_matcher| problem_matcher.resolve().log_err())
+            .map(|problem_matcher| ProblemScanner::new(problem_matcher, task_cwd.clone()))
+            .collect::<Vec<_>>();
+        let run = self.task_diagnostics(&task_label).run;
+        cx.spawn(|project, mut cx| async move {
+            while let Some(lines) = lines_rx.next().await {
+                let (returned_scanners, problems) = cx
+                    .background_executor()
+                    .spawn(async move {
+                        let mut problems = Vec::new();
+                        for line in &lines {
+                            for scanner in &mut scanners {
+                                problems.extend(scanner.scan_line(line));
+                            }
+                        }
+                        (scanners, problems)
+                    })
+                    .await;
+                scanners = returned_scanners;
+                if !problems.is_empty() {
+                    project.update(&mut cx, |project, cx| {
+                        project.add_task_diagnostics(&task_label, run, problems, cx)
+                    })?;
+                }
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// The diagnostics of the task, which keeps reporting them under the same server id across its runs.
+    fn task_diagnostics(&mut self, task_label: &str) -> &mut TaskDiagnostics {
+        if !self.terminals.task_diagnostics.contains_key(task_label) {
+            self.terminals.task_diagnostics.insert(
+                task_label.to_string(),
+                TaskDiagnostics {
+                    server_id: self.languages.next_language_server_id(),
+                    run: 0,
+                    diagnostics_by_path: BTreeMap::default(),
+                },
+            );
+        }
+        self.terminals
+            .task_diagnostics
+            .get_mut(task_label)
+            .expect("task diagnostics were just inserted")
+    }
+
+    fn add_task_diagnostics(
+        &mut self,
+        task_label: &str,
+        run: usize,
+        problems: Vec<Problem>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let mut next_diagnostic_group_id = self.next_diagnostic_group_id;
+        let task_diagnostics = self.task_diagnostics(task_label);
+        // The problems of a previous run that is still printing output are stale.
+        if task_diagnostics.run != run {
+            return;
+        }
+
+        let mut changed_paths = BTreeSet::default();
+        for problem in problems {
+            let start = PointUtf16::new(
+                problem.line.saturating_sub(1),
+                problem.column.unwrap_or(1).saturating_sub(1),
+            );
+            // Without a column, the whole line is highlighted.
+            let end = PointUtf16::new(
+                problem.end_line.unwrap_or(problem.line).saturating_sub(1),
+                match (problem.end_column, problem.column) {
+                    (Some(end_column), _) => end_column.saturating_sub(1),
+                    (None, Some(column)) => column.saturating_sub(1),
+                    (None, None) => u32::MAX,
+                },
+            );
+            changed_paths.insert(problem.path.clone());
+            task_diagnostics
+                .diagnostics_by_path
+                .entry(problem.path)
+                .or_default()
+                .push(DiagnosticEntry {
+                    range: Unclipped(start)..Unclipped(end),
+                    diagnostic: Diagnostic {
+                        source: problem.source,
+                        code: problem.code,
+                        severity: match problem.severity {
+                            ProblemSeverity::Error => DiagnosticSeverity::ERROR,
+                            ProblemSeverity::Warning => DiagnosticSeverity::WARNING,
+                            ProblemSeverity::Info => DiagnosticSeverity::INFORMATION,
+                        },
+                        message: problem.message,
+                        group_id: post_inc(&mut next_diagnostic_group_id),
+                        is_primary: true,
+                        is_disk_based: true,
+                        is_unnecessary: false,
+                    },
+                });
+        }
+
+        let server_id = task_diagnostics.server_id;
+        let diagnostics = changed_paths
+            .into_iter()
+            .map(|abs_path| {
+                let diagnostics = task_diagnostics.diagnostics_by_path[&abs_path].clone();
+                (abs_path, diagnostics)
+            })
+            .collect::<Vec<_>>();
+        self.next_diagnostic_group_id = next_diagnostic_group_id;
+        for (abs_path, diagnostics) in diagnostics {
+            self.update_diagnostic_entries(server_id, abs_path, None, diagnostics, cx)
+                .log_err();
+        }
+    }
+
+    /// Removes the diagnostics of the task's previous run, and starts a new one.
+    fn clear_task_diagnostics(&mut self, task_label: &str, cx: &mut ModelContext<Self>) {
+        let Some(task_diagnostics) = self.terminals.task_diagnostics.get_mut(task_label) else {
+            return;
+        };
+        task_diagnostics.run += 1;
+        let server_id = task_diagnostics.server_id;
+        let abs_paths = std::mem::take(&mut task_diagnostics.diagnostics_by_path).into_keys();
+        for abs_path in abs_paths {
+            self.update_diagnostic_entries(server_id, abs_path, None, Vec::new(), cx)
+                .log_err();
+        }
+    }
 }
 
 fn prepare_ssh_shell(
@@ -313,6 +313,9 @@ messages!(
     (SetRoomParticipantRole, Foreground),
     (BlameBuffer, Foreground),
     (BlameBufferResponse, Foreground),
+    (SetIndexText, Foreground),
+    (GetHeadText, Foreground),
+    (GetHeadTextResponse, Foreground),
     (CreateDevServerProject, Background),
     (CreateDevServerProjectResponse, Foreground),
     (CreateDevServer, Foreground),
@@ -337,7 +340,29 @@ messages!(
     (OpenNewBuffer, Foreground),
     (RestartLanguageServers, Foreground),
     (LinkedEditingRange, Background),
-    (LinkedEditingRangeResponse, Background)
+    (LinkedEditingRangeResponse, Background),
+    (PrepareCallHierarchy, Background),
+    (PrepareCallHierarchyResponse, Background),
+    (GetIncomingCalls, Background),
+    (GetIncomingCallsResponse, Background),
+    (GetOutgoingCalls, Background),
+    (GetOutgoingCallsResponse, Background),
+    (PrepareTypeHierarchy, Background),
+    (PrepareTypeHierarchyResponse, Background),
+    (GetSupertypes, Background),
+    (GetSupertypesResponse, Background),
+    (GetSubtypes, Background),
+    (GetSubtypesResponse, Background),
+    (GetSemanticTokens, Background),
+    (GetSemanticTokensResponse, Background),
+    (GetCodeLens, Background),
+    (GetCodeLensResponse, Background),
+    (ResolveCodeLens, Background),
+    (ResolveCodeLensResponse, Background),
+    (ApplyCodeLens, Background),
+    (ApplyCodeLensResponse, Background),
+    (GetFoldingRanges, Background),
+    (GetFoldingRangesResponse, Background)
 );
 
 request_messages!(
@@ -379,6 +404,17 @@ request_messages!(
     (GetSupermavenApiKey, GetSupermavenApiKeyResponse),
     (GetTypeDefinition, GetTypeDefinitionResponse),
     (LinkedEditingRange, LinkedEditingRangeResponse),
+    (PrepareCallHierarchy, PrepareCallHierarchyResponse),
+    (GetIncomingCalls, GetIncomingCallsResponse),
+    (GetOutgoingCalls, GetOutgoingCallsResponse),
+    (PrepareTypeHierarchy, PrepareTypeHierarchyResponse),
+    (GetSupertypes, GetSupertypesResponse),
+    (GetSubtypes, GetSubtypesResponse),
+    (GetSemanticTokens, GetSemanticTokensResponse),
+    (GetCodeLens, GetCodeLensResponse),
+    (ResolveCodeLens, ResolveCodeLensResponse),
+    (ApplyCodeLens, ApplyCodeLensResponse),
+    (GetFoldingRanges, GetFoldingRangesResponse),
     (GetUsers, UsersResponse),
     (IncomingCall, Ack),
     (InlayHints, InlayHintsResponse),
@@ -436,6 +472,8 @@ request_messages!(
     (LspExtExpandMacro, LspExtExpandMacroResponse),
     (SetRoomParticipantRole, Ack),
     (BlameBuffer, BlameBufferResponse),
+    (SetIndexText, Ack),
+    (GetHeadText, GetHeadTextResponse),
     (CreateDevServerProject, CreateDevServerProjectResponse),
     (CreateDevServer, CreateDevServerResponse),
     (ShutdownDevServer, Ack),
@@ -458,6 +496,8 @@ entity_messages!(
     ApplyCodeAction,
     ApplyCompletionAdditionalEdits,
     BlameBuffer,
+    SetIndexText,
+    GetHeadText,
     BufferReloaded,
     BufferSaved,
     CopyProjectEntry,
@@ -479,6 +519,17 @@ entity_messages!(
     JoinProject,
     LeaveProject,
     LinkedEditingRange,
+    PrepareCallHierarchy,
+    GetIncomingCalls,
+    GetOutgoingCalls,
+    PrepareTypeHierarchy,
+    GetSupertypes,
+    GetSubtypes,
+    GetSemanticTokens,
+    GetCodeLens,
+    ResolveCodeLens,
+    ApplyCodeLens,
+    GetFoldingRanges,
     MultiLspQuery,
     RestartLanguageServers,
     OnTypeFormatting,
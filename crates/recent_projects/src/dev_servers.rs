@@ -1178,6 +1178,9 @@ pub async fn spawn_ssh_task(
                     use_new_terminal: true,
                     allow_concurrent_runs: false,
                     reveal: RevealStrategy::Always,
+                    prerequisites: Vec::new(),
+                    problem_matchers: Vec::new(),
+                    collect_output: false,
                 },
                 cx,
             )
@@ -0,0 +1,173 @@
+use crate::{KernelStatus, RuntimeManager};
+use anyhow::Result;
+use collections::HashSet;
+use editor::{CompletionProvider, Editor, EditorSettings};
+use futures::Future;
+use gpui::{EntityId, Model, Task, ViewContext};
+use language::{Anchor, Buffer, CodeLabel, LanguageServerId, ToOffset as _};
+use parking_lot::RwLock;
+use project::Completion;
+use settings::Settings as _;
+use std::sync::Arc;
+use util::ResultExt as _;
+
+/// Completes code with the names known to the kernel of an editor, in addition to the completions
+/// of the provider that the editor had before, such as the project's language servers.
+pub struct KernelCompletionProvider {
+    /// The entity that the kernel was launched for.
+    entity_id: EntityId,
+    fallback: Option<Box<dyn CompletionProvider>>,
+}
+
+impl KernelCompletionProvider {
+    pub fn new(entity_id: EntityId, fallback: Option<Box<dyn CompletionProvider>>) -> Self {
+        Self {
+            entity_id,
+            fallback,
+        }
+    }
+
+    fn kernel_completions(
+        &self,
+        buffer: &Model<Buffer>,
+        buffer_position: Anchor,
+        cx: &mut ViewContext<Editor>,
+    ) -> impl Future<Output = Result<Vec<Completion>>> {
+        let snapshot = buffer.read(cx).snapshot();
+        let code = snapshot.text();
+        let offset = buffer_position.to_offset(&snapshot);
+        let cursor_pos = code[..offset].chars().count();
+
+        // A busy kernel only replies once it's done executing, when the completions are stale.
+        let reply = RuntimeManager::global(cx).and_then(|runtime_manager| {
+            let runtime_manager = runtime_manager.read(cx);
+            (runtime_manager.kernel_status(self.entity_id) == Some(KernelStatus::Idle))
+                .then(|| runtime_manager.complete(self.entity_id, code.clone(), cursor_pos))
+        });
+
+        async move {
+            let Some(reply) = reply else {
+                return Ok(Vec::new());
+            };
+            let reply = reply.await?;
+
+            // The kernel reports the range to replace in unicode code points.
+            let byte_offset = |char_offset: usize| {
+                code.char_indices()
+                    .nth(char_offset)
+                    .map_or(code.len(), |(offset, _)| offset)
+            };
+            let old_range = snapshot.anchor_before(byte_offset(reply.cursor_start))
+                ..snapshot.anchor_after(byte_offset(reply.cursor_end));
+
+            Ok(reply
+                .matches
+                .into_iter()
+                .map(|name| Completion {
+                    old_range: old_range.clone(),
+                    label: CodeLabel::plain(name.clone(), None),
+                    new_text: name,
+                    server_id: LanguageServerId::NONE,
+                    documentation: None,
+                    lsp_completion: Default::default(),
+                    confirm: None,
+                    show_new_completions_on_confirm: false,
+                })
+                .collect())
+        }
+    }
+}
+
+impl CompletionProvider for KernelCompletionProvider {
+    fn completions(
+        &self,
+        buffer: &Model<Buffer>,
+        buffer_position: Anchor,
+        trigger: editor::CompletionContext,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Vec<Completion>>> {
+        let fallback_completions = self
+            .fallback
+            .as_ref()
+            .map(|fallback| fallback.completions(buffer, buffer_position, trigger, cx));
+        let kernel_completions = self.kernel_completions(buffer, buffer_position, cx);
+
+        cx.background_executor().spawn(async move {
+            let mut completions = match fallback_completions {
+                Some(fallback_completions) => fallback_completions.await?,
+                None => Vec::new(),
+            };
+
+            // Language servers know more about the names they offer than the kernel does.
+            let known_names = completions
+                .iter()
+                .map(|completion| completion.new_text.clone())
+                .collect::<HashSet<_>>();
+            let kernel_completions = kernel_completions.await.log_err().unwrap_or_default();
+            completions.extend(
+                kernel_completions
+                    .into_iter()
+                    .filter(|completion| !known_names.contains(&completion.new_text)),
+            );
+            Ok(completions)
+        })
+    }
+
+    fn resolve_completions(
+        &self,
+        buffer: Model<Buffer>,
+        completion_indices: Vec<usize>,
+        completions: Arc<RwLock<Box<[Completion]>>>,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<bool>> {
+        match &self.fallback {
+            Some(fallback) => {
+                fallback.resolve_completions(buffer, completion_indices, completions, cx)
+            }
+            None => Task::ready(Ok(true)),
+        }
+    }
+
+    fn apply_additional_edits_for_completion(
+        &self,
+        buffer: Model<Buffer>,
+        completion: Completion,
+        push_to_history: bool,
+        cx: &mut ViewContext<Editor>,
+    ) -> Task<Result<Option<language::Transaction>>> {
+        match &self.fallback {
+            Some(fallback) => fallback.apply_additional_edits_for_completion(
+                buffer,
+                completion,
+                push_to_history,
+                cx,
+            ),
+            None => Task::ready(Ok(None)),
+        }
+    }
+
+    fn is_completion_trigger(
+        &self,
+        buffer: &Model<Buffer>,
+        position: language::Anchor,
+        text: &str,
+        trigger_in_words: bool,
+        cx: &mut ViewContext<Editor>,
+    ) -> bool {
+        if !EditorSettings::get_global(cx).show_completions_on_input {
+            return false;
+        }
+        if let Some(fallback) = &self.fallback {
+            if fallback.is_completion_trigger(buffer, position, text, trigger_in_words, cx) {
+                return true;
+            }
+        }
+
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some('.'), None) => true,
+            (Some(char), None) => trigger_in_words && (char.is_alphanumeric() || char == '_'),
+            _ => false,
+        }
+    }
+}
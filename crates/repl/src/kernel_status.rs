@@ -0,0 +1,71 @@
+use crate::{variables_panel, KernelStatus, RuntimeManager, RuntimeManagerGlobal};
+use gpui::{EntityId, IntoElement, Render, Subscription, ViewContext};
+use ui::{prelude::*, Tooltip};
+use workspace::{item::ItemHandle, StatusItemView};
+
+/// Shows whether the kernel of the active editor or notebook is busy.
+pub struct KernelStatusIndicator {
+    active_entity: Option<EntityId>,
+    _observe_runtime_manager: Option<Subscription>,
+    _observe_runtime_manager_global: Subscription,
+}
+
+impl KernelStatusIndicator {
+    pub fn new(cx: &mut ViewContext<Self>) -> Self {
+        let mut this = Self {
+            active_entity: None,
+            _observe_runtime_manager: None,
+            _observe_runtime_manager_global: cx
+                .observe_global::<RuntimeManagerGlobal>(Self::observe_runtime_manager),
+        };
+        this.observe_runtime_manager(cx);
+        this
+    }
+
+    fn observe_runtime_manager(&mut self, cx: &mut ViewContext<Self>) {
+        self._observe_runtime_manager = RuntimeManager::global(cx)
+            .map(|runtime_manager| cx.observe(&runtime_manager, |_, _, cx| cx.notify()));
+        cx.notify();
+    }
+}
+
+impl Render for KernelStatusIndicator {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let status = self.active_entity.zip(RuntimeManager::global(cx)).and_then(
+            |(entity_id, runtime_manager)| runtime_manager.read(cx).kernel_status(entity_id),
+        );
+
+        div().when_some(status, |el, status| {
+            let color = match status {
+                KernelStatus::Idle => Color::Success,
+                KernelStatus::Busy => Color::Warning,
+                KernelStatus::Starting | KernelStatus::ShuttingDown => Color::Muted,
+                KernelStatus::Failed => Color::Error,
+            };
+
+            el.child(
+                Button::new("kernel-status", format!("Kernel {}", status.label()))
+                    .label_size(LabelSize::Small)
+                    .icon(IconName::Indicator)
+                    .icon_size(IconSize::XSmall)
+                    .icon_color(color)
+                    .icon_position(IconPosition::Start)
+                    .on_click(|_, cx| cx.dispatch_action(Box::new(variables_panel::ToggleFocus)))
+                    .tooltip(|cx| {
+                        Tooltip::for_action("Show Variables", &variables_panel::ToggleFocus, cx)
+                    }),
+            )
+        })
+    }
+}
+
+impl StatusItemView for KernelStatusIndicator {
+    fn set_active_pane_item(
+        &mut self,
+        active_pane_item: Option<&dyn ItemHandle>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.active_entity = active_pane_item.map(|item| item.item_id());
+        cx.notify();
+    }
+}
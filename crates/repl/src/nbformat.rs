@@ -0,0 +1,556 @@
+//! Reading and writing Jupyter notebooks in the nbformat 4 JSON format.
+//! See https://nbformat.readthedocs.io/en/latest/format_description.html
+
+use crate::outputs::{ErrorView, OutputType};
+use crate::stdio::TerminalOutput;
+use anyhow::{anyhow, Result};
+use collections::HashMap;
+use gpui::WindowContext;
+use runtimelib::{JupyterMessageContent, MimeBundle};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+pub const NBFORMAT: u32 = 4;
+const NBFORMAT_MINOR: u32 = 5;
+/// The first minor version of the format whose cells have ids.
+const CELL_IDS_MINOR: u32 = 5;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Notebook {
+    pub cells: Vec<Cell>,
+    #[serde(default)]
+    pub metadata: Map<String, Value>,
+    pub nbformat: u32,
+    pub nbformat_minor: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cell {
+    #[serde(flatten)]
+    pub kind: CellKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub metadata: Map<String, Value>,
+    pub source: MultilineString,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "cell_type", rename_all = "snake_case")]
+pub enum CellKind {
+    Code {
+        execution_count: Option<u64>,
+        #[serde(default)]
+        outputs: Vec<Output>,
+    },
+    Markdown {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attachments: Option<Value>,
+    },
+    Raw {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        attachments: Option<Value>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "output_type", rename_all = "snake_case")]
+pub enum Output {
+    Stream {
+        name: String,
+        text: MultilineString,
+    },
+    DisplayData {
+        data: Map<String, Value>,
+        #[serde(default)]
+        metadata: Map<String, Value>,
+    },
+    ExecuteResult {
+        data: Map<String, Value>,
+        execution_count: Option<u64>,
+        #[serde(default)]
+        metadata: Map<String, Value>,
+    },
+    Error {
+        ename: String,
+        evalue: String,
+        traceback: Vec<String>,
+    },
+}
+
+/// A string that nbformat stores either as is, or split into lines.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MultilineString(pub String);
+
+impl Serialize for MultilineString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.split_inclusive('\n'))
+    }
+}
+
+impl<'de> Deserialize<'de> for MultilineString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            String(String),
+            Lines(Vec<String>),
+        }
+
+        Ok(match Repr::deserialize(deserializer)? {
+            Repr::String(string) => Self(string),
+            Repr::Lines(lines) => Self(lines.concat()),
+        })
+    }
+}
+
+impl Notebook {
+    pub fn parse(text: &str) -> Result<Self> {
+        let notebook: Self = serde_json::from_str(text)?;
+        if notebook.nbformat != NBFORMAT {
+            return Err(anyhow!(
+                "unsupported notebook format version {}",
+                notebook.nbformat
+            ));
+        }
+        Ok(notebook)
+    }
+
+    /// Serializes the notebook the way Jupyter does, to keep the diffs of saved notebooks small.
+    pub fn to_json(&self) -> Result<String> {
+        let mut json = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+        let mut serializer = serde_json::Serializer::with_formatter(&mut json, formatter);
+        self.serialize(&mut serializer)?;
+        json.push(b'\n');
+        Ok(String::from_utf8(json)?)
+    }
+
+    /// The language of the kernel that the notebook was written for.
+    pub fn kernel_language(&self) -> Option<&str> {
+        self.metadata
+            .get("kernelspec")
+            .and_then(|kernelspec| kernelspec.get("language"))
+            .or_else(|| {
+                self.metadata
+                    .get("language_info")
+                    .and_then(|language_info| language_info.get("name"))
+            })
+            .and_then(Value::as_str)
+    }
+}
+
+impl Default for Notebook {
+    fn default() -> Self {
+        Self {
+            cells: Vec::new(),
+            metadata: Map::new(),
+            nbformat: NBFORMAT,
+            nbformat_minor: NBFORMAT_MINOR,
+        }
+    }
+}
+
+impl Cell {
+    /// A code cell for a notebook of the given minor format version, which decides whether the
+    /// cell has an id.
+    pub fn code(source: String, nbformat_minor: u32) -> Self {
+        Self::new(
+            CellKind::Code {
+                execution_count: None,
+                outputs: Vec::new(),
+            },
+            source,
+            nbformat_minor,
+        )
+    }
+
+    pub fn markdown(source: String, nbformat_minor: u32) -> Self {
+        Self::new(
+            CellKind::Markdown { attachments: None },
+            source,
+            nbformat_minor,
+        )
+    }
+
+    fn new(kind: CellKind, source: String, nbformat_minor: u32) -> Self {
+        // Notebooks before nbformat 4.5 don't allow cell ids.
+        let id = (nbformat_minor >= CELL_IDS_MINOR)
+            .then(|| uuid::Uuid::new_v4().simple().to_string()[..8].to_string());
+        Self {
+            kind,
+            id,
+            metadata: Map::new(),
+            source: MultilineString(source),
+        }
+    }
+}
+
+impl Output {
+    /// The output that a message of the kernel adds to the executed cell, if any.
+    pub fn from_message(message: &JupyterMessageContent) -> Option<Self> {
+        match message {
+            JupyterMessageContent::ExecuteResult(result) => Some(Self::ExecuteResult {
+                data: mime_bundle_to_data(&result.data),
+                execution_count: execution_count(result),
+                metadata: Map::new(),
+            }),
+            JupyterMessageContent::DisplayData(result) => Some(Self::DisplayData {
+                data: mime_bundle_to_data(&result.data),
+                metadata: Map::new(),
+            }),
+            JupyterMessageContent::StreamContent(result) => Some(Self::Stream {
+                name: serde_json::to_value(&result.name)
+                    .ok()
+                    .and_then(|name| name.as_str().map(ToString::to_string))
+                    .unwrap_or_else(|| "stdout".to_string()),
+                text: MultilineString(result.text.clone()),
+            }),
+            JupyterMessageContent::ErrorOutput(result) => Some(Self::Error {
+                ename: result.ename.clone(),
+                evalue: result.evalue.clone(),
+                traceback: result.traceback.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The execution count that a message of the kernel reports, if any.
+pub fn execution_count(message: &impl Serialize) -> Option<u64> {
+    serde_json::to_value(message)
+        .ok()?
+        .get("execution_count")?
+        .as_u64()
+}
+
+/// The id that a display is shown with, for the kernel to update it later.
+pub fn display_id(message: &impl Serialize) -> Option<String> {
+    serde_json::to_value(message)
+        .ok()?
+        .get("transient")?
+        .get("display_id")?
+        .as_str()
+        .map(ToString::to_string)
+}
+
+fn mime_bundle_to_data(bundle: &MimeBundle) -> Map<String, Value> {
+    match serde_json::to_value(bundle) {
+        Ok(Value::Object(data)) => data,
+        _ => Map::new(),
+    }
+}
+
+/// Notebooks may store the text of a mime type split into lines, which the mime bundle doesn't accept.
+fn data_to_mime_bundle(data: &Map<String, Value>) -> Option<MimeBundle> {
+    let data = data
+        .iter()
+        .map(|(mime_type, value)| {
+            let value = match value {
+                Value::Array(lines) if lines.iter().all(Value::is_string) => {
+                    Value::String(lines.iter().filter_map(Value::as_str).collect())
+                }
+                value => value.clone(),
+            };
+            (mime_type.clone(), value)
+        })
+        .collect();
+    serde_json::from_value(Value::Object(data)).ok()
+}
+
+impl Output {
+    pub fn to_output_type(&self, cx: &mut WindowContext) -> OutputType {
+        match self {
+            Output::Stream { text, .. } => OutputType::Stream(TerminalOutput::from(&text.0)),
+            Output::DisplayData { data, .. } | Output::ExecuteResult { data, .. } => {
+                match data_to_mime_bundle(data) {
+                    Some(bundle) => OutputType::new(&bundle, cx),
+                    None => OutputType::Message("Unsupported media type".to_string()),
+                }
+            }
+            Output::Error {
+                ename,
+                evalue,
+                traceback,
+            } => OutputType::ErrorOutput(ErrorView {
+                ename: ename.clone(),
+                evalue: evalue.clone(),
+                traceback: TerminalOutput::from(&traceback.join("\n")),
+            }),
+        }
+    }
+}
+
+/// What is known about the outputs of a cell while it's executing, and isn't saved with them.
+#[derive(Default)]
+pub struct OutputState {
+    /// Set while outputs are waiting to be cleared once the next output arrives.
+    pub clear_pending: bool,
+    /// The indices of the outputs that were displayed with an id.
+    pub display_ids: HashMap<String, usize>,
+}
+
+impl OutputState {
+    fn clear(&mut self, outputs: &mut Vec<Output>) {
+        outputs.clear();
+        self.clear_pending = false;
+        self.display_ids.clear();
+    }
+}
+
+/// Adds the output of a kernel message to the outputs of a cell, following the `clear_output`
+/// and `update_display_data` requests. Returns whether the outputs changed.
+pub fn apply_message(
+    outputs: &mut Vec<Output>,
+    state: &mut OutputState,
+    message: &JupyterMessageContent,
+) -> bool {
+    match message {
+        JupyterMessageContent::ClearOutput(options) => {
+            if options.wait {
+                state.clear_pending = true;
+                return false;
+            }
+            state.clear(outputs);
+            return true;
+        }
+        JupyterMessageContent::UpdateDisplayData(update) => {
+            let output = display_id(update)
+                .and_then(|display_id| state.display_ids.get(&display_id))
+                .and_then(|ix| outputs.get_mut(*ix));
+            return match output {
+                Some(Output::DisplayData { data, .. }) => {
+                    *data = mime_bundle_to_data(&update.data);
+                    true
+                }
+                _ => false,
+            };
+        }
+        _ => {}
+    }
+
+    let Some(output) = Output::from_message(message) else {
+        return false;
+    };
+    if state.clear_pending {
+        state.clear(outputs);
+    }
+    if let JupyterMessageContent::DisplayData(result) = message {
+        if let Some(display_id) = display_id(result) {
+            state.display_ids.insert(display_id, outputs.len());
+        }
+    }
+    if let (
+        Some(Output::Stream {
+            name: last_name,
+            text: last_text,
+        }),
+        Output::Stream { name, text },
+    ) = (outputs.last_mut(), &output)
+    {
+        if last_name == name {
+            last_text.0.push_str(&text.0);
+            return true;
+        }
+    }
+    outputs.push(output);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "id": "a1",
+   "metadata": {},
+   "source": [
+    "# Title\n",
+    "Some text"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 2,
+   "id": "b2",
+   "metadata": {
+    "tags": []
+   },
+   "outputs": [
+    {
+     "name": "stdout",
+     "output_type": "stream",
+     "text": [
+      "hello\n"
+     ]
+    },
+    {
+     "data": {
+      "text/plain": [
+       "3"
+      ]
+     },
+     "execution_count": 2,
+     "metadata": {},
+     "output_type": "execute_result"
+    }
+   ],
+   "source": "print('hello')\n1 + 2"
+  }
+ ],
+ "metadata": {
+  "kernelspec": {
+   "display_name": "Python 3",
+   "language": "python",
+   "name": "python3"
+  }
+ },
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##;
+
+    #[test]
+    fn test_parse_notebook() {
+        let notebook = Notebook::parse(NOTEBOOK).unwrap();
+        assert_eq!(notebook.kernel_language(), Some("python"));
+        assert_eq!(notebook.cells.len(), 2);
+
+        let markdown = &notebook.cells[0];
+        assert_eq!(markdown.kind, CellKind::Markdown { attachments: None });
+        assert_eq!(markdown.source.0, "# Title\nSome text");
+
+        let code = &notebook.cells[1];
+        assert_eq!(code.id.as_deref(), Some("b2"));
+        assert_eq!(code.source.0, "print('hello')\n1 + 2");
+        let CellKind::Code {
+            execution_count,
+            outputs,
+        } = &code.kind
+        else {
+            panic!("expected a code cell");
+        };
+        assert_eq!(*execution_count, Some(2));
+        assert_eq!(
+            outputs[0],
+            Output::Stream {
+                name: "stdout".into(),
+                text: MultilineString("hello\n".into()),
+            }
+        );
+        let Output::ExecuteResult { data, .. } = &outputs[1] else {
+            panic!("expected an execute result");
+        };
+        assert_eq!(data["text/plain"], json!(["3"]));
+    }
+
+    #[test]
+    fn test_notebook_round_trip() {
+        let notebook = Notebook::parse(NOTEBOOK).unwrap();
+        let json = notebook.to_json().unwrap();
+        assert_eq!(Notebook::parse(&json).unwrap(), notebook);
+
+        // Sources are written as lines, like Jupyter does.
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value["cells"][1]["source"],
+            json!(["print('hello')\n", "1 + 2"])
+        );
+        assert_eq!(value["cells"][1]["execution_count"], json!(2));
+        assert!(json.starts_with("{\n \"cells\": [\n  {\n   \"cell_type\": \"markdown\""));
+    }
+
+    #[test]
+    fn test_cell_ids() {
+        assert!(Cell::code(String::new(), 4).id.is_none());
+        assert!(Cell::markdown(String::new(), 4).id.is_none());
+        let id = Cell::code(String::new(), 5).id.unwrap();
+        assert_eq!(id.len(), 8);
+        assert_ne!(Cell::code(String::new(), 5).id.unwrap(), id);
+    }
+
+    #[test]
+    fn test_unsupported_notebook() {
+        assert!(Notebook::parse(
+            r#"{"cells": [], "metadata": {}, "nbformat": 3, "nbformat_minor": 0}"#
+        )
+        .is_err());
+        assert!(Notebook::parse("[]").is_err());
+    }
+
+    #[test]
+    fn test_apply_message() {
+        let stream = |text: &str| {
+            JupyterMessageContent::StreamContent(runtimelib::StreamContent {
+                name: runtimelib::Stdio::Stdout,
+                text: text.into(),
+            })
+        };
+        let clear_output =
+            |wait| JupyterMessageContent::ClearOutput(runtimelib::ClearOutput { wait });
+
+        let mut outputs = Vec::new();
+        let mut state = OutputState::default();
+        apply_message(&mut outputs, &mut state, &stream("a\n"));
+        apply_message(&mut outputs, &mut state, &stream("b\n"));
+        assert_eq!(
+            outputs,
+            vec![Output::Stream {
+                name: "stdout".into(),
+                text: MultilineString("a\nb\n".into()),
+            }]
+        );
+
+        // Waiting clears keep the outputs until the next one arrives.
+        apply_message(&mut outputs, &mut state, &clear_output(true));
+        assert_eq!(outputs.len(), 1);
+        apply_message(&mut outputs, &mut state, &stream("c\n"));
+        assert_eq!(
+            outputs,
+            vec![Output::Stream {
+                name: "stdout".into(),
+                text: MultilineString("c\n".into()),
+            }]
+        );
+
+        apply_message(&mut outputs, &mut state, &clear_output(false));
+        assert!(outputs.is_empty());
+    }
+
+    #[test]
+    fn test_update_display_data() {
+        let display = |text: &str| {
+            let content = json!({
+                "data": {"text/plain": text},
+                "metadata": {},
+                "transient": {"display_id": "progress"},
+            });
+            (
+                JupyterMessageContent::DisplayData(
+                    serde_json::from_value(content.clone()).unwrap(),
+                ),
+                JupyterMessageContent::UpdateDisplayData(serde_json::from_value(content).unwrap()),
+            )
+        };
+        let text = |output: &Output| match output {
+            Output::DisplayData { data, .. } => data["text/plain"].clone(),
+            _ => panic!("expected a display"),
+        };
+
+        let mut outputs = Vec::new();
+        let mut state = OutputState::default();
+        apply_message(&mut outputs, &mut state, &display("0%").0);
+        assert!(apply_message(&mut outputs, &mut state, &display("50%").1));
+        assert!(apply_message(&mut outputs, &mut state, &display("100%").1));
+        assert_eq!(outputs.len(), 1);
+        assert_eq!(text(&outputs[0]), json!("100%"));
+
+        // Displays that are gone can't be updated.
+        state.clear(&mut outputs);
+        assert!(!apply_message(&mut outputs, &mut state, &display("0%").1));
+        assert!(outputs.is_empty());
+    }
+}
@@ -0,0 +1,1096 @@
+use crate::{
+    completions::KernelCompletionProvider,
+    nbformat::{self, CellKind, Notebook},
+    outputs::{Displays, ExecutionStatus, ExecutionView, OutputType},
+    runtime_settings::JupyterSettings,
+    RuntimeManager,
+};
+use anyhow::Result;
+use collections::HashSet;
+use editor::{Editor, EditorElement, EditorEvent, EditorMode, EditorStyle, MultiBuffer};
+use futures::StreamExt as _;
+use gpui::{
+    actions, AnyElement, AppContext, EventEmitter, FocusHandle, FocusableView, Model, Subscription,
+    Task, TextStyle, View,
+};
+use language::{Buffer, Language};
+use project::{Project, ProjectEntryId, ProjectPath};
+use runtimelib::JupyterMessageContent;
+use serde_json::{Map, Value};
+use settings::Settings as _;
+use std::{ffi::OsStr, sync::Arc};
+use theme::ThemeSettings;
+use ui::{prelude::*, Tooltip};
+use util::ResultExt as _;
+use workspace::item::{Item, ItemEvent, ProjectItem, TabContentParams};
+
+actions!(
+    notebook,
+    [
+        RunCell,
+        RunAllCells,
+        AddCodeCell,
+        AddMarkdownCell,
+        MoveCellUp,
+        MoveCellDown,
+        DeleteCell
+    ]
+);
+
+const DEFAULT_KERNEL_LANGUAGE: &str = "python";
+
+pub fn init(cx: &mut AppContext) {
+    workspace::register_project_item::<NotebookEditor>(cx);
+}
+
+/// A `.ipynb` file, backed by the buffer holding its JSON.
+pub struct NotebookItem {
+    buffer: Model<Buffer>,
+    project_path: ProjectPath,
+}
+
+impl project::Item for NotebookItem {
+    fn try_open(
+        project: &Model<Project>,
+        path: &ProjectPath,
+        cx: &mut AppContext,
+    ) -> Option<Task<gpui::Result<Model<Self>>>> {
+        let is_notebook = path.path.extension().and_then(OsStr::to_str) == Some("ipynb");
+        if !is_notebook || !JupyterSettings::get_global(cx).enabled {
+            return None;
+        }
+
+        let project_path = path.clone();
+        let open_buffer = project.update(cx, |project, cx| project.open_buffer(path.clone(), cx));
+        Some(cx.spawn(|mut cx| async move {
+            let buffer = open_buffer.await?;
+            cx.new_model(|_| NotebookItem {
+                buffer,
+                project_path,
+            })
+        }))
+    }
+
+    fn entry_id(&self, cx: &AppContext) -> Option<ProjectEntryId> {
+        project::Item::entry_id(self.buffer.read(cx), cx)
+    }
+
+    fn project_path(&self, _: &AppContext) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+}
+
+struct NotebookCell {
+    cell: nbformat::Cell,
+    editor: View<Editor>,
+    execution_view: View<ExecutionView>,
+    output_state: nbformat::OutputState,
+    _execution: Option<Task<()>>,
+    _subscription: Subscription,
+}
+
+impl NotebookCell {
+    fn is_code(&self) -> bool {
+        matches!(self.cell.kind, CellKind::Code { .. })
+    }
+
+    fn buffer(&self, cx: &AppContext) -> Option<Model<Buffer>> {
+        self.editor.read(cx).buffer().read(cx).as_singleton()
+    }
+}
+
+/// Presents the cells of a notebook as editors, each followed by the outputs of its last run.
+pub struct NotebookEditor {
+    notebook: Model<NotebookItem>,
+    project: Model<Project>,
+    focus_handle: FocusHandle,
+    metadata: Map<String, Value>,
+    nbformat_minor: u32,
+    kernel_language: Arc<str>,
+    code_language: Option<Arc<Language>>,
+    markdown_language: Option<Arc<Language>>,
+    cells: Vec<NotebookCell>,
+    displays: Model<Displays>,
+    selected_cell: usize,
+    dirty: bool,
+    parse_error: Option<String>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl NotebookEditor {
+    fn new(
+        project: Model<Project>,
+        notebook: Model<NotebookItem>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self {
+        let buffer = notebook.read(cx).buffer.clone();
+        let subscriptions = vec![
+            cx.subscribe(&buffer, |this, _, event: &language::Event, cx| {
+                if let language::Event::Reloaded = event {
+                    if !this.dirty {
+                        this.load_cells(cx);
+                    }
+                }
+            }),
+        ];
+
+        let mut this = Self {
+            notebook,
+            project,
+            focus_handle: cx.focus_handle(),
+            metadata: Map::new(),
+            nbformat_minor: 0,
+            kernel_language: DEFAULT_KERNEL_LANGUAGE.into(),
+            code_language: None,
+            markdown_language: None,
+            cells: Vec::new(),
+            displays: cx.new_model(|_| Displays::default()),
+            selected_cell: 0,
+            dirty: false,
+            parse_error: None,
+            _subscriptions: subscriptions,
+        };
+        this.load_cells(cx);
+        this
+    }
+
+    fn buffer(&self, cx: &AppContext) -> Model<Buffer> {
+        self.notebook.read(cx).buffer.clone()
+    }
+
+    /// Replaces the cells with the ones of the notebook file.
+    fn load_cells(&mut self, cx: &mut ViewContext<Self>) {
+        let text = self.buffer(cx).read(cx).text();
+        let notebook = match Notebook::parse(&text) {
+            Ok(notebook) => notebook,
+            Err(error) => {
+                self.parse_error = Some(error.to_string());
+                self.cells.clear();
+                cx.notify();
+                return;
+            }
+        };
+
+        let kernel_language: Arc<str> = notebook
+            .kernel_language()
+            .unwrap_or(DEFAULT_KERNEL_LANGUAGE)
+            .into();
+        if kernel_language != self.kernel_language || self.code_language.is_none() {
+            self.kernel_language = kernel_language;
+            self.load_languages(cx);
+        }
+
+        self.parse_error = None;
+        self.metadata = notebook.metadata;
+        self.nbformat_minor = notebook.nbformat_minor;
+        self.cells = notebook
+            .cells
+            .into_iter()
+            .map(|cell| self.build_cell(cell, cx))
+            .collect();
+        self.selected_cell = self.selected_cell.min(self.cells.len().saturating_sub(1));
+        self.dirty = false;
+        cx.emit(ItemEvent::UpdateTab);
+        cx.notify();
+    }
+
+    fn load_languages(&mut self, cx: &mut ViewContext<Self>) {
+        let languages = self.project.read(cx).languages().clone();
+        let code_language = languages.language_for_name(&self.kernel_language);
+        let markdown_language = languages.language_for_name("Markdown");
+        cx.spawn(|this, mut cx| async move {
+            let code_language = code_language.await.log_err();
+            let markdown_language = markdown_language.await.log_err();
+            this.update(&mut cx, |this, cx| {
+                this.code_language = code_language;
+                this.markdown_language = markdown_language;
+                for cell in &this.cells {
+                    let language = this.cell_language(&cell.cell);
+                    if let Some(buffer) = cell.buffer(cx) {
+                        buffer.update(cx, |buffer, cx| buffer.set_language(language, cx));
+                    }
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn cell_language(&self, cell: &nbformat::Cell) -> Option<Arc<Language>> {
+        match cell.kind {
+            CellKind::Code { .. } => self.code_language.clone(),
+            CellKind::Markdown { .. } => self.markdown_language.clone(),
+            CellKind::Raw { .. } => None,
+        }
+    }
+
+    fn build_cell(&self, cell: nbformat::Cell, cx: &mut ViewContext<Self>) -> NotebookCell {
+        let language = self.cell_language(&cell);
+        let language_registry = self.project.read(cx).languages().clone();
+        let buffer = cx.new_model(|cx| {
+            let mut buffer = Buffer::local(cell.source.0.clone(), cx);
+            buffer.set_language_registry(language_registry);
+            buffer.set_language(language, cx);
+            buffer
+        });
+        let notebook_id = cx.entity_id();
+        let editor = cx.new_view(|cx| {
+            let buffer = cx.new_model(|cx| MultiBuffer::singleton(buffer, cx));
+            let mut editor = Editor::new(
+                EditorMode::AutoHeight {
+                    max_lines: usize::MAX,
+                },
+                buffer,
+                None,
+                false,
+                cx,
+            );
+            editor.set_show_gutter(false, cx);
+            editor.set_completion_provider(Box::new(KernelCompletionProvider::new(
+                notebook_id,
+                None,
+            )));
+            editor
+        });
+
+        let displays = self.displays.clone();
+        let execution_view = cx.new_view(|cx| {
+            let mut execution_view = ExecutionView::new(displays, cx);
+            if let CellKind::Code { outputs, .. } = &cell.kind {
+                execution_view.outputs = outputs
+                    .iter()
+                    .map(|output| output.to_output_type(cx))
+                    .collect();
+            }
+            execution_view
+        });
+
+        let subscription = cx.subscribe(
+            &editor,
+            |this, editor, event: &EditorEvent, cx| match event {
+                EditorEvent::BufferEdited => this.mark_dirty(cx),
+                EditorEvent::Focused => {
+                    if let Some(ix) = this.cells.iter().position(|cell| cell.editor == editor) {
+                        this.selected_cell = ix;
+                        cx.notify();
+                    }
+                }
+                _ => {}
+            },
+        );
+
+        NotebookCell {
+            cell,
+            editor,
+            execution_view,
+            output_state: nbformat::OutputState::default(),
+            _execution: None,
+            _subscription: subscription,
+        }
+    }
+
+    fn mark_dirty(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.dirty {
+            self.dirty = true;
+            cx.emit(ItemEvent::UpdateTab);
+        }
+        cx.emit(ItemEvent::Edit);
+        cx.notify();
+    }
+
+    /// The notebook with the current contents of the cell editors.
+    fn to_notebook(&self, cx: &AppContext) -> Notebook {
+        Notebook {
+            cells: self
+                .cells
+                .iter()
+                .map(|cell| nbformat::Cell {
+                    source: nbformat::MultilineString(cell.editor.read(cx).text(cx)),
+                    ..cell.cell.clone()
+                })
+                .collect(),
+            metadata: self.metadata.clone(),
+            nbformat: nbformat::NBFORMAT,
+            nbformat_minor: self.nbformat_minor,
+        }
+    }
+
+    fn run_cell(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        let Some(runtime_manager) = RuntimeManager::global(cx) else {
+            log::warn!("No runtime manager found");
+            return;
+        };
+        let Some(cell) = self.cells.get_mut(ix) else {
+            return;
+        };
+        let CellKind::Code {
+            execution_count,
+            outputs,
+        } = &mut cell.cell.kind
+        else {
+            return;
+        };
+
+        *execution_count = None;
+        outputs.clear();
+        cell.output_state = nbformat::OutputState::default();
+
+        let code = cell.editor.read(cx).text(cx);
+        let displays = self.displays.clone();
+        let execution_view = cx.new_view(|cx| ExecutionView::new(displays, cx));
+        cell.execution_view = execution_view.clone();
+
+        let entity_id = cx.entity_id();
+        let language_name = self.kernel_language.clone();
+        let receiver = runtime_manager.update(cx, |runtime_manager, cx| {
+            runtime_manager.execute_code(entity_id, language_name, code, cx)
+        });
+
+        cell._execution = Some(cx.spawn(|this, mut cx| async move {
+            execution_view
+                .update(&mut cx, |execution_view, cx| {
+                    execution_view.set_status(ExecutionStatus::ConnectingToKernel, cx);
+                })
+                .ok();
+
+            let mut receiver = match receiver.await {
+                Ok(receiver) => receiver,
+                Err(error) => {
+                    execution_view
+                        .update(&mut cx, |execution_view, cx| {
+                            execution_view
+                                .outputs
+                                .push(OutputType::Message(error.to_string()));
+                            execution_view.set_status(ExecutionStatus::Finished, cx);
+                        })
+                        .ok();
+                    return;
+                }
+            };
+
+            while let Some(message) = receiver.next().await {
+                let updated = execution_view.update(&mut cx, |execution_view, cx| {
+                    execution_view.push_message(&message, cx)
+                });
+                let stored = this.update(&mut cx, |this, cx| {
+                    this.store_output(&execution_view, &message, cx)
+                });
+                if updated.is_err() || stored.is_err() {
+                    break;
+                }
+            }
+        }));
+        cx.notify();
+    }
+
+    /// Records a message of the kernel in the cell whose execution it belongs to, so that it's saved
+    /// with the notebook.
+    fn store_output(
+        &mut self,
+        execution_view: &View<ExecutionView>,
+        message: &JupyterMessageContent,
+        cx: &mut ViewContext<Self>,
+    ) {
+        // A display can be updated by a later execution than the one that showed it.
+        if let JupyterMessageContent::UpdateDisplayData(_) = message {
+            let mut changed = false;
+            for cell in &mut self.cells {
+                if let CellKind::Code { outputs, .. } = &mut cell.cell.kind {
+                    changed |= nbformat::apply_message(outputs, &mut cell.output_state, message);
+                }
+            }
+            if changed {
+                self.mark_dirty(cx);
+            }
+            return;
+        }
+
+        let Some(cell) = self
+            .cells
+            .iter_mut()
+            .find(|cell| &cell.execution_view == execution_view)
+        else {
+            return;
+        };
+        let CellKind::Code {
+            execution_count,
+            outputs,
+        } = &mut cell.cell.kind
+        else {
+            return;
+        };
+
+        let changed = match message {
+            JupyterMessageContent::ExecuteInput(input) => {
+                *execution_count = nbformat::execution_count(input);
+                true
+            }
+            message => nbformat::apply_message(outputs, &mut cell.output_state, message),
+        };
+        if changed {
+            self.mark_dirty(cx);
+        }
+    }
+
+    fn selected_cell(&self) -> Option<usize> {
+        (self.selected_cell < self.cells.len()).then_some(self.selected_cell)
+    }
+
+    fn select_cell(&mut self, ix: usize, cx: &mut ViewContext<Self>) {
+        if let Some(cell) = self.cells.get(ix) {
+            self.selected_cell = ix;
+            cell.editor.focus_handle(cx).focus(cx);
+            cx.notify();
+        }
+    }
+
+    fn run_selected_cell(&mut self, _: &RunCell, cx: &mut ViewContext<Self>) {
+        let Some(ix) = self.selected_cell() else {
+            return;
+        };
+        self.run_cell(ix, cx);
+        if ix + 1 < self.cells.len() {
+            self.select_cell(ix + 1, cx);
+        }
+    }
+
+    fn run_all_cells(&mut self, _: &RunAllCells, cx: &mut ViewContext<Self>) {
+        for ix in 0..self.cells.len() {
+            self.run_cell(ix, cx);
+        }
+    }
+
+    fn add_code_cell(&mut self, _: &AddCodeCell, cx: &mut ViewContext<Self>) {
+        self.insert_cell(nbformat::Cell::code(String::new(), self.nbformat_minor), cx);
+    }
+
+    fn add_markdown_cell(&mut self, _: &AddMarkdownCell, cx: &mut ViewContext<Self>) {
+        self.insert_cell(
+            nbformat::Cell::markdown(String::new(), self.nbformat_minor),
+            cx,
+        );
+    }
+
+    /// Inserts a cell after the selected one.
+    fn insert_cell(&mut self, cell: nbformat::Cell, cx: &mut ViewContext<Self>) {
+        let ix = self.selected_cell().map_or(self.cells.len(), |ix| ix + 1);
+        let cell = self.build_cell(cell, cx);
+        self.cells.insert(ix, cell);
+        self.select_cell(ix, cx);
+        self.mark_dirty(cx);
+    }
+
+    fn move_cell_up(&mut self, _: &MoveCellUp, cx: &mut ViewContext<Self>) {
+        if let Some(ix) = self.selected_cell().filter(|ix| *ix > 0) {
+            self.move_cell(ix, ix - 1, cx);
+        }
+    }
+
+    fn move_cell_down(&mut self, _: &MoveCellDown, cx: &mut ViewContext<Self>) {
+        if let Some(ix) = self.selected_cell().filter(|ix| ix + 1 < self.cells.len()) {
+            self.move_cell(ix, ix + 1, cx);
+        }
+    }
+
+    fn move_cell(&mut self, from: usize, to: usize, cx: &mut ViewContext<Self>) {
+        self.cells.swap(from, to);
+        self.select_cell(to, cx);
+        self.mark_dirty(cx);
+    }
+
+    fn delete_cell(&mut self, _: &DeleteCell, cx: &mut ViewContext<Self>) {
+        let Some(ix) = self.selected_cell() else {
+            return;
+        };
+        self.cells.remove(ix);
+        if !self.cells.is_empty() {
+            self.select_cell(ix.min(self.cells.len() - 1), cx);
+        } else {
+            self.selected_cell = 0;
+            cx.focus(&self.focus_handle);
+        }
+        self.mark_dirty(cx);
+    }
+
+    fn editor_style(cx: &WindowContext) -> EditorStyle {
+        let settings = ThemeSettings::get_global(cx);
+        EditorStyle {
+            background: cx.theme().colors().editor_background,
+            local_player: cx.theme().players().local(),
+            text: TextStyle {
+                color: cx.theme().colors().editor_foreground,
+                font_family: settings.buffer_font.family.clone(),
+                font_features: settings.buffer_font.features.clone(),
+                font_size: settings.buffer_font_size(cx).into(),
+                font_weight: settings.buffer_font.weight,
+                line_height: relative(settings.buffer_line_height.value()),
+                ..Default::default()
+            },
+            syntax: cx.theme().syntax().clone(),
+            status: cx.theme().status().clone(),
+            ..Default::default()
+        }
+    }
+
+    fn render_cell(&self, ix: usize, cx: &mut ViewContext<Self>) -> AnyElement {
+        let cell = &self.cells[ix];
+        let colors = cx.theme().colors();
+        let is_selected = self.selected_cell() == Some(ix);
+
+        let (kind, prompt) = match &cell.cell.kind {
+            CellKind::Code {
+                execution_count, ..
+            } => (
+                "Code",
+                format!(
+                    "[{}]",
+                    execution_count.map_or(" ".to_string(), |count| count.to_string())
+                ),
+            ),
+            CellKind::Markdown { .. } => ("Markdown", String::new()),
+            CellKind::Raw { .. } => ("Raw", String::new()),
+        };
+
+        let execution_view = cell.execution_view.read(cx);
+        let has_output = !execution_view.outputs.is_empty()
+            || !matches!(execution_view.status, ExecutionStatus::Unknown);
+
+        v_flex()
+            .id(("notebook-cell", ix))
+            .w_full()
+            .rounded_md()
+            .border_1()
+            .border_color(if is_selected {
+                colors.border_focused
+            } else {
+                colors.border_variant
+            })
+            .on_click(cx.listener(move |this, _, cx| this.select_cell(ix, cx)))
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_2()
+                    .border_b_1()
+                    .border_color(colors.border_variant)
+                    .child(Label::new(kind).size(LabelSize::Small).color(Color::Muted))
+                    .child(
+                        Label::new(prompt)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(div().flex_1())
+                    .when(cell.is_code(), |this| {
+                        this.child(
+                            IconButton::new(("run-cell", ix), IconName::Play)
+                                .icon_size(IconSize::Small)
+                                .tooltip(|cx| Tooltip::for_action("Run Cell", &RunCell, cx))
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    this.selected_cell = ix;
+                                    this.run_cell(ix, cx);
+                                })),
+                        )
+                    })
+                    .child(
+                        IconButton::new(("move-cell-up", ix), IconName::ArrowUp)
+                            .icon_size(IconSize::Small)
+                            .disabled(ix == 0)
+                            .tooltip(|cx| Tooltip::text("Move Cell Up", cx))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.selected_cell = ix;
+                                this.move_cell_up(&MoveCellUp, cx);
+                            })),
+                    )
+                    .child(
+                        IconButton::new(("move-cell-down", ix), IconName::ArrowDown)
+                            .icon_size(IconSize::Small)
+                            .disabled(ix + 1 == self.cells.len())
+                            .tooltip(|cx| Tooltip::text("Move Cell Down", cx))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.selected_cell = ix;
+                                this.move_cell_down(&MoveCellDown, cx);
+                            })),
+                    )
+                    .child(
+                        IconButton::new(("delete-cell", ix), IconName::Trash)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::text("Delete Cell", cx))
+                            .on_click(cx.listener(move |this, _, cx| {
+                                this.selected_cell = ix;
+                                this.delete_cell(&DeleteCell, cx);
+                            })),
+                    ),
+            )
+            .child(
+                div()
+                    .p_2()
+                    .child(EditorElement::new(&cell.editor, Self::editor_style(cx))),
+            )
+            .when(cell.is_code() && has_output, |this| {
+                this.child(
+                    div()
+                        .p_2()
+                        .border_t_1()
+                        .border_color(colors.border_variant)
+                        .font_family(ThemeSettings::get_global(cx).buffer_font.family.clone())
+                        .child(cell.execution_view.clone()),
+                )
+            })
+            .into_any_element()
+    }
+}
+
+impl Render for NotebookEditor {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let toolbar = h_flex()
+            .p_2()
+            .gap_1()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(
+                Button::new("run-all-cells", "Run All")
+                    .icon(IconName::Play)
+                    .icon_position(IconPosition::Start)
+                    .icon_size(IconSize::Small)
+                    .on_click(cx.listener(|this, _, cx| this.run_all_cells(&RunAllCells, cx))),
+            )
+            .child(
+                Button::new("add-code-cell", "Code")
+                    .icon(IconName::Plus)
+                    .icon_position(IconPosition::Start)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::for_action("Add Code Cell", &AddCodeCell, cx))
+                    .on_click(cx.listener(|this, _, cx| this.add_code_cell(&AddCodeCell, cx))),
+            )
+            .child(
+                Button::new("add-markdown-cell", "Markdown")
+                    .icon(IconName::Plus)
+                    .icon_position(IconPosition::Start)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::for_action("Add Markdown Cell", &AddMarkdownCell, cx))
+                    .on_click(
+                        cx.listener(|this, _, cx| this.add_markdown_cell(&AddMarkdownCell, cx)),
+                    ),
+            )
+            .child(div().flex_1())
+            .child(
+                Label::new(self.kernel_language.to_string())
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            );
+
+        let content = if let Some(error) = &self.parse_error {
+            div()
+                .p_4()
+                .child(
+                    Label::new(format!("Failed to read the notebook: {error}")).color(Color::Error),
+                )
+                .into_any_element()
+        } else {
+            v_flex()
+                .id("notebook-cells")
+                .flex_1()
+                .p_2()
+                .gap_2()
+                .overflow_y_scroll()
+                .children((0..self.cells.len()).map(|ix| self.render_cell(ix, cx)))
+                .into_any_element()
+        };
+
+        v_flex()
+            .key_context("NotebookEditor")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .on_action(cx.listener(Self::run_selected_cell))
+            .on_action(cx.listener(Self::run_all_cells))
+            .on_action(cx.listener(Self::add_code_cell))
+            .on_action(cx.listener(Self::add_markdown_cell))
+            .on_action(cx.listener(Self::move_cell_up))
+            .on_action(cx.listener(Self::move_cell_down))
+            .on_action(cx.listener(Self::delete_cell))
+            .child(toolbar)
+            .child(content)
+    }
+}
+
+impl FocusableView for NotebookEditor {
+    fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
+        match self.cells.get(self.selected_cell) {
+            Some(cell) => cell.editor.focus_handle(cx),
+            None => self.focus_handle.clone(),
+        }
+    }
+}
+
+impl EventEmitter<ItemEvent> for NotebookEditor {}
+
+impl Item for NotebookEditor {
+    type Event = ItemEvent;
+
+    fn tab_content(&self, params: TabContentParams, cx: &WindowContext) -> AnyElement {
+        let title = self
+            .notebook
+            .read(cx)
+            .project_path
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Label::new(title)
+            .single_line()
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .italic(params.preview)
+            .into_any_element()
+    }
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &AppContext,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::Item),
+    ) {
+        f(self.notebook.entity_id(), self.notebook.read(cx))
+    }
+
+    fn is_singleton(&self, _: &AppContext) -> bool {
+        true
+    }
+
+    fn is_dirty(&self, _: &AppContext) -> bool {
+        self.dirty
+    }
+
+    fn has_conflict(&self, cx: &AppContext) -> bool {
+        self.buffer(cx).read(cx).has_conflict()
+    }
+
+    fn can_save(&self, _: &AppContext) -> bool {
+        // Saving a notebook that couldn't be read would overwrite it with an empty one.
+        self.parse_error.is_none()
+    }
+
+    fn save(
+        &mut self,
+        _format: bool,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let buffer = self.update_buffer(cx);
+        let save =
+            buffer.map(|buffer| project.update(cx, |project, cx| project.save_buffer(buffer, cx)));
+        self.finish_save(save, None, cx)
+    }
+
+    fn save_as(
+        &mut self,
+        project: Model<Project>,
+        path: ProjectPath,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let buffer = self.update_buffer(cx);
+        let save = buffer.map(|buffer| {
+            project.update(cx, |project, cx| {
+                project.save_buffer_as(buffer, path.clone(), cx)
+            })
+        });
+        self.finish_save(save, Some(path), cx)
+    }
+
+    fn reload(&mut self, project: Model<Project>, cx: &mut ViewContext<Self>) -> Task<Result<()>> {
+        let buffer = self.buffer(cx);
+        let reload = project.update(cx, |project, cx| {
+            project.reload_buffers(HashSet::from_iter([buffer]), true, cx)
+        });
+        cx.spawn(|this, mut cx| async move {
+            reload.await?;
+            this.update(&mut cx, |this, cx| this.load_cells(cx))
+        })
+    }
+}
+
+impl NotebookEditor {
+    /// Writes the cells into the notebook's buffer, ready to be saved.
+    fn update_buffer(&mut self, cx: &mut ViewContext<Self>) -> Result<Model<Buffer>> {
+        let json = self.to_notebook(cx).to_json()?;
+        let buffer = self.buffer(cx);
+        buffer.update(cx, |buffer, cx| {
+            buffer.set_text(json, cx);
+        });
+        Ok(buffer)
+    }
+
+    /// Marks the notebook as saved once the save succeeds, at the new path it was saved as if any.
+    fn finish_save(
+        &mut self,
+        save: Result<Task<Result<()>>>,
+        project_path: Option<ProjectPath>,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<()>> {
+        let save = match save {
+            Ok(save) => save,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        cx.spawn(|this, mut cx| async move {
+            save.await?;
+            this.update(&mut cx, |this, cx| {
+                if let Some(project_path) = project_path {
+                    this.notebook
+                        .update(cx, |notebook, _| notebook.project_path = project_path);
+                }
+                this.dirty = false;
+                cx.emit(ItemEvent::UpdateTab);
+            })
+        })
+    }
+}
+
+impl ProjectItem for NotebookEditor {
+    type Item = NotebookItem;
+
+    fn for_project_item(
+        project: Model<Project>,
+        item: Model<Self::Item>,
+        cx: &mut ViewContext<Self>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(project, item, cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::{FakeFs, Fs as _, WorktreeId};
+    use serde_json::json;
+    use settings::{Settings as _, SettingsStore};
+    use std::path::Path;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "code",
+   "execution_count": null,
+   "metadata": {},
+   "outputs": [],
+   "source": "a = 1"
+  }
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 4
+}
+"##;
+
+    #[gpui::test]
+    async fn test_edit_and_save_cells(cx: &mut TestAppContext) {
+        let (fs, project, notebook, cx) = open_notebook(cx).await;
+
+        notebook.update(cx, |notebook, cx| {
+            notebook.add_code_cell(&AddCodeCell, cx);
+            notebook.cells[1]
+                .editor
+                .update(cx, |editor, cx| editor.set_text("b = 2", cx));
+            notebook.add_markdown_cell(&AddMarkdownCell, cx);
+            notebook.cells[2]
+                .editor
+                .update(cx, |editor, cx| editor.set_text("# Notes", cx));
+            notebook.move_cell_up(&MoveCellUp, cx);
+            assert_eq!(notebook.selected_cell(), Some(1));
+            assert_eq!(cell_sources(notebook, cx), ["a = 1", "# Notes", "b = 2"]);
+
+            notebook.select_cell(0, cx);
+            notebook.delete_cell(&DeleteCell, cx);
+            assert_eq!(notebook.selected_cell(), Some(0));
+            assert!(notebook.is_dirty(cx));
+        });
+
+        notebook
+            .update(cx, |notebook, cx| notebook.save(false, project, cx))
+            .await
+            .unwrap();
+        notebook.update(cx, |notebook, cx| assert!(!notebook.is_dirty(cx)));
+
+        let saved =
+            Notebook::parse(&fs.load(Path::new("/notebook/test.ipynb")).await.unwrap()).unwrap();
+        assert_eq!(saved.nbformat_minor, 4);
+        let cells = saved
+            .cells
+            .iter()
+            .map(|cell| (cell.kind.clone(), cell.source.0.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            [
+                (CellKind::Markdown { attachments: None }, "# Notes"),
+                (
+                    CellKind::Code {
+                        execution_count: None,
+                        outputs: Vec::new()
+                    },
+                    "b = 2"
+                ),
+            ]
+        );
+        // Cells of notebooks before nbformat 4.5 don't have ids.
+        assert!(saved.cells.iter().all(|cell| cell.id.is_none()));
+    }
+
+    #[gpui::test]
+    async fn test_save_cell_outputs(cx: &mut TestAppContext) {
+        let (fs, project, notebook, cx) = open_notebook(cx).await;
+
+        // The messages of the kernel for a run of the cell are stored with it.
+        notebook.update(cx, |notebook, cx| {
+            let execution_view = notebook.cells[0].execution_view.clone();
+            let messages = [
+                JupyterMessageContent::ExecuteInput(
+                    serde_json::from_value(json!({"code": "a = 1", "execution_count": 3})).unwrap(),
+                ),
+                JupyterMessageContent::StreamContent(runtimelib::StreamContent {
+                    name: runtimelib::Stdio::Stdout,
+                    text: "done\n".into(),
+                }),
+            ];
+            for message in &messages {
+                notebook.store_output(&execution_view, message, cx);
+            }
+            assert!(notebook.is_dirty(cx));
+        });
+
+        notebook
+            .update(cx, |notebook, cx| notebook.save(false, project, cx))
+            .await
+            .unwrap();
+        let saved =
+            Notebook::parse(&fs.load(Path::new("/notebook/test.ipynb")).await.unwrap()).unwrap();
+        assert_eq!(
+            saved.cells[0].kind,
+            CellKind::Code {
+                execution_count: Some(3),
+                outputs: vec![nbformat::Output::Stream {
+                    name: "stdout".into(),
+                    text: nbformat::MultilineString("done\n".into()),
+                }],
+            }
+        );
+
+        // Reloading the saved notebook shows the same cells.
+        notebook.update(cx, |notebook, cx| {
+            notebook.load_cells(cx);
+            assert_eq!(notebook.to_notebook(cx), saved);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_save_as(cx: &mut TestAppContext) {
+        let (fs, project, notebook, cx) = open_notebook(cx).await;
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees().next().unwrap().read(cx).id()
+        });
+        let project_path = |notebook: &View<NotebookEditor>, cx: &mut VisualTestContext| {
+            notebook.update(cx, |notebook, cx| {
+                notebook.notebook.read(cx).project_path.path.clone()
+            })
+        };
+        notebook.update(cx, |notebook, cx| notebook.add_code_cell(&AddCodeCell, cx));
+
+        // The notebook keeps its path when saving it fails.
+        let result = notebook
+            .update(cx, |notebook, cx| {
+                let path = ProjectPath {
+                    worktree_id: WorktreeId::from_usize(100),
+                    path: Path::new("missing.ipynb").into(),
+                };
+                notebook.save_as(project.clone(), path, cx)
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            project_path(&notebook, cx).as_ref(),
+            Path::new("test.ipynb")
+        );
+        notebook.update(cx, |notebook, cx| assert!(notebook.is_dirty(cx)));
+
+        notebook
+            .update(cx, |notebook, cx| {
+                let path = ProjectPath {
+                    worktree_id,
+                    path: Path::new("copy.ipynb").into(),
+                };
+                notebook.save_as(project.clone(), path, cx)
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            project_path(&notebook, cx).as_ref(),
+            Path::new("copy.ipynb")
+        );
+        notebook.update(cx, |notebook, cx| assert!(!notebook.is_dirty(cx)));
+        let saved =
+            Notebook::parse(&fs.load(Path::new("/notebook/copy.ipynb")).await.unwrap()).unwrap();
+        assert_eq!(saved.cells.len(), 2);
+    }
+
+    async fn open_notebook(
+        cx: &mut TestAppContext,
+    ) -> (
+        Arc<FakeFs>,
+        Model<Project>,
+        View<NotebookEditor>,
+        &mut VisualTestContext,
+    ) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            Project::init_settings(cx);
+            workspace::init_settings(cx);
+            editor::init_settings(cx);
+            JupyterSettings::register(cx);
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree("/notebook", json!({ "test.ipynb": NOTEBOOK }))
+            .await;
+        let project = Project::test(fs.clone(), ["/notebook".as_ref()], cx).await;
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/notebook/test.ipynb", cx)
+            })
+            .await
+            .unwrap();
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees().next().unwrap().read(cx).id()
+        });
+        let item = cx.new_model(|_| NotebookItem {
+            buffer,
+            project_path: ProjectPath {
+                worktree_id,
+                path: Path::new("test.ipynb").into(),
+            },
+        });
+
+        let (notebook, cx) =
+            cx.add_window_view(|cx| NotebookEditor::new(project.clone(), item, cx));
+        (fs, project, notebook, cx)
+    }
+
+    fn cell_sources(notebook: &NotebookEditor, cx: &AppContext) -> Vec<String> {
+        notebook
+            .cells
+            .iter()
+            .map(|cell| cell.editor.read(cx).text(cx))
+            .collect()
+    }
+}
@@ -1,8 +1,21 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
 use std::sync::Arc;
 
+use crate::nbformat;
 use crate::stdio::TerminalOutput;
 use anyhow::Result;
-use gpui::{img, AnyElement, FontWeight, ImageData, Render, View};
+use collections::{HashMap, HashSet};
+use gpui::{
+    canvas, img, AnyElement, FontWeight, ImageData, Model, Render, TextStyleRefinement, View,
+    WeakView,
+};
+use html_to_markdown::markdown::{
+    CodeHandler, HeadingHandler, ListHandler, ParagraphHandler, StyledTextHandler, TableHandler,
+    WebpageChromeRemover,
+};
+use html_to_markdown::{convert_html_to_markdown, TagHandler};
+use markdown::{Markdown, MarkdownStyle};
 use runtimelib::datatable::TableSchema;
 use runtimelib::media::datatable::TabularDataResource;
 use runtimelib::{ExecutionState, JupyterMessageContent, MimeBundle, MimeType};
@@ -18,10 +31,14 @@ pub trait LineHeight: Sized {
 // When deciding what to render from a collection of mediatypes, we need to rank them in order of importance
 fn rank_mime_type(mimetype: &MimeType) -> usize {
     match mimetype {
-        MimeType::DataTable(_) => 6,
-        MimeType::Png(_) => 4,
-        MimeType::Jpeg(_) => 3,
-        MimeType::Markdown(_) => 2,
+        MimeType::DataTable(_) => 8,
+        MimeType::Png(_) => 7,
+        MimeType::Jpeg(_) => 6,
+        // Plots come with an HTML fallback that's only a caption, so images are preferred
+        MimeType::Html(html) if is_simple_html(html) => 5,
+        MimeType::Markdown(_) => 4,
+        MimeType::Latex(_) => 3,
+        MimeType::Json(_) => 2,
         MimeType::Plain(_) => 1,
         // All other media types are not supported in Zed at this time
         _ => 0,
@@ -198,6 +215,261 @@ impl LineHeight for TableView {
     }
 }
 
+/// MarkdownView renders markdown, as well as the HTML and LaTeX outputs that are converted to it.
+pub struct MarkdownView {
+    markdown: View<Markdown>,
+    /// The height of the markdown when it was last drawn. Paragraphs wrap, and headings and
+    /// blocks are spaced out, so the source doesn't tell how many lines it takes up.
+    height: Rc<Cell<Option<Pixels>>>,
+}
+
+impl MarkdownView {
+    fn new(source: String, cx: &mut WindowContext) -> Self {
+        let style = markdown_style(cx);
+        let markdown = cx.new_view(|cx| Markdown::new(source, style, None, cx));
+        Self {
+            markdown,
+            height: Rc::default(),
+        }
+    }
+
+    fn render(&self, cx: &ViewContext<ExecutionView>) -> AnyElement {
+        let height = self.height.clone();
+        let view_id = cx.entity_id();
+        div()
+            .relative()
+            .child(self.markdown.clone())
+            .child(
+                canvas(
+                    move |bounds, cx| {
+                        // Once the markdown has been laid out, let the block resize to fit it.
+                        if height.replace(Some(bounds.size.height)) != Some(bounds.size.height) {
+                            cx.defer(move |cx| cx.notify(view_id));
+                        }
+                    },
+                    |_, _, _| {},
+                )
+                .absolute()
+                .size_full(),
+            )
+            .into_any_element()
+    }
+}
+
+impl LineHeight for MarkdownView {
+    fn num_lines(&self, cx: &mut WindowContext) -> u8 {
+        // Until the markdown is drawn, guess a line for each line of text in it.
+        let Some(height) = self.height.get() else {
+            let lines = self
+                .markdown
+                .read(cx)
+                .source()
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .count();
+            return lines.clamp(1, u8::MAX as usize) as u8;
+        };
+        (height / cx.line_height()).ceil().clamp(1., u8::MAX as f32) as u8
+    }
+}
+
+fn markdown_style(cx: &WindowContext) -> MarkdownStyle {
+    let colors = cx.theme().colors();
+    MarkdownStyle {
+        code_block: TextStyleRefinement {
+            background_color: Some(colors.editor_background),
+            ..Default::default()
+        },
+        inline_code: TextStyleRefinement {
+            background_color: Some(colors.editor_background),
+            ..Default::default()
+        },
+        block_quote: TextStyleRefinement {
+            color: Some(colors.text_muted),
+            ..Default::default()
+        },
+        link: TextStyleRefinement {
+            color: Some(Color::Accent.color(cx)),
+            ..Default::default()
+        },
+        rule_color: colors.border,
+        block_quote_border_color: colors.border,
+        syntax: cx.theme().syntax().clone(),
+        selection_background_color: cx.theme().players().local().selection,
+    }
+}
+
+/// Whether the HTML is a document that converts to markdown, as opposed to an interactive one
+/// that needs a browser to render, like a plot.
+fn is_simple_html(html: &str) -> bool {
+    let html = html.to_ascii_lowercase();
+    !html.contains("<script") && !html.contains("<iframe")
+}
+
+fn html_to_markdown(html: &str) -> Result<String> {
+    let mut handlers: Vec<TagHandler> = vec![
+        Rc::new(RefCell::new(WebpageChromeRemover)),
+        Rc::new(RefCell::new(ParagraphHandler)),
+        Rc::new(RefCell::new(HeadingHandler)),
+        Rc::new(RefCell::new(ListHandler)),
+        Rc::new(RefCell::new(TableHandler::new())),
+        Rc::new(RefCell::new(StyledTextHandler)),
+        Rc::new(RefCell::new(CodeHandler)),
+    ];
+    convert_html_to_markdown(html.as_bytes(), &mut handlers)
+}
+
+/// LaTeX can't be typeset in Zed yet, so its source is shown instead, without the math delimiters.
+fn latex_to_markdown(latex: &str) -> String {
+    let latex = latex.trim();
+    let latex = latex
+        .strip_prefix("$$")
+        .and_then(|latex| latex.strip_suffix("$$"))
+        .or_else(|| {
+            latex
+                .strip_prefix('$')
+                .and_then(|latex| latex.strip_suffix('$'))
+        })
+        .unwrap_or(latex)
+        .trim();
+    let latex = latex.strip_prefix("\\displaystyle").unwrap_or(latex).trim();
+    format!("```latex\n{latex}\n```")
+}
+
+/// JsonView renders JSON as a tree, whose objects and arrays can be expanded and collapsed.
+pub struct JsonView {
+    value: Value,
+    /// The JSON pointers of the expanded objects and arrays. Only the root is expanded at first.
+    expanded: HashSet<String>,
+}
+
+/// A line of a JSON tree: a scalar, or an object or array that can be expanded.
+#[derive(Debug, PartialEq)]
+struct JsonRow {
+    pointer: String,
+    depth: usize,
+    key: Option<String>,
+    text: String,
+    expanded: Option<bool>,
+}
+
+impl JsonView {
+    fn new(value: Value) -> Self {
+        Self {
+            value,
+            expanded: HashSet::from_iter([String::new()]),
+        }
+    }
+
+    fn toggle(&mut self, pointer: &str) {
+        if !self.expanded.remove(pointer) {
+            self.expanded.insert(pointer.to_string());
+        }
+    }
+
+    fn rows(&self) -> Vec<JsonRow> {
+        let mut rows = Vec::new();
+        self.push_rows(&self.value, String::new(), 0, None, &mut rows);
+        rows
+    }
+
+    fn push_rows(
+        &self,
+        value: &Value,
+        pointer: String,
+        depth: usize,
+        key: Option<String>,
+        rows: &mut Vec<JsonRow>,
+    ) {
+        let children: Vec<(String, &Value)> = match value {
+            Value::Object(object) => object
+                .iter()
+                .map(|(key, value)| (key.clone(), value))
+                .collect(),
+            Value::Array(array) => array
+                .iter()
+                .enumerate()
+                .map(|(ix, value)| (ix.to_string(), value))
+                .collect(),
+            scalar => {
+                rows.push(JsonRow {
+                    pointer,
+                    depth,
+                    key,
+                    text: scalar.to_string(),
+                    expanded: None,
+                });
+                return;
+            }
+        };
+
+        let text = match value {
+            Value::Object(_) if children.len() == 1 => "{…} 1 key".to_string(),
+            Value::Object(_) => format!("{{…}} {} keys", children.len()),
+            _ if children.len() == 1 => "[…] 1 item".to_string(),
+            _ => format!("[…] {} items", children.len()),
+        };
+        let expanded = self.expanded.contains(&pointer);
+        rows.push(JsonRow {
+            pointer: pointer.clone(),
+            depth,
+            key,
+            text,
+            expanded: Some(expanded),
+        });
+        if expanded {
+            for (child_key, child) in children {
+                let child_pointer = format!(
+                    "{pointer}/{}",
+                    child_key.replace('~', "~0").replace('/', "~1")
+                );
+                self.push_rows(child, child_pointer, depth + 1, Some(child_key), rows);
+            }
+        }
+    }
+
+    fn render(&self, output_ix: usize, cx: &ViewContext<ExecutionView>) -> AnyElement {
+        let rows = self.rows().into_iter().map(|row| {
+            let disclosure = row.expanded.map(|expanded| {
+                Icon::new(if expanded {
+                    IconName::ChevronDown
+                } else {
+                    IconName::ChevronRight
+                })
+                .size(IconSize::XSmall)
+                .color(Color::Muted)
+            });
+            let pointer = row.pointer.clone();
+            h_flex()
+                .id(SharedString::from(format!(
+                    "json-{output_ix}-{}",
+                    row.pointer
+                )))
+                .gap_1()
+                .pl(px(16. * row.depth as f32))
+                .children(disclosure)
+                .children(row.key.map(|key| {
+                    div()
+                        .text_color(Color::Muted.color(cx))
+                        .child(format!("{key}:"))
+                }))
+                .child(row.text)
+                .when(row.expanded.is_some(), |row| {
+                    row.cursor_pointer().on_click(
+                        cx.listener(move |this, _, cx| this.toggle_json(output_ix, &pointer, cx)),
+                    )
+                })
+        });
+        v_flex().w_full().children(rows).into_any_element()
+    }
+}
+
+impl LineHeight for JsonView {
+    fn num_lines(&self, _cx: &mut WindowContext) -> u8 {
+        self.rows().len().min(u8::MAX as usize) as u8
+    }
+}
+
 // Userspace error from the kernel
 pub struct ErrorView {
     pub ename: String,
@@ -246,20 +518,50 @@ pub enum OutputType {
     ErrorOutput(ErrorView),
     Message(String),
     Table(TableView),
+    Markdown(MarkdownView),
+    Json(JsonView),
     ClearOutputWaitMarker,
 }
 
 impl OutputType {
-    fn render(&self, cx: &ViewContext<ExecutionView>) -> Option<AnyElement> {
+    /// Renders the richest of the media types that the kernel sent.
+    pub fn new(data: &MimeBundle, cx: &mut WindowContext) -> Self {
+        match data.richest(rank_mime_type) {
+            Some(MimeType::Plain(text)) => OutputType::Plain(TerminalOutput::from(text)),
+            Some(MimeType::Markdown(text)) => {
+                OutputType::Markdown(MarkdownView::new(text.clone(), cx))
+            }
+            Some(MimeType::Html(html)) if is_simple_html(html) => match html_to_markdown(html) {
+                Ok(markdown) => OutputType::Markdown(MarkdownView::new(markdown, cx)),
+                Err(error) => OutputType::Message(format!("Failed to convert HTML: {}", error)),
+            },
+            Some(MimeType::Latex(latex)) => {
+                OutputType::Markdown(MarkdownView::new(latex_to_markdown(latex), cx))
+            }
+            Some(MimeType::Json(value)) => OutputType::Json(JsonView::new(value.clone())),
+            Some(MimeType::Png(data)) | Some(MimeType::Jpeg(data)) => match ImageView::from(data) {
+                Ok(view) => OutputType::Image(view),
+                Err(error) => OutputType::Message(format!("Failed to load image: {}", error)),
+            },
+            Some(MimeType::DataTable(data)) => OutputType::Table(TableView {
+                table: data.clone(),
+            }),
+            // Any other media types are not supported
+            _ => OutputType::Message("Unsupported media type".to_string()),
+        }
+    }
+
+    fn render(&self, ix: usize, cx: &ViewContext<ExecutionView>) -> Option<AnyElement> {
         let el = match self {
             // Note: in typical frontends we would show the execute_result.execution_count
             // Here we can just handle either
             Self::Plain(stdio) => Some(stdio.render(cx)),
-            // Self::Markdown(markdown) => Some(markdown.render(theme)),
             Self::Stream(stdio) => Some(stdio.render(cx)),
             Self::Image(image) => Some(image.render(cx)),
             Self::Message(message) => Some(div().child(message.clone()).into_any_element()),
             Self::Table(table) => Some(table.render(cx)),
+            Self::Markdown(markdown) => Some(markdown.render(cx)),
+            Self::Json(json) => Some(json.render(ix, cx)),
             Self::ErrorOutput(error_view) => error_view.render(cx),
             Self::ClearOutputWaitMarker => None,
         };
@@ -277,30 +579,14 @@ impl LineHeight for OutputType {
             Self::Image(image) => image.num_lines(cx),
             Self::Message(message) => message.lines().count() as u8,
             Self::Table(table) => table.num_lines(cx),
+            Self::Markdown(markdown) => markdown.num_lines(cx),
+            Self::Json(json) => json.num_lines(cx),
             Self::ErrorOutput(error_view) => error_view.num_lines(cx),
             Self::ClearOutputWaitMarker => 0,
         }
     }
 }
 
-impl From<&MimeBundle> for OutputType {
-    fn from(data: &MimeBundle) -> Self {
-        match data.richest(rank_mime_type) {
-            Some(MimeType::Plain(text)) => OutputType::Plain(TerminalOutput::from(text)),
-            Some(MimeType::Markdown(text)) => OutputType::Plain(TerminalOutput::from(text)),
-            Some(MimeType::Png(data)) | Some(MimeType::Jpeg(data)) => match ImageView::from(data) {
-                Ok(view) => OutputType::Image(view),
-                Err(error) => OutputType::Message(format!("Failed to load image: {}", error)),
-            },
-            Some(MimeType::DataTable(data)) => OutputType::Table(TableView {
-                table: data.clone(),
-            }),
-            // Any other media types are not supported
-            _ => OutputType::Message("Unsupported media type".to_string()),
-        }
-    }
-}
-
 #[derive(Default)]
 pub enum ExecutionStatus {
     #[default]
@@ -311,24 +597,70 @@ pub enum ExecutionStatus {
     Finished,
 }
 
+/// The executions that displayed outputs with an id, across everything run on a kernel.
+/// `update_display_data` messages replace those outputs in place, like the frames of a
+/// progress bar, even when a later execution sends them.
+#[derive(Default)]
+pub struct Displays {
+    views: HashMap<String, Vec<WeakView<ExecutionView>>>,
+}
+
+impl Displays {
+    fn insert(&mut self, display_id: String, view: WeakView<ExecutionView>) {
+        let views = self.views.entry(display_id).or_default();
+        views.retain(|existing| {
+            existing.upgrade().is_some() && existing.entity_id() != view.entity_id()
+        });
+        views.push(view);
+    }
+
+    fn views(&self, display_id: &str) -> Vec<WeakView<ExecutionView>> {
+        self.views.get(display_id).cloned().unwrap_or_default()
+    }
+}
+
 pub struct ExecutionView {
     pub outputs: Vec<OutputType>,
     pub status: ExecutionStatus,
+    displays: Model<Displays>,
+    /// The indices of this execution's outputs that were displayed with an id.
+    display_ids: HashMap<String, usize>,
 }
 
 impl ExecutionView {
-    pub fn new(_cx: &mut ViewContext<Self>) -> Self {
+    pub fn new(displays: Model<Displays>, _cx: &mut ViewContext<Self>) -> Self {
         Self {
             outputs: Default::default(),
             status: ExecutionStatus::Unknown,
+            displays,
+            display_ids: HashMap::default(),
         }
     }
 
     /// Accept a Jupyter message belonging to this execution
     pub fn push_message(&mut self, message: &JupyterMessageContent, cx: &mut ViewContext<Self>) {
+        let mut display_id = None;
         let output: OutputType = match message {
-            JupyterMessageContent::ExecuteResult(result) => (&result.data).into(),
-            JupyterMessageContent::DisplayData(result) => (&result.data).into(),
+            JupyterMessageContent::ExecuteResult(result) => OutputType::new(&result.data, cx),
+            JupyterMessageContent::DisplayData(result) => {
+                display_id = nbformat::display_id(result);
+                OutputType::new(&result.data, cx)
+            }
+            JupyterMessageContent::UpdateDisplayData(update) => {
+                let Some(display_id) = nbformat::display_id(update) else {
+                    return;
+                };
+                self.update_display(&display_id, &update.data, cx);
+                for view in self.displays.read(cx).views(&display_id) {
+                    if view.entity_id() != cx.entity_id() {
+                        view.update(cx, |view, cx| {
+                            view.update_display(&display_id, &update.data, cx)
+                        })
+                        .ok();
+                    }
+                }
+                return;
+            }
             JupyterMessageContent::StreamContent(result) => {
                 // Previous stream data will combine together, handling colors, carriage returns, etc
                 if let Some(new_terminal) = self.apply_terminal_text(&result.text) {
@@ -354,7 +686,7 @@ impl ExecutionView {
                         // Pager data comes in via `?` at the end of a statement in Python, used for showing documentation.
                         // Some UI will show this as a popup. For ease of implementation, it's included as an output here.
                         runtimelib::Payload::Page { data, .. } => {
-                            let output: OutputType = (data).into();
+                            let output = OutputType::new(data, cx);
                             self.outputs.push(output);
                         }
 
@@ -376,6 +708,7 @@ impl ExecutionView {
             JupyterMessageContent::ClearOutput(options) => {
                 if !options.wait {
                     self.outputs.clear();
+                    self.display_ids.clear();
                     cx.notify();
                     return;
                 }
@@ -401,13 +734,36 @@ impl ExecutionView {
         // Check for a clear output marker as the previous output, so we can clear it out
         if let Some(OutputType::ClearOutputWaitMarker) = self.outputs.last() {
             self.outputs.clear();
+            self.display_ids.clear();
         }
 
         self.outputs.push(output);
+        if let Some(display_id) = display_id {
+            self.display_ids
+                .insert(display_id.clone(), self.outputs.len() - 1);
+            let view = cx.view().downgrade();
+            self.displays
+                .update(cx, |displays, _| displays.insert(display_id, view));
+        }
 
         cx.notify();
     }
 
+    fn update_display(&mut self, display_id: &str, data: &MimeBundle, cx: &mut ViewContext<Self>) {
+        if let Some(&ix) = self.display_ids.get(display_id) {
+            self.outputs[ix] = OutputType::new(data, cx);
+            cx.notify();
+        }
+    }
+
+    /// Expands or collapses an object or array of a JSON output, which changes its height.
+    fn toggle_json(&mut self, output_ix: usize, pointer: &str, cx: &mut ViewContext<Self>) {
+        if let Some(OutputType::Json(json)) = self.outputs.get_mut(output_ix) {
+            json.toggle(pointer);
+            cx.notify();
+        }
+    }
+
     fn apply_terminal_text(&mut self, text: &str) -> Option<OutputType> {
         if let Some(last_output) = self.outputs.last_mut() {
             match last_output {
@@ -457,7 +813,12 @@ impl Render for ExecutionView {
 
         div()
             .w_full()
-            .children(self.outputs.iter().filter_map(|output| output.render(cx)))
+            .children(
+                self.outputs
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(ix, output)| output.render(ix, cx)),
+            )
             .into_any_element()
     }
 }
@@ -482,3 +843,133 @@ impl LineHeight for View<ExecutionView> {
         self.update(cx, |execution_view, cx| execution_view.num_lines(cx))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_rows() {
+        let mut json = JsonView::new(json!({"name": "zed", "tags": ["editor", "rust"]}));
+        let texts = |json: &JsonView| {
+            json.rows()
+                .into_iter()
+                .map(|row| (row.depth, row.key, row.text))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            texts(&json),
+            vec![
+                (0, None, "{…} 2 keys".to_string()),
+                (1, Some("name".to_string()), "\"zed\"".to_string()),
+                (1, Some("tags".to_string()), "[…] 2 items".to_string()),
+            ]
+        );
+
+        json.toggle("/tags");
+        assert_eq!(json.rows()[3].pointer, "/tags/0");
+        assert_eq!(json.rows().len(), 5);
+
+        json.toggle("");
+        assert_eq!(json.rows().len(), 1);
+    }
+
+    #[test]
+    fn test_rich_text_to_markdown() {
+        assert_eq!(
+            latex_to_markdown("$\\displaystyle x^{2}$"),
+            "```latex\nx^{2}\n```"
+        );
+        assert!(!is_simple_html("<div><script>render()</script></div>"));
+
+        let markdown = html_to_markdown(
+            "<style scoped>.dataframe { color: red; }</style><p>Some <strong>bold</strong> text</p>",
+        )
+        .unwrap();
+        assert_eq!(markdown.trim(), "Some **bold** text");
+    }
+
+    fn display(value: Value) -> (JupyterMessageContent, JupyterMessageContent) {
+        let content = json!({
+            "data": {"application/json": value},
+            "metadata": {},
+            "transient": {"display_id": "progress"},
+        });
+        (
+            JupyterMessageContent::DisplayData(serde_json::from_value(content.clone()).unwrap()),
+            JupyterMessageContent::UpdateDisplayData(serde_json::from_value(content).unwrap()),
+        )
+    }
+
+    fn json_values(view: &View<ExecutionView>, cx: &mut gpui::VisualTestContext) -> Vec<Value> {
+        view.update(cx, |view, _| {
+            view.outputs
+                .iter()
+                .filter_map(|output| match output {
+                    OutputType::Json(json) => Some(json.value.clone()),
+                    _ => None,
+                })
+                .collect()
+        })
+    }
+
+    #[gpui::test]
+    fn test_json_toggle_resizes_output(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let displays = cx.new_model(|_| Displays::default());
+        let view = cx.new_view(|cx| ExecutionView::new(displays, cx));
+        view.update(cx, |view, cx| {
+            view.push_message(&display(json!({"tags": ["editor", "rust"]})).0, cx)
+        });
+
+        let heights = Rc::new(RefCell::new(Vec::new()));
+        let _subscription = cx.update(|cx| {
+            let heights = heights.clone();
+            cx.observe(&view, move |view, cx| {
+                heights.borrow_mut().push(view.num_lines(cx));
+            })
+        });
+        assert_eq!(cx.update(|cx| view.num_lines(cx)), 2);
+
+        view.update(cx, |view, cx| view.toggle_json(0, "/tags", cx));
+        view.update(cx, |view, cx| view.toggle_json(0, "", cx));
+        assert_eq!(*heights.borrow(), vec![4, 1]);
+    }
+
+    #[gpui::test]
+    fn test_update_display_of_earlier_execution(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let displays = cx.new_model(|_| Displays::default());
+        let first = cx.new_view(|cx| ExecutionView::new(displays.clone(), cx));
+        let second = cx.new_view(|cx| ExecutionView::new(displays.clone(), cx));
+
+        first.update(cx, |view, cx| {
+            view.push_message(&display(json!("0%")).0, cx)
+        });
+        second.update(cx, |view, cx| {
+            view.push_message(&display(json!("50%")).1, cx)
+        });
+        assert_eq!(json_values(&first, cx), vec![json!("50%")]);
+        assert!(json_values(&second, cx).is_empty());
+
+        // The latest execution to show a display is updated along with the earlier ones.
+        second.update(cx, |view, cx| {
+            view.push_message(&display(json!("50%")).0, cx)
+        });
+        first.update(cx, |view, cx| {
+            view.push_message(&display(json!("100%")).1, cx)
+        });
+        assert_eq!(json_values(&first, cx), vec![json!("100%")]);
+        assert_eq!(json_values(&second, cx), vec![json!("100%")]);
+
+        // Executions that are gone are skipped.
+        drop(first);
+        cx.run_until_parked();
+        second.update(cx, |view, cx| {
+            view.push_message(&display(json!("done")).1, cx)
+        });
+        assert_eq!(json_values(&second, cx), vec![json!("done")]);
+    }
+}
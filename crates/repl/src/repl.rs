@@ -19,26 +19,38 @@ use gpui::{
 };
 use gpui::{Entity, View};
 use language::Point;
-use outputs::{ExecutionStatus, ExecutionView, LineHeight as _};
+use outputs::{Displays, ExecutionStatus, ExecutionView, LineHeight as _};
 use project::Fs;
 use runtime_settings::JupyterSettings;
-use runtimelib::JupyterMessageContent;
+use runtimelib::{CompleteReply, InspectReply, JupyterMessageContent};
 use settings::{Settings as _, SettingsStore};
 use std::{ops::Range, time::Instant};
 use std::{sync::Arc, time::Duration};
 use theme::{ActiveTheme, ThemeSettings};
 use ui::prelude::*;
+use util::ResultExt as _;
 use workspace::Workspace;
 
+mod completions;
+mod kernel_status;
+mod nbformat;
+mod notebook;
 mod outputs;
 // mod runtime_panel;
 mod runtime_settings;
 mod runtimes;
 mod stdio;
+mod variables_panel;
 
+use completions::KernelCompletionProvider;
+pub use kernel_status::KernelStatusIndicator;
+pub use runtimes::KernelStatus;
 use runtimes::{get_runtime_specifications, Request, RunningKernel, RuntimeSpecification};
+pub use variables_panel::VariablesPanel;
 
-actions!(repl, [Run]);
+actions!(repl, [Run, InterruptKernel, RestartKernel, ShutdownKernel]);
+
+const INITIAL_NAMES_TIMEOUT: Duration = Duration::from_secs(1);
 
 #[derive(Clone)]
 pub struct RuntimeManagerGlobal(Model<RuntimeManager>);
@@ -74,13 +86,19 @@ pub fn init(fs: Arc<dyn Fs>, cx: &mut AppContext) {
     JupyterSettings::register(cx);
 
     observe_jupyter_settings_changes(fs.clone(), cx);
+    notebook::init(cx);
 
     cx.observe_new_views(
         |workspace: &mut Workspace, _: &mut ViewContext<Workspace>| {
-            workspace.register_action(run);
+            workspace
+                .register_action(run)
+                .register_action(interrupt_kernel)
+                .register_action(restart_kernel)
+                .register_action(shutdown_kernel);
         },
     )
     .detach();
+    variables_panel::init(cx);
 
     let settings = JupyterSettings::get_global(cx);
 
@@ -127,6 +145,18 @@ pub enum Kernel {
     RunningKernel(RunningKernel),
     StartingKernel(Shared<Task<()>>),
     FailedLaunch,
+    ShuttingDown,
+}
+
+impl Kernel {
+    pub fn status(&self) -> KernelStatus {
+        match self {
+            Kernel::RunningKernel(running_kernel) => running_kernel.status,
+            Kernel::StartingKernel(_) => KernelStatus::Starting,
+            Kernel::FailedLaunch => KernelStatus::Failed,
+            Kernel::ShuttingDown => KernelStatus::ShuttingDown,
+        }
+    }
 }
 
 // Per workspace
@@ -135,6 +165,7 @@ pub struct RuntimeManager {
     runtime_specifications: Vec<RuntimeSpecification>,
 
     instances: HashMap<EntityId, Kernel>,
+    displays: HashMap<EntityId, Model<Displays>>,
     editors: HashMap<WeakView<Editor>, EditorRuntimeState>,
     // todo!(): Next
     // To reduce the number of open tasks and channels we have, let's feed the response
@@ -163,6 +194,7 @@ impl RuntimeManager {
             fs,
             runtime_specifications: Default::default(),
             instances: Default::default(),
+            displays: Default::default(),
             editors: Default::default(),
             _execution_views_by_id: Default::default(),
         }
@@ -180,6 +212,9 @@ impl RuntimeManager {
                 return Task::ready(anyhow::Ok(running_kernel.request_tx.clone()));
             }
             Some(Kernel::StartingKernel(task)) => task.clone(),
+            Some(Kernel::ShuttingDown) => {
+                return Task::ready(Err(anyhow!("the kernel is shutting down")));
+            }
             Some(Kernel::FailedLaunch) | None => {
                 self.instances.remove(&entity_id);
 
@@ -190,14 +225,16 @@ impl RuntimeManager {
 
                         match running_kernel {
                             Ok(running_kernel) => {
-                                let _ = this.update(&mut cx, |this, _cx| {
+                                let _ = this.update(&mut cx, |this, cx| {
                                     this.instances
                                         .insert(entity_id, Kernel::RunningKernel(running_kernel));
+                                    cx.notify();
                                 });
                             }
                             Err(_err) => {
-                                let _ = this.update(&mut cx, |this, _cx| {
+                                let _ = this.update(&mut cx, |this, cx| {
                                     this.instances.insert(entity_id, Kernel::FailedLaunch);
+                                    cx.notify();
                                 });
                             }
                         }
@@ -206,6 +243,7 @@ impl RuntimeManager {
 
                 self.instances
                     .insert(entity_id, Kernel::StartingKernel(pending_kernel.clone()));
+                cx.notify();
 
                 pending_kernel
             }
@@ -256,11 +294,29 @@ impl RuntimeManager {
 
         let fs = self.fs.clone();
 
+        let (status_tx, mut status_rx) = mpsc::unbounded();
+        cx.spawn(|this, mut cx| async move {
+            while let Some(status) = status_rx.next().await {
+                let updated = this.update(&mut cx, |this, cx| {
+                    if let Some(Kernel::RunningKernel(running_kernel)) =
+                        this.instances.get_mut(&entity_id)
+                    {
+                        running_kernel.status = status;
+                        cx.notify();
+                    }
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
+
         cx.spawn(|_, cx| async move {
             let running_kernel =
-                RunningKernel::new(runtime_specification, entity_id, fs.clone(), cx);
+                RunningKernel::new(runtime_specification, entity_id, fs.clone(), status_tx, cx);
 
-            let running_kernel = running_kernel.await?;
+            let mut running_kernel = running_kernel.await?;
 
             let mut request_tx = running_kernel.request_tx.clone();
 
@@ -307,10 +363,150 @@ impl RuntimeManager {
                 }
             }
 
+            let initial_names = defined_names(
+                &running_kernel.request_tx,
+                &running_kernel.runtime.kernelspec.language,
+            );
+            if let Ok(Ok(names)) = timeout(INITIAL_NAMES_TIMEOUT, initial_names).await {
+                running_kernel.initial_names = names.into_iter().collect();
+            }
+
             anyhow::Ok(running_kernel)
         })
     }
 
+    pub fn kernel_status(&self, entity_id: EntityId) -> Option<KernelStatus> {
+        self.instances.get(&entity_id).map(Kernel::status)
+    }
+
+    fn running_kernel(&self, entity_id: EntityId) -> Result<&RunningKernel> {
+        match self.instances.get(&entity_id) {
+            Some(Kernel::RunningKernel(running_kernel)) => Ok(running_kernel),
+            _ => Err(anyhow!("no running kernel")),
+        }
+    }
+
+    /// The names that the running kernel defined before running any code.
+    pub fn initial_names(&self, entity_id: EntityId) -> Option<&HashSet<String>> {
+        self.running_kernel(entity_id)
+            .ok()
+            .map(|running_kernel| &running_kernel.initial_names)
+    }
+
+    pub fn interrupt_kernel(&self, entity_id: EntityId) -> Result<()> {
+        self.running_kernel(entity_id)?.interrupt()
+    }
+
+    pub fn shutdown_kernel(
+        &mut self,
+        entity_id: EntityId,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let running_kernel = match self.instances.remove(&entity_id) {
+            Some(Kernel::RunningKernel(running_kernel)) => running_kernel,
+            Some(kernel) => {
+                self.instances.insert(entity_id, kernel);
+                return Task::ready(Err(anyhow!("no running kernel")));
+            }
+            None => return Task::ready(Ok(())),
+        };
+
+        self.instances.insert(entity_id, Kernel::ShuttingDown);
+        self.displays.remove(&entity_id);
+        cx.notify();
+
+        cx.spawn(|this, mut cx| async move {
+            running_kernel.shutdown().await;
+            this.update(&mut cx, |this, cx| {
+                if let Some(Kernel::ShuttingDown) = this.instances.get(&entity_id) {
+                    this.instances.remove(&entity_id);
+                }
+                cx.notify();
+            })
+        })
+    }
+
+    /// Shuts down the running kernel and launches a new one for the same language.
+    pub fn restart_kernel(
+        &mut self,
+        entity_id: EntityId,
+        cx: &mut ModelContext<Self>,
+    ) -> Task<Result<()>> {
+        let language_name: Arc<str> = match self.running_kernel(entity_id) {
+            Ok(running_kernel) => running_kernel.runtime.kernelspec.language.as_str().into(),
+            Err(error) => return Task::ready(Err(error)),
+        };
+
+        let shutdown = self.shutdown_kernel(entity_id, cx);
+        cx.spawn(|this, mut cx| async move {
+            shutdown.await?;
+            this.update(&mut cx, |this, cx| {
+                this.get_or_launch_kernel(entity_id, language_name, cx)
+            })?
+            .await?;
+            anyhow::Ok(())
+        })
+    }
+
+    /// Asks the running kernel for the completions of the code at `cursor_pos`, which counts
+    /// unicode code points as the Jupyter protocol does.
+    pub fn complete(
+        &self,
+        entity_id: EntityId,
+        code: String,
+        cursor_pos: usize,
+    ) -> impl Future<Output = Result<CompleteReply>> {
+        let request_tx = self
+            .running_kernel(entity_id)
+            .map(|running_kernel| running_kernel.request_tx.clone());
+        async move {
+            request_reply(
+                &request_tx?,
+                complete_request(code, cursor_pos),
+                complete_reply,
+            )
+            .await
+        }
+    }
+
+    /// Asks the running kernel for the names defined in its global namespace.
+    pub fn defined_names(&self, entity_id: EntityId) -> impl Future<Output = Result<Vec<String>>> {
+        let kernel = self.running_kernel(entity_id).map(|running_kernel| {
+            (
+                running_kernel.request_tx.clone(),
+                running_kernel.runtime.kernelspec.language.clone(),
+            )
+        });
+        async move {
+            let (request_tx, language) = kernel?;
+            defined_names(&request_tx, &language).await
+        }
+    }
+
+    /// Asks the running kernel for information about the object at `cursor_pos`.
+    pub fn inspect(
+        &self,
+        entity_id: EntityId,
+        code: String,
+        cursor_pos: usize,
+    ) -> impl Future<Output = Result<InspectReply>> {
+        let request_tx = self
+            .running_kernel(entity_id)
+            .map(|running_kernel| running_kernel.request_tx.clone());
+        async move {
+            let request = runtimelib::InspectRequest {
+                code,
+                cursor_pos,
+                detail_level: Some(0),
+            };
+            request_reply(&request_tx?, request.into(), |message| match message {
+                JupyterMessageContent::InspectReply(reply) => Some(reply),
+                _ => None,
+            })
+            .await
+        }
+    }
+
     fn execute_code(
         &mut self,
         entity_id: EntityId,
@@ -344,6 +540,18 @@ impl RuntimeManager {
         }
     }
 
+    /// The outputs displayed with an id by the executions of the kernel of an editor.
+    pub fn displays(
+        &mut self,
+        entity_id: EntityId,
+        cx: &mut ModelContext<Self>,
+    ) -> Model<Displays> {
+        self.displays
+            .entry(entity_id)
+            .or_insert_with(|| cx.new_model(|_| Displays::default()))
+            .clone()
+    }
+
     pub fn global(cx: &AppContext) -> Option<Model<Self>> {
         cx.try_global::<RuntimeManagerGlobal>()
             .map(|runtime_manager| runtime_manager.0.clone())
@@ -360,6 +568,89 @@ impl RuntimeManager {
     }
 }
 
+fn complete_request(code: String, cursor_pos: usize) -> JupyterMessageContent {
+    runtimelib::CompleteRequest { code, cursor_pos }.into()
+}
+
+fn complete_reply(message: JupyterMessageContent) -> Option<CompleteReply> {
+    match message {
+        JupyterMessageContent::CompleteReply(reply) => Some(reply),
+        _ => None,
+    }
+}
+
+/// Lists the names defined in the global namespace of a kernel, by running code that prints them
+/// without being recorded in the history or making the kernel report itself busy.
+async fn defined_names(
+    request_tx: &UnboundedSender<Request>,
+    language: &str,
+) -> Result<Vec<String>> {
+    let code = match language {
+        "python" => "print(*globals(), sep='\\n')",
+        _ => {
+            return Err(anyhow!(
+                "listing the names of {language} kernels is not supported"
+            ))
+        }
+    };
+    let (tx, mut rx) = mpsc::unbounded();
+    request_tx
+        .unbounded_send(Request {
+            request: runtimelib::ExecuteRequest {
+                code: code.to_string(),
+                allow_stdin: false,
+                silent: true,
+                store_history: false,
+                stop_on_error: false,
+                ..Default::default()
+            }
+            .into(),
+            responses_rx: tx,
+        })
+        .context("Failed to send request")?;
+
+    let mut output = String::new();
+    while let Some(message) = rx.next().await {
+        match message {
+            JupyterMessageContent::StreamContent(stream)
+                if matches!(stream.name, runtimelib::Stdio::Stdout) =>
+            {
+                output.push_str(&stream.text);
+            }
+            JupyterMessageContent::Status(status)
+                if matches!(status.execution_state, runtimelib::ExecutionState::Idle) =>
+            {
+                return Ok(output.lines().map(str::to_string).collect());
+            }
+            _ => {}
+        }
+    }
+    Err(anyhow!("the kernel stopped before replying"))
+}
+
+/// Sends a request to a kernel, and waits for the response that `reply` picks out of the messages
+/// the kernel sends about it.
+async fn request_reply<T>(
+    request_tx: &UnboundedSender<Request>,
+    request: JupyterMessageContent,
+    reply: impl Fn(JupyterMessageContent) -> Option<T>,
+) -> Result<T> {
+    let (tx, mut rx) = mpsc::unbounded();
+    request_tx
+        .unbounded_send(Request {
+            request,
+            responses_rx: tx,
+        })
+        .context("Failed to send request")?;
+
+    while let Some(message) = rx.next().await {
+        if let Some(reply) = reply(message) {
+            return Ok(reply);
+        }
+    }
+    Err(anyhow!("the kernel stopped before replying"))
+}
+
 pub fn get_active_editor(
     workspace: &mut Workspace,
     cx: &mut ViewContext<Workspace>,
@@ -449,16 +740,23 @@ pub fn run(workspace: &mut Workspace, _: &Run, cx: &mut ViewContext<Workspace>)
 
     let entity_id = editor.entity_id();
 
-    let execution_view = cx.new_view(|cx| ExecutionView::new(cx));
+    let displays = runtime_manager.update(cx, |runtime_manager, cx| {
+        runtime_manager.displays(entity_id, cx)
+    });
+    let execution_view = cx.new_view(|cx| ExecutionView::new(displays, cx));
 
     // If any block overlaps with the new block, remove it
     // TODO: When inserting a new block, put it in order so that search is efficient
-    let blocks_to_remove = runtime_manager.update(cx, |runtime_manager, _cx| {
+    let (blocks_to_remove, newly_tracked) = runtime_manager.update(cx, |runtime_manager, _cx| {
         // Get the current `EditorRuntimeState` for this runtime_manager, inserting it if it doesn't exist
+        let mut newly_tracked = false;
         let editor_runtime_state = runtime_manager
             .editors
             .entry(editor.downgrade())
-            .or_insert_with(|| EditorRuntimeState { blocks: Vec::new() });
+            .or_insert_with(|| {
+                newly_tracked = true;
+                EditorRuntimeState { blocks: Vec::new() }
+            });
 
         let mut blocks_to_remove: HashSet<BlockId> = HashSet::default();
 
@@ -472,12 +770,17 @@ pub fn run(workspace: &mut Workspace, _: &Run, cx: &mut ViewContext<Workspace>)
             }
         });
 
-        blocks_to_remove
+        (blocks_to_remove, newly_tracked)
     });
 
-    let blocks_to_remove = blocks_to_remove.clone();
-
     let block_id = editor.update(cx, |editor, cx| {
+        // Offer the names known to the kernel alongside the completions the editor had, once per editor.
+        if newly_tracked {
+            let fallback = editor.take_completion_provider();
+            editor.set_completion_provider(Box::new(KernelCompletionProvider::new(
+                entity_id, fallback,
+            )));
+        }
         editor.remove_blocks(blocks_to_remove, None, cx);
         let block = BlockProperties {
             position: anchor_range.end,
@@ -487,7 +790,23 @@ pub fn run(workspace: &mut Workspace, _: &Run, cx: &mut ViewContext<Workspace>)
             disposition: BlockDisposition::Below,
         };
 
-        editor.insert_blocks([block], None, cx)[0]
+        let block_id = editor.insert_blocks([block], None, cx)[0];
+
+        // Outputs change size as messages arrive, and as JSON trees are expanded and collapsed.
+        cx.observe(&execution_view, move |editor, execution_view, cx| {
+            let mut replacements = HashMap::default();
+            replacements.insert(
+                block_id,
+                (
+                    Some(execution_view.num_lines(cx).saturating_add(1)),
+                    create_output_area_render(execution_view.clone()),
+                ),
+            );
+            editor.replace_blocks(replacements, None, cx);
+        })
+        .detach();
+
+        block_id
     });
 
     let receiver = runtime_manager.update(cx, |runtime_manager, cx| {
@@ -516,29 +835,74 @@ pub fn run(workspace: &mut Workspace, _: &Run, cx: &mut ViewContext<Workspace>)
         })?;
         let mut receiver = receiver.await?;
 
-        let execution_view = execution_view.clone();
         while let Some(content) = receiver.next().await {
             execution_view.update(&mut cx, |execution_view, cx| {
                 execution_view.push_message(&content, cx)
             })?;
-
-            editor.update(&mut cx, |editor, cx| {
-                let mut replacements = HashMap::default();
-                replacements.insert(
-                    block_id,
-                    (
-                        Some(execution_view.num_lines(cx).saturating_add(1)),
-                        create_output_area_render(execution_view.clone()),
-                    ),
-                );
-                editor.replace_blocks(replacements, None, cx);
-            })?;
         }
         anyhow::Ok(())
     })
     .detach_and_log_err(cx);
 }
 
+/// The entity whose kernel the kernel actions apply to: the active editor or notebook.
+fn active_kernel_entity(workspace: &Workspace, cx: &AppContext) -> Option<EntityId> {
+    workspace.active_item(cx).map(|item| item.item_id())
+}
+
+pub fn interrupt_kernel(
+    workspace: &mut Workspace,
+    _: &InterruptKernel,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let (Some(entity_id), Some(runtime_manager)) = (
+        active_kernel_entity(workspace, cx),
+        RuntimeManager::global(cx),
+    ) else {
+        return;
+    };
+    runtime_manager
+        .read(cx)
+        .interrupt_kernel(entity_id)
+        .log_err();
+}
+
+pub fn restart_kernel(
+    workspace: &mut Workspace,
+    _: &RestartKernel,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let (Some(entity_id), Some(runtime_manager)) = (
+        active_kernel_entity(workspace, cx),
+        RuntimeManager::global(cx),
+    ) else {
+        return;
+    };
+    runtime_manager
+        .update(cx, |runtime_manager, cx| {
+            runtime_manager.restart_kernel(entity_id, cx)
+        })
+        .detach_and_log_err(cx);
+}
+
+pub fn shutdown_kernel(
+    workspace: &mut Workspace,
+    _: &ShutdownKernel,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let (Some(entity_id), Some(runtime_manager)) = (
+        active_kernel_entity(workspace, cx),
+        RuntimeManager::global(cx),
+    ) else {
+        return;
+    };
+    runtime_manager
+        .update(cx, |runtime_manager, cx| {
+            runtime_manager.shutdown_kernel(entity_id, cx)
+        })
+        .detach_and_log_err(cx);
+}
+
 fn create_output_area_render(execution_view: View<ExecutionView>) -> RenderBlock {
     let render = move |cx: &mut BlockContext| {
         let execution_view = execution_view.clone();
@@ -1,15 +1,21 @@
 use anyhow::{Context as _, Result};
-use collections::HashMap;
+use async_dispatcher::timeout;
+use collections::{HashMap, HashSet};
 use futures::lock::Mutex;
 use futures::{channel::mpsc, SinkExt as _, StreamExt as _};
 use gpui::{AsyncAppContext, EntityId};
 use project::Fs;
-use runtimelib::{dirs, ConnectionInfo, JupyterKernelspec, JupyterMessage, JupyterMessageContent};
+use runtimelib::{
+    dirs, ConnectionInfo, ExecutionState, JupyterKernelspec, JupyterMessage, JupyterMessageContent,
+};
 use smol::{net::TcpListener, process::Command};
 use std::fmt::Debug;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
 use std::{path::PathBuf, sync::Arc};
 
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Debug)]
 pub struct Request {
     pub request: runtimelib::JupyterMessageContent,
@@ -75,13 +81,41 @@ async fn peek_ports(ip: IpAddr) -> anyhow::Result<[u16; 5]> {
     Ok(ports)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelStatus {
+    Starting,
+    /// The kernel is running and waiting for requests.
+    Idle,
+    /// The kernel is running and executing a request.
+    Busy,
+    ShuttingDown,
+    Failed,
+}
+
+impl KernelStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            KernelStatus::Starting => "Starting",
+            KernelStatus::Idle => "Idle",
+            KernelStatus::Busy => "Busy",
+            KernelStatus::ShuttingDown => "Shutting Down",
+            KernelStatus::Failed => "Failed",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct RunningKernel {
-    #[allow(unused)]
-    runtime: RuntimeSpecification,
-    #[allow(unused)]
+    pub runtime: RuntimeSpecification,
     process: smol::process::Child,
     pub request_tx: mpsc::UnboundedSender<Request>,
+    /// Sends requests on the control channel, which the kernel handles even while it's executing code.
+    control_tx: mpsc::UnboundedSender<Request>,
+    /// Either `Idle` or `Busy`, as reported by the status messages of the kernel.
+    pub status: KernelStatus,
+    /// The names the kernel defined before running any code, which tell the user's variables
+    /// apart from the builtins.
+    pub initial_names: HashSet<String>,
 }
 
 impl RunningKernel {
@@ -89,6 +123,7 @@ impl RunningKernel {
         runtime: RuntimeSpecification,
         entity_id: EntityId,
         fs: Arc<dyn Fs>,
+        status_tx: mpsc::UnboundedSender<KernelStatus>,
         cx: AsyncAppContext,
     ) -> anyhow::Result<Self> {
         let ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
@@ -122,6 +157,7 @@ impl RunningKernel {
 
         let mut iopub = connection_info.create_client_iopub_connection("").await?;
         let mut shell = connection_info.create_client_shell_connection().await?;
+        let mut control = connection_info.create_client_control_connection().await?;
 
         // Spawn a background task to handle incoming messages from the kernel as well
         // as outgoing messages to the kernel
@@ -129,16 +165,38 @@ impl RunningKernel {
         let child_messages: Arc<
             Mutex<HashMap<String, mpsc::UnboundedSender<JupyterMessageContent>>>,
         > = Default::default();
+        // The ids of the silent execute requests, which query the kernel instead of running the user's code.
+        let silent_requests: Arc<Mutex<HashSet<String>>> = Default::default();
 
         let (request_tx, mut request_rx) = mpsc::unbounded::<Request>();
 
         cx.background_executor()
             .spawn({
                 let child_messages = child_messages.clone();
+                let silent_requests = silent_requests.clone();
 
                 async move {
                     let child_messages = child_messages.clone();
                     while let Ok(message) = iopub.read().await {
+                        // Only executing code makes the kernel busy, as opposed to the
+                        // completion and inspection requests that run in the background.
+                        if let (JupyterMessageContent::Status(status), Some(parent_header)) =
+                            (&message.content, &message.parent_header)
+                        {
+                            let mut silent_requests = silent_requests.lock().await;
+                            if silent_requests.contains(&parent_header.msg_id) {
+                                if matches!(status.execution_state, ExecutionState::Idle) {
+                                    silent_requests.remove(&parent_header.msg_id);
+                                }
+                            } else if parent_header.msg_type == "execute_request" {
+                                let kernel_status = match status.execution_state {
+                                    ExecutionState::Busy => KernelStatus::Busy,
+                                    ExecutionState::Idle => KernelStatus::Idle,
+                                };
+                                status_tx.unbounded_send(kernel_status).ok();
+                            }
+                        }
+
                         if let Some(parent_header) = message.parent_header {
                             let child_messages = child_messages.lock().await;
 
@@ -164,9 +222,16 @@ impl RunningKernel {
                 async move {
                     while let Some(request) = request_rx.next().await {
                         let rx = request.responses_rx.clone();
+                        let silent = matches!(
+                            &request.request,
+                            JupyterMessageContent::ExecuteRequest(execute) if execute.silent
+                        );
 
                         let request: JupyterMessage = request.request.into();
                         let msg_id = request.header.msg_id.clone();
+                        if silent {
+                            silent_requests.lock().await.insert(msg_id.clone());
+                        }
 
                         let mut sender = rx.clone();
 
@@ -187,12 +252,86 @@ impl RunningKernel {
             })
             .detach();
 
+        let (control_tx, mut control_rx) = mpsc::unbounded::<Request>();
+
+        cx.background_executor()
+            .spawn(async move {
+                while let Some(request) = control_rx.next().await {
+                    let mut sender = request.responses_rx;
+                    let request: JupyterMessage = request.request.into();
+
+                    control.send(request).await?;
+
+                    let response = control.read().await?;
+
+                    // Nobody waits for the reply to an interrupt request.
+                    sender.send(response.content).await.ok();
+                }
+
+                anyhow::Ok(())
+            })
+            .detach();
+
         Ok(Self {
             runtime,
             process,
             request_tx,
+            control_tx,
+            status: KernelStatus::Idle,
+            initial_names: HashSet::default(),
         })
     }
+
+    /// Interrupts the code that the kernel is executing, with a request on the control channel
+    /// or a signal, depending on the `interrupt_mode` of its kernelspec.
+    pub fn interrupt(&self) -> Result<()> {
+        if self.runtime.kernelspec.interrupt_mode.as_deref() == Some("message") {
+            let (responses_tx, _) = mpsc::unbounded();
+            let request = Request {
+                request: runtimelib::InterruptRequest {}.into(),
+                responses_rx: responses_tx,
+            };
+            return self
+                .control_tx
+                .unbounded_send(request)
+                .map_err(|_| anyhow::anyhow!("the kernel is no longer running"));
+        }
+        self.interrupt_with_signal()
+    }
+
+    /// Interrupts the kernel the way Jupyter does for kernels with the default `signal` interrupt mode.
+    #[cfg(unix)]
+    fn interrupt_with_signal(&self) -> Result<()> {
+        let pid = self.process.id() as libc::pid_t;
+        if unsafe { libc::kill(pid, libc::SIGINT) } != 0 {
+            return Err(std::io::Error::last_os_error()).context("failed to interrupt the kernel");
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn interrupt_with_signal(&self) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "interrupting kernels is not supported on this platform"
+        ))
+    }
+
+    /// Asks the kernel to shut down on the control channel, and kills its process if it doesn't reply in time.
+    pub async fn shutdown(mut self) {
+        let (tx, mut rx) = mpsc::unbounded();
+        let request = Request {
+            request: runtimelib::ShutdownRequest { restart: false }.into(),
+            responses_rx: tx,
+        };
+        if self.control_tx.unbounded_send(request).is_ok() {
+            while let Ok(Some(message)) = timeout(SHUTDOWN_TIMEOUT, rx.next()).await {
+                if let JupyterMessageContent::ShutdownReply(_) = message {
+                    break;
+                }
+            }
+        }
+        self.process.kill().ok();
+    }
 }
 
 async fn read_kernelspec_at(
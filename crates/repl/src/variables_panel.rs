@@ -0,0 +1,529 @@
+use crate::{
+    runtime_settings::{JupyterSettings, RuntimesDockPosition},
+    InterruptKernel, KernelStatus, RestartKernel, RuntimeManager, RuntimeManagerGlobal,
+    ShutdownKernel,
+};
+use collections::BTreeSet;
+use gpui::{
+    actions, Action, AppContext, AsyncWindowContext, EntityId, EventEmitter, FocusHandle,
+    FocusableView, Subscription, Task, View, WeakView,
+};
+use project::Fs;
+use settings::Settings as _;
+use std::sync::Arc;
+use theme::ThemeSettings;
+use ui::{prelude::*, ListItem, Tooltip};
+use util::ResultExt as _;
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+actions!(variables_panel, [ToggleFocus, RefreshVariables]);
+
+/// Inspecting a variable is a round trip to the kernel, so only the first ones are listed.
+const MAX_VARIABLES: usize = 200;
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace.register_action(|workspace, _: &ToggleFocus, cx| {
+            workspace.toggle_panel_focus::<VariablesPanel>(cx);
+        });
+    })
+    .detach();
+}
+
+#[derive(Debug, PartialEq)]
+struct Variable {
+    name: String,
+    type_name: Option<String>,
+    preview: String,
+    /// Everything the kernel said about the variable.
+    details: String,
+}
+
+/// Lists the variables that the code run in the kernel of the active editor or notebook defined.
+pub struct VariablesPanel {
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    size: Option<Pixels>,
+    active_entity: Option<EntityId>,
+    kernel_status: Option<KernelStatus>,
+    variables: Vec<Variable>,
+    expanded_variable: Option<String>,
+    refresh_task: Task<Option<()>>,
+    _runtime_manager_subscription: Option<Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl VariablesPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        workspace.update(&mut cx, |workspace, cx| Self::new(workspace, cx))
+    }
+
+    pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let fs = workspace.app_state().fs.clone();
+        let active_entity = workspace.active_item(cx).map(|item| item.item_id());
+        let workspace_view = cx.view().clone();
+        cx.new_view(|cx| {
+            let subscriptions = vec![
+                cx.subscribe(&workspace_view, |this, workspace, event, cx| {
+                    if let workspace::Event::ActiveItemChanged = event {
+                        let active_entity = workspace
+                            .read(cx)
+                            .active_item(cx)
+                            .map(|item| item.item_id());
+                        this.set_active_entity(active_entity, cx);
+                    }
+                }),
+                cx.observe_global::<RuntimeManagerGlobal>(Self::observe_runtime_manager),
+            ];
+            let mut this = Self {
+                fs,
+                focus_handle: cx.focus_handle(),
+                size: None,
+                active_entity: None,
+                kernel_status: None,
+                variables: Vec::new(),
+                expanded_variable: None,
+                refresh_task: Task::ready(None),
+                _runtime_manager_subscription: None,
+                _subscriptions: subscriptions,
+            };
+            this.observe_runtime_manager(cx);
+            this.set_active_entity(active_entity, cx);
+            this
+        })
+    }
+
+    fn observe_runtime_manager(&mut self, cx: &mut ViewContext<Self>) {
+        self._runtime_manager_subscription = RuntimeManager::global(cx).map(|runtime_manager| {
+            cx.observe(&runtime_manager, |this, _, cx| this.kernel_changed(cx))
+        });
+        self.kernel_changed(cx);
+    }
+
+    fn set_active_entity(&mut self, active_entity: Option<EntityId>, cx: &mut ViewContext<Self>) {
+        if self.active_entity == active_entity {
+            return;
+        }
+        self.active_entity = active_entity;
+        self.kernel_status = None;
+        self.variables.clear();
+        self.expanded_variable = None;
+        self.kernel_changed(cx);
+    }
+
+    /// Lists the variables again whenever the kernel is done running code.
+    fn kernel_changed(&mut self, cx: &mut ViewContext<Self>) {
+        let kernel_status = self.active_entity.zip(RuntimeManager::global(cx)).and_then(
+            |(entity_id, runtime_manager)| runtime_manager.read(cx).kernel_status(entity_id),
+        );
+        if kernel_status == self.kernel_status {
+            return;
+        }
+        self.kernel_status = kernel_status;
+        match kernel_status {
+            Some(KernelStatus::Idle) => self.refresh(&RefreshVariables, cx),
+            Some(KernelStatus::Busy) => {}
+            _ => {
+                self.variables.clear();
+                self.expanded_variable = None;
+            }
+        }
+        cx.notify();
+    }
+
+    fn refresh(&mut self, _: &RefreshVariables, cx: &mut ViewContext<Self>) {
+        let (Some(entity_id), Some(runtime_manager)) =
+            (self.active_entity, RuntimeManager::global(cx))
+        else {
+            return;
+        };
+        let runtime_manager = runtime_manager.downgrade();
+
+        self.refresh_task = cx.spawn(|this, mut cx| async move {
+            let (names, initial_names) = runtime_manager
+                .update(&mut cx, |runtime_manager, _| {
+                    let initial_names = runtime_manager
+                        .initial_names(entity_id)
+                        .cloned()
+                        .unwrap_or_default();
+                    (runtime_manager.defined_names(entity_id), initial_names)
+                })
+                .ok()?;
+            let names = names
+                .await
+                .log_err()?
+                .into_iter()
+                .filter(|name| is_variable_name(name) && !initial_names.contains(name))
+                .collect::<BTreeSet<_>>();
+
+            let mut variables = Vec::new();
+            for name in names.into_iter().take(MAX_VARIABLES) {
+                let reply = runtime_manager
+                    .update(&mut cx, |runtime_manager, _| {
+                        runtime_manager.inspect(entity_id, name.clone(), name.chars().count())
+                    })
+                    .ok()?
+                    .await;
+                let Ok(reply) = reply else {
+                    continue;
+                };
+                if !reply.found {
+                    continue;
+                }
+                let text = serde_json::to_value(&reply.data)
+                    .ok()
+                    .and_then(|data| Some(data.get("text/plain")?.as_str()?.to_string()))
+                    .unwrap_or_default();
+                variables.push(parse_variable(name, &text));
+            }
+
+            this.update(&mut cx, |this, cx| {
+                if this.active_entity == Some(entity_id) {
+                    this.variables = variables;
+                    cx.notify();
+                }
+            })
+            .ok()
+        });
+    }
+
+    fn render_header(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let status = self
+            .kernel_status
+            .map_or("No Kernel", |kernel_status| kernel_status.label());
+        let running = matches!(
+            self.kernel_status,
+            Some(KernelStatus::Idle | KernelStatus::Busy)
+        );
+
+        h_flex()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(
+                Label::new(format!("Kernel: {status}"))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted),
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        IconButton::new("variables-refresh", IconName::ArrowCircle)
+                            .icon_size(IconSize::Small)
+                            .disabled(self.kernel_status != Some(KernelStatus::Idle))
+                            .tooltip(|cx| {
+                                Tooltip::for_action("Refresh Variables", &RefreshVariables, cx)
+                            })
+                            .on_click(
+                                cx.listener(|this, _, cx| this.refresh(&RefreshVariables, cx)),
+                            ),
+                    )
+                    .child(
+                        IconButton::new("variables-interrupt", IconName::Stop)
+                            .icon_size(IconSize::Small)
+                            .disabled(self.kernel_status != Some(KernelStatus::Busy))
+                            .tooltip(|cx| {
+                                Tooltip::for_action("Interrupt Kernel", &InterruptKernel, cx)
+                            })
+                            .on_click(cx.listener(|this, _, cx| this.interrupt_kernel(cx))),
+                    )
+                    .child(
+                        IconButton::new("variables-restart", IconName::RotateCw)
+                            .icon_size(IconSize::Small)
+                            .disabled(!running)
+                            .tooltip(|cx| Tooltip::for_action("Restart Kernel", &RestartKernel, cx))
+                            .on_click(cx.listener(|this, _, cx| this.restart_kernel(cx))),
+                    )
+                    .child(
+                        IconButton::new("variables-shutdown", IconName::Exit)
+                            .icon_size(IconSize::Small)
+                            .disabled(!running)
+                            .tooltip(|cx| {
+                                Tooltip::for_action("Shut Down Kernel", &ShutdownKernel, cx)
+                            })
+                            .on_click(cx.listener(|this, _, cx| this.shutdown_kernel(cx))),
+                    ),
+            )
+    }
+
+    // The kernel actions are registered on the workspace and apply to its active item, which
+    // loses focus when the panel is clicked, so the buttons act on the panel's entity directly.
+    fn interrupt_kernel(&mut self, cx: &mut ViewContext<Self>) {
+        let (Some(entity_id), Some(runtime_manager)) =
+            (self.active_entity, RuntimeManager::global(cx))
+        else {
+            return;
+        };
+        runtime_manager
+            .read(cx)
+            .interrupt_kernel(entity_id)
+            .log_err();
+    }
+
+    fn restart_kernel(&mut self, cx: &mut ViewContext<Self>) {
+        let (Some(entity_id), Some(runtime_manager)) =
+            (self.active_entity, RuntimeManager::global(cx))
+        else {
+            return;
+        };
+        runtime_manager
+            .update(cx, |runtime_manager, cx| {
+                runtime_manager.restart_kernel(entity_id, cx)
+            })
+            .detach_and_log_err(cx);
+    }
+
+    fn shutdown_kernel(&mut self, cx: &mut ViewContext<Self>) {
+        let (Some(entity_id), Some(runtime_manager)) =
+            (self.active_entity, RuntimeManager::global(cx))
+        else {
+            return;
+        };
+        runtime_manager
+            .update(cx, |runtime_manager, cx| {
+                runtime_manager.shutdown_kernel(entity_id, cx)
+            })
+            .detach_and_log_err(cx);
+    }
+
+    fn render_variable(
+        &self,
+        ix: usize,
+        variable: &Variable,
+        cx: &mut ViewContext<Self>,
+    ) -> AnyElement {
+        let expanded = self.expanded_variable.as_deref() == Some(variable.name.as_str());
+        let name = variable.name.clone();
+        let buffer_font = ThemeSettings::get_global(cx).buffer_font.family.clone();
+
+        v_flex()
+            .child(
+                ListItem::new(("variable", ix))
+                    .selected(expanded)
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new(variable.name.clone()).size(LabelSize::Small))
+                            .children(variable.type_name.clone().map(|type_name| {
+                                Label::new(type_name)
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted)
+                            })),
+                    )
+                    .end_slot(
+                        Label::new(variable.preview.clone())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .on_click(cx.listener(move |this, _, cx| {
+                        if this.expanded_variable.as_ref() == Some(&name) {
+                            this.expanded_variable = None;
+                        } else {
+                            this.expanded_variable = Some(name.clone());
+                        }
+                        cx.notify();
+                    })),
+            )
+            .when(expanded && !variable.details.is_empty(), |el| {
+                el.child(
+                    div().pl_6().pr_2().py_1().font_family(buffer_font).child(
+                        Label::new(variable.details.clone())
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                    ),
+                )
+            })
+            .into_any_element()
+    }
+}
+
+/// Whether the name is one the user would bind, as opposed to the private names starting with `_`
+/// and the ones of IPython's history like `_1`.
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().map_or(false, char::is_alphabetic)
+        && chars.all(|char| char.is_alphanumeric() || char == '_')
+}
+
+/// Reads the type and the value of a variable from the `text/plain` reply to an inspect request,
+/// in the format that IPython uses, falling back to its first line for other kernels.
+fn parse_variable(name: String, text: &str) -> Variable {
+    let details = strip_ansi_escapes(text).trim().to_string();
+    let field = |label: &str| {
+        details.lines().find_map(|line| {
+            let value = line.strip_prefix(label)?.strip_prefix(':')?.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        })
+    };
+    let type_name = field("Type");
+    let preview = field("String form")
+        .or_else(|| {
+            details
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
+
+    Variable {
+        name,
+        type_name,
+        preview,
+        details,
+    }
+}
+
+fn strip_ansi_escapes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        if char == '\x1b' {
+            // Skip the control sequence up to its final byte.
+            if chars.next() == Some('[') {
+                for char in chars.by_ref() {
+                    if ('@'..='~').contains(&char) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            stripped.push(char);
+        }
+    }
+    stripped
+}
+
+impl EventEmitter<PanelEvent> for VariablesPanel {}
+
+impl FocusableView for VariablesPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for VariablesPanel {
+    fn persistent_name() -> &'static str {
+        "Variables Panel"
+    }
+
+    fn position(&self, cx: &WindowContext) -> DockPosition {
+        match JupyterSettings::get_global(cx).dock {
+            RuntimesDockPosition::Left => DockPosition::Left,
+            RuntimesDockPosition::Right => DockPosition::Right,
+            RuntimesDockPosition::Bottom => DockPosition::Bottom,
+        }
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        settings::update_settings_file::<JupyterSettings>(self.fs.clone(), cx, move |settings| {
+            let dock = match position {
+                DockPosition::Left => RuntimesDockPosition::Left,
+                DockPosition::Right => RuntimesDockPosition::Right,
+                DockPosition::Bottom => RuntimesDockPosition::Bottom,
+            };
+            settings.dock = Some(dock);
+        });
+    }
+
+    fn size(&self, cx: &WindowContext) -> Pixels {
+        self.size.unwrap_or_else(|| match self.position(cx) {
+            DockPosition::Left | DockPosition::Right => px(320.),
+            DockPosition::Bottom => px(240.),
+        })
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.size = size;
+        cx.notify();
+    }
+
+    fn icon(&self, cx: &WindowContext) -> Option<IconName> {
+        JupyterSettings::get_global(cx)
+            .enabled
+            .then_some(IconName::Code)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Variables")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
+
+impl Render for VariablesPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let variables = self
+            .variables
+            .iter()
+            .enumerate()
+            .map(|(ix, variable)| self.render_variable(ix, variable, cx))
+            .collect::<Vec<_>>();
+        let message = match self.kernel_status {
+            None => Some("Run code in an editor or notebook to start a kernel."),
+            Some(KernelStatus::Idle | KernelStatus::Busy) if variables.is_empty() => {
+                Some("No variables defined.")
+            }
+            _ => None,
+        };
+
+        v_flex()
+            .key_context("VariablesPanel")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::refresh))
+            .size_full()
+            .child(self.render_header(cx))
+            .child(
+                v_flex()
+                    .id("variables")
+                    .p_1()
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .children(variables)
+                    .children(message.map(|message| {
+                        Label::new(message)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    })),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_variable() {
+        let text = "\x1b[0;31mType:\x1b[0m        int\n\x1b[0;31mString form:\x1b[0m 42\n\x1b[0;31mDocstring:\x1b[0m   int([x]) -> integer";
+        let variable = parse_variable("answer".into(), text);
+        assert_eq!(variable.type_name.as_deref(), Some("int"));
+        assert_eq!(variable.preview, "42");
+        assert!(variable.details.starts_with("Type:"));
+
+        let variable = parse_variable("xs".into(), "\n[1, 2, 3]\n");
+        assert_eq!(variable.type_name, None);
+        assert_eq!(variable.preview, "[1, 2, 3]");
+
+        assert!(is_variable_name("answer_2"));
+        assert!(!is_variable_name("_"));
+        assert!(!is_variable_name("_1"));
+        assert!(!is_variable_name("__name__"));
+    }
+}
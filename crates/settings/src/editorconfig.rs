@@ -0,0 +1,299 @@
+//! Support for [EditorConfig](https://editorconfig.org) files.
+
+use anyhow::Result;
+use globset::{GlobBuilder, GlobMatcher};
+use std::{num::NonZeroU32, path::Path};
+
+/// The name of EditorConfig files.
+pub const EDITORCONFIG_NAME: &str = ".editorconfig";
+
+/// A parsed `.editorconfig` file.
+#[derive(Debug, Clone)]
+pub struct Editorconfig {
+    /// Whether this file is the top-most one, i.e. files in parent
+    /// directories should not be consulted.
+    pub root: bool,
+    sections: Vec<EditorconfigSection>,
+}
+
+#[derive(Debug, Clone)]
+struct EditorconfigSection {
+    matcher: GlobMatcher,
+    properties: Vec<(String, String)>,
+}
+
+/// The EditorConfig properties supported by Zed, as they apply to a particular file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EditorconfigProperties {
+    pub indent_style: Option<IndentStyle>,
+    pub indent_size: Option<IndentSize>,
+    pub tab_width: Option<NonZeroU32>,
+    pub end_of_line: Option<EndOfLine>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+    pub max_line_length: Option<MaxLineLength>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentSize {
+    /// Indent by the width of a tab character, see [`EditorconfigProperties::tab_width`].
+    Tab,
+    Columns(NonZeroU32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndOfLine {
+    Lf,
+    Crlf,
+    Cr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxLineLength {
+    Off,
+    Columns(u32),
+}
+
+impl Editorconfig {
+    /// Parses the contents of an `.editorconfig` file, skipping malformed lines
+    /// the way other EditorConfig implementations do.
+    pub fn parse(content: &str) -> Self {
+        let mut root = false;
+        let mut sections = Vec::new();
+        // Properties of sections whose glob could not be parsed are skipped.
+        let mut current_section: Option<Option<EditorconfigSection>> = None;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(glob) = line.strip_prefix('[') {
+                let Some(glob) = glob.strip_suffix(']') else {
+                    continue;
+                };
+                if let Some(section) = current_section.take().flatten() {
+                    sections.push(section);
+                }
+                current_section = Some(
+                    section_matcher(glob)
+                        .map_err(|error| {
+                            log::warn!("ignoring .editorconfig section [{glob}]: {error}")
+                        })
+                        .ok()
+                        .map(|matcher| EditorconfigSection {
+                            matcher,
+                            properties: Vec::new(),
+                        }),
+                );
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim().to_lowercase();
+            match &mut current_section {
+                None => {
+                    if key == "root" {
+                        root = value == "true";
+                    }
+                }
+                Some(Some(section)) => section.properties.push((key, value)),
+                Some(None) => {}
+            }
+        }
+        if let Some(section) = current_section.flatten() {
+            sections.push(section);
+        }
+
+        Self { root, sections }
+    }
+
+    /// Applies the properties of all sections matching the given path, relative
+    /// to the directory containing this file.
+    pub fn apply(&self, relative_path: &Path, properties: &mut EditorconfigProperties) {
+        for section in &self.sections {
+            if !section.matcher.is_match(relative_path) {
+                continue;
+            }
+            for (key, value) in &section.properties {
+                properties.set(key, value);
+            }
+        }
+    }
+}
+
+/// Translates a section name into a glob, following the rules of the EditorConfig specification:
+/// globs without a slash match files in any subdirectory, other globs are relative to the
+/// directory of the `.editorconfig` file.
+fn section_matcher(glob: &str) -> Result<GlobMatcher> {
+    let glob = match glob.strip_prefix('/') {
+        Some(glob) => glob.to_string(),
+        None if glob.contains('/') => glob.to_string(),
+        None => format!("**/{glob}"),
+    };
+    Ok(GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()?
+        .compile_matcher())
+}
+
+impl EditorconfigProperties {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns the width of an indentation level, if specified.
+    pub fn indent_width(&self) -> Option<NonZeroU32> {
+        match self.indent_size {
+            Some(IndentSize::Columns(columns)) => Some(columns),
+            Some(IndentSize::Tab) | None => self.tab_width,
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        // Any property can be reset to its default with `unset`.
+        let unset = value == "unset";
+        match key {
+            "indent_style" => {
+                self.indent_style = match value {
+                    "tab" => Some(IndentStyle::Tab),
+                    "space" => Some(IndentStyle::Space),
+                    _ if unset => None,
+                    _ => return,
+                }
+            }
+            "indent_size" => {
+                self.indent_size = match value {
+                    "tab" => Some(IndentSize::Tab),
+                    _ if unset => None,
+                    _ => match value.parse() {
+                        Ok(columns) => Some(IndentSize::Columns(columns)),
+                        Err(_) => return,
+                    },
+                }
+            }
+            "tab_width" => {
+                self.tab_width = match value.parse() {
+                    Ok(width) => Some(width),
+                    Err(_) if unset => None,
+                    Err(_) => return,
+                }
+            }
+            "end_of_line" => {
+                self.end_of_line = match value {
+                    "lf" => Some(EndOfLine::Lf),
+                    "crlf" => Some(EndOfLine::Crlf),
+                    "cr" => Some(EndOfLine::Cr),
+                    _ if unset => None,
+                    _ => return,
+                }
+            }
+            "trim_trailing_whitespace" => {
+                self.trim_trailing_whitespace = match value.parse() {
+                    Ok(trim) => Some(trim),
+                    Err(_) if unset => None,
+                    Err(_) => return,
+                }
+            }
+            "insert_final_newline" => {
+                self.insert_final_newline = match value.parse() {
+                    Ok(insert) => Some(insert),
+                    Err(_) if unset => None,
+                    Err(_) => return,
+                }
+            }
+            "max_line_length" => {
+                self.max_line_length = match value {
+                    "off" => Some(MaxLineLength::Off),
+                    _ if unset => None,
+                    _ => match value.parse() {
+                        Ok(columns) => Some(MaxLineLength::Columns(columns)),
+                        Err(_) => return,
+                    },
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editorconfig_properties() {
+        let editorconfig = Editorconfig::parse(
+            r#"
+            # top-most EditorConfig file
+            root = true
+
+            [*]
+            end_of_line = lf
+            insert_final_newline = true
+            indent_style = space
+            indent_size = 4
+
+            [*.{js,ts}]
+            indent_size = 2
+            max_line_length = 100
+
+            [Makefile]
+            indent_style = tab
+            indent_size = tab
+            tab_width = 8
+
+            [/vendor/**]
+            insert_final_newline = unset
+            trim_trailing_whitespace = false
+
+            [[invalid]
+            indent_size = 3
+            "#,
+        );
+        assert!(editorconfig.root);
+
+        let properties_for = |path: &str| {
+            let mut properties = EditorconfigProperties::default();
+            editorconfig.apply(Path::new(path), &mut properties);
+            properties
+        };
+
+        let rust = properties_for("src/main.rs");
+        assert_eq!(rust.indent_style, Some(IndentStyle::Space));
+        assert_eq!(rust.indent_width(), NonZeroU32::new(4));
+        assert_eq!(rust.end_of_line, Some(EndOfLine::Lf));
+        assert_eq!(rust.insert_final_newline, Some(true));
+        assert_eq!(rust.max_line_length, None);
+
+        let typescript = properties_for("web/app/index.ts");
+        assert_eq!(typescript.indent_width(), NonZeroU32::new(2));
+        assert_eq!(
+            typescript.max_line_length,
+            Some(MaxLineLength::Columns(100))
+        );
+
+        let makefile = properties_for("build/Makefile");
+        assert_eq!(makefile.indent_style, Some(IndentStyle::Tab));
+        assert_eq!(makefile.indent_size, Some(IndentSize::Tab));
+        assert_eq!(makefile.indent_width(), NonZeroU32::new(8));
+
+        let vendored = properties_for("vendor/lib/lib.c");
+        assert_eq!(vendored.insert_final_newline, None);
+        assert_eq!(vendored.trim_trailing_whitespace, Some(false));
+        assert_eq!(
+            properties_for("src/vendor/lib.c").trim_trailing_whitespace,
+            None
+        );
+    }
+}
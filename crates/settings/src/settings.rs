@@ -1,3 +1,4 @@
+pub mod editorconfig;
 mod keymap_file;
 mod settings_file;
 mod settings_store;
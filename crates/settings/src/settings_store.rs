@@ -2,6 +2,7 @@ use anyhow::{anyhow, Context, Result};
 use collections::{btree_map, hash_map, BTreeMap, HashMap};
 use gpui::{AppContext, AsyncAppContext, BorrowAppContext, Global, UpdateGlobal};
 use lazy_static::lazy_static;
+use parking_lot::RwLock;
 use schemars::{gen::SchemaGenerator, schema::RootSchema, JsonSchema};
 use serde::{de::DeserializeOwned, Deserialize as _, Serialize};
 use smallvec::SmallVec;
@@ -15,6 +16,8 @@ use std::{
 };
 use util::{merge_non_null_json_value_into, RangeExt, ResultExt as _};
 
+use crate::editorconfig::{Editorconfig, EditorconfigProperties};
+
 /// A value that can be defined as a user setting.
 ///
 /// Settings can be loaded from a combination of multiple JSON files.
@@ -157,6 +160,14 @@ pub struct SettingsStore {
     raw_user_settings: serde_json::Value,
     raw_extension_settings: serde_json::Value,
     raw_local_settings: BTreeMap<(usize, Arc<Path>), serde_json::Value>,
+    raw_editorconfigs: BTreeMap<(usize, Arc<Path>), Editorconfig>,
+    /// The `.editorconfig` files of the directories above each worktree, nearest first, with the
+    /// path of the worktree's root relative to their directory.
+    parent_editorconfigs: HashMap<usize, Vec<(Arc<Path>, Editorconfig)>>,
+    /// The EditorConfig properties of each file they were requested for, until the `.editorconfig`
+    /// files of its worktree change.
+    editorconfig_properties:
+        RwLock<HashMap<(usize, Arc<Path>), Option<Arc<EditorconfigProperties>>>>,
     tab_size_callback: Option<(
         TypeId,
         Box<dyn Fn(&dyn Any) -> Option<usize> + Send + Sync + 'static>,
@@ -173,6 +184,9 @@ impl Default for SettingsStore {
             raw_user_settings: serde_json::json!({}),
             raw_extension_settings: serde_json::json!({}),
             raw_local_settings: Default::default(),
+            raw_editorconfigs: Default::default(),
+            parent_editorconfigs: Default::default(),
+            editorconfig_properties: Default::default(),
             tab_size_callback: Default::default(),
         }
     }
@@ -464,6 +478,134 @@ impl SettingsStore {
         Ok(())
     }
 
+    /// Add or remove the `.editorconfig` file of a directory in a worktree.
+    pub fn set_local_editorconfig(
+        &mut self,
+        root_id: usize,
+        directory_path: Arc<Path>,
+        editorconfig_content: Option<&str>,
+    ) {
+        match editorconfig_content {
+            Some(content) => {
+                self.raw_editorconfigs
+                    .insert((root_id, directory_path), Editorconfig::parse(content));
+            }
+            None => {
+                self.raw_editorconfigs.remove(&(root_id, directory_path));
+            }
+        }
+        self.clear_editorconfig_properties(root_id);
+    }
+
+    /// Sets the `.editorconfig` files of the directories above a worktree, nearest first, along
+    /// with the path of the worktree's root relative to their directory.
+    pub fn set_parent_editorconfigs(
+        &mut self,
+        root_id: usize,
+        editorconfigs: Vec<(Arc<Path>, Editorconfig)>,
+    ) {
+        if editorconfigs.is_empty() {
+            self.parent_editorconfigs.remove(&root_id);
+        } else {
+            self.parent_editorconfigs.insert(root_id, editorconfigs);
+        }
+        self.clear_editorconfig_properties(root_id);
+    }
+
+    /// Forgets the EditorConfig properties of files that were removed from a worktree.
+    pub fn remove_editorconfig_properties<'a>(
+        &mut self,
+        root_id: usize,
+        paths: impl IntoIterator<Item = &'a Arc<Path>>,
+    ) {
+        let properties = self.editorconfig_properties.get_mut();
+        for path in paths {
+            properties.remove(&(root_id, path.clone()));
+        }
+    }
+
+    fn clear_editorconfig_properties(&mut self, root_id: usize) {
+        self.editorconfig_properties
+            .get_mut()
+            .retain(|(properties_root_id, _), _| *properties_root_id != root_id);
+    }
+
+    /// Returns the EditorConfig properties for the file at the given path in a worktree,
+    /// combining the `.editorconfig` files of all of its ancestor directories.
+    ///
+    /// The properties are resolved once per file, until the `.editorconfig` files change.
+    pub fn editorconfig_properties(
+        &self,
+        root_id: usize,
+        path: &Arc<Path>,
+    ) -> Option<Arc<EditorconfigProperties>> {
+        if self.raw_editorconfigs.is_empty() && self.parent_editorconfigs.is_empty() {
+            return None;
+        }
+
+        let key = (root_id, path.clone());
+        if let Some(properties) = self.editorconfig_properties.read().get(&key) {
+            return properties.clone();
+        }
+        let properties = self
+            .resolve_editorconfig_properties(root_id, path)
+            .map(Arc::new);
+        self.editorconfig_properties
+            .write()
+            .insert(key, properties.clone());
+        properties
+    }
+
+    fn resolve_editorconfig_properties(
+        &self,
+        root_id: usize,
+        path: &Path,
+    ) -> Option<EditorconfigProperties> {
+        let mut editorconfigs = Vec::new();
+        let mut reached_root = false;
+        for directory in path.ancestors().skip(1) {
+            if let Some(editorconfig) = self
+                .raw_editorconfigs
+                .get(&(root_id, Arc::<Path>::from(directory)))
+            {
+                if let Ok(relative_path) = path.strip_prefix(directory) {
+                    editorconfigs.push((relative_path.to_path_buf(), editorconfig));
+                }
+                if editorconfig.root {
+                    reached_root = true;
+                    break;
+                }
+            }
+        }
+        // Like other EditorConfig implementations, keep looking above the worktree until a file
+        // marked as the root is found.
+        if !reached_root {
+            for (root_path, editorconfig) in self
+                .parent_editorconfigs
+                .get(&root_id)
+                .into_iter()
+                .flatten()
+            {
+                let relative_path = if path.as_os_str().is_empty() {
+                    root_path.to_path_buf()
+                } else {
+                    root_path.join(path)
+                };
+                editorconfigs.push((relative_path, editorconfig));
+                if editorconfig.root {
+                    break;
+                }
+            }
+        }
+
+        // Files closer to the given path take precedence.
+        let mut properties = EditorconfigProperties::default();
+        for (relative_path, editorconfig) in editorconfigs.into_iter().rev() {
+            editorconfig.apply(&relative_path, &mut properties);
+        }
+        (!properties.is_empty()).then_some(properties)
+    }
+
     pub fn set_extension_settings<T: Serialize>(
         &mut self,
         content: T,
@@ -482,10 +624,26 @@ impl SettingsStore {
     /// Add or remove a set of local settings via a JSON string.
     pub fn clear_local_settings(&mut self, root_id: usize, cx: &mut AppContext) -> Result<()> {
         self.raw_local_settings.retain(|k, _| k.0 != root_id);
+        self.raw_editorconfigs.retain(|k, _| k.0 != root_id);
+        self.parent_editorconfigs.remove(&root_id);
+        self.clear_editorconfig_properties(root_id);
         self.recompute_values(Some((root_id, "".as_ref())), cx)?;
         Ok(())
     }
 
+    /// Returns the raw contents of the local settings files that apply to the given path,
+    /// from the outermost directory to the innermost one.
+    pub fn local_settings_for_path<'a>(
+        &'a self,
+        root_id: usize,
+        path: &'a Path,
+    ) -> impl 'a + Iterator<Item = &'a serde_json::Value> {
+        self.raw_local_settings
+            .range((root_id, Path::new("").into())..(root_id + 1, Path::new("").into()))
+            .filter(move |((_, directory), _)| path.starts_with(directory))
+            .map(|(_, content)| content)
+    }
+
     pub fn local_settings(&self, root_id: usize) -> impl '_ + Iterator<Item = (Arc<Path>, String)> {
         self.raw_local_settings
             .range((root_id, Path::new("").into())..(root_id + 1, Path::new("").into()))
@@ -1189,6 +1347,61 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    fn test_editorconfig_properties_eviction(cx: &mut AppContext) {
+        let mut store = SettingsStore::default();
+        let a_path: Arc<Path> = Path::new("a.rs").into();
+        let b_path: Arc<Path> = Path::new("b.rs").into();
+        store.set_local_editorconfig(1, Path::new("").into(), Some("[*]\nindent_size = 2\n"));
+        store.set_local_editorconfig(2, Path::new("").into(), Some("[*]\nindent_size = 4\n"));
+        for root_id in [1, 2] {
+            store.editorconfig_properties(root_id, &a_path);
+            store.editorconfig_properties(root_id, &b_path);
+        }
+        assert_eq!(store.editorconfig_properties.read().len(), 4);
+
+        // Removed files are forgotten.
+        store.remove_editorconfig_properties(1, [&a_path]);
+        assert_eq!(
+            cached_editorconfig_paths(&store),
+            [
+                (1, b_path.clone()),
+                (2, a_path.clone()),
+                (2, b_path.clone())
+            ]
+        );
+
+        // Changing an `.editorconfig` file forgets the files of its worktree only.
+        store.set_local_editorconfig(1, Path::new("").into(), Some("[*]\nindent_size = 3\n"));
+        assert_eq!(
+            cached_editorconfig_paths(&store),
+            [(2, a_path.clone()), (2, b_path.clone())]
+        );
+        assert_eq!(
+            store
+                .editorconfig_properties(1, &a_path)
+                .and_then(|properties| properties.indent_size),
+            Some(crate::editorconfig::IndentSize::Columns(
+                3.try_into().unwrap()
+            ))
+        );
+
+        // Removing a worktree forgets all of its files.
+        store.clear_local_settings(2, cx).unwrap();
+        assert_eq!(cached_editorconfig_paths(&store), [(1, a_path.clone())]);
+    }
+
+    fn cached_editorconfig_paths(store: &SettingsStore) -> Vec<(usize, Arc<Path>)> {
+        let mut paths = store
+            .editorconfig_properties
+            .read()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
     #[gpui::test]
     fn test_setting_store_update(cx: &mut AppContext) {
         let mut store = SettingsStore::default();
@@ -0,0 +1,51 @@
+use collections::BTreeMap;
+use serde::Deserialize;
+
+/// The contents of a VS Code snippets file.
+///
+/// Each key is the snippet's name, mapping to its definition.
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub(crate) struct VsSnippetsFile {
+    pub(crate) snippets: BTreeMap<String, VsCodeSnippet>,
+}
+
+/// A single snippet in the VS Code snippet JSON format.
+#[derive(Deserialize)]
+pub(crate) struct VsCodeSnippet {
+    #[serde(default)]
+    pub(crate) prefix: Option<ListOrDirect>,
+    pub(crate) body: ListOrDirect,
+    #[serde(default)]
+    pub(crate) description: Option<ListOrDirect>,
+    /// A comma-separated list of language identifiers, only used by global `.code-snippets` files.
+    #[serde(default)]
+    pub(crate) scope: Option<String>,
+}
+
+/// VS Code allows most snippet fields to be either a single string or a list of strings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum ListOrDirect {
+    Single(String),
+    List(Vec<String>),
+}
+
+impl ListOrDirect {
+    /// Returns the contained strings as a list.
+    pub(crate) fn into_list(self) -> Vec<String> {
+        match self {
+            Self::Single(entry) => vec![entry],
+            Self::List(entries) => entries,
+        }
+    }
+
+    /// Joins the contained strings with newlines, which is how VS Code
+    /// interprets multi-line bodies and descriptions.
+    pub(crate) fn into_joined(self) -> String {
+        match self {
+            Self::Single(entry) => entry,
+            Self::List(entries) => entries.join("\n"),
+        }
+    }
+}
@@ -0,0 +1,331 @@
+mod format;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use collections::BTreeMap;
+use format::VsSnippetsFile;
+use fs::Fs;
+use futures::StreamExt;
+use gpui::{AppContext, Global};
+use parking_lot::RwLock;
+use util::ResultExt;
+
+/// Initializes the global [`SnippetRegistry`] and starts watching the user's
+/// snippets directory for changes.
+pub fn init(fs: Arc<dyn Fs>, cx: &mut AppContext) {
+    let registry = Arc::new(SnippetRegistry::default());
+    cx.set_global(GlobalSnippetRegistry(registry.clone()));
+
+    let snippets_dir = paths::snippets_dir().clone();
+    cx.background_executor()
+        .spawn(async move { registry.watch_dir(fs, &snippets_dir).await.log_err() })
+        .detach();
+}
+
+#[derive(Default)]
+struct GlobalSnippetRegistry(Arc<SnippetRegistry>);
+
+impl Global for GlobalSnippetRegistry {}
+
+/// A snippet defined by the user in one of their snippet files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserSnippet {
+    /// The name of the snippet, as it appears as a key in the snippet file.
+    pub name: String,
+    /// The words that trigger this snippet in the completion menu.
+    pub prefixes: Vec<String>,
+    /// The snippet body, in LSP snippet syntax.
+    pub body: String,
+    pub description: Option<String>,
+    /// The normalized names of the languages this snippet applies to,
+    /// or `None` if it applies to all languages.
+    scope: Option<Vec<String>>,
+}
+
+impl UserSnippet {
+    /// Returns whether this snippet should be offered in a buffer of the given language.
+    pub fn applies_to(&self, language_name: Option<&str>) -> bool {
+        match (&self.scope, language_name) {
+            (None, _) => true,
+            (Some(scope), Some(language_name)) => {
+                let language_name = normalize_language_name(language_name);
+                scope.iter().any(|name| *name == language_name)
+            }
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Stores the snippets loaded from the user's snippet files.
+///
+/// Snippets are read from VS Code-style snippet files:
+/// - `<language>.json` files contain snippets for a single language.
+/// - `*.code-snippets` files contain global snippets, optionally restricted
+///   to some languages with a `scope` field.
+#[derive(Default)]
+pub struct SnippetRegistry {
+    snippets_by_file: RwLock<BTreeMap<PathBuf, Vec<Arc<UserSnippet>>>>,
+}
+
+impl SnippetRegistry {
+    /// Returns the global [`SnippetRegistry`].
+    pub fn global(cx: &AppContext) -> Arc<Self> {
+        cx.global::<GlobalSnippetRegistry>().0.clone()
+    }
+
+    /// Returns the global [`SnippetRegistry`], if one has been initialized.
+    pub fn try_global(cx: &AppContext) -> Option<Arc<Self>> {
+        cx.try_global::<GlobalSnippetRegistry>()
+            .map(|registry| registry.0.clone())
+    }
+
+    /// Returns all snippets that apply to the given language.
+    pub fn snippets_for_language(&self, language_name: Option<&str>) -> Vec<Arc<UserSnippet>> {
+        self.snippets_by_file
+            .read()
+            .values()
+            .flatten()
+            .filter(|snippet| snippet.applies_to(language_name))
+            .cloned()
+            .collect()
+    }
+
+    /// Loads every snippet file in `dir` and reloads them whenever they change.
+    pub async fn watch_dir(&self, fs: Arc<dyn Fs>, dir: &Path) -> Result<()> {
+        if !fs.is_dir(dir).await {
+            fs.create_dir(dir)
+                .await
+                .with_context(|| format!("failed to create snippets dir at path {dir:?}"))?;
+        }
+
+        // Start watching before the initial load, so no change goes unnoticed.
+        let (mut events, _watcher) = fs.watch(dir, Duration::from_millis(100)).await;
+        self.load_dir(fs.as_ref(), dir).await?;
+
+        while let Some(paths) = events.next().await {
+            for path in paths {
+                self.reload_file(fs.as_ref(), &path).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads every snippet file in `dir`.
+    pub async fn load_dir(&self, fs: &dyn Fs, dir: &Path) -> Result<()> {
+        let mut paths = fs.read_dir(dir).await?;
+        while let Some(path) = paths.next().await {
+            if let Some(path) = path.log_err() {
+                self.reload_file(fs, &path).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reloads the snippets defined in the file at `path`, forgetting them
+    /// if the file no longer exists.
+    ///
+    /// If the file fails to parse, the previously loaded snippets are kept.
+    pub async fn reload_file(&self, fs: &dyn Fs, path: &Path) {
+        if snippet_file_kind(path).is_none() {
+            return;
+        }
+
+        if !fs.is_file(path).await {
+            self.snippets_by_file.write().remove(path);
+            return;
+        }
+
+        let snippets = async {
+            let contents = fs.load(path).await?;
+            parse_snippets_file(path, &contents)
+        }
+        .await
+        .with_context(|| format!("failed to load snippets from {path:?}"))
+        .log_err();
+        if let Some(snippets) = snippets {
+            self.snippets_by_file
+                .write()
+                .insert(path.to_path_buf(), snippets);
+        }
+    }
+}
+
+enum SnippetFileKind {
+    /// A `<language>.json` file.
+    Language(String),
+    /// A `*.code-snippets` file.
+    Global,
+}
+
+fn snippet_file_kind(path: &Path) -> Option<SnippetFileKind> {
+    match path.extension()?.to_str()? {
+        "json" => Some(SnippetFileKind::Language(normalize_language_name(
+            path.file_stem()?.to_str()?,
+        ))),
+        "code-snippets" => Some(SnippetFileKind::Global),
+        _ => None,
+    }
+}
+
+fn parse_snippets_file(path: &Path, contents: &str) -> Result<Vec<Arc<UserSnippet>>> {
+    let kind = snippet_file_kind(path).context("not a snippets file")?;
+    let file: VsSnippetsFile = serde_json_lenient::from_str(contents)?;
+    let mut snippets = Vec::new();
+    for (name, snippet) in file.snippets {
+        let body = snippet.body.into_joined();
+        if let Err(error) = snippet::Snippet::parse(&body) {
+            log::error!("invalid body for snippet {name:?} in {path:?}: {error:#}");
+            continue;
+        }
+
+        let prefixes = snippet
+            .prefix
+            .map(|prefix| prefix.into_list())
+            .unwrap_or_else(|| vec![name.clone()]);
+        let scope = match &kind {
+            SnippetFileKind::Language(language) => Some(vec![language.clone()]),
+            SnippetFileKind::Global => snippet.scope.map(|scope| {
+                scope
+                    .split(',')
+                    .map(|language| normalize_language_name(language.trim()))
+                    .filter(|language| !language.is_empty())
+                    .collect()
+            }),
+        };
+        snippets.push(Arc::new(UserSnippet {
+            name,
+            prefixes,
+            body,
+            description: snippet.description.map(|description| description.into_joined()),
+            scope,
+        }));
+    }
+    Ok(snippets)
+}
+
+/// Maps both Zed language names (e.g. "Shell Script") and VS Code language
+/// identifiers (e.g. "shellscript") to a common form.
+fn normalize_language_name(name: &str) -> String {
+    let name = name.to_lowercase().replace(' ', "");
+    match name.as_str() {
+        "cpp" => "c++".into(),
+        "csharp" => "c#".into(),
+        "typescriptreact" => "tsx".into(),
+        "javascriptreact" => "javascript".into(),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+
+    #[test]
+    fn test_parse_language_snippets_file() {
+        let snippets = parse_snippets_file(
+            Path::new("/snippets/rust.json"),
+            r#"{
+                // Comments are allowed, as in VS Code.
+                "Print debug": {
+                    "prefix": ["dbg", "debug"],
+                    "body": ["dbg!(${1:value});", "$0"],
+                    "description": "Print a value",
+                },
+                "Invalid": {
+                    "prefix": "invalid",
+                    "body": "${1:unterminated",
+                },
+                "Test module": {
+                    "body": "mod tests {\n\t$0\n}",
+                },
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].name, "Print debug");
+        assert_eq!(snippets[0].prefixes, vec!["dbg", "debug"]);
+        assert_eq!(snippets[0].body, "dbg!(${1:value});\n$0");
+        assert_eq!(snippets[0].description.as_deref(), Some("Print a value"));
+        assert_eq!(snippets[1].prefixes, vec!["Test module"]);
+
+        assert!(snippets[0].applies_to(Some("Rust")));
+        assert!(!snippets[0].applies_to(Some("Python")));
+        assert!(!snippets[0].applies_to(None));
+    }
+
+    #[test]
+    fn test_parse_global_snippets_file() {
+        let snippets = parse_snippets_file(
+            Path::new("/snippets/web.code-snippets"),
+            r#"{
+                "Todo": { "prefix": "todo", "body": "TODO($1): $0" },
+                "Log": {
+                    "scope": "javascript, typescriptreact",
+                    "prefix": "log",
+                    "body": "console.log($1);"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let log = snippets.iter().find(|s| s.name == "Log").unwrap();
+        assert!(log.applies_to(Some("JavaScript")));
+        assert!(log.applies_to(Some("TSX")));
+        assert!(!log.applies_to(Some("Rust")));
+
+        let todo = snippets.iter().find(|s| s.name == "Todo").unwrap();
+        assert!(todo.applies_to(Some("Rust")));
+        assert!(todo.applies_to(None));
+    }
+
+    #[gpui::test]
+    async fn test_reloading_snippet_files(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_file(
+            "/snippets/rust.json",
+            br#"{ "Main": { "prefix": "main", "body": "fn main() {\n\t$0\n}" } }"#.to_vec(),
+        )
+        .await;
+        fs.insert_file("/snippets/notes.txt", b"not a snippet file".to_vec())
+            .await;
+
+        let registry = SnippetRegistry::default();
+        registry
+            .load_dir(fs.as_ref(), Path::new("/snippets"))
+            .await
+            .unwrap();
+        let prefixes = |language| {
+            registry
+                .snippets_for_language(language)
+                .iter()
+                .flat_map(|snippet| snippet.prefixes.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(prefixes(Some("Rust")), vec!["main"]);
+        assert!(prefixes(Some("Python")).is_empty());
+
+        // A file that fails to parse keeps its previous snippets.
+        fs.insert_file("/snippets/rust.json", b"{ \"Main\": ".to_vec())
+            .await;
+        registry
+            .reload_file(fs.as_ref(), Path::new("/snippets/rust.json"))
+            .await;
+        assert_eq!(prefixes(Some("Rust")), vec!["main"]);
+
+        fs.remove_file(Path::new("/snippets/rust.json"), Default::default())
+            .await
+            .unwrap();
+        registry
+            .reload_file(fs.as_ref(), Path::new("/snippets/rust.json"))
+            .await;
+        assert!(prefixes(Some("Rust")).is_empty());
+    }
+}
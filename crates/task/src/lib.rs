@@ -1,6 +1,7 @@
 //! Baseline interface of Tasks in Zed: all tasks in Zed are intended to use those for implementing their own logic.
 #![deny(missing_docs)]
 
+pub mod problem_matcher;
 pub mod static_source;
 mod task_template;
 mod vscode_format;
@@ -12,7 +13,8 @@ use std::path::PathBuf;
 use std::str::FromStr;
 use std::{borrow::Cow, path::Path};
 
-pub use task_template::{RevealStrategy, TaskTemplate, TaskTemplates};
+pub use problem_matcher::ProblemMatcher;
+pub use task_template::{DependsOrder, RevealStrategy, TaskTemplate, TaskTemplates};
 pub use vscode_format::VsCodeTaskFile;
 
 /// Task identifier, unique within the application.
@@ -78,6 +80,15 @@ pub struct SpawnInTerminal {
     pub allow_concurrent_runs: bool,
     /// What to do with the terminal pane and tab, after the command was started.
     pub reveal: RevealStrategy,
+    /// Tasks to run before this one, grouped in stages: all tasks of a stage are spawned at once,
+    /// and the next stage only starts after every one of them finished successfully.
+    /// The command of the task itself may be empty, if it only groups its prerequisites.
+    pub prerequisites: Vec<Vec<SpawnInTerminal>>,
+    /// Problem matchers to find errors and warnings in the task output with, while it runs.
+    pub problem_matchers: Vec<ProblemMatcher>,
+    /// Whether the lines that the task prints are kept for the code that spawned it to read,
+    /// e.g. to parse the results of a test run.
+    pub collect_output: bool,
 }
 
 /// A final form of the [`TaskTemplate`], that got resolved with a particualar [`TaskContext`] and now is ready to spawn the actual task.
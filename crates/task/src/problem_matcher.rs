@@ -0,0 +1,556 @@
+//! Problem matchers scan the output of a task for errors and warnings, in a format compatible with Code's `problemMatcher`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A problem matcher to apply to the task output: either a name of a built-in matcher or a custom definition.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ProblemMatcher {
+    /// One of the built-in problem matchers: `$rustc`, `$tsc` or `$gcc`.
+    BuiltIn(String),
+    /// A custom problem matcher, defined the same way as in Code's `tasks.json`.
+    Custom(ProblemMatcherDefinition),
+}
+
+/// A custom problem matcher definition.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemMatcherDefinition {
+    /// A built-in problem matcher to use the patterns of, e.g. `$tsc`.
+    #[serde(default)]
+    pub base: Option<String>,
+    /// Name of the tool that produced the problems, displayed as the source of the diagnostics.
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Overrides the `owner` as the source of the diagnostics.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Severity of the problems that do not capture their own, defaults to `error`.
+    #[serde(default)]
+    pub severity: Option<ProblemSeverity>,
+    /// How to interpret the file paths from the task output:
+    /// * `"absolute"` — paths are absolute
+    /// * `"relative"` — paths are relative to the task's working directory
+    /// * `"autoDetect"` — either of the above, depending on the path (default)
+    /// * `["relative", "/some/path"]` — paths are relative to the path given
+    #[serde(default)]
+    pub file_location: Option<FileLocation>,
+    /// A pattern or a sequence of patterns, each matching a consecutive line of the output.
+    #[serde(default)]
+    pub pattern: Option<ProblemPatterns>,
+}
+
+/// Patterns of a problem matcher.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ProblemPatterns {
+    /// A single pattern, matching problems reported on one line.
+    Single(ProblemPattern),
+    /// Patterns matching consecutive lines of the output, that together report a problem.
+    Multiple(Vec<ProblemPattern>),
+}
+
+/// A regular expression to match a line of the task output against,
+/// along with the indices of its capture groups that contain the problem's properties.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemPattern {
+    /// The regular expression to match a line with.
+    pub regexp: String,
+    /// Capture group with the path of the file.
+    #[serde(default)]
+    pub file: Option<usize>,
+    /// Capture group with a `line`, `line,column` or `line,column,endLine,endColumn` location.
+    #[serde(default)]
+    pub location: Option<usize>,
+    /// Capture group with the 1-based line number.
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// Capture group with the 1-based column number.
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// Capture group with the 1-based line number the problem ends at.
+    #[serde(default)]
+    pub end_line: Option<usize>,
+    /// Capture group with the 1-based column number the problem ends at.
+    #[serde(default)]
+    pub end_column: Option<usize>,
+    /// Capture group with the severity, such as `error` or `warning`.
+    #[serde(default)]
+    pub severity: Option<usize>,
+    /// Capture group with the code of the problem.
+    #[serde(default)]
+    pub code: Option<usize>,
+    /// Capture group with the message.
+    #[serde(default)]
+    pub message: Option<usize>,
+    /// For the last pattern of several, whether it can match multiple consecutive lines, each reporting a problem.
+    #[serde(default)]
+    pub r#loop: bool,
+}
+
+/// Where the file paths matched in the output are relative to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum FileLocation {
+    /// Paths relative to the task's working directory, if they are not absolute.
+    Kind(FileLocationKind),
+    /// Paths relative to the directory given, if they are not absolute.
+    WithBase(FileLocationKind, String),
+}
+
+/// How to interpret the file paths matched in the output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum FileLocationKind {
+    /// Paths are absolute.
+    Absolute,
+    /// Paths are relative.
+    Relative,
+    /// Absolute paths are used as is, others are considered relative.
+    AutoDetect,
+}
+
+/// Severity of a problem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemSeverity {
+    /// An error.
+    Error,
+    /// A warning.
+    Warning,
+    /// Any other kind of message, such as a note or a hint.
+    Info,
+}
+
+impl ProblemSeverity {
+    fn parse(severity: &str) -> Self {
+        let severity = severity.to_lowercase();
+        if severity.starts_with('e') || severity == "fatal" {
+            Self::Error
+        } else if severity.starts_with('w') {
+            Self::Warning
+        } else {
+            Self::Info
+        }
+    }
+}
+
+/// A problem found in the output of a task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    /// The file the problem is in, absolute if the task's working directory is known.
+    pub path: PathBuf,
+    /// 1-based line the problem starts at.
+    pub line: u32,
+    /// 1-based column the problem starts at, if reported.
+    pub column: Option<u32>,
+    /// 1-based line the problem ends at, if reported.
+    pub end_line: Option<u32>,
+    /// 1-based column the problem ends at, if reported.
+    pub end_column: Option<u32>,
+    /// The problem's severity.
+    pub severity: ProblemSeverity,
+    /// The problem's code, if reported.
+    pub code: Option<String>,
+    /// The description of the problem.
+    pub message: String,
+    /// Name of the tool that reported the problem.
+    pub source: Option<String>,
+}
+
+/// A problem matcher with its patterns compiled, ready to be matched against a task output.
+#[derive(Clone, Debug)]
+pub struct ResolvedProblemMatcher {
+    source: Option<String>,
+    severity: ProblemSeverity,
+    file_location: FileLocationKind,
+    base_directory: Option<PathBuf>,
+    patterns: Vec<(Regex, ProblemPattern)>,
+}
+
+/// Matches the output of a running task line by line, as the task prints it.
+#[derive(Clone, Debug)]
+pub struct ProblemScanner {
+    matcher: ResolvedProblemMatcher,
+    cwd: Option<PathBuf>,
+    /// The index of the pattern to match the next line with, and the problem matched so far.
+    pending: Option<(usize, PartialProblem)>,
+}
+
+impl ProblemScanner {
+    /// Creates a scanner that resolves relative paths against the task's working directory, if it is given.
+    pub fn new(matcher: ResolvedProblemMatcher, cwd: Option<PathBuf>) -> Self {
+        Self {
+            matcher,
+            cwd,
+            pending: None,
+        }
+    }
+
+    /// Matches the next line of the output, returning the problem that it completes, if any.
+    pub fn scan_line(&mut self, line: &str) -> Option<Problem> {
+        self.matcher
+            .match_line(line, &mut self.pending, self.cwd.as_deref())
+    }
+}
+
+impl ProblemMatcher {
+    /// Compiles the patterns of the matcher, looking up the built-in matchers it refers to.
+    pub fn resolve(&self) -> anyhow::Result<ResolvedProblemMatcher> {
+        let definition = match self {
+            Self::BuiltIn(name) => built_in_problem_matcher(name)?,
+            Self::Custom(definition) => match &definition.base {
+                Some(base) => {
+                    let base = built_in_problem_matcher(base)?;
+                    ProblemMatcherDefinition {
+                        base: None,
+                        owner: definition.owner.clone().or(base.owner),
+                        source: definition.source.clone().or(base.source),
+                        severity: definition.severity.or(base.severity),
+                        file_location: definition.file_location.clone().or(base.file_location),
+                        pattern: definition.pattern.clone().or(base.pattern),
+                    }
+                }
+                None => definition.clone(),
+            },
+        };
+
+        let patterns = match definition.pattern {
+            Some(ProblemPatterns::Single(pattern)) => vec![pattern],
+            Some(ProblemPatterns::Multiple(patterns)) => patterns,
+            None => Vec::new(),
+        };
+        if patterns.is_empty() {
+            return Err(anyhow!("Problem matcher has no patterns"));
+        }
+        let patterns = patterns
+            .into_iter()
+            .map(|pattern| {
+                let regex = Regex::new(&pattern.regexp)
+                    .with_context(|| format!("Invalid problem pattern `{}`", pattern.regexp))?;
+                anyhow::Ok((regex, pattern))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let (file_location, base_directory) = match definition.file_location {
+            Some(FileLocation::Kind(kind)) => (kind, None),
+            Some(FileLocation::WithBase(kind, base)) => (kind, Some(PathBuf::from(base))),
+            None => (FileLocationKind::AutoDetect, None),
+        };
+        Ok(ResolvedProblemMatcher {
+            source: definition.source.or(definition.owner),
+            severity: definition.severity.unwrap_or(ProblemSeverity::Error),
+            file_location,
+            base_directory,
+            patterns,
+        })
+    }
+}
+
+impl ResolvedProblemMatcher {
+    /// Finds all problems reported in the task output.
+    /// Relative paths are resolved against the task's working directory, if it is given.
+    pub fn find_problems(&self, output: &str, cwd: Option<&Path>) -> Vec<Problem> {
+        let mut pending = None;
+        output
+            .lines()
+            .filter_map(|line| self.match_line(line, &mut pending, cwd))
+            .collect()
+    }
+
+    /// Matches the next line of the output, continuing the problem matched on the previous lines, if any.
+    fn match_line(
+        &self,
+        line: &str,
+        pending: &mut Option<(usize, PartialProblem)>,
+        cwd: Option<&Path>,
+    ) -> Option<Problem> {
+        let last_pattern_ix = self.patterns.len() - 1;
+        if let Some((pattern_ix, problem)) = pending.as_mut() {
+            let (regex, pattern) = &self.patterns[*pattern_ix];
+            if let Some(captures) = regex.captures(line) {
+                let mut problem = problem.clone();
+                problem.fill(pattern, &captures);
+                if *pattern_ix == last_pattern_ix {
+                    if !pattern.r#loop {
+                        *pending = None;
+                    }
+                    return self.finish_problem(problem, cwd);
+                }
+                *pending = Some((*pattern_ix + 1, problem));
+                return None;
+            }
+            *pending = None;
+        }
+
+        let (regex, pattern) = &self.patterns[0];
+        let captures = regex.captures(line)?;
+        let mut problem = PartialProblem::default();
+        problem.fill(pattern, &captures);
+        if last_pattern_ix == 0 {
+            self.finish_problem(problem, cwd)
+        } else {
+            *pending = Some((1, problem));
+            None
+        }
+    }
+
+    fn finish_problem(&self, problem: PartialProblem, cwd: Option<&Path>) -> Option<Problem> {
+        let path = problem.file?;
+        let path = Path::new(path.trim());
+        let path = match self.file_location {
+            FileLocationKind::Absolute => path.to_path_buf(),
+            FileLocationKind::AutoDetect if path.is_absolute() => path.to_path_buf(),
+            FileLocationKind::Relative | FileLocationKind::AutoDetect => {
+                let base_directory = match (&self.base_directory, cwd) {
+                    (Some(base_directory), Some(cwd)) => Some(cwd.join(base_directory)),
+                    (Some(base_directory), None) => Some(base_directory.clone()),
+                    (None, cwd) => cwd.map(Path::to_path_buf),
+                };
+                match base_directory {
+                    Some(base_directory) => base_directory.join(path),
+                    None => path.to_path_buf(),
+                }
+            }
+        };
+        Some(Problem {
+            path,
+            line: problem.line?,
+            column: problem.column,
+            end_line: problem.end_line,
+            end_column: problem.end_column,
+            severity: problem.severity.unwrap_or(self.severity),
+            code: problem.code,
+            message: problem.message?,
+            source: self.source.clone(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct PartialProblem {
+    file: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+    severity: Option<ProblemSeverity>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+impl PartialProblem {
+    fn fill(&mut self, pattern: &ProblemPattern, captures: &Captures) {
+        let group = |ix: Option<usize>| {
+            let capture = captures.get(ix?)?.as_str();
+            (!capture.is_empty()).then_some(capture)
+        };
+        let number = |ix: Option<usize>| group(ix)?.parse::<u32>().ok();
+
+        if let Some(file) = group(pattern.file) {
+            self.file = Some(file.to_string());
+        }
+        if let Some(location) = group(pattern.location) {
+            let mut numbers = location
+                .split(',')
+                .map(|number| number.trim().parse::<u32>().ok());
+            self.line = numbers.next().flatten().or(self.line);
+            self.column = numbers.next().flatten().or(self.column);
+            self.end_line = numbers.next().flatten().or(self.end_line);
+            self.end_column = numbers.next().flatten().or(self.end_column);
+        }
+        self.line = number(pattern.line).or(self.line);
+        self.column = number(pattern.column).or(self.column);
+        self.end_line = number(pattern.end_line).or(self.end_line);
+        self.end_column = number(pattern.end_column).or(self.end_column);
+        if let Some(severity) = group(pattern.severity) {
+            self.severity = Some(ProblemSeverity::parse(severity));
+        }
+        if let Some(code) = group(pattern.code) {
+            self.code = Some(code.to_string());
+        }
+        if let Some(message) = group(pattern.message) {
+            self.message = Some(message.to_string());
+        }
+    }
+}
+
+fn built_in_problem_matcher(name: &str) -> anyhow::Result<ProblemMatcherDefinition> {
+    let (owner, patterns) = match name {
+        "$rustc" => (
+            "rustc",
+            vec![
+                ProblemPattern {
+                    regexp: r"^(warning|error)(?:\[(\w+)\])?: (.*)$".to_string(),
+                    severity: Some(1),
+                    code: Some(2),
+                    message: Some(3),
+                    ..ProblemPattern::default()
+                },
+                ProblemPattern {
+                    regexp: r"^\s*--> (.*?):(\d+):(\d+)$".to_string(),
+                    file: Some(1),
+                    line: Some(2),
+                    column: Some(3),
+                    ..ProblemPattern::default()
+                },
+            ],
+        ),
+        "$tsc" | "$tsc-watch" => (
+            "tsc",
+            vec![ProblemPattern {
+                regexp: r"^([^\s].*)[\(:](\d+)[,:](\d+)(?:\):\s+|\s+-\s+)(error|warning|info)\s+TS(\d+)\s*:\s*(.*)$"
+                    .to_string(),
+                file: Some(1),
+                line: Some(2),
+                column: Some(3),
+                severity: Some(4),
+                code: Some(5),
+                message: Some(6),
+                ..ProblemPattern::default()
+            }],
+        ),
+        "$gcc" => (
+            "gcc",
+            vec![ProblemPattern {
+                regexp: r"^(.*?):(\d+):(\d*):?\s+(?:fatal\s+)?(warning|error):\s+(.*)$".to_string(),
+                file: Some(1),
+                line: Some(2),
+                column: Some(3),
+                severity: Some(4),
+                message: Some(5),
+                ..ProblemPattern::default()
+            }],
+        ),
+        _ => return Err(anyhow!("Unknown problem matcher `{name}`")),
+    };
+    Ok(ProblemMatcherDefinition {
+        owner: Some(owner.to_string()),
+        pattern: Some(ProblemPatterns::Multiple(patterns)),
+        ..ProblemMatcherDefinition::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rustc_problem_matcher() {
+        let output = "\
+   Compiling crate v0.1.0 (/project)
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:13
+  |
+2 |     let y = x + 1;
+  |             ^ not found in this scope
+
+warning: unused variable: `y`
+ --> /project/src/lib.rs:4:9
+  |
+warning: `crate` (bin) generated 1 warning
+error: could not compile `crate` (bin) due to 1 previous error
+";
+        let problems = ProblemMatcher::BuiltIn("$rustc".to_string())
+            .resolve()
+            .unwrap()
+            .find_problems(output, Some(Path::new("/project")));
+        assert_eq!(
+            problems,
+            vec![
+                Problem {
+                    path: PathBuf::from("/project/src/main.rs"),
+                    line: 2,
+                    column: Some(13),
+                    end_line: None,
+                    end_column: None,
+                    severity: ProblemSeverity::Error,
+                    code: Some("E0425".to_string()),
+                    message: "cannot find value `x` in this scope".to_string(),
+                    source: Some("rustc".to_string()),
+                },
+                Problem {
+                    path: PathBuf::from("/project/src/lib.rs"),
+                    line: 4,
+                    column: Some(9),
+                    end_line: None,
+                    end_column: None,
+                    severity: ProblemSeverity::Warning,
+                    code: None,
+                    message: "unused variable: `y`".to_string(),
+                    source: Some("rustc".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_problem_matcher() {
+        let matcher: ProblemMatcher = serde_json_lenient::from_str(
+            r#"{
+                "owner": "lint",
+                "severity": "warning",
+                "fileLocation": ["relative", "web"],
+                "pattern": {
+                    "regexp": "^(.+)\\((\\d+,\\d+)\\): (.*)$",
+                    "file": 1,
+                    "location": 2,
+                    "message": 3
+                }
+            }"#,
+        )
+        .unwrap();
+        let problems = matcher.resolve().unwrap().find_problems(
+            "src/a.ts(3,7): missing semicolon\nok\n",
+            Some(Path::new("/p")),
+        );
+        assert_eq!(
+            problems,
+            vec![Problem {
+                path: PathBuf::from("/p/web/src/a.ts"),
+                line: 3,
+                column: Some(7),
+                end_line: None,
+                end_column: None,
+                severity: ProblemSeverity::Warning,
+                code: None,
+                message: "missing semicolon".to_string(),
+                source: Some("lint".to_string()),
+            }]
+        );
+
+        let extended_matcher: ProblemMatcher =
+            serde_json_lenient::from_str(r#"{ "base": "$tsc", "fileLocation": "absolute" }"#)
+                .unwrap();
+        let problems = extended_matcher.resolve().unwrap().find_problems(
+            "/p/src/a.ts(1,2): error TS2304: Cannot find name 'b'.",
+            Some(Path::new("/elsewhere")),
+        );
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].path, PathBuf::from("/p/src/a.ts"));
+        assert_eq!(problems[0].code.as_deref(), Some("2304"));
+
+        assert!(ProblemMatcher::BuiltIn("$unknown".to_string())
+            .resolve()
+            .is_err());
+    }
+
+    #[test]
+    fn test_problem_scanner() {
+        let matcher = ProblemMatcher::BuiltIn("$rustc".to_string())
+            .resolve()
+            .unwrap();
+        let mut scanner = ProblemScanner::new(matcher, Some(PathBuf::from("/project")));
+        // Problems spanning several lines are found when their last line is printed.
+        assert_eq!(scanner.scan_line("error: expected `;`"), None);
+        let problem = scanner.scan_line(" --> src/main.rs:1:5").unwrap();
+        assert_eq!(problem.path, PathBuf::from("/project/src/main.rs"));
+        assert_eq!(problem.message, "expected `;`");
+        assert_eq!(scanner.scan_line(" --> src/main.rs:1:5"), None);
+    }
+}
@@ -8,6 +8,7 @@ use sha2::{Digest, Sha256};
 use util::{truncate_and_remove_front, ResultExt};
 
 use crate::{
+    problem_matcher::{FileLocation, ProblemMatcher},
     ResolvedTask, SpawnInTerminal, TaskContext, TaskId, TerminalWorkDir, VariableName,
     ZED_VARIABLE_NAME_PREFIX,
 };
@@ -23,7 +24,8 @@ use crate::{
 pub struct TaskTemplate {
     /// Human readable name of the task to display in the UI.
     pub label: String,
-    /// Executable command to spawn.
+    /// Executable command to spawn, may be omitted if the task only groups its `depends_on` tasks.
+    #[serde(default)]
     pub command: String,
     /// Arguments to the command.
     #[serde(default)]
@@ -49,6 +51,31 @@ pub struct TaskTemplate {
     /// Represents the tags which this template attaches to. Adding this removes this task from other UI.
     #[serde(default)]
     pub tags: Vec<String>,
+    /// Labels of the tasks that have to finish successfully before this one is spawned.
+    /// A task that only has dependencies and no command may be used to group other tasks.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// How to run the tasks from `depends_on`:
+    /// * `sequence` — one after another, in the order they are listed (default)
+    /// * `parallel` — all at once
+    #[serde(default)]
+    pub depends_order: DependsOrder,
+    /// Problem matchers to turn the task output into diagnostics with, as the task prints it.
+    /// Each one is either a name of a built-in matcher (`$rustc`, `$tsc` or `$gcc`), or a definition in the format of Code's `problemMatcher`.
+    /// Diagnostics of a task are kept until it is spawned again.
+    #[serde(default)]
+    pub problem_matchers: Vec<ProblemMatcher>,
+}
+
+/// How to run the dependencies of a task.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DependsOrder {
+    /// Run the dependencies one after another, stopping at the first one that fails.
+    #[default]
+    Sequence,
+    /// Run all dependencies at once.
+    Parallel,
 }
 
 /// What to do with the terminal pane and tab, after the command was started.
@@ -86,7 +113,9 @@ impl TaskTemplate {
     /// Every [`ResolvedTask`] gets a [`TaskId`], based on the `id_base` (to avoid collision with various task sources),
     /// and hashes of its template and [`TaskContext`], see [`ResolvedTask`] fields' documentation for more details.
     pub fn resolve_task(&self, id_base: &str, cx: &TaskContext) -> Option<ResolvedTask> {
-        if self.label.trim().is_empty() || self.command.trim().is_empty() {
+        if self.label.trim().is_empty()
+            || (self.command.trim().is_empty() && self.depends_on.is_empty())
+        {
             return None;
         }
 
@@ -169,6 +198,24 @@ impl TaskTemplate {
             &variable_names,
             &mut substituted_variables,
         )?;
+        let problem_matchers = self
+            .problem_matchers
+            .iter()
+            .map(|problem_matcher| {
+                let mut problem_matcher = problem_matcher.clone();
+                if let ProblemMatcher::Custom(definition) = &mut problem_matcher {
+                    if let Some(FileLocation::WithBase(_, base)) = &mut definition.file_location {
+                        *base = substitute_all_template_variables_in_str(
+                            base,
+                            &task_variables,
+                            &variable_names,
+                            &mut substituted_variables,
+                        )?;
+                    }
+                }
+                Some(problem_matcher)
+            })
+            .collect::<Option<Vec<_>>>()?;
         env.extend(task_variables.into_iter().map(|(k, v)| (k, v.to_owned())));
         Some(ResolvedTask {
             id: id.clone(),
@@ -194,6 +241,9 @@ impl TaskTemplate {
                 use_new_terminal: self.use_new_terminal,
                 allow_concurrent_runs: self.allow_concurrent_runs,
                 reveal: self.reveal,
+                prerequisites: Vec::new(),
+                problem_matchers,
+                collect_output: false,
             }),
         })
     }
@@ -342,6 +392,18 @@ mod tests {
                 "should not resolve task with blank label and/or command: {task_with_blank_property:?}"
             );
         }
+
+        let task_with_dependencies_only = TaskTemplate {
+            label: "test_label".to_string(),
+            depends_on: vec!["other_label".to_string()],
+            ..TaskTemplate::default()
+        };
+        let resolved = task_with_dependencies_only
+            .resolve_task(TEST_ID_BASE, &TaskContext::default())
+            .and_then(|resolved_task| resolved_task.resolved)
+            .expect("should resolve task that only groups its dependencies");
+        assert_eq!(resolved.command, "");
+        assert!(resolved.prerequisites.is_empty());
     }
 
     #[test]
@@ -1,9 +1,12 @@
-use anyhow::bail;
+use anyhow::{bail, Context};
 use collections::HashMap;
 use serde::Deserialize;
 use util::ResultExt;
 
-use crate::{TaskTemplate, TaskTemplates, VariableName};
+use crate::{
+    problem_matcher::{FileLocation, ProblemMatcher},
+    DependsOrder, TaskTemplate, TaskTemplates, VariableName,
+};
 
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -24,6 +27,22 @@ struct VsCodeTaskDefinition {
     options: Option<TaskOptions>,
 }
 
+/// Code allows to list either a single task label or several of them in `dependsOn`.
+#[derive(Clone, Deserialize, PartialEq, Debug)]
+#[serde(untagged)]
+enum DependsOn {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+/// Code allows to specify either a single problem matcher or several of them in `problemMatcher`.
+#[derive(Clone, Deserialize, PartialEq, Debug)]
+#[serde(untagged)]
+enum ProblemMatchers {
+    Single(ProblemMatcher),
+    Multiple(Vec<ProblemMatcher>),
+}
+
 #[derive(Clone, Deserialize, PartialEq, Debug)]
 #[serde(tag = "type")]
 #[serde(rename_all = "camelCase")]
@@ -84,16 +103,53 @@ impl EnvVariableReplacer {
 }
 
 impl VsCodeTaskDefinition {
-    fn to_zed_format(self, replacer: &EnvVariableReplacer) -> anyhow::Result<TaskTemplate> {
-        if self.other_attributes.contains_key("dependsOn") {
-            bail!("Encountered unsupported `dependsOn` key during deserialization");
+    fn to_zed_format(mut self, replacer: &EnvVariableReplacer) -> anyhow::Result<TaskTemplate> {
+        let depends_on = match self.other_attributes.remove("dependsOn") {
+            Some(depends_on) => match serde_json_lenient::from_value(depends_on)
+                .context("Failed to deserialize `dependsOn` key")?
+            {
+                DependsOn::Single(label) => vec![label],
+                DependsOn::Multiple(labels) => labels,
+            },
+            None => Vec::new(),
+        };
+        // Unlike Zed, Code runs the dependencies in parallel by default.
+        let depends_order = match self
+            .other_attributes
+            .get("dependsOrder")
+            .and_then(|order| order.as_str())
+        {
+            _ if depends_on.is_empty() => DependsOrder::default(),
+            Some("sequence") => DependsOrder::Sequence,
+            _ => DependsOrder::Parallel,
+        };
+        let problem_matchers = match self.other_attributes.remove("problemMatcher") {
+            Some(problem_matchers) => match serde_json_lenient::from_value(problem_matchers)
+                .context("Failed to deserialize `problemMatcher` key")?
+            {
+                ProblemMatchers::Single(problem_matcher) => vec![problem_matcher],
+                ProblemMatchers::Multiple(problem_matchers) => problem_matchers,
+            },
+            None => Vec::new(),
         }
-        // `type` might not be set in e.g. tasks that use `dependsOn`; we still want to deserialize the whole object though (hence command is an Option),
-        // as that way we can provide more specific description of why deserialization failed.
-        // E.g. if the command is missing due to `dependsOn` presence, we can check other_attributes first before doing this (and provide nice error message)
-        // catch-all if on value.command presence.
-        let Some(command) = self.command else {
-            bail!("Missing `type` field in task");
+        .into_iter()
+        .map(|mut problem_matcher| {
+            if let ProblemMatcher::Custom(definition) = &mut problem_matcher {
+                if let Some(FileLocation::WithBase(_, base)) = &mut definition.file_location {
+                    *base = replacer.replace(base);
+                }
+            }
+            problem_matcher
+        })
+        .collect();
+        // `type` might not be set in tasks that only use `dependsOn` to group other tasks; we still want to deserialize the whole object though (hence command is an Option).
+        let command = match self.command {
+            Some(command) => command,
+            None if !depends_on.is_empty() => Command::Shell {
+                command: String::new(),
+                args: Vec::new(),
+            },
+            None => bail!("Missing `type` field in task"),
         };
 
         let (command, args) = match command {
@@ -108,6 +164,9 @@ impl VsCodeTaskDefinition {
             label: self.label,
             command,
             args,
+            depends_on,
+            depends_order,
+            problem_matchers,
             ..Default::default()
         };
         if let Some(options) = self.options {
@@ -154,8 +213,11 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::{
+        problem_matcher::{
+            FileLocation, FileLocationKind, ProblemMatcher, ProblemMatcherDefinition,
+        },
         vscode_format::{Command, VsCodeTaskDefinition},
-        TaskTemplate, TaskTemplates, VsCodeTaskFile,
+        DependsOrder, TaskTemplate, TaskTemplates, VsCodeTaskFile,
     };
 
     use super::EnvVariableReplacer;
@@ -257,6 +319,7 @@ mod tests {
                 label: "gulp: tests".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:tests:notypecheck".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
@@ -268,18 +331,21 @@ mod tests {
                     "${ZED_WORKTREE_ROOT}/src".to_string(),
                     "--watch".to_string(),
                 ],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc-watch".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "npm: build:compiler".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:compiler".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "npm: build:tests".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build:tests:notypecheck".to_string()],
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$tsc".to_string())],
                 ..Default::default()
             },
         ];
@@ -360,28 +426,71 @@ mod tests {
                 label: "Build Extension in Background".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "watch".to_string()],
+                problem_matchers: vec![ProblemMatcher::Custom(ProblemMatcherDefinition {
+                    base: Some("$tsc-watch".to_string()),
+                    file_location: Some(FileLocation::WithBase(
+                        FileLocationKind::Relative,
+                        "${ZED_WORKTREE_ROOT}/editors/code/".to_string(),
+                    )),
+                    ..ProblemMatcherDefinition::default()
+                })],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Extension".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "build".to_string()],
+                problem_matchers: vec![ProblemMatcher::Custom(ProblemMatcherDefinition {
+                    base: Some("$tsc".to_string()),
+                    file_location: Some(FileLocation::WithBase(
+                        FileLocationKind::Relative,
+                        "${ZED_WORKTREE_ROOT}/editors/code/".to_string(),
+                    )),
+                    ..ProblemMatcherDefinition::default()
+                })],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Server".to_string(),
                 command: "cargo build --package rust-analyzer".to_string(),
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$rustc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Build Server (Release)".to_string(),
                 command: "cargo build --release --package rust-analyzer".to_string(),
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$rustc".to_string())],
                 ..Default::default()
             },
             TaskTemplate {
                 label: "Pretest".to_string(),
                 command: "npm".to_string(),
                 args: vec!["run".to_string(), "pretest".to_string()],
+                problem_matchers: vec![ProblemMatcher::Custom(ProblemMatcherDefinition {
+                    base: Some("$tsc".to_string()),
+                    file_location: Some(FileLocation::WithBase(
+                        FileLocationKind::Relative,
+                        "${ZED_WORKTREE_ROOT}/editors/code/".to_string(),
+                    )),
+                    ..ProblemMatcherDefinition::default()
+                })],
+                ..Default::default()
+            },
+            TaskTemplate {
+                label: "Build Server and Extension".to_string(),
+                depends_on: vec!["Build Server".to_string(), "Build Extension".to_string()],
+                depends_order: DependsOrder::Parallel,
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$rustc".to_string())],
+                ..Default::default()
+            },
+            TaskTemplate {
+                label: "Build Server (Release) and Extension".to_string(),
+                depends_on: vec![
+                    "Build Server (Release)".to_string(),
+                    "Build Extension".to_string(),
+                ],
+                depends_order: DependsOrder::Parallel,
+                problem_matchers: vec![ProblemMatcher::BuiltIn("$rustc".to_string())],
                 ..Default::default()
             },
         ];
@@ -0,0 +1,111 @@
+//! A PTY whose output passes through a chain of [`OutputFilter`]s before the terminal processes it.
+
+use std::{
+    io::{self, Read},
+    sync::Arc,
+};
+
+use alacritty_terminal::{
+    event::{OnResize, WindowSize},
+    tty::{ChildEvent, EventedPty, EventedReadWrite},
+};
+use polling::{Event, PollMode, Poller};
+
+/// Inspects the PTY output, possibly rewriting it.
+pub(crate) trait OutputFilter: Send {
+    /// Filters the next chunk of the output, appending the result to `output`.
+    /// Sequences split between chunks may be held back until they are complete.
+    fn filter(&mut self, input: &[u8], output: &mut Vec<u8>);
+}
+
+pub(crate) struct FilteredPty<P> {
+    pty: P,
+    filters: Vec<Box<dyn OutputFilter>>,
+    input: Vec<u8>,
+    /// Filtered output that did not fit into the buffer of the last read.
+    output: Vec<u8>,
+    scratch: Vec<u8>,
+}
+
+impl<P> FilteredPty<P> {
+    pub fn new(pty: P, filters: Vec<Box<dyn OutputFilter>>) -> Self {
+        Self {
+            pty,
+            filters,
+            input: Vec::new(),
+            output: Vec::new(),
+            scratch: Vec::new(),
+        }
+    }
+}
+
+impl<P: EventedReadWrite> io::Read for FilteredPty<P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.output.is_empty() {
+            // Filters may make the output longer than the input, leave room for that in the buffer.
+            self.input.resize((buf.len() / 2).max(1), 0);
+            let read = self.pty.reader().read(&mut self.input)?;
+            if read == 0 {
+                return Ok(0);
+            }
+            self.output.extend_from_slice(&self.input[..read]);
+            for filter in &mut self.filters {
+                self.scratch.clear();
+                filter.filter(&self.output, &mut self.scratch);
+                std::mem::swap(&mut self.output, &mut self.scratch);
+            }
+        }
+
+        let len = buf.len().min(self.output.len());
+        buf[..len].copy_from_slice(&self.output[..len]);
+        self.output.drain(..len);
+        Ok(len)
+    }
+}
+
+impl<P: EventedReadWrite> EventedReadWrite for FilteredPty<P> {
+    type Reader = Self;
+    type Writer = P::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.register(poll, interest, mode)
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: Event,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        self
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.pty.writer()
+    }
+}
+
+impl<P: EventedPty> EventedPty for FilteredPty<P> {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl<P: EventedReadWrite + OnResize> OnResize for FilteredPty<P> {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.pty.on_resize(window_size)
+    }
+}
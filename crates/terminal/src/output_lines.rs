@@ -0,0 +1,104 @@
+//! Lines of text printed to the terminal, collected from the PTY output as it is read,
+//! so that they can be scanned while the task is running, regardless of the scrollback size.
+
+use std::sync::Arc;
+
+use alacritty_terminal::{
+    sync::FairMutex,
+    vte::{Parser, Perform},
+};
+
+use crate::filtered_pty::OutputFilter;
+
+/// The output lines, stripped of escape sequences, that were not taken yet.
+#[derive(Default)]
+struct LineCollector {
+    lines: Vec<String>,
+    line: String,
+    /// Set after a carriage return, the text printed next overwrites the line.
+    carriage_return: bool,
+}
+
+impl Perform for LineCollector {
+    fn print(&mut self, c: char) {
+        if std::mem::take(&mut self.carriage_return) {
+            self.line.clear();
+        }
+        self.line.push(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' => {
+                self.lines.push(std::mem::take(&mut self.line));
+                self.carriage_return = false;
+            }
+            b'\r' => self.carriage_return = true,
+            b'\t' => self.print('\t'),
+            _ => {}
+        }
+    }
+}
+
+pub(crate) struct OutputLines {
+    parser: Parser,
+    collector: LineCollector,
+}
+
+impl OutputLines {
+    pub fn new() -> Arc<FairMutex<Self>> {
+        Arc::new(FairMutex::new(Self {
+            parser: Parser::new(),
+            collector: LineCollector::default(),
+        }))
+    }
+
+    /// Takes the lines completed since the last call, and the unfinished last line if `finished` is set.
+    pub fn take_lines(&mut self, finished: bool) -> Vec<String> {
+        let mut lines = std::mem::take(&mut self.collector.lines);
+        if finished && !self.collector.line.is_empty() {
+            lines.push(std::mem::take(&mut self.collector.line));
+        }
+        lines
+    }
+}
+
+impl OutputFilter for Arc<FairMutex<OutputLines>> {
+    fn filter(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        let mut output_lines = self.lock();
+        let OutputLines { parser, collector } = &mut *output_lines;
+        for &byte in input {
+            parser.advance(collector, byte);
+        }
+        output.extend_from_slice(input);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_lines() {
+        let mut output_lines = OutputLines::new();
+        let chunks = [
+            &b"\x1b[1;31merror\x1b[0m: oh"[..],
+            b" no\r\n  Building 1/2\r\x1b[K --> src/main.rs:1:1\n",
+            b"last",
+        ];
+        let mut output = Vec::new();
+        for chunk in chunks {
+            output_lines.filter(chunk, &mut output);
+        }
+        // The output itself is left intact.
+        assert_eq!(output, chunks.concat());
+
+        let mut output_lines = output_lines.lock();
+        assert_eq!(
+            output_lines.take_lines(false),
+            ["error: oh no", " --> src/main.rs:1:1"]
+        );
+        assert!(output_lines.take_lines(false).is_empty());
+        assert_eq!(output_lines.take_lines(true), ["last"]);
+    }
+}
@@ -0,0 +1,756 @@
+//! Shell integration through the `OSC 133` (FinalTerm) escape sequences, which shells emit
+//! around their prompts, the commands typed in and the output of those commands.
+//!
+//! Alacritty ignores these sequences, and would not tell where the cursor was when one got printed.
+//! So the PTY output is filtered before it reaches the [`Term`], replacing every mark with the
+//! `CSI 5 n` and `CSI 6 n` status requests: the event listener takes the cursor position that the terminal
+//! reports back as the position of the mark, instead of writing the reply to the PTY.
+//!
+//! The positions are anchored to lines counted from the start of the output, which are moved as the grid scrolls.
+//! This is exact for the lines that scrolled before a mark was reported, but has a few limitations:
+//! - The lines that scroll after the mark is reported and before the next [`ShellMarks::sync`] are estimated
+//!   from the line feeds printed after the mark, so output that wraps lines or moves the cursor up may misplace
+//!   the marks printed right before it.
+//! - The history is allowed to grow by [`HISTORY_SLACK`] lines between syncs before it is trimmed back,
+//!   when more lines than that are printed at once, all marks are forgotten.
+//! - Clearing the history forgets the marks in it, and resizing the terminal while it shows the alternate screen
+//!   does not move the marks with the lines that get rewrapped.
+//! - A program that sends both status requests right before a mark may get its replies taken for the mark's position.
+
+use std::{collections::VecDeque, ops::RangeInclusive, sync::Arc};
+
+use alacritty_terminal::{
+    event::EventListener,
+    grid::Dimensions,
+    index::{Boundary, Column, Line, Point as AlacPoint},
+    sync::FairMutex,
+    term::{cell::Flags, TermMode},
+    Term,
+};
+
+use crate::{filtered_pty::OutputFilter, CommandMarker, TerminalSize};
+
+/// How many lines the history may grow past its configured size between two syncs, without losing the marks.
+pub(crate) const HISTORY_SLACK: usize = 10_000;
+const MARK_PREFIX: &[u8] = b"\x1b]133;";
+/// Longest `OSC 133` sequence that is looked for, longer ones are passed through unchanged.
+const MAX_MARK_LEN: usize = 256;
+/// Requests the terminal status and then the cursor position, for the marks.
+const MARK_REQUESTS: &[u8] = b"\x1b[5n\x1b[6n";
+const STATUS_REPLY: &str = "\x1b[0n";
+
+/// A boundary that the shell reported with `OSC 133`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShellMark {
+    /// `OSC 133 ; A`: the prompt starts.
+    PromptStart,
+    /// `OSC 133 ; B`: the prompt ended, and the command is typed in after it.
+    CommandStart,
+    /// `OSC 133 ; C`: the command was entered, and its output follows.
+    OutputStart,
+    /// `OSC 133 ; D [; exit code]`: the command finished.
+    CommandEnd { exit_code: Option<i32> },
+}
+
+impl ShellMark {
+    /// Parses the parameters of an `OSC 133` sequence, following the `133;`.
+    fn parse(params: &[u8]) -> Option<Self> {
+        let params = std::str::from_utf8(params).ok()?;
+        let mut params = params.split(';');
+        match params.next()? {
+            "A" => Some(Self::PromptStart),
+            "B" => Some(Self::CommandStart),
+            "C" => Some(Self::OutputStart),
+            "D" => Some(Self::CommandEnd {
+                exit_code: params.next().and_then(|code| code.parse().ok()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// How a command run at a shell prompt ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandStatus {
+    Running,
+    Finished { exit_code: Option<i32> },
+}
+
+/// A shell prompt, the command that was entered at it and the output of that command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShellCommand {
+    pub prompt_start: AlacPoint,
+    /// Where the command is typed in, after the prompt.
+    pub command_start: Option<AlacPoint>,
+    pub output_start: Option<AlacPoint>,
+    /// The cell right after the output, once the command finished.
+    pub output_end: Option<AlacPoint>,
+    pub exit_code: Option<i32>,
+}
+
+impl ShellCommand {
+    /// The status of the command, if it was entered.
+    pub fn status(&self) -> Option<CommandStatus> {
+        self.output_start?;
+        Some(match self.output_end {
+            Some(_) => CommandStatus::Finished {
+                exit_code: self.exit_code,
+            },
+            None => CommandStatus::Running,
+        })
+    }
+
+    /// The range of the command output, if the command finished and printed anything.
+    pub fn output<T>(&self, term: &Term<T>) -> Option<RangeInclusive<AlacPoint>> {
+        let start = self.output_start?;
+        let end = self.output_end?;
+        if end <= start {
+            return None;
+        }
+        Some(start..=end.sub(term, Boundary::Grid, 1))
+    }
+
+    /// The command line as it was entered, or as it is being typed in at the prompt.
+    pub fn command_text<T>(&self, term: &Term<T>) -> Option<String> {
+        let start = self.command_start?;
+        let end = match self.output_start {
+            Some(output_start) => output_start.sub(term, Boundary::Grid, 1),
+            None => AlacPoint::new(term.grid().cursor.point.line, term.last_column()),
+        };
+        if end < start {
+            return None;
+        }
+        let text = term.bounds_to_string(start, end);
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+}
+
+/// A position in the output, with the lines counted from the first line that the terminal printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Anchor {
+    line: i64,
+    column: Column,
+}
+
+#[derive(Clone, Debug)]
+struct AnchoredCommand {
+    prompt_start: Anchor,
+    command_start: Option<Anchor>,
+    output_start: Option<Anchor>,
+    output_end: Option<Anchor>,
+    exit_code: Option<i32>,
+}
+
+impl AnchoredCommand {
+    fn anchors_mut(&mut self) -> impl Iterator<Item = &mut Anchor> {
+        std::iter::once(&mut self.prompt_start).chain(
+            [
+                &mut self.command_start,
+                &mut self.output_start,
+                &mut self.output_end,
+            ]
+            .into_iter()
+            .flatten(),
+        )
+    }
+}
+
+/// A mark whose position the terminal did not report yet.
+struct PendingMark {
+    mark: ShellMark,
+    /// Line feeds printed before the mark.
+    line_feeds: u64,
+}
+
+/// A mark with the cursor position that the terminal reported for it, anchored at the next sync.
+struct ReportedMark {
+    mark: ShellMark,
+    point: AlacPoint,
+    line_feeds: u64,
+}
+
+/// The marks of a terminal, shared between the output filter, the event listener and the terminal.
+pub(crate) struct ShellMarks {
+    pending: VecDeque<PendingMark>,
+    reported: Vec<ReportedMark>,
+    /// Set when a status reply was held back, as the cursor position for a mark may follow it.
+    status_reply_held: bool,
+    /// Line feeds printed so far.
+    line_feeds: u64,
+    commands: VecDeque<AnchoredCommand>,
+    /// Lines that scrolled into the history so far, to turn the anchors into lines of the grid.
+    scrolled_lines: i64,
+    /// The size of the history at the last sync.
+    history_size: usize,
+    /// The configured size of the history, which it is trimmed to at every sync.
+    max_history_size: usize,
+}
+
+impl ShellMarks {
+    pub fn new(max_history_size: usize) -> Arc<FairMutex<Self>> {
+        Arc::new(FairMutex::new(Self {
+            pending: VecDeque::new(),
+            reported: Vec::new(),
+            status_reply_held: false,
+            line_feeds: 0,
+            commands: VecDeque::new(),
+            scrolled_lines: 0,
+            history_size: 0,
+            max_history_size,
+        }))
+    }
+
+    /// Takes a reply that the terminal writes back to the PTY, returning the replies to write.
+    /// The replies to the requests sent for a mark are not written, the cursor position is stored for the mark instead.
+    pub fn filter_reply(&mut self, reply: String) -> Vec<String> {
+        let mut replies = Vec::new();
+        if std::mem::take(&mut self.status_reply_held) {
+            match parse_cursor_position(&reply) {
+                Some(point) if !self.pending.is_empty() => {
+                    if let Some(pending) = self.pending.pop_front() {
+                        self.reported.push(ReportedMark {
+                            mark: pending.mark,
+                            point,
+                            line_feeds: pending.line_feeds,
+                        });
+                    }
+                    return replies;
+                }
+                // The held reply was the one for a request of the program.
+                _ => replies.push(STATUS_REPLY.to_string()),
+            }
+        }
+        if reply == STATUS_REPLY && !self.pending.is_empty() {
+            self.status_reply_held = true;
+        } else {
+            replies.push(reply);
+        }
+        replies
+    }
+
+    /// Anchors the marks reported since the last sync, moves the anchors with the lines that scrolled
+    /// into the history since, and trims the history back to its configured size.
+    pub fn sync<T>(&mut self, term: &mut Term<T>) {
+        // The marks are only printed on the primary screen, whose grid is out of reach meanwhile.
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+
+        let history_size = term.grid().history_size();
+        if history_size >= self.max_history_size + HISTORY_SLACK {
+            // Lines were dropped from the history, by an unknown number.
+            self.commands.clear();
+            self.reported.clear();
+        }
+        let scrolled_lines = if history_size >= self.history_size {
+            history_size - self.history_size
+        } else {
+            // The history was cleared.
+            let screen_top = self.scrolled_lines;
+            self.retain_commands(|command| command.prompt_start.line >= screen_top);
+            0
+        };
+        self.scrolled_lines += scrolled_lines as i64;
+
+        let bottom_line = term.bottommost_line().0 as i64;
+        for reported in std::mem::take(&mut self.reported) {
+            let line_feeds = (self.line_feeds - reported.line_feeds) as i64;
+            let lines_below = bottom_line - reported.point.line.0 as i64;
+            let scrolled_after = (line_feeds - lines_below).clamp(0, scrolled_lines as i64);
+            let anchor = Anchor {
+                line: reported.point.line.0 as i64 - scrolled_after + self.scrolled_lines,
+                column: reported.point.column,
+            };
+            self.add_mark(reported.mark, anchor);
+        }
+
+        if history_size > self.max_history_size {
+            let grid = term.grid_mut();
+            grid.update_history(self.max_history_size);
+            grid.update_history(self.max_history_size + HISTORY_SLACK);
+            let topmost_line = self.scrolled_lines - self.max_history_size as i64;
+            self.retain_commands(|command| command.prompt_start.line >= topmost_line);
+        }
+        self.history_size = term.grid().history_size();
+    }
+
+    fn add_mark(&mut self, mark: ShellMark, anchor: Anchor) {
+        if let ShellMark::PromptStart = mark {
+            self.commands.push_back(AnchoredCommand {
+                prompt_start: anchor,
+                command_start: None,
+                output_start: None,
+                output_end: None,
+                exit_code: None,
+            });
+            return;
+        }
+        let Some(command) = self.commands.back_mut() else {
+            return;
+        };
+        match mark {
+            ShellMark::PromptStart => {}
+            ShellMark::CommandStart => {
+                if command.output_start.is_none() {
+                    command.command_start = Some(anchor);
+                }
+            }
+            ShellMark::OutputStart => {
+                command.output_start.get_or_insert(anchor);
+            }
+            ShellMark::CommandEnd { exit_code } => {
+                if command.output_end.is_none() {
+                    command.output_end = Some(anchor);
+                    command.exit_code = exit_code;
+                }
+            }
+        }
+    }
+
+    fn retain_commands(&mut self, mut f: impl FnMut(&AnchoredCommand) -> bool) {
+        self.commands.retain(|command| f(command));
+    }
+
+    /// Resizes the terminal, moving the anchors with the lines that get rewrapped.
+    pub fn resize<T: EventListener>(&mut self, term: &mut Term<T>, size: TerminalSize) {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            term.resize(size);
+            return;
+        }
+
+        // The lines are counted from the line of the cursor, which the rewrapping keeps in place.
+        let scrolled_lines = self.scrolled_lines;
+        let (line_starts, cursor_line) = logical_lines(term);
+        let columns = term.columns();
+        let positions = self
+            .commands
+            .iter_mut()
+            .flat_map(|command| command.anchors_mut())
+            .map(|anchor| {
+                let line = Line((anchor.line - scrolled_lines) as i32);
+                let logical_line = line_starts
+                    .partition_point(|start| *start <= line)
+                    .saturating_sub(1);
+                let offset = (line - line_starts[logical_line]).0.max(0) as usize * columns
+                    + anchor.column.0;
+                (logical_line as i64 - cursor_line as i64, offset)
+            })
+            .collect::<Vec<_>>();
+
+        term.resize(size);
+
+        let (line_starts, cursor_line) = logical_lines(term);
+        let columns = term.columns();
+        let bottommost_line = term.bottommost_line();
+        let anchors = self
+            .commands
+            .iter_mut()
+            .flat_map(|command| command.anchors_mut());
+        for (anchor, (logical_line, offset)) in anchors.zip(positions) {
+            let logical_line = (cursor_line as i64 + logical_line).max(0) as usize;
+            let start = line_starts
+                .get(logical_line)
+                .copied()
+                .unwrap_or(bottommost_line);
+            let line = (start + (offset / columns) as i32).min(bottommost_line);
+            *anchor = Anchor {
+                line: line.0 as i64 + scrolled_lines,
+                column: Column(offset % columns),
+            };
+        }
+        self.history_size = term.grid().history_size();
+    }
+
+    /// Forgets the marks of the lines that were cleared, after the line of the cursor was moved to the top.
+    pub fn clear(&mut self, cursor_line: Line) {
+        let cursor_line = cursor_line.0 as i64 + self.scrolled_lines;
+        let top_line = self.scrolled_lines;
+        self.retain_commands(|command| command.prompt_start.line == cursor_line);
+        for command in &mut self.commands {
+            command.command_start = command
+                .command_start
+                .filter(|anchor| anchor.line == cursor_line);
+            command.output_start = None;
+            command.output_end = None;
+            for anchor in command.anchors_mut() {
+                anchor.line = top_line;
+            }
+        }
+        self.history_size = 0;
+    }
+
+    /// The commands of the terminal, including its history, with their positions in the grid.
+    pub fn commands(&self) -> impl DoubleEndedIterator<Item = ShellCommand> + '_ {
+        self.commands
+            .iter()
+            .map(|command| self.grid_command(command))
+    }
+
+    /// Markers for the prompts in the given lines, of the commands that were entered at them.
+    pub fn command_markers(&self, lines: RangeInclusive<Line>) -> Vec<CommandMarker> {
+        let start = self.anchor_line(*lines.start());
+        let end = self.anchor_line(*lines.end());
+        let first = self
+            .commands
+            .partition_point(|command| command.prompt_start.line < start);
+        self.commands
+            .range(first..)
+            .take_while(|command| command.prompt_start.line <= end)
+            .filter_map(|command| {
+                let command = self.grid_command(command);
+                Some(CommandMarker {
+                    line: command.prompt_start.line,
+                    status: command.status()?,
+                })
+            })
+            .collect()
+    }
+
+    fn anchor_line(&self, line: Line) -> i64 {
+        line.0 as i64 + self.scrolled_lines
+    }
+
+    fn grid_point(&self, anchor: Anchor) -> AlacPoint {
+        AlacPoint::new(
+            Line((anchor.line - self.scrolled_lines) as i32),
+            anchor.column,
+        )
+    }
+
+    fn grid_command(&self, command: &AnchoredCommand) -> ShellCommand {
+        ShellCommand {
+            prompt_start: self.grid_point(command.prompt_start),
+            command_start: command.command_start.map(|anchor| self.grid_point(anchor)),
+            output_start: command.output_start.map(|anchor| self.grid_point(anchor)),
+            output_end: command.output_end.map(|anchor| self.grid_point(anchor)),
+            exit_code: command.exit_code,
+        }
+    }
+}
+
+/// The lines of the grid that do not continue a line wrapped before them, from the top,
+/// and the index of the one that the cursor is on.
+fn logical_lines<T>(term: &Term<T>) -> (Vec<Line>, usize) {
+    let last_column = term.last_column();
+    let cursor_line = term.grid().cursor.point.line;
+    let mut starts = Vec::new();
+    let mut wrapped = false;
+    for line in term.topmost_line().0..=term.bottommost_line().0 {
+        let line = Line(line);
+        if !wrapped {
+            starts.push(line);
+        }
+        wrapped = term.grid()[line][last_column]
+            .flags
+            .contains(Flags::WRAPLINE);
+    }
+    let cursor = starts.partition_point(|start| *start <= cursor_line) - 1;
+    (starts, cursor)
+}
+
+/// Parses a `CSI line ; column R` cursor position report.
+fn parse_cursor_position(reply: &str) -> Option<AlacPoint> {
+    let (line, column) = reply
+        .strip_prefix("\x1b[")?
+        .strip_suffix('R')?
+        .split_once(';')?;
+    let line = line.parse::<i32>().ok()?.checked_sub(1)?;
+    let column = column.parse::<usize>().ok()?.checked_sub(1)?;
+    Some(AlacPoint::new(Line(line), Column(column)))
+}
+
+/// Replaces the `OSC 133` sequences of the PTY output with the requests for the cursor position,
+/// leaving all other output as is.
+pub(crate) struct ShellIntegrationFilter {
+    marks: Arc<FairMutex<ShellMarks>>,
+    /// The start of a mark that continues in the next chunk of the output.
+    partial: Vec<u8>,
+}
+
+impl ShellIntegrationFilter {
+    pub fn new(marks: Arc<FairMutex<ShellMarks>>) -> Self {
+        Self {
+            marks,
+            partial: Vec::new(),
+        }
+    }
+}
+
+impl OutputFilter for ShellIntegrationFilter {
+    fn filter(&mut self, input: &[u8], output: &mut Vec<u8>) {
+        let buffered;
+        let mut rest = if self.partial.is_empty() {
+            input
+        } else {
+            buffered = [std::mem::take(&mut self.partial).as_slice(), input].concat();
+            buffered.as_slice()
+        };
+
+        let mut marks = self.marks.lock();
+        let marks = &mut *marks;
+        let pass = |marks: &mut ShellMarks, bytes: &[u8], output: &mut Vec<u8>| {
+            marks.line_feeds += bytes.iter().filter(|byte| **byte == b'\n').count() as u64;
+            output.extend_from_slice(bytes);
+        };
+        while let Some(escape) = rest.iter().position(|byte| *byte == 0x1b) {
+            let (text, sequence) = rest.split_at(escape);
+            pass(marks, text, output);
+            if !sequence.starts_with(MARK_PREFIX) {
+                if sequence.len() < MARK_PREFIX.len() && MARK_PREFIX.starts_with(sequence) {
+                    self.partial = sequence.to_vec();
+                    return;
+                }
+                pass(marks, &sequence[..1], output);
+                rest = &sequence[1..];
+                continue;
+            }
+
+            let params = &sequence[MARK_PREFIX.len()..];
+            let Some(end) = params
+                .iter()
+                .take(MAX_MARK_LEN)
+                .position(|byte| *byte == 0x07 || *byte == 0x1b)
+            else {
+                if params.len() < MAX_MARK_LEN {
+                    self.partial = sequence.to_vec();
+                    return;
+                }
+                pass(marks, &sequence[..1], output);
+                rest = &sequence[1..];
+                continue;
+            };
+            let terminator_len = match (params[end], params.get(end + 1)) {
+                (0x07, _) => 1,
+                (_, Some(b'\\')) => 2,
+                (_, None) => {
+                    self.partial = sequence.to_vec();
+                    return;
+                }
+                // Another escape sequence interrupted this one, which the terminal drops.
+                (_, Some(_)) => {
+                    rest = &params[end..];
+                    continue;
+                }
+            };
+            if let Some(mark) = ShellMark::parse(&params[..end]) {
+                let line_feeds = marks.line_feeds;
+                marks.pending.push_back(PendingMark { mark, line_feeds });
+                output.extend_from_slice(MARK_REQUESTS);
+            }
+            rest = &params[end + terminator_len..];
+        }
+        pass(marks, rest, output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alacritty_terminal::{event::Event as AlacTermEvent, term::Config, vte::ansi::Processor};
+    use gpui::{px, size};
+
+    use super::*;
+
+    /// Hands the replies of the terminal to the marks, like the listener of the terminal does.
+    #[derive(Clone)]
+    struct Listener {
+        marks: Arc<FairMutex<ShellMarks>>,
+        replies: Arc<FairMutex<Vec<String>>>,
+    }
+
+    impl EventListener for Listener {
+        fn send_event(&self, event: AlacTermEvent) {
+            if let AlacTermEvent::PtyWrite(reply) = event {
+                let replies = self.marks.lock().filter_reply(reply);
+                self.replies.lock().extend(replies);
+            }
+        }
+    }
+
+    fn terminal(columns: usize, lines: usize) -> (Term<Listener>, Listener) {
+        let listener = Listener {
+            marks: ShellMarks::new(Config::default().scrolling_history),
+            replies: Default::default(),
+        };
+        let size = TerminalSize::new(px(1.), px(1.), size(px(columns as f32), px(lines as f32)));
+        let config = Config {
+            scrolling_history: Config::default().scrolling_history + HISTORY_SLACK,
+            ..Config::default()
+        };
+        (Term::new(config, &size, listener.clone()), listener)
+    }
+
+    /// Feeds the output to the terminal through the filter, split into chunks of the given size.
+    fn feed(term: &mut Term<Listener>, listener: &Listener, output: &str, chunk_size: usize) {
+        let mut filter = ShellIntegrationFilter::new(listener.marks.clone());
+        let mut processor: Processor = Processor::new();
+        for chunk in output.as_bytes().chunks(chunk_size) {
+            let mut filtered = Vec::new();
+            filter.filter(chunk, &mut filtered);
+            for byte in filtered {
+                processor.advance(term, byte);
+            }
+        }
+        listener.marks.lock().sync(term);
+    }
+
+    fn text(term: &Term<Listener>, range: RangeInclusive<AlacPoint>) -> String {
+        term.bounds_to_string(*range.start(), *range.end())
+    }
+
+    const SESSION: &str = concat!(
+        "\x1b]133;A\x07$ \x1b]133;B\x07echo hi\r\n",
+        "\x1b]133;C\x07\x1b]8;;https://zed.dev\x1b\\hi\x1b]8;;\x1b\\\r\n",
+        "\x1b]133;D;0\x07\x1b]133;A\x1b\\$ \x1b]133;B\x1b\\false\r\n",
+        "\x1b]133;C\x07\x1b]133;D;1\x07\x1b]133;A\x07$ \x1b]133;B\x07",
+    );
+
+    #[test]
+    fn test_shell_commands() {
+        for chunk_size in [1, 3, SESSION.len()] {
+            let (mut term, listener) = terminal(40, 10);
+            feed(&mut term, &listener, SESSION, chunk_size);
+
+            let commands = listener.marks.lock().commands().collect::<Vec<_>>();
+            assert_eq!(commands.len(), 3, "chunk size {chunk_size}");
+            assert!(listener.replies.lock().is_empty());
+
+            let echo = &commands[0];
+            assert_eq!(echo.prompt_start, AlacPoint::new(Line(0), Column(0)));
+            assert_eq!(echo.command_text(&term).as_deref(), Some("echo hi"));
+            assert_eq!(text(&term, echo.output(&term).unwrap()).trim(), "hi");
+            assert_eq!(
+                echo.status(),
+                Some(CommandStatus::Finished { exit_code: Some(0) })
+            );
+
+            let failed = &commands[1];
+            assert_eq!(failed.prompt_start, AlacPoint::new(Line(2), Column(0)));
+            assert_eq!(failed.command_text(&term).as_deref(), Some("false"));
+            assert_eq!(failed.output(&term), None);
+            assert_eq!(
+                failed.status(),
+                Some(CommandStatus::Finished { exit_code: Some(1) })
+            );
+
+            let current = &commands[2];
+            assert_eq!(current.prompt_start, AlacPoint::new(Line(3), Column(0)));
+            assert_eq!(current.status(), None);
+            assert_eq!(current.command_text(&term), None);
+
+            // The text is left intact, and so are the hyperlinks of the programs.
+            assert_eq!(
+                text(
+                    &term,
+                    AlacPoint::new(Line(0), Column(0))..=AlacPoint::new(Line(3), Column(39))
+                )
+                .lines()
+                .map(str::trim_end)
+                .collect::<Vec<_>>(),
+                ["$ echo hi", "hi", "$ false", "$"]
+            );
+            let link = term.grid()[Line(1)][Column(1)].hyperlink().unwrap();
+            assert_eq!(link.uri(), "https://zed.dev");
+        }
+    }
+
+    #[test]
+    fn test_running_command_and_scrollback() {
+        let (mut term, listener) = terminal(20, 3);
+        let mut output = String::from("\x1b]133;A\x07$ \x1b]133;B\x07seq 5\r\n\x1b]133;C\x07");
+        for i in 1..=5 {
+            output.push_str(&format!("{i}\r\n"));
+        }
+        feed(&mut term, &listener, &output, 7);
+
+        let marks = listener.marks.lock();
+        let commands = marks.commands().collect::<Vec<_>>();
+        assert_eq!(commands.len(), 1);
+        let command = &commands[0];
+        assert_eq!(command.prompt_start.line, term.topmost_line());
+        assert_eq!(command.command_text(&term).as_deref(), Some("seq 5"));
+        assert_eq!(command.status(), Some(CommandStatus::Running));
+        assert_eq!(command.output(&term), None);
+
+        // Only the prompts in the given lines get markers.
+        let visible_lines = Line(0)..=term.bottommost_line();
+        assert!(marks.command_markers(visible_lines).is_empty());
+        assert_eq!(
+            marks.command_markers(term.topmost_line()..=Line(0)),
+            [CommandMarker {
+                line: term.topmost_line(),
+                status: CommandStatus::Running
+            }]
+        );
+        drop(marks);
+
+        // The anchors keep moving with the output printed after the marks were anchored.
+        feed(&mut term, &listener, "6\r\n7\r\n\x1b]133;D;0\x07", 2);
+        let commands = listener.marks.lock().commands().collect::<Vec<_>>();
+        assert_eq!(commands[0].prompt_start.line, term.topmost_line());
+        assert_eq!(
+            text(&term, commands[0].output(&term).unwrap())
+                .lines()
+                .map(str::trim_end)
+                .collect::<Vec<_>>(),
+            ["1", "2", "3", "4", "5", "6", "7"]
+        );
+    }
+
+    #[test]
+    fn test_rewrapped_lines() {
+        let (mut term, listener) = terminal(10, 5);
+        feed(
+            &mut term,
+            &listener,
+            "\x1b]133;A\x07$ \x1b]133;B\x07echo 0123456789\r\n\x1b]133;C\x07ok\r\n\x1b]133;D;0\x07",
+            5,
+        );
+        let output_start = listener
+            .marks
+            .lock()
+            .commands()
+            .next()
+            .unwrap()
+            .output_start;
+        assert_eq!(output_start, Some(AlacPoint::new(Line(2), Column(0))));
+
+        let size = TerminalSize::new(px(1.), px(1.), size(px(20.), px(5.)));
+        listener.marks.lock().resize(&mut term, size);
+        let command = listener.marks.lock().commands().next().unwrap();
+        assert_eq!(command.prompt_start.column, Column(0));
+        assert_eq!(
+            command.command_text(&term).as_deref(),
+            Some("echo 0123456789")
+        );
+        assert_eq!(
+            command.output_start,
+            Some(AlacPoint::new(command.prompt_start.line + 1, Column(0)))
+        );
+        assert_eq!(text(&term, command.output(&term).unwrap()).trim(), "ok");
+    }
+
+    #[test]
+    fn test_unrelated_output_passes_through() {
+        let marks = ShellMarks::new(0);
+        let output = b"\x1b[1;31mred\x1b[0m \x1b]0;title\x07\x1bP1$r\x1b\\\xc3\xa9\x1b]133;C\x07\xe2\x9c\x93";
+        let mut filtered = Vec::new();
+        ShellIntegrationFilter::new(marks.clone()).filter(output, &mut filtered);
+        let expected = [
+            &b"\x1b[1;31mred\x1b[0m \x1b]0;title\x07\x1bP1$r\x1b\\\xc3\xa9"[..],
+            MARK_REQUESTS,
+            b"\xe2\x9c\x93",
+        ]
+        .concat();
+        assert_eq!(filtered, expected);
+
+        // Replies to the requests of programs are written back, even while a mark waits for its position.
+        let mut marks = marks.lock();
+        assert_eq!(marks.filter_reply("\x1b[2;3R".into()), ["\x1b[2;3R"]);
+        assert!(marks.filter_reply(STATUS_REPLY.into()).is_empty());
+        assert_eq!(marks.filter_reply(STATUS_REPLY.into()), [STATUS_REPLY]);
+        assert!(marks.filter_reply("\x1b[2;3R".into()).is_empty());
+        assert!(marks.pending.is_empty());
+        assert_eq!(marks.filter_reply(STATUS_REPLY.into()), [STATUS_REPLY]);
+    }
+}
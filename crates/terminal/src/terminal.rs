@@ -2,9 +2,14 @@ pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod filtered_pty;
+mod output_lines;
 mod pty_info;
+mod shell_integration;
 pub mod terminal_settings;
 
+pub use shell_integration::CommandStatus;
+
 use alacritty_terminal::{
     event::{Event as AlacTermEvent, EventListener, Notify, WindowSize},
     event_loop::{EventLoop, Msg, Notifier},
@@ -34,10 +39,13 @@ use mappings::mouse::{
 };
 
 use collections::{HashMap, VecDeque};
+use filtered_pty::{FilteredPty, OutputFilter};
 use futures::StreamExt;
+use output_lines::OutputLines;
 use pty_info::PtyProcessInfo;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use shell_integration::{ShellIntegrationFilter, ShellMarks, HISTORY_SLACK};
 use smol::channel::{Receiver, Sender};
 use task::TaskId;
 use terminal_settings::{AlternateScroll, Shell, TerminalBlink, TerminalSettings};
@@ -76,6 +84,13 @@ actions!(
         ScrollPageDown,
         ScrollToTop,
         ScrollToBottom,
+        ScrollToPreviousPrompt,
+        ScrollToNextPrompt,
+        SelectLastCommandOutput,
+        CopyLastCommandOutput,
+        RerunCommand,
+        ExportScrollback,
+        ExportSearchMatches,
     ]
 );
 
@@ -104,6 +119,8 @@ pub enum Event {
     SelectionsChanged,
     NewNavigationTarget(Option<MaybeNavigationTarget>),
     Open(MaybeNavigationTarget),
+    /// The task of the terminal stopped running.
+    TaskFinished,
 }
 
 #[derive(Clone, Debug)]
@@ -142,11 +159,23 @@ enum InternalEvent {
 
 ///A translation struct for Alacritty to communicate with us from their event loop
 #[derive(Clone)]
-pub struct ZedListener(UnboundedSender<AlacTermEvent>);
+pub struct ZedListener {
+    events: UnboundedSender<AlacTermEvent>,
+    /// Takes the replies to the cursor position requests sent for the shell integration marks.
+    shell_marks: Arc<FairMutex<ShellMarks>>,
+}
 
 impl EventListener for ZedListener {
     fn send_event(&self, event: AlacTermEvent) {
-        self.0.unbounded_send(event).ok();
+        if let AlacTermEvent::PtyWrite(reply) = event {
+            for reply in self.shell_marks.lock().filter_reply(reply) {
+                self.events
+                    .unbounded_send(AlacTermEvent::PtyWrite(reply))
+                    .ok();
+            }
+            return;
+        }
+        self.events.unbounded_send(event).ok();
     }
 }
 
@@ -317,6 +346,7 @@ impl TerminalBuilder {
         blink_settings: Option<TerminalBlink>,
         alternate_scroll: AlternateScroll,
         max_scroll_history_lines: Option<usize>,
+        collect_output_lines: bool,
         window: AnyWindowHandle,
         completion_tx: Sender<()>,
     ) -> Result<TerminalBuilder> {
@@ -358,20 +388,22 @@ impl TerminalBuilder {
                 .unwrap_or(DEFAULT_SCROLL_HISTORY_LINES)
                 .min(MAX_SCROLL_HISTORY_LINES)
         };
+        // The shell integration trims the history back to its size, keeping the marks in place.
         let config = Config {
-            scrolling_history,
+            scrolling_history: scrolling_history + HISTORY_SLACK,
             ..Config::default()
         };
+        let shell_marks = ShellMarks::new(scrolling_history);
 
         //Spawn a task so the Alacritty EventLoop can communicate with us in a view context
         //TODO: Remove with a bounded sender which can be dispatched on &self
         let (events_tx, events_rx) = unbounded();
+        let listener = ZedListener {
+            events: events_tx,
+            shell_marks: shell_marks.clone(),
+        };
         //Set up the terminal...
-        let mut term = Term::new(
-            config,
-            &TerminalSize::default(),
-            ZedListener(events_tx.clone()),
-        );
+        let mut term = Term::new(config, &TerminalSize::default(), listener.clone());
 
         //Start off blinking if we need to
         if let Some(TerminalBlink::On) = blink_settings {
@@ -403,11 +435,18 @@ impl TerminalBuilder {
 
         let pty_info = PtyProcessInfo::new(&pty);
 
+        let output_lines = collect_output_lines.then(OutputLines::new);
+        let mut output_filters: Vec<Box<dyn OutputFilter>> =
+            vec![Box::new(ShellIntegrationFilter::new(shell_marks.clone()))];
+        if let Some(output_lines) = &output_lines {
+            output_filters.push(Box::new(output_lines.clone()));
+        }
+
         //And connect them together
         let event_loop = EventLoop::new(
             term.clone(),
-            ZedListener(events_tx.clone()),
-            pty,
+            listener,
+            FilteredPty::new(pty, output_filters),
             pty_options.hold,
             false,
         )?;
@@ -424,6 +463,7 @@ impl TerminalBuilder {
             pty_tx: Notifier(pty_tx),
             completion_tx,
             term,
+            shell_marks,
             events: VecDeque::with_capacity(10), //Should never get this high.
             last_content: Default::default(),
             last_mouse: None,
@@ -439,6 +479,7 @@ impl TerminalBuilder {
             hovered_word: false,
             url_regex,
             word_regex,
+            output_lines,
         };
 
         Ok(TerminalBuilder {
@@ -537,6 +578,14 @@ pub struct TerminalContent {
     pub cursor_char: char,
     pub size: TerminalSize,
     pub last_hovered_word: Option<HoveredWord>,
+    pub command_markers: Vec<CommandMarker>,
+}
+
+/// A shell prompt in the viewport, at which a command was run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandMarker {
+    pub line: Line,
+    pub status: CommandStatus,
 }
 
 #[derive(Clone)]
@@ -561,6 +610,7 @@ impl Default for TerminalContent {
             cursor_char: Default::default(),
             size: Default::default(),
             last_hovered_word: None,
+            command_markers: Vec::new(),
         }
     }
 }
@@ -575,6 +625,7 @@ pub struct Terminal {
     pty_tx: Notifier,
     completion_tx: Sender<()>,
     term: Arc<FairMutex<Term<ZedListener>>>,
+    shell_marks: Arc<FairMutex<ShellMarks>>,
     events: VecDeque<InternalEvent>,
     /// This is only used for mouse mode cell change detection
     last_mouse: Option<(AlacPoint, AlacDirection)>,
@@ -593,6 +644,7 @@ pub struct Terminal {
     url_regex: RegexSearch,
     word_regex: RegexSearch,
     task: Option<TaskState>,
+    output_lines: Option<Arc<FairMutex<OutputLines>>>,
 }
 
 pub struct TaskState {
@@ -710,13 +762,14 @@ impl Terminal {
 
                 self.pty_tx.0.send(Msg::Resize(new_size.into())).ok();
 
-                term.resize(new_size);
+                self.shell_marks.lock().resize(term, new_size);
             }
             InternalEvent::Clear => {
                 // Clear back buffer
                 term.clear_screen(ClearMode::Saved);
 
                 let cursor = term.grid().cursor.point;
+                self.shell_marks.lock().clear(cursor.line);
 
                 // Clear the lines above
                 term.grid_mut().reset_region(..cursor.line);
@@ -1024,6 +1077,114 @@ impl Terminal {
             .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
     }
 
+    pub fn scroll_to_previous_prompt(&mut self) {
+        let top = self.viewport_top();
+        let prompt = self
+            .shell_marks
+            .lock()
+            .commands()
+            .rev()
+            .map(|command| command.prompt_start.line)
+            .find(|line| *line < top);
+        if let Some(prompt) = prompt {
+            self.scroll_prompt_to_top(top, prompt);
+        }
+    }
+
+    pub fn scroll_to_next_prompt(&mut self) {
+        let top = self.viewport_top();
+        let prompt = self
+            .shell_marks
+            .lock()
+            .commands()
+            .map(|command| command.prompt_start.line)
+            .find(|line| *line > top);
+        match prompt {
+            Some(prompt) => self.scroll_prompt_to_top(top, prompt),
+            None => self.scroll_to_bottom(),
+        }
+    }
+
+    fn viewport_top(&self) -> Line {
+        Line(-(self.term.lock().grid().display_offset() as i32))
+    }
+
+    fn scroll_prompt_to_top(&mut self, top: Line, prompt: Line) {
+        self.events
+            .push_back(InternalEvent::Scroll(AlacScroll::Delta(top.0 - prompt.0)));
+    }
+
+    /// Selects the output of the last command that finished, returning whether there was any.
+    pub fn select_last_command_output(&mut self) -> bool {
+        let term = self.term.lock();
+        let output = self
+            .shell_marks
+            .lock()
+            .commands()
+            .rev()
+            .find_map(|command| command.output(&term));
+        drop(term);
+        match output {
+            Some(output) => {
+                self.set_selection(Some((make_selection(&output), *output.end())));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn copy_last_command_output(&mut self) {
+        if self.select_last_command_output() {
+            self.copy();
+        }
+    }
+
+    /// Runs again the command under the mouse cursor, or the last one that was run when the mouse is elsewhere.
+    /// Nothing is run unless the shell waits at an empty prompt, so that the command is not appended to
+    /// the input typed in at the prompt, or sent to a command that is still running.
+    pub fn rerun_command(&mut self) {
+        let term = self.term.lock();
+        let commands = self.shell_marks.lock().commands().collect::<Vec<_>>();
+        let at_empty_prompt = commands.last().is_some_and(|prompt| {
+            prompt.status().is_none()
+                && prompt.command_start.is_some()
+                && prompt.command_text(&term).is_none()
+        });
+        if !at_empty_prompt {
+            return;
+        }
+
+        let hovered_command = self.last_mouse_position.and_then(|position| {
+            let point = grid_point(
+                position,
+                self.last_content.size,
+                term.grid().display_offset(),
+            );
+            commands
+                .iter()
+                .rev()
+                .find(|command| command.prompt_start.line <= point.line)
+                .filter(|command| {
+                    command
+                        .output_end
+                        .map_or(false, |output_end| point.line <= output_end.line)
+                })
+        });
+        let command = hovered_command
+            .or_else(|| {
+                commands
+                    .iter()
+                    .rev()
+                    .find(|command| command.output_end.is_some())
+            })
+            .and_then(|command| command.command_text(&term));
+        drop(term);
+
+        if let Some(command) = command {
+            self.input(format!("{command}\r"));
+        }
+    }
+
     ///Resize the terminal and the PTY.
     pub fn set_size(&mut self, new_size: TerminalSize) {
         if self.last_content.size != new_size {
@@ -1089,15 +1250,23 @@ impl Terminal {
     pub fn sync(&mut self, cx: &mut ModelContext<Self>) {
         let term = self.term.clone();
         let mut terminal = term.lock_unfair();
+        self.shell_marks.lock().sync(&mut terminal);
         //Note that the ordering of events matters for event processing
         while let Some(e) = self.events.pop_front() {
             self.process_terminal_event(&e, &mut terminal, cx)
         }
 
-        self.last_content = Self::make_content(&terminal, &self.last_content);
+        let top = Line(-(terminal.grid().display_offset() as i32));
+        let visible_lines = top..=top + (terminal.screen_lines() as i32 - 1);
+        let command_markers = self.shell_marks.lock().command_markers(visible_lines);
+        self.last_content = Self::make_content(&terminal, &self.last_content, command_markers);
     }
 
-    fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
+    fn make_content(
+        term: &Term<ZedListener>,
+        last_content: &TerminalContent,
+        command_markers: Vec<CommandMarker>,
+    ) -> TerminalContent {
         let content = term.renderable_content();
         TerminalContent {
             cells: content
@@ -1122,6 +1291,7 @@ impl Terminal {
             cursor_char: term.grid()[content.cursor.point].c,
             size: last_content.size,
             last_hovered_word: last_content.last_hovered_word.clone(),
+            command_markers,
         }
     }
 
@@ -1436,6 +1606,16 @@ impl Terminal {
         })
     }
 
+    /// The text of the whole terminal, including its scrollback.
+    pub fn scrollback_text(&self) -> String {
+        scrollback_text(&self.term.lock())
+    }
+
+    /// The text of the lines that contain the current search matches.
+    pub fn search_matches_text(&self) -> String {
+        matching_lines_text(&self.term.lock(), &self.matches)
+    }
+
     pub fn title(&self, truncate: bool) -> String {
         const MAX_CHARS: usize = 25;
         match &self.task {
@@ -1489,6 +1669,21 @@ impl Terminal {
         self.task.as_ref()
     }
 
+    /// Takes the output lines printed since the last call, without escape sequences.
+    /// Once the task finished, its last line is included even if it did not end with a newline.
+    ///
+    /// Lines are only collected if the terminal was built with `collect_output_lines`.
+    pub fn take_output_lines(&mut self) -> Vec<String> {
+        let finished = self
+            .task
+            .as_ref()
+            .map_or(false, |task| task.status != TaskStatus::Running);
+        self.output_lines
+            .as_ref()
+            .map(|output_lines| output_lines.lock().take_lines(finished))
+            .unwrap_or_default()
+    }
+
     pub fn wait_for_completed_task(&self, cx: &mut AppContext) -> Task<()> {
         if let Some(task) = self.task() {
             if task.status == TaskStatus::Running {
@@ -1534,6 +1729,7 @@ impl Terminal {
         // when Zed task finishes and no more output is made.
         // After the task summary is output once, no more text is appended to the terminal.
         unsafe { append_text_to_term(&mut self.term.lock(), &[&task_line, &command_line]) };
+        cx.emit(Event::TaskFinished);
     }
 }
 
@@ -1640,6 +1836,61 @@ fn all_search_matches<'a, T>(
     RegexIter::new(start, end, AlacDirection::Right, term, regex)
 }
 
+/// Builds the searcher for a query of the search bar, whose regex syntax is the one of the `regex` crate.
+/// Unless requested otherwise, the case is ignored for the queries that are all lowercase ("smart case").
+pub fn search_regex(query: &str, case_sensitive: bool, whole_word: bool) -> Option<RegexSearch> {
+    let mut pattern = query.to_string();
+    if whole_word {
+        // Alacritty's DFAs support only ASCII word boundaries.
+        pattern = format!(r"(?-u:\b)(?:{pattern})(?-u:\b)");
+    }
+    let case_flag = if case_sensitive || has_uppercase(query) {
+        "(?-i)"
+    } else {
+        "(?i)"
+    };
+    RegexSearch::new(&format!("{case_flag}{pattern}")).ok()
+}
+
+/// Whether the regex matches any uppercase letter literally, ignoring escapes such as `\W`.
+fn has_uppercase(query: &str) -> bool {
+    let mut chars = query.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+    false
+}
+
+fn scrollback_text<T>(term: &Term<T>) -> String {
+    let start = AlacPoint::new(term.topmost_line(), Column(0));
+    let end = AlacPoint::new(term.bottommost_line(), term.last_column());
+    let mut text = term.bounds_to_string(start, end).trim_end().to_string();
+    text.push('\n');
+    text
+}
+
+/// The lines containing the given ranges, joining the lines that were wrapped.
+fn matching_lines_text<T>(term: &Term<T>, ranges: &[RangeInclusive<AlacPoint>]) -> String {
+    let mut text = String::new();
+    let mut last_line_end = None;
+    for range in ranges {
+        if last_line_end.is_some_and(|line_end| *range.start() <= line_end) {
+            continue;
+        }
+        let line_start = term.line_search_left(*range.start());
+        let line_end = term.line_search_right(*range.end());
+        let line = term.bounds_to_string(line_start, line_end);
+        text.push_str(line.trim_end());
+        text.push('\n');
+        last_line_end = Some(line_end);
+    }
+    text
+}
+
 fn content_index_for_mouse(pos: Point<Pixels>, size: &TerminalSize) -> usize {
     let col = (pos.x / size.cell_width()).round() as usize;
     let clamped_col = min(col, size.columns() - 1);
@@ -1735,16 +1986,78 @@ pub fn rgba_color(r: u8, g: u8, b: u8) -> Hsla {
 #[cfg(test)]
 mod tests {
     use alacritty_terminal::{
+        event::VoidListener,
+        grid::Dimensions,
         index::{Column, Line, Point as AlacPoint},
-        term::cell::Cell,
+        term::{cell::Cell, Config},
+        vte::ansi::Processor,
+        Term,
     };
-    use gpui::{point, size, Pixels};
+    use gpui::{point, px, size, Pixels};
     use rand::{distributions::Alphanumeric, rngs::ThreadRng, thread_rng, Rng};
 
     use crate::{
-        content_index_for_mouse, rgb_for_index, IndexedCell, TerminalContent, TerminalSize,
+        all_search_matches, content_index_for_mouse, matching_lines_text, rgb_for_index,
+        scrollback_text, search_regex, IndexedCell, TerminalContent, TerminalSize,
     };
 
+    fn terminal_with_output(output: &str) -> Term<VoidListener> {
+        let size = TerminalSize::new(px(1.), px(1.), size(px(20.), px(4.)));
+        let mut term = Term::new(Config::default(), &size, VoidListener);
+        let mut processor: Processor = Processor::new();
+        for byte in output.bytes() {
+            processor.advance(&mut term, byte);
+        }
+        term
+    }
+
+    fn search(term: &Term<VoidListener>, query: &str, case: bool, word: bool) -> Vec<String> {
+        let mut regex = search_regex(query, case, word).unwrap();
+        all_search_matches(term, &mut regex)
+            .map(|search_match| term.bounds_to_string(*search_match.start(), *search_match.end()))
+            .collect()
+    }
+
+    #[test]
+    fn test_search_options() {
+        let term = terminal_with_output("Error: error\r\nerrors\r\n");
+
+        assert_eq!(
+            search(&term, "error", false, false),
+            ["Error", "error", "error"]
+        );
+        assert_eq!(search(&term, "Error", false, false), ["Error"]);
+        assert_eq!(
+            search(&term, r"e\Sror", false, false),
+            ["Error", "error", "error"]
+        );
+        assert_eq!(search(&term, "error", true, false), ["error", "error"]);
+        assert_eq!(search(&term, "error", false, true), ["Error", "error"]);
+        assert_eq!(search(&term, "error", true, true), ["error"]);
+        assert_eq!(search(&term, "e.r", false, false), ["Err", "err", "err"]);
+    }
+
+    #[test]
+    fn test_export_scrollback() {
+        // The output overflows the screen and wraps the long line.
+        let term = terminal_with_output(
+            "one\r\ntwo match\r\nthree\r\nfour\r\na long line with a match\r\nsix\r\n",
+        );
+        assert!(term.topmost_line() < Line(0));
+        assert_eq!(
+            scrollback_text(&term),
+            "one\ntwo match\nthree\nfour\na long line with a match\nsix\n"
+        );
+
+        let mut regex = search_regex("match|long", false, false).unwrap();
+        let matches = all_search_matches(&term, &mut regex).collect::<Vec<_>>();
+        assert_eq!(matches.len(), 3);
+        assert_eq!(
+            matching_lines_text(&term, &matches),
+            "two match\na long line with a match\n"
+        );
+    }
+
     #[test]
     fn test_rgb_for_index() {
         // Test every possible value in the color cube.
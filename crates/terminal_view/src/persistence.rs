@@ -1,7 +1,15 @@
 use std::path::PathBuf;
 
+use async_recursion::async_recursion;
 use db::{define_connection, query, sqlez_macros::sql};
-use workspace::{ItemId, WorkspaceDb, WorkspaceId};
+use futures::future::join_all;
+use gpui::{AppContext, AsyncWindowContext, Axis, Entity, Model, Pixels, View, WeakView};
+use project::Project;
+use serde::{Deserialize, Serialize};
+use util::ResultExt;
+use workspace::{ItemId, Pane, PaneGroupLayout, Workspace, WorkspaceDb, WorkspaceId};
+
+use crate::{terminal_panel::TerminalPanel, TerminalView};
 
 define_connection! {
     pub static ref TERMINAL_DB: TerminalDb<WorkspaceDb> =
@@ -69,3 +77,280 @@ impl TerminalDb {
         }
     }
 }
+
+/// The state of the terminal panel, stored in the key-value store.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SerializedTerminalPanel {
+    /// Ids of the terminals of all panes, in the order they are laid out.
+    /// Panels serialized before panes could be split only have these.
+    pub items: Vec<u64>,
+    pub active_item_id: Option<u64>,
+    #[serde(default)]
+    pub panes: Option<SerializedPaneGroup>,
+    pub width: Option<Pixels>,
+    pub height: Option<Pixels>,
+}
+
+/// The split layout of the terminal panel, with the ids of the terminals in each pane.
+/// Working directories of the terminals are stored in [`TERMINAL_DB`] under the same ids.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum SerializedPaneGroup {
+    Group {
+        axis: SerializedAxis,
+        flexes: Option<Vec<f32>>,
+        children: Vec<SerializedPaneGroup>,
+    },
+    Pane(SerializedPane),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SerializedPane {
+    pub children: Vec<u64>,
+    pub active_item_id: Option<u64>,
+    pub active: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) enum SerializedAxis {
+    Horizontal,
+    Vertical,
+}
+
+impl From<Axis> for SerializedAxis {
+    fn from(axis: Axis) -> Self {
+        match axis {
+            Axis::Horizontal => Self::Horizontal,
+            Axis::Vertical => Self::Vertical,
+        }
+    }
+}
+
+impl From<SerializedAxis> for Axis {
+    fn from(axis: SerializedAxis) -> Self {
+        match axis {
+            SerializedAxis::Horizontal => Self::Horizontal,
+            SerializedAxis::Vertical => Self::Vertical,
+        }
+    }
+}
+
+impl SerializedPaneGroup {
+    /// Serializes the layout of the panes, skipping the terminals of tasks, as those are not restored.
+    pub(crate) fn new(layout: &PaneGroupLayout, active_pane: &View<Pane>, cx: &AppContext) -> Self {
+        match layout {
+            PaneGroupLayout::Axis {
+                axis,
+                flexes,
+                members,
+            } => Self::Group {
+                axis: (*axis).into(),
+                flexes: Some(flexes.clone()),
+                children: members
+                    .iter()
+                    .map(|member| Self::new(member, active_pane, cx))
+                    .collect(),
+            },
+            PaneGroupLayout::Pane(pane) => {
+                let children = serializable_terminals(pane, cx)
+                    .map(|terminal_view| terminal_view.entity_id().as_u64())
+                    .collect::<Vec<_>>();
+                let active_item_id = pane
+                    .read(cx)
+                    .active_item()
+                    .map(|item| item.item_id().as_u64())
+                    .filter(|item_id| children.contains(item_id));
+                Self::Pane(SerializedPane {
+                    children,
+                    active_item_id,
+                    active: pane == active_pane,
+                })
+            }
+        }
+    }
+
+    /// Ids of all terminals of the layout, in the order they are laid out.
+    pub(crate) fn item_ids(&self) -> Vec<u64> {
+        match self {
+            Self::Group { children, .. } => {
+                children.iter().flat_map(|child| child.item_ids()).collect()
+            }
+            Self::Pane(pane) => pane.children.clone(),
+        }
+    }
+
+    /// Recreates the panes of the layout with their terminals, in the working directories stored for them.
+    /// Returns `None` if none of the terminals could be restored.
+    #[async_recursion(?Send)]
+    pub(crate) async fn deserialize(
+        self,
+        terminal_panel: WeakView<TerminalPanel>,
+        workspace: WeakView<Workspace>,
+        project: Model<Project>,
+        workspace_id: WorkspaceId,
+        cx: &mut AsyncWindowContext,
+    ) -> Option<(PaneGroupLayout, Option<View<Pane>>)> {
+        match self {
+            Self::Group {
+                axis,
+                flexes,
+                children,
+            } => {
+                let mut members = Vec::new();
+                let mut restored_flexes = Vec::new();
+                let mut active_pane = None;
+                for (ix, child) in children.into_iter().enumerate() {
+                    let flex = flexes.as_ref().and_then(|flexes| flexes.get(ix).copied());
+                    if let Some((member, active)) = child
+                        .deserialize(
+                            terminal_panel.clone(),
+                            workspace.clone(),
+                            project.clone(),
+                            workspace_id,
+                            cx,
+                        )
+                        .await
+                    {
+                        members.push(member);
+                        restored_flexes.push(flex.unwrap_or(1.));
+                        active_pane = active_pane.or(active);
+                    }
+                }
+
+                match members.len() {
+                    0 => None,
+                    1 => Some((members.remove(0), active_pane)),
+                    _ => Some((
+                        PaneGroupLayout::Axis {
+                            axis: axis.into(),
+                            flexes: restored_flexes,
+                            members,
+                        },
+                        active_pane,
+                    )),
+                }
+            }
+            Self::Pane(serialized_pane) => {
+                let pane = terminal_panel
+                    .update(cx, |terminal_panel, cx| {
+                        terminal_panel.new_pane(project.clone(), cx)
+                    })
+                    .log_err()?;
+                let items = pane
+                    .update(cx, |_, cx| {
+                        serialized_pane
+                            .children
+                            .iter()
+                            .map(|item_id| {
+                                TerminalView::deserialize(
+                                    project.clone(),
+                                    workspace.clone(),
+                                    workspace_id,
+                                    *item_id,
+                                    cx,
+                                )
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .log_err()?;
+                let items = join_all(items).await;
+                pane.update(cx, |pane, cx| {
+                    let mut active_ix = None;
+                    for (item_id, item) in serialized_pane.children.iter().zip(items) {
+                        if let Some(item) = item.log_err() {
+                            pane.add_item(Box::new(item), false, false, None, cx);
+                            if Some(*item_id) == serialized_pane.active_item_id {
+                                active_ix = Some(pane.items_len() - 1);
+                            }
+                        }
+                    }
+                    if let Some(active_ix) = active_ix {
+                        pane.activate_item(active_ix, false, false, cx);
+                    }
+                })
+                .log_err()?;
+
+                if pane.update(cx, |pane, _| pane.items_len() == 0).log_err()? {
+                    return None;
+                }
+                let active_pane = serialized_pane.active.then(|| pane.clone());
+                Some((PaneGroupLayout::Pane(pane), active_pane))
+            }
+        }
+    }
+}
+
+/// Terminals of the pane that are restored on restart, which are all but the ones running tasks.
+pub(crate) fn serializable_terminals<'a>(
+    pane: &'a View<Pane>,
+    cx: &'a AppContext,
+) -> impl Iterator<Item = View<TerminalView>> + 'a {
+    pane.read(cx).items().filter_map(|item| {
+        let terminal_view = item.act_as::<TerminalView>(cx)?;
+        terminal_view
+            .read(cx)
+            .terminal()
+            .read(cx)
+            .task()
+            .is_none()
+            .then_some(terminal_view)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_terminal_panel_formats() {
+        let old_panel = serde_json::from_str::<SerializedTerminalPanel>(
+            r#"{"items":[1,2],"active_item_id":2,"width":null,"height":320.0}"#,
+        )
+        .unwrap();
+        assert_eq!(old_panel.items, vec![1, 2]);
+        assert_eq!(old_panel.active_item_id, Some(2));
+        assert_eq!(old_panel.panes, None);
+
+        let panes = SerializedPaneGroup::Group {
+            axis: SerializedAxis::Horizontal,
+            flexes: Some(vec![0.5, 1.5]),
+            children: vec![
+                SerializedPaneGroup::Pane(SerializedPane {
+                    children: vec![1],
+                    active_item_id: Some(1),
+                    active: false,
+                }),
+                SerializedPaneGroup::Group {
+                    axis: SerializedAxis::Vertical,
+                    flexes: None,
+                    children: vec![
+                        SerializedPaneGroup::Pane(SerializedPane {
+                            children: vec![2, 3],
+                            active_item_id: Some(3),
+                            active: true,
+                        }),
+                        SerializedPaneGroup::Pane(SerializedPane {
+                            children: vec![4],
+                            active_item_id: None,
+                            active: false,
+                        }),
+                    ],
+                },
+            ],
+        };
+        assert_eq!(panes.item_ids(), vec![1, 2, 3, 4]);
+
+        let panel = SerializedTerminalPanel {
+            items: panes.item_ids(),
+            active_item_id: Some(3),
+            panes: Some(panes),
+            width: None,
+            height: None,
+        };
+        let deserialized = serde_json::from_str::<SerializedTerminalPanel>(
+            &serde_json::to_string(&panel).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(deserialized.items, panel.items);
+        assert_eq!(deserialized.panes, panel.panes);
+    }
+}
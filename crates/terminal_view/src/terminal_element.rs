@@ -1,6 +1,6 @@
 use editor::{CursorLayout, HighlightedRange, HighlightedRangeLine};
 use gpui::{
-    div, fill, point, px, relative, AnyElement, Bounds, DispatchPhase, Element, ElementId,
+    div, fill, point, px, relative, size, AnyElement, Bounds, DispatchPhase, Element, ElementId,
     FocusHandle, Font, FontStyle, FontWeight, GlobalElementId, HighlightStyle, Hitbox, Hsla,
     InputHandler, InteractiveElement, Interactivity, IntoElement, LayoutId, Model, ModelContext,
     ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels, Point, ShapedLine,
@@ -21,7 +21,7 @@ use terminal::{
         },
     },
     terminal_settings::TerminalSettings,
-    HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalSize,
+    CommandStatus, HoveredWord, IndexedCell, Terminal, TerminalContent, TerminalSize,
 };
 use theme::{ActiveTheme, Theme, ThemeSettings};
 use ui::Tooltip;
@@ -43,6 +43,8 @@ pub struct LayoutState {
     display_offset: usize,
     hyperlink_tooltip: Option<AnyElement>,
     gutter: Pixels,
+    /// The display lines of the shell prompts, with the colors that show how their commands ended.
+    command_markers: Vec<(i32, Hsla)>,
     last_hovered_word: Option<HoveredWord>,
 }
 
@@ -693,9 +695,24 @@ impl Element for TerminalElement {
                     cursor_char,
                     selection,
                     cursor,
+                    command_markers,
                     ..
                 } = &self.terminal.read(cx).last_content;
 
+                let command_markers = command_markers
+                    .iter()
+                    .map(|marker| {
+                        let color = match marker.status {
+                            CommandStatus::Running => theme.status().info,
+                            CommandStatus::Finished {
+                                exit_code: Some(exit_code),
+                            } if exit_code != 0 => theme.status().error,
+                            CommandStatus::Finished { .. } => theme.status().success,
+                        };
+                        (marker.line.0 + *display_offset as i32, color)
+                    })
+                    .collect();
+
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
                 for search_match in search_matches {
@@ -780,6 +797,7 @@ impl Element for TerminalElement {
                     display_offset: *display_offset,
                     hyperlink_tooltip,
                     gutter,
+                    command_markers,
                     last_hovered_word,
                 }
             })
@@ -838,6 +856,23 @@ impl Element for TerminalElement {
                     rect.paint(origin, &layout, cx);
                 }
 
+                let marker_size = layout.gutter * 0.5;
+                for (line, color) in &layout.command_markers {
+                    let marker_origin = point(
+                        bounds.origin.x + (layout.gutter - marker_size) / 2.,
+                        bounds.origin.y
+                            + layout.dimensions.line_height * *line as f32
+                            + (layout.dimensions.line_height - marker_size) / 2.,
+                    );
+                    cx.paint_quad(
+                        fill(
+                            Bounds::new(marker_origin, size(marker_size, marker_size)),
+                            *color,
+                        )
+                        .corner_radii(marker_size / 2.),
+                    );
+                }
+
                 for (relative_highlighted_range, color) in layout.relative_highlighted_ranges.iter()
                 {
                     if let Some((start_y, highlighted_range_lines)) =
@@ -1,23 +1,28 @@
 use std::{ops::ControlFlow, path::PathBuf, sync::Arc};
 
-use crate::TerminalView;
+use crate::{
+    persistence::{
+        serializable_terminals, SerializedPane, SerializedPaneGroup, SerializedTerminalPanel,
+        TERMINAL_DB,
+    },
+    TerminalView,
+};
 use collections::{HashMap, HashSet};
 use db::kvp::KEY_VALUE_STORE;
 use futures::future::join_all;
 use gpui::{
-    actions, Action, AppContext, AsyncWindowContext, DismissEvent, Entity, EventEmitter,
+    actions, Action, AppContext, AsyncWindowContext, DismissEvent, Entity, EntityId, EventEmitter,
     ExternalPaths, FocusHandle, FocusableView, IntoElement, Model, ParentElement, Pixels, Render,
     Styled, Subscription, Task, View, ViewContext, VisualContext, WeakView, WindowContext,
 };
 use itertools::Itertools;
-use project::{Fs, ProjectEntryId};
+use project::{Fs, Project, ProjectEntryId};
 use search::{buffer_search::DivRegistrar, BufferSearchBar};
-use serde::{Deserialize, Serialize};
 use settings::Settings;
 use task::{RevealStrategy, SpawnInTerminal, TaskId, TerminalWorkDir};
 use terminal::{
     terminal_settings::{Shell, TerminalDockPosition, TerminalSettings},
-    Terminal,
+    TaskStatus, Terminal,
 };
 use ui::{
     h_flex, ButtonCommon, Clickable, ContextMenu, FluentBuilder, IconButton, IconSize, Selectable,
@@ -29,10 +34,11 @@ use workspace::{
     item::Item,
     pane,
     ui::IconName,
-    DraggedTab, NewTerminal, Pane, ToggleZoom, Workspace,
+    DraggedTab, NewTerminal, Pane, PaneGroup, PaneGroupLayout, SplitDirection, ToggleZoom,
+    Workspace,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Context as _, Result};
 
 const TERMINAL_PANEL_KEY: &str = "TerminalPanel";
 
@@ -58,149 +64,32 @@ pub fn init(cx: &mut AppContext) {
 }
 
 pub struct TerminalPanel {
-    pane: View<Pane>,
+    center: PaneGroup,
+    active_pane: View<Pane>,
     fs: Arc<dyn Fs>,
     workspace: WeakView<Workspace>,
     width: Option<Pixels>,
     height: Option<Pixels>,
     pending_serialization: Task<Option<()>>,
     pending_terminals_to_add: usize,
+    /// Subscriptions to the panes of the panel, dropped with the pane.
+    pane_subscriptions: HashMap<EntityId, Vec<Subscription>>,
     _subscriptions: Vec<Subscription>,
     deferred_tasks: HashMap<TaskId, Task<()>>,
+    prerequisite_chains: HashMap<TaskId, Task<Option<()>>>,
     enabled: bool,
 }
 
 impl TerminalPanel {
     fn new(workspace: &Workspace, cx: &mut ViewContext<Self>) -> Self {
-        let pane = cx.new_view(|cx| {
-            let mut pane = Pane::new(
-                workspace.weak_handle(),
-                workspace.project().clone(),
-                Default::default(),
-                None,
-                NewTerminal.boxed_clone(),
-                cx,
-            );
-            pane.set_can_split(false, cx);
-            pane.set_can_navigate(false, cx);
-            pane.display_nav_history_buttons(None);
-            pane.set_should_display_tab_bar(|_| true);
-            pane.set_render_tab_bar_buttons(cx, move |pane, cx| {
-                h_flex()
-                    .gap_2()
-                    .child(
-                        IconButton::new("plus", IconName::Plus)
-                            .icon_size(IconSize::Small)
-                            .on_click(cx.listener(|pane, _, cx| {
-                                let focus_handle = pane.focus_handle(cx);
-                                let menu = ContextMenu::build(cx, |menu, _| {
-                                    menu.action(
-                                        "New Terminal",
-                                        workspace::NewTerminal.boxed_clone(),
-                                    )
-                                    .entry(
-                                        "Spawn task",
-                                        Some(tasks_ui::Spawn::modal().boxed_clone()),
-                                        move |cx| {
-                                            // We want the focus to go back to terminal panel once task modal is dismissed,
-                                            // hence we focus that first. Otherwise, we'd end up without a focused element, as
-                                            // context menu will be gone the moment we spawn the modal.
-                                            cx.focus(&focus_handle);
-                                            cx.dispatch_action(
-                                                tasks_ui::Spawn::modal().boxed_clone(),
-                                            );
-                                        },
-                                    )
-                                });
-                                cx.subscribe(&menu, |pane, _, _: &DismissEvent, _| {
-                                    pane.new_item_menu = None;
-                                })
-                                .detach();
-                                pane.new_item_menu = Some(menu);
-                            }))
-                            .tooltip(|cx| Tooltip::text("New...", cx)),
-                    )
-                    .when_some(pane.new_item_menu.as_ref(), |el, new_item_menu| {
-                        el.child(Pane::render_menu_overlay(new_item_menu))
-                    })
-                    .child({
-                        let zoomed = pane.is_zoomed();
-                        IconButton::new("toggle_zoom", IconName::Maximize)
-                            .icon_size(IconSize::Small)
-                            .selected(zoomed)
-                            .selected_icon(IconName::Minimize)
-                            .on_click(cx.listener(|pane, _, cx| {
-                                pane.toggle_zoom(&workspace::ToggleZoom, cx);
-                            }))
-                            .tooltip(move |cx| {
-                                Tooltip::for_action(
-                                    if zoomed { "Zoom Out" } else { "Zoom In" },
-                                    &ToggleZoom,
-                                    cx,
-                                )
-                            })
-                    })
-                    .into_any_element()
-            });
-
-            let workspace = workspace.weak_handle();
-            pane.set_custom_drop_handle(cx, move |pane, dropped_item, cx| {
-                if let Some(tab) = dropped_item.downcast_ref::<DraggedTab>() {
-                    let item = if &tab.pane == cx.view() {
-                        pane.item_for_index(tab.ix)
-                    } else {
-                        tab.pane.read(cx).item_for_index(tab.ix)
-                    };
-                    if let Some(item) = item {
-                        if item.downcast::<TerminalView>().is_some() {
-                            return ControlFlow::Continue(());
-                        } else if let Some(project_path) = item.project_path(cx) {
-                            if let Some(entry_path) = workspace
-                                .update(cx, |workspace, cx| {
-                                    workspace
-                                        .project()
-                                        .read(cx)
-                                        .absolute_path(&project_path, cx)
-                                })
-                                .log_err()
-                                .flatten()
-                            {
-                                add_paths_to_terminal(pane, &[entry_path], cx);
-                            }
-                        }
-                    }
-                } else if let Some(&entry_id) = dropped_item.downcast_ref::<ProjectEntryId>() {
-                    if let Some(entry_path) = workspace
-                        .update(cx, |workspace, cx| {
-                            let project = workspace.project().read(cx);
-                            project
-                                .path_for_entry(entry_id, cx)
-                                .and_then(|project_path| project.absolute_path(&project_path, cx))
-                        })
-                        .log_err()
-                        .flatten()
-                    {
-                        add_paths_to_terminal(pane, &[entry_path], cx);
-                    }
-                } else if let Some(paths) = dropped_item.downcast_ref::<ExternalPaths>() {
-                    add_paths_to_terminal(pane, paths.paths(), cx);
-                }
-
-                ControlFlow::Break(())
-            });
-            let buffer_search_bar = cx.new_view(search::BufferSearchBar::new);
-            pane.toolbar()
-                .update(cx, |toolbar, cx| toolbar.add_item(buffer_search_bar, cx));
-            pane
-        });
-        let subscriptions = vec![
-            cx.observe(&pane, |_, _, cx| cx.notify()),
-            cx.subscribe(&pane, Self::handle_pane_event),
-        ];
         let project = workspace.project().read(cx);
         let enabled = project.is_local() || project.supports_remote_terminal(cx);
-        let this = Self {
-            pane,
+        let pane = new_terminal_pane(workspace.weak_handle(), workspace.project().clone(), cx);
+        let pane_subscriptions =
+            HashMap::from_iter([(pane.entity_id(), Self::subscribe_to_pane(&pane, cx))]);
+        Self {
+            center: PaneGroup::new(pane.clone()),
+            active_pane: pane,
             fs: workspace.app_state().fs.clone(),
             workspace: workspace.weak_handle(),
             pending_serialization: Task::ready(None),
@@ -208,10 +97,30 @@ impl TerminalPanel {
             height: None,
             pending_terminals_to_add: 0,
             deferred_tasks: HashMap::default(),
-            _subscriptions: subscriptions,
+            prerequisite_chains: HashMap::default(),
+            pane_subscriptions,
+            _subscriptions: Vec::new(),
             enabled,
-        };
-        this
+        }
+    }
+
+    /// Creates an empty pane for the panel to split into, subscribed to the panel.
+    pub(crate) fn new_pane(
+        &mut self,
+        project: Model<Project>,
+        cx: &mut ViewContext<Self>,
+    ) -> View<Pane> {
+        let pane = new_terminal_pane(self.workspace.clone(), project, cx);
+        self.pane_subscriptions
+            .insert(pane.entity_id(), Self::subscribe_to_pane(&pane, cx));
+        pane
+    }
+
+    fn subscribe_to_pane(pane: &View<Pane>, cx: &mut ViewContext<Self>) -> Vec<Subscription> {
+        vec![
+            cx.observe(pane, |_, _, cx| cx.notify()),
+            cx.subscribe(pane, Self::handle_pane_event),
+        ]
     }
 
     pub async fn load(
@@ -229,36 +138,29 @@ impl TerminalPanel {
             .log_err()
             .flatten();
 
-        let (panel, pane, items) = workspace.update(&mut cx, |workspace, cx| {
+        let (panel, serialized_panes) = workspace.update(&mut cx, |workspace, cx| {
             let panel = cx.new_view(|cx| TerminalPanel::new(workspace, cx));
-            let items = if let Some((serialized_panel, database_id)) =
-                serialized_panel.as_ref().zip(workspace.database_id())
+            let serialized_panes = if let Some((serialized_panel, database_id)) =
+                serialized_panel.zip(workspace.database_id())
             {
                 panel.update(cx, |panel, cx| {
                     cx.notify();
                     panel.height = serialized_panel.height.map(|h| h.round());
                     panel.width = serialized_panel.width.map(|w| w.round());
-                    panel.pane.update(cx, |_, cx| {
-                        serialized_panel
-                            .items
-                            .iter()
-                            .map(|item_id| {
-                                TerminalView::deserialize(
-                                    workspace.project().clone(),
-                                    workspace.weak_handle(),
-                                    database_id,
-                                    *item_id,
-                                    cx,
-                                )
-                            })
-                            .collect::<Vec<_>>()
+                });
+                // Panels serialized before the panes could be split have all terminals in one pane.
+                let panes = serialized_panel.panes.unwrap_or_else(|| {
+                    SerializedPaneGroup::Pane(SerializedPane {
+                        children: serialized_panel.items,
+                        active_item_id: serialized_panel.active_item_id,
+                        active: true,
                     })
-                })
+                });
+                Some((panes, database_id, workspace.project().clone()))
             } else {
-                Vec::new()
+                None
             };
-            let pane = panel.read(cx).pane.clone();
-            (panel, pane, items)
+            (panel, serialized_panes)
         })?;
 
         if let Some(workspace) = workspace.upgrade() {
@@ -276,47 +178,81 @@ impl TerminalPanel {
                 .ok();
         }
 
-        let pane = pane.downgrade();
-        let items = futures::future::join_all(items).await;
-        pane.update(&mut cx, |pane, cx| {
-            let active_item_id = serialized_panel
-                .as_ref()
-                .and_then(|panel| panel.active_item_id);
-            let mut active_ix = None;
-            for item in items {
-                if let Some(item) = item.log_err() {
-                    let item_id = item.entity_id().as_u64();
-                    pane.add_item(Box::new(item), false, false, None, cx);
-                    if Some(item_id) == active_item_id {
-                        active_ix = Some(pane.items_len() - 1);
-                    }
-                }
-            }
-
-            if let Some(active_ix) = active_ix {
-                pane.activate_item(active_ix, false, false, cx)
+        if let Some((serialized_panes, database_id, project)) = serialized_panes {
+            let restored = serialized_panes
+                .deserialize(
+                    panel.downgrade(),
+                    workspace.clone(),
+                    project,
+                    database_id,
+                    &mut cx,
+                )
+                .await;
+            if let Some((layout, active_pane)) = restored {
+                panel.update(&mut cx, |panel, cx| {
+                    panel.restore_layout(layout, active_pane, cx)
+                })?;
             }
-        })?;
+        }
 
         Ok(panel)
     }
 
+    /// Replaces the panes of the panel with the restored ones, dropping the subscriptions to the replaced panes.
+    fn restore_layout(
+        &mut self,
+        layout: PaneGroupLayout,
+        active_pane: Option<View<Pane>>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.center = PaneGroup::from_layout(layout);
+        let panes = self
+            .center
+            .panes()
+            .into_iter()
+            .map(|pane| pane.entity_id())
+            .collect::<HashSet<_>>();
+        self.pane_subscriptions
+            .retain(|pane_id, _| panes.contains(pane_id));
+        self.active_pane = active_pane.unwrap_or_else(|| self.center.first_pane());
+        cx.notify();
+    }
+
     fn handle_pane_event(
         &mut self,
-        _pane: View<Pane>,
+        pane: View<Pane>,
         event: &pane::Event,
         cx: &mut ViewContext<Self>,
     ) {
         match event {
             pane::Event::ActivateItem { .. } => self.serialize(cx),
             pane::Event::RemoveItem { .. } => self.serialize(cx),
-            pane::Event::Remove => cx.emit(PanelEvent::Close),
+            pane::Event::Remove => {
+                // The last pane is kept, closing the panel instead.
+                if self.center.remove(&pane).log_err() == Some(true) {
+                    self.pane_subscriptions.remove(&pane.entity_id());
+                    if self.active_pane == pane {
+                        self.active_pane = self.center.first_pane();
+                        cx.focus_view(&self.active_pane);
+                    }
+                    self.serialize(cx);
+                    cx.notify();
+                } else {
+                    cx.emit(PanelEvent::Close)
+                }
+            }
             pane::Event::ZoomIn => cx.emit(PanelEvent::ZoomIn),
             pane::Event::ZoomOut => cx.emit(PanelEvent::ZoomOut),
+            pane::Event::Focus => {
+                if self.active_pane != pane {
+                    self.active_pane = pane;
+                    cx.notify();
+                }
+            }
+            pane::Event::Split(direction) => self.split_pane(pane, *direction, cx),
 
             pane::Event::AddItem { item } => {
                 if let Some(workspace) = self.workspace.upgrade() {
-                    let pane = self.pane.clone();
                     workspace.update(cx, |workspace, cx| item.added_to_pane(workspace, pane, cx))
                 }
             }
@@ -325,6 +261,43 @@ impl TerminalPanel {
         }
     }
 
+    /// Splits the pane, opening a terminal in the new one in the working directory of the pane's active terminal.
+    fn split_pane(
+        &mut self,
+        pane: View<Pane>,
+        direction: SplitDirection,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some(project) = self
+            .workspace
+            .upgrade()
+            .map(|workspace| workspace.read(cx).project().clone())
+        else {
+            return;
+        };
+        let working_directory = pane
+            .read(cx)
+            .active_item()
+            .and_then(|item| item.downcast::<TerminalView>())
+            .and_then(|terminal_view| terminal_view.read(cx).terminal().read(cx).get_cwd())
+            .and_then(|cwd| project.read(cx).terminal_work_dir_for(Some(&cwd), cx));
+
+        let new_pane = self.new_pane(project, cx);
+        if self
+            .center
+            .split(&pane, &new_pane, direction)
+            .log_err()
+            .is_none()
+        {
+            return;
+        }
+        self.active_pane = new_pane.clone();
+        cx.focus_view(&new_pane);
+        self.add_terminal(working_directory, None, RevealStrategy::Always, cx)
+            .detach_and_log_err(cx);
+        cx.notify();
+    }
+
     pub fn open_terminal(
         workspace: &mut Workspace,
         action: &workspace::OpenTerminal,
@@ -347,30 +320,18 @@ impl TerminalPanel {
     }
 
     fn spawn_task(&mut self, spawn_in_terminal: &SpawnInTerminal, cx: &mut ViewContext<Self>) {
-        let mut spawn_task = spawn_in_terminal.clone();
-        // Set up shell args unconditionally, as tasks are always spawned inside of a shell.
-        let Some((shell, mut user_args)) = (match TerminalSettings::get_global(cx).shell.clone() {
-            Shell::System => std::env::var("SHELL").ok().map(|shell| (shell, Vec::new())),
-            Shell::Program(shell) => Some((shell, Vec::new())),
-            Shell::WithArguments { program, args } => Some((program, args)),
-        }) else {
+        if !spawn_in_terminal.prerequisites.is_empty() {
+            self.spawn_task_after_prerequisites(spawn_in_terminal.clone(), cx);
+            return;
+        }
+        // Tasks without a command only group their prerequisites.
+        if spawn_in_terminal.command.is_empty() {
+            return;
+        }
+        let Some(spawn_task) = Self::wrap_in_shell(spawn_in_terminal, cx) else {
             return;
         };
 
-        spawn_task.command_label = format!("{shell} -i -c `{}`", spawn_task.command_label);
-        let task_command = std::mem::replace(&mut spawn_task.command, shell);
-        let task_args = std::mem::take(&mut spawn_task.args);
-        let combined_command = task_args
-            .into_iter()
-            .fold(task_command, |mut command, arg| {
-                command.push(' ');
-                command.push_str(&arg);
-                command
-            });
-        user_args.extend(["-i".to_owned(), "-c".to_owned(), combined_command]);
-        spawn_task.args = user_args;
-        let spawn_task = spawn_task;
-
         let reveal = spawn_task.reveal;
         let allow_concurrent_runs = spawn_in_terminal.allow_concurrent_runs;
         let use_new_terminal = spawn_in_terminal.use_new_terminal;
@@ -387,7 +348,7 @@ impl TerminalPanel {
                 .detach_and_log_err(cx);
             return;
         }
-        let (existing_item_index, existing_terminal) = terminals_for_task
+        let existing_terminal = terminals_for_task
             .last()
             .expect("covered no terminals case above")
             .clone();
@@ -396,8 +357,9 @@ impl TerminalPanel {
                 !use_new_terminal,
                 "Should have handled 'allow_concurrent_runs && use_new_terminal' case above"
             );
-            self.replace_terminal(spawn_task, existing_item_index, existing_terminal, cx);
+            self.replace_terminal(spawn_task, existing_terminal, cx);
         } else {
+            let terminal_to_replace = existing_terminal.clone();
             self.deferred_tasks.insert(
                 spawn_in_terminal.id.clone(),
                 cx.spawn(|terminal_panel, mut cx| async move {
@@ -411,8 +373,7 @@ impl TerminalPanel {
                             } else {
                                 terminal_panel.replace_terminal(
                                     spawn_task,
-                                    existing_item_index,
-                                    existing_terminal,
+                                    terminal_to_replace,
                                     cx,
                                 );
                             }
@@ -423,7 +384,7 @@ impl TerminalPanel {
 
             match reveal {
                 RevealStrategy::Always => {
-                    self.activate_terminal_view(existing_item_index, cx);
+                    self.activate_terminal_view(&existing_terminal, cx);
                     let task_workspace = self.workspace.clone();
                     cx.spawn(|_, mut cx| async move {
                         task_workspace
@@ -437,6 +398,114 @@ impl TerminalPanel {
         }
     }
 
+    /// Sets up shell args unconditionally, as tasks are always spawned inside of a shell.
+    fn wrap_in_shell(
+        spawn_in_terminal: &SpawnInTerminal,
+        cx: &AppContext,
+    ) -> Option<SpawnInTerminal> {
+        let mut spawn_task = spawn_in_terminal.clone();
+        let (shell, mut user_args) = match TerminalSettings::get_global(cx).shell.clone() {
+            Shell::System => (std::env::var("SHELL").ok()?, Vec::new()),
+            Shell::Program(shell) => (shell, Vec::new()),
+            Shell::WithArguments { program, args } => (program, args),
+        };
+
+        spawn_task.command_label = format!("{shell} -i -c `{}`", spawn_task.command_label);
+        let task_command = std::mem::replace(&mut spawn_task.command, shell);
+        let task_args = std::mem::take(&mut spawn_task.args);
+        let combined_command = task_args
+            .into_iter()
+            .fold(task_command, |mut command, arg| {
+                command.push(' ');
+                command.push_str(&arg);
+                command
+            });
+        user_args.extend(["-i".to_owned(), "-c".to_owned(), combined_command]);
+        spawn_task.args = user_args;
+        Some(spawn_task)
+    }
+
+    /// Spawns the prerequisites of the task stage by stage, and the task itself after all of them succeeded.
+    /// Stops the chain as soon as any of the prerequisites fails.
+    fn spawn_task_after_prerequisites(
+        &mut self,
+        mut spawn_in_terminal: SpawnInTerminal,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let stages = std::mem::take(&mut spawn_in_terminal.prerequisites);
+        self.prerequisite_chains.insert(
+            spawn_in_terminal.id.clone(),
+            cx.spawn(|terminal_panel, mut cx| {
+                async move {
+                    for stage in stages {
+                        let terminals = terminal_panel.update(&mut cx, |terminal_panel, cx| {
+                            stage
+                                .iter()
+                                .map(|task| terminal_panel.spawn_single_task(task, cx))
+                                .collect::<Vec<_>>()
+                        })?;
+                        let terminals = join_all(terminals).await;
+                        for (task, terminal) in stage.iter().zip(terminals) {
+                            let terminal = terminal.with_context(|| {
+                                format!("spawning prerequisite task `{}`", task.label)
+                            })?;
+                            let completion = terminal.update(&mut cx, |terminal, cx| {
+                                terminal.wait_for_completed_task(cx)
+                            })?;
+                            completion.await;
+                            let status = terminal.update(&mut cx, |terminal, _| {
+                                terminal.task().map(|task| task.status)
+                            })?;
+                            if status != Some(TaskStatus::Completed { success: true }) {
+                                log::info!(
+                                    "Not running task `{}`, as its prerequisite `{}` did not succeed",
+                                    spawn_in_terminal.label,
+                                    task.label
+                                );
+                                return Ok(());
+                            }
+                        }
+                    }
+                    terminal_panel.update(&mut cx, |terminal_panel, cx| {
+                        terminal_panel.spawn_task(&spawn_in_terminal, cx)
+                    })
+                }
+                .log_err()
+            }),
+        );
+    }
+
+    /// Spawns the task without its prerequisites, reusing the last terminal of the same task once it finished, if allowed.
+    /// Returns the terminal that the task runs in.
+    pub fn spawn_single_task(
+        &mut self,
+        spawn_in_terminal: &SpawnInTerminal,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<Model<Terminal>>> {
+        let Some(spawn_task) = Self::wrap_in_shell(spawn_in_terminal, cx) else {
+            return Task::ready(Err(anyhow!("no shell to spawn the task in")));
+        };
+        let terminals_for_task = self.terminals_for_task(&spawn_in_terminal.full_label, cx);
+        let Some(existing_terminal) = terminals_for_task
+            .last()
+            .filter(|_| !spawn_in_terminal.use_new_terminal)
+            .cloned()
+        else {
+            return self.spawn_in_new_terminal(spawn_task, cx);
+        };
+
+        cx.spawn(|terminal_panel, mut cx| async move {
+            if !spawn_task.allow_concurrent_runs {
+                wait_for_terminals_tasks(terminals_for_task, &mut cx).await;
+            }
+            terminal_panel
+                .update(&mut cx, |terminal_panel, cx| {
+                    terminal_panel.replace_terminal(spawn_task, existing_terminal, cx)
+                })?
+                .context("replacing the task terminal")
+        })
+    }
+
     pub fn spawn_in_new_terminal(
         &mut self,
         spawn_task: SpawnInTerminal,
@@ -463,31 +532,39 @@ impl TerminalPanel {
             .detach_and_log_err(cx);
     }
 
-    fn terminals_for_task(
-        &self,
-        label: &str,
-        cx: &mut AppContext,
-    ) -> Vec<(usize, View<TerminalView>)> {
-        self.pane
-            .read(cx)
-            .items()
-            .enumerate()
-            .filter_map(|(index, item)| Some((index, item.act_as::<TerminalView>(cx)?)))
-            .filter_map(|(index, terminal_view)| {
-                let task_state = terminal_view.read(cx).terminal().read(cx).task()?;
-                if &task_state.full_label == label {
-                    Some((index, terminal_view))
-                } else {
-                    None
-                }
+    fn terminals_for_task(&self, label: &str, cx: &mut AppContext) -> Vec<View<TerminalView>> {
+        self.center
+            .panes()
+            .into_iter()
+            .flat_map(|pane| pane.read(cx).items())
+            .filter_map(|item| item.act_as::<TerminalView>(cx))
+            .filter(|terminal_view| {
+                terminal_view
+                    .read(cx)
+                    .terminal()
+                    .read(cx)
+                    .task()
+                    .is_some_and(|task_state| task_state.full_label == label)
             })
             .collect()
     }
 
-    fn activate_terminal_view(&self, item_index: usize, cx: &mut WindowContext) {
-        self.pane.update(cx, |pane, cx| {
+    /// Activates the terminal in the pane it is in, making that pane the active one.
+    fn activate_terminal_view(
+        &mut self,
+        terminal_view: &View<TerminalView>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let Some((pane, item_index)) = self.center.panes().into_iter().find_map(|pane| {
+            let item_index = pane.read(cx).index_for_item(terminal_view)?;
+            Some((pane.clone(), item_index))
+        }) else {
+            return;
+        };
+        pane.update(cx, |pane, cx| {
             pane.activate_item(item_index, true, true, cx)
-        })
+        });
+        self.active_pane = pane;
     }
 
     fn add_terminal(
@@ -514,7 +591,7 @@ impl TerminalPanel {
         self.pending_terminals_to_add += 1;
 
         cx.spawn(|terminal_panel, mut cx| async move {
-            let pane = terminal_panel.update(&mut cx, |this, _| this.pane.clone())?;
+            let pane = terminal_panel.update(&mut cx, |this, _| this.active_pane.clone())?;
             let result = workspace.update(&mut cx, |workspace, cx| {
                 let working_directory = if let Some(working_directory) = working_directory {
                     Some(working_directory)
@@ -555,28 +632,28 @@ impl TerminalPanel {
     }
 
     fn serialize(&mut self, cx: &mut ViewContext<Self>) {
-        let mut items_to_serialize = HashSet::default();
-        let items = self
-            .pane
-            .read(cx)
-            .items()
-            .filter_map(|item| {
-                let terminal_view = item.act_as::<TerminalView>(cx)?;
-                if terminal_view.read(cx).terminal().read(cx).task().is_some() {
-                    None
-                } else {
-                    let id = item.item_id().as_u64();
-                    items_to_serialize.insert(id);
-                    Some(id)
-                }
-            })
-            .collect::<Vec<_>>();
+        let panes = SerializedPaneGroup::new(&self.center.layout(), &self.active_pane, cx);
+        let items = panes.item_ids();
         let active_item_id = self
-            .pane
+            .active_pane
             .read(cx)
             .active_item()
             .map(|item| item.item_id().as_u64())
-            .filter(|active_id| items_to_serialize.contains(active_id));
+            .filter(|active_id| items.contains(active_id));
+        // Working directories are also saved when the terminal title changes, but not every shell reports that.
+        let working_directories = self
+            .center
+            .panes()
+            .into_iter()
+            .flat_map(|pane| serializable_terminals(pane, cx))
+            .filter_map(|terminal_view| {
+                let item_id = terminal_view.entity_id().as_u64();
+                let terminal_view = terminal_view.read(cx);
+                let workspace_id = terminal_view.workspace_id?;
+                let cwd = terminal_view.terminal().read(cx).get_cwd()?;
+                Some((item_id, workspace_id, cwd))
+            })
+            .collect::<Vec<_>>();
         let height = self.height;
         let width = self.width;
         self.pending_serialization = cx.background_executor().spawn(
@@ -587,11 +664,18 @@ impl TerminalPanel {
                         serde_json::to_string(&SerializedTerminalPanel {
                             items,
                             active_item_id,
+                            panes: Some(panes),
                             height,
                             width,
                         })?,
                     )
                     .await?;
+                for (item_id, workspace_id, cwd) in working_directories {
+                    TERMINAL_DB
+                        .save_working_directory(item_id, workspace_id, cwd)
+                        .await
+                        .log_err();
+                }
                 anyhow::Ok(())
             }
             .log_err(),
@@ -599,12 +683,11 @@ impl TerminalPanel {
     }
 
     fn replace_terminal(
-        &self,
+        &mut self,
         spawn_task: SpawnInTerminal,
-        terminal_item_index: usize,
         terminal_to_replace: View<TerminalView>,
         cx: &mut ViewContext<'_, Self>,
-    ) -> Option<()> {
+    ) -> Option<Model<Terminal>> {
         let project = self
             .workspace
             .update(cx, |workspace, _| workspace.project().clone())
@@ -618,12 +701,12 @@ impl TerminalPanel {
                 .log_err()
         })?;
         terminal_to_replace.update(cx, |terminal_to_replace, cx| {
-            terminal_to_replace.set_terminal(new_terminal, cx);
+            terminal_to_replace.set_terminal(new_terminal.clone(), cx);
         });
 
         match reveal {
             RevealStrategy::Always => {
-                self.activate_terminal_view(terminal_item_index, cx);
+                self.activate_terminal_view(&terminal_to_replace, cx);
                 let task_workspace = self.workspace.clone();
                 cx.spawn(|_, mut cx| async move {
                     task_workspace
@@ -635,23 +718,168 @@ impl TerminalPanel {
             RevealStrategy::Never => {}
         }
 
-        Some(())
+        Some(new_terminal)
     }
 
+    /// The pane of the panel that was focused last.
     pub fn pane(&self) -> &View<Pane> {
-        &self.pane
+        &self.active_pane
     }
 
     fn has_no_terminals(&self, cx: &WindowContext) -> bool {
-        self.pane.read(cx).items_len() == 0 && self.pending_terminals_to_add == 0
+        self.terminals_count(cx) == 0 && self.pending_terminals_to_add == 0
     }
+
+    fn terminals_count(&self, cx: &WindowContext) -> usize {
+        self.center
+            .panes()
+            .into_iter()
+            .map(|pane| pane.read(cx).items_len())
+            .sum()
+    }
+}
+
+fn new_terminal_pane(
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    cx: &mut ViewContext<TerminalPanel>,
+) -> View<Pane> {
+    cx.new_view(|cx| {
+        let mut pane = Pane::new(
+            workspace.clone(),
+            project,
+            Default::default(),
+            None,
+            NewTerminal.boxed_clone(),
+            cx,
+        );
+        // Dragging tabs to the edges would split the center of the workspace, not the panel.
+        pane.set_can_split(false, cx);
+        pane.set_can_navigate(false, cx);
+        pane.display_nav_history_buttons(None);
+        pane.set_should_display_tab_bar(|_| true);
+        pane.set_render_tab_bar_buttons(cx, move |pane, cx| {
+            h_flex()
+                .gap_2()
+                .child(
+                    IconButton::new("plus", IconName::Plus)
+                        .icon_size(IconSize::Small)
+                        .on_click(cx.listener(|pane, _, cx| {
+                            let focus_handle = pane.focus_handle(cx);
+                            let menu = ContextMenu::build(cx, |menu, _| {
+                                menu.action("New Terminal", workspace::NewTerminal.boxed_clone())
+                                    .entry(
+                                        "Spawn task",
+                                        Some(tasks_ui::Spawn::modal().boxed_clone()),
+                                        move |cx| {
+                                            // We want the focus to go back to terminal panel once task modal is dismissed,
+                                            // hence we focus that first. Otherwise, we'd end up without a focused element, as
+                                            // context menu will be gone the moment we spawn the modal.
+                                            cx.focus(&focus_handle);
+                                            cx.dispatch_action(
+                                                tasks_ui::Spawn::modal().boxed_clone(),
+                                            );
+                                        },
+                                    )
+                            });
+                            cx.subscribe(&menu, |pane, _, _: &DismissEvent, _| {
+                                pane.new_item_menu = None;
+                            })
+                            .detach();
+                            pane.new_item_menu = Some(menu);
+                        }))
+                        .tooltip(|cx| Tooltip::text("New...", cx)),
+                )
+                .when_some(pane.new_item_menu.as_ref(), |el, new_item_menu| {
+                    el.child(Pane::render_menu_overlay(new_item_menu))
+                })
+                .child(
+                    IconButton::new("split", IconName::Split)
+                        .icon_size(IconSize::Small)
+                        .on_click(cx.listener(|pane, _, cx| {
+                            pane.deploy_split_item_menu(cx);
+                        }))
+                        .tooltip(|cx| Tooltip::text("Split Pane", cx)),
+                )
+                .when_some(pane.split_item_menu(), |el, split_item_menu| {
+                    el.child(Pane::render_menu_overlay(split_item_menu))
+                })
+                .child({
+                    let zoomed = pane.is_zoomed();
+                    IconButton::new("toggle_zoom", IconName::Maximize)
+                        .icon_size(IconSize::Small)
+                        .selected(zoomed)
+                        .selected_icon(IconName::Minimize)
+                        .on_click(cx.listener(|pane, _, cx| {
+                            pane.toggle_zoom(&workspace::ToggleZoom, cx);
+                        }))
+                        .tooltip(move |cx| {
+                            Tooltip::for_action(
+                                if zoomed { "Zoom Out" } else { "Zoom In" },
+                                &ToggleZoom,
+                                cx,
+                            )
+                        })
+                })
+                .into_any_element()
+        });
+
+        pane.set_custom_drop_handle(cx, move |pane, dropped_item, cx| {
+            if let Some(tab) = dropped_item.downcast_ref::<DraggedTab>() {
+                let item = if &tab.pane == cx.view() {
+                    pane.item_for_index(tab.ix)
+                } else {
+                    tab.pane.read(cx).item_for_index(tab.ix)
+                };
+                if let Some(item) = item {
+                    if item.downcast::<TerminalView>().is_some() {
+                        return ControlFlow::Continue(());
+                    } else if let Some(project_path) = item.project_path(cx) {
+                        if let Some(entry_path) = workspace
+                            .update(cx, |workspace, cx| {
+                                workspace
+                                    .project()
+                                    .read(cx)
+                                    .absolute_path(&project_path, cx)
+                            })
+                            .log_err()
+                            .flatten()
+                        {
+                            add_paths_to_terminal(pane, &[entry_path], cx);
+                        }
+                    }
+                }
+            } else if let Some(&entry_id) = dropped_item.downcast_ref::<ProjectEntryId>() {
+                if let Some(entry_path) = workspace
+                    .update(cx, |workspace, cx| {
+                        let project = workspace.project().read(cx);
+                        project
+                            .path_for_entry(entry_id, cx)
+                            .and_then(|project_path| project.absolute_path(&project_path, cx))
+                    })
+                    .log_err()
+                    .flatten()
+                {
+                    add_paths_to_terminal(pane, &[entry_path], cx);
+                }
+            } else if let Some(paths) = dropped_item.downcast_ref::<ExternalPaths>() {
+                add_paths_to_terminal(pane, paths.paths(), cx);
+            }
+
+            ControlFlow::Break(())
+        });
+        let buffer_search_bar = cx.new_view(search::BufferSearchBar::new);
+        pane.toolbar()
+            .update(cx, |toolbar, cx| toolbar.add_item(buffer_search_bar, cx));
+        pane
+    })
 }
 
 async fn wait_for_terminals_tasks(
-    terminals_for_task: Vec<(usize, View<TerminalView>)>,
+    terminals_for_task: Vec<View<TerminalView>>,
     cx: &mut AsyncWindowContext,
 ) {
-    let pending_tasks = terminals_for_task.iter().filter_map(|(_, terminal)| {
+    let pending_tasks = terminals_for_task.iter().filter_map(|terminal| {
         terminal
             .update(cx, |terminal_view, cx| {
                 terminal_view
@@ -686,7 +914,7 @@ impl Render for TerminalPanel {
         let mut registrar = DivRegistrar::new(
             |panel, cx| {
                 panel
-                    .pane
+                    .active_pane
                     .read(cx)
                     .toolbar()
                     .read(cx)
@@ -695,13 +923,23 @@ impl Render for TerminalPanel {
             cx,
         );
         BufferSearchBar::register(&mut registrar);
-        registrar.into_div().size_full().child(self.pane.clone())
+        let center = self.workspace.update(cx, |workspace, cx| {
+            self.center
+                .render_detached(
+                    workspace.project(),
+                    &self.active_pane,
+                    workspace.app_state(),
+                    cx,
+                )
+                .into_any_element()
+        });
+        registrar.into_div().size_full().children(center.ok())
     }
 }
 
 impl FocusableView for TerminalPanel {
     fn focus_handle(&self, cx: &AppContext) -> FocusHandle {
-        self.pane.focus_handle(cx)
+        self.active_pane.focus_handle(cx)
     }
 }
 
@@ -749,11 +987,13 @@ impl Panel for TerminalPanel {
     }
 
     fn is_zoomed(&self, cx: &WindowContext) -> bool {
-        self.pane.read(cx).is_zoomed()
+        self.active_pane.read(cx).is_zoomed()
     }
 
     fn set_zoomed(&mut self, zoomed: bool, cx: &mut ViewContext<Self>) {
-        self.pane.update(cx, |pane, cx| pane.set_zoomed(zoomed, cx));
+        for pane in self.center.panes() {
+            pane.update(cx, |pane, cx| pane.set_zoomed(zoomed, cx));
+        }
     }
 
     fn set_active(&mut self, active: bool, cx: &mut ViewContext<Self>) {
@@ -764,7 +1004,7 @@ impl Panel for TerminalPanel {
     }
 
     fn icon_label(&self, cx: &WindowContext) -> Option<String> {
-        let count = self.pane.read(cx).items_len();
+        let count = self.terminals_count(cx);
         if count == 0 {
             None
         } else {
@@ -793,10 +1033,215 @@ impl Panel for TerminalPanel {
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct SerializedTerminalPanel {
-    items: Vec<u64>,
-    active_item_id: Option<u64>,
-    width: Option<Pixels>,
-    height: Option<Pixels>,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::SerializedAxis;
+    use gpui::{TestAppContext, VisualTestContext};
+    use settings::SettingsStore;
+    use workspace::{AppState, WORKSPACE_DB};
+
+    #[gpui::test]
+    async fn test_split_and_restore_panes(cx: &mut TestAppContext) {
+        // The terminals run real shells.
+        cx.executor().allow_parking();
+        let app_state = init_test(cx);
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| {
+                cx.new_view(|cx| TerminalPanel::new(workspace, cx))
+            })
+            .unwrap();
+
+        panel
+            .update(cx, |panel, cx| {
+                panel.add_terminal(None, None, RevealStrategy::Never, cx)
+            })
+            .await
+            .unwrap();
+        panel.update(cx, |panel, cx| {
+            let pane = panel.active_pane.clone();
+            panel.split_pane(pane, SplitDirection::Right, cx);
+        });
+        cx.run_until_parked();
+
+        let serialized = panel.update(cx, |panel, cx| {
+            assert_eq!(panel.center.panes().len(), 2);
+            assert_eq!(panel.pane_subscriptions.len(), 2);
+            assert_eq!(panel.terminals_count(cx), 2);
+            SerializedPaneGroup::new(&panel.center.layout(), &panel.active_pane, cx)
+        });
+        let SerializedPaneGroup::Group { axis, children, .. } = &serialized else {
+            panic!("expected the panes to be split, got {serialized:?}");
+        };
+        assert_eq!(*axis, SerializedAxis::Horizontal);
+        let [SerializedPaneGroup::Pane(left), SerializedPaneGroup::Pane(right)] =
+            children.as_slice()
+        else {
+            panic!("expected two panes, got {children:?}");
+        };
+        assert_eq!((left.children.len(), left.active), (1, false));
+        assert_eq!((right.children.len(), right.active), (1, true));
+
+        // Restoring the layout replaces the initial pane of a new panel.
+        let workspace_id = WORKSPACE_DB.next_id().await.unwrap();
+        let restored_panel = workspace
+            .update(cx, |workspace, cx| {
+                cx.new_view(|cx| TerminalPanel::new(workspace, cx))
+            })
+            .unwrap();
+        let mut async_cx = cx.update(|cx| cx.to_async());
+        let (layout, active_pane) = serialized
+            .deserialize(
+                restored_panel.downgrade(),
+                workspace
+                    .update(cx, |workspace, _| workspace.weak_handle())
+                    .unwrap(),
+                project.clone(),
+                workspace_id,
+                &mut async_cx,
+            )
+            .await
+            .unwrap();
+        restored_panel.update(cx, |panel, cx| {
+            panel.restore_layout(layout, active_pane, cx);
+            let panes = panel.center.panes();
+            assert_eq!(panes.len(), 2);
+            assert_eq!(panel.active_pane, *panes[1]);
+            assert_eq!(panel.pane_subscriptions.len(), 2);
+            assert_eq!(panel.terminals_count(cx), 2);
+        });
+        cx.run_until_parked();
+        restored_panel.update(cx, |panel, cx| {
+            let serialized_again =
+                SerializedPaneGroup::new(&panel.center.layout(), &panel.active_pane, cx);
+            let SerializedPaneGroup::Group { axis, children, .. } = serialized_again else {
+                panic!("expected the restored panes to be split");
+            };
+            assert_eq!(axis, SerializedAxis::Horizontal);
+            assert_eq!(children.len(), 2);
+        });
+
+        // Closing a pane drops the subscriptions to it.
+        let active_pane = restored_panel.update(cx, |panel, _| panel.active_pane.clone());
+        active_pane.update(cx, |_, cx| cx.emit(pane::Event::Remove));
+        restored_panel.update(cx, |panel, _| {
+            assert_eq!(panel.center.panes().len(), 1);
+            assert_eq!(panel.pane_subscriptions.len(), 1);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_failing_prerequisite_stops_task(cx: &mut TestAppContext) {
+        // The terminals run real shells.
+        cx.executor().allow_parking();
+        let app_state = init_test(cx);
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<TerminalSettings>(cx, |settings| {
+                    settings.shell = Some(Shell::Program("sh".to_string()));
+                });
+            });
+        });
+        let project = Project::test(app_state.fs.clone(), [], cx).await;
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| {
+                cx.new_view(|cx| TerminalPanel::new(workspace, cx))
+            })
+            .unwrap();
+
+        // A failing prerequisite stops the chain before the next stage and the task itself.
+        let failing = task("failing", "exit 1", Vec::new());
+        let skipped = task("skipped", "true", Vec::new());
+        let dependent = task(
+            "dependent",
+            "true",
+            vec![vec![failing.clone()], vec![skipped.clone()]],
+        );
+        run_prerequisites(&panel, &dependent, cx).await;
+        panel.update(cx, |panel, cx| {
+            let failing_terminals = panel.terminals_for_task(&failing.full_label, cx);
+            assert_eq!(failing_terminals.len(), 1);
+            assert_eq!(
+                failing_terminals[0]
+                    .read(cx)
+                    .terminal()
+                    .read(cx)
+                    .task()
+                    .map(|task| task.status),
+                Some(TaskStatus::Completed { success: false })
+            );
+            assert!(panel.terminals_for_task(&skipped.full_label, cx).is_empty());
+            assert!(panel
+                .terminals_for_task(&dependent.full_label, cx)
+                .is_empty());
+        });
+
+        // Once all prerequisites succeed, the task is spawned.
+        let succeeding = task("succeeding", "true", Vec::new());
+        let dependent = task("dependent", "true", vec![vec![succeeding.clone()]]);
+        run_prerequisites(&panel, &dependent, cx).await;
+        panel.update(cx, |panel, cx| {
+            assert_eq!(
+                panel.terminals_for_task(&succeeding.full_label, cx).len(),
+                1
+            );
+            assert_eq!(panel.terminals_for_task(&dependent.full_label, cx).len(), 1);
+        });
+    }
+
+    /// Spawns the task and waits until its prerequisites finished.
+    async fn run_prerequisites(
+        panel: &View<TerminalPanel>,
+        spawn_in_terminal: &SpawnInTerminal,
+        cx: &mut VisualTestContext,
+    ) {
+        let chain = panel.update(cx, |panel, cx| {
+            panel.spawn_task(spawn_in_terminal, cx);
+            panel
+                .prerequisite_chains
+                .remove(&spawn_in_terminal.id)
+                .unwrap()
+        });
+        chain.await;
+        cx.run_until_parked();
+    }
+
+    fn task(
+        label: &str,
+        command: &str,
+        prerequisites: Vec<Vec<SpawnInTerminal>>,
+    ) -> SpawnInTerminal {
+        SpawnInTerminal {
+            id: TaskId(label.to_string()),
+            full_label: label.to_string(),
+            label: label.to_string(),
+            command: command.to_string(),
+            args: Vec::new(),
+            command_label: command.to_string(),
+            cwd: None,
+            env: HashMap::default(),
+            use_new_terminal: false,
+            allow_concurrent_runs: false,
+            reveal: RevealStrategy::Never,
+            prerequisites,
+            problem_matchers: Vec::new(),
+            collect_output: false,
+        }
+    }
+
+    fn init_test(cx: &mut TestAppContext) -> Arc<AppState> {
+        let app_state = cx.update(AppState::test);
+        cx.update(|cx| {
+            Project::init_settings(cx);
+            language::init(cx);
+            editor::init(cx);
+            crate::init(cx);
+        });
+        app_state
+    }
 }
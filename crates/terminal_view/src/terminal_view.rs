@@ -3,7 +3,7 @@ pub mod terminal_element;
 pub mod terminal_panel;
 
 use collections::HashSet;
-use editor::{scroll::Autoscroll, Editor};
+use editor::{scroll::Autoscroll, Editor, MultiBuffer};
 use futures::{stream::FuturesUnordered, StreamExt};
 use gpui::{
     anchored, deferred, div, impl_actions, AnyElement, AppContext, DismissEvent, EventEmitter,
@@ -20,9 +20,12 @@ use terminal::{
         index::Point,
         term::{search::RegexSearch, TermMode},
     },
+    search_regex,
     terminal_settings::{TerminalBlink, TerminalSettings, WorkingDirectory},
-    Clear, Copy, Event, MaybeNavigationTarget, Paste, ScrollLineDown, ScrollLineUp, ScrollPageDown,
-    ScrollPageUp, ScrollToBottom, ScrollToTop, ShowCharacterPalette, TaskStatus, Terminal,
+    Clear, Copy, CopyLastCommandOutput, Event, ExportScrollback, ExportSearchMatches,
+    MaybeNavigationTarget, Paste, RerunCommand, ScrollLineDown, ScrollLineUp, ScrollPageDown,
+    ScrollPageUp, ScrollToBottom, ScrollToNextPrompt, ScrollToPreviousPrompt, ScrollToTop,
+    SelectLastCommandOutput, ShowCharacterPalette, TaskStatus, Terminal,
 };
 use terminal_element::TerminalElement;
 use ui::{h_flex, prelude::*, ContextMenu, Icon, IconName, Label, Tooltip};
@@ -54,6 +57,8 @@ const REGEX_SPECIAL_CHARS: &[char] = &[
 ];
 
 const CURSOR_BLINK_INTERVAL: Duration = Duration::from_millis(500);
+/// How often the search matches are updated while the terminal prints output.
+const SEARCH_MATCHES_UPDATE_INTERVAL: Duration = Duration::from_millis(100);
 
 ///Event to transmit the scroll from the element to the view
 #[derive(Clone, Debug, PartialEq)]
@@ -94,6 +99,7 @@ pub struct TerminalView {
     can_navigate_to_selected_word: bool,
     workspace_id: Option<WorkspaceId>,
     show_title: bool,
+    search_matches_update: Option<Task<()>>,
     _subscriptions: Vec<Subscription>,
     _terminal_subscriptions: Vec<Subscription>,
 }
@@ -170,6 +176,7 @@ impl TerminalView {
             can_navigate_to_selected_word: false,
             workspace_id,
             show_title: TerminalSettings::get_global(cx).toolbar.title,
+            search_matches_update: None,
             _subscriptions: vec![
                 focus_in,
                 focus_out,
@@ -198,7 +205,10 @@ impl TerminalView {
         cx: &mut ViewContext<Self>,
     ) {
         let context_menu = ContextMenu::build(cx, |menu, _| {
-            menu.action("Clear", Box::new(Clear))
+            menu.action("Rerun Command", Box::new(RerunCommand))
+                .action("Copy Last Command Output", Box::new(CopyLastCommandOutput))
+                .separator()
+                .action("Clear", Box::new(Clear))
                 .action("Close", Box::new(CloseActiveItem { save_intent: None }))
         });
 
@@ -282,6 +292,100 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_to_previous_prompt(
+        &mut self,
+        _: &ScrollToPreviousPrompt,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_prompt());
+        cx.notify();
+    }
+
+    fn scroll_to_next_prompt(&mut self, _: &ScrollToNextPrompt, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_prompt());
+        cx.notify();
+    }
+
+    fn select_last_command_output(
+        &mut self,
+        _: &SelectLastCommandOutput,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.terminal.update(cx, |term, _| {
+            term.select_last_command_output();
+        });
+        cx.notify();
+    }
+
+    fn copy_last_command_output(&mut self, _: &CopyLastCommandOutput, cx: &mut ViewContext<Self>) {
+        self.terminal
+            .update(cx, |term, _| term.copy_last_command_output());
+        cx.notify();
+    }
+
+    fn rerun_command(&mut self, _: &RerunCommand, cx: &mut ViewContext<Self>) {
+        self.terminal.update(cx, |term, _| term.rerun_command());
+        cx.notify();
+    }
+
+    /// Lets the search bar search the new output, at most once per [`SEARCH_MATCHES_UPDATE_INTERVAL`],
+    /// as searching the whole scrollback after each chunk of output would be too slow.
+    fn invalidate_search_matches(&mut self, cx: &mut ViewContext<Self>) {
+        if self.search_matches_update.is_some() {
+            return;
+        }
+        self.search_matches_update = Some(cx.spawn(|this, mut cx| async move {
+            Timer::after(SEARCH_MATCHES_UPDATE_INTERVAL).await;
+            this.update(&mut cx, |this, cx| {
+                this.search_matches_update = None;
+                cx.emit(SearchEvent::MatchesInvalidated);
+            })
+            .ok();
+        }));
+    }
+
+    fn export_scrollback(&mut self, _: &ExportScrollback, cx: &mut ViewContext<Self>) {
+        let text = self.terminal.read(cx).scrollback_text();
+        let title = format!("{} Scrollback", self.terminal.read(cx).title(true));
+        self.open_in_editor(text, title, cx);
+    }
+
+    fn export_search_matches(&mut self, _: &ExportSearchMatches, cx: &mut ViewContext<Self>) {
+        let text = self.terminal.read(cx).search_matches_text();
+        if text.is_empty() {
+            return;
+        }
+        let title = format!("{} Search Matches", self.terminal.read(cx).title(true));
+        self.open_in_editor(text, title, cx);
+    }
+
+    fn open_in_editor(&self, text: String, title: String, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let project = workspace.read(cx).project().clone();
+        let create_buffer = project.update(cx, |project, cx| project.create_buffer(cx));
+        let workspace = workspace.downgrade();
+        cx.spawn(|_, mut cx| async move {
+            let buffer = create_buffer.await?;
+            workspace.update(&mut cx, |workspace, cx| {
+                buffer.update(cx, |buffer, cx| buffer.edit([(0..0, text)], None, cx));
+                let multibuffer =
+                    cx.new_model(|cx| MultiBuffer::singleton(buffer, cx).with_title(title));
+                workspace.add_item_to_active_pane(
+                    Box::new(cx.new_view(|cx| {
+                        Editor::for_multibuffer(multibuffer, Some(project), true, cx)
+                    })),
+                    None,
+                    cx,
+                );
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
     pub fn should_show_cursor(&self, focused: bool, cx: &mut gpui::ViewContext<Self>) -> bool {
         //Don't blink the cursor when not focused, blinking is disabled, or paused
         if !focused
@@ -473,7 +577,7 @@ fn subscribe_for_terminal_events(
                 cx.notify();
                 cx.emit(Event::Wakeup);
                 cx.emit(ItemEvent::UpdateTab);
-                cx.emit(SearchEvent::MatchesInvalidated);
+                this.invalidate_search_matches(cx);
             }
 
             Event::Bell => {
@@ -622,6 +726,7 @@ fn subscribe_for_terminal_events(
             Event::BreadcrumbsChanged => cx.emit(ItemEvent::UpdateBreadcrumbs),
             Event::CloseTerminal => cx.emit(ItemEvent::CloseItem),
             Event::SelectionsChanged => cx.emit(SearchEvent::ActiveMatchChanged),
+            Event::TaskFinished => cx.emit(ItemEvent::UpdateTab),
         });
     vec![terminal_subscription, terminal_events_subscription]
 }
@@ -723,12 +828,14 @@ fn regex_to_literal(regex: &str) -> String {
 }
 
 pub fn regex_search_for_query(query: &project::search::SearchQuery) -> Option<RegexSearch> {
-    let query = query.as_str();
-    if query == "." {
+    let pattern = match query {
+        SearchQuery::Text { .. } => regex_to_literal(query.as_str()),
+        SearchQuery::Regex { .. } => query.as_str().to_string(),
+    };
+    if pattern == "." {
         return None;
     }
-    let searcher = RegexSearch::new(&query);
-    searcher.ok()
+    search_regex(&pattern, query.case_sensitive(), query.whole_word())
 }
 
 impl TerminalView {
@@ -780,6 +887,13 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::scroll_page_down))
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_prompt))
+            .on_action(cx.listener(TerminalView::scroll_to_next_prompt))
+            .on_action(cx.listener(TerminalView::select_last_command_output))
+            .on_action(cx.listener(TerminalView::copy_last_command_output))
+            .on_action(cx.listener(TerminalView::rerun_command))
+            .on_action(cx.listener(TerminalView::export_scrollback))
+            .on_action(cx.listener(TerminalView::export_search_matches))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
             .on_key_down(cx.listener(Self::key_down))
@@ -1005,8 +1119,8 @@ impl SearchableItem for TerminalView {
 
     fn supported_options() -> SearchOptions {
         SearchOptions {
-            case: false,
-            word: false,
+            case: true,
+            word: true,
             regex: true,
             replacement: false,
             selection: false,
@@ -1054,22 +1168,7 @@ impl SearchableItem for TerminalView {
         query: Arc<SearchQuery>,
         cx: &mut ViewContext<Self>,
     ) -> Task<Vec<Self::Match>> {
-        let searcher = match &*query {
-            SearchQuery::Text { .. } => regex_search_for_query(
-                &(SearchQuery::text(
-                    regex_to_literal(&query.as_str()),
-                    query.whole_word(),
-                    query.case_sensitive(),
-                    query.include_ignored(),
-                    query.files_to_include().clone(),
-                    query.files_to_exclude().clone(),
-                )
-                .unwrap()),
-            ),
-            SearchQuery::Regex { .. } => regex_search_for_query(&query),
-        };
-
-        if let Some(s) = searcher {
+        if let Some(s) = regex_search_for_query(&query) {
             self.terminal()
                 .update(cx, |term, cx| term.find_matches(s, cx))
         } else {
@@ -0,0 +1,317 @@
+use std::path::{Path, PathBuf};
+
+use collections::HashMap;
+use language::{BufferSnapshot, RunnableRange};
+use task::{RevealStrategy, SpawnInTerminal, TaskId, TerminalWorkDir};
+
+use crate::results::{parse_go_test_json, parse_libtest_output, parse_pytest_output, TestResult};
+
+/// A test harness whose tests are discovered with the runnable queries of a language,
+/// and whose results can be parsed from its output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TestFramework {
+    Libtest,
+    Pytest,
+    GoTest,
+}
+
+/// What part of the discovered tests to run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TestScope {
+    /// A single test of the file, by the name it was discovered with.
+    Test(String),
+    /// All tests of the file.
+    File,
+    /// All tests in the directory the command runs in.
+    All,
+}
+
+/// A command that runs tests and prints their results in a format that the framework can parse.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+}
+
+impl TestCommand {
+    /// The task that runs the command in a terminal, collecting its output for the results to be parsed from.
+    pub fn spawn_in_terminal(&self) -> SpawnInTerminal {
+        let command_label = std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let label = format!("Tests: {command_label}");
+        SpawnInTerminal {
+            id: TaskId(format!("test-explorer-{}", self.cwd.display())),
+            full_label: label.clone(),
+            label,
+            command: self.program.clone(),
+            // The arguments are joined into a shell command without being quoted.
+            args: self
+                .args
+                .iter()
+                .map(|arg| {
+                    shlex::try_quote(arg).map_or_else(|_| arg.clone(), |arg| arg.into_owned())
+                })
+                .collect(),
+            command_label,
+            cwd: Some(TerminalWorkDir::Local(self.cwd.clone())),
+            env: HashMap::default(),
+            use_new_terminal: false,
+            allow_concurrent_runs: false,
+            reveal: RevealStrategy::Never,
+            prerequisites: Vec::new(),
+            problem_matchers: Vec::new(),
+            collect_output: true,
+        }
+    }
+}
+
+impl TestFramework {
+    /// The framework that runs the tests with the given runnable tag, as set in `runnables.scm`.
+    pub fn for_runnable_tag(tag: &str) -> Option<Self> {
+        match tag {
+            "rust-test" => Some(Self::Libtest),
+            "python-unittest-method" | "python-pytest-function" => Some(Self::Pytest),
+            "go-test" => Some(Self::GoTest),
+            _ => None,
+        }
+    }
+
+    /// Whether a file at the given path may contain tests of this framework.
+    pub fn matches_path(&self, path: &Path) -> bool {
+        let extension = path.extension().and_then(|extension| extension.to_str());
+        match self {
+            Self::Libtest => extension == Some("rs"),
+            Self::Pytest => extension == Some("py"),
+            Self::GoTest => path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .map_or(false, |file_name| file_name.ends_with("_test.go")),
+        }
+    }
+
+    /// Whether the text of a file may contain tests of this framework, to avoid parsing the ones that cannot.
+    pub fn matches_text(&self, text: &str) -> bool {
+        match self {
+            Self::Libtest => text.contains("test]"),
+            Self::Pytest => text.contains("TestCase") || text.contains("def test"),
+            Self::GoTest => text.contains("func Test"),
+        }
+    }
+
+    /// The name of the test that the runnable tags, in the form that is used to select it for a run.
+    /// `path` is the path of the file, relative to its worktree.
+    pub fn test_name(
+        &self,
+        path: &Path,
+        buffer: &BufferSnapshot,
+        runnable: &RunnableRange,
+    ) -> Option<String> {
+        match self {
+            Self::Libtest => {
+                let mut name = rust_module_path(path);
+                name.extend(
+                    buffer
+                        .symbols_containing(runnable.run_range.start, None)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|item| {
+                            let (keyword, name) = item.text.rsplit_once(' ')?;
+                            keyword.ends_with("mod").then(|| name.to_string())
+                        }),
+                );
+                name.push(buffer.text_for_range(runnable.run_range.clone()).collect());
+                Some(name.join("::"))
+            }
+            Self::Pytest => match runnable.extra_captures.get("_pytest_function_name") {
+                Some(function_name) => Some(function_name.clone()),
+                None => Some(format!(
+                    "{}::{}",
+                    runnable.extra_captures.get("_unittest_class_name")?,
+                    runnable.extra_captures.get("_unittest_method_name")?
+                )),
+            },
+            Self::GoTest => Some(buffer.text_for_range(runnable.run_range.clone()).collect()),
+        }
+    }
+
+    /// Whether a reported result belongs to the test, discovered under the given name in the given file.
+    pub fn result_matches(&self, result: &TestResult, path: &Path, test_name: &str) -> bool {
+        match self {
+            Self::Libtest | Self::GoTest => result.name == test_name,
+            Self::Pytest => {
+                let Some((result_path, result_test)) = result.name.split_once("::") else {
+                    return false;
+                };
+                result_test == test_name && path.ends_with(result_path)
+            }
+        }
+    }
+
+    /// The command that runs the tests of the given scope.
+    ///
+    /// `path` is the absolute path of a file with tests, and `tests` are the names of all tests
+    /// discovered in it. `root` is the directory to run all tests of the project in.
+    pub fn command(
+        &self,
+        scope: &TestScope,
+        path: &Path,
+        tests: &[String],
+        root: &Path,
+    ) -> TestCommand {
+        let directory = path.parent().unwrap_or(root).to_path_buf();
+        match self {
+            Self::Libtest => {
+                let mut args = vec!["test".to_string(), "--no-fail-fast".to_string()];
+                // Test names are full module paths, which must not match the tests of nested modules too.
+                match scope {
+                    TestScope::Test(name) => {
+                        args.extend(["--".to_string(), "--exact".to_string(), name.clone()])
+                    }
+                    TestScope::File => {
+                        args.extend(["--".to_string(), "--exact".to_string()]);
+                        args.extend(tests.iter().cloned());
+                    }
+                    TestScope::All => {}
+                }
+                TestCommand {
+                    program: "cargo".to_string(),
+                    args,
+                    // Cargo runs the tests of the package that the directory belongs to.
+                    cwd: if *scope == TestScope::All {
+                        root.to_path_buf()
+                    } else {
+                        directory
+                    },
+                }
+            }
+            Self::Pytest => {
+                let mut args = vec!["-m".to_string(), "pytest".to_string(), "-v".to_string()];
+                let path = path.strip_prefix(root).unwrap_or(path).to_string_lossy();
+                match scope {
+                    TestScope::Test(name) => args.push(format!("{path}::{name}")),
+                    TestScope::File => args.push(path.to_string()),
+                    TestScope::All => {}
+                }
+                TestCommand {
+                    program: "python3".to_string(),
+                    args,
+                    cwd: root.to_path_buf(),
+                }
+            }
+            Self::GoTest => {
+                let mut args = vec!["test".to_string(), "-json".to_string()];
+                let names = match scope {
+                    TestScope::Test(name) => Some(name.clone()),
+                    TestScope::File => Some(tests.join("|")),
+                    TestScope::All => None,
+                };
+                match names {
+                    Some(names) => {
+                        args.extend(["-run".to_string(), format!("^({names})$"), ".".to_string()])
+                    }
+                    None => args.push("./...".to_string()),
+                }
+                TestCommand {
+                    program: "go".to_string(),
+                    args,
+                    cwd: if *scope == TestScope::All {
+                        root.to_path_buf()
+                    } else {
+                        directory
+                    },
+                }
+            }
+        }
+    }
+
+    pub fn parse_results(&self, output: &str) -> Vec<TestResult> {
+        match self {
+            Self::Libtest => parse_libtest_output(output),
+            Self::Pytest => parse_pytest_output(output),
+            Self::GoTest => parse_go_test_json(output),
+        }
+    }
+}
+
+/// The module path of a Rust file within its crate target, following the conventional Cargo layout.
+///
+/// Files of `src` are modules of the library or the main binary, while the files directly in `src/bin`,
+/// `tests`, `benches` and `examples` are crate roots of their own, as are the `main.rs` files of
+/// their subdirectories.
+fn rust_module_path(path: &Path) -> Vec<String> {
+    let components = path
+        .iter()
+        .map(|component| component.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let (mut modules, separate_targets) = match components.iter().rposition(|c| c == "src") {
+        Some(src_ix) => {
+            let modules = components[src_ix + 1..].to_vec();
+            match modules.split_first() {
+                Some((first, binary)) if first == "bin" => (binary.to_vec(), true),
+                _ => (modules, false),
+            }
+        }
+        None => match components
+            .iter()
+            .rposition(|c| matches!(c.as_str(), "tests" | "benches" | "examples"))
+        {
+            Some(target_dir_ix) => (components[target_dir_ix + 1..].to_vec(), true),
+            None => return Vec::new(),
+        },
+    };
+    if separate_targets {
+        // The file or directory of the target itself.
+        if modules.len() < 2 {
+            return Vec::new();
+        }
+        modules.remove(0);
+    }
+
+    let Some(file_name) = modules.pop() else {
+        return Vec::new();
+    };
+    let Some(stem) = Path::new(&file_name).file_stem() else {
+        return modules;
+    };
+    let stem = stem.to_string_lossy();
+    let is_crate_root = modules.is_empty()
+        && (separate_targets && stem == "main"
+            || !separate_targets && matches!(&*stem, "lib" | "main"));
+    if stem != "mod" && !is_crate_root {
+        modules.push(stem.into_owned());
+    }
+    modules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rust_module_path() {
+        for (path, module_path) in [
+            ("src/lib.rs", ""),
+            ("src/main.rs", ""),
+            ("src/math.rs", "math"),
+            ("crates/calc/src/math/mod.rs", "math"),
+            ("crates/calc/src/math/ops/add.rs", "math::ops::add"),
+            ("src/tests/helpers.rs", "tests::helpers"),
+            ("src/bin/tool.rs", ""),
+            ("src/bin/tool/main.rs", ""),
+            ("src/bin/tool/args.rs", "args"),
+            ("tests/integration.rs", ""),
+            ("tests/integration/main.rs", ""),
+            ("tests/integration/common.rs", "common"),
+            ("build.rs", ""),
+        ] {
+            assert_eq!(
+                rust_module_path(Path::new(path)).join("::"),
+                module_path,
+                "module path of {path}"
+            );
+        }
+    }
+}
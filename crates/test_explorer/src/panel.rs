@@ -0,0 +1,1333 @@
+use crate::{
+    framework::{TestCommand, TestFramework, TestScope},
+    results::{TestOutcome, TestResult},
+    JumpToFailure, RefreshTests, RunAllTests, RunFileTests, ToggleFocus,
+};
+use anyhow::Context as _;
+use collections::{BTreeMap, HashMap, HashSet};
+use db::kvp::KEY_VALUE_STORE;
+use editor::{scroll::Autoscroll, Editor};
+use fs::Fs;
+use futures::channel::oneshot;
+use gpui::{
+    Action, AppContext, AsyncAppContext, AsyncWindowContext, EntityId, EventEmitter, FocusHandle,
+    FocusableView, Hsla, Model, Subscription, Task, View, WeakView,
+};
+use language::{Anchor, Buffer, BufferSnapshot, LanguageRegistry, Point};
+use project::{Project, WorktreeId};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use task::SpawnInTerminal;
+use terminal::TaskStatus;
+use terminal_view::terminal_panel::TerminalPanel;
+use ui::{prelude::*, ListItem, Tooltip};
+use util::{ResultExt, TryFutureExt};
+use workspace::{
+    dock::{DockPosition, Panel, PanelEvent},
+    Workspace,
+};
+
+const TEST_EXPLORER_PANEL_KEY: &str = "TestExplorerPanel";
+const DISCOVERY_DEBOUNCE: Duration = Duration::from_millis(100);
+
+#[derive(Serialize, Deserialize)]
+struct SerializedTestExplorerPanel {
+    size: Option<Pixels>,
+    position: Option<SerializedDockPosition>,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SerializedDockPosition {
+    Left,
+    Bottom,
+    Right,
+}
+
+/// A worktree file, identified by its worktree and its path relative to it.
+type FileKey = (WorktreeId, Arc<Path>);
+
+/// A discovered test, identified by its file and the name it was discovered with.
+type TestKey = (WorktreeId, Arc<Path>, String);
+
+/// A file with tests, discovered with the runnable queries of its language.
+struct TestFile {
+    abs_path: PathBuf,
+    /// The root of the worktree that the file belongs to.
+    root: PathBuf,
+    framework: TestFramework,
+    tests: Vec<DiscoveredTest>,
+}
+
+struct DiscoveredTest {
+    name: String,
+    row: u32,
+}
+
+enum TestState {
+    Running,
+    Finished(TestResult),
+}
+
+/// Gutter highlights of the tests that passed during their last run.
+enum PassedTests {}
+
+/// Gutter highlights of the tests that failed during their last run, and of their failure locations.
+enum FailedTests {}
+
+pub struct TestExplorerPanel {
+    workspace: WeakView<Workspace>,
+    project: Model<Project>,
+    focus_handle: FocusHandle,
+    size: Option<Pixels>,
+    position: DockPosition,
+    files: BTreeMap<FileKey, TestFile>,
+    states: HashMap<TestKey, TestState>,
+    /// The failed test whose output is shown.
+    expanded_test: Option<TestKey>,
+    /// Files to look for tests in during the next discovery, unless all files are.
+    pending_discovery: HashSet<FileKey>,
+    full_discovery_pending: bool,
+    discovery_task: Task<Option<()>>,
+    run_task: Task<Option<()>>,
+    pending_serialization: Task<Option<()>>,
+    /// Subscriptions to the edits of the open buffers, whose unsaved tests are discovered too.
+    buffer_subscriptions: HashMap<EntityId, Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl TestExplorerPanel {
+    pub async fn load(
+        workspace: WeakView<Workspace>,
+        mut cx: AsyncWindowContext,
+    ) -> anyhow::Result<View<Self>> {
+        let serialized_panel = cx
+            .background_executor()
+            .spawn(async move { KEY_VALUE_STORE.read_kvp(TEST_EXPLORER_PANEL_KEY) })
+            .await
+            .context("loading test explorer panel")
+            .log_err()
+            .flatten()
+            .map(|panel| serde_json::from_str::<SerializedTestExplorerPanel>(&panel))
+            .transpose()
+            .log_err()
+            .flatten();
+
+        workspace.update(&mut cx, |workspace, cx| {
+            let panel = Self::new(workspace, cx);
+            if let Some(serialized_panel) = serialized_panel {
+                panel.update(cx, |panel, cx| {
+                    panel.size = serialized_panel.size.map(|px| px.round());
+                    if let Some(position) = serialized_panel.position {
+                        panel.position = match position {
+                            SerializedDockPosition::Left => DockPosition::Left,
+                            SerializedDockPosition::Bottom => DockPosition::Bottom,
+                            SerializedDockPosition::Right => DockPosition::Right,
+                        };
+                    }
+                    cx.notify();
+                });
+            }
+            panel
+        })
+    }
+
+    pub fn new(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) -> View<Self> {
+        let project = workspace.project().clone();
+        let workspace_view = cx.view().clone();
+        cx.new_view(|cx| {
+            let subscriptions = vec![
+                cx.subscribe(&project, |this, _, event, cx| match event {
+                    project::Event::WorktreeAdded | project::Event::WorktreeRemoved(_) => {
+                        this.full_discovery_pending = true;
+                        this.schedule_discovery(cx);
+                    }
+                    project::Event::WorktreeUpdatedEntries(worktree_id, entries) => {
+                        this.pending_discovery.extend(
+                            entries
+                                .iter()
+                                .map(|(path, _, _)| (*worktree_id, path.clone())),
+                        );
+                        this.schedule_discovery(cx);
+                    }
+                    _ => {}
+                }),
+                cx.subscribe(&workspace_view, |this, _, event, cx| {
+                    if let workspace::Event::ItemAdded = event {
+                        this.watch_open_buffers(cx);
+                        this.update_gutter_markers(cx);
+                    }
+                }),
+            ];
+            let mut this = Self {
+                workspace: workspace_view.downgrade(),
+                project,
+                focus_handle: cx.focus_handle(),
+                size: None,
+                position: DockPosition::Left,
+                files: BTreeMap::default(),
+                states: HashMap::default(),
+                expanded_test: None,
+                pending_discovery: HashSet::default(),
+                full_discovery_pending: true,
+                discovery_task: Task::ready(None),
+                run_task: Task::ready(None),
+                pending_serialization: Task::ready(None),
+                buffer_subscriptions: HashMap::default(),
+                _subscriptions: subscriptions,
+            };
+            this.watch_open_buffers(cx);
+            this.schedule_discovery(cx);
+            this
+        })
+    }
+
+    fn serialize(&mut self, cx: &mut ViewContext<Self>) {
+        let size = self.size;
+        let position = match self.position {
+            DockPosition::Left => SerializedDockPosition::Left,
+            DockPosition::Bottom => SerializedDockPosition::Bottom,
+            DockPosition::Right => SerializedDockPosition::Right,
+        };
+        self.pending_serialization = cx.background_executor().spawn(
+            async move {
+                KEY_VALUE_STORE
+                    .write_kvp(
+                        TEST_EXPLORER_PANEL_KEY.into(),
+                        serde_json::to_string(&SerializedTestExplorerPanel {
+                            size,
+                            position: Some(position),
+                        })?,
+                    )
+                    .await?;
+                anyhow::Ok(())
+            }
+            .log_err(),
+        );
+    }
+
+    /// Rediscovers the tests of the open buffers when they are edited, and when they are closed,
+    /// possibly without saving their changes.
+    fn watch_open_buffers(&mut self, cx: &mut ViewContext<Self>) {
+        for buffer in self.project.read(cx).opened_buffers() {
+            let buffer_id = buffer.entity_id();
+            if self.buffer_subscriptions.contains_key(&buffer_id) {
+                continue;
+            }
+            let subscription = cx.subscribe(&buffer, |this, buffer, event, cx| {
+                if let language::Event::Edited = event {
+                    if let Some(key) = buffer_file_key(&buffer, cx) {
+                        this.pending_discovery.insert(key);
+                        this.schedule_discovery(cx);
+                    }
+                }
+            });
+            self.buffer_subscriptions.insert(buffer_id, subscription);
+            let key = buffer_file_key(&buffer, cx);
+            cx.observe_release(&buffer, move |this, _, cx| {
+                this.buffer_subscriptions.remove(&buffer_id);
+                if let Some(key) = key.clone() {
+                    this.pending_discovery.insert(key);
+                    this.schedule_discovery(cx);
+                }
+            })
+            .detach();
+        }
+    }
+
+    fn refresh_tests(&mut self, _: &RefreshTests, cx: &mut ViewContext<Self>) {
+        self.full_discovery_pending = true;
+        self.schedule_discovery(cx);
+    }
+
+    /// Looks for tests in the pending files, or in all files of the visible local worktrees.
+    ///
+    /// Files are parsed in detached buffers, so that the project's language servers are not
+    /// told about every file with tests.
+    fn schedule_discovery(&mut self, cx: &mut ViewContext<Self>) {
+        self.discovery_task = cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(DISCOVERY_DEBOUNCE).await;
+            let (full, candidates, removed, fs, languages) = this
+                .update(&mut cx, |this, cx| {
+                    let (candidates, removed) = this.discovery_candidates(cx);
+                    let project = this.project.read(cx);
+                    (
+                        this.full_discovery_pending,
+                        candidates,
+                        removed,
+                        project.fs().clone(),
+                        project.languages().clone(),
+                    )
+                })
+                .ok()?;
+
+            let mut discovered = Vec::new();
+            for (key, abs_path, root, unsaved_text) in candidates {
+                let file =
+                    discover_tests(&key.1, &abs_path, unsaved_text, &fs, &languages, &mut cx)
+                        .await
+                        .log_err()
+                        .flatten();
+                discovered.push((key, abs_path, root, file));
+            }
+
+            this.update(&mut cx, |this, cx| {
+                if full {
+                    this.full_discovery_pending = false;
+                    this.files.clear();
+                }
+                for key in removed {
+                    this.pending_discovery.remove(&key);
+                    this.files.remove(&key);
+                }
+                for (key, abs_path, root, file) in discovered {
+                    this.pending_discovery.remove(&key);
+                    match file {
+                        Some((framework, tests)) => {
+                            this.files.insert(
+                                key,
+                                TestFile {
+                                    abs_path,
+                                    root,
+                                    framework,
+                                    tests,
+                                },
+                            );
+                        }
+                        None => {
+                            this.files.remove(&key);
+                        }
+                    }
+                }
+                let files = &this.files;
+                this.states.retain(|(worktree_id, path, name), _| {
+                    files
+                        .get(&(*worktree_id, path.clone()))
+                        .map_or(false, |file| {
+                            file.tests.iter().any(|test| &test.name == name)
+                        })
+                });
+                this.update_gutter_markers(cx);
+                cx.notify();
+            })
+            .ok()
+        });
+    }
+
+    /// The files to look for tests in, with their absolute paths, the roots of their worktrees
+    /// and the texts of their buffers that have unsaved changes, and the pending files that do not exist anymore.
+    fn discovery_candidates(
+        &self,
+        cx: &AppContext,
+    ) -> (
+        Vec<(FileKey, PathBuf, PathBuf, Option<String>)>,
+        Vec<FileKey>,
+    ) {
+        let mut candidates = Vec::new();
+        let mut removed = Vec::new();
+        let mut unsaved_texts = self
+            .project
+            .read(cx)
+            .opened_buffers()
+            .into_iter()
+            .filter_map(|buffer| {
+                let buffer = buffer.read(cx);
+                if !buffer.is_dirty() {
+                    return None;
+                }
+                let file = buffer.file()?;
+                Some((
+                    (
+                        WorktreeId::from_usize(file.worktree_id()),
+                        file.path().clone(),
+                    ),
+                    buffer.text(),
+                ))
+            })
+            .collect::<HashMap<_, _>>();
+        let may_contain_tests = |path: &Path| {
+            [
+                TestFramework::Libtest,
+                TestFramework::Pytest,
+                TestFramework::GoTest,
+            ]
+            .iter()
+            .any(|framework| framework.matches_path(path))
+        };
+
+        let project = self.project.read(cx);
+        if self.full_discovery_pending {
+            for worktree in project.visible_worktrees(cx) {
+                let worktree = worktree.read(cx);
+                if !worktree.is_local() {
+                    continue;
+                }
+                let root = worktree.abs_path().to_path_buf();
+                for entry in worktree.files(false, 0) {
+                    if may_contain_tests(&entry.path) {
+                        let key = (worktree.id(), entry.path.clone());
+                        let unsaved_text = unsaved_texts.remove(&key);
+                        candidates.push((key, root.join(&entry.path), root.clone(), unsaved_text));
+                    }
+                }
+            }
+        } else {
+            for (worktree_id, path) in &self.pending_discovery {
+                let key = (*worktree_id, path.clone());
+                let Some(worktree) = project.worktree_for_id(*worktree_id, cx) else {
+                    removed.push(key);
+                    continue;
+                };
+                let worktree = worktree.read(cx);
+                match worktree.entry_for_path(path) {
+                    Some(entry) if entry.is_file() && may_contain_tests(path) => {
+                        let root = worktree.abs_path().to_path_buf();
+                        let unsaved_text = unsaved_texts.remove(&key);
+                        candidates.push((key, root.join(path), root, unsaved_text));
+                    }
+                    _ => removed.push(key),
+                }
+            }
+        }
+        (candidates, removed)
+    }
+
+    pub(crate) fn run_all_tests(&mut self, _: &RunAllTests, cx: &mut ViewContext<Self>) {
+        let mut runs = Vec::<(TestFramework, TestCommand, Vec<TestKey>)>::new();
+        for ((worktree_id, path), file) in &self.files {
+            let command = file
+                .framework
+                .command(&TestScope::All, &file.abs_path, &[], &file.root);
+            let keys = file
+                .tests
+                .iter()
+                .map(|test| (*worktree_id, path.clone(), test.name.clone()));
+            match runs.iter_mut().find(|(framework, run_command, _)| {
+                *framework == file.framework && *run_command == command
+            }) {
+                Some((_, _, run_keys)) => run_keys.extend(keys),
+                None => runs.push((file.framework, command, keys.collect())),
+            }
+        }
+        self.run(runs, cx);
+    }
+
+    pub(crate) fn run_file_tests(&mut self, _: &RunFileTests, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let project_path = workspace
+            .read(cx)
+            .active_item(cx)
+            .and_then(|item| item.project_path(cx));
+        if let Some(project_path) = project_path {
+            self.run_scope(
+                (project_path.worktree_id, project_path.path),
+                TestScope::File,
+                cx,
+            );
+        }
+    }
+
+    fn run_scope(&mut self, key: FileKey, scope: TestScope, cx: &mut ViewContext<Self>) {
+        let Some(file) = self.files.get(&key) else {
+            return;
+        };
+        let names = file
+            .tests
+            .iter()
+            .map(|test| test.name.clone())
+            .collect::<Vec<_>>();
+        let command = file
+            .framework
+            .command(&scope, &file.abs_path, &names, &file.root);
+        let (worktree_id, path) = key;
+        let keys = match &scope {
+            TestScope::Test(name) => vec![(worktree_id, path, name.clone())],
+            TestScope::File | TestScope::All => names
+                .into_iter()
+                .map(|name| (worktree_id, path.clone(), name))
+                .collect(),
+        };
+        self.run(vec![(file.framework, command, keys)], cx);
+    }
+
+    /// Runs the commands one after another, replacing any tests that are still running.
+    fn run(
+        &mut self,
+        runs: Vec<(TestFramework, TestCommand, Vec<TestKey>)>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        if runs.is_empty() {
+            return;
+        }
+        self.states
+            .retain(|_, state| !matches!(state, TestState::Running));
+        for (_, _, keys) in &runs {
+            for key in keys {
+                self.states.insert(key.clone(), TestState::Running);
+            }
+        }
+        self.update_gutter_markers(cx);
+        cx.notify();
+
+        let workspace = self.workspace.clone();
+        self.run_task = cx.spawn(|this, mut cx| {
+            async move {
+                for (framework, command, keys) in runs {
+                    let spawn_in_terminal = command.spawn_in_terminal();
+                    let (output, success) =
+                        match run_in_terminal(&workspace, &spawn_in_terminal, &mut cx).await {
+                            Ok(result) => result,
+                            Err(error) => (format!("{error:#}"), false),
+                        };
+                    this.update(&mut cx, |this, cx| {
+                        this.apply_results(framework, &command, keys, &output, success, cx)
+                    })?;
+                }
+                anyhow::Ok(())
+            }
+            .log_err()
+        });
+    }
+
+    /// Updates the states of the tests that the command ran with the results printed in its output.
+    fn apply_results(
+        &mut self,
+        framework: TestFramework,
+        command: &TestCommand,
+        keys: Vec<TestKey>,
+        output: &str,
+        success: bool,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let results = framework.parse_results(output);
+        for key in keys {
+            let (worktree_id, path, name) = &key;
+            let result = results
+                .iter()
+                .find(|result| framework.result_matches(result, path, name));
+            let state = match result {
+                Some(result) => {
+                    let mut result = result.clone();
+                    if let Some(location) = result.failure_location.as_mut() {
+                        location.path = self
+                            .files
+                            .get(&(*worktree_id, path.clone()))
+                            .filter(|file| file.abs_path.ends_with(&location.path))
+                            .map(|file| file.abs_path.clone())
+                            .unwrap_or_else(|| command.cwd.join(&location.path));
+                    }
+                    TestState::Finished(result)
+                }
+                // Nothing ran, the tests probably did not build.
+                None if results.is_empty() && !success => TestState::Finished(TestResult {
+                    name: name.clone(),
+                    outcome: TestOutcome::Failed,
+                    output: output.trim().to_string(),
+                    failure_location: None,
+                }),
+                None => {
+                    self.states.remove(&key);
+                    continue;
+                }
+            };
+            self.states.insert(key, state);
+        }
+        self.update_gutter_markers(cx);
+        cx.notify();
+    }
+
+    fn outcome(&self, key: &TestKey) -> Option<TestOutcome> {
+        match self.states.get(key)? {
+            TestState::Running => None,
+            TestState::Finished(result) => Some(result.outcome),
+        }
+    }
+
+    /// Highlights the tests of the open editors in the gutter by their last outcome.
+    fn update_gutter_markers(&mut self, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let editors = workspace
+            .read(cx)
+            .items_of_type::<Editor>(cx)
+            .collect::<Vec<_>>();
+        for editor in editors {
+            let mut passed_rows = Vec::new();
+            let mut failed_rows = Vec::new();
+            let file = editor
+                .read(cx)
+                .buffer()
+                .read(cx)
+                .as_singleton()
+                .and_then(|buffer| {
+                    let file = buffer.read(cx).file()?;
+                    Some((file.worktree_id(), file.path().clone()))
+                });
+            if let Some((worktree_id, path)) = file {
+                let worktree_id = WorktreeId::from_usize(worktree_id);
+                if let Some(test_file) = self.files.get(&(worktree_id, path.clone())) {
+                    for test in &test_file.tests {
+                        let key = (worktree_id, path.clone(), test.name.clone());
+                        match self.states.get(&key) {
+                            Some(TestState::Finished(result)) => match result.outcome {
+                                TestOutcome::Passed => passed_rows.push(test.row),
+                                TestOutcome::Failed => {
+                                    failed_rows.push(test.row);
+                                    failed_rows.extend(
+                                        result
+                                            .failure_location
+                                            .as_ref()
+                                            .filter(|location| location.path == test_file.abs_path)
+                                            .map(|location| location.row),
+                                    );
+                                }
+                                TestOutcome::Ignored => {}
+                            },
+                            Some(TestState::Running) | None => {}
+                        }
+                    }
+                }
+            }
+
+            editor.update(cx, |editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let ranges = |mut rows: Vec<u32>| {
+                    rows.sort_unstable();
+                    rows.dedup();
+                    rows.into_iter()
+                        .filter(|row| *row <= snapshot.max_point().row)
+                        .map(|row| {
+                            let anchor = snapshot.anchor_before(Point::new(row, 0));
+                            anchor..anchor
+                        })
+                        .collect::<Vec<_>>()
+                };
+                let passed_ranges = ranges(passed_rows);
+                let failed_ranges = ranges(failed_rows);
+                if passed_ranges.is_empty() {
+                    editor.clear_gutter_highlights::<PassedTests>(cx);
+                } else {
+                    editor.highlight_gutter::<PassedTests>(&passed_ranges, passed_color, cx);
+                }
+                if failed_ranges.is_empty() {
+                    editor.clear_gutter_highlights::<FailedTests>(cx);
+                } else {
+                    editor.highlight_gutter::<FailedTests>(&failed_ranges, failed_color, cx);
+                }
+            });
+        }
+    }
+
+    fn open_test(&mut self, key: &TestKey, cx: &mut ViewContext<Self>) {
+        let (worktree_id, path, name) = key;
+        let Some(file) = self.files.get(&(*worktree_id, path.clone())) else {
+            return;
+        };
+        let Some(test) = file.tests.iter().find(|test| &test.name == name) else {
+            return;
+        };
+        self.open_location(file.abs_path.clone(), Point::new(test.row, 0), cx);
+    }
+
+    /// Opens the location where the test failed, or the test itself if it is not known.
+    fn open_failure(&mut self, key: &TestKey, cx: &mut ViewContext<Self>) {
+        let location = match self.states.get(key) {
+            Some(TestState::Finished(result)) => result.failure_location.clone(),
+            _ => None,
+        };
+        match location {
+            Some(location) => {
+                self.open_location(location.path, Point::new(location.row, location.column), cx)
+            }
+            None => self.open_test(key, cx),
+        }
+    }
+
+    pub(crate) fn jump_to_failure(&mut self, _: &JumpToFailure, cx: &mut ViewContext<Self>) {
+        let failed_tests = self.failed_tests();
+        if failed_tests.is_empty() {
+            return;
+        }
+        // Cycle through the failures, starting after the one whose output is shown.
+        let next_ix = self
+            .expanded_test
+            .as_ref()
+            .and_then(|expanded| failed_tests.iter().position(|key| key == expanded))
+            .map_or(0, |ix| (ix + 1) % failed_tests.len());
+        let key = failed_tests[next_ix].clone();
+        self.expanded_test = Some(key.clone());
+        self.open_failure(&key, cx);
+        cx.notify();
+    }
+
+    fn failed_tests(&self) -> Vec<TestKey> {
+        self.files
+            .iter()
+            .flat_map(|((worktree_id, path), file)| {
+                file.tests
+                    .iter()
+                    .map(move |test| (*worktree_id, path.clone(), test.name.clone()))
+            })
+            .filter(|key| self.outcome(key) == Some(TestOutcome::Failed))
+            .collect()
+    }
+
+    fn open_location(&mut self, abs_path: PathBuf, point: Point, cx: &mut ViewContext<Self>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let open_task = workspace.update(cx, |workspace, cx| {
+            workspace.open_abs_path(abs_path, false, cx)
+        });
+        cx.spawn(|_, mut cx| async move {
+            let item = open_task.await?;
+            if let Some(editor) = item.downcast::<Editor>() {
+                editor.update(&mut cx, |editor, cx| {
+                    editor.change_selections(Some(Autoscroll::center()), cx, |selections| {
+                        selections.select_ranges([point..point])
+                    });
+                })?;
+            }
+            anyhow::Ok(())
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_header(&self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let test_count = self
+            .files
+            .values()
+            .map(|file| file.tests.len())
+            .sum::<usize>();
+        let failed_count = self.failed_tests().len();
+        h_flex()
+            .px_2()
+            .py_1()
+            .gap_1()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border)
+            .child(
+                Label::new(if failed_count > 0 {
+                    format!("{test_count} tests, {failed_count} failed")
+                } else {
+                    format!("{test_count} tests")
+                })
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+            )
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        IconButton::new("test-explorer-refresh", IconName::ArrowCircle)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|cx| Tooltip::for_action("Refresh Tests", &RefreshTests, cx))
+                            .on_click(
+                                cx.listener(|this, _, cx| this.refresh_tests(&RefreshTests, cx)),
+                            ),
+                    )
+                    .child(
+                        IconButton::new("test-explorer-jump-to-failure", IconName::XCircle)
+                            .icon_size(IconSize::Small)
+                            .disabled(failed_count == 0)
+                            .tooltip(|cx| {
+                                Tooltip::for_action("Jump to Failure", &JumpToFailure, cx)
+                            })
+                            .on_click(
+                                cx.listener(|this, _, cx| this.jump_to_failure(&JumpToFailure, cx)),
+                            ),
+                    )
+                    .child(
+                        IconButton::new("test-explorer-run-all", IconName::Play)
+                            .icon_size(IconSize::Small)
+                            .disabled(test_count == 0)
+                            .tooltip(|cx| Tooltip::for_action("Run All Tests", &RunAllTests, cx))
+                            .on_click(
+                                cx.listener(|this, _, cx| this.run_all_tests(&RunAllTests, cx)),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_file(
+        &self,
+        ix: usize,
+        key: &FileKey,
+        file: &TestFile,
+        cx: &mut ViewContext<Self>,
+    ) -> AnyElement {
+        let (worktree_id, path) = key;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let directory = path
+            .parent()
+            .map(|parent| parent.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let file_key = key.clone();
+        let header = ListItem::new(("test-explorer-file", ix))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(file_name).size(LabelSize::Small))
+                    .child(
+                        Label::new(directory)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .end_slot(
+                IconButton::new(("test-explorer-run-file", ix), IconName::Play)
+                    .icon_size(IconSize::Small)
+                    .tooltip(|cx| Tooltip::text("Run Tests in File", cx))
+                    .on_click(cx.listener(move |this, _, cx| {
+                        this.run_scope(file_key.clone(), TestScope::File, cx)
+                    })),
+            );
+
+        let tests = file.tests.iter().enumerate().map(|(test_ix, test)| {
+            let key = (*worktree_id, path.clone(), test.name.clone());
+            let id = SharedString::from(format!("test-explorer-test-{ix}-{test_ix}"));
+            let status_icon = match self.states.get(&key) {
+                Some(TestState::Running) => Icon::new(IconName::ArrowCircle).color(Color::Accent),
+                Some(TestState::Finished(result)) => match result.outcome {
+                    TestOutcome::Passed => Icon::new(IconName::Check).color(Color::Success),
+                    TestOutcome::Failed => Icon::new(IconName::XCircle).color(Color::Error),
+                    TestOutcome::Ignored => Icon::new(IconName::Dash).color(Color::Muted),
+                },
+                None => Icon::new(IconName::Dash).color(Color::Disabled),
+            };
+            let failed = self.outcome(&key) == Some(TestOutcome::Failed);
+            let expanded = failed && self.expanded_test.as_ref() == Some(&key);
+            let output = match self.states.get(&key) {
+                Some(TestState::Finished(result)) if expanded && !result.output.is_empty() => {
+                    Some(result.output.clone())
+                }
+                _ => None,
+            };
+
+            let run_key = (key.0, key.1.clone());
+            let run_name = test.name.clone();
+            let click_key = key.clone();
+            v_flex()
+                .child(
+                    ListItem::new(id.clone())
+                        .indent_level(1)
+                        .start_slot(status_icon.size(IconSize::Small))
+                        .child(Label::new(test.name.clone()).size(LabelSize::Small))
+                        .end_slot(
+                            IconButton::new(id, IconName::Play)
+                                .icon_size(IconSize::Small)
+                                .tooltip(|cx| Tooltip::text("Run Test", cx))
+                                .on_click(cx.listener(move |this, _, cx| {
+                                    this.run_scope(
+                                        run_key.clone(),
+                                        TestScope::Test(run_name.clone()),
+                                        cx,
+                                    )
+                                })),
+                        )
+                        .on_click(cx.listener(move |this, _, cx| {
+                            if failed {
+                                this.expanded_test = Some(click_key.clone());
+                                this.open_failure(&click_key, cx);
+                                cx.notify();
+                            } else {
+                                this.open_test(&click_key, cx);
+                            }
+                        })),
+                )
+                .children(output.map(|output| {
+                    div().pl_8().pr_2().py_1().child(
+                        Label::new(output)
+                            .size(LabelSize::XSmall)
+                            .color(Color::Muted),
+                    )
+                }))
+        });
+
+        v_flex().child(header).children(tests).into_any_element()
+    }
+}
+
+fn passed_color(cx: &AppContext) -> Hsla {
+    cx.theme().status().success
+}
+
+fn failed_color(cx: &AppContext) -> Hsla {
+    cx.theme().status().error
+}
+
+/// Parses the file and returns the tests that its runnables tag, if there are any.
+/// Runs the task in a terminal of the terminal panel, returning its output and whether it succeeded.
+async fn run_in_terminal(
+    workspace: &WeakView<Workspace>,
+    spawn_in_terminal: &SpawnInTerminal,
+    cx: &mut AsyncWindowContext,
+) -> anyhow::Result<(String, bool)> {
+    let terminal_panel = workspace
+        .update(cx, |workspace, cx| workspace.panel::<TerminalPanel>(cx))?
+        .context("no terminal panel to run the tests in")?;
+    let terminal = terminal_panel
+        .update(cx, |terminal_panel, cx| {
+            terminal_panel.spawn_single_task(spawn_in_terminal, cx)
+        })?
+        .await?;
+    terminal
+        .update(cx, |terminal, cx| terminal.wait_for_completed_task(cx))?
+        .await;
+    terminal.update(cx, |terminal, _| {
+        let output = terminal.take_output_lines().join("\n");
+        let success = terminal.task().map_or(false, |task| {
+            task.status == TaskStatus::Completed { success: true }
+        });
+        (output, success)
+    })
+}
+
+fn buffer_file_key(buffer: &Model<Buffer>, cx: &AppContext) -> Option<FileKey> {
+    let file = buffer.read(cx).file()?;
+    Some((
+        WorktreeId::from_usize(file.worktree_id()),
+        file.path().clone(),
+    ))
+}
+
+/// Finds the tests of a file, in the unsaved text of its buffer if it is given, or in the file on disk otherwise.
+async fn discover_tests(
+    path: &Path,
+    abs_path: &Path,
+    unsaved_text: Option<String>,
+    fs: &Arc<dyn Fs>,
+    languages: &Arc<LanguageRegistry>,
+    cx: &mut AsyncAppContext,
+) -> anyhow::Result<Option<(TestFramework, Vec<DiscoveredTest>)>> {
+    let text = match unsaved_text {
+        Some(text) => text,
+        None => fs.load(abs_path).await?,
+    };
+    let Some(framework) = [
+        TestFramework::Libtest,
+        TestFramework::Pytest,
+        TestFramework::GoTest,
+    ]
+    .into_iter()
+    .find(|framework| framework.matches_path(abs_path) && framework.matches_text(&text)) else {
+        return Ok(None);
+    };
+    let language = languages.language_for_file_path(abs_path).await?;
+    let buffer = cx.new_model(|cx| Buffer::local(text, cx).with_language(language, cx))?;
+    let snapshot = parsed_snapshot(&buffer, cx).await?;
+
+    let tests = snapshot
+        .runnable_ranges(Anchor::MIN..Anchor::MAX)
+        .filter(|runnable| {
+            runnable
+                .runnable
+                .tags
+                .iter()
+                .any(|tag| TestFramework::for_runnable_tag(&tag.0) == Some(framework))
+        })
+        .filter_map(|runnable| {
+            Some(DiscoveredTest {
+                name: framework.test_name(path, &snapshot, &runnable)?,
+                row: snapshot.offset_to_point(runnable.run_range.start).row,
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok((!tests.is_empty()).then_some((framework, tests)))
+}
+
+async fn parsed_snapshot(
+    buffer: &Model<Buffer>,
+    cx: &mut AsyncAppContext,
+) -> anyhow::Result<BufferSnapshot> {
+    let (parsed_tx, parsed_rx) = oneshot::channel();
+    let mut parsed_tx = Some(parsed_tx);
+    let _subscription = cx.update(|cx| {
+        cx.observe(buffer, move |buffer, cx| {
+            if !buffer.read(cx).is_parsing() {
+                if let Some(parsed_tx) = parsed_tx.take() {
+                    parsed_tx.send(()).ok();
+                }
+            }
+        })
+    })?;
+    if buffer.read_with(cx, |buffer, _| buffer.is_parsing())? {
+        parsed_rx.await.ok();
+    }
+    buffer.read_with(cx, |buffer, _| buffer.snapshot())
+}
+
+impl EventEmitter<PanelEvent> for TestExplorerPanel {}
+
+impl FocusableView for TestExplorerPanel {
+    fn focus_handle(&self, _: &AppContext) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Panel for TestExplorerPanel {
+    fn persistent_name() -> &'static str {
+        "Test Explorer Panel"
+    }
+
+    fn position(&self, _: &WindowContext) -> DockPosition {
+        self.position
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, cx: &mut ViewContext<Self>) {
+        self.position = position;
+        self.serialize(cx);
+    }
+
+    fn size(&self, _: &WindowContext) -> Pixels {
+        self.size.unwrap_or_else(|| match self.position {
+            DockPosition::Left | DockPosition::Right => px(320.),
+            DockPosition::Bottom => px(240.),
+        })
+    }
+
+    fn set_size(&mut self, size: Option<Pixels>, cx: &mut ViewContext<Self>) {
+        self.size = size;
+        self.serialize(cx);
+        cx.notify();
+    }
+
+    fn icon(&self, _: &WindowContext) -> Option<IconName> {
+        Some(IconName::Play)
+    }
+
+    fn icon_tooltip(&self, _: &WindowContext) -> Option<&'static str> {
+        Some("Test Explorer")
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+}
+
+impl Render for TestExplorerPanel {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let files = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(ix, (key, file))| self.render_file(ix, key, file, cx))
+            .collect::<Vec<_>>();
+        let message = if files.is_empty() {
+            Some("No tests found.")
+        } else {
+            None
+        };
+
+        v_flex()
+            .key_context("TestExplorerPanel")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::refresh_tests))
+            .size_full()
+            .child(self.render_header(cx))
+            .child(
+                v_flex()
+                    .id("test-explorer-entries")
+                    .p_1()
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .children(files)
+                    .children(message.map(|message| {
+                        Label::new(message)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                    })),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use indoc::indoc;
+    use language::{Language, LanguageConfig, LanguageMatcher};
+    use project::FakeFs;
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_discover_and_run_tests(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/project",
+            json!({
+                "Cargo.toml": "",
+                "src": {
+                    "main.rs": "fn main() {}",
+                    "math.rs": indoc! {"
+                        pub fn add(a: u32, b: u32) -> u32 {
+                            a + b
+                        }
+
+                        #[cfg(test)]
+                        mod tests {
+                            #[test]
+                            fn test_add() {
+                                assert_eq!(super::add(1, 1), 2);
+                            }
+
+                            mod overflow {
+                                #[test]
+                                fn test_add() {
+                                    assert_eq!(super::super::add(u32::MAX, 1), 0);
+                                }
+                            }
+                        }
+                    "},
+                },
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/project".as_ref()], cx).await;
+        project.read_with(cx, |project, _| project.languages().add(rust_lang()));
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| TestExplorerPanel::new(workspace, cx))
+            .unwrap();
+        cx.executor().advance_clock(DISCOVERY_DEBOUNCE);
+        cx.run_until_parked();
+        assert_eq!(
+            visible_tests(&panel, cx),
+            [
+                (
+                    "src/math.rs".to_string(),
+                    "math::tests::test_add".to_string(),
+                    7,
+                    None
+                ),
+                (
+                    "src/math.rs".to_string(),
+                    "math::tests::overflow::test_add".to_string(),
+                    13,
+                    None
+                ),
+            ]
+        );
+
+        panel.update(cx, |panel, cx| {
+            let key = panel.files.keys().next().unwrap().clone();
+            let file = &panel.files[&key];
+            let command = file
+                .framework
+                .command(&TestScope::File, &file.abs_path, &[], &file.root);
+            assert_eq!(command.cwd, Path::new("/project/src"));
+            let keys = file
+                .tests
+                .iter()
+                .map(|test| (key.0, key.1.clone(), test.name.clone()))
+                .collect();
+            panel.apply_results(
+                TestFramework::Libtest,
+                &command,
+                keys,
+                indoc! {"
+                    running 2 tests
+                    test math::tests::test_add ... ok
+                    test math::tests::overflow::test_add ... FAILED
+
+                    failures:
+
+                    ---- math::tests::overflow::test_add stdout ----
+                    thread 'math::tests::overflow::test_add' panicked at src/math.rs:15:13:
+                    attempt to add with overflow
+
+                    failures:
+                        math::tests::overflow::test_add
+
+                    test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+                "},
+                false,
+                cx,
+            );
+        });
+        assert_eq!(
+            visible_tests(&panel, cx),
+            [
+                (
+                    "src/math.rs".to_string(),
+                    "math::tests::test_add".to_string(),
+                    7,
+                    Some(TestOutcome::Passed)
+                ),
+                (
+                    "src/math.rs".to_string(),
+                    "math::tests::overflow::test_add".to_string(),
+                    13,
+                    Some(TestOutcome::Failed)
+                ),
+            ]
+        );
+        panel.update(cx, |panel, _| {
+            let failed_tests = panel.failed_tests();
+            assert_eq!(failed_tests.len(), 1);
+            let Some(TestState::Finished(result)) = panel.states.get(&failed_tests[0]) else {
+                panic!("test did not finish");
+            };
+            let location = result.failure_location.as_ref().unwrap();
+            assert_eq!(location.path, Path::new("/project/src/math.rs"));
+            assert_eq!((location.row, location.column), (14, 12));
+        });
+
+        // Removing the tests from the file removes them from the panel, with their results.
+        fs.save(
+            "/project/src/math.rs".as_ref(),
+            &"pub fn add(a: u32, b: u32) -> u32 { a + b }".into(),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+        cx.executor().advance_clock(DISCOVERY_DEBOUNCE);
+        cx.run_until_parked();
+        assert!(visible_tests(&panel, cx).is_empty());
+        panel.update(cx, |panel, _| assert!(panel.states.is_empty()));
+    }
+
+    #[gpui::test]
+    async fn test_discover_unsaved_tests(cx: &mut TestAppContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/project",
+            json!({
+                "Cargo.toml": "",
+                "src": {
+                    "lib.rs": "pub fn one() -> u32 { 1 }\n",
+                },
+            }),
+        )
+        .await;
+
+        let project = Project::test(fs.clone(), ["/project".as_ref()], cx).await;
+        project.read_with(cx, |project, _| project.languages().add(rust_lang()));
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/project/src/lib.rs", cx)
+            })
+            .await
+            .unwrap();
+        let workspace = cx.add_window(|cx| Workspace::test_new(project.clone(), cx));
+        let cx = &mut VisualTestContext::from_window(*workspace, cx);
+        let panel = workspace
+            .update(cx, |workspace, cx| TestExplorerPanel::new(workspace, cx))
+            .unwrap();
+        cx.executor().advance_clock(DISCOVERY_DEBOUNCE);
+        cx.run_until_parked();
+        assert!(visible_tests(&panel, cx).is_empty());
+
+        // Tests are discovered in the buffer before it is saved.
+        buffer.update(cx, |buffer, cx| {
+            let len = buffer.len();
+            buffer.edit(
+                [(
+                    len..len,
+                    "#[test]\nfn test_one() {\n    assert_eq!(one(), 1);\n}\n",
+                )],
+                None,
+                cx,
+            );
+        });
+        cx.executor().advance_clock(DISCOVERY_DEBOUNCE);
+        cx.run_until_parked();
+        assert_eq!(
+            visible_tests(&panel, cx),
+            [("src/lib.rs".to_string(), "test_one".to_string(), 2, None)]
+        );
+    }
+
+    fn visible_tests(
+        panel: &View<TestExplorerPanel>,
+        cx: &mut VisualTestContext,
+    ) -> Vec<(String, String, u32, Option<TestOutcome>)> {
+        panel.update(cx, |panel, _| {
+            panel
+                .files
+                .iter()
+                .flat_map(|((worktree_id, path), file)| {
+                    file.tests.iter().map(move |test| {
+                        let key = (*worktree_id, path.clone(), test.name.clone());
+                        (
+                            path.to_string_lossy().to_string(),
+                            test.name.clone(),
+                            test.row,
+                            panel.outcome(&key),
+                        )
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn rust_lang() -> Arc<Language> {
+        Arc::new(
+            Language::new(
+                LanguageConfig {
+                    name: "Rust".into(),
+                    matcher: LanguageMatcher {
+                        path_suffixes: vec!["rs".to_string()],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Some(tree_sitter_rust::language()),
+            )
+            .with_outline_query(
+                r#"
+                (mod_item "mod" @context name: (_) @name) @item
+                (function_item "fn" @context name: (_) @name) @item
+                "#,
+            )
+            .unwrap()
+            .with_runnable_query(
+                r#"
+                (
+                    (
+                        (attribute_item (attribute (identifier) @_attribute) (#eq? @_attribute "test")) @start
+                        .
+                        (function_item name: (_) @run body: _) @end
+                    )
+                    (#set! tag rust-test)
+                )
+                "#,
+            )
+            .unwrap(),
+        )
+    }
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            language::init(cx);
+            editor::init_settings(cx);
+            workspace::init_settings(cx);
+            Project::init_settings(cx);
+        });
+    }
+}
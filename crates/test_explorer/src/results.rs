@@ -0,0 +1,399 @@
+use std::{path::PathBuf, sync::OnceLock};
+
+use collections::HashMap;
+use regex::Regex;
+use serde::Deserialize;
+
+/// How a single test ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+    Ignored,
+}
+
+/// The result of a single test, as reported by its test harness.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestResult {
+    /// The name the harness reports the test under, e.g. `tests::test_parse` for libtest
+    /// or `tests/test_api.py::ApiTest::test_get` for pytest.
+    pub name: String,
+    pub outcome: TestOutcome,
+    /// The output the harness printed for the test, if it failed.
+    pub output: String,
+    /// Where the test failed, if the harness reported it.
+    pub failure_location: Option<FailureLocation>,
+}
+
+/// A position in the source code where a test failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FailureLocation {
+    /// The path as printed by the harness; it is usually relative to the directory the tests ran in.
+    pub path: PathBuf,
+    /// 0-based row.
+    pub row: u32,
+    /// 0-based column.
+    pub column: u32,
+}
+
+/// Parses the human-readable output of Rust's libtest harness, as printed by `cargo test`.
+pub fn parse_libtest_output(output: &str) -> Vec<TestResult> {
+    static RESULT_REGEX: OnceLock<Regex> = OnceLock::new();
+    static PANIC_REGEX: OnceLock<Regex> = OnceLock::new();
+    let result_regex =
+        RESULT_REGEX.get_or_init(|| Regex::new(r"^test (.+?) \.\.\. (ok|FAILED|ignored)").unwrap());
+    let panic_regex = PANIC_REGEX
+        .get_or_init(|| Regex::new(r"panicked at (?:'.*', )?([^\s:]+):(\d+):(\d+)").unwrap());
+
+    let mut results = Vec::<TestResult>::new();
+    let mut failure_outputs = HashMap::<String, String>::default();
+    let mut current_failure = None::<(String, String)>;
+    for line in output.lines() {
+        if let Some(name) = line
+            .strip_prefix("---- ")
+            .and_then(|line| line.strip_suffix(" stdout ----"))
+        {
+            failure_outputs.extend(current_failure.take());
+            current_failure = Some((name.to_string(), String::new()));
+            continue;
+        }
+        if line == "failures:" || line.starts_with("test result:") {
+            failure_outputs.extend(current_failure.take());
+            continue;
+        }
+        if let Some((_, failure_output)) = current_failure.as_mut() {
+            failure_output.push_str(line);
+            failure_output.push('\n');
+            continue;
+        }
+
+        if let Some(captures) = result_regex.captures(line) {
+            let outcome = match &captures[2] {
+                "ok" => TestOutcome::Passed,
+                "FAILED" => TestOutcome::Failed,
+                _ => TestOutcome::Ignored,
+            };
+            results.push(TestResult {
+                name: captures[1].to_string(),
+                outcome,
+                output: String::new(),
+                failure_location: None,
+            });
+        }
+    }
+    failure_outputs.extend(current_failure);
+
+    for result in &mut results {
+        if let Some(output) = failure_outputs.remove(&result.name) {
+            result.failure_location =
+                panic_regex
+                    .captures(&output)
+                    .map(|captures| FailureLocation {
+                        path: PathBuf::from(&captures[1]),
+                        row: captures[2].parse::<u32>().unwrap_or(1).saturating_sub(1),
+                        column: captures[3].parse::<u32>().unwrap_or(1).saturating_sub(1),
+                    });
+            result.output = output.trim_end().to_string();
+        }
+    }
+    results
+}
+
+/// Parses the output of `pytest -v`.
+pub fn parse_pytest_output(output: &str) -> Vec<TestResult> {
+    static RESULT_REGEX: OnceLock<Regex> = OnceLock::new();
+    static SECTION_REGEX: OnceLock<Regex> = OnceLock::new();
+    static LOCATION_REGEX: OnceLock<Regex> = OnceLock::new();
+    let result_regex = RESULT_REGEX.get_or_init(|| {
+        Regex::new(r"^(\S+::\S+) (PASSED|FAILED|ERROR|SKIPPED|XFAIL|XPASS)\b").unwrap()
+    });
+    let section_regex = SECTION_REGEX.get_or_init(|| Regex::new(r"^_{3,} (.+?) _{3,}$").unwrap());
+    let location_regex = LOCATION_REGEX.get_or_init(|| Regex::new(r"^(\S+\.py):(\d+): ").unwrap());
+
+    let mut results = Vec::<TestResult>::new();
+    let mut sections = Vec::<(String, String)>::new();
+    let mut in_section = false;
+    for line in output.lines() {
+        if let Some(captures) = section_regex.captures(line) {
+            // Setup and teardown errors are titled `ERROR at setup of TestCase.test_name`.
+            let title = captures[1].rsplit(' ').next().unwrap_or_default();
+            sections.push((title.replace('.', "::"), String::new()));
+            in_section = true;
+            continue;
+        }
+        if line.starts_with('=') {
+            in_section = false;
+            continue;
+        }
+        if in_section {
+            if let Some((_, section_output)) = sections.last_mut() {
+                section_output.push_str(line);
+                section_output.push('\n');
+            }
+            continue;
+        }
+
+        if let Some(captures) = result_regex.captures(line) {
+            let name = captures[1].to_string();
+            // A test with a teardown error is reported twice, keep the failure.
+            if results.iter().any(|result| result.name == name) {
+                if &captures[2] == "ERROR" {
+                    for result in results.iter_mut().filter(|result| result.name == name) {
+                        result.outcome = TestOutcome::Failed;
+                    }
+                }
+                continue;
+            }
+            let outcome = match &captures[2] {
+                "PASSED" | "XPASS" => TestOutcome::Passed,
+                "FAILED" | "ERROR" => TestOutcome::Failed,
+                _ => TestOutcome::Ignored,
+            };
+            results.push(TestResult {
+                name,
+                outcome,
+                output: String::new(),
+                failure_location: None,
+            });
+        }
+    }
+
+    for result in &mut results {
+        let Some((_, output)) = sections
+            .iter()
+            .find(|(title, _)| result.name.ends_with(&format!("::{title}")))
+        else {
+            continue;
+        };
+        // The first frame of the traceback is the one of the test itself.
+        result.failure_location = output.lines().find_map(|line| {
+            let captures = location_regex.captures(line)?;
+            Some(FailureLocation {
+                path: PathBuf::from(&captures[1]),
+                row: captures[2].parse::<u32>().ok()?.saturating_sub(1),
+                column: 0,
+            })
+        });
+        result.output = output.trim().to_string();
+    }
+    results
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GoTestEvent {
+    action: String,
+    test: Option<String>,
+    output: Option<String>,
+}
+
+/// Parses the output of `go test -json`.
+pub fn parse_go_test_json(output: &str) -> Vec<TestResult> {
+    static LOCATION_REGEX: OnceLock<Regex> = OnceLock::new();
+    let location_regex =
+        LOCATION_REGEX.get_or_init(|| Regex::new(r"^\s+(\S+\.go):(\d+): ").unwrap());
+
+    let mut results = Vec::new();
+    let mut test_outputs = HashMap::<String, String>::default();
+    for line in output.lines() {
+        // Build failures are printed as plain text, in between the events.
+        let Ok(event) = serde_json::from_str::<GoTestEvent>(line) else {
+            continue;
+        };
+        let Some(test) = event.test else {
+            continue;
+        };
+        let outcome = match event.action.as_str() {
+            "output" => {
+                if let Some(output) = event.output {
+                    test_outputs.entry(test).or_default().push_str(&output);
+                }
+                continue;
+            }
+            "pass" => TestOutcome::Passed,
+            "fail" => TestOutcome::Failed,
+            "skip" => TestOutcome::Ignored,
+            _ => continue,
+        };
+
+        let output = test_outputs.remove(&test).unwrap_or_default();
+        let (output, failure_location) = if outcome == TestOutcome::Failed {
+            let failure_location = output.lines().find_map(|line| {
+                let captures = location_regex.captures(line)?;
+                Some(FailureLocation {
+                    path: PathBuf::from(&captures[1]),
+                    row: captures[2].parse::<u32>().ok()?.saturating_sub(1),
+                    column: 0,
+                })
+            });
+            (output.trim_end().to_string(), failure_location)
+        } else {
+            (String::new(), None)
+        };
+        results.push(TestResult {
+            name: test,
+            outcome,
+            output,
+            failure_location,
+        });
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_parse_libtest_output() {
+        let output = indoc! {"
+            running 3 tests
+            test tests::test_ignored ... ignored
+            test tests::test_add ... ok
+            test parser::tests::test_parse ... FAILED
+
+            failures:
+
+            ---- parser::tests::test_parse stdout ----
+            thread 'parser::tests::test_parse' panicked at crates/parser/src/parser.rs:42:9:
+            assertion `left == right` failed
+              left: 1
+             right: 2
+            note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+
+            failures:
+                parser::tests::test_parse
+
+            test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+        "};
+        assert_eq!(
+            parse_libtest_output(output),
+            [
+                TestResult {
+                    name: "tests::test_ignored".into(),
+                    outcome: TestOutcome::Ignored,
+                    output: String::new(),
+                    failure_location: None,
+                },
+                TestResult {
+                    name: "tests::test_add".into(),
+                    outcome: TestOutcome::Passed,
+                    output: String::new(),
+                    failure_location: None,
+                },
+                TestResult {
+                    name: "parser::tests::test_parse".into(),
+                    outcome: TestOutcome::Failed,
+                    output: indoc! {"
+                        thread 'parser::tests::test_parse' panicked at crates/parser/src/parser.rs:42:9:
+                        assertion `left == right` failed
+                          left: 1
+                         right: 2
+                        note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"}
+                    .into(),
+                    failure_location: Some(FailureLocation {
+                        path: "crates/parser/src/parser.rs".into(),
+                        row: 41,
+                        column: 8,
+                    }),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_pytest_output() {
+        let output = indoc! {"
+            ============================= test session starts ==============================
+            collected 3 items
+
+            tests/test_api.py::ApiTest::test_get PASSED                              [ 33%]
+            tests/test_api.py::ApiTest::test_post FAILED                             [ 66%]
+            tests/test_api.py::ApiTest::test_slow SKIPPED (too slow)                 [100%]
+
+            =================================== FAILURES ===================================
+            _____________________________ ApiTest.test_post ______________________________
+
+            self = <tests.test_api.ApiTest testMethod=test_post>
+
+                def test_post(self):
+            >       self.assertEqual(post(), 201)
+            E       AssertionError: 200 != 201
+
+            tests/test_api.py:12: AssertionError
+            =========================== short test summary info ============================
+            FAILED tests/test_api.py::ApiTest::test_post - AssertionError: 200 != 201
+            ==================== 1 failed, 1 passed, 1 skipped in 0.02s ====================
+        "};
+        let results = parse_pytest_output(output);
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| (result.name.as_str(), result.outcome))
+                .collect::<Vec<_>>(),
+            [
+                ("tests/test_api.py::ApiTest::test_get", TestOutcome::Passed),
+                ("tests/test_api.py::ApiTest::test_post", TestOutcome::Failed),
+                (
+                    "tests/test_api.py::ApiTest::test_slow",
+                    TestOutcome::Ignored
+                ),
+            ]
+        );
+        assert_eq!(
+            results[1].failure_location,
+            Some(FailureLocation {
+                path: "tests/test_api.py".into(),
+                row: 11,
+                column: 0,
+            })
+        );
+        assert!(results[1]
+            .output
+            .contains("E       AssertionError: 200 != 201"));
+    }
+
+    #[test]
+    fn test_parse_go_test_json() {
+        let output = indoc! {r#"
+            {"Action":"run","Package":"example.com/calc","Test":"TestAdd"}
+            {"Action":"output","Package":"example.com/calc","Test":"TestAdd","Output":"=== RUN   TestAdd\n"}
+            {"Action":"output","Package":"example.com/calc","Test":"TestAdd","Output":"--- PASS: TestAdd (0.00s)\n"}
+            {"Action":"pass","Package":"example.com/calc","Test":"TestAdd","Elapsed":0}
+            {"Action":"run","Package":"example.com/calc","Test":"TestSub"}
+            {"Action":"output","Package":"example.com/calc","Test":"TestSub","Output":"=== RUN   TestSub\n"}
+            {"Action":"output","Package":"example.com/calc","Test":"TestSub","Output":"    calc_test.go:15: got 1, want 2\n"}
+            {"Action":"output","Package":"example.com/calc","Test":"TestSub","Output":"--- FAIL: TestSub (0.00s)\n"}
+            {"Action":"fail","Package":"example.com/calc","Test":"TestSub","Elapsed":0}
+            {"Action":"output","Package":"example.com/calc","Output":"FAIL\n"}
+            {"Action":"fail","Package":"example.com/calc","Elapsed":0.1}
+        "#};
+        assert_eq!(
+            parse_go_test_json(output),
+            [
+                TestResult {
+                    name: "TestAdd".into(),
+                    outcome: TestOutcome::Passed,
+                    output: String::new(),
+                    failure_location: None,
+                },
+                TestResult {
+                    name: "TestSub".into(),
+                    outcome: TestOutcome::Failed,
+                    output: indoc! {"
+                        === RUN   TestSub
+                            calc_test.go:15: got 1, want 2
+                        --- FAIL: TestSub (0.00s)"}
+                    .into(),
+                    failure_location: Some(FailureLocation {
+                        path: "calc_test.go".into(),
+                        row: 14,
+                        column: 0,
+                    }),
+                },
+            ]
+        );
+    }
+}
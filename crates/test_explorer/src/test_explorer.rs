@@ -0,0 +1,43 @@
+mod framework;
+mod panel;
+mod results;
+
+use gpui::{actions, AppContext};
+pub use panel::*;
+use workspace::Workspace;
+
+actions!(
+    test_explorer,
+    [
+        JumpToFailure,
+        RefreshTests,
+        RunAllTests,
+        RunFileTests,
+        ToggleFocus
+    ]
+);
+
+pub fn init(cx: &mut AppContext) {
+    cx.observe_new_views(|workspace: &mut Workspace, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, cx| {
+                workspace.toggle_panel_focus::<TestExplorerPanel>(cx);
+            })
+            .register_action(|workspace, action: &RunAllTests, cx| {
+                if let Some(panel) = workspace.panel::<TestExplorerPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.run_all_tests(action, cx));
+                }
+            })
+            .register_action(|workspace, action: &RunFileTests, cx| {
+                if let Some(panel) = workspace.panel::<TestExplorerPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.run_file_tests(action, cx));
+                }
+            })
+            .register_action(|workspace, action: &JumpToFailure, cx| {
+                if let Some(panel) = workspace.panel::<TestExplorerPanel>(cx) {
+                    panel.update(cx, |panel, cx| panel.jump_to_failure(action, cx));
+                }
+            });
+    })
+    .detach();
+}
@@ -1,15 +1,23 @@
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Result};
 use command_palette_hooks::CommandInterceptResult;
-use editor::actions::{SortLinesCaseInsensitive, SortLinesCaseSensitive};
-use gpui::{impl_actions, Action, AppContext, ViewContext};
+use editor::{scroll::Autoscroll, Anchor, Editor, MultiBufferSnapshot, ToPoint};
+use gpui::{impl_actions, Action, AppContext, Keystroke, ViewContext, WindowContext};
+use language::Point;
+use multi_buffer::MultiBufferRow;
+use regex::Regex;
 use serde_derive::Deserialize;
+use util::ResultExt;
 use workspace::{SaveIntent, Workspace};
 
 use crate::{
     motion::{EndOfDocument, Motion, StartOfDocument},
     normal::{
         move_cursor,
-        search::{range_regex, FindCommand, ReplaceCommand},
-        JoinLines,
+        repeat::char_keystroke,
+        search::{replace_lines, FindCommand},
+        yank::{copy_selections_content, yank_selections_content},
     },
     state::Mode,
     Vim,
@@ -20,7 +28,81 @@ pub struct GoToLine {
     pub line: u32,
 }
 
-impl_actions!(vim, [GoToLine]);
+/// An Ex command run on a range of lines, e.g. `:10,20d` or `:g/foo/normal A;`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RangeCommand {
+    /// The lines to run the command on. Defaults to the visual selection if the
+    /// command was started in visual mode, and to the command's default range otherwise.
+    pub range: Option<CommandRange>,
+    pub command: LineCommand,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum LineCommand {
+    GoTo,
+    Delete {
+        register: Option<char>,
+    },
+    Yank {
+        register: Option<char>,
+    },
+    Join,
+    /// `:s`, the query is everything after the command name, e.g. `/foo/bar/g`.
+    Substitute {
+        query: String,
+    },
+    Sort {
+        reverse: bool,
+        ignore_case: bool,
+        unique: bool,
+    },
+    Move {
+        address: Position,
+    },
+    Copy {
+        address: Position,
+    },
+    Normal {
+        keys: String,
+    },
+    Global {
+        pattern: String,
+        invert: bool,
+        command: Box<RangeCommand>,
+    },
+}
+
+/// The lines an Ex command applies to, e.g. `%`, `10,20`, `'<,'>` or `.,/foo/`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CommandRange {
+    start: Position,
+    end: Option<Position>,
+    /// Whether the positions were separated by `;`, in which case the end is
+    /// resolved relative to the start instead of the cursor.
+    relative_end: bool,
+}
+
+/// A line address in an Ex command, with an optional offset like `+2`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Position {
+    address: Address,
+    offset: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub enum Address {
+    /// A 1-based line number, `0` refers to the position before the first line.
+    Line(u32),
+    CurrentLine,
+    LastLine,
+    Mark(char),
+    Pattern {
+        pattern: String,
+        backwards: bool,
+    },
+}
+
+impl_actions!(vim, [GoToLine, RangeCommand]);
 
 pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, action: &GoToLine, cx| {
@@ -29,18 +111,14 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
             move_cursor(vim, Motion::StartOfDocument, Some(action.line as usize), cx);
         });
     });
+    workspace.register_action(run_range_command);
 }
 
 pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
     // Note: this is a very poor simulation of vim's command palette.
-    // In the future we should adjust it to handle parsing range syntax,
-    // and then calling the appropriate commands with/without ranges.
-    //
-    // We also need to support passing arguments to commands like :w
-    // (ideally with filename autocompletion).
-    //
-    // For now, you can only do a replace on the % range, and you can
-    // only use a specific line number range to "go to line"
+    // Commands that operate on lines are parsed with their range (see
+    // `parse_range_command`), but we still need to support passing arguments
+    // to commands like :w (ideally with filename autocompletion).
     while query.starts_with(':') {
         query = &query[1..];
     }
@@ -255,15 +333,6 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
             ("lNext", editor::actions::GoToPrevDiagnostic.boxed_clone())
         }
 
-        // modify the buffer (should accept [range])
-        "j" | "jo" | "joi" | "join" => ("join", JoinLines.boxed_clone()),
-        "d" | "de" | "del" | "dele" | "delet" | "delete" | "dl" | "dell" | "delel" | "deletl"
-        | "deletel" | "dp" | "dep" | "delp" | "delep" | "deletp" | "deletep" => {
-            ("delete", editor::actions::DeleteLine.boxed_clone())
-        }
-        "sor" | "sor " | "sort" | "sort " => ("sort", SortLinesCaseSensitive.boxed_clone()),
-        "sor i" | "sort i" => ("sort i", SortLinesCaseInsensitive.boxed_clone()),
-
         // Explore, etc.
         "E" | "Ex" | "Exp" | "Expl" | "Explo" | "Explor" | "Explore" => (
             "Explore",
@@ -321,7 +390,13 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
         "0" => ("0", StartOfDocument.boxed_clone()),
 
         _ => {
-            if query.starts_with('/') || query.starts_with('?') {
+            let range_command = parse_range_command(query);
+            // Without a command, `/foo` and `?foo` search rather than just moving to the line.
+            let is_search = (query.starts_with('/') || query.starts_with('?'))
+                && range_command.as_ref().map_or(true, |range_command| {
+                    range_command.command == LineCommand::GoTo
+                });
+            if is_search {
                 (
                     query,
                     FindCommand {
@@ -330,24 +405,8 @@ pub fn command_interceptor(mut query: &str, cx: &AppContext) -> Option<CommandIn
                     }
                     .boxed_clone(),
                 )
-            } else if query.starts_with('%') {
-                (
-                    query,
-                    ReplaceCommand {
-                        query: query.to_string(),
-                    }
-                    .boxed_clone(),
-                )
-            } else if let Ok(line) = query.parse::<u32>() {
-                (query, GoToLine { line }.boxed_clone())
-            } else if range_regex().is_match(query) {
-                (
-                    query,
-                    ReplaceCommand {
-                        query: query.to_string(),
-                    }
-                    .boxed_clone(),
-                )
+            } else if let Some(range_command) = range_command {
+                (query, range_command.boxed_clone())
             } else {
                 return None;
             }
@@ -386,11 +445,793 @@ fn generate_positions(string: &str, query: &str) -> Vec<usize> {
     positions
 }
 
+/// Parses an Ex command that operates on lines, like `10,20d`, `'<,'>s/a/b/g`,
+/// `.,$y` or `g/pattern/normal A;`.
+pub(crate) fn parse_range_command(query: &str) -> Option<RangeCommand> {
+    let (range, rest) = parse_range(query)?;
+    let rest = rest.trim_start();
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(rest.len());
+    let (name, args) = rest.split_at(name_len);
+    let (bang, args) = match args.strip_prefix('!') {
+        Some(args) => (true, args),
+        None => (false, args),
+    };
+
+    let command = match name {
+        "" if range.is_some() && !bang && args.trim().is_empty() => LineCommand::GoTo,
+        "d" | "de" | "del" | "dele" | "delet" | "delete" | "dl" | "dell" | "delel" | "deletl"
+        | "deletel" | "dp" | "dep" | "delp" | "delep" | "deletp" | "deletep" => {
+            LineCommand::Delete {
+                register: parse_register(args)?,
+            }
+        }
+        "y" | "ya" | "yan" | "yank" => LineCommand::Yank {
+            register: parse_register(args)?,
+        },
+        "j" | "jo" | "joi" | "join" if args.trim().is_empty() => LineCommand::Join,
+        "s" | "su" | "sub" | "subs" | "subst" | "substi" | "substit" | "substitu" | "substitut"
+        | "substitute"
+            if !args.is_empty() =>
+        {
+            LineCommand::Substitute {
+                query: args.to_string(),
+            }
+        }
+        "sor" | "sort" => LineCommand::Sort {
+            reverse: bang,
+            ignore_case: args.contains('i'),
+            unique: args.contains('u'),
+        },
+        "m" | "mo" | "mov" | "move" => LineCommand::Move {
+            address: parse_address_argument(args)?,
+        },
+        "t" | "co" | "cop" | "copy" => LineCommand::Copy {
+            address: parse_address_argument(args)?,
+        },
+        "norm" | "norma" | "normal" if !args.trim().is_empty() => LineCommand::Normal {
+            keys: args.trim_start().to_string(),
+        },
+        "g" | "gl" | "glo" | "glob" | "globa" | "global" => parse_global(args, bang)?,
+        "v" | "vg" | "vgl" | "vglo" | "vglob" | "vgloba" | "vglobal" => parse_global(args, true)?,
+        _ => return None,
+    };
+
+    Some(RangeCommand { range, command })
+}
+
+fn parse_register(args: &str) -> Option<Option<char>> {
+    let mut chars = args.trim().chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => Some(None),
+        (Some(register), None) if !register.is_ascii_digit() => Some(Some(register)),
+        _ => None,
+    }
+}
+
+fn parse_address_argument(args: &str) -> Option<Position> {
+    let (position, rest) = parse_position(args)?;
+    rest.trim().is_empty().then_some(position)
+}
+
+fn parse_global(args: &str, invert: bool) -> Option<LineCommand> {
+    let mut chars = args.chars();
+    let delimiter = chars
+        .next()
+        .filter(|c| !c.is_ascii_alphanumeric() && !c.is_whitespace() && *c != '"')?;
+    let (pattern, rest) = parse_pattern(chars.as_str(), delimiter);
+    let command = parse_range_command(rest)?;
+    if matches!(
+        command.command,
+        LineCommand::GoTo | LineCommand::Global { .. }
+    ) {
+        return None;
+    }
+    Some(LineCommand::Global {
+        pattern,
+        invert,
+        command: Box::new(command),
+    })
+}
+
+fn parse_range(query: &str) -> Option<(Option<CommandRange>, &str)> {
+    let query = query.trim_start();
+    if let Some(rest) = query.strip_prefix('%') {
+        return Some((Some(CommandRange::whole_buffer()), rest));
+    }
+    let Some((start, rest)) = parse_position(query) else {
+        return Some((None, query));
+    };
+    let rest = rest.trim_start();
+    let (separator, rest) = match rest.chars().next() {
+        Some(separator @ (',' | ';')) => (separator, &rest[1..]),
+        _ => {
+            let range = CommandRange {
+                start,
+                end: None,
+                relative_end: false,
+            };
+            return Some((Some(range), rest));
+        }
+    };
+    let (end, rest) = parse_position(rest)?;
+    let range = CommandRange {
+        start,
+        end: Some(end),
+        relative_end: separator == ';',
+    };
+    Some((Some(range), rest))
+}
+
+fn parse_position(query: &str) -> Option<(Position, &str)> {
+    let query = query.trim_start();
+    let (address, rest) = match query.chars().next() {
+        Some('.') => (Some(Address::CurrentLine), &query[1..]),
+        Some('$') => (Some(Address::LastLine), &query[1..]),
+        Some('\'') => {
+            let mut chars = query[1..].chars();
+            (Some(Address::Mark(chars.next()?)), chars.as_str())
+        }
+        Some(delimiter @ ('/' | '?')) => {
+            let (pattern, rest) = parse_pattern(&query[1..], delimiter);
+            let address = Address::Pattern {
+                pattern,
+                backwards: delimiter == '?',
+            };
+            (Some(address), rest)
+        }
+        Some(c) if c.is_ascii_digit() => {
+            let len = query
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(query.len());
+            (
+                Some(Address::Line(query[..len].parse().ok()?)),
+                &query[len..],
+            )
+        }
+        _ => (None, query),
+    };
+
+    let mut rest = rest;
+    let mut offset = None;
+    while let Some(sign @ ('+' | '-')) = rest.chars().next() {
+        rest = &rest[1..];
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount = if len == 0 {
+            1
+        } else {
+            rest[..len].parse::<i32>().ok()?
+        };
+        rest = &rest[len..];
+        let amount = if sign == '-' { -amount } else { amount };
+        offset = Some(offset.unwrap_or(0).checked_add(amount)?);
+    }
+
+    let address = match (address, offset) {
+        (Some(address), _) => address,
+        // A bare offset like `+2` is relative to the current line.
+        (None, Some(_)) => Address::CurrentLine,
+        (None, None) => return None,
+    };
+    let position = Position {
+        address,
+        offset: offset.unwrap_or(0),
+    };
+    Some((position, rest))
+}
+
+/// Reads a pattern up to the given (unescaped) delimiter, returning the pattern and the rest
+/// of the query. A missing closing delimiter ends the pattern at the end of the query.
+pub(crate) fn parse_pattern(query: &str, delimiter: char) -> (String, &str) {
+    let mut pattern = String::new();
+    let mut escaped = false;
+    for (ix, c) in query.char_indices() {
+        if escaped {
+            escaped = false;
+            if c != delimiter {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            return (pattern, &query[ix + c.len_utf8()..]);
+        } else {
+            pattern.push(c);
+        }
+    }
+    if escaped {
+        pattern.push('\\');
+    }
+    (pattern, "")
+}
+
+/// Converts a vim pattern (with the default `magic` option) into the regex syntax used by
+/// the `regex` crate, e.g. `\(a\|b\)\{2}\>` becomes `(a|b){2}\b`.
+pub(crate) fn vim_pattern_to_regex(pattern: &str) -> String {
+    let mut regex = String::new();
+    let mut case_insensitive = false;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            match c {
+                // These are literal in vim unless escaped.
+                '(' | ')' | '|' | '+' | '?' | '{' | '}' => {
+                    regex.push('\\');
+                    regex.push(c);
+                }
+                c => regex.push(c),
+            }
+            continue;
+        }
+        let Some(c) = chars.next() else {
+            regex.push_str("\\\\");
+            break;
+        };
+        match c {
+            '(' | ')' | '|' | '+' => regex.push(c),
+            '%' if chars.peek() == Some(&'(') => {
+                chars.next();
+                regex.push_str("(?:");
+            }
+            '=' | '?' => regex.push('?'),
+            '<' | '>' => regex.push_str("\\b"),
+            '{' => {
+                let mut count = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    if c != '\\' {
+                        count.push(c);
+                    }
+                }
+                // `\{-n,m}` matches as few as possible.
+                let (count, lazy) = match count.strip_prefix('-') {
+                    Some(count) => (count, "?"),
+                    None => (count.as_str(), ""),
+                };
+                let count = match count {
+                    "" | "," => "*".to_string(),
+                    count if count.starts_with(',') => format!("{{0{count}}}"),
+                    count => format!("{{{count}}}"),
+                };
+                regex.push_str(&count);
+                regex.push_str(lazy);
+            }
+            'c' => case_insensitive = true,
+            'C' => {}
+            'a' => regex.push_str("[a-zA-Z]"),
+            'A' => regex.push_str("[^a-zA-Z]"),
+            'l' => regex.push_str("[a-z]"),
+            'L' => regex.push_str("[^a-z]"),
+            'u' => regex.push_str("[A-Z]"),
+            'U' => regex.push_str("[^A-Z]"),
+            'x' => regex.push_str("[0-9a-fA-F]"),
+            'X' => regex.push_str("[^0-9a-fA-F]"),
+            'o' => regex.push_str("[0-7]"),
+            'O' => regex.push_str("[^0-7]"),
+            'h' => regex.push_str("[a-zA-Z_]"),
+            'H' => regex.push_str("[^a-zA-Z_]"),
+            'e' => regex.push_str("\\x1b"),
+            's' | 'S' | 'd' | 'D' | 'w' | 'W' | 'n' | 't' | 'r' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c if c.is_ascii_digit() => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if case_insensitive {
+        regex.insert_str(0, "(?i)");
+    }
+    regex
+}
+
+impl CommandRange {
+    fn whole_buffer() -> Self {
+        Self {
+            start: Position {
+                address: Address::Line(1),
+                offset: 0,
+            },
+            end: Some(Position {
+                address: Address::LastLine,
+                offset: 0,
+            }),
+            relative_end: false,
+        }
+    }
+
+    fn current_line() -> Self {
+        Self {
+            start: Position {
+                address: Address::CurrentLine,
+                offset: 0,
+            },
+            end: None,
+            relative_end: false,
+        }
+    }
+
+    fn visual_selection() -> Self {
+        Self {
+            start: Position {
+                address: Address::Mark('<'),
+                offset: 0,
+            },
+            end: Some(Position {
+                address: Address::Mark('>'),
+                offset: 0,
+            }),
+            relative_end: false,
+        }
+    }
+
+    /// Resolves this range to the (0-based) buffer rows it covers.
+    pub(crate) fn buffer_rows(
+        &self,
+        vim: &Vim,
+        editor: &Editor,
+        cx: &AppContext,
+    ) -> Result<RangeInclusive<u32>> {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        let current_line = current_line(editor, &snapshot);
+        let start = self.start.line_number(current_line, vim, &snapshot)?;
+        let end = match &self.end {
+            Some(end) if self.relative_end => end.line_number(start, vim, &snapshot)?,
+            Some(end) => end.line_number(current_line, vim, &snapshot)?,
+            None => start,
+        };
+        // Vim asks before swapping a backwards range, we just swap it.
+        let (start, end) = if start <= end {
+            (start, end)
+        } else {
+            (end, start)
+        };
+        Ok(start.saturating_sub(1)..=end.saturating_sub(1))
+    }
+}
+
+impl Position {
+    /// Resolves this position to a 1-based line number, where `0` is the position
+    /// before the first line.
+    fn line_number(
+        &self,
+        current_line: u32,
+        vim: &Vim,
+        snapshot: &MultiBufferSnapshot,
+    ) -> Result<u32> {
+        let last_line = last_line(snapshot);
+        let line = match &self.address {
+            Address::Line(line) => *line,
+            Address::CurrentLine => current_line,
+            Address::LastLine => last_line,
+            Address::Mark(name) => {
+                let anchor = vim
+                    .state()
+                    .marks
+                    .get(&name.to_string())
+                    .and_then(|anchors| anchors.first())
+                    .ok_or_else(|| anyhow!("mark '{name}' is not set"))?;
+                anchor.to_point(snapshot).row + 1
+            }
+            Address::Pattern { pattern, backwards } => {
+                matching_line(pattern, *backwards, current_line, snapshot)?
+            }
+        };
+        Ok((line as i64 + self.offset as i64).clamp(0, last_line as i64) as u32)
+    }
+}
+
+/// Finds the next line matching the pattern after (or before) the given line,
+/// wrapping around the end of the buffer like vim's `wrapscan`.
+fn matching_line(
+    pattern: &str,
+    backwards: bool,
+    current_line: u32,
+    snapshot: &MultiBufferSnapshot,
+) -> Result<u32> {
+    let regex = Regex::new(&vim_pattern_to_regex(pattern))?;
+    let line_count = last_line(snapshot);
+    let current_row = current_line.saturating_sub(1);
+    (1..=line_count)
+        .map(|distance| {
+            if backwards {
+                (current_row + line_count - distance) % line_count
+            } else {
+                (current_row + distance) % line_count
+            }
+        })
+        .find(|row| regex.is_match(&line_text(snapshot, *row)))
+        .map(|row| row + 1)
+        .ok_or_else(|| anyhow!("pattern not found: {pattern}"))
+}
+
+/// The 1-based number of the last line, not counting the empty line after a trailing newline.
+fn last_line(snapshot: &MultiBufferSnapshot) -> u32 {
+    let max_point = snapshot.max_point();
+    if max_point.row > 0 && max_point.column == 0 {
+        max_point.row
+    } else {
+        max_point.row + 1
+    }
+}
+
+fn current_line(editor: &Editor, snapshot: &MultiBufferSnapshot) -> u32 {
+    editor
+        .selections
+        .newest_anchor()
+        .head()
+        .to_point(snapshot)
+        .row
+        + 1
+}
+
+fn line_text(snapshot: &MultiBufferSnapshot, row: u32) -> String {
+    snapshot
+        .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(MultiBufferRow(row))))
+        .collect()
+}
+
+fn first_non_blank(snapshot: &MultiBufferSnapshot, row: u32) -> Point {
+    Point::new(row, snapshot.indent_size_for_line(MultiBufferRow(row)).len)
+}
+
+/// The given rows, together with the newline separating them from the rest of the
+/// buffer, so that removing the range removes the lines entirely.
+fn rows_with_newline(
+    snapshot: &MultiBufferSnapshot,
+    rows: &RangeInclusive<u32>,
+) -> std::ops::Range<Point> {
+    let (start, end) = (*rows.start(), *rows.end());
+    if end < snapshot.max_point().row {
+        Point::new(start, 0)..Point::new(end + 1, 0)
+    } else if start > 0 {
+        Point::new(start - 1, snapshot.line_len(MultiBufferRow(start - 1)))..snapshot.max_point()
+    } else {
+        Point::zero()..snapshot.max_point()
+    }
+}
+
+impl LineCommand {
+    fn default_range(&self) -> CommandRange {
+        match self {
+            LineCommand::Sort { .. } | LineCommand::Global { .. } => CommandRange::whole_buffer(),
+            _ => CommandRange::current_line(),
+        }
+    }
+}
+
+impl RangeCommand {
+    fn buffer_rows(
+        &self,
+        was_visual: bool,
+        vim: &Vim,
+        editor: &Editor,
+        cx: &AppContext,
+    ) -> Result<RangeInclusive<u32>> {
+        match &self.range {
+            Some(range) => range.buffer_rows(vim, editor, cx),
+            // Like `:` in visual mode inserting `'<,'>`.
+            None if was_visual => CommandRange::visual_selection().buffer_rows(vim, editor, cx),
+            None => self.command.default_range().buffer_rows(vim, editor, cx),
+        }
+    }
+}
+
+pub(crate) fn run_range_command(
+    workspace: &mut Workspace,
+    action: &RangeCommand,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let rows = Vim::update(cx, |vim, cx| {
+        let was_visual = vim.state().mode.is_visual();
+        if was_visual {
+            vim.switch_mode(Mode::Normal, false, cx);
+        }
+        vim.update_active_editor(cx, |vim, editor, cx| {
+            action.buffer_rows(was_visual, vim, editor, cx)
+        })
+    });
+    let Some(rows) = rows.and_then(|rows| rows.log_err()) else {
+        return;
+    };
+
+    match &action.command {
+        LineCommand::GoTo => Vim::update(cx, |vim, cx| {
+            move_cursor(
+                vim,
+                Motion::StartOfDocument,
+                Some(*rows.end() as usize + 1),
+                cx,
+            )
+        }),
+        LineCommand::Substitute { query } => replace_lines(workspace, query, vec![rows], cx),
+        LineCommand::Normal { keys } => {
+            let anchors = Vim::update(cx, |vim, cx| {
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    rows.map(|row| snapshot.anchor_before(Point::new(row, 0)))
+                        .collect()
+                })
+            });
+            run_normal(keys, anchors.unwrap_or_default(), cx.window_context());
+        }
+        LineCommand::Global {
+            pattern,
+            invert,
+            command,
+        } => run_global(workspace, pattern, *invert, command, rows, cx),
+        command => Vim::update(cx, |vim, cx| {
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                run_line_command(vim, editor, rows, command, cx)
+            });
+        }),
+    }
+}
+
+/// Runs `command` on every line in `rows` that matches (or with `invert`, doesn't match) the pattern.
+fn run_global(
+    workspace: &mut Workspace,
+    pattern: &str,
+    invert: bool,
+    command: &RangeCommand,
+    rows: RangeInclusive<u32>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let Some(regex) = Regex::new(&vim_pattern_to_regex(pattern)).log_err() else {
+        return;
+    };
+    let line_ranges = Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |vim, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            // Lines are marked before running the command, so that edits don't affect
+            // which lines it runs on.
+            let anchors = rows
+                .filter(|row| regex.is_match(&line_text(&snapshot, *row)) != invert)
+                .map(|row| snapshot.anchor_before(Point::new(row, 0)))
+                .collect::<Vec<_>>();
+
+            match &command.command {
+                // Substitutions and normal mode commands are asynchronous, so we resolve
+                // all of their lines up front and run them once.
+                LineCommand::Substitute { .. } | LineCommand::Normal { .. } => {
+                    let mut line_ranges = Vec::new();
+                    for anchor in anchors {
+                        editor.change_selections(None, cx, |s| {
+                            s.select_anchor_ranges([anchor..anchor])
+                        });
+                        if let Some(rows) = command.buffer_rows(false, vim, editor, cx).log_err() {
+                            line_ranges.push(rows);
+                        }
+                    }
+                    Some(line_ranges)
+                }
+                _ => {
+                    editor.transact(cx, |editor, cx| {
+                        for anchor in anchors {
+                            editor.change_selections(None, cx, |s| {
+                                s.select_anchor_ranges([anchor..anchor])
+                            });
+                            if let Some(rows) =
+                                command.buffer_rows(false, vim, editor, cx).log_err()
+                            {
+                                run_line_command(vim, editor, rows, &command.command, cx);
+                            }
+                        }
+                    });
+                    None
+                }
+            }
+        })
+    });
+    let Some(Some(line_ranges)) = line_ranges else {
+        return;
+    };
+
+    match &command.command {
+        LineCommand::Substitute { query } => replace_lines(workspace, query, line_ranges, cx),
+        LineCommand::Normal { keys } => {
+            let anchors = Vim::update(cx, |vim, cx| {
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    line_ranges
+                        .into_iter()
+                        .flatten()
+                        .map(|row| snapshot.anchor_before(Point::new(row, 0)))
+                        .collect()
+                })
+            });
+            run_normal(keys, anchors.unwrap_or_default(), cx.window_context());
+        }
+        _ => {}
+    }
+}
+
+/// Runs the commands that edit the buffer synchronously.
+fn run_line_command(
+    vim: &mut Vim,
+    editor: &mut Editor,
+    rows: RangeInclusive<u32>,
+    command: &LineCommand,
+    cx: &mut ViewContext<Editor>,
+) {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let (start, end) = (*rows.start(), *rows.end());
+    match command {
+        LineCommand::Delete { register } => {
+            if register.is_some() {
+                vim.update_state(|state| state.selected_register = *register);
+            }
+            editor.transact(cx, |editor, cx| {
+                let lines = rows_with_newline(&snapshot, &rows);
+                editor.change_selections(None, cx, |s| s.select_ranges([lines]));
+                copy_selections_content(vim, editor, true, cx);
+                editor.insert("", cx);
+
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let cursor = first_non_blank(&snapshot, start.min(snapshot.max_point().row));
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.select_ranges([cursor..cursor])
+                });
+            });
+        }
+        LineCommand::Yank { register } => {
+            if register.is_some() {
+                vim.update_state(|state| state.selected_register = *register);
+            }
+            let original_selections = editor.selections.disjoint_anchors();
+            let lines = rows_with_newline(&snapshot, &rows);
+            editor.change_selections(None, cx, |s| s.select_ranges([lines]));
+            yank_selections_content(vim, editor, true, cx);
+            editor.change_selections(None, cx, |s| s.select_anchors(original_selections.to_vec()));
+        }
+        LineCommand::Join => {
+            editor.change_selections(None, cx, |s| {
+                s.select_ranges([Point::new(start, 0)..Point::new(end, 0)])
+            });
+            editor.join_lines(&Default::default(), cx);
+        }
+        LineCommand::Sort {
+            reverse,
+            ignore_case,
+            unique,
+        } => {
+            let lines =
+                Point::new(start, 0)..Point::new(end, snapshot.line_len(MultiBufferRow(end)));
+            editor.transact(cx, |editor, cx| {
+                editor.change_selections(None, cx, |s| s.select_ranges([lines]));
+                if *ignore_case {
+                    editor.sort_lines_case_insensitive(&Default::default(), cx);
+                } else {
+                    editor.sort_lines_case_sensitive(&Default::default(), cx);
+                }
+                if *reverse {
+                    editor.reverse_lines(&Default::default(), cx);
+                }
+                if *unique {
+                    if *ignore_case {
+                        editor.unique_lines_case_insensitive(&Default::default(), cx);
+                    } else {
+                        editor.unique_lines_case_sensitive(&Default::default(), cx);
+                    }
+                }
+                let cursor = Point::new(start, 0);
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.select_ranges([cursor..cursor])
+                });
+            });
+        }
+        LineCommand::Move { address } | LineCommand::Copy { address } => {
+            let is_move = matches!(command, LineCommand::Move { .. });
+            let current_line = current_line(editor, &snapshot);
+            let Some(target) = address.line_number(current_line, vim, &snapshot).log_err() else {
+                return;
+            };
+            // Moving lines to (or into) their own range leaves them where they are.
+            if is_move && target >= start && target <= end + 1 {
+                return;
+            }
+
+            let text = snapshot
+                .text_for_range(
+                    Point::new(start, 0)..Point::new(end, snapshot.line_len(MultiBufferRow(end))),
+                )
+                .collect::<String>();
+            let insertion = if target == 0 {
+                (Point::zero()..Point::zero(), text + "\n")
+            } else {
+                let point = Point::new(target - 1, snapshot.line_len(MultiBufferRow(target - 1)));
+                (point..point, "\n".to_string() + &text)
+            };
+            let mut edits = vec![insertion];
+            if is_move {
+                let deletion = (rows_with_newline(&snapshot, &rows), String::new());
+                if target > end {
+                    edits.insert(0, deletion);
+                } else {
+                    edits.push(deletion);
+                }
+            }
+
+            // Like vim, leave the cursor on the last line that was moved or copied.
+            let cursor_row = if is_move && target > end {
+                target - 1
+            } else {
+                target + end - start
+            };
+            editor.transact(cx, |editor, cx| {
+                editor.edit(edits, cx);
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let cursor = first_non_blank(&snapshot, cursor_row);
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.select_ranges([cursor..cursor])
+                });
+            });
+        }
+        LineCommand::GoTo
+        | LineCommand::Substitute { .. }
+        | LineCommand::Normal { .. }
+        | LineCommand::Global { .. } => {}
+    }
+}
+
+/// Types `keys` in normal mode at the start of each of the given lines, like `:normal`.
+fn run_normal(keys: &str, lines: Vec<Anchor>, cx: &mut WindowContext) {
+    // Unlike macros, `:normal` types its argument literally, so `<Esc>` is four keys.
+    let keystrokes = keys.chars().map(char_keystroke).collect::<Vec<_>>();
+
+    // Keystrokes can't be dispatched while the workspace is being updated.
+    cx.defer(move |cx| {
+        let Some(editor) = Vim::read(cx)
+            .active_editor
+            .as_ref()
+            .and_then(|editor| editor.upgrade())
+        else {
+            return;
+        };
+        // Group the edits into a single undo step.
+        editor.update(cx, |editor, cx| {
+            editor
+                .buffer()
+                .update(cx, |buffer, cx| buffer.start_transaction(cx))
+        });
+        for line in lines {
+            editor.update(cx, |editor, cx| {
+                editor.change_selections(None, cx, |s| s.select_anchor_ranges([line..line]))
+            });
+            for keystroke in &keystrokes {
+                cx.dispatch_keystroke(keystroke.clone());
+            }
+            // Each line ends as if escape had been typed.
+            let state = Vim::read(cx).state();
+            if state.mode != Mode::Normal || !state.operator_stack.is_empty() {
+                cx.dispatch_keystroke(Keystroke::parse("escape").unwrap());
+            }
+        }
+        editor.update(cx, |editor, cx| {
+            editor
+                .buffer()
+                .update(cx, |buffer, cx| buffer.end_transaction(cx))
+        });
+    });
+}
+
 #[cfg(test)]
 mod test {
     use std::path::Path;
 
-    use crate::test::{NeovimBackedTestContext, VimTestContext};
+    use crate::{
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+    };
     use gpui::TestAppContext;
     use indoc::indoc;
 
@@ -452,6 +1293,31 @@ mod test {
             ˇcc"});
     }
 
+    #[gpui::test]
+    async fn test_command_replace_vim_regex(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇfoo.bar
+            baz.qux"})
+            .await;
+        cx.simulate_shared_keystrokes(
+            ": % s / \\ ( \\ w \\ + \\ ) \\ . \\ ( \\ w \\ + \\ ) / \\ 2 - \\ 1 / enter",
+        )
+        .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            bar-foo
+            ˇqux-baz"});
+
+        cx.set_shared_state("ˇaa aaa aaaa (a)").await;
+        cx.simulate_shared_keystrokes(": s / \\ < a \\ { 3 } \\ > / x / enter")
+            .await;
+        cx.shared_state().await.assert_eq("ˇaa x aaaa (a)");
+        cx.simulate_shared_keystrokes(": s / ( a ) / [ & ] / enter")
+            .await;
+        cx.shared_state().await.assert_eq("ˇaa x aaaa [(a)]");
+    }
+
     #[gpui::test]
     async fn test_command_search(cx: &mut TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -516,4 +1382,123 @@ mod test {
         cx.simulate_keystrokes(": q a enter");
         cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 0));
     }
+
+    #[gpui::test]
+    async fn test_command_ranges(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc\nd\ne", Mode::Normal);
+        cx.simulate_keystrokes(": 2 , 3 d enter");
+        cx.assert_state("a\nˇd\ne", Mode::Normal);
+        cx.simulate_keystrokes(": . , $ d enter");
+        cx.assert_state("ˇa", Mode::Normal);
+        cx.simulate_keystrokes("p");
+        cx.assert_state("a\nˇd\ne", Mode::Normal);
+
+        cx.set_state("ˇone\ntwo\nthree\nfour\nfive", Mode::Normal);
+        cx.simulate_keystrokes("j m a shift-g");
+        cx.simulate_keystrokes(": ' a ; + 1 d enter");
+        cx.assert_state("one\nˇfour\nfive", Mode::Normal);
+        cx.simulate_keystrokes(": / f i v e / d enter");
+        cx.assert_state("one\nˇfour", Mode::Normal);
+
+        cx.set_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes("j shift-v j : d enter");
+        cx.assert_state("a\nˇd", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_move_and_copy(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": m $ enter");
+        cx.assert_state("b\nc\nˇa", Mode::Normal);
+        cx.simulate_keystrokes(": 1 t . enter");
+        cx.assert_state("b\nc\na\nˇb", Mode::Normal);
+        cx.simulate_keystrokes(": 3 , 4 m 0 enter");
+        cx.assert_state("a\nˇb\nb\nc", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_sort(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("c\nA\nˇb\na", Mode::Normal);
+        cx.simulate_keystrokes(": s o r t enter");
+        cx.assert_state("ˇA\na\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": s o r t ! enter");
+        cx.assert_state("ˇc\nb\na\nA", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_global(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇfoo 1\nbar\nfoo 2\nbaz", Mode::Normal);
+        cx.simulate_keystrokes(": g / f o o / d enter");
+        cx.assert_state("bar\nˇbaz", Mode::Normal);
+        cx.simulate_keystrokes(": v / b a r / d enter");
+        cx.assert_state("ˇbar", Mode::Normal);
+
+        cx.set_state("ˇfoo a\nbar a\nfoo a", Mode::Normal);
+        cx.simulate_keystrokes(": g / f o o / s / a / b enter");
+        cx.assert_state("foo b\nbar a\nˇfoo b", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_global_vim_regex(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇfoo(1)
+            bar
+            fo(2)
+            foo(3)"})
+            .await;
+        cx.simulate_shared_keystrokes(": g / o \\ { 2 } ( / d enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            bar
+            ˇfo(2)"});
+    }
+
+    #[gpui::test]
+    async fn test_command_normal(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": % n o r m a l space A ; enter");
+        cx.assert_state("a;\nb;\ncˇ;", Mode::Normal);
+        // The whole command is undone at once.
+        cx.simulate_keystrokes("u");
+        assert_eq!(cx.buffer_text(), "a\nb\nc");
+
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": g ! / b / n o r m a l space d d enter");
+        assert_eq!(cx.buffer_text(), "b");
+    }
+
+    #[gpui::test]
+    async fn test_command_normal_special_characters(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b"})
+            .await;
+        cx.simulate_shared_keystrokes(": % n o r m a l space shift-a - space < x > enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            a- <x>
+            b- <xˇ>"});
+    }
+
+    #[test]
+    fn test_parse_position_overflow() {
+        let (position, rest) = super::parse_position("+2147483646+1 d").unwrap();
+        assert_eq!((position.offset, rest), (i32::MAX, " d"));
+        assert!(super::parse_position("+2147483647+1").is_none());
+        assert!(super::parse_position("-2147483647-2").is_none());
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{insert::NormalBefore, Vim, VimModeSetting};
+use crate::{insert::NormalBefore, normal::mark::serialize_marks, Vim, VimModeSetting};
 use editor::{Editor, EditorEvent};
 use gpui::{Action, AppContext, Entity, EntityId, UpdateGlobal, View, ViewContext, WindowContext};
 use settings::{Settings, SettingsStore};
@@ -49,6 +49,8 @@ fn blurred(editor: View<Editor>, cx: &mut WindowContext) {
                 .upgrade()
                 .is_some_and(|previous| previous == editor.clone())
             {
+                // Marks move with edits, so their positions are stored again.
+                serialize_marks(vim, cx);
                 vim.clear_operator(cx);
             }
         }
@@ -1,16 +1,18 @@
-use std::{ops::Range, sync::Arc};
+use std::{ops::Range, path::PathBuf, sync::Arc};
 
+use collections::HashMap;
 use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     movement,
     scroll::Autoscroll,
-    Anchor, Bias, DisplayPoint,
+    Anchor, Bias, DisplayPoint, Editor, EditorMode, LineFingerprints, SerializedPoint, ToPoint,
 };
-use gpui::WindowContext;
+use gpui::{AppContext, View, WindowContext};
 use language::SelectionGoal;
 
 use crate::{
     motion::{self, Motion},
+    persistence::VIM_DB,
     state::Mode,
     Vim,
 };
@@ -27,6 +29,7 @@ pub fn create_mark(vim: &mut Vim, text: Arc<str>, tail: bool, cx: &mut WindowCon
         return;
     };
     vim.update_state(|state| state.marks.insert(text.to_string(), anchors));
+    serialize_marks(vim, cx);
     vim.clear_operator(cx);
 }
 
@@ -56,9 +59,108 @@ pub fn create_visual_marks(vim: &mut Vim, mode: Mode, cx: &mut WindowContext) {
         state.marks.insert(">".to_string(), ends);
         state.stored_visual_mode.replace((mode, reversed));
     });
+    serialize_marks(vim, cx);
     vim.clear_operator(cx);
 }
 
+/// The path that the marks of an editor are stored for, when it edits a single local file.
+fn marks_path(editor: &View<Editor>, cx: &AppContext) -> Option<PathBuf> {
+    let editor = editor.read(cx);
+    if editor.mode() != EditorMode::Full {
+        return None;
+    }
+    let buffer = editor.buffer().read(cx).as_singleton()?;
+    let path = buffer.read(cx).file()?.as_local()?.abs_path(cx);
+    Some(path)
+}
+
+/// Stores the marks of the active editor for its file, so that they're restored when the file is
+/// opened again.
+pub fn serialize_marks(vim: &Vim, cx: &mut WindowContext) {
+    let Some(editor) = vim
+        .active_editor
+        .as_ref()
+        .and_then(|editor| editor.upgrade())
+    else {
+        return;
+    };
+    let Some(path) = marks_path(&editor, cx) else {
+        return;
+    };
+    // Marks that were all deleted are stored too, so that they aren't restored.
+    let marks = &vim.state().marks;
+    let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+    let marks = marks
+        .iter()
+        .map(|(name, anchors)| {
+            let points = anchors
+                .iter()
+                .map(|anchor| SerializedPoint::from(anchor.to_point(&snapshot)))
+                .collect::<Vec<_>>();
+            (name.clone(), points)
+        })
+        .collect::<HashMap<_, _>>();
+    cx.background_executor()
+        .spawn(async move {
+            let marks = serde_json::to_string(&marks)?;
+            let line_fingerprints = LineFingerprints::new(&snapshot);
+            VIM_DB
+                .save_marks(path, line_fingerprints.to_bytes(), marks)
+                .await
+        })
+        .detach_and_log_err(cx);
+}
+
+/// Restores the marks stored for the file of an editor, the first time it's activated.
+/// Marks on lines that changed outside of the editor move to the start of the nearest line.
+pub fn restore_marks(vim: &mut Vim, editor: &View<Editor>, cx: &mut WindowContext) {
+    vim.editor_states.entry(editor.entity_id()).or_default();
+    let Some(path) = marks_path(editor, cx) else {
+        return;
+    };
+
+    // Reading the marks and diffing the file's lines takes a while for large files.
+    let snapshot = editor.read(cx).buffer().read(cx).snapshot(cx);
+    let marks = cx.background_executor().spawn(async move {
+        let Some((line_fingerprints, marks)) = VIM_DB.get_marks(path)? else {
+            return Ok(Vec::new());
+        };
+        let marks = serde_json::from_str::<HashMap<String, Vec<SerializedPoint>>>(&marks)?;
+        let row_map = LineFingerprints::from_bytes(&line_fingerprints)
+            .row_map(&LineFingerprints::new(&snapshot));
+        anyhow::Ok(
+            marks
+                .into_iter()
+                .map(|(name, points)| {
+                    let anchors = points
+                        .into_iter()
+                        .map(|point| {
+                            snapshot.anchor_before(row_map.nearest_point(point, &snapshot))
+                        })
+                        .collect::<Vec<_>>();
+                    (name, anchors)
+                })
+                .collect::<Vec<_>>(),
+        )
+    });
+
+    let editor_id = editor.entity_id();
+    cx.spawn(|mut cx| async move {
+        let marks = marks.await?;
+        cx.update(|cx| {
+            Vim::update(cx, |vim, _| {
+                // Marks that were set while these were being read take precedence.
+                if let Some(state) = vim.editor_states.get_mut(&editor_id) {
+                    for (name, anchors) in marks {
+                        state.marks.entry(name).or_insert(anchors);
+                    }
+                }
+            })
+        })
+    })
+    .detach_and_log_err(cx);
+}
+
 pub fn jump(text: Arc<str>, line: bool, cx: &mut WindowContext) {
     let anchors = Vim::update(cx, |vim, cx| {
         vim.pop_operator(cx);
@@ -1,14 +1,21 @@
+use std::{cell::RefCell, mem, rc::Rc};
+
 use crate::{
     insert::NormalBefore,
     motion::Motion,
-    state::{Mode, RecordedSelection, ReplayableAction},
+    state::{Mode, Operator, RecordedSelection, ReplayableAction},
     visual::visual_motion,
     Vim,
 };
-use gpui::{actions, Action, ViewContext, WindowContext};
+use editor::Editor;
+use gpui::{actions, Action, Keystroke, Modifiers, ViewContext, WeakView, WindowContext};
 use workspace::Workspace;
 
-actions!(vim, [Repeat, EndRepeat]);
+actions!(vim, [Repeat, EndRepeat, ToggleRecord]);
+
+/// The maximum number of actions replayed at once, so that recursive
+/// macros don't run forever.
+const MAX_REPLAYED_ACTIONS: usize = 10_000;
 
 fn should_replay(action: &Box<dyn Action>) -> bool {
     // skip so that we don't leave the character palette open
@@ -37,7 +44,7 @@ fn repeatable_insert(action: &ReplayableAction) -> Option<Box<dyn Action>> {
                 None
             }
         }
-        ReplayableAction::Insertion { .. } => None,
+        ReplayableAction::Insertion { .. } | ReplayableAction::Keystroke(_) => None,
     }
 }
 
@@ -50,18 +57,359 @@ pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>
     });
 
     workspace.register_action(|_: &mut Workspace, _: &Repeat, cx| repeat(cx, false));
+
+    workspace.register_action(|_: &mut Workspace, _: &ToggleRecord, cx| {
+        Vim::update(cx, |vim, cx| {
+            if let Some(register) = vim.workspace_state.recording_register.take() {
+                let keystrokes = mem::take(&mut vim.workspace_state.recorded_keystrokes);
+                vim.workspace_state
+                    .registers
+                    .insert(register, keystrokes.into());
+            } else {
+                vim.push_operator(Operator::RecordRegister, cx);
+            }
+        })
+    });
+}
+
+/// Replays recorded actions one at a time, so that actions replayed while
+/// replaying (such as `.` or `@` within a macro) run in the right order.
+#[derive(Clone, Default)]
+pub struct Replayer(Rc<RefCell<ReplayerState>>);
+
+#[derive(Default)]
+struct ReplayerState {
+    actions: Vec<ReplayableAction>,
+    ix: usize,
+    running: bool,
+    editor: Option<WeakView<Editor>>,
+}
+
+impl Replayer {
+    /// Queues the actions to run right after the action currently being replayed.
+    pub fn replay(&self, actions: Vec<ReplayableAction>, cx: &mut WindowContext) {
+        let mut state = self.0.borrow_mut();
+        let ix = state.ix;
+        state.actions.splice(ix..ix, actions);
+        if state.running {
+            return;
+        }
+        state.running = true;
+        let this = self.clone();
+        cx.defer(move |cx| this.next(cx));
+    }
+
+    /// Whether any of the queued actions has been replayed yet.
+    pub fn has_started(&self) -> bool {
+        self.0.borrow().ix > 0
+    }
+
+    /// Drops all actions that have not been replayed yet.
+    pub fn stop(&self) {
+        let mut state = self.0.borrow_mut();
+        let ix = state.ix;
+        state.actions.truncate(ix);
+    }
+
+    fn next(self, cx: &mut WindowContext) {
+        let mut state = self.0.borrow_mut();
+        if state.ix == 0 {
+            state.editor = Vim::read(cx).active_editor.clone();
+            if let Some(editor) = state.editor.as_ref().and_then(|editor| editor.upgrade()) {
+                editor.update(cx, |editor, _| editor.show_local_selections = false);
+            }
+        }
+        let action = if state.ix < MAX_REPLAYED_ACTIONS {
+            state.actions.get(state.ix).cloned()
+        } else {
+            log::error!("stopped replaying after {MAX_REPLAYED_ACTIONS} actions");
+            None
+        };
+        state.ix += 1;
+        let editor = state.editor.clone();
+        drop(state);
+
+        let Some(action) = action else {
+            if let Some(editor) = editor.and_then(|editor| editor.upgrade()) {
+                editor.update(cx, |editor, _| editor.show_local_selections = true);
+            }
+            Vim::update(cx, |vim, _| vim.workspace_state.replayer.take());
+            return;
+        };
+
+        match action {
+            ReplayableAction::Action(action) => {
+                if should_replay(&action) {
+                    cx.dispatch_action(action.boxed_clone());
+                    // Observe the action after it has been handled, like keystrokes are,
+                    // so that `.` can repeat the changes made by a macro.
+                    cx.defer(move |cx| {
+                        Vim::update(cx, |vim, _| vim.observe_action(action));
+                        cx.defer(move |cx| self.next(cx));
+                    });
+                    return;
+                }
+            }
+            ReplayableAction::Keystroke(keystroke) => {
+                cx.dispatch_keystroke(keystroke);
+            }
+            ReplayableAction::Insertion {
+                text,
+                utf16_range_to_replace,
+            } => {
+                let editor = Vim::read(cx)
+                    .active_editor
+                    .as_ref()
+                    .and_then(|editor| editor.upgrade());
+                if let Some(editor) = editor {
+                    editor.update(cx, |editor, cx| {
+                        editor.replay_insert_event(&text, utf16_range_to_replace, cx)
+                    });
+                }
+            }
+        }
+        cx.defer(move |cx| self.next(cx));
+    }
+}
+
+/// Starts recording a macro into the given register after `q`.
+/// Recording into an uppercase register appends to the lowercase one.
+pub(crate) fn record_register(vim: &mut Vim, register: char, cx: &mut WindowContext) {
+    vim.clear_operator(cx);
+    if !register.is_ascii_alphanumeric() && register != '"' {
+        return;
+    }
+
+    let lower = register.to_ascii_lowercase();
+    let state = &mut vim.workspace_state;
+    if lower == register {
+        state.recordings.remove(&lower);
+        state.recorded_keystrokes.clear();
+    } else {
+        state.recorded_keystrokes = state
+            .registers
+            .get(&lower)
+            .map(|register| register.text.to_string())
+            .unwrap_or_default();
+    }
+    state.recordings.entry(lower).or_default();
+    state.recording_register = Some(lower);
+    // The keystroke naming the register is not part of the macro.
+    state.ignore_current_insertion = true;
+}
+
+/// Replays the macro recorded in the given register (`@@` replays the last one)
+/// as many times as the count.
+pub(crate) fn replay_register(vim: &mut Vim, register: char, cx: &mut WindowContext) {
+    let count = vim.take_count(cx).unwrap_or(1);
+    vim.clear_operator(cx);
+
+    let register = match register {
+        '@' => match vim.workspace_state.last_replayed_register {
+            Some(register) => register,
+            None => return,
+        },
+        register => register.to_ascii_lowercase(),
+    };
+    // Registers written by yanks or deletes have no recording, so their text
+    // is typed instead, like vim does.
+    let recording = match vim.workspace_state.recordings.get(&register) {
+        Some(recording) => recording.clone(),
+        None => match vim.workspace_state.registers.get(&register) {
+            Some(text) => parse_keystrokes(&text.text)
+                .into_iter()
+                .map(ReplayableAction::Keystroke)
+                .collect(),
+            None => return,
+        },
+    };
+    let mut actions = Vec::with_capacity(recording.len() * count);
+    for _ in 0..count {
+        actions.extend(recording.iter().cloned());
+    }
+    vim.workspace_state.last_replayed_register = Some(register);
+    replay(vim, actions, cx);
+}
+
+/// Appends a keystroke typed while recording a macro to the text of its register.
+pub(crate) fn record_keystroke(
+    keystroke: &Keystroke,
+    action: Option<&dyn Action>,
+    cx: &mut WindowContext,
+) {
+    if Vim::read(cx).workspace_state.recording_register.is_none() {
+        return;
+    }
+
+    // Only the last keystroke of a binding is observed, so recover the
+    // keystrokes before it from the binding that was used.
+    let binding = action.and_then(|action| {
+        cx.bindings_for_action(action)
+            .into_iter()
+            .rev()
+            .find(|binding| {
+                binding.keystrokes().last().is_some_and(|last| {
+                    last.key == keystroke.key && last.modifiers == keystroke.modifiers
+                })
+            })
+    });
+    let text = match binding {
+        Some(binding) => binding.keystrokes().iter().map(keystroke_text).collect(),
+        None => keystroke_text(keystroke),
+    };
+    Vim::update(cx, |vim, _| {
+        vim.workspace_state.recorded_keystrokes.push_str(&text)
+    });
+}
+
+/// Formats a keystroke the way vim displays it in registers, e.g. `<Esc>` or `<C-w>`.
+fn keystroke_text(keystroke: &Keystroke) -> String {
+    let modifiers = keystroke.modifiers;
+    let key = match keystroke.key.as_str() {
+        "escape" => "Esc",
+        "enter" => "CR",
+        "tab" => "Tab",
+        "backspace" => "BS",
+        "delete" => "Del",
+        "left" => "Left",
+        "right" => "Right",
+        "up" => "Up",
+        "down" => "Down",
+        "home" => "Home",
+        "end" => "End",
+        "pageup" => "PageUp",
+        "pagedown" => "PageDown",
+        key => {
+            if !modifiers.control && !modifiers.alt && !modifiers.platform {
+                if let Some(ime_key) = keystroke.ime_key.as_ref() {
+                    return ime_key.clone();
+                }
+                if key.chars().count() == 1 {
+                    return if modifiers.shift {
+                        key.to_uppercase()
+                    } else {
+                        key.to_string()
+                    };
+                }
+            }
+            key
+        }
+    };
+
+    let mut text = "<".to_string();
+    for (pressed, prefix) in [
+        (modifiers.control, "C-"),
+        (modifiers.alt, "M-"),
+        (modifiers.platform, "D-"),
+        (modifiers.shift, "S-"),
+    ] {
+        if pressed {
+            text.push_str(prefix);
+        }
+    }
+    text.push_str(key);
+    text.push('>');
+    text
+}
+
+/// Parses keystrokes in the format vim displays them in registers, e.g. `dw<Esc>`.
+/// Text that is not a known `<...>` key is typed literally.
+pub(crate) fn parse_keystrokes(text: &str) -> Vec<Keystroke> {
+    let mut keystrokes = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            if let Some((keystroke, len)) = rest
+                .find('>')
+                .and_then(|end| Some((parse_special_key(&rest[1..end])?, end + 1)))
+            {
+                keystrokes.push(keystroke);
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        keystrokes.push(char_keystroke(c));
+        rest = &rest[c.len_utf8()..];
+    }
+    keystrokes
+}
+
+/// Parses the inside of a `<...>` key such as `C-w` or `Esc`.
+fn parse_special_key(text: &str) -> Option<Keystroke> {
+    let mut modifiers = Modifiers::default();
+    let mut key = text;
+    while key.len() > 2 && key.as_bytes()[1] == b'-' {
+        match key.as_bytes()[0].to_ascii_uppercase() {
+            b'C' => modifiers.control = true,
+            b'M' | b'A' => modifiers.alt = true,
+            b'D' => modifiers.platform = true,
+            b'S' => modifiers.shift = true,
+            _ => return None,
+        }
+        key = &key[2..];
+    }
+    let lowercase_key = key.to_ascii_lowercase();
+    let key = match lowercase_key.as_str() {
+        "esc" => "escape",
+        "cr" | "enter" | "return" => "enter",
+        "tab" => "tab",
+        "bs" => "backspace",
+        "del" => "delete",
+        "space" if !modifiers.modified() => return Some(char_keystroke(' ')),
+        "space" => "space",
+        "lt" if !modifiers.modified() => return Some(char_keystroke('<')),
+        "left" => "left",
+        "right" => "right",
+        "up" => "up",
+        "down" => "down",
+        "home" => "home",
+        "end" => "end",
+        "pageup" => "pageup",
+        "pagedown" => "pagedown",
+        _ if key.chars().count() == 1 && modifiers.modified() => key,
+        _ => return None,
+    };
+    Some(Keystroke {
+        modifiers,
+        key: key.to_lowercase(),
+        ime_key: None,
+    })
+}
+
+/// The keystroke that types the given character.
+pub(crate) fn char_keystroke(c: char) -> Keystroke {
+    let (key, ime_key) = match c {
+        ' ' => ("space".to_string(), Some(" ".to_string())),
+        '\n' | '\r' => ("enter".to_string(), None),
+        '\t' => ("tab".to_string(), None),
+        '\x1b' => ("escape".to_string(), None),
+        c => (c.to_lowercase().to_string(), Some(c.to_string())),
+    };
+    Keystroke {
+        modifiers: Modifiers {
+            shift: c.is_uppercase(),
+            ..Default::default()
+        },
+        key,
+        ime_key,
+    }
+}
+
+fn replay(vim: &mut Vim, actions: Vec<ReplayableAction>, cx: &mut WindowContext) {
+    vim.workspace_state
+        .replayer
+        .get_or_insert_with(Default::default)
+        .clone()
+        .replay(actions, cx);
 }
 
 pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
-    let Some((mut actions, editor, selection)) = Vim::update(cx, |vim, cx| {
+    let Some((mut actions, selection)) = Vim::update(cx, |vim, cx| {
         let actions = vim.workspace_state.recorded_actions.clone();
-        if actions.is_empty() {
+        if actions.is_empty() || vim.active_editor.is_none() {
             return None;
         }
 
-        let Some(editor) = vim.active_editor.clone() else {
-            return None;
-        };
         let count = vim.take_count(cx);
 
         let selection = vim.workspace_state.recorded_selection.clone();
@@ -85,7 +433,7 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
             }
         }
 
-        Some((actions, editor, selection))
+        Some((actions, selection))
     }) else {
         return;
     };
@@ -167,42 +515,11 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
         actions = new_actions;
     }
 
-    Vim::update(cx, |vim, _| vim.workspace_state.replaying = true);
-    let window = cx.window_handle();
-    cx.spawn(move |mut cx| async move {
-        editor.update(&mut cx, |editor, _| {
-            editor.show_local_selections = false;
-        })?;
-        for action in actions {
-            if !matches!(
-                cx.update(|cx| Vim::read(cx).workspace_state.replaying),
-                Ok(true)
-            ) {
-                break;
-            }
-
-            match action {
-                ReplayableAction::Action(action) => {
-                    if should_replay(&action) {
-                        window.update(&mut cx, |_, cx| cx.dispatch_action(action))
-                    } else {
-                        Ok(())
-                    }
-                }
-                ReplayableAction::Insertion {
-                    text,
-                    utf16_range_to_replace,
-                } => editor.update(&mut cx, |editor, cx| {
-                    editor.replay_insert_event(&text, utf16_range_to_replace.clone(), cx)
-                }),
-            }?
-        }
-        editor.update(&mut cx, |editor, _| {
-            editor.show_local_selections = true;
-        })?;
-        window.update(&mut cx, |_, cx| cx.dispatch_action(EndRepeat.boxed_clone()))
-    })
-    .detach_and_log_err(cx);
+    actions.push(ReplayableAction::Action(EndRepeat.boxed_clone()));
+    Vim::update(cx, |vim, cx| {
+        vim.workspace_state.replaying = true;
+        replay(vim, actions, cx);
+    });
 }
 
 #[cfg(test)]
@@ -216,6 +533,7 @@ mod test {
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        Vim,
     };
 
     #[gpui::test]
@@ -510,4 +828,140 @@ mod test {
         cx.simulate_shared_keystrokes("u").await;
         cx.shared_state().await.assert_eq("hellˇo");
     }
+
+    #[gpui::test]
+    async fn test_record_and_replay_macro(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+            ˇone two
+            one two
+            one two
+            one two
+            one two"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("q w c w x escape j 0 q");
+        assert_eq!(
+            cx.update(|cx| Vim::read(cx).workspace_state.recording_register),
+            None
+        );
+        cx.assert_state(
+            indoc! {"
+            x two
+            ˇone two
+            one two
+            one two
+            one two"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("@ w");
+        cx.assert_state(
+            indoc! {"
+            x two
+            x two
+            ˇone two
+            one two
+            one two"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("2 @ @");
+        cx.assert_state(
+            indoc! {"
+            x two
+            x two
+            x two
+            x two
+            ˇone two"},
+            Mode::Normal,
+        );
+        // `.` repeats the last change made by the macro.
+        cx.simulate_keystrokes(".");
+        cx.assert_state(
+            indoc! {"
+            x two
+            x two
+            x two
+            x two
+            ˇx two"},
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_macro_register_text(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let register_text = |cx: &mut VimTestContext| {
+            cx.update(|cx| {
+                Vim::read(cx).workspace_state.registers[&'a']
+                    .text
+                    .to_string()
+            })
+        };
+
+        cx.set_state("ˇone", Mode::Normal);
+        cx.simulate_keystrokes("q a d w i x escape q");
+        cx.assert_state("ˇx", Mode::Normal);
+        assert_eq!(register_text(&mut cx), "dwix<Esc>");
+
+        // Uppercase registers append to the macro.
+        cx.simulate_keystrokes("q shift-a x q");
+        assert_eq!(register_text(&mut cx), "dwix<Esc>x");
+    }
+
+    #[gpui::test]
+    async fn test_replay_register_text(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // Yanked text is typed as keystrokes.
+        cx.set_shared_state(indoc! {
+            "ˇdwA!<Esc>
+            one two
+            one two"
+        })
+        .await;
+        cx.simulate_shared_keystrokes("\" a y $ j @ a").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "dwA!<Esc>
+            twoˇ!
+            one two"
+        });
+        cx.simulate_shared_keystrokes("j 0 @ @").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "dwA!<Esc>
+            two!
+            twoˇ!"
+        });
+
+        // Writing to a register replaces the macro recorded in it.
+        cx.set_shared_state("ˇxwone two").await;
+        cx.simulate_shared_keystrokes("q a x q \" a y l").await;
+        cx.simulate_shared_keystrokes("@ a").await;
+        cx.shared_state().await.assert_eq("wone ˇtwo");
+    }
+
+    #[gpui::test]
+    async fn test_replay_macro_with_multiple_cursors(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+            ˇa
+            ˇb
+            c
+            d"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("q q shift-a ! escape q");
+        cx.simulate_keystrokes("j @ q");
+        cx.assert_state(
+            indoc! {"
+            a!
+            b!
+            cˇ!
+            dˇ!"},
+            Mode::Normal,
+        );
+    }
 }
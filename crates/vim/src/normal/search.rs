@@ -1,13 +1,16 @@
-use std::{ops::Range, sync::OnceLock, time::Duration};
+use std::{ops::RangeInclusive, time::Duration};
 
 use gpui::{actions, impl_actions, ViewContext};
 use language::Point;
-use regex::Regex;
+use multi_buffer::MultiBufferRow;
 use search::{buffer_search, BufferSearchBar, SearchOptions};
 use serde_derive::Deserialize;
 use workspace::{searchable::Direction, Workspace};
 
 use crate::{
+    command::{
+        parse_pattern, parse_range_command, run_range_command, vim_pattern_to_regex, LineCommand,
+    },
     motion::{search_motion, Motion},
     normal::move_cursor,
     state::{Mode, SearchState},
@@ -51,7 +54,6 @@ struct Replacement {
     replacement: String,
     should_replace_all: bool,
     is_case_sensitive: bool,
-    range: Option<Range<usize>>,
 }
 
 actions!(vim, [SearchSubmit, MoveToNextMatch, MoveToPrevMatch]);
@@ -60,11 +62,6 @@ impl_actions!(
     [FindCommand, ReplaceCommand, Search, MoveToPrev, MoveToNext]
 );
 
-static RANGE_REGEX: OnceLock<Regex> = OnceLock::new();
-pub(crate) fn range_regex() -> &'static Regex {
-    RANGE_REGEX.get_or_init(|| Regex::new(r"^(\d+),(\d+)s(.*)").unwrap())
-}
-
 pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(move_to_next);
     workspace.register_action(move_to_prev);
@@ -352,22 +349,41 @@ fn replace_command(
     action: &ReplaceCommand,
     cx: &mut ViewContext<Workspace>,
 ) {
-    let replacement = parse_replace_all(&action.query);
+    // The query is a full `:s` command line, including its range.
+    let Some(command) = parse_range_command(&action.query) else {
+        return;
+    };
+    if matches!(command.command, LineCommand::Substitute { .. }) {
+        run_range_command(workspace, &command, cx);
+    }
+}
+
+/// Runs the `:s` query (everything after the command name) on the given buffer rows.
+pub(crate) fn replace_lines(
+    workspace: &mut Workspace,
+    query: &str,
+    rows: Vec<RangeInclusive<u32>>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let replacement = parse_replace_all(query);
     let pane = workspace.active_pane().clone();
-    let mut editor = Vim::read(cx)
+    let editor = Vim::read(cx)
         .active_editor
         .as_ref()
         .and_then(|editor| editor.upgrade());
-    if let Some(range) = &replacement.range {
-        if let Some(editor) = editor.as_mut() {
-            editor.update(cx, |editor, cx| {
-                let snapshot = &editor.snapshot(cx).buffer_snapshot;
-                let range = snapshot
-                    .anchor_before(Point::new(range.start.saturating_sub(1) as u32, 0))
-                    ..snapshot.anchor_before(Point::new(range.end as u32, 0));
-                editor.set_search_within_ranges(&[range], cx)
-            })
-        }
+    if let Some(editor) = editor.as_ref() {
+        editor.update(cx, |editor, cx| {
+            let snapshot = &editor.snapshot(cx).buffer_snapshot;
+            let ranges = rows
+                .iter()
+                .map(|rows| {
+                    let end =
+                        Point::new(*rows.end(), snapshot.line_len(MultiBufferRow(*rows.end())));
+                    snapshot.anchor_before(Point::new(*rows.start(), 0))..snapshot.anchor_after(end)
+                })
+                .collect::<Vec<_>>();
+            editor.set_search_within_ranges(&ranges, cx)
+        })
     }
     pane.update(cx, |pane, cx| {
         let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() else {
@@ -428,74 +444,49 @@ fn replace_command(
     })
 }
 
-// convert a vim query into something more usable by zed.
-// we don't attempt to fully convert between the two regex syntaxes,
-// but we do flip \( and \) to ( and ) (and vice-versa) in the pattern,
-// and convert \0..\9 to $0..$9 in the replacement so that common idioms work.
+// convert a vim query (e.g. `/foo/bar/g`) into something more usable by zed.
+// the pattern is translated from vim's regex syntax, and in the replacement
+// \0..\9 and & become $0..$9 so that the matched groups are inserted.
 fn parse_replace_all(query: &str) -> Replacement {
     let mut chars = query.chars();
-    let mut range = None;
-    let maybe_line_range_and_rest: Option<(Range<usize>, &str)> =
-        range_regex().captures(query).map(|captures| {
-            (
-                captures.get(1).unwrap().as_str().parse().unwrap()
-                    ..captures.get(2).unwrap().as_str().parse().unwrap(),
-                captures.get(3).unwrap().as_str(),
-            )
-        });
-    if maybe_line_range_and_rest.is_some() {
-        let (line_range, rest) = maybe_line_range_and_rest.unwrap();
-        range = Some(line_range);
-        chars = rest.chars();
-    } else if Some('%') != chars.next() || Some('s') != chars.next() {
-        return Replacement::default();
-    }
-
     let Some(delimiter) = chars.next() else {
         return Replacement::default();
     };
 
-    let mut search = String::new();
-    let mut replacement = String::new();
-    let mut flags = String::new();
-
-    let mut buffer = &mut search;
+    let (search, rest) = parse_pattern(chars.as_str(), delimiter);
+    let search = if search.is_empty() {
+        search
+    } else {
+        vim_pattern_to_regex(&search)
+    };
 
+    let mut replacement = String::new();
+    let mut flags = "";
     let mut escaped = false;
-    // 0 - parsing search
-    // 1 - parsing replacement
-    // 2 - parsing flags
-    let mut phase = 0;
-
-    for c in chars {
+    for (ix, c) in rest.char_indices() {
         if escaped {
             escaped = false;
-            if phase == 1 && c.is_digit(10) {
-                buffer.push('$')
-            // unescape escaped parens
-            } else if phase == 0 && c == '(' || c == ')' {
-            } else if c != delimiter {
-                buffer.push('\\')
+            match c {
+                c if c.is_ascii_digit() => {
+                    replacement.push_str(&format!("${{{c}}}"));
+                }
+                'n' | 'r' => replacement.push_str("\\n"),
+                't' => replacement.push_str("\\t"),
+                '\\' => replacement.push_str("\\\\"),
+                '$' => replacement.push_str("$$"),
+                c => replacement.push(c),
             }
-            buffer.push(c)
         } else if c == '\\' {
             escaped = true;
         } else if c == delimiter {
-            if phase == 0 {
-                buffer = &mut replacement;
-                phase = 1;
-            } else if phase == 1 {
-                buffer = &mut flags;
-                phase = 2;
-            } else {
-                break;
-            }
+            flags = &rest[ix + c.len_utf8()..];
+            break;
+        } else if c == '&' {
+            replacement.push_str("${0}");
+        } else if c == '$' {
+            replacement.push_str("$$");
         } else {
-            // escape unescaped parens
-            if phase == 0 && c == '(' || c == ')' {
-                buffer.push('\\')
-            }
-            buffer.push(c)
+            replacement.push(c);
         }
     }
 
@@ -504,7 +495,6 @@ fn parse_replace_all(query: &str) -> Replacement {
         replacement,
         should_replace_all: true,
         is_case_sensitive: true,
-        range,
     };
 
     for c in flags.chars() {
@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use db::{define_connection, query, sqlez_macros::sql};
+use workspace::WorkspaceDb;
+
+define_connection! {
+    // Current schema shape using pseudo-rust syntax:
+    // vim_marks(
+    //   path: PathBuf,
+    //   line_fingerprints: Vec<u8>, // a hash of each line of the file, to re-anchor the marks
+    //   marks: String, // JSON
+    // )
+    pub static ref VIM_DB: VimDb<WorkspaceDb> =
+        &[sql!(
+            CREATE TABLE vim_marks(
+                path BLOB NOT NULL PRIMARY KEY,
+                line_fingerprints BLOB NOT NULL,
+                marks TEXT NOT NULL
+            ) STRICT;
+        )];
+}
+
+impl VimDb {
+    // Returns the line fingerprints and marks stored for the file
+    query! {
+        pub fn get_marks(path: PathBuf) -> Result<Option<(Vec<u8>, String)>> {
+            SELECT line_fingerprints, marks
+            FROM vim_marks
+            WHERE path = ?
+        }
+    }
+
+    query! {
+        pub async fn save_marks(path: PathBuf, line_fingerprints: Vec<u8>, marks: String) -> Result<()> {
+            INSERT INTO vim_marks
+                (path, line_fingerprints, marks)
+            VALUES
+                (?1, ?2, ?3)
+            ON CONFLICT DO UPDATE SET
+                line_fingerprints = ?2,
+                marks = ?3
+        }
+    }
+}
@@ -1,10 +1,11 @@
 use std::{fmt::Display, ops::Range, sync::Arc};
 
+use crate::normal::repeat::Replayer;
 use crate::surrounds::SurroundsType;
 use crate::{motion::Motion, object::Object};
 use collections::HashMap;
 use editor::{Anchor, ClipboardSelection};
-use gpui::{Action, ClipboardItem, KeyContext};
+use gpui::{Action, ClipboardItem, KeyContext, Keystroke};
 use language::{CursorShape, Selection, TransactionId};
 use serde::{Deserialize, Serialize};
 use ui::SharedString;
@@ -68,6 +69,8 @@ pub enum Operator {
     Uppercase,
     OppositeCase,
     Register,
+    RecordRegister,
+    ReplayRegister,
 }
 
 #[derive(Default, Clone)]
@@ -164,6 +167,18 @@ pub struct WorkspaceState {
 
     pub last_yank: Option<SharedString>,
     pub registers: HashMap<char, Register>,
+
+    /// The register a macro is being recorded into with `q`, if any.
+    pub recording_register: Option<char>,
+    /// The actions recorded for each macro register.
+    pub recordings: HashMap<char, Vec<ReplayableAction>>,
+    /// The keystrokes typed while recording, which become the text of the register.
+    pub recorded_keystrokes: String,
+    pub last_replayed_register: Option<char>,
+    /// Set when the insertion that selects the macro register should not be recorded.
+    pub ignore_current_insertion: bool,
+    /// Replays recorded actions for `.` and `@`, if any are running.
+    pub replayer: Option<Replayer>,
 }
 
 #[derive(Debug)]
@@ -173,6 +188,8 @@ pub enum ReplayableAction {
         text: Arc<str>,
         utf16_range_to_replace: Option<Range<isize>>,
     },
+    /// A keystroke typed from the text of a register that has no recorded actions.
+    Keystroke(Keystroke),
 }
 
 impl Clone for ReplayableAction {
@@ -186,6 +203,7 @@ impl Clone for ReplayableAction {
                 text: text.clone(),
                 utf16_range_to_replace: utf16_range_to_replace.clone(),
             },
+            Self::Keystroke(keystroke) => Self::Keystroke(keystroke.clone()),
         }
     }
 }
@@ -322,6 +340,8 @@ impl Operator {
             Operator::Lowercase => "gu",
             Operator::OppositeCase => "g~",
             Operator::Register => "\"",
+            Operator::RecordRegister => "q",
+            Operator::ReplayRegister => "@",
         }
     }
 
@@ -333,6 +353,8 @@ impl Operator {
             | Operator::Jump { .. }
             | Operator::FindBackward { .. }
             | Operator::Register
+            | Operator::RecordRegister
+            | Operator::ReplayRegister
             | Operator::Replace
             | Operator::AddSurrounds { target: Some(_) }
             | Operator::ChangeSurrounds { .. }
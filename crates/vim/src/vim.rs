@@ -11,6 +11,7 @@ mod mode_indicator;
 mod motion;
 mod normal;
 mod object;
+mod persistence;
 mod replace;
 mod state;
 mod surrounds;
@@ -31,14 +32,17 @@ use gpui::{
 use language::{CursorShape, Point, SelectionGoal, TransactionId};
 pub use mode_indicator::ModeIndicator;
 use motion::Motion;
-use normal::{mark::create_visual_marks, normal_replace};
+use normal::{
+    mark::{create_visual_marks, restore_marks},
+    normal_replace, repeat,
+};
 use replace::multi_replace;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_derive::Serialize;
 use settings::{update_settings_file, Settings, SettingsSources, SettingsStore};
 use state::{EditorState, Mode, Operator, RecordedSelection, Register, WorkspaceState};
-use std::{ops::Range, sync::Arc};
+use std::{mem, ops::Range, sync::Arc};
 use surrounds::{add_surrounds, change_surrounds, delete_surrounds, SurroundsType};
 use ui::BorrowAppContext;
 use visual::{visual_block_motion, visual_replace};
@@ -170,17 +174,16 @@ fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext)
         .as_ref()
         .map(|action| action.boxed_clone())
     {
+        repeat::record_keystroke(&keystroke_event.keystroke, Some(action.as_ref()), cx);
         Vim::update(cx, |vim, _| {
-            if vim.workspace_state.recording {
+            if let Some(register) = vim.workspace_state.recording_register {
                 vim.workspace_state
-                    .recorded_actions
+                    .recordings
+                    .entry(register)
+                    .or_default()
                     .push(ReplayableAction::Action(action.boxed_clone()));
-
-                if vim.workspace_state.stop_recording_after_next_action {
-                    vim.workspace_state.recording = false;
-                    vim.workspace_state.stop_recording_after_next_action = false;
-                }
             }
+            vim.observe_action(action.boxed_clone());
         });
 
         // Keystroke is handled by the vim system, so continue forward
@@ -189,6 +192,8 @@ fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext)
         }
     } else if cx.has_pending_keystrokes() || keystroke_event.keystroke.is_ime_in_progress() {
         return;
+    } else {
+        repeat::record_keystroke(&keystroke_event.keystroke, None, cx);
     }
 
     Vim::update(cx, |vim, cx| match vim.active_operator() {
@@ -201,7 +206,9 @@ fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext)
             | Operator::DeleteSurrounds
             | Operator::Mark
             | Operator::Jump { .. }
-            | Operator::Register,
+            | Operator::Register
+            | Operator::RecordRegister
+            | Operator::ReplayRegister,
         ) => {}
         Some(_) => {
             vim.clear_operator(cx);
@@ -243,6 +250,9 @@ impl Vim {
             return;
         }
 
+        if !self.editor_states.contains_key(&editor.entity_id()) {
+            restore_marks(self, &editor, cx);
+        }
         self.active_editor = Some(editor.clone().downgrade());
         self.editor_subscription = Some(cx.subscribe(&editor, |editor, event, cx| match event {
             EditorEvent::SelectionsChanged { local: true } => {
@@ -288,12 +298,49 @@ impl Vim {
         self.sync_vim_settings(cx);
     }
 
+    /// Records an action for `.`, called after the action has been handled.
+    fn observe_action(&mut self, action: Box<dyn Action>) {
+        if self.workspace_state.recording {
+            self.workspace_state
+                .recorded_actions
+                .push(ReplayableAction::Action(action));
+
+            if self.workspace_state.stop_recording_after_next_action {
+                self.workspace_state.recording = false;
+                self.workspace_state.stop_recording_after_next_action = false;
+            }
+        }
+    }
+
     fn record_insertion(
         text: &Arc<str>,
         range_to_replace: Option<Range<isize>>,
         cx: &mut WindowContext,
     ) {
         Vim::update(cx, |vim, _| {
+            if mem::take(&mut vim.workspace_state.ignore_current_insertion) {
+                return;
+            }
+            // Insertions made by replaying actions are not part of a macro being recorded.
+            let replaying = vim
+                .workspace_state
+                .replayer
+                .as_ref()
+                .is_some_and(|replayer| replayer.has_started());
+            if let Some(register) = vim
+                .workspace_state
+                .recording_register
+                .filter(|_| !replaying)
+            {
+                vim.workspace_state
+                    .recordings
+                    .entry(register)
+                    .or_default()
+                    .push(ReplayableAction::Insertion {
+                        text: text.clone(),
+                        utf16_range_to_replace: range_to_replace.clone(),
+                    });
+            }
             if vim.workspace_state.recording {
                 vim.workspace_state
                     .recorded_actions
@@ -376,8 +423,14 @@ impl Vim {
         }
     }
 
-    pub fn stop_replaying(&mut self) {
-        self.workspace_state.replaying = false;
+    /// Stops replaying actions, e.g. when a replayed search fails.
+    pub fn stop_replaying(&mut self, cx: &mut WindowContext) {
+        if let Some(replayer) = self.workspace_state.replayer.as_ref() {
+            replayer.stop();
+        }
+        if mem::take(&mut self.workspace_state.replaying) {
+            self.switch_mode(Mode::Normal, false, cx);
+        }
     }
 
     /// When finishing an action that modifies the buffer, stop recording.
@@ -551,6 +604,8 @@ impl Vim {
     ) {
         if let Some(register) = register {
             let lower = register.to_lowercase().next().unwrap_or(register);
+            // A macro stored in the register is replaced by the new contents.
+            self.workspace_state.recordings.remove(&lower);
             if lower != register {
                 let current = self.workspace_state.registers.entry(lower).or_default();
                 current.text = (current.text.to_string() + &content.text).into();
@@ -918,6 +973,16 @@ impl Vim {
                     vim.select_register(text, cx);
                 }
             }),
+            Some(Operator::RecordRegister) => Vim::update(cx, |vim, cx| {
+                if let Some(register) = text.chars().next() {
+                    repeat::record_register(vim, register, cx)
+                }
+            }),
+            Some(Operator::ReplayRegister) => Vim::update(cx, |vim, cx| {
+                if let Some(register) = text.chars().next() {
+                    repeat::replay_register(vim, register, cx)
+                }
+            }),
             Some(Operator::Jump { line }) => normal::mark::jump(text, line, cx),
             _ => match Vim::read(cx).state().mode {
                 Mode::Replace => multi_replace(text, cx),
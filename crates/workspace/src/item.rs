@@ -212,6 +212,12 @@ pub trait Item: FocusableView + EventEmitter<Self::Event> {
     ) -> Task<Result<()>> {
         unimplemented!("reload() must be implemented if can_save() returns true")
     }
+    /// Stores the unsaved changes of the item, so that they're restored when the item is
+    /// deserialized instead of prompting to save them on quit. Returns `None` if the item
+    /// can't restore them.
+    fn persist_unsaved_changes(&mut self, _: &mut ViewContext<Self>) -> Option<Task<Result<()>>> {
+        None
+    }
 
     fn act_as_type<'a>(
         &'a self,
@@ -317,6 +323,7 @@ pub trait ItemHandle: 'static + Send {
         cx: &mut WindowContext,
     ) -> Task<Result<()>>;
     fn reload(&self, project: Model<Project>, cx: &mut WindowContext) -> Task<Result<()>>;
+    fn persist_unsaved_changes(&self, cx: &mut WindowContext) -> Option<Task<Result<()>>>;
     fn act_as_type(&self, type_id: TypeId, cx: &AppContext) -> Option<AnyView>;
     fn to_followable_item_handle(&self, cx: &AppContext) -> Option<Box<dyn FollowableItemHandle>>;
     fn on_release(
@@ -677,6 +684,10 @@ impl<T: Item> ItemHandle for View<T> {
         self.update(cx, |item, cx| item.reload(project, cx))
     }
 
+    fn persist_unsaved_changes(&self, cx: &mut WindowContext) -> Option<Task<Result<()>>> {
+        self.update(cx, |item, cx| item.persist_unsaved_changes(cx))
+    }
+
     fn act_as_type<'a>(&'a self, type_id: TypeId, cx: &'a AppContext) -> Option<AnyView> {
         self.read(cx).act_as_type(type_id, self, cx)
     }
@@ -904,9 +915,11 @@ pub mod test {
         pub save_count: usize,
         pub save_as_count: usize,
         pub reload_count: usize,
+        pub persist_count: usize,
         pub is_dirty: bool,
         pub is_singleton: bool,
         pub has_conflict: bool,
+        pub persists_unsaved_changes: bool,
         pub project_items: Vec<Model<TestProjectItem>>,
         pub nav_history: Option<ItemNavHistory>,
         pub tab_descriptions: Option<Vec<&'static str>>,
@@ -965,8 +978,10 @@ pub mod test {
                 save_count: 0,
                 save_as_count: 0,
                 reload_count: 0,
+                persist_count: 0,
                 is_dirty: false,
                 has_conflict: false,
+                persists_unsaved_changes: false,
                 project_items: Vec::new(),
                 is_singleton: true,
                 nav_history: None,
@@ -1003,6 +1018,11 @@ pub mod test {
             self
         }
 
+        pub fn with_persists_unsaved_changes(mut self, persists_unsaved_changes: bool) -> Self {
+            self.persists_unsaved_changes = persists_unsaved_changes;
+            self
+        }
+
         pub fn with_project_items(mut self, items: &[Model<TestProjectItem>]) -> Self {
             self.project_items.clear();
             self.project_items.extend(items.iter().cloned());
@@ -1108,9 +1128,11 @@ pub mod test {
                 save_count: self.save_count,
                 save_as_count: self.save_as_count,
                 reload_count: self.reload_count,
+                persist_count: self.persist_count,
                 is_dirty: self.is_dirty,
                 is_singleton: self.is_singleton,
                 has_conflict: self.has_conflict,
+                persists_unsaved_changes: self.persists_unsaved_changes,
                 project_items: self.project_items.clone(),
                 nav_history: None,
                 tab_descriptions: None,
@@ -1168,6 +1190,17 @@ pub mod test {
             Task::ready(Ok(()))
         }
 
+        fn persist_unsaved_changes(
+            &mut self,
+            _: &mut ViewContext<Self>,
+        ) -> Option<Task<anyhow::Result<()>>> {
+            if !self.persists_unsaved_changes {
+                return None;
+            }
+            self.persist_count += 1;
+            Some(Task::ready(Ok(())))
+        }
+
         fn serialized_item_kind() -> Option<&'static str> {
             Some("TestItem")
         }
@@ -401,18 +401,7 @@ impl Pane {
                         IconButton::new("split", IconName::Split)
                             .icon_size(IconSize::Small)
                             .on_click(cx.listener(|pane, _, cx| {
-                                let menu = ContextMenu::build(cx, |menu, _| {
-                                    menu.action("Split Right", SplitRight.boxed_clone())
-                                        .action("Split Left", SplitLeft.boxed_clone())
-                                        .action("Split Up", SplitUp.boxed_clone())
-                                        .action("Split Down", SplitDown.boxed_clone())
-                                });
-                                cx.subscribe(&menu, |pane, _, _: &DismissEvent, cx| {
-                                    pane.focus(cx);
-                                    pane.split_item_menu = None;
-                                })
-                                .detach();
-                                pane.split_item_menu = Some(menu);
+                                pane.deploy_split_item_menu(cx);
                             }))
                             .tooltip(|cx| Tooltip::text("Split Pane", cx)),
                     )
@@ -1887,6 +1876,26 @@ impl Pane {
             )
     }
 
+    /// Opens the menu to split the pane in any direction, as the split button of the tab bar does.
+    pub fn deploy_split_item_menu(&mut self, cx: &mut ViewContext<Self>) {
+        let menu = ContextMenu::build(cx, |menu, _| {
+            menu.action("Split Right", SplitRight.boxed_clone())
+                .action("Split Left", SplitLeft.boxed_clone())
+                .action("Split Up", SplitUp.boxed_clone())
+                .action("Split Down", SplitDown.boxed_clone())
+        });
+        cx.subscribe(&menu, |pane, _, _: &DismissEvent, cx| {
+            pane.focus(cx);
+            pane.split_item_menu = None;
+        })
+        .detach();
+        self.split_item_menu = Some(menu);
+    }
+
+    pub fn split_item_menu(&self) -> Option<&View<ContextMenu>> {
+        self.split_item_menu.as_ref()
+    }
+
     pub fn render_menu_overlay(menu: &View<ContextMenu>) -> Div {
         div().absolute().bottom_0().right_0().size_0().child(
             deferred(
@@ -24,11 +24,30 @@ pub struct PaneGroup {
     pub(crate) root: Member,
 }
 
+/// The arrangement of the panes of a [`PaneGroup`], for groups outside of the workspace center
+/// to be stored and recreated from.
+pub enum PaneGroupLayout {
+    Pane(View<Pane>),
+    Axis {
+        axis: Axis,
+        flexes: Vec<f32>,
+        members: Vec<PaneGroupLayout>,
+    },
+}
+
 impl PaneGroup {
     pub(crate) fn with_root(root: Member) -> Self {
         Self { root }
     }
 
+    pub fn from_layout(layout: PaneGroupLayout) -> Self {
+        Self::with_root(Member::from_layout(layout))
+    }
+
+    pub fn layout(&self) -> PaneGroupLayout {
+        self.root.layout()
+    }
+
     pub fn new(pane: View<Pane>) -> Self {
         Self {
             root: Member::Pane(pane),
@@ -114,13 +133,33 @@ impl PaneGroup {
         )
     }
 
-    pub(crate) fn panes(&self) -> Vec<&View<Pane>> {
+    /// Renders a group outside of the workspace center, e.g. the one of a panel, whose panes
+    /// are neither followed nor zoomed into separately.
+    pub fn render_detached(
+        &self,
+        project: &Model<Project>,
+        active_pane: &View<Pane>,
+        app_state: &Arc<AppState>,
+        cx: &mut ViewContext<Workspace>,
+    ) -> impl IntoElement {
+        self.render(
+            project,
+            &HashMap::default(),
+            None,
+            active_pane,
+            None,
+            app_state,
+            cx,
+        )
+    }
+
+    pub fn panes(&self) -> Vec<&View<Pane>> {
         let mut panes = Vec::new();
         self.root.collect_panes(&mut panes);
         panes
     }
 
-    pub(crate) fn first_pane(&self) -> View<Pane> {
+    pub fn first_pane(&self) -> View<Pane> {
         self.root.first_pane()
     }
 }
@@ -149,6 +188,32 @@ impl Member {
         Member::Axis(PaneAxis::new(axis, members))
     }
 
+    fn from_layout(layout: PaneGroupLayout) -> Self {
+        match layout {
+            PaneGroupLayout::Pane(pane) => Member::Pane(pane),
+            PaneGroupLayout::Axis {
+                axis,
+                flexes,
+                members,
+            } => Member::Axis(PaneAxis::load(
+                axis,
+                members.into_iter().map(Self::from_layout).collect(),
+                Some(flexes),
+            )),
+        }
+    }
+
+    fn layout(&self) -> PaneGroupLayout {
+        match self {
+            Member::Axis(axis) => PaneGroupLayout::Axis {
+                axis: axis.axis,
+                flexes: axis.flexes.lock().clone(),
+                members: axis.members.iter().map(Self::layout).collect(),
+            },
+            Member::Pane(pane) => PaneGroupLayout::Pane(pane.clone()),
+        }
+    }
+
     fn contains(&self, needle: &View<Pane>) -> bool {
         match self {
             Member::Axis(axis) => axis.members.iter().any(|member| member.contains(needle)),
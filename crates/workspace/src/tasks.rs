@@ -1,6 +1,7 @@
 use project::TaskSourceKind;
 use task::{ResolvedTask, TaskContext, TaskTemplate};
 use ui::ViewContext;
+use util::ResultExt;
 
 use crate::Workspace;
 
@@ -12,9 +13,15 @@ pub fn schedule_task(
     omit_history: bool,
     cx: &mut ViewContext<'_, Workspace>,
 ) {
-    if let Some(spawn_in_terminal) =
-        task_to_resolve.resolve_task(&task_source_kind.to_id_base(), task_cx)
-    {
+    let resolved_task = workspace
+        .project()
+        .read(cx)
+        .task_inventory()
+        .read(cx)
+        .resolve_task_with_dependencies(&task_source_kind, task_to_resolve, task_cx)
+        .log_err()
+        .flatten();
+    if let Some(spawn_in_terminal) = resolved_task {
         schedule_resolved_task(
             workspace,
             task_source_kind,
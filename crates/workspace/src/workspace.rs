@@ -1449,13 +1449,56 @@ impl Workspace {
                 }
             }
 
+            let hot_exit =
+                quitting && (*cx).update(|cx| WorkspaceSettings::get_global(cx).hot_exit)?;
+            if !hot_exit {
+                return this
+                    .update(&mut cx, |this, cx| {
+                        this.save_all_internal(SaveIntent::Close, cx)
+                    })?
+                    .await;
+            }
+
+            // Keep the unsaved changes of the items that can restore them, and only prompt to
+            // save the others.
+            let persisted_items = this
+                .update(&mut cx, |this, cx| this.persist_unsaved_changes(cx))?
+                .await;
             this.update(&mut cx, |this, cx| {
-                this.save_all_internal(SaveIntent::Close, cx)
+                let dirty_items = this
+                    .dirty_items(cx)
+                    .into_iter()
+                    .filter(|(_, item)| !persisted_items.contains(&item.item_id()))
+                    .collect();
+                this.save_items(dirty_items, SaveIntent::Close, cx)
             })?
             .await
         })
     }
 
+    /// Stores the unsaved changes of the dirty items that support it, along with the workspace
+    /// that they're restored into. Returns the items whose changes were stored.
+    fn persist_unsaved_changes(&mut self, cx: &mut ViewContext<Self>) -> Task<HashSet<EntityId>> {
+        let tasks = self
+            .dirty_items(cx)
+            .into_iter()
+            .filter_map(|(_, item)| {
+                let item_id = item.item_id();
+                let task = item.persist_unsaved_changes(cx)?;
+                Some(async move { task.await.log_err().map(|_| item_id) })
+            })
+            .collect::<Vec<_>>();
+        let serialize_workspace = self.serialize_workspace_internal(cx);
+        cx.spawn(|_, _| async move {
+            serialize_workspace.await;
+            futures::future::join_all(tasks)
+                .await
+                .into_iter()
+                .flatten()
+                .collect()
+        })
+    }
+
     fn save_all(&mut self, action: &SaveAll, cx: &mut ViewContext<Self>) {
         self.save_all_internal(action.save_intent.unwrap_or(SaveIntent::SaveAll), cx)
             .detach_and_log_err(cx);
@@ -1503,14 +1546,15 @@ impl Workspace {
 
     fn save_all_internal(
         &mut self,
-        mut save_intent: SaveIntent,
+        save_intent: SaveIntent,
         cx: &mut ViewContext<Self>,
     ) -> Task<Result<bool>> {
-        if self.project.read(cx).is_disconnected() {
-            return Task::ready(Ok(true));
-        }
-        let dirty_items = self
-            .panes
+        let dirty_items = self.dirty_items(cx);
+        self.save_items(dirty_items, save_intent, cx)
+    }
+
+    fn dirty_items(&self, cx: &AppContext) -> Vec<(WeakView<Pane>, Box<dyn ItemHandle>)> {
+        self.panes
             .iter()
             .flat_map(|pane| {
                 pane.read(cx).items().filter_map(|item| {
@@ -1521,7 +1565,18 @@ impl Workspace {
                     }
                 })
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
+
+    fn save_items(
+        &mut self,
+        dirty_items: Vec<(WeakView<Pane>, Box<dyn ItemHandle>)>,
+        mut save_intent: SaveIntent,
+        cx: &mut ViewContext<Self>,
+    ) -> Task<Result<bool>> {
+        if self.project.read(cx).is_disconnected() {
+            return Task::ready(Ok(true));
+        }
 
         let project = self.project.clone();
         cx.spawn(|workspace, mut cx| async move {
@@ -3539,6 +3594,11 @@ impl Workspace {
         self.database_id
     }
 
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn set_database_id(&mut self, database_id: WorkspaceId) {
+        self.database_id = Some(database_id);
+    }
+
     fn local_paths(&self, cx: &AppContext) -> Option<Vec<Arc<Path>>> {
         let project = self.project().read(cx);
 
@@ -5384,6 +5444,66 @@ mod tests {
         assert!(!task.await.unwrap());
     }
 
+    #[gpui::test]
+    async fn test_quit_with_hot_exit(cx: &mut TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |settings, cx| {
+                settings.update_user_settings::<WorkspaceSettings>(cx, |settings| {
+                    settings.hot_exit = Some(true);
+                })
+            });
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, None, cx).await;
+        let (workspace, cx) = cx.add_window_view(|cx| Workspace::test_new(project, cx));
+
+        let item1 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_persists_unsaved_changes(true)
+                .with_project_items(&[TestProjectItem::new(1, "1.txt", cx)])
+        });
+        let item2 = cx.new_view(|cx| {
+            TestItem::new(cx)
+                .with_dirty(true)
+                .with_project_items(&[TestProjectItem::new(2, "2.txt", cx)])
+        });
+        workspace.update(cx, |w, cx| {
+            w.add_item_to_active_pane(Box::new(item1.clone()), None, cx);
+            w.add_item_to_active_pane(Box::new(item2.clone()), None, cx);
+        });
+
+        // When quitting, the unsaved changes of item 1 are kept, and only item 2 needs saving.
+        let task = workspace.update(cx, |w, cx| w.prepare_to_close(true, cx));
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(0);
+        cx.executor().run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        assert!(task.await.unwrap());
+        item1.update(cx, |item, _| {
+            assert_eq!(item.persist_count, 1);
+            assert_eq!(item.save_count, 0);
+        });
+        item2.update(cx, |item, _| {
+            assert_eq!(item.persist_count, 0);
+            assert_eq!(item.save_count, 1);
+        });
+
+        // When closing the window, item 1 needs saving too.
+        let task = workspace.update(cx, |w, cx| w.prepare_to_close(false, cx));
+        cx.executor().run_until_parked();
+        cx.simulate_prompt_answer(0);
+        cx.executor().run_until_parked();
+        assert!(!cx.has_pending_prompt());
+        assert!(task.await.unwrap());
+        item1.update(cx, |item, _| {
+            assert_eq!(item.persist_count, 1);
+            assert_eq!(item.save_count, 1);
+        });
+    }
+
     #[gpui::test]
     async fn test_close_pane_items(cx: &mut TestAppContext) {
         init_test(cx);
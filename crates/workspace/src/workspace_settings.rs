@@ -12,6 +12,7 @@ pub struct WorkspaceSettings {
     pub show_call_status_icon: bool,
     pub autosave: AutosaveSetting,
     pub restore_on_startup: RestoreOnStartupBehaviour,
+    pub hot_exit: bool,
     pub drop_target_size: f32,
     pub when_closing_with_no_tabs: CloseWindowWhenNoItems,
 }
@@ -74,6 +75,11 @@ pub struct WorkspaceSettingsContent {
     /// Values: none, last_workspace
     /// Default: last_workspace
     pub restore_on_startup: Option<RestoreOnStartupBehaviour>,
+    /// Whether to keep the unsaved changes of buffers when quitting, and restore them
+    /// when their workspace is opened again, instead of prompting to save them.
+    ///
+    /// Default: false
+    pub hot_exit: Option<bool>,
     /// The size of the workspace split drop targets on the outer edges.
     /// Given as a fraction that will be multiplied by the smaller dimension of the workspace.
     ///
@@ -189,11 +189,16 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
     project_symbols::init(cx);
     project_panel::init(Assets, cx);
     outline_panel::init(Assets, cx);
+    debugger_ui::init(cx);
+    git_panel::init(cx);
+    hierarchy_panel::init(cx);
     tasks_ui::init(cx);
     channel::init(&app_state.client.clone(), app_state.user_store.clone(), cx);
     search::init(cx);
     vim::init(cx);
     terminal_view::init(cx);
+    test_explorer::init(cx);
+    file_history::init(cx);
 
     journal::init(app_state.clone(), cx);
     language_selector::init(cx);
@@ -223,6 +228,8 @@ fn init_ui(app_state: Arc<AppState>, cx: &mut AppContext) -> Result<()> {
 
     repl::init(app_state.fs.clone(), cx);
 
+    snippet_provider::init(app_state.fs.clone(), cx);
+
     cx.observe_global::<SettingsStore>({
         let languages = app_state.languages.clone();
         let http = app_state.client.http_client();
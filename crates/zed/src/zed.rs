@@ -10,11 +10,14 @@ pub use app_menus::*;
 use breadcrumbs::Breadcrumbs;
 use client::ZED_URL_SCHEME;
 use collections::VecDeque;
+use debugger_ui::DebugPanel;
 use editor::{scroll::Autoscroll, Editor, MultiBuffer};
+use git_panel::GitPanel;
 use gpui::{
     actions, point, px, AppContext, AsyncAppContext, Context, FocusableView, MenuItem, PromptLevel,
     ReadGlobal, TitlebarOptions, View, ViewContext, VisualContext, WindowKind, WindowOptions,
 };
+use hierarchy_panel::HierarchyPanel;
 pub use open_listener::*;
 
 use anyhow::Context as _;
@@ -38,6 +41,7 @@ use workspace::notifications::NotificationId;
 
 use paths::{local_settings_file_relative_path, local_tasks_file_relative_path};
 use terminal_view::terminal_panel::{self, TerminalPanel};
+use test_explorer::TestExplorerPanel;
 use util::{asset_str, ResultExt};
 use uuid::Uuid;
 use vim::VimModeSetting;
@@ -145,12 +149,14 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
         let vim_mode_indicator = cx.new_view(|cx| vim::ModeIndicator::new(cx));
         let cursor_position =
             cx.new_view(|_| go_to_line::cursor_position::CursorPosition::new(workspace));
+        let kernel_status = cx.new_view(repl::KernelStatusIndicator::new);
         workspace.status_bar().update(cx, |status_bar, cx| {
             status_bar.add_left_item(diagnostic_summary, cx);
             status_bar.add_left_item(activity_indicator, cx);
             status_bar.add_right_item(inline_completion_button, cx);
             status_bar.add_right_item(active_buffer_language, cx);
             status_bar.add_right_item(vim_mode_indicator, cx);
+            status_bar.add_right_item(kernel_status, cx);
             status_bar.add_right_item(cursor_position, cx);
         });
 
@@ -193,7 +199,15 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 assistant::AssistantPanel::load(workspace_handle.clone(), cx.clone());
             let project_panel = ProjectPanel::load(workspace_handle.clone(), cx.clone());
             let outline_panel = OutlinePanel::load(workspace_handle.clone(), cx.clone());
+            let debug_panel = DebugPanel::load(workspace_handle.clone(), cx.clone());
+            let git_panel = GitPanel::load(workspace_handle.clone(), cx.clone());
+            let hierarchy_panel = HierarchyPanel::load(workspace_handle.clone(), cx.clone());
             let terminal_panel = TerminalPanel::load(workspace_handle.clone(), cx.clone());
+            let test_explorer_panel = TestExplorerPanel::load(workspace_handle.clone(), cx.clone());
+            let variables_panel =
+                repl::VariablesPanel::load(workspace_handle.clone(), cx.clone());
+            let file_history_panel =
+                file_history::FileHistoryPanel::load(workspace_handle.clone(), cx.clone());
             let channels_panel =
                 collab_ui::collab_panel::CollabPanel::load(workspace_handle.clone(), cx.clone());
             let chat_panel =
@@ -206,7 +220,13 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             let (
                 project_panel,
                 outline_panel,
+                debug_panel,
+                git_panel,
+                hierarchy_panel,
                 terminal_panel,
+                test_explorer_panel,
+                variables_panel,
+                file_history_panel,
                 assistant_panel,
                 channels_panel,
                 chat_panel,
@@ -214,7 +234,13 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
             ) = futures::try_join!(
                 project_panel,
                 outline_panel,
+                debug_panel,
+                git_panel,
+                hierarchy_panel,
                 terminal_panel,
+                test_explorer_panel,
+                variables_panel,
+                file_history_panel,
                 assistant_panel,
                 channels_panel,
                 chat_panel,
@@ -225,7 +251,13 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
                 workspace.add_panel(assistant_panel, cx);
                 workspace.add_panel(project_panel, cx);
                 workspace.add_panel(outline_panel, cx);
+                workspace.add_panel(debug_panel, cx);
+                workspace.add_panel(git_panel, cx);
+                workspace.add_panel(hierarchy_panel, cx);
                 workspace.add_panel(terminal_panel, cx);
+                workspace.add_panel(test_explorer_panel, cx);
+                workspace.add_panel(variables_panel, cx);
+                workspace.add_panel(file_history_panel, cx);
                 workspace.add_panel(channels_panel, cx);
                 workspace.add_panel(chat_panel, cx);
                 workspace.add_panel(notification_panel, cx);
@@ -3180,7 +3212,12 @@ mod tests {
             collab_ui::init(&app_state, cx);
             project_panel::init((), cx);
             outline_panel::init((), cx);
+            debugger_ui::init(cx);
+            git_panel::init(cx);
+            hierarchy_panel::init(cx);
             terminal_view::init(cx);
+            test_explorer::init(cx);
+            file_history::init(cx);
             assistant::init(app_state.fs.clone(), app_state.client.clone(), cx);
             tasks_ui::init(cx);
             initialize_workspace(app_state.clone(), cx);